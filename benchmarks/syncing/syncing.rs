@@ -40,7 +40,7 @@ fn providing_sync_blocks(c: &mut Criterion) {
     let blocks = TestBlocks::load(Some(NUM_BLOCKS), "test_blocks_100_1");
     for block in &blocks.0 {
         rt.block_on(provider.expect_sync().consensus.receive_block(&block))
-            .unwrap()
+            .unwrap();
     }
     assert_eq!(provider.expect_sync().current_block_height() as usize, NUM_BLOCKS);
 
@@ -52,7 +52,7 @@ fn providing_sync_blocks(c: &mut Criterion) {
             // requester obtains hashes
             let hashes = match requester.lock().await.read_payload().await.unwrap() {
                 Payload::Sync(hashes) => hashes,
-                Payload::Ping(_) => return,
+                Payload::Ping(..) => return,
                 x => {
                     panic!("unexpected payload: {:?}", x);
                 }
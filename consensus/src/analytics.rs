@@ -0,0 +1,235 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{error::ConsensusError, Tx};
+use snarkvm_dpc::{Block, BlockHeaderHash};
+
+use std::{collections::VecDeque, sync::Mutex};
+
+/// The number of the most recent canon blocks that the chain statistics are computed over.
+pub const CHAIN_ANALYTICS_WINDOW: usize = 1000;
+
+/// The properties of a canon block that the chain statistics are computed from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockSample {
+    pub height: u32,
+    pub hash: BlockHeaderHash,
+    /// The timestamp from the block header.
+    pub time: i64,
+    /// The size of the serialized block, in bytes.
+    pub size: usize,
+    /// The number of transactions in the block, including the coinbase one.
+    pub transactions: usize,
+}
+
+impl BlockSample {
+    pub fn new(height: u32, block: &Block<Tx>) -> Result<Self, ConsensusError> {
+        Ok(Self {
+            height,
+            hash: block.header.get_hash(),
+            time: block.header.time,
+            size: block.serialize()?.len(),
+            transactions: block.transactions.len(),
+        })
+    }
+}
+
+/// Rolling statistics about the most recent canon blocks.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChainStatistics {
+    /// The height of the oldest block the statistics cover.
+    pub from_height: u32,
+    /// The height of the newest block the statistics cover, i.e. the tip of the canon chain.
+    pub to_height: u32,
+    pub block_count: usize,
+    /// The average number of seconds between two consecutive blocks, or `None` with fewer than two blocks.
+    pub average_block_interval_secs: Option<f64>,
+    pub average_block_size: f64,
+    pub min_block_size: usize,
+    pub median_block_size: usize,
+    pub p90_block_size: usize,
+    pub max_block_size: usize,
+    /// The average block size as a fraction of the maximum size permitted by consensus.
+    pub average_fullness: f64,
+    pub average_transactions: f64,
+    pub max_transactions: usize,
+}
+
+impl ChainStatistics {
+    /// Computes the statistics of the given consecutive blocks, ordered by height; returns `None` if there are none.
+    pub fn compute<'a, I: IntoIterator<Item = &'a BlockSample>>(samples: I, max_block_size: usize) -> Option<Self> {
+        let samples: Vec<&BlockSample> = samples.into_iter().collect();
+        let (first, last) = (samples.first()?, samples.last()?);
+        let count = samples.len();
+
+        let mut sizes: Vec<usize> = samples.iter().map(|sample| sample.size).collect();
+        sizes.sort_unstable();
+        let total_size: usize = sizes.iter().sum();
+        let average_block_size = total_size as f64 / count as f64;
+        let total_transactions: usize = samples.iter().map(|sample| sample.transactions).sum();
+
+        let average_block_interval_secs = if count > 1 {
+            Some((last.time - first.time) as f64 / (count - 1) as f64)
+        } else {
+            None
+        };
+
+        Some(Self {
+            from_height: first.height,
+            to_height: last.height,
+            block_count: count,
+            average_block_interval_secs,
+            average_block_size,
+            min_block_size: sizes[0],
+            median_block_size: sizes[(count - 1) / 2],
+            p90_block_size: sizes[(count - 1) * 9 / 10],
+            max_block_size: sizes[count - 1],
+            average_fullness: average_block_size / max_block_size.max(1) as f64,
+            average_transactions: total_transactions as f64 / count as f64,
+            max_transactions: samples.iter().map(|sample| sample.transactions).max().unwrap_or(0),
+        })
+    }
+}
+
+/// The samples of the latest `CHAIN_ANALYTICS_WINDOW` canon blocks, kept up to date as blocks are committed
+/// and reverted, so that the chain statistics don't need to be recomputed from the stored blocks.
+#[derive(Debug, Default)]
+pub struct ChainAnalytics {
+    samples: Mutex<VecDeque<BlockSample>>,
+}
+
+impl ChainAnalytics {
+    /// Registers a block that was committed to the canon chain.
+    pub(crate) fn block_connected(&self, sample: BlockSample) {
+        let mut samples = self.samples.lock().unwrap();
+
+        // The window only ever covers consecutive blocks.
+        if matches!(samples.back(), Some(last) if last.height + 1 != sample.height) {
+            samples.clear();
+        }
+        samples.push_back(sample);
+        if samples.len() > CHAIN_ANALYTICS_WINDOW {
+            samples.pop_front();
+        }
+    }
+
+    /// Discards the samples of the blocks above the given height, which were reverted from the canon chain.
+    pub(crate) fn reverted_to(&self, height: u32) {
+        self.samples.lock().unwrap().retain(|sample| sample.height <= height);
+    }
+
+    /// Returns the heights between `first` and `tip` whose samples are missing, i.e. weren't collected
+    /// since the node started; the samples are discarded if they don't lead up to the given tip.
+    pub(crate) fn missing_heights(&self, first: u32, tip: u32, tip_hash: &BlockHeaderHash) -> Vec<u32> {
+        let mut samples = self.samples.lock().unwrap();
+
+        match samples.back() {
+            Some(last) if last.height == tip && last.hash == *tip_hash => {
+                (first..samples.front().map(|sample| sample.height).unwrap_or(first)).collect()
+            }
+            _ => {
+                samples.clear();
+                (first..=tip).collect()
+            }
+        }
+    }
+
+    /// Adds the samples of the blocks preceding the ones already sampled, ordered by height.
+    pub(crate) fn backfill(&self, earlier: Vec<BlockSample>) {
+        let mut samples = self.samples.lock().unwrap();
+
+        for sample in earlier.into_iter().rev() {
+            if matches!(samples.front(), Some(first) if sample.height + 1 != first.height) {
+                break;
+            }
+            samples.push_front(sample);
+        }
+        while samples.len() > CHAIN_ANALYTICS_WINDOW {
+            samples.pop_front();
+        }
+    }
+
+    /// Computes the statistics of the sampled blocks.
+    pub fn statistics(&self, max_block_size: usize) -> Option<ChainStatistics> {
+        ChainStatistics::compute(self.samples.lock().unwrap().iter(), max_block_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(height: u32, time: i64, size: usize, transactions: usize) -> BlockSample {
+        BlockSample {
+            height,
+            hash: BlockHeaderHash([height as u8; 32]),
+            time,
+            size,
+            transactions,
+        }
+    }
+
+    #[test]
+    fn statistics_of_blocks() {
+        let samples = (0..10)
+            .map(|i| sample(i, 1_000 + 20 * i as i64, 100 * (i as usize + 1), i as usize % 3 + 1))
+            .collect::<Vec<_>>();
+
+        let stats = ChainStatistics::compute(&samples, 2_000).unwrap();
+        assert_eq!((stats.from_height, stats.to_height, stats.block_count), (0, 9, 10));
+        assert_eq!(stats.average_block_interval_secs, Some(20.0));
+        assert_eq!(stats.average_block_size, 550.0);
+        assert_eq!(stats.min_block_size, 100);
+        assert_eq!(stats.median_block_size, 500);
+        assert_eq!(stats.p90_block_size, 900);
+        assert_eq!(stats.max_block_size, 1_000);
+        assert_eq!(stats.average_fullness, 0.275);
+        assert_eq!(stats.average_transactions, 1.9);
+        assert_eq!(stats.max_transactions, 3);
+
+        let stats = ChainStatistics::compute(&samples[..1], 2_000).unwrap();
+        assert_eq!(stats.average_block_interval_secs, None);
+
+        assert_eq!(ChainStatistics::compute(&[], 2_000), None);
+    }
+
+    #[test]
+    fn samples_follow_the_canon_chain() {
+        let analytics = ChainAnalytics::default();
+        for height in 5..10 {
+            analytics.block_connected(sample(height, 0, 0, 1));
+        }
+
+        // the earlier blocks weren't sampled yet
+        let tip_hash = sample(9, 0, 0, 1).hash;
+        assert_eq!(analytics.missing_heights(2, 9, &tip_hash), vec![2, 3, 4]);
+        analytics.backfill((2..5).map(|height| sample(height, 0, 0, 1)).collect());
+        assert!(analytics.missing_heights(2, 9, &tip_hash).is_empty());
+
+        // a reorganization reverts some of the blocks
+        analytics.reverted_to(7);
+        analytics.block_connected(sample(8, 0, 0, 1));
+        assert_eq!(analytics.statistics(1).unwrap().to_height, 8);
+
+        // a gap discards the samples
+        analytics.block_connected(sample(12, 0, 0, 1));
+        assert_eq!(analytics.statistics(1).unwrap().block_count, 1);
+
+        // samples that don't lead up to the tip are discarded
+        assert_eq!(analytics.missing_heights(11, 13, &tip_hash), vec![11, 12, 13]);
+        assert_eq!(analytics.statistics(1), None);
+    }
+}
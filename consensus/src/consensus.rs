@@ -14,8 +14,16 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{error::ConsensusError, ConsensusParameters, MemoryPool, MerkleTreeLedger, Tx};
-use snarkos_storage::BlockPath;
+use crate::{
+    analytics::{BlockSample, ChainAnalytics, ChainStatistics, CHAIN_ANALYTICS_WINDOW},
+    error::ConsensusError,
+    fork_choice::{block_work, ForkChoice, ForkChoiceOutcome, ReorgEvent, ReorgEvents, FORK_CHOICE_HISTORY_LEN},
+    ConsensusParameters,
+    MemoryPool,
+    MerkleTreeLedger,
+    Tx,
+};
+use snarkos_storage::{BlockPath, SideChainPath};
 use snarkvm_algorithms::CRH;
 use snarkvm_dpc::{
     testnet1::{
@@ -30,6 +38,7 @@ use snarkvm_dpc::{
     AccountPrivateKey,
     AccountScheme,
     Block,
+    BlockHeaderHash,
     DPCComponents,
     DPCScheme,
     LedgerScheme,
@@ -39,15 +48,52 @@ use snarkvm_dpc::{
 use snarkvm_posw::txids_to_roots;
 use snarkvm_utilities::{to_bytes, ToBytes};
 
+use chrono::Utc;
 use rand::Rng;
-
-use std::sync::Arc;
+use rayon::prelude::*;
+
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+        Mutex,
+    },
+};
+use tokio::sync::{broadcast, Notify};
+
+/// The outcome of receiving a valid block from an external source.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockOutcome {
+    /// The block was already stored; nothing was done with it.
+    Known,
+    /// The parent of the block is unknown; the block was stored until its ancestors arrive.
+    Orphan,
+    /// The block extends a side chain that isn't longer than the canon chain; it was stored.
+    SideChain,
+    /// The block extends the canon chain; it was committed, along with any of its known descendants.
+    Canon,
+    /// The block extends a side chain that became longer than the canon chain; the node forked to it.
+    Reorganized,
+}
 
 pub struct Consensus<S: Storage> {
     pub parameters: ConsensusParameters,
     pub public_parameters: PublicParameters<Components>,
     pub ledger: Arc<MerkleTreeLedger<S>>,
     pub memory_pool: MemoryPool<Tx>,
+    /// The blocks whose transactions were already verified as part of a batch.
+    pub preverified_blocks: Mutex<HashSet<BlockHeaderHash>>,
+    /// The number of blocks received from external sources.
+    pub received_blocks: AtomicU64,
+    /// The most recent fork choice decisions, oldest first.
+    pub fork_choices: Mutex<VecDeque<ForkChoice>>,
+    /// The notifications about the reorganizations of the canon chain.
+    pub reorgs: ReorgEvents,
+    /// The samples of the latest canon blocks, used to compute the chain statistics.
+    pub analytics: ChainAnalytics,
+    /// Notified whenever a block is committed to the canon chain.
+    pub tip_changes: Notify,
 }
 
 impl<S: Storage> Consensus<S> {
@@ -134,14 +180,24 @@ impl<S: Storage> Consensus<S> {
             return Ok(false);
         }
 
-        // Check that all the transaction proofs verify
+        // Check that all the transaction proofs verify, unless it was already done for a batch
+        if self.preverified_blocks.lock().unwrap().remove(&block.header.get_hash()) {
+            return Ok(true);
+        }
+
         self.verify_transactions(&block.transactions.0)
     }
 
-    /// Receive a block from an external source and process it based on ledger state.
-    pub async fn receive_block(&self, block: &Block<Tx>) -> Result<(), ConsensusError> {
+    /// Receive a block from an external source and process it based on ledger state; returns what
+    /// became of the block, so that the caller can react to it accordingly.
+    pub async fn receive_block(&self, block: &Block<Tx>) -> Result<BlockOutcome, ConsensusError> {
+        let received_order = self.received_blocks.fetch_add(1, Ordering::SeqCst);
+
+        let is_unknown_orphan =
+            !self.ledger.previous_block_hash_exists(block) && !self.ledger.is_previous_block_canon(&block.header);
+
         // Block is an unknown orphan
-        if !self.ledger.previous_block_hash_exists(block) && !self.ledger.is_previous_block_canon(&block.header) {
+        let outcome = if is_unknown_orphan {
             debug!("Processing a block that is an unknown orphan");
 
             // There are two possible cases for an unknown orphan.
@@ -149,15 +205,19 @@ impl<S: Storage> Consensus<S> {
             // 2) The block is unknown and does not correspond with the canon chain.
             if crate::is_genesis(&block.header) && self.ledger.is_empty() {
                 self.process_block(block).await?;
+                BlockOutcome::Canon
+            } else if self.ledger.block_hash_exists(&block.header.get_hash()) {
+                BlockOutcome::Known
             } else {
                 self.ledger.insert_only(block)?;
+                BlockOutcome::Orphan
             }
         } else {
             // If the block is not an unknown orphan, find the origin of the block
             match self.ledger.get_block_path(&block.header)? {
                 BlockPath::ExistingBlock => {
                     debug!("Received a pre-existing block");
-                    return Err(ConsensusError::PreExistingBlock);
+                    BlockOutcome::Known
                 }
                 BlockPath::CanonChain(block_height) => {
                     debug!("Processing a block that is on canon chain. Height {}", block_height);
@@ -184,6 +244,12 @@ impl<S: Storage> Consensus<S> {
                         );
                         self.process_block(&new_block).await?;
                     }
+
+                    BlockOutcome::Canon
+                }
+                BlockPath::SideChain(_) if self.ledger.block_hash_exists(&block.header.get_hash()) => {
+                    debug!("Received a pre-existing side chain block");
+                    BlockOutcome::Known
                 }
                 BlockPath::SideChain(side_chain_path) => {
                     debug!(
@@ -191,17 +257,28 @@ impl<S: Storage> Consensus<S> {
                         side_chain_path.new_block_number
                     );
 
+                    let fork_choice = self.fork_choice(block, &side_chain_path, received_order)?;
+                    self.record_fork_choice(fork_choice.clone());
+
                     // If the side chain is now longer than the canon chain,
                     // perform a fork to the side chain.
-                    if side_chain_path.new_block_number > self.ledger.get_current_block_height() {
+                    if fork_choice.outcome == ForkChoiceOutcome::Reorganized {
                         debug!(
                             "Determined side chain is longer than canon chain by {} blocks",
                             side_chain_path.new_block_number - self.ledger.get_current_block_height()
                         );
                         warn!("A valid fork has been detected. Performing a fork to the side chain.");
 
+                        // The ledger digests used in the verification of the batch may no longer be valid
+                        self.preverified_blocks.lock().unwrap().clear();
+
+                        let old_height = self.ledger.get_current_block_height();
+                        let old_tip = self.ledger.get_block_hash(old_height)?;
+                        let shared_block_height = side_chain_path.shared_block_number;
+
                         // Fork to superior side chain
                         self.ledger.revert_for_fork(&side_chain_path)?;
+                        self.analytics.reverted_to(shared_block_height);
 
                         if !side_chain_path.path.is_empty() {
                             for block_hash in side_chain_path.path {
@@ -213,15 +290,155 @@ impl<S: Storage> Consensus<S> {
                                 }
                             }
                         }
+
+                        let new_height = self.ledger.get_current_block_height();
+                        self.reorgs.emit(ReorgEvent {
+                            old_tip,
+                            old_height,
+                            new_tip: self.ledger.get_block_hash(new_height)?,
+                            new_height,
+                            shared_block_height,
+                            timestamp: Utc::now(),
+                        });
+
+                        BlockOutcome::Reorganized
                     } else {
                         // If the sidechain is not longer than the main canon chain, simply store the block
                         self.ledger.insert_only(block)?;
+
+                        BlockOutcome::SideChain
                     }
                 }
-            };
+            }
+        };
+
+        Ok(outcome)
+    }
+
+    /// Determines whether the canon chain should be replaced with the side chain extended by the given block.
+    /// The longer chain wins; if both are of the same length, the one received first (the canon one) does.
+    fn fork_choice(
+        &self,
+        block: &Block<Tx>,
+        side_chain_path: &SideChainPath,
+        received_order: u64,
+    ) -> Result<ForkChoice, ConsensusError> {
+        let block_hash = block.header.get_hash();
+        let canon_height = self.ledger.get_current_block_height();
+
+        let mut canon_chain_work = 0;
+        for height in side_chain_path.shared_block_number + 1..=canon_height {
+            let hash = self.ledger.get_block_hash(height)?;
+            canon_chain_work += block_work(&self.ledger.get_block_header(&hash)?);
         }
 
-        Ok(())
+        let mut side_chain_work = 0;
+        for hash in &side_chain_path.path {
+            // The block itself hasn't been stored yet.
+            if *hash == block_hash {
+                side_chain_work += block_work(&block.header);
+            } else {
+                side_chain_work += block_work(&self.ledger.get_block_header(hash)?);
+            }
+        }
+
+        let outcome = if side_chain_path.new_block_number > canon_height {
+            ForkChoiceOutcome::Reorganized
+        } else {
+            ForkChoiceOutcome::Stored
+        };
+
+        Ok(ForkChoice {
+            block_hash,
+            received_order,
+            shared_block_height: side_chain_path.shared_block_number,
+            side_chain_height: side_chain_path.new_block_number,
+            canon_height,
+            side_chain_work,
+            canon_chain_work,
+            outcome,
+        })
+    }
+
+    fn record_fork_choice(&self, fork_choice: ForkChoice) {
+        info!(
+            block = %fork_choice.block_hash,
+            received_order = fork_choice.received_order,
+            shared_block_height = fork_choice.shared_block_height,
+            side_chain_height = fork_choice.side_chain_height,
+            canon_height = fork_choice.canon_height,
+            side_chain_work = %fork_choice.side_chain_work,
+            canon_chain_work = %fork_choice.canon_chain_work,
+            outcome = %fork_choice.outcome,
+            "Fork choice"
+        );
+
+        let mut fork_choices = self.fork_choices.lock().unwrap();
+        if fork_choices.len() == FORK_CHOICE_HISTORY_LEN {
+            fork_choices.pop_front();
+        }
+        fork_choices.push_back(fork_choice);
+    }
+
+    /// Returns a receiver of the notifications about the reorganizations of the canon chain.
+    pub fn subscribe_reorgs(&self) -> broadcast::Receiver<ReorgEvent> {
+        self.reorgs.subscribe()
+    }
+
+    /// Returns the most recent fork choice decision caused by the given block, if it's still known.
+    pub fn get_fork_choice(&self, block_hash: &BlockHeaderHash) -> Option<ForkChoice> {
+        self.fork_choices
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|fork_choice| fork_choice.block_hash == *block_hash)
+            .cloned()
+    }
+
+    /// Returns the statistics of the latest `CHAIN_ANALYTICS_WINDOW` canon blocks; the ones that weren't
+    /// committed since the node started are sampled from storage first.
+    pub fn chain_statistics(&self) -> Result<Option<ChainStatistics>, ConsensusError> {
+        if self.ledger.is_empty() {
+            return Ok(None);
+        }
+
+        let tip = self.ledger.get_current_block_height();
+        let first = (tip + 1).saturating_sub(CHAIN_ANALYTICS_WINDOW as u32);
+        let tip_hash = self.ledger.get_block_hash(tip)?;
+
+        let mut samples = vec![];
+        for height in self.analytics.missing_heights(first, tip, &tip_hash) {
+            let block = self.ledger.get_block(&self.ledger.get_block_hash(height)?)?;
+            samples.push(BlockSample::new(height, &block)?);
+        }
+        self.analytics.backfill(samples);
+
+        Ok(self.analytics.statistics(self.parameters.max_block_size))
+    }
+
+    /// Verifies the transactions of the given blocks in parallel, using a thread per CPU core, so
+    /// that they don't need to be verified again when the blocks are processed in order. The blocks
+    /// that fail the check are verified again when processed, as they may depend on the preceding ones.
+    /// The results for any previous batch are discarded.
+    pub fn preverify_blocks(&self, blocks: &[Block<Tx>])
+    where
+        S: Send + Sync,
+    {
+        let preverified: Vec<BlockHeaderHash> = blocks
+            .par_iter()
+            .filter(|block| matches!(self.verify_transactions(&block.transactions.0), Ok(true)))
+            .map(|block| block.header.get_hash())
+            .collect();
+
+        debug!("Pre-verified {} out of {} blocks", preverified.len(), blocks.len());
+
+        *self.preverified_blocks.lock().unwrap() = preverified.into_iter().collect();
+    }
+
+    /// Discards the results of any block pre-verification that weren't used.
+    pub fn clear_preverified_blocks(&self) {
+        self.preverified_blocks.lock().unwrap().clear();
     }
 
     /// Return whether or not the given block is valid and insert it.
@@ -240,11 +457,15 @@ impl<S: Storage> Consensus<S> {
 
         // 2. Insert/canonize block
         self.ledger.insert_and_commit(block)?;
+        self.analytics
+            .block_connected(BlockSample::new(self.ledger.get_current_block_height(), block)?);
+        self.tip_changes.notify_waiters();
 
-        // 3. Remove transactions from the mempool
+        // 3. Remove transactions from the mempool, making room for the ones spilled to storage
         for transaction_id in block.transactions.to_transaction_ids()? {
             self.memory_pool.remove_by_hash(&transaction_id).await?;
         }
+        self.memory_pool.refill(&self.ledger).await?;
 
         Ok(())
     }
@@ -89,6 +89,12 @@ pub enum ConsensusError {
     #[error(transparent)]
     PoswError(#[from] PoswError),
 
+    #[error("the search for a block was interrupted")]
+    SearchInterrupted,
+
+    #[error("the block template went stale while mining; its difficulty target was {0}")]
+    StaleBlockTemplate(u64),
+
     #[error("{}", _0)]
     StorageError(StorageError),
 
@@ -100,9 +106,32 @@ pub enum ConsensusError {
 
     #[error("Transactions are spending more funds than they have available")]
     TransactionOverspending,
+}
 
-    #[error("The block is already known")]
-    PreExistingBlock,
+impl ConsensusError {
+    /// Returns `true` if the error means that the block itself is invalid, as opposed to a local failure
+    /// to process it; the source of such a block can be held accountable for it.
+    pub fn is_invalid_block(&self) -> bool {
+        matches!(
+            self,
+            Self::AlreadySpent(..)
+                | Self::BlockTooLarge(..)
+                | Self::CoinbaseTransactionAlreadyExists()
+                | Self::ConflictingNetworkId(..)
+                | Self::DifficultyMismatch(..)
+                | Self::FuturisticTimestamp(..)
+                | Self::InvalidBlock(_)
+                | Self::InvalidCoinbaseTransaction
+                | Self::MerkleRoot(_)
+                | Self::MultipleCoinbaseTransactions(_)
+                | Self::NonceInvalid(..)
+                | Self::PedersenMerkleRoot(_)
+                | Self::PowInvalid(..)
+                | Self::PoswError(_)
+                | Self::TimestampInvalid(..)
+                | Self::TransactionOverspending
+        )
+    }
 }
 
 impl From<BlockError> for ConsensusError {
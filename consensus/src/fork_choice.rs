@@ -0,0 +1,112 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkvm_dpc::{BlockHeader, BlockHeaderHash};
+
+use chrono::{DateTime, Utc};
+use std::fmt;
+use tokio::sync::broadcast;
+
+/// The maximum number of fork choice decisions kept for inspection.
+pub const FORK_CHOICE_HISTORY_LEN: usize = 1024;
+
+/// The maximum number of chain reorganization events buffered for each subscriber.
+pub const REORG_EVENT_CHANNEL_DEPTH: usize = 64;
+
+/// The outcome of a fork choice between the canon chain and a side chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ForkChoiceOutcome {
+    /// The side chain was longer than the canon chain, so it became the canon chain.
+    Reorganized,
+    /// The side chain wasn't longer than the canon chain, which was received earlier, so the block was only stored.
+    Stored,
+}
+
+impl fmt::Display for ForkChoiceOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Reorganized => write!(f, "reorganized"),
+            Self::Stored => write!(f, "stored"),
+        }
+    }
+}
+
+/// The inputs and the outcome of a fork choice caused by a block extending a side chain.
+#[derive(Clone, Debug)]
+pub struct ForkChoice {
+    /// The hash of the block that caused the fork choice.
+    pub block_hash: BlockHeaderHash,
+    /// The position of the block among all the blocks received by the node since it started.
+    pub received_order: u64,
+    /// The height of the last block shared by the canon chain and the side chain.
+    pub shared_block_height: u32,
+    /// The height of the side chain including the block.
+    pub side_chain_height: u32,
+    /// The height of the canon chain at the time of the decision.
+    pub canon_height: u32,
+    /// The cumulative work of the side chain after the shared block.
+    pub side_chain_work: u128,
+    /// The cumulative work of the canon chain after the shared block.
+    pub canon_chain_work: u128,
+    pub outcome: ForkChoiceOutcome,
+}
+
+/// Returns the amount of work represented by the given block header, i.e. the expected number of
+/// hashes needed in order to meet its difficulty target.
+pub fn block_work(header: &BlockHeader) -> u128 {
+    u128::from(u64::MAX / header.difficulty_target.max(1))
+}
+
+/// A switch of the canon chain to a longer side chain.
+#[derive(Clone, Debug)]
+pub struct ReorgEvent {
+    /// The hash of the tip of the canon chain before the reorganization.
+    pub old_tip: BlockHeaderHash,
+    /// The height of the canon chain before the reorganization.
+    pub old_height: u32,
+    /// The hash of the tip of the canon chain after the reorganization.
+    pub new_tip: BlockHeaderHash,
+    /// The height of the canon chain after the reorganization.
+    pub new_height: u32,
+    /// The height of the last block shared by both chains.
+    pub shared_block_height: u32,
+    /// The time of the reorganization.
+    pub timestamp: DateTime<Utc>,
+}
+
+/// The sender of the chain reorganization events to their subscribers.
+pub struct ReorgEvents(broadcast::Sender<ReorgEvent>);
+
+impl Default for ReorgEvents {
+    fn default() -> Self {
+        let (events, _) = broadcast::channel(REORG_EVENT_CHANNEL_DEPTH);
+
+        Self(events)
+    }
+}
+
+impl ReorgEvents {
+    /// Returns a receiver of the chain reorganization events.
+    pub fn subscribe(&self) -> broadcast::Receiver<ReorgEvent> {
+        self.0.subscribe()
+    }
+
+    /// Sends a chain reorganization event to the subscribers, if there are any.
+    pub(crate) fn emit(&self, event: ReorgEvent) {
+        // An error only means that there are no subscribers at the moment.
+        let _ = self.0.send(event);
+    }
+}
@@ -40,6 +40,9 @@ extern crate thiserror;
 #[macro_use]
 extern crate tracing;
 
+pub mod analytics;
+pub use analytics::*;
+
 pub mod consensus;
 pub use consensus::*;
 
@@ -48,9 +51,15 @@ pub use difficulty::*;
 
 pub mod error;
 
+pub mod fork_choice;
+pub use fork_choice::*;
+
 pub mod miner;
 pub use miner::Miner;
 
+pub mod mining_controller;
+pub use mining_controller::{MiningController, MiningHooks};
+
 pub mod memory_pool;
 pub use memory_pool::MemoryPool;
 
@@ -16,26 +16,143 @@
 
 //! Transactions memory pool
 //!
-//! `MemoryPool` keeps a vector of transactions seen by the miner.
-
-use std::sync::atomic::{AtomicUsize, Ordering};
+//! `MemoryPool` keeps a vector of transactions seen by the miner. Once it's full, the transactions paying the lowest
+//! fees can optionally be spilled to storage instead of being dropped; they're reconsidered when block templates are
+//! built and moved back once there's room for them again.
+
+use std::{
+    cmp,
+    collections::HashMap,
+    convert::TryInto,
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+        Mutex,
+    },
+};
 
 use crate::error::ConsensusError;
+use chrono::{DateTime, TimeZone, Utc};
 use mpmc_map::MpmcMap;
 use snarkos_storage::Ledger;
 use snarkvm_algorithms::traits::LoadableMerkleParameters;
-use snarkvm_dpc::{BlockHeader, LedgerScheme, Storage, TransactionScheme, Transactions as DPCTransactions};
+use snarkvm_dpc::{
+    testnet1::{BaseDPCComponents, Transaction},
+    BlockHeader,
+    LedgerScheme,
+    Storage,
+    TransactionScheme,
+    Transactions as DPCTransactions,
+};
 use snarkvm_utilities::{
     bytes::{FromBytes, ToBytes},
     has_duplicates,
     to_bytes,
 };
 
+/// A transaction that pays a fee to the miner that includes it in a block.
+pub trait FeeTransaction {
+    /// Returns the fee offered by the transaction.
+    fn fee(&self) -> i64;
+}
+
+impl<C: BaseDPCComponents> FeeTransaction for Transaction<C> {
+    fn fee(&self) -> i64 {
+        self.value_balance.0
+    }
+}
+
 /// Stores a transaction and it's size in the memory pool.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Entry<T: TransactionScheme> {
     pub size_in_bytes: usize,
     pub transaction: T,
+    /// The time the entry was created at, i.e. when the transaction was received.
+    pub received: DateTime<Utc>,
+}
+
+impl<T: TransactionScheme> Entry<T> {
+    pub fn new(transaction: T, size_in_bytes: usize) -> Self {
+        Self {
+            size_in_bytes,
+            transaction,
+            received: Utc::now(),
+        }
+    }
+}
+
+impl<T: TransactionScheme + FeeTransaction> Entry<T> {
+    /// Compares the fee per byte offered by the entries, without losing precision to division.
+    fn cmp_fee_rate(&self, other: &Self) -> cmp::Ordering {
+        cmp_fee_rate(
+            (self.transaction.fee(), self.size_in_bytes),
+            (other.transaction.fee(), other.size_in_bytes),
+        )
+    }
+}
+
+/// Compares the fee per byte offered by the given fees and sizes in bytes, without losing precision to division.
+fn cmp_fee_rate((fee, size_in_bytes): (i64, usize), (other_fee, other_size_in_bytes): (i64, usize)) -> cmp::Ordering {
+    let own_rate = fee as i128 * other_size_in_bytes as i128;
+    let other_rate = other_fee as i128 * size_in_bytes as i128;
+
+    own_rate.cmp(&other_rate)
+}
+
+/// The parts of a transaction spilled to storage that are kept in memory, so that the spilled transactions can be
+/// ranked and checked against the ledger without being read back from storage.
+#[derive(Clone)]
+struct OverflowSummary<T: TransactionScheme> {
+    size_in_bytes: usize,
+    fee: i64,
+    serial_numbers: Vec<T::SerialNumber>,
+    commitments: Vec<T::Commitment>,
+    memo: T::Memorandum,
+}
+
+impl<T: TransactionScheme + FeeTransaction> OverflowSummary<T> {
+    fn new(entry: &Entry<T>) -> Self {
+        Self {
+            size_in_bytes: entry.size_in_bytes,
+            fee: entry.transaction.fee(),
+            serial_numbers: entry.transaction.old_serial_numbers().to_vec(),
+            commitments: entry.transaction.new_commitments().to_vec(),
+            memo: entry.transaction.memorandum().clone(),
+        }
+    }
+
+    /// Returns `true` if the transaction is no longer valid in the given ledger.
+    fn conflicts<P: LoadableMerkleParameters, S: Storage>(&self, storage: &Ledger<T, P, S>) -> bool {
+        storage.transaction_parts_conflict(&self.serial_numbers, &self.commitments, &self.memo)
+    }
+}
+
+/// A transaction considered for a new block; the ones spilled to storage are only read back once they're picked.
+enum Candidate<T: TransactionScheme> {
+    /// A transaction kept in memory.
+    Pooled(Entry<T>),
+    /// The id and the summary of a transaction spilled to storage.
+    Spilled(Vec<u8>, OverflowSummary<T>),
+}
+
+impl<T: TransactionScheme + FeeTransaction> Candidate<T> {
+    /// Returns the fee offered by the transaction and its size in bytes.
+    fn fee_and_size(&self) -> (i64, usize) {
+        match self {
+            Self::Pooled(entry) => (entry.transaction.fee(), entry.size_in_bytes),
+            Self::Spilled(_, summary) => (summary.fee, summary.size_in_bytes),
+        }
+    }
+}
+
+impl<T: TransactionScheme> fmt::Debug for OverflowSummary<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OverflowSummary")
+            .field("size_in_bytes", &self.size_in_bytes)
+            .field("fee", &self.fee)
+            .finish()
+    }
 }
 
 /// Stores transactions received by the server.
@@ -46,6 +163,18 @@ pub struct MemoryPool<T: TransactionScheme + Send + Sync + 'static> {
     pub transactions: MpmcMap<Vec<u8>, Entry<T>>,
     /// The total size in bytes of the current memory pool.
     pub total_size_in_bytes: AtomicUsize,
+    /// The size in bytes the memory pool can't exceed; the lowest-fee transactions are evicted to make room.
+    pub max_size_in_bytes: usize,
+    /// The total size in bytes of the transactions spilled to storage.
+    pub overflow_size_in_bytes: AtomicUsize,
+    /// The size in bytes the transactions spilled to storage can't exceed; nothing is spilled if it's 0.
+    pub max_overflow_size_in_bytes: usize,
+    /// The summaries of the transactions spilled to storage, by their ids.
+    overflow: Mutex<HashMap<Vec<u8>, OverflowSummary<T>>>,
+    /// Held while a transaction is admitted, so that the room made for it can't be taken by a concurrent insert.
+    admission: tokio::sync::Mutex<()>,
+    /// Notified whenever transactions are added to or removed from the memory pool.
+    changes: Arc<tokio::sync::Notify>,
 }
 
 impl<T: TransactionScheme + Send + Sync + 'static> Clone for MemoryPool<T> {
@@ -53,6 +182,12 @@ impl<T: TransactionScheme + Send + Sync + 'static> Clone for MemoryPool<T> {
         Self {
             transactions: self.transactions.clone(),
             total_size_in_bytes: AtomicUsize::new(self.total_size_in_bytes.load(Ordering::SeqCst)),
+            max_size_in_bytes: self.max_size_in_bytes,
+            overflow_size_in_bytes: AtomicUsize::new(self.overflow_size_in_bytes.load(Ordering::SeqCst)),
+            max_overflow_size_in_bytes: self.max_overflow_size_in_bytes,
+            overflow: Mutex::new(self.overflow.lock().unwrap().clone()),
+            admission: Default::default(),
+            changes: self.changes.clone(),
         }
     }
 }
@@ -60,32 +195,87 @@ impl<T: TransactionScheme + Send + Sync + 'static> Clone for MemoryPool<T> {
 const BLOCK_HEADER_SIZE: usize = BlockHeader::size();
 const COINBASE_TRANSACTION_SIZE: usize = 1490; // TODO Find the value for actual coinbase transaction size
 
-impl<T: TransactionScheme + Send + Sync + 'static> MemoryPool<T> {
+/// The default maximum size of the memory pool.
+pub const DEFAULT_MEMORY_POOL_SIZE: usize = 32 * 1024 * 1024;
+
+/// The size of the header of an entry spilled to storage: the time it was received at and its size.
+const OVERFLOW_ENTRY_HEADER_SIZE: usize = 8 + 8;
+
+/// Serializes an entry spilled to storage.
+fn serialize_overflow_entry<T: TransactionScheme>(entry: &Entry<T>) -> Result<Vec<u8>, ConsensusError> {
+    let mut bytes = Vec::with_capacity(OVERFLOW_ENTRY_HEADER_SIZE + entry.size_in_bytes);
+    bytes.extend_from_slice(&entry.received.timestamp_millis().to_le_bytes());
+    bytes.extend_from_slice(&(entry.size_in_bytes as u64).to_le_bytes());
+    entry.transaction.write(&mut bytes)?;
+
+    Ok(bytes)
+}
+
+/// Deserializes an entry spilled to storage.
+fn deserialize_overflow_entry<T: TransactionScheme>(bytes: &[u8]) -> Option<Entry<T>> {
+    if bytes.len() < OVERFLOW_ENTRY_HEADER_SIZE {
+        return None;
+    }
+    let received = i64::from_le_bytes(bytes[..8].try_into().ok()?);
+    let size_in_bytes = u64::from_le_bytes(bytes[8..16].try_into().ok()?) as usize;
+    let transaction = T::read(&bytes[OVERFLOW_ENTRY_HEADER_SIZE..]).ok()?;
+
+    Some(Entry {
+        size_in_bytes,
+        transaction,
+        received: Utc.timestamp_millis_opt(received).single()?,
+    })
+}
+
+impl<T: TransactionScheme + FeeTransaction + Send + Sync + 'static> MemoryPool<T> {
     /// Initialize a new memory pool with no transactions
     #[inline]
     pub fn new() -> Self {
         Self::default()
     }
 
-    /// Load the memory pool from previously stored state in storage
+    /// Initialize a new memory pool with no transactions that can hold up to the given number of bytes
+    #[inline]
+    pub fn with_max_size(max_size_in_bytes: usize) -> Self {
+        Self {
+            max_size_in_bytes,
+            ..Self::default()
+        }
+    }
+
+    /// Initialize a new memory pool with no transactions that can hold up to the given number of bytes, and spill
+    /// up to the given number of bytes of the transactions it has no room for to storage
+    #[inline]
+    pub fn with_overflow(max_size_in_bytes: usize, max_overflow_size_in_bytes: usize) -> Self {
+        Self {
+            max_size_in_bytes,
+            max_overflow_size_in_bytes,
+            ..Self::default()
+        }
+    }
+
+    /// Load the memory pool from previously stored state in storage; the transactions spilled to storage are moved
+    /// back if there's room for them, and discarded if the overflow is disabled
     pub async fn from_storage<P: LoadableMerkleParameters, S: Storage>(
         storage: &Ledger<T, P, S>,
+        max_size_in_bytes: usize,
+        max_overflow_size_in_bytes: usize,
     ) -> Result<Self, ConsensusError> {
-        let memory_pool = Self::new();
+        let memory_pool = Self::with_overflow(max_size_in_bytes, max_overflow_size_in_bytes);
+        memory_pool.index_overflow(storage)?;
 
         if let Ok(Some(serialized_transactions)) = storage.get_memory_pool() {
             if let Ok(transaction_bytes) = DPCTransactions::<T>::read(&serialized_transactions[..]) {
                 for transaction in transaction_bytes.0 {
                     let size = transaction.size();
-                    let entry = Entry {
-                        transaction,
-                        size_in_bytes: size,
-                    };
+                    let entry = Entry::new(transaction, size);
                     memory_pool.insert(storage, entry).await?;
                 }
             }
         }
 
+        memory_pool.refill(storage).await?;
+
         Ok(memory_pool)
     }
 
@@ -114,6 +304,9 @@ impl<T: TransactionScheme + Send + Sync + 'static> MemoryPool<T> {
         storage: &Ledger<T, P, S>,
         entry: Entry<T>,
     ) -> Result<Option<Vec<u8>>, ConsensusError> {
+        // The conflict checks, the room check, the evictions and the insertion happen under a single acquisition.
+        let _admission = self.admission.lock().await;
+
         let transaction_serial_numbers = entry.transaction.old_serial_numbers();
         let transaction_commitments = entry.transaction.new_commitments();
         let transaction_memo = entry.transaction.memorandum();
@@ -154,20 +347,257 @@ impl<T: TransactionScheme + Send + Sync + 'static> MemoryPool<T> {
 
         let transaction_id = entry.transaction.transaction_id()?.to_vec();
 
+        if !self.make_room_for(storage, &entry).await {
+            self.spill(storage, &transaction_id, &entry);
+            return Ok(None);
+        }
+
+        // A transaction that was spilled to storage is only kept in one of the tiers.
+        self.remove_overflow(storage, &transaction_id)?;
+
         self.total_size_in_bytes
             .fetch_add(entry.size_in_bytes, Ordering::SeqCst);
         self.transactions.insert(transaction_id.clone(), entry).await;
+        self.changes.notify_waiters();
 
         Ok(Some(transaction_id))
     }
 
+    /// Evicts the transactions paying a lower fee per byte than the given entry until it fits in the memory pool;
+    /// the evicted transactions are spilled to storage if the overflow is enabled.
+    /// Returns `false` without evicting anything if it can't be made to fit. It must only be called from `insert`,
+    /// which holds the admission lock.
+    async fn make_room_for<P: LoadableMerkleParameters, S: Storage>(
+        &self,
+        storage: &Ledger<T, P, S>,
+        entry: &Entry<T>,
+    ) -> bool {
+        let total_size = self.total_size_in_bytes.load(Ordering::SeqCst);
+        if total_size + entry.size_in_bytes <= self.max_size_in_bytes {
+            return true;
+        }
+        if entry.size_in_bytes > self.max_size_in_bytes {
+            return false;
+        }
+
+        let mut cheaper = self
+            .transactions
+            .inner()
+            .iter()
+            .filter(|(_, other)| other.cmp_fee_rate(entry) == cmp::Ordering::Less)
+            .map(|(id, other)| (id.clone(), other.clone()))
+            .collect::<Vec<_>>();
+        cheaper.sort_unstable_by(|(_, a), (_, b)| a.cmp_fee_rate(b));
+
+        let mut to_evict = vec![];
+        let mut freed_size = 0;
+        for (id, other) in cheaper {
+            if total_size - freed_size + entry.size_in_bytes <= self.max_size_in_bytes {
+                break;
+            }
+            freed_size += other.size_in_bytes;
+            to_evict.push(id);
+        }
+
+        if total_size - freed_size + entry.size_in_bytes > self.max_size_in_bytes {
+            return false;
+        }
+
+        for id in to_evict {
+            if let Ok(Some(evicted)) = self.remove_by_hash(&id).await {
+                if !self.spill(storage, &id, &evicted) {
+                    debug!(
+                        "Evicted transaction {} from the memory pool, paying a fee of {}",
+                        hex::encode(&id),
+                        evicted.transaction.fee()
+                    );
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Spills the given entry to storage if the overflow is enabled and has room for it; returns `true` if the
+    /// entry is kept there.
+    fn spill<P: LoadableMerkleParameters, S: Storage>(
+        &self,
+        storage: &Ledger<T, P, S>,
+        transaction_id: &[u8],
+        entry: &Entry<T>,
+    ) -> bool {
+        if self.max_overflow_size_in_bytes == 0 {
+            return false;
+        }
+        if self.overflow.lock().unwrap().contains_key(transaction_id) {
+            return true;
+        }
+
+        let overflow_size = self.overflow_size_in_bytes.load(Ordering::SeqCst);
+        if overflow_size + entry.size_in_bytes > self.max_overflow_size_in_bytes {
+            debug!(
+                "Dropped transaction {}: the memory pool overflow is full",
+                hex::encode(transaction_id)
+            );
+            return false;
+        }
+
+        let stored = serialize_overflow_entry(entry)
+            .and_then(|bytes| Ok(storage.store_overflow_transaction(transaction_id, bytes)?));
+        match stored {
+            Ok(()) => {
+                self.overflow
+                    .lock()
+                    .unwrap()
+                    .insert(transaction_id.to_vec(), OverflowSummary::new(entry));
+                self.overflow_size_in_bytes
+                    .fetch_add(entry.size_in_bytes, Ordering::SeqCst);
+                debug!(
+                    "Spilled transaction {} to storage, paying a fee of {}",
+                    hex::encode(transaction_id),
+                    entry.transaction.fee()
+                );
+                true
+            }
+            Err(e) => {
+                warn!("Couldn't spill a transaction to storage: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Removes the given transaction from the ones spilled to storage, if it's there.
+    fn remove_overflow<P: LoadableMerkleParameters, S: Storage>(
+        &self,
+        storage: &Ledger<T, P, S>,
+        transaction_id: &[u8],
+    ) -> Result<(), ConsensusError> {
+        let summary = match self.overflow.lock().unwrap().remove(transaction_id) {
+            Some(summary) => summary,
+            None => return Ok(()),
+        };
+        storage.remove_overflow_transactions(&[transaction_id.to_vec()])?;
+
+        let overflow_size = self.overflow_size_in_bytes.load(Ordering::SeqCst);
+        self.overflow_size_in_bytes
+            .store(overflow_size.saturating_sub(summary.size_in_bytes), Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    /// Reads all the entries spilled to storage and indexes them in memory; the ones that can't be read are
+    /// discarded. It's only done once, when the memory pool is loaded.
+    fn index_overflow<P: LoadableMerkleParameters, S: Storage>(
+        &self,
+        storage: &Ledger<T, P, S>,
+    ) -> Result<(), ConsensusError> {
+        let mut overflow = HashMap::new();
+        let mut unreadable = vec![];
+        for (transaction_id, bytes) in storage.get_overflow_transactions()? {
+            match deserialize_overflow_entry(&bytes) {
+                Some(entry) => {
+                    overflow.insert(transaction_id, OverflowSummary::new(&entry));
+                }
+                None => unreadable.push(transaction_id),
+            }
+        }
+
+        if !unreadable.is_empty() {
+            warn!(
+                "Discarding {} unreadable transactions spilled to storage",
+                unreadable.len()
+            );
+            storage.remove_overflow_transactions(&unreadable)?;
+        }
+
+        let overflow_size = overflow.values().map(|summary| summary.size_in_bytes).sum();
+        self.overflow_size_in_bytes.store(overflow_size, Ordering::SeqCst);
+        *self.overflow.lock().unwrap() = overflow;
+
+        Ok(())
+    }
+
+    /// Reads the given entry spilled to storage; it's discarded if it's missing or can't be read.
+    fn read_overflow<P: LoadableMerkleParameters, S: Storage>(
+        &self,
+        storage: &Ledger<T, P, S>,
+        transaction_id: &[u8],
+    ) -> Result<Option<Entry<T>>, ConsensusError> {
+        let entry = storage
+            .get_overflow_transaction(transaction_id)?
+            .and_then(|bytes| deserialize_overflow_entry(&bytes));
+        if entry.is_none() {
+            warn!(
+                "Discarding unreadable transaction {} spilled to storage",
+                hex::encode(transaction_id)
+            );
+            self.remove_overflow(storage, transaction_id)?;
+        }
+
+        Ok(entry)
+    }
+
+    /// Returns the ids and the summaries of the entries spilled to storage, from the one paying the highest fee per
+    /// byte to the one paying the lowest.
+    fn ranked_overflow(&self) -> Vec<(Vec<u8>, OverflowSummary<T>)> {
+        let mut spilled = self
+            .overflow
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, summary)| (id.clone(), summary.clone()))
+            .collect::<Vec<_>>();
+        spilled.sort_unstable_by(|(_, a), (_, b)| cmp_fee_rate((b.fee, b.size_in_bytes), (a.fee, a.size_in_bytes)));
+
+        spilled
+    }
+
+    /// Moves the transactions spilled to storage back into the memory pool while there's room for them, starting with
+    /// the ones paying the highest fee per byte; the ones that are no longer valid in the current ledger, and all of
+    /// them if the overflow is disabled, are discarded. Returns the number of transactions moved back.
+    pub async fn refill<P: LoadableMerkleParameters, S: Storage>(
+        &self,
+        storage: &Ledger<T, P, S>,
+    ) -> Result<usize, ConsensusError> {
+        let mut restored = 0;
+        for (transaction_id, summary) in self.ranked_overflow() {
+            let total_size = self.total_size_in_bytes.load(Ordering::SeqCst);
+            let fits = total_size + summary.size_in_bytes <= self.max_size_in_bytes;
+            if !fits && self.max_overflow_size_in_bytes != 0 && !summary.conflicts(storage) {
+                continue;
+            }
+
+            // Only the transactions moved back are read from storage.
+            let entry = if fits {
+                self.read_overflow(storage, &transaction_id)?
+            } else {
+                None
+            };
+            self.remove_overflow(storage, &transaction_id)?;
+            if let Some(entry) = entry {
+                if self.insert(storage, entry).await?.is_some() {
+                    restored += 1;
+                }
+            }
+        }
+
+        if restored != 0 {
+            debug!(
+                "Moved {} transactions spilled to storage back to the memory pool",
+                restored
+            );
+        }
+
+        Ok(restored)
+    }
+
     /// Cleanse the memory pool of outdated transactions.
     #[inline]
     pub async fn cleanse<P: LoadableMerkleParameters, S: Storage>(
         &self,
         storage: &Ledger<T, P, S>,
     ) -> Result<(), ConsensusError> {
-        let new_memory_pool = Self::new();
+        let new_memory_pool = Self::with_max_size(self.max_size_in_bytes);
 
         for (_, entry) in self.clone().transactions.inner().iter() {
             new_memory_pool.insert(storage, entry.clone()).await?;
@@ -178,6 +608,9 @@ impl<T: TransactionScheme + Send + Sync + 'static> MemoryPool<T> {
             Ordering::SeqCst,
         );
         self.transactions.reset(new_memory_pool.transactions.inner_full());
+        self.changes.notify_waiters();
+
+        self.refill(storage).await?;
 
         Ok(())
     }
@@ -192,6 +625,7 @@ impl<T: TransactionScheme + Send + Sync + 'static> MemoryPool<T> {
             let transaction_id = entry.transaction.transaction_id()?.to_vec();
 
             self.transactions.remove(transaction_id.to_vec()).await;
+            self.changes.notify_waiters();
 
             return Ok(Some(transaction_id));
         }
@@ -208,6 +642,7 @@ impl<T: TransactionScheme + Send + Sync + 'static> MemoryPool<T> {
                     .fetch_sub(entry.size_in_bytes, Ordering::SeqCst);
 
                 self.transactions.remove(transaction_id.to_vec()).await;
+                self.changes.notify_waiters();
 
                 Ok(Some(entry.clone()))
             }
@@ -215,6 +650,12 @@ impl<T: TransactionScheme + Send + Sync + 'static> MemoryPool<T> {
         }
     }
 
+    /// Returns a future completing on the next change of the memory pool's transactions; the changes made after the
+    /// future is created are observed even if it's not polled yet.
+    pub fn changed(&self) -> tokio::sync::futures::Notified<'_> {
+        self.changes.notified()
+    }
+
     /// Returns whether or not the memory pool contains the entry.
     #[inline]
     pub fn contains(&self, entry: &Entry<T>) -> bool {
@@ -224,7 +665,8 @@ impl<T: TransactionScheme + Send + Sync + 'static> MemoryPool<T> {
         }
     }
 
-    /// Get candidate transactions for a new block.
+    /// Get candidate transactions for a new block, preferring the ones paying the highest fee per byte; the ones
+    /// spilled to storage are considered too.
     pub fn get_candidates<P: LoadableMerkleParameters, S: Storage>(
         &self,
         storage: &Ledger<T, P, S>,
@@ -235,15 +677,36 @@ impl<T: TransactionScheme + Send + Sync + 'static> MemoryPool<T> {
         let mut block_size = 0;
         let mut transactions = DPCTransactions::new();
 
-        // TODO Change naive transaction selection
-        for (_transaction_id, entry) in self.transactions.inner().iter() {
-            if block_size + entry.size_in_bytes <= max_size {
-                if storage.transaction_conflicts(&entry.transaction) || transactions.conflicts(&entry.transaction) {
+        let mut candidates = self
+            .transactions
+            .inner()
+            .values()
+            .cloned()
+            .map(Candidate::Pooled)
+            .collect::<Vec<_>>();
+        candidates.extend(
+            self.ranked_overflow()
+                .into_iter()
+                .map(|(id, summary)| Candidate::Spilled(id, summary)),
+        );
+        candidates.sort_unstable_by(|a, b| cmp_fee_rate(b.fee_and_size(), a.fee_and_size()));
+
+        for candidate in candidates {
+            let (_, size_in_bytes) = candidate.fee_and_size();
+            if block_size + size_in_bytes <= max_size {
+                let transaction = match candidate {
+                    Candidate::Pooled(entry) => entry.transaction,
+                    Candidate::Spilled(id, _) => match self.read_overflow(storage, &id)? {
+                        Some(entry) => entry.transaction,
+                        None => continue,
+                    },
+                };
+                if storage.transaction_conflicts(&transaction) || transactions.conflicts(&transaction) {
                     continue;
                 }
 
-                block_size += entry.size_in_bytes;
-                transactions.push(entry.transaction.clone());
+                block_size += size_in_bytes;
+                transactions.push(transaction);
             }
         }
 
@@ -255,6 +718,12 @@ impl<T: TransactionScheme + Send + Sync + 'static> Default for MemoryPool<T> {
     fn default() -> Self {
         Self {
             total_size_in_bytes: AtomicUsize::new(0),
+            max_size_in_bytes: DEFAULT_MEMORY_POOL_SIZE,
+            overflow_size_in_bytes: AtomicUsize::new(0),
+            max_overflow_size_in_bytes: 0,
+            overflow: Default::default(),
+            admission: Default::default(),
+            changes: Default::default(),
             transactions: MpmcMap::<Vec<u8>, Entry<T>>::new(),
         }
     }
@@ -277,10 +746,7 @@ mod tests {
         let size = TRANSACTION_2.len();
 
         mem_pool
-            .insert(&blockchain, Entry {
-                size_in_bytes: size,
-                transaction: transaction.clone(),
-            })
+            .insert(&blockchain, Entry::new(transaction.clone(), size))
             .await
             .unwrap();
 
@@ -290,10 +756,7 @@ mod tests {
         // Duplicate pushes don't do anything
 
         mem_pool
-            .insert(&blockchain, Entry {
-                size_in_bytes: size,
-                transaction,
-            })
+            .insert(&blockchain, Entry::new(transaction, size))
             .await
             .unwrap();
 
@@ -309,10 +772,7 @@ mod tests {
         let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
         let size = TRANSACTION_2.len();
 
-        let entry = Entry::<Tx> {
-            size_in_bytes: size,
-            transaction,
-        };
+        let entry = Entry::<Tx>::new(transaction, size);
 
         mem_pool.insert(&blockchain, entry.clone()).await.unwrap();
 
@@ -334,10 +794,7 @@ mod tests {
         let size = TRANSACTION_2.len();
 
         mem_pool
-            .insert(&blockchain, Entry {
-                size_in_bytes: size,
-                transaction: transaction.clone(),
-            })
+            .insert(&blockchain, Entry::new(transaction.clone(), size))
             .await
             .unwrap();
 
@@ -364,10 +821,7 @@ mod tests {
 
         let expected_transaction = transaction.clone();
         mem_pool
-            .insert(&blockchain, Entry {
-                size_in_bytes: size,
-                transaction,
-            })
+            .insert(&blockchain, Entry::new(transaction, size))
             .await
             .unwrap();
 
@@ -378,6 +832,91 @@ mod tests {
         assert!(candidates.contains(&expected_transaction));
     }
 
+    #[tokio::test]
+    async fn evict_lowest_fee() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let cheap_entry = Entry::<Tx>::new(Tx::read(&TRANSACTION_1[..]).unwrap(), TRANSACTION_1.len());
+        let expensive_entry = Entry::<Tx>::new(Tx::read(&TRANSACTION_2[..]).unwrap(), TRANSACTION_2.len());
+        assert!(cheap_entry.cmp_fee_rate(&expensive_entry) == cmp::Ordering::Less);
+
+        // There's only room for one of the transactions.
+        let mem_pool = MemoryPool::with_max_size(cmp::max(TRANSACTION_1.len(), TRANSACTION_2.len()));
+
+        mem_pool.insert(&blockchain, cheap_entry.clone()).await.unwrap();
+        assert!(mem_pool.contains(&cheap_entry));
+
+        // The transaction paying more evicts the cheaper one.
+        mem_pool.insert(&blockchain, expensive_entry.clone()).await.unwrap();
+        assert!(mem_pool.contains(&expensive_entry));
+        assert!(!mem_pool.contains(&cheap_entry));
+        assert_eq!(TRANSACTION_2.len(), mem_pool.total_size_in_bytes.load(Ordering::SeqCst));
+
+        // The cheaper transaction can't evict the one paying more.
+        assert_eq!(None, mem_pool.insert(&blockchain, cheap_entry.clone()).await.unwrap());
+        assert!(mem_pool.contains(&expensive_entry));
+    }
+
+    #[tokio::test]
+    async fn concurrent_inserts_respect_max_size() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let cheap_entry = Entry::<Tx>::new(Tx::read(&TRANSACTION_1[..]).unwrap(), TRANSACTION_1.len());
+        let expensive_entry = Entry::<Tx>::new(Tx::read(&TRANSACTION_2[..]).unwrap(), TRANSACTION_2.len());
+
+        // There's only room for one of the transactions, which are inserted concurrently.
+        let max_size = cmp::max(TRANSACTION_1.len(), TRANSACTION_2.len());
+        let mem_pool = MemoryPool::with_max_size(max_size);
+
+        let (cheap, expensive) = tokio::join!(
+            mem_pool.insert(&blockchain, cheap_entry.clone()),
+            mem_pool.insert(&blockchain, expensive_entry.clone())
+        );
+        cheap.unwrap();
+        expensive.unwrap();
+
+        assert!(mem_pool.contains(&expensive_entry));
+        assert!(!mem_pool.contains(&cheap_entry));
+        assert!(mem_pool.total_size_in_bytes.load(Ordering::SeqCst) <= max_size);
+        assert_eq!(1, mem_pool.transactions.inner().len());
+    }
+
+    #[tokio::test]
+    async fn spill_to_storage() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let cheap_entry = Entry::<Tx>::new(Tx::read(&TRANSACTION_1[..]).unwrap(), TRANSACTION_1.len());
+        let expensive_entry = Entry::<Tx>::new(Tx::read(&TRANSACTION_2[..]).unwrap(), TRANSACTION_2.len());
+        let expensive_id = expensive_entry.transaction.transaction_id().unwrap().to_vec();
+
+        // There's only room for one of the transactions in memory, and for the other one in storage.
+        let max_size = cmp::max(TRANSACTION_1.len(), TRANSACTION_2.len());
+        let mem_pool = MemoryPool::with_overflow(max_size, max_size);
+
+        mem_pool.insert(&blockchain, cheap_entry.clone()).await.unwrap();
+        mem_pool.insert(&blockchain, expensive_entry.clone()).await.unwrap();
+
+        // The cheaper transaction was spilled to storage, and is still considered for new blocks.
+        assert!(!mem_pool.contains(&cheap_entry));
+        assert_eq!(
+            TRANSACTION_1.len(),
+            mem_pool.overflow_size_in_bytes.load(Ordering::SeqCst)
+        );
+
+        let candidates = mem_pool.get_candidates(&blockchain, usize::MAX / 2).unwrap();
+        assert!(candidates.contains(&cheap_entry.transaction));
+        assert!(candidates.contains(&expensive_entry.transaction));
+
+        // It's moved back once there's room for it.
+        assert_eq!(mem_pool.refill(&blockchain).await.unwrap(), 0);
+        mem_pool.remove_by_hash(&expensive_id).await.unwrap();
+        assert_eq!(mem_pool.refill(&blockchain).await.unwrap(), 1);
+
+        assert!(mem_pool.contains(&cheap_entry));
+        assert_eq!(0, mem_pool.overflow_size_in_bytes.load(Ordering::SeqCst));
+        assert!(blockchain.get_overflow_transactions().unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn store_memory_pool() {
         let blockchain = FIXTURE_VK.ledger();
@@ -385,10 +924,7 @@ mod tests {
         let mem_pool = MemoryPool::new();
         let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
         mem_pool
-            .insert(&blockchain, Entry {
-                size_in_bytes: TRANSACTION_2.len(),
-                transaction,
-            })
+            .insert(&blockchain, Entry::new(transaction, TRANSACTION_2.len()))
             .await
             .unwrap();
 
@@ -396,7 +932,9 @@ mod tests {
 
         mem_pool.store(&blockchain).unwrap();
 
-        let new_mem_pool = MemoryPool::from_storage(&blockchain).await.unwrap();
+        let new_mem_pool = MemoryPool::from_storage(&blockchain, DEFAULT_MEMORY_POOL_SIZE, 0)
+            .await
+            .unwrap();
 
         assert_eq!(
             mem_pool.total_size_in_bytes.load(Ordering::SeqCst),
@@ -411,10 +949,7 @@ mod tests {
         let mem_pool = MemoryPool::new();
         let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
         mem_pool
-            .insert(&blockchain, Entry {
-                size_in_bytes: TRANSACTION_2.len(),
-                transaction,
-            })
+            .insert(&blockchain, Entry::new(transaction, TRANSACTION_2.len()))
             .await
             .unwrap();
 
@@ -15,12 +15,14 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{error::ConsensusError, Consensus};
+use snarkos_storage::{MiningEvent, MiningEventKind};
 use snarkvm_algorithms::CRH;
 use snarkvm_dpc::{
     testnet1::{instantiated::*, Record as DPCRecord},
     AccountAddress,
     Block,
     BlockHeader,
+    BlockHeaderHash,
     DPCScheme,
     RecordScheme,
     Storage,
@@ -32,7 +34,10 @@ use snarkvm_utilities::{bytes::ToBytes, to_bytes};
 
 use chrono::Utc;
 use rand::{thread_rng, Rng};
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicI64, Ordering},
+    Arc,
+};
 
 /// Compiles transactions into blocks to be submitted to the network.
 /// Uses a proof of work based algorithm to find valid blocks.
@@ -43,6 +48,8 @@ pub struct Miner<S: Storage> {
     pub consensus: Arc<Consensus<S>>,
     /// The mining instance that is initialized with a proving key.
     miner: PoswMarlin,
+    /// The offset applied to the local clock in the timestamps of the mined blocks, in seconds.
+    time_offset: AtomicI64,
 }
 
 impl<S: Storage> Miner<S> {
@@ -53,9 +60,15 @@ impl<S: Storage> Miner<S> {
             consensus,
             // Load the miner with the proving key, this should never fail
             miner: PoswMarlin::load().expect("could not instantiate the miner"),
+            time_offset: Default::default(),
         }
     }
 
+    /// Sets the offset applied to the local clock in the timestamps of the mined blocks, in seconds.
+    pub fn set_time_offset(&self, offset: i64) {
+        self.time_offset.store(offset, Ordering::Relaxed);
+    }
+
     /// Fetches new transactions from the memory pool.
     pub fn fetch_memory_pool_transactions(&self) -> Result<DPCTransactions<Tx>, ConsensusError> {
         let max_block_size = self.consensus.parameters.max_block_size;
@@ -139,40 +152,87 @@ impl<S: Storage> Miner<S> {
         &self,
         transactions: &DPCTransactions<T>,
         parent_header: &BlockHeader,
+    ) -> Result<BlockHeader, ConsensusError> {
+        self.find_block_until(transactions, parent_header, &|| false)
+    }
+
+    /// Run proof of work to find block, giving up with `ConsensusError::SearchInterrupted` once `is_interrupted`
+    /// returns `true`; it's checked between the attempts with the random nonces.
+    pub fn find_block_until<T: TransactionScheme>(
+        &self,
+        transactions: &DPCTransactions<T>,
+        parent_header: &BlockHeader,
+        is_interrupted: &dyn Fn() -> bool,
     ) -> Result<BlockHeader, ConsensusError> {
         let txids = transactions.to_transaction_ids()?;
         let (merkle_root_hash, pedersen_merkle_root_hash, subroots) = txids_to_roots(&txids);
 
-        let time = Utc::now().timestamp();
+        let time = Utc::now().timestamp() + self.time_offset.load(Ordering::Relaxed);
         let difficulty_target = self.consensus.parameters.get_block_difficulty(parent_header, time);
 
         // TODO: Switch this to use a user-provided RNG
-        let (nonce, proof) = self.miner.mine(
-            &subroots,
-            difficulty_target,
-            &mut thread_rng(),
-            self.consensus.parameters.max_nonce,
-        )?;
+        let rng = &mut thread_rng();
+        let max_nonce = self.consensus.parameters.max_nonce;
+        loop {
+            if is_interrupted() {
+                return Err(ConsensusError::SearchInterrupted);
+            }
+
+            // Any proof meets the easiest target, so a single nonce is tried at a time, and its proof is
+            // checked against the actual target here.
+            let (nonce, proof) = self.miner.mine(&subroots, u64::MAX, rng, max_nonce)?;
+            let header = BlockHeader {
+                previous_block_hash: parent_header.get_hash(),
+                merkle_root_hash: merkle_root_hash.clone(),
+                pedersen_merkle_root_hash: pedersen_merkle_root_hash.clone(),
+                time,
+                difficulty_target,
+                nonce,
+                proof: proof.into(),
+            };
+            if header.to_difficulty_hash() <= difficulty_target {
+                return Ok(header);
+            }
+        }
+    }
 
-        Ok(BlockHeader {
-            previous_block_hash: parent_header.get_hash(),
-            merkle_root_hash,
-            pedersen_merkle_root_hash,
-            time,
-            difficulty_target,
-            nonce,
-            proof: proof.into(),
-        })
+    /// Records an event in the mining history; a failure to do so is logged, but doesn't interrupt mining.
+    fn record_mining_event(
+        &self,
+        kind: MiningEventKind,
+        block_height: u32,
+        block_hash: Option<&BlockHeaderHash>,
+        previous_block_hash: &BlockHeaderHash,
+    ) {
+        let event = MiningEvent {
+            kind,
+            block_height,
+            block_hash: block_hash.map(|hash| hash.0),
+            previous_block_hash: previous_block_hash.0,
+            timestamp: Utc::now().timestamp(),
+        };
+
+        if let Err(e) = self.consensus.ledger.store_mining_event(&event) {
+            warn!("Couldn't record a mining event: {}", e);
+        }
     }
 
     /// Returns a mined block.
     /// Calls methods to fetch transactions, run proof of work, and add the block into the chain for storage.
-    pub async fn mine_block(&self) -> Result<(Block<Tx>, Vec<DPCRecord<Components>>), ConsensusError> {
+    /// The search is interrupted once the height of the canon chain changes or `is_stopped` returns `true`.
+    pub async fn mine_block(
+        &self,
+        is_stopped: &dyn Fn() -> bool,
+    ) -> Result<(Block<Tx>, Vec<DPCRecord<Components>>), ConsensusError> {
         let candidate_transactions = self.fetch_memory_pool_transactions()?;
 
         debug!("The miner is creating a block");
 
+        let block_height = self.consensus.ledger.get_current_block_height() + 1;
         let (previous_block_header, transactions, coinbase_records) = self.establish_block(&candidate_transactions)?;
+        let previous_block_hash = previous_block_header.get_hash();
+
+        self.record_mining_event(MiningEventKind::Template, block_height, None, &previous_block_hash);
 
         debug!("The miner generated a coinbase transaction");
 
@@ -181,13 +241,46 @@ impl<S: Storage> Miner<S> {
             debug!("Coinbase record {:?} commitment: {:?}", index, record_commitment);
         }
 
-        let header = self.find_block(&transactions, &previous_block_header)?;
+        let ledger = &self.consensus.ledger;
+        let is_interrupted = || is_stopped() || ledger.get_current_block_height() + 1 != block_height;
+        let header = self.find_block_until(&transactions, &previous_block_header, &is_interrupted)?;
 
         debug!("The Miner found a block");
 
         let block = Block { header, transactions };
+        let block_hash = block.header.get_hash();
+
+        // A reorganization that doesn't change the height doesn't interrupt the search, so a block found on top of
+        // a former tip is discarded afterwards.
+        if self.consensus.ledger.get_latest_block()?.header.get_hash() != previous_block_hash {
+            self.record_mining_event(
+                MiningEventKind::Rejected,
+                block_height,
+                Some(&block_hash),
+                &previous_block_hash,
+            );
+            return Err(ConsensusError::StaleBlockTemplate(block.header.difficulty_target));
+        }
 
-        self.consensus.receive_block(&block).await?;
+        self.record_mining_event(
+            MiningEventKind::Submitted,
+            block_height,
+            Some(&block_hash),
+            &previous_block_hash,
+        );
+
+        let result = self.consensus.receive_block(&block).await;
+
+        // The block may also have been stored without extending the canonical chain, e.g. if a competing one
+        // arrived in the meantime.
+        let outcome = if result.is_ok() && self.consensus.ledger.is_canon(&block_hash) {
+            MiningEventKind::Accepted
+        } else {
+            MiningEventKind::Rejected
+        };
+        self.record_mining_event(outcome, block_height, Some(&block_hash), &previous_block_hash);
+
+        result?;
 
         // Store the non-dummy coinbase records.
         let mut records_to_store = vec![];
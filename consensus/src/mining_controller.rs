@@ -0,0 +1,321 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{error::ConsensusError, Consensus, Miner};
+use snarkvm_dpc::{testnet1::instantiated::*, AccountAddress, Block, Storage};
+
+use futures::executor::block_on;
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tokio::runtime::Handle;
+
+/// The number of consecutive failures after which a mining thread gives up.
+const MAX_MINING_FAILURES: u32 = 10;
+
+/// The interval at which a paused mining thread checks whether it can resume.
+const MINING_PAUSE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The integration of the miners with the rest of the node.
+pub trait MiningHooks: Send + Sync + 'static {
+    /// Checks whether the miners should hold off, e.g. while the node is syncing blocks.
+    fn is_paused(&self) -> bool;
+
+    /// Called with every block mined and accepted by the ledger, e.g. in order to propagate it.
+    fn block_mined(&self, block: &Block<Tx>);
+
+    /// Returns the offset applied to the local clock in the timestamps of the mined blocks, in seconds, e.g. in
+    /// order to follow the network's time on a host with a skewed clock.
+    fn time_offset(&self) -> i64 {
+        0
+    }
+
+    /// Called with the number of threads whenever mining is started or stopped, e.g. in order to report it.
+    fn threads_changed(&self, _num_threads: usize) {}
+
+    /// Called with the updated estimate of the proofs computed per hour after every completed search.
+    fn proof_rate_changed(&self, _proofs_per_hour: u64) {}
+}
+
+/// A single run of the mining threads, from their start to their stop.
+struct MiningSession {
+    /// The number of mining threads.
+    num_threads: usize,
+    /// The time the threads were started at.
+    started: Instant,
+    /// Tells the threads to exit once they're done with their current search.
+    stopped: AtomicBool,
+    /// The expected number of proofs computed in the completed searches, based on their difficulty targets.
+    expected_proofs: AtomicU64,
+    /// The handles of the threads.
+    threads: Mutex<Vec<thread::JoinHandle<()>>>,
+}
+
+impl MiningSession {
+    /// Tells the threads to exit, interrupting their current searches and pauses.
+    fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        for handle in self.threads.lock().unwrap().iter() {
+            handle.thread().unpark();
+        }
+    }
+
+    /// Waits for the threads to exit; a block that was already found is still submitted first.
+    fn join(&self) {
+        let threads = std::mem::take(&mut *self.threads.lock().unwrap());
+        for handle in threads {
+            if handle.join().is_err() {
+                warn!("A mining thread panicked");
+            }
+        }
+    }
+
+    /// Returns the estimated number of proofs computed per hour since the threads were started.
+    fn proofs_per_hour(&self) -> u64 {
+        let elapsed_secs = self.started.elapsed().as_secs().max(1);
+        self.expected_proofs.load(Ordering::Relaxed).saturating_mul(3600) / elapsed_secs
+    }
+}
+
+/// Manages the threads mining blocks on top of the current tip of the canon chain.
+///
+/// The searches in progress are interrupted when mining is stopped and when the height of the canon chain
+/// changes, in which case the block template is refreshed; a block found on top of a former tip is discarded.
+pub struct MiningController<S: Storage> {
+    /// The coinbase address that mining rewards are assigned to.
+    address: AccountAddress<Components>,
+    /// The consensus the mined blocks are submitted to.
+    consensus: Arc<Consensus<S>>,
+    /// The integration of the miners with the rest of the node.
+    hooks: Arc<dyn MiningHooks>,
+    /// The miner shared by the mining threads; its proving key is only loaded once mining is first started.
+    miner: Mutex<Option<Arc<Miner<S>>>>,
+    /// The current mining session, if the node is mining.
+    session: Mutex<Option<Arc<MiningSession>>>,
+}
+
+impl<S: Storage + Send + Sync + 'static> MiningController<S> {
+    /// Creates a new instance of `MiningController`; no mining threads are started yet.
+    pub fn new(address: AccountAddress<Components>, consensus: Arc<Consensus<S>>, hooks: Arc<dyn MiningHooks>) -> Self {
+        Self {
+            address,
+            consensus,
+            hooks,
+            miner: Default::default(),
+            session: Default::default(),
+        }
+    }
+
+    /// Starts mining with the given number of threads; if the node is already mining, its threads are
+    /// stopped and replaced with the new ones. The stopped threads are waited for in the background.
+    pub fn start(&self, num_threads: usize) -> Result<(), ConsensusError> {
+        if num_threads == 0 {
+            return Err(ConsensusError::Message("at least one mining thread is required".into()));
+        }
+
+        let miner = self
+            .miner
+            .lock()
+            .unwrap()
+            .get_or_insert_with(|| Arc::new(Miner::new(self.address.clone(), Arc::clone(&self.consensus))))
+            .clone();
+
+        // The session is replaced while the lock is held, so that concurrent starts don't leave any threads
+        // behind; the previous threads aren't waited for until it's released.
+        let mut current_session = self.session.lock().unwrap();
+        let previous_session = current_session.take();
+        if let Some(ref previous_session) = previous_session {
+            previous_session.stop();
+        }
+
+        let result = self.start_session(miner, num_threads);
+        match result {
+            Ok(ref session) => {
+                *current_session = Some(Arc::clone(session));
+                self.hooks.threads_changed(num_threads);
+            }
+            Err(_) if previous_session.is_some() => self.hooks.threads_changed(0),
+            Err(_) => {}
+        }
+        drop(current_session);
+
+        if let Some(previous_session) = previous_session {
+            join_in_background(previous_session);
+        }
+
+        result.map(|_| ())
+    }
+
+    /// Spawns the threads of a new mining session.
+    fn start_session(&self, miner: Arc<Miner<S>>, num_threads: usize) -> Result<Arc<MiningSession>, ConsensusError> {
+        let session = Arc::new(MiningSession {
+            num_threads,
+            started: Instant::now(),
+            stopped: AtomicBool::new(false),
+            expected_proofs: AtomicU64::new(0),
+            threads: Default::default(),
+        });
+
+        for i in 0..num_threads {
+            let miner = Arc::clone(&miner);
+            let hooks = Arc::clone(&self.hooks);
+            let worker_session = Arc::clone(&session);
+
+            match thread::Builder::new()
+                .name(format!("miner-{}", i))
+                .spawn(move || mine(miner, hooks, worker_session))
+            {
+                Ok(handle) => session.threads.lock().unwrap().push(handle),
+                Err(e) => {
+                    session.stop();
+                    return Err(ConsensusError::Message(format!(
+                        "couldn't spawn a mining thread: {}",
+                        e
+                    )));
+                }
+            }
+        }
+
+        info!(
+            "Started mining with {} thread(s); the miner address is {}",
+            num_threads, self.address
+        );
+
+        Ok(session)
+    }
+
+    /// Stops mining, interrupting the searches in progress; returns `false` if the node wasn't mining. The
+    /// threads aren't waited for, so a block that was already found may still be submitted afterwards.
+    pub fn stop(&self) -> bool {
+        match self.session.lock().unwrap().take() {
+            Some(session) => {
+                session.stop();
+                self.hooks.threads_changed(0);
+                self.hooks.proof_rate_changed(0);
+                info!("Stopped mining");
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns `true` if the node is mining.
+    pub fn is_mining(&self) -> bool {
+        self.session.lock().unwrap().is_some()
+    }
+
+    /// Returns the number of threads the node is mining with.
+    pub fn num_threads(&self) -> usize {
+        self.session
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|s| s.num_threads)
+            .unwrap_or(0)
+    }
+
+    /// Returns the estimated number of proofs computed per hour since mining was started.
+    pub fn proofs_per_hour(&self) -> u64 {
+        self.session
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|s| s.proofs_per_hour())
+            .unwrap_or(0)
+    }
+}
+
+/// Waits for the threads of a stopped session to exit without blocking the caller, e.g. an async worker.
+fn join_in_background(session: Arc<MiningSession>) {
+    match Handle::try_current() {
+        Ok(runtime) => {
+            runtime.spawn_blocking(move || session.join());
+        }
+        // e.g. when mining is started from a plain thread
+        Err(_) => {
+            thread::spawn(move || session.join());
+        }
+    }
+}
+
+/// Mines blocks until the session is stopped or too many consecutive failures occur.
+fn mine<S: Storage + Send + Sync + 'static>(
+    miner: Arc<Miner<S>>,
+    hooks: Arc<dyn MiningHooks>,
+    session: Arc<MiningSession>,
+) {
+    let mut failures = 0;
+
+    while !session.stopped.load(Ordering::SeqCst) {
+        if hooks.is_paused() {
+            // A stop unparks the thread.
+            thread::park_timeout(MINING_PAUSE_INTERVAL);
+            continue;
+        }
+
+        debug!("Starting to mine the next block");
+
+        // A fresh template is established on top of the current tip for every search.
+        miner.set_time_offset(hooks.time_offset());
+        let is_stopped = || session.stopped.load(Ordering::SeqCst);
+        let difficulty_target = match block_on(miner.mine_block(&is_stopped)) {
+            Ok((block, _coinbase_records)) => {
+                failures = 0;
+                hooks.block_mined(&block);
+                block.header.difficulty_target
+            }
+            Err(ConsensusError::StaleBlockTemplate(difficulty_target)) => {
+                debug!("A new block arrived while mining; refreshing the block template");
+                difficulty_target
+            }
+            Err(ConsensusError::SearchInterrupted) => {
+                // The interrupted searches aren't counted, as their progress is unknown.
+                debug!("The search for a block was interrupted");
+                continue;
+            }
+            Err(e) => {
+                failures += 1;
+                warn!(
+                    "Miner failed to mine a block {} time(s). (error message: {}).",
+                    failures, e
+                );
+
+                if failures >= MAX_MINING_FAILURES {
+                    warn!(
+                        "Miner has failed to mine a block {} times. Stopping the mining thread.",
+                        failures
+                    );
+                    break;
+                }
+                continue;
+            }
+        };
+
+        // A proof meets the difficulty target with a probability of (target + 1) / 2^64.
+        session
+            .expected_proofs
+            .fetch_add(u64::MAX / difficulty_target.max(1), Ordering::Relaxed);
+        if !is_stopped() {
+            hooks.proof_rate_changed(session.proofs_per_hour());
+        }
+    }
+}
@@ -20,6 +20,7 @@ use snarkvm_algorithms::{CRH, SNARK};
 use snarkvm_curves::bls12_377::Bls12_377;
 use snarkvm_dpc::{
     testnet1::{instantiated::*, program::NoopProgram, BaseDPCComponents},
+    Block,
     BlockHeader,
     DPCScheme,
     MerkleRootHash,
@@ -28,7 +29,7 @@ use snarkvm_dpc::{
     ProgramScheme,
     Storage,
 };
-use snarkvm_posw::{Marlin, PoswMarlin};
+use snarkvm_posw::{txids_to_roots, Marlin, PoswMarlin};
 use snarkvm_utilities::{to_bytes, FromBytes, ToBytes};
 
 use chrono::Utc;
@@ -120,6 +121,14 @@ impl ConsensusParameters {
         Ok(())
     }
 
+    /// Verifies the header of the given block against the header of its parent and the block's own transactions.
+    pub fn verify_block_header(&self, block: &Block<Tx>, parent_header: &BlockHeader) -> Result<(), ConsensusError> {
+        let transaction_ids: Vec<_> = block.transactions.to_transaction_ids()?;
+        let (merkle_root, pedersen_merkle_root, _) = txids_to_roots(&transaction_ids);
+
+        self.verify_header(&block.header, parent_header, &merkle_root, &pedersen_merkle_root)
+    }
+
     // TODO (raychu86): Genericize this model to allow for generic programs.
     /// Generate the birth and death program proofs for a transaction for a given transaction kernel
     #[allow(clippy::type_complexity)]
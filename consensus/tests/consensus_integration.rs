@@ -17,8 +17,9 @@
 mod consensus_integration {
     use snarkos_consensus::miner::Miner;
     use snarkos_testing::sync::*;
-    use snarkvm_dpc::{block::Transactions as DPCTransactions, testnet1::instantiated::Tx, BlockHeader};
+    use snarkvm_dpc::{block::Transactions as DPCTransactions, testnet1::instantiated::Tx, Block, BlockHeader};
     use snarkvm_posw::txids_to_roots;
+    use snarkvm_utilities::bytes::FromBytes;
 
     use std::sync::Arc;
 
@@ -56,4 +57,27 @@ mod consensus_integration {
         let parent_header = genesis().header;
         test_find_block(&transactions, &parent_header);
     }
+
+    // Verify the transactions of a batch of blocks in parallel, and process the blocks in order.
+    #[tokio::test]
+    async fn preverified_batch() {
+        let consensus = snarkos_testing::sync::create_test_consensus();
+        let old_block_height = consensus.ledger.get_current_block_height();
+
+        let blocks = vec![
+            Block::<Tx>::read(&BLOCK_1[..]).unwrap(),
+            Block::<Tx>::read(&BLOCK_2[..]).unwrap(),
+        ];
+        consensus.preverify_blocks(&blocks);
+        let preverified = consensus.preverified_blocks.lock().unwrap().clone();
+        assert!(preverified.contains(&blocks[0].header.get_hash()));
+
+        for block in &blocks {
+            consensus.receive_block(block).await.unwrap();
+        }
+
+        // the pre-verification results are consumed during processing
+        assert!(consensus.preverified_blocks.lock().unwrap().is_empty());
+        assert_eq!(old_block_height + 2, consensus.ledger.get_current_block_height());
+    }
 }
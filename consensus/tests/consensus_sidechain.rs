@@ -15,9 +15,17 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 mod consensus_sidechain {
-    use snarkos_storage::validator::FixMode;
+    use snarkos_consensus::{BlockOutcome, ForkChoiceOutcome};
+    use snarkos_storage::{
+        migrations,
+        set_schema_version,
+        validator::FixMode,
+        ChainTipStatus,
+        MigrationRunner,
+        COL_BRANCH_TIPS,
+    };
     use snarkos_testing::sync::*;
-    use snarkvm_dpc::{testnet1::instantiated::Tx, Block};
+    use snarkvm_dpc::{testnet1::instantiated::Tx, Block, DatabaseTransaction, Op, Storage};
     use snarkvm_utilities::bytes::FromBytes;
 
     use rand::{seq::IteratorRandom, thread_rng, Rng};
@@ -109,6 +117,152 @@ mod consensus_sidechain {
         assert_eq!(old_block_height + 1, new_block_height);
     }
 
+    // Receive competing blocks and check the recorded fork choice decisions.
+    #[tokio::test]
+    async fn fork_choices_are_recorded() {
+        let consensus = snarkos_testing::sync::create_test_consensus();
+
+        let block_1_canon = Block::<Tx>::read(&ALTERNATIVE_BLOCK_1[..]).unwrap();
+        let block_1_side = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+        let block_2_side = Block::<Tx>::read(&BLOCK_2[..]).unwrap();
+
+        consensus.receive_block(&block_1_canon).await.unwrap();
+        assert!(consensus.get_fork_choice(&block_1_canon.header.get_hash()).is_none());
+
+        // A side chain of the same length doesn't replace the canon chain received earlier.
+        consensus.receive_block(&block_1_side).await.unwrap();
+        let fork_choice = consensus.get_fork_choice(&block_1_side.header.get_hash()).unwrap();
+        assert_eq!(fork_choice.received_order, 1);
+        assert_eq!(fork_choice.side_chain_height, fork_choice.canon_height);
+        assert_eq!(fork_choice.outcome, ForkChoiceOutcome::Stored);
+
+        // A longer side chain does.
+        consensus.receive_block(&block_2_side).await.unwrap();
+        let fork_choice = consensus.get_fork_choice(&block_2_side.header.get_hash()).unwrap();
+        assert_eq!(fork_choice.received_order, 2);
+        assert_eq!(fork_choice.side_chain_height, fork_choice.canon_height + 1);
+        assert!(fork_choice.side_chain_work > fork_choice.canon_chain_work);
+        assert_eq!(fork_choice.outcome, ForkChoiceOutcome::Reorganized);
+    }
+
+    // Receive blocks in various orders and check the reported outcomes.
+    #[tokio::test]
+    async fn block_outcomes() {
+        let consensus = snarkos_testing::sync::create_test_consensus();
+
+        let block_1_canon = Block::<Tx>::read(&ALTERNATIVE_BLOCK_1[..]).unwrap();
+        let block_1_side = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+        let block_2_side = Block::<Tx>::read(&BLOCK_2[..]).unwrap();
+
+        assert_eq!(
+            consensus.receive_block(&block_2_side).await.unwrap(),
+            BlockOutcome::Orphan
+        );
+        assert_eq!(
+            consensus.receive_block(&block_2_side).await.unwrap(),
+            BlockOutcome::Known
+        );
+        assert_eq!(
+            consensus.receive_block(&block_1_canon).await.unwrap(),
+            BlockOutcome::Canon
+        );
+        assert_eq!(
+            consensus.receive_block(&block_1_canon).await.unwrap(),
+            BlockOutcome::Known
+        );
+
+        // The side chain block completes a side chain longer than the canon one, which is known already.
+        assert_eq!(
+            consensus.receive_block(&block_1_side).await.unwrap(),
+            BlockOutcome::Reorganized
+        );
+        assert_eq!(
+            consensus.receive_block(&block_1_canon).await.unwrap(),
+            BlockOutcome::Known
+        );
+    }
+
+    // Switch to a longer side chain and check the reorganization notification and the resulting chain tips.
+    #[tokio::test]
+    async fn reorgs_are_notified() {
+        let consensus = snarkos_testing::sync::create_test_consensus();
+        let mut reorgs = consensus.subscribe_reorgs();
+
+        let block_1_canon = Block::<Tx>::read(&ALTERNATIVE_BLOCK_1[..]).unwrap();
+        let block_1_side = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+        let block_2_side = Block::<Tx>::read(&BLOCK_2[..]).unwrap();
+
+        consensus.receive_block(&block_1_canon).await.unwrap();
+        consensus.receive_block(&block_1_side).await.unwrap();
+        assert!(reorgs.try_recv().is_err());
+
+        let tips = consensus.ledger.get_chain_tips().unwrap();
+        assert_eq!(tips.len(), 2);
+        assert_eq!(tips[0].hash, block_1_canon.header.get_hash());
+        assert_eq!(tips[0].status, ChainTipStatus::Active);
+        assert_eq!(tips[1].hash, block_1_side.header.get_hash());
+        assert_eq!(tips[1].status, ChainTipStatus::Fork);
+        assert_eq!((tips[1].height, tips[1].branch_length), (Some(1), 1));
+
+        consensus.receive_block(&block_2_side).await.unwrap();
+        let reorg = reorgs.try_recv().unwrap();
+        assert_eq!(reorg.old_tip, block_1_canon.header.get_hash());
+        assert_eq!(reorg.old_height, 1);
+        assert_eq!(reorg.new_tip, block_2_side.header.get_hash());
+        assert_eq!(reorg.new_height, 2);
+        assert_eq!(reorg.shared_block_height, 0);
+
+        // The previous canon block is now the tip of a side chain.
+        let tips = consensus.ledger.get_chain_tips().unwrap();
+        assert_eq!(tips.len(), 2);
+        assert_eq!(tips[0].hash, block_2_side.header.get_hash());
+        assert_eq!(tips[0].height, Some(2));
+        assert_eq!(tips[1].hash, block_1_canon.header.get_hash());
+        assert_eq!(tips[1].status, ChainTipStatus::Fork);
+    }
+
+    // Revert the canon block and check that the tips of the branches are indexed, and re-indexed by the migration.
+    #[tokio::test]
+    async fn branch_tips_are_indexed() {
+        let consensus = snarkos_testing::sync::create_test_consensus();
+
+        let block_1_canon = Block::<Tx>::read(&ALTERNATIVE_BLOCK_1[..]).unwrap();
+        let block_1_side = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+
+        consensus.receive_block(&block_1_canon).await.unwrap();
+        consensus.receive_block(&block_1_side).await.unwrap();
+
+        // The reverted canon block becomes the tip of another branch.
+        consensus.ledger.decommit_latest_block().unwrap();
+        let tips = consensus.ledger.get_chain_tips().unwrap();
+        assert_eq!(tips.len(), 3);
+        assert_eq!(tips[0].height, Some(0));
+        assert!(tips[1..].iter().any(|tip| tip.hash == block_1_canon.header.get_hash()));
+        assert!(tips[1..].iter().any(|tip| tip.hash == block_1_side.header.get_hash()));
+
+        // Storage predating the index has the tips indexed by the migration.
+        let storage = &consensus.ledger.storage;
+        let indexed = storage.get_keys(COL_BRANCH_TIPS).unwrap();
+        let mut database_transaction = DatabaseTransaction::new();
+        for key in indexed {
+            database_transaction.push(Op::Delete {
+                col: COL_BRANCH_TIPS,
+                key: key.to_vec(),
+            });
+        }
+        storage.batch(database_transaction).unwrap();
+        assert_eq!(consensus.ledger.get_chain_tips().unwrap().len(), 1);
+
+        set_schema_version(storage, 4).unwrap();
+        assert_eq!(
+            MigrationRunner::new(storage, migrations::<Tx, _>()).run().unwrap(),
+            vec![5]
+        );
+        let reindexed = consensus.ledger.get_chain_tips().unwrap();
+        assert_eq!(reindexed.len(), tips.len());
+        assert!(reindexed.iter().all(|tip| tips.contains(tip)));
+    }
+
     // Receive blocks from a sidechain (out of order) that overtakes our current canonical chain.
     #[tokio::test]
     async fn fork_out_of_order() {
@@ -15,7 +15,7 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 mod miner {
-    use snarkos_consensus::Miner;
+    use snarkos_consensus::{Miner, MiningController, MiningHooks};
     use snarkos_testing::sync::*;
     use snarkvm_algorithms::traits::{
         commitment::CommitmentScheme,
@@ -24,8 +24,10 @@ mod miner {
     };
     use snarkvm_dpc::{
         block::Transactions as DPCTransactions,
+        testnet1::instantiated::Tx,
         AccountAddress,
         AccountPrivateKey,
+        Block,
         BlockHeader,
         DPCComponents,
     };
@@ -74,4 +76,40 @@ mod miner {
         let parent_header = genesis().header;
         test_find_block(&transactions, &parent_header);
     }
+
+    struct IdleHooks;
+
+    impl MiningHooks for IdleHooks {
+        fn is_paused(&self) -> bool {
+            true
+        }
+
+        fn block_mined(&self, _block: &Block<Tx>) {}
+    }
+
+    #[test]
+    fn mining_controller_starts_and_stops() {
+        let consensus = Arc::new(snarkos_testing::sync::create_test_consensus());
+        let mut rng = XorShiftRng::seed_from_u64(3);
+
+        let (_, miner_address) = keygen(&mut rng);
+        let controller = MiningController::new(miner_address, consensus, Arc::new(IdleHooks));
+
+        assert!(controller.start(0).is_err());
+        assert!(!controller.is_mining());
+        assert!(!controller.stop());
+
+        // The threads stay paused, so no blocks are mined in the meantime.
+        controller.start(2).unwrap();
+        assert!(controller.is_mining());
+        assert_eq!(controller.num_threads(), 2);
+
+        controller.start(1).unwrap();
+        assert_eq!(controller.num_threads(), 1);
+        assert_eq!(controller.proofs_per_hour(), 0);
+
+        assert!(controller.stop());
+        assert!(!controller.is_mining());
+        assert_eq!(controller.num_threads(), 0);
+    }
 }
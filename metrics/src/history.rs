@@ -0,0 +1,86 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::snapshots::{NodeStats, NodeStatsEntry};
+
+/// A bounded history of periodic [`NodeStats`] snapshots; the oldest ones are discarded first.
+pub struct StatsHistory {
+    entries: Mutex<VecDeque<NodeStatsEntry>>,
+    capacity: usize,
+}
+
+impl StatsHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    /// Records the given snapshot with the current timestamp.
+    pub fn push(&self, stats: NodeStats) {
+        let mut entries = self.entries.lock().unwrap();
+
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+
+        entries.push_back(NodeStatsEntry {
+            timestamp: unix_timestamp(),
+            stats,
+        });
+    }
+
+    /// Returns the snapshots taken within the last `range_secs` seconds, with at least
+    /// `step_secs` seconds between each of them, from the oldest to the newest.
+    pub fn query(&self, range_secs: u64, step_secs: u64) -> Vec<NodeStatsEntry> {
+        let since = unix_timestamp().saturating_sub(range_secs);
+        let entries = self.entries.lock().unwrap();
+
+        let mut selected: Vec<NodeStatsEntry> = Vec::new();
+        for entry in entries.iter().filter(|entry| entry.timestamp >= since) {
+            match selected.last() {
+                Some(last) if entry.timestamp < last.timestamp + step_secs => continue,
+                _ => selected.push(entry.clone()),
+            }
+        }
+
+        selected
+    }
+
+    /// Returns the number of stored snapshots.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Returns `true` if no snapshots have been stored yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
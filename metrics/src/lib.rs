@@ -19,6 +19,7 @@ mod names;
 
 pub use names::*;
 
+pub mod history;
 pub mod snapshots;
 pub mod stats;
 
@@ -18,16 +18,21 @@ pub mod inbound {
     pub const ALL_SUCCESSES: &str = "snarkos_inbound_all_successes_total";
     pub const ALL_FAILURES: &str = "snarkos_inbound_all_failures_total";
     pub const BLOCKS: &str = "snarkos_inbound_blocks_total";
+    pub const BLOCKHASHES: &str = "snarkos_inbound_blockhashes_total";
+    pub const GETBLOCK: &str = "snarkos_inbound_getblock_total";
     pub const GETBLOCKS: &str = "snarkos_inbound_getblocks_total";
     pub const GETMEMORYPOOL: &str = "snarkos_inbound_getmemorypool_total";
     pub const GETPEERS: &str = "snarkos_inbound_getpeers_total";
     pub const GETSYNC: &str = "snarkos_inbound_getsync_total";
+    pub const GETSYNC_DEEP: &str = "snarkos_inbound_getsync_deep_total";
+    pub const GETSYNC_REPEATED: &str = "snarkos_inbound_getsync_repeated_total";
     pub const MEMORYPOOL: &str = "snarkos_inbound_memorypool_total";
     pub const PEERS: &str = "snarkos_inbound_peers_total";
     pub const PINGS: &str = "snarkos_inbound_pings_total";
     pub const PONGS: &str = "snarkos_inbound_pongs_total";
     pub const SYNCS: &str = "snarkos_inbound_syncs_total";
     pub const SYNCBLOCKS: &str = "snarkos_inbound_syncblocks_total";
+    pub const SYNC_REQUESTS_THROTTLED: &str = "snarkos_inbound_sync_requests_throttled_total";
     pub const TRANSACTIONS: &str = "snarkos_inbound_transactions_total";
     pub const UNKNOWN: &str = "snarkos_inbound_unknown_total";
 }
@@ -44,6 +49,11 @@ pub mod connections {
     pub const CONNECTING: &str = "snarkos_connections_connecting_total";
     pub const CONNECTED: &str = "snarkos_connections_connected_total";
     pub const DISCONNECTED: &str = "snarkos_connections_disconnected_total";
+    pub const EVICTED_EXCESS: &str = "snarkos_connections_evicted_excess_total";
+    pub const EVICTED_EXPIRED: &str = "snarkos_connections_evicted_expired_total";
+    pub const EVICTED_FAILING: &str = "snarkos_connections_evicted_failing_total";
+    pub const INBOUND: &str = "snarkos_connections_inbound_total";
+    pub const OUTBOUND: &str = "snarkos_connections_outbound_total";
 }
 
 pub mod handshakes {
@@ -60,10 +70,24 @@ pub mod queues {
     pub const OUTBOUND: &str = "snarkos_queues_outbound_total";
 }
 
+pub mod latency {
+    pub const PEER_RTT: &str = "snarkos_latency_peer_rtt_ms";
+    pub const RTT_P50: &str = "snarkos_latency_rtt_p50_ms";
+    pub const RTT_P95: &str = "snarkos_latency_rtt_p95_ms";
+    pub const RTT_P99: &str = "snarkos_latency_rtt_p99_ms";
+}
+
 pub mod misc {
+    pub const ACTIVE_ALARMS: &str = "snarkos_misc_active_alarms_total";
+    pub const ALARMS_RAISED: &str = "snarkos_misc_alarms_raised_total";
     pub const BLOCK_HEIGHT: &str = "snarkos_misc_block_height_total";
     pub const BLOCKS_MINED: &str = "snarkos_misc_blocks_mined_total";
     pub const DUPLICATE_BLOCKS: &str = "snarkos_misc_duplicate_blocks_total";
     pub const DUPLICATE_SYNC_BLOCKS: &str = "snarkos_misc_duplicate_sync_blocks_total";
+    pub const MINING_PROOF_RATE: &str = "snarkos_misc_mining_proofs_per_hour";
+    pub const MINING_THREADS: &str = "snarkos_misc_mining_threads_total";
     pub const RPC_REQUESTS: &str = "snarkos_misc_rpc_requests_total";
+    pub const SYNC_ATTEMPTS: &str = "snarkos_misc_sync_attempts_total";
+    pub const SYNC_BATCHES: &str = "snarkos_misc_sync_batches_total";
+    pub const SYNC_STALLS: &str = "snarkos_misc_sync_stalls_total";
 }
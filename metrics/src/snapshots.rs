@@ -29,10 +29,21 @@ pub struct NodeStats {
     pub handshakes: NodeHandshakeStats,
     /// Stats related to the node's queues.
     pub queues: NodeQueueStats,
+    /// Stats related to the latency of the node's peers.
+    pub latency: NodeLatencyStats,
     /// Miscellaneous stats related to the node.
     pub misc: NodeMiscStats,
 }
 
+/// A single entry returned by the `getnodestatshistory` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct NodeStatsEntry {
+    /// The UNIX timestamp of when the snapshot was taken.
+    pub timestamp: u64,
+    /// The node's stats at the time.
+    pub stats: NodeStats,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct NodeInboundStats {
     /// The number of successfully processed inbound messages.
@@ -41,6 +52,10 @@ pub struct NodeInboundStats {
     pub all_failures: u64,
     /// The number of all received `Block` messages.
     pub blocks: u64,
+    /// The number of all received `BlockHash` messages.
+    pub blockhashes: u64,
+    /// The number of all received `GetBlock` messages.
+    pub getblock: u64,
     /// The number of all received `GetBlocks` messages.
     pub getblocks: u64,
     /// The number of all received `GetMemoryPool` messages.
@@ -49,6 +64,10 @@ pub struct NodeInboundStats {
     pub getpeers: u64,
     /// The number of all received `GetSync` messages.
     pub getsync: u64,
+    /// The number of received `GetSync` messages whose latest shared block was deep below the node's tip.
+    pub getsync_deep: u64,
+    /// The number of received `GetSync` messages that repeated the sender's previous one.
+    pub getsync_repeated: u64,
     /// The number of all received `MemoryPool` messages.
     pub memorypool: u64,
     /// The number of all received `Peers` messages.
@@ -61,6 +80,8 @@ pub struct NodeInboundStats {
     pub syncs: u64,
     /// The number of all received `SyncBlock` messages.
     pub syncblocks: u64,
+    /// The number of received `GetBlocks` and `GetSync` messages that exceeded the sender's serving quota.
+    pub sync_requests_throttled: u64,
     /// The number of all received `Transaction` messages.
     pub transactions: u64,
     /// The number of all received `Unknown` messages.
@@ -89,6 +110,16 @@ pub struct NodeConnectionStats {
     pub connected_peers: u32,
     /// Number of known disconnected peers.
     pub disconnected_peers: u32,
+    /// The number of disconnected peers evicted to keep the peer book within its size limits.
+    pub evicted_excess: u64,
+    /// The number of disconnected peers evicted as they weren't connected to for too long.
+    pub evicted_expired: u64,
+    /// The number of disconnected peers evicted after too many failed connection attempts.
+    pub evicted_failing: u64,
+    /// Number of connected peers that connected to the node.
+    pub inbound_peers: u32,
+    /// Number of connected peers the node connected to.
+    pub outbound_peers: u32,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -115,16 +146,40 @@ pub struct NodeQueueStats {
     pub outbound: u64,
 }
 
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct NodeLatencyStats {
+    /// The median round-trip time across the recent samples of all the connected peers.
+    pub rtt_p50_ms: u64,
+    /// The 95th percentile of the round-trip times across the recent samples of all the connected peers.
+    pub rtt_p95_ms: u64,
+    /// The 99th percentile of the round-trip times across the recent samples of all the connected peers.
+    pub rtt_p99_ms: u64,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct NodeMiscStats {
     /// The current block height of the node.
     pub block_height: u64,
+    /// The number of currently raised alarms.
+    pub active_alarms: u64,
+    /// The number of alarms raised since the node started.
+    pub alarms_raised: u64,
     /// The number of blocks the node has mined.
     pub blocks_mined: u64,
     /// The number of duplicate blocks received.
     pub duplicate_blocks: u64,
     /// The number of duplicate sync blocks received.
     pub duplicate_sync_blocks: u64,
+    /// The number of threads the node is mining with.
+    pub mining_threads: u64,
+    /// The estimated number of proofs computed per hour by the miners.
+    pub proofs_per_hour: u64,
     /// The number of RPC requests received.
     pub rpc_requests: u64,
+    /// The number of block sync attempts.
+    pub sync_attempts: u64,
+    /// The number of batches of sync blocks requested.
+    pub sync_batches: u64,
+    /// The number of batches of sync blocks that weren't completed in time.
+    pub sync_stalls: u64,
 }
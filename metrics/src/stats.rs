@@ -23,6 +23,7 @@ use crate::{
         NodeConnectionStats,
         NodeHandshakeStats,
         NodeInboundStats,
+        NodeLatencyStats,
         NodeMiscStats,
         NodeOutboundStats,
         NodeQueueStats,
@@ -43,6 +44,8 @@ pub struct Stats {
     handshakes: HandshakeStats,
     /// Stats related to the node's queues.
     queues: QueueStats,
+    /// Stats related to the latency of the node's peers.
+    latency: LatencyStats,
     /// Miscellaneous stats related to the node.
     misc: MiscStats,
 }
@@ -55,6 +58,7 @@ impl Stats {
             connections: ConnectionStats::new(),
             handshakes: HandshakeStats::new(),
             queues: QueueStats::new(),
+            latency: LatencyStats::new(),
             misc: MiscStats::new(),
         }
     }
@@ -66,6 +70,7 @@ impl Stats {
             connections: self.connections.snapshot(),
             handshakes: self.handshakes.snapshot(),
             queues: self.queues.snapshot(),
+            latency: self.latency.snapshot(),
             misc: self.misc.snapshot(),
         }
     }
@@ -78,6 +83,10 @@ pub struct InboundStats {
     all_failures: Counter,
     /// The number of all received `Block` messages.
     blocks: Counter,
+    /// The number of all received `BlockHash` messages.
+    blockhashes: Counter,
+    /// The number of all received `GetBlock` messages.
+    getblock: Counter,
     /// The number of all received `GetBlocks` messages.
     getblocks: Counter,
     /// The number of all received `GetMemoryPool` messages.
@@ -86,6 +95,10 @@ pub struct InboundStats {
     getpeers: Counter,
     /// The number of all received `GetSync` messages.
     getsync: Counter,
+    /// The number of received `GetSync` messages whose latest shared block was deep below the node's tip.
+    getsync_deep: Counter,
+    /// The number of received `GetSync` messages that repeated the sender's previous one.
+    getsync_repeated: Counter,
     /// The number of all received `MemoryPool` messages.
     memorypool: Counter,
     /// The number of all received `Peers` messages.
@@ -98,6 +111,8 @@ pub struct InboundStats {
     syncs: Counter,
     /// The number of all received `SyncBlock` messages.
     syncblocks: Counter,
+    /// The number of received `GetBlocks` and `GetSync` messages that exceeded the sender's serving quota.
+    sync_requests_throttled: Counter,
     /// The number of all received `Transaction` messages.
     transactions: Counter,
     /// The number of all received `Unknown` messages.
@@ -110,16 +125,21 @@ impl InboundStats {
             all_successes: Counter::new(),
             all_failures: Counter::new(),
             blocks: Counter::new(),
+            blockhashes: Counter::new(),
+            getblock: Counter::new(),
             getblocks: Counter::new(),
             getmemorypool: Counter::new(),
             getpeers: Counter::new(),
             getsync: Counter::new(),
+            getsync_deep: Counter::new(),
+            getsync_repeated: Counter::new(),
             memorypool: Counter::new(),
             peers: Counter::new(),
             pings: Counter::new(),
             pongs: Counter::new(),
             syncs: Counter::new(),
             syncblocks: Counter::new(),
+            sync_requests_throttled: Counter::new(),
             transactions: Counter::new(),
             unknown: Counter::new(),
         }
@@ -130,16 +150,21 @@ impl InboundStats {
             all_successes: self.all_successes.read(),
             all_failures: self.all_failures.read(),
             blocks: self.blocks.read(),
+            blockhashes: self.blockhashes.read(),
+            getblock: self.getblock.read(),
             getblocks: self.getblocks.read(),
             getmemorypool: self.getmemorypool.read(),
             getpeers: self.getpeers.read(),
             getsync: self.getsync.read(),
+            getsync_deep: self.getsync_deep.read(),
+            getsync_repeated: self.getsync_repeated.read(),
             memorypool: self.memorypool.read(),
             peers: self.peers.read(),
             pings: self.pings.read(),
             pongs: self.pongs.read(),
             syncs: self.syncs.read(),
             syncblocks: self.syncblocks.read(),
+            sync_requests_throttled: self.sync_requests_throttled.read(),
             transactions: self.transactions.read(),
             unknown: self.unknown.read(),
         }
@@ -182,6 +207,16 @@ pub struct ConnectionStats {
     connected_peers: DiscreteGauge,
     /// Number of known disconnected peers.
     disconnected_peers: DiscreteGauge,
+    /// The number of disconnected peers evicted to keep the peer book within its size limits.
+    evicted_excess: Counter,
+    /// The number of disconnected peers evicted as they weren't connected to for too long.
+    evicted_expired: Counter,
+    /// The number of disconnected peers evicted after too many failed connection attempts.
+    evicted_failing: Counter,
+    /// Number of connected peers that connected to the node.
+    inbound_peers: DiscreteGauge,
+    /// Number of connected peers the node connected to.
+    outbound_peers: DiscreteGauge,
 }
 
 impl ConnectionStats {
@@ -193,6 +228,11 @@ impl ConnectionStats {
             connecting_peers: DiscreteGauge::new(),
             connected_peers: DiscreteGauge::new(),
             disconnected_peers: DiscreteGauge::new(),
+            evicted_excess: Counter::new(),
+            evicted_expired: Counter::new(),
+            evicted_failing: Counter::new(),
+            inbound_peers: DiscreteGauge::new(),
+            outbound_peers: DiscreteGauge::new(),
         }
     }
 
@@ -204,6 +244,11 @@ impl ConnectionStats {
             connecting_peers: self.connecting_peers.read() as u32,
             connected_peers: self.connected_peers.read() as u32,
             disconnected_peers: self.disconnected_peers.read() as u32,
+            evicted_excess: self.evicted_excess.read(),
+            evicted_expired: self.evicted_expired.read(),
+            evicted_failing: self.evicted_failing.read(),
+            inbound_peers: self.inbound_peers.read() as u32,
+            outbound_peers: self.outbound_peers.read() as u32,
         }
     }
 }
@@ -270,36 +315,91 @@ impl QueueStats {
     }
 }
 
+pub struct LatencyStats {
+    /// The median round-trip time across the recent samples of all the connected peers.
+    rtt_p50_ms: DiscreteGauge,
+    /// The 95th percentile of the round-trip times across the recent samples of all the connected peers.
+    rtt_p95_ms: DiscreteGauge,
+    /// The 99th percentile of the round-trip times across the recent samples of all the connected peers.
+    rtt_p99_ms: DiscreteGauge,
+}
+
+impl LatencyStats {
+    const fn new() -> Self {
+        Self {
+            rtt_p50_ms: DiscreteGauge::new(),
+            rtt_p95_ms: DiscreteGauge::new(),
+            rtt_p99_ms: DiscreteGauge::new(),
+        }
+    }
+
+    pub fn snapshot(&self) -> NodeLatencyStats {
+        NodeLatencyStats {
+            rtt_p50_ms: self.rtt_p50_ms.read(),
+            rtt_p95_ms: self.rtt_p95_ms.read(),
+            rtt_p99_ms: self.rtt_p99_ms.read(),
+        }
+    }
+}
+
 pub struct MiscStats {
     block_height: DiscreteGauge,
+    /// The number of currently raised alarms.
+    active_alarms: DiscreteGauge,
+    /// The number of alarms raised.
+    alarms_raised: Counter,
     /// The number of mined blocks.
     blocks_mined: Counter,
     /// The number of duplicate blocks received.
     duplicate_blocks: Counter,
     /// The number of duplicate sync blocks received.
     duplicate_sync_blocks: Counter,
+    /// The number of threads the node is mining with.
+    mining_threads: DiscreteGauge,
+    /// The estimated number of proofs computed per hour by the miners.
+    proofs_per_hour: DiscreteGauge,
     /// The number of RPC requests received.
     rpc_requests: Counter,
+    /// The number of block sync attempts.
+    sync_attempts: Counter,
+    /// The number of batches of sync blocks requested.
+    sync_batches: Counter,
+    /// The number of batches of sync blocks that weren't completed in time.
+    sync_stalls: Counter,
 }
 
 impl MiscStats {
     const fn new() -> Self {
         Self {
             block_height: DiscreteGauge::new(),
+            active_alarms: DiscreteGauge::new(),
+            alarms_raised: Counter::new(),
             blocks_mined: Counter::new(),
             duplicate_blocks: Counter::new(),
             duplicate_sync_blocks: Counter::new(),
+            mining_threads: DiscreteGauge::new(),
+            proofs_per_hour: DiscreteGauge::new(),
             rpc_requests: Counter::new(),
+            sync_attempts: Counter::new(),
+            sync_batches: Counter::new(),
+            sync_stalls: Counter::new(),
         }
     }
 
     pub fn snapshot(&self) -> NodeMiscStats {
         NodeMiscStats {
             block_height: self.block_height.read(),
+            active_alarms: self.active_alarms.read(),
+            alarms_raised: self.alarms_raised.read(),
             blocks_mined: self.blocks_mined.read(),
             duplicate_blocks: self.duplicate_blocks.read(),
             duplicate_sync_blocks: self.duplicate_sync_blocks.read(),
+            mining_threads: self.mining_threads.read(),
+            proofs_per_hour: self.proofs_per_hour.read(),
             rpc_requests: self.rpc_requests.read(),
+            sync_attempts: self.sync_attempts.read(),
+            sync_batches: self.sync_batches.read(),
+            sync_stalls: self.sync_stalls.read(),
         }
     }
 }
@@ -320,16 +420,21 @@ impl Recorder for Stats {
             inbound::ALL_SUCCESSES => &self.inbound.all_successes,
             inbound::ALL_FAILURES => &self.inbound.all_failures,
             inbound::BLOCKS => &self.inbound.blocks,
+            inbound::BLOCKHASHES => &self.inbound.blockhashes,
+            inbound::GETBLOCK => &self.inbound.getblock,
             inbound::GETBLOCKS => &self.inbound.getblocks,
             inbound::GETMEMORYPOOL => &self.inbound.getmemorypool,
             inbound::GETPEERS => &self.inbound.getpeers,
             inbound::GETSYNC => &self.inbound.getsync,
+            inbound::GETSYNC_DEEP => &self.inbound.getsync_deep,
+            inbound::GETSYNC_REPEATED => &self.inbound.getsync_repeated,
             inbound::MEMORYPOOL => &self.inbound.memorypool,
             inbound::PEERS => &self.inbound.peers,
             inbound::PINGS => &self.inbound.pings,
             inbound::PONGS => &self.inbound.pongs,
             inbound::SYNCS => &self.inbound.syncs,
             inbound::SYNCBLOCKS => &self.inbound.syncblocks,
+            inbound::SYNC_REQUESTS_THROTTLED => &self.inbound.sync_requests_throttled,
             inbound::TRANSACTIONS => &self.inbound.transactions,
             inbound::UNKNOWN => &self.inbound.unknown,
             // outbound
@@ -339,6 +444,9 @@ impl Recorder for Stats {
             connections::ALL_ACCEPTED => &self.connections.all_accepted,
             connections::ALL_INITIATED => &self.connections.all_initiated,
             connections::ALL_REJECTED => &self.connections.all_rejected,
+            connections::EVICTED_EXCESS => &self.connections.evicted_excess,
+            connections::EVICTED_EXPIRED => &self.connections.evicted_expired,
+            connections::EVICTED_FAILING => &self.connections.evicted_failing,
             // handshakes
             handshakes::FAILURES_INIT => &self.handshakes.failures_init,
             handshakes::FAILURES_RESP => &self.handshakes.failures_resp,
@@ -347,10 +455,14 @@ impl Recorder for Stats {
             handshakes::TIMEOUTS_INIT => &self.handshakes.timeouts_init,
             handshakes::TIMEOUTS_RESP => &self.handshakes.timeouts_resp,
             // misc
+            misc::ALARMS_RAISED => &self.misc.alarms_raised,
             misc::BLOCKS_MINED => &self.misc.blocks_mined,
             misc::DUPLICATE_BLOCKS => &self.misc.duplicate_blocks,
             misc::DUPLICATE_SYNC_BLOCKS => &self.misc.duplicate_sync_blocks,
             misc::RPC_REQUESTS => &self.misc.rpc_requests,
+            misc::SYNC_ATTEMPTS => &self.misc.sync_attempts,
+            misc::SYNC_BATCHES => &self.misc.sync_batches,
+            misc::SYNC_STALLS => &self.misc.sync_stalls,
             _ => {
                 return;
             }
@@ -363,12 +475,21 @@ impl Recorder for Stats {
             // queues
             queues::INBOUND => &self.queues.inbound,
             queues::OUTBOUND => &self.queues.outbound,
+            // latency
+            latency::RTT_P50 => &self.latency.rtt_p50_ms,
+            latency::RTT_P95 => &self.latency.rtt_p95_ms,
+            latency::RTT_P99 => &self.latency.rtt_p99_ms,
             // misc
             misc::BLOCK_HEIGHT => &self.misc.block_height,
+            misc::ACTIVE_ALARMS => &self.misc.active_alarms,
+            misc::MINING_THREADS => &self.misc.mining_threads,
+            misc::MINING_PROOF_RATE => &self.misc.proofs_per_hour,
             // connections
             connections::CONNECTING => &self.connections.connecting_peers,
             connections::CONNECTED => &self.connections.connected_peers,
             connections::DISCONNECTED => &self.connections.disconnected_peers,
+            connections::INBOUND => &self.connections.inbound_peers,
+            connections::OUTBOUND => &self.connections.outbound_peers,
             _ => {
                 return;
             }
@@ -0,0 +1,377 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{Node, WebhookEvent};
+use snarkos_metrics::{self as metrics, misc};
+use snarkvm_dpc::Storage;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tokio::sync::broadcast;
+
+/// The thresholds at which the node raises alarms about the state of its chain and connectivity;
+/// the rules that aren't set are never evaluated.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AlarmRules {
+    /// The age of the latest block above which the chain is considered stalled.
+    pub max_tip_age: Option<Duration>,
+    /// The number of connected peers below which the node is considered poorly connected.
+    pub min_peers: Option<usize>,
+    /// The number of pending transactions above which the memory pool is considered stuck, once it
+    /// stays there for `mempool_stuck_for`.
+    pub max_mempool_transactions: Option<usize>,
+    /// The amount of time the memory pool needs to stay above its threshold for to be considered stuck.
+    pub mempool_stuck_for: Duration,
+}
+
+impl AlarmRules {
+    /// Returns `true` if none of the rules are set.
+    pub fn is_empty(&self) -> bool {
+        self.max_tip_age.is_none() && self.min_peers.is_none() && self.max_mempool_transactions.is_none()
+    }
+
+    /// Returns the alarms whose conditions currently hold for the given readings.
+    pub fn breached(&self, readings: &AlarmReadings) -> Vec<Alarm> {
+        let mut breached = vec![];
+
+        if let (Some(max_tip_age), Some(tip_age)) = (self.max_tip_age, readings.tip_age) {
+            if tip_age > max_tip_age {
+                breached.push(Alarm::StaleTip);
+            }
+        }
+        if let Some(min_peers) = self.min_peers {
+            if readings.connected_peers < min_peers {
+                breached.push(Alarm::LowPeers);
+            }
+        }
+        if let (Some(max_transactions), Some(transactions)) =
+            (self.max_mempool_transactions, readings.mempool_transactions)
+        {
+            if transactions > max_transactions {
+                breached.push(Alarm::MempoolStuck);
+            }
+        }
+
+        breached
+    }
+
+    /// Returns the amount of time the condition of the given alarm needs to hold for before it's raised.
+    fn grace_period(&self, alarm: Alarm) -> Duration {
+        match alarm {
+            Alarm::MempoolStuck => self.mempool_stuck_for,
+            Alarm::StaleTip | Alarm::LowPeers => Duration::from_secs(0),
+        }
+    }
+
+    /// Returns the details of the given alarm reported along with it.
+    fn details(&self, alarm: Alarm, readings: &AlarmReadings) -> serde_json::Value {
+        match alarm {
+            Alarm::StaleTip => serde_json::json!({
+                "tip_age_secs": readings.tip_age.map(|age| age.as_secs()),
+                "max_tip_age_secs": self.max_tip_age.map(|age| age.as_secs()),
+            }),
+            Alarm::LowPeers => serde_json::json!({
+                "connected_peers": readings.connected_peers,
+                "min_peers": self.min_peers,
+            }),
+            Alarm::MempoolStuck => serde_json::json!({
+                "mempool_transactions": readings.mempool_transactions,
+                "max_mempool_transactions": self.max_mempool_transactions,
+                "stuck_for_secs": self.mempool_stuck_for.as_secs(),
+            }),
+        }
+    }
+}
+
+/// The alarms the node can raise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Alarm {
+    /// The latest block is older than the configured maximum age.
+    StaleTip,
+    /// The node is connected to fewer peers than the configured minimum.
+    LowPeers,
+    /// The memory pool has held more transactions than the configured maximum for too long.
+    MempoolStuck,
+}
+
+impl fmt::Display for Alarm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::StaleTip => write!(f, "stale_tip"),
+            Self::LowPeers => write!(f, "low_peers"),
+            Self::MempoolStuck => write!(f, "mempool_stuck"),
+        }
+    }
+}
+
+/// The state of the node the alarm rules are evaluated against.
+#[derive(Clone, Debug, Default)]
+pub struct AlarmReadings {
+    /// The age of the latest block, if the node follows the chain.
+    pub tip_age: Option<Duration>,
+    /// The number of connected peers.
+    pub connected_peers: usize,
+    /// The number of transactions in the memory pool, if the node keeps one.
+    pub mempool_transactions: Option<usize>,
+}
+
+/// A notification about an alarm being raised or cleared.
+#[derive(Clone, Debug, Serialize)]
+pub struct AlarmEvent {
+    /// The alarm the event is about.
+    pub alarm: Alarm,
+    /// `true` if the alarm was raised, `false` if it was cleared.
+    pub raised: bool,
+    /// The time at which the alarm was raised or cleared.
+    pub timestamp: DateTime<Utc>,
+    /// The readings and thresholds relevant to the alarm.
+    pub details: serde_json::Value,
+}
+
+/// The state of the node's alarms.
+pub struct Alarms {
+    /// The sender of the alarm events to the subscribers.
+    events: broadcast::Sender<AlarmEvent>,
+    /// The time since which the condition of each alarm has held, and whether the alarm was raised.
+    conditions: Mutex<HashMap<Alarm, (Instant, bool)>>,
+}
+
+impl Default for Alarms {
+    fn default() -> Self {
+        let (events, _) = broadcast::channel(crate::ALARM_EVENT_CHANNEL_DEPTH);
+
+        Self {
+            events,
+            conditions: Default::default(),
+        }
+    }
+}
+
+impl Alarms {
+    /// Returns a receiver of the events about alarms being raised or cleared.
+    pub fn subscribe(&self) -> broadcast::Receiver<AlarmEvent> {
+        self.events.subscribe()
+    }
+
+    /// Returns the currently raised alarms.
+    pub fn active(&self) -> Vec<Alarm> {
+        self.conditions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, (_, raised))| *raised)
+            .map(|(alarm, _)| *alarm)
+            .collect()
+    }
+
+    /// Updates the state of the alarms with the ones whose conditions currently hold; returns the alarms
+    /// that were raised and the ones that were cleared as a result.
+    pub fn update(&self, rules: &AlarmRules, breached: &[Alarm], now: Instant) -> (Vec<Alarm>, Vec<Alarm>) {
+        let mut conditions = self.conditions.lock().unwrap();
+
+        let mut cleared = vec![];
+        conditions.retain(|alarm, (_, raised)| {
+            let holds = breached.contains(alarm);
+            if !holds && *raised {
+                cleared.push(*alarm);
+            }
+            holds
+        });
+
+        let mut raised = vec![];
+        for alarm in breached {
+            let (since, is_raised) = conditions.entry(*alarm).or_insert((now, false));
+            if !*is_raised && now.saturating_duration_since(*since) >= rules.grace_period(*alarm) {
+                *is_raised = true;
+                raised.push(*alarm);
+            }
+        }
+
+        (raised, cleared)
+    }
+
+    /// Sends an event about the given alarm to the subscribers, if there are any.
+    fn emit(&self, event: AlarmEvent) {
+        // An error only means that there are no subscribers at the moment.
+        let _ = self.events.send(event);
+    }
+}
+
+impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
+    /// Returns a receiver of the events about the node's alarms being raised or cleared.
+    pub fn subscribe_alarms(&self) -> broadcast::Receiver<AlarmEvent> {
+        self.alarms.subscribe()
+    }
+
+    /// Collects the state of the node the alarm rules are evaluated against.
+    fn alarm_readings(&self) -> AlarmReadings {
+        let connected_peers = self.peer_book.get_active_peer_count() as usize;
+
+        #[cfg(feature = "consensus")]
+        let (tip_age, mempool_transactions) = match self.sync() {
+            Some(sync) => {
+                let storage = sync.storage();
+                let tip_time = storage
+                    .get_block_hash(sync.current_block_height())
+                    .and_then(|hash| storage.get_block_header(&hash))
+                    .map(|header| header.time)
+                    .ok();
+                let tip_age = tip_time.map(|time| Duration::from_secs((Utc::now().timestamp() - time).max(0) as u64));
+
+                (tip_age, Some(sync.memory_pool().transactions.len()))
+            }
+            None => (None, None),
+        };
+        // Without the `consensus` feature, the node follows neither the chain nor the memory pool.
+        #[cfg(not(feature = "consensus"))]
+        let (tip_age, mempool_transactions) = (None, None);
+
+        AlarmReadings {
+            tip_age,
+            connected_peers,
+            mempool_transactions,
+        }
+    }
+
+    /// Evaluates the configured alarm rules, raising the alarms whose conditions hold and clearing the ones
+    /// whose conditions no longer do; the changes are logged, counted, sent to the subscribers and reported
+    /// to the webhook.
+    pub(crate) fn check_alarms(&self) {
+        let rules = &self.config.alarms;
+        let readings = self.alarm_readings();
+        let breached = rules.breached(&readings);
+
+        let (raised, cleared) = self.alarms.update(rules, &breached, Instant::now());
+        let changes = raised
+            .into_iter()
+            .map(|alarm| (alarm, true))
+            .chain(cleared.into_iter().map(|alarm| (alarm, false)));
+
+        for (alarm, is_raised) in changes {
+            let details = rules.details(alarm, &readings);
+
+            if is_raised {
+                warn!("Alarm '{}' raised: {}", alarm, details);
+                metrics::increment_counter!(misc::ALARMS_RAISED);
+                metrics::increment_gauge!(misc::ACTIVE_ALARMS, 1.0);
+            } else {
+                info!("Alarm '{}' cleared: {}", alarm, details);
+                metrics::decrement_gauge!(misc::ACTIVE_ALARMS, 1.0);
+            }
+
+            self.notify_webhook(
+                WebhookEvent::Alarm,
+                serde_json::json!({
+                    "alarm": alarm,
+                    "raised": is_raised,
+                    "details": details,
+                }),
+            );
+
+            self.alarms.emit(AlarmEvent {
+                alarm,
+                raised: is_raised,
+                timestamp: Utc::now(),
+                details,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> AlarmRules {
+        AlarmRules {
+            max_tip_age: Some(Duration::from_secs(600)),
+            min_peers: Some(3),
+            max_mempool_transactions: Some(100),
+            mempool_stuck_for: Duration::from_secs(300),
+        }
+    }
+
+    #[test]
+    fn only_the_set_rules_are_breached() {
+        let readings = AlarmReadings {
+            tip_age: Some(Duration::from_secs(601)),
+            connected_peers: 2,
+            mempool_transactions: Some(101),
+        };
+        assert_eq!(
+            rules().breached(&readings),
+            vec![Alarm::StaleTip, Alarm::LowPeers, Alarm::MempoolStuck]
+        );
+
+        assert!(AlarmRules::default().is_empty());
+        assert!(AlarmRules::default().breached(&readings).is_empty());
+
+        // The readings that aren't available don't breach any rules.
+        let readings = AlarmReadings {
+            connected_peers: 3,
+            ..Default::default()
+        };
+        assert!(rules().breached(&readings).is_empty());
+    }
+
+    #[test]
+    fn alarms_are_raised_once_and_cleared() {
+        let alarms = Alarms::default();
+        let rules = rules();
+        let start = Instant::now();
+
+        assert_eq!(
+            alarms.update(&rules, &[Alarm::LowPeers], start),
+            (vec![Alarm::LowPeers], vec![])
+        );
+        // An alarm that's already raised isn't raised again.
+        assert_eq!(alarms.update(&rules, &[Alarm::LowPeers], start), (vec![], vec![]));
+        assert_eq!(alarms.active(), vec![Alarm::LowPeers]);
+
+        assert_eq!(alarms.update(&rules, &[], start), (vec![], vec![Alarm::LowPeers]));
+        assert!(alarms.active().is_empty());
+    }
+
+    #[test]
+    fn stuck_mempool_is_only_raised_after_the_grace_period() {
+        let alarms = Alarms::default();
+        let rules = rules();
+        let start = Instant::now();
+
+        assert_eq!(alarms.update(&rules, &[Alarm::MempoolStuck], start), (vec![], vec![]));
+        let later = start + Duration::from_secs(299);
+        assert_eq!(alarms.update(&rules, &[Alarm::MempoolStuck], later), (vec![], vec![]));
+        let later = start + Duration::from_secs(300);
+        assert_eq!(
+            alarms.update(&rules, &[Alarm::MempoolStuck], later),
+            (vec![Alarm::MempoolStuck], vec![])
+        );
+
+        // A condition that stops holding before the alarm is raised restarts the grace period.
+        let alarms = Alarms::default();
+        alarms.update(&rules, &[Alarm::MempoolStuck], start);
+        alarms.update(&rules, &[], start + Duration::from_secs(200));
+        let later = start + Duration::from_secs(400);
+        assert_eq!(alarms.update(&rules, &[Alarm::MempoolStuck], later), (vec![], vec![]));
+    }
+}
@@ -0,0 +1,275 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{Config, NetworkError, Node, NodeCallbacks, PeerConnected, SyncComplete};
+#[cfg(feature = "consensus")]
+use crate::{BlockAccepted, Sync, Watcher};
+use snarkvm_dpc::Storage;
+#[cfg(feature = "consensus")]
+use snarkvm_dpc::{testnet1::instantiated::Components, AccountAddress};
+
+#[cfg(not(feature = "consensus"))]
+use std::marker::PhantomData;
+use std::{net::SocketAddr, time::Duration};
+
+/// The layer following the chain on behalf of the node.
+#[cfg(feature = "consensus")]
+enum ChainLayer<S: Storage> {
+    Sync(Sync<S>),
+    Watcher(Watcher),
+}
+
+/// Assembles a `Node` for the applications embedding snarkOS as a library; the parameters that aren't
+/// specified use the same defaults as the snarkOS binary.
+///
+/// ```ignore
+/// let node = NodeBuilder::new("0.0.0.0:4131".parse()?)
+///     .bootnodes(vec!["50.18.83.123:4131".to_string()])
+///     .sync(sync)
+///     .on_block_accepted(|block| println!("accepted block {} at height {}", block.hash, block.height))
+///     .start()
+///     .await?;
+/// ```
+pub struct NodeBuilder<S: Storage + core::marker::Sync + Send + 'static> {
+    desired_address: SocketAddr,
+    additional_listen_addresses: Vec<SocketAddr>,
+    advertised_address: Option<SocketAddr>,
+    min_peers: u16,
+    max_peers: u16,
+    bootnodes: Vec<String>,
+    is_bootnode: bool,
+    is_crawler: bool,
+    peer_sync_interval: Duration,
+    nat_traversal: bool,
+    socks5_proxy: Option<SocketAddr>,
+    no_listen: bool,
+    dual_stack: bool,
+    /// The adjustments of the remaining parameters of the node's `Config`, applied in order.
+    adjustments: Vec<Box<dyn FnOnce(&mut Config) + Send>>,
+    #[cfg(feature = "consensus")]
+    chain_layer: Option<ChainLayer<S>>,
+    #[cfg(feature = "consensus")]
+    mining: Option<(AccountAddress<Components>, usize)>,
+    callbacks: NodeCallbacks,
+    /// Without the `consensus` feature, there's no chain layer to hold the storage.
+    #[cfg(not(feature = "consensus"))]
+    storage: PhantomData<S>,
+}
+
+impl<S: Storage + core::marker::Sync + Send + 'static> NodeBuilder<S> {
+    /// Creates a builder of a node listening on the given address; it connects to 20-50 peers and doesn't
+    /// follow the chain unless `sync` or `watcher` is used.
+    pub fn new(desired_address: SocketAddr) -> Self {
+        Self {
+            desired_address,
+            additional_listen_addresses: vec![],
+            advertised_address: None,
+            min_peers: 20,
+            max_peers: 50,
+            bootnodes: vec![],
+            is_bootnode: false,
+            is_crawler: false,
+            peer_sync_interval: Duration::from_secs(15),
+            nat_traversal: false,
+            socks5_proxy: None,
+            no_listen: false,
+            dual_stack: false,
+            adjustments: vec![],
+            #[cfg(feature = "consensus")]
+            chain_layer: None,
+            #[cfg(feature = "consensus")]
+            mining: None,
+            callbacks: Default::default(),
+            #[cfg(not(feature = "consensus"))]
+            storage: PhantomData,
+        }
+    }
+
+    /// Accepts inbound connections on an additional address, e.g. another interface.
+    pub fn listen_address(mut self, address: SocketAddr) -> Self {
+        self.additional_listen_addresses.push(address);
+        self
+    }
+
+    /// Advertises the given address to other nodes instead of the listening port.
+    pub fn advertised_address(mut self, address: SocketAddr) -> Self {
+        self.advertised_address = Some(address);
+        self
+    }
+
+    /// Sets the minimum and the maximum number of connected peers.
+    pub fn peer_limits(mut self, min_peers: u16, max_peers: u16) -> Self {
+        self.min_peers = min_peers;
+        self.max_peers = max_peers;
+        self
+    }
+
+    /// Sets the bootnodes the node connects to; the ones that aren't valid socket addresses are ignored.
+    pub fn bootnodes<I: IntoIterator<Item = String>>(mut self, bootnodes: I) -> Self {
+        self.bootnodes = bootnodes.into_iter().collect();
+        self
+    }
+
+    /// Makes the node a bootnode, which forgoes connecting to the bootnodes and the saved peers.
+    pub fn bootnode(mut self) -> Self {
+        self.is_bootnode = true;
+        self
+    }
+
+    /// Makes the node a crawler, which maps the network and doesn't participate in consensus.
+    pub fn crawler(mut self) -> Self {
+        self.is_crawler = true;
+        self
+    }
+
+    /// Sets the interval between each peer sync.
+    pub fn peer_sync_interval(mut self, interval: Duration) -> Self {
+        self.peer_sync_interval = interval;
+        self
+    }
+
+    /// Attempts to map the listening port on the local gateway via NAT-PMP or UPnP.
+    pub fn nat_traversal(mut self) -> Self {
+        self.nat_traversal = true;
+        self
+    }
+
+    /// Routes all the outbound connections through the given SOCKS5 proxy.
+    pub fn socks5_proxy(mut self, proxy: SocketAddr) -> Self {
+        self.socks5_proxy = Some(proxy);
+        self
+    }
+
+    /// Doesn't accept any inbound connections.
+    pub fn no_listen(mut self) -> Self {
+        self.no_listen = true;
+        self
+    }
+
+    /// Makes the IPv6 listeners accept IPv4 connections too, and connects to peers of both families.
+    pub fn dual_stack(mut self) -> Self {
+        self.dual_stack = true;
+        self
+    }
+
+    /// Adjusts any of the remaining parameters of the node's `Config`, e.g. its `network_id` or `peer_selection`.
+    pub fn configure<F: FnOnce(&mut Config) + Send + 'static>(mut self, adjustment: F) -> Self {
+        self.adjustments.push(Box::new(adjustment));
+        self
+    }
+
+    /// Makes the node sync and process the blocks with the given sync layer; it replaces any `watcher`.
+    #[cfg(feature = "consensus")]
+    pub fn sync(mut self, sync: Sync<S>) -> Self {
+        self.chain_layer = Some(ChainLayer::Sync(sync));
+        self
+    }
+
+    /// Makes the node follow the headers and the memory pool with the given watcher; it replaces any `sync`.
+    #[cfg(feature = "consensus")]
+    pub fn watcher(mut self, watcher: Watcher) -> Self {
+        self.chain_layer = Some(ChainLayer::Watcher(watcher));
+        self
+    }
+
+    /// Makes the node mine blocks rewarded to the given address with the given number of threads once it's
+    /// started; it requires a `sync` layer.
+    #[cfg(feature = "consensus")]
+    pub fn mining(mut self, miner_address: AccountAddress<Components>, num_threads: usize) -> Self {
+        self.mining = Some((miner_address, num_threads));
+        self
+    }
+
+    /// Registers a callback invoked whenever a block is accepted into the node's storage.
+    #[cfg(feature = "consensus")]
+    pub fn on_block_accepted<F: Fn(&BlockAccepted) + Send + core::marker::Sync + 'static>(self, callback: F) -> Self {
+        self.callbacks.on_block_accepted(callback);
+        self
+    }
+
+    /// Registers a callback invoked whenever a connection with a peer is established.
+    pub fn on_peer_connected<F: Fn(&PeerConnected) + Send + core::marker::Sync + 'static>(self, callback: F) -> Self {
+        self.callbacks.on_peer_connected(callback);
+        self
+    }
+
+    /// Registers a callback invoked whenever a block sync catches the node up with the network.
+    pub fn on_sync_complete<F: Fn(&SyncComplete) + Send + core::marker::Sync + 'static>(self, callback: F) -> Self {
+        self.callbacks.on_sync_complete(callback);
+        self
+    }
+
+    /// Creates the node without starting it; it can be started with `listen` and `start_services`.
+    /// Must be called from within a tokio runtime.
+    pub async fn build(self) -> Result<Node<S>, NetworkError> {
+        #[cfg(feature = "consensus")]
+        if self.mining.is_some() && !matches!(self.chain_layer, Some(ChainLayer::Sync(_))) {
+            return Err(NetworkError::MiningUnavailable);
+        }
+
+        let mut config = Config::new(
+            self.desired_address,
+            self.additional_listen_addresses,
+            self.advertised_address,
+            self.min_peers,
+            self.max_peers,
+            self.bootnodes,
+            self.is_bootnode,
+            self.is_crawler,
+            self.peer_sync_interval,
+            self.nat_traversal,
+            self.socks5_proxy,
+            self.no_listen,
+            self.dual_stack,
+        )?;
+        for adjustment in self.adjustments {
+            adjustment(&mut config);
+        }
+
+        let mut node = Node::new(config).await?;
+        node.callbacks.extend(self.callbacks);
+        #[cfg(feature = "consensus")]
+        {
+            match self.chain_layer {
+                Some(ChainLayer::Sync(sync)) => node.set_sync(sync),
+                Some(ChainLayer::Watcher(watcher)) => node.set_watcher(watcher),
+                None => {}
+            }
+            if let Some((miner_address, _)) = &self.mining {
+                node.enable_mining(miner_address.clone());
+            }
+        }
+
+        Ok(node)
+    }
+
+    /// Creates the node, starts listening for inbound connections and starts its services, including mining
+    /// if it was requested. Must be called from within a tokio runtime.
+    pub async fn start(self) -> Result<Node<S>, NetworkError> {
+        #[cfg(feature = "consensus")]
+        let num_mining_threads = self.mining.as_ref().map(|(_, num_threads)| *num_threads);
+        let node = self.build().await?;
+
+        node.listen().await?;
+        node.start_services().await?;
+        #[cfg(feature = "consensus")]
+        if let Some(num_threads) = num_mining_threads {
+            node.start_mining(num_threads)?;
+        }
+
+        Ok(node)
+    }
+}
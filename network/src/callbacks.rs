@@ -0,0 +1,211 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{BlockHeight, Node};
+#[cfg(feature = "consensus")]
+use snarkos_consensus::BlockOutcome;
+use snarkvm_dpc::{BlockHeaderHash, Storage};
+
+use std::{
+    net::SocketAddr,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, RwLock},
+};
+
+/// A block accepted into the node's storage, either received from a peer or mined by the node.
+#[cfg(feature = "consensus")]
+#[derive(Clone, Debug)]
+pub struct BlockAccepted {
+    /// The hash of the block.
+    pub hash: BlockHeaderHash,
+    /// The way the block was incorporated into the node's chain; it may also extend a side chain.
+    pub outcome: BlockOutcome,
+    /// The peer the block was received from; `None` if it was mined by the node.
+    pub source: Option<SocketAddr>,
+    /// The height of the node's canon chain after the block was accepted.
+    pub height: BlockHeight,
+}
+
+/// A peer the node has established a connection with.
+#[derive(Clone, Debug)]
+pub struct PeerConnected {
+    /// The listening address of the peer.
+    pub address: SocketAddr,
+    /// `true` if the connection was initiated by the node.
+    pub outbound: bool,
+}
+
+/// A block sync that caught the node up with the network.
+#[derive(Clone, Debug)]
+pub struct SyncComplete {
+    /// The height of the node's canon chain.
+    pub height: BlockHeight,
+    /// The height of the network's chain, as reported by the connected peers, if there are any.
+    pub network_height: Option<BlockHeight>,
+}
+
+type Callback<T> = Arc<dyn Fn(&T) + Send + core::marker::Sync>;
+
+/// The callbacks registered by the applications embedding the node, grouped by the events they react to.
+///
+/// The callbacks are invoked on the node's tasks and threads as the events occur, so they need to return quickly;
+/// any lengthy processing should be handed off to another task or thread. A callback that panics is logged, and
+/// doesn't affect the node or the other callbacks.
+#[derive(Default)]
+pub struct NodeCallbacks {
+    #[cfg(feature = "consensus")]
+    block_accepted: RwLock<Vec<Callback<BlockAccepted>>>,
+    peer_connected: RwLock<Vec<Callback<PeerConnected>>>,
+    sync_complete: RwLock<Vec<Callback<SyncComplete>>>,
+}
+
+impl NodeCallbacks {
+    /// Registers a callback invoked whenever a block is accepted into the node's storage.
+    #[cfg(feature = "consensus")]
+    pub fn on_block_accepted<F: Fn(&BlockAccepted) + Send + core::marker::Sync + 'static>(&self, callback: F) {
+        self.block_accepted.write().unwrap().push(Arc::new(callback));
+    }
+
+    /// Registers a callback invoked whenever a connection with a peer is established.
+    pub fn on_peer_connected<F: Fn(&PeerConnected) + Send + core::marker::Sync + 'static>(&self, callback: F) {
+        self.peer_connected.write().unwrap().push(Arc::new(callback));
+    }
+
+    /// Registers a callback invoked whenever a block sync catches the node up with the network.
+    pub fn on_sync_complete<F: Fn(&SyncComplete) + Send + core::marker::Sync + 'static>(&self, callback: F) {
+        self.sync_complete.write().unwrap().push(Arc::new(callback));
+    }
+
+    /// Moves the callbacks registered with another instance into this one.
+    pub(crate) fn extend(&self, other: NodeCallbacks) {
+        #[cfg(feature = "consensus")]
+        self.block_accepted
+            .write()
+            .unwrap()
+            .extend(other.block_accepted.into_inner().unwrap());
+        self.peer_connected
+            .write()
+            .unwrap()
+            .extend(other.peer_connected.into_inner().unwrap());
+        self.sync_complete
+            .write()
+            .unwrap()
+            .extend(other.sync_complete.into_inner().unwrap());
+    }
+
+    #[cfg(feature = "consensus")]
+    pub(crate) fn block_accepted(&self, event: &BlockAccepted) {
+        Self::invoke(&self.block_accepted, event);
+    }
+
+    pub(crate) fn peer_connected(&self, event: &PeerConnected) {
+        Self::invoke(&self.peer_connected, event);
+    }
+
+    pub(crate) fn sync_complete(&self, event: &SyncComplete) {
+        Self::invoke(&self.sync_complete, event);
+    }
+
+    fn invoke<T>(callbacks: &RwLock<Vec<Callback<T>>>, event: &T) {
+        // The lock isn't held while the callbacks run, so that they can register further callbacks.
+        let callbacks = callbacks.read().unwrap().clone();
+        for callback in callbacks {
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| callback(event))) {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .copied()
+                    .or_else(|| payload.downcast_ref::<String>().map(|message| message.as_str()))
+                    .unwrap_or("unknown cause");
+                error!("A node callback panicked: {}", message);
+            }
+        }
+    }
+}
+
+impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
+    /// Registers a callback invoked whenever a block is accepted into the node's storage.
+    #[cfg(feature = "consensus")]
+    pub fn on_block_accepted<F: Fn(&BlockAccepted) + Send + core::marker::Sync + 'static>(&self, callback: F) {
+        self.callbacks.on_block_accepted(callback);
+    }
+
+    /// Registers a callback invoked whenever a connection with a peer is established.
+    pub fn on_peer_connected<F: Fn(&PeerConnected) + Send + core::marker::Sync + 'static>(&self, callback: F) {
+        self.callbacks.on_peer_connected(callback);
+    }
+
+    /// Registers a callback invoked whenever a block sync catches the node up with the network.
+    pub fn on_sync_complete<F: Fn(&SyncComplete) + Send + core::marker::Sync + 'static>(&self, callback: F) {
+        self.callbacks.on_sync_complete(callback);
+    }
+
+    /// Reports a block accepted into the node's storage to the registered callbacks.
+    #[cfg(feature = "consensus")]
+    pub(crate) fn report_accepted_block(
+        &self,
+        hash: BlockHeaderHash,
+        outcome: BlockOutcome,
+        source: Option<SocketAddr>,
+    ) {
+        self.callbacks.block_accepted(&BlockAccepted {
+            hash,
+            outcome,
+            source,
+            height: self.current_block_height(),
+        });
+    }
+
+    /// Reports a completed block sync to the registered callbacks if it caught the node up with the network.
+    #[cfg(feature = "consensus")]
+    pub(crate) async fn report_completed_sync(&self) {
+        if !self.is_synced().await {
+            return;
+        }
+
+        self.callbacks.sync_complete(&SyncComplete {
+            height: self.current_block_height(),
+            network_height: self.peer_book.network_height().await,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn panicking_callbacks_dont_affect_the_others() {
+        let callbacks = NodeCallbacks::default();
+        let invocations = Arc::new(AtomicUsize::new(0));
+
+        callbacks.on_peer_connected(|_| panic!("a faulty callback"));
+        let invocations_clone = invocations.clone();
+        callbacks.on_peer_connected(move |_| {
+            invocations_clone.fetch_add(1, Ordering::SeqCst);
+        });
+
+        let event = PeerConnected {
+            address: "127.0.0.1:4131".parse().unwrap(),
+            outbound: true,
+        };
+        callbacks.peer_connected(&event);
+        callbacks.peer_connected(&event);
+
+        assert_eq!(invocations.load(Ordering::SeqCst), 2);
+    }
+}
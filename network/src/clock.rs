@@ -0,0 +1,282 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{EventCategory, Node};
+use snarkvm_dpc::Storage;
+
+use chrono::Utc;
+use std::{collections::VecDeque, net::SocketAddr, sync::Mutex};
+
+/// The estimate of the network's time, derived from the timestamps the peers report in their handshakes and then
+/// periodically in their `Ping` messages. Only the peers the node connected to are sampled, as anyone can open any
+/// number of inbound connections in order to skew the estimate.
+///
+/// The network-adjusted time is the node's own time shifted by the median of the offsets of the peers' clocks, so that
+/// a handful of peers with wrong clocks can't shift it. It requires `MIN_CLOCK_SAMPLES` peers, and offsets above
+/// `MAX_CLOCK_ADJUSTMENT_SECS` aren't applied at all, as the local clock is then more likely to be right than the
+/// peers' clocks; the node only warns about them. The samples of the peers are dropped once they disconnect or the
+/// samples are older than `MAX_CLOCK_SAMPLE_AGE_SECS`, so that the estimate follows the currently connected peers.
+#[derive(Default)]
+pub struct NetworkClock(Mutex<ClockState>);
+
+/// The latest offset of a peer's clock from the node's.
+struct ClockSample {
+    peer: SocketAddr,
+    /// The offset, in seconds.
+    offset: i64,
+    /// The local UNIX timestamp the offset was sampled at, in seconds.
+    sampled_at: i64,
+}
+
+#[derive(Default)]
+struct ClockState {
+    /// The latest sample of each peer's clock, the oldest ones first.
+    samples: VecDeque<ClockSample>,
+    /// The median of the samples; 0 until there are enough of them.
+    median_offset: i64,
+}
+
+impl NetworkClock {
+    /// Records the timestamp reported by a peer at the given local time; returns the new state of the node's clock
+    /// if the sample made it skewed (`Some(true)`) or no longer skewed (`Some(false)`). Timestamps of 0 come from the
+    /// nodes that don't report them, and are ignored.
+    pub fn add_sample(&self, peer: SocketAddr, timestamp: i64, now: i64) -> Option<bool> {
+        if timestamp <= 0 {
+            return None;
+        }
+
+        let mut state = self.0.lock().unwrap();
+        state.samples.retain(|sample| sample.peer != peer);
+        if state.samples.len() >= crate::MAX_CLOCK_SAMPLES {
+            state.samples.pop_front();
+        }
+        state.samples.push_back(ClockSample {
+            peer,
+            offset: timestamp - now,
+            sampled_at: now,
+        });
+
+        Self::refresh(&mut state, now)
+    }
+
+    /// Drops the sample of a peer that disconnected at the given local time; returns the new state of the node's
+    /// clock like `add_sample` does.
+    pub fn remove_sample(&self, peer: SocketAddr, now: i64) -> Option<bool> {
+        let mut state = self.0.lock().unwrap();
+        state.samples.retain(|sample| sample.peer != peer);
+
+        Self::refresh(&mut state, now)
+    }
+
+    /// Drops the expired samples and recomputes the median offset; returns the new state of the node's clock if it
+    /// changed.
+    fn refresh(state: &mut ClockState, now: i64) -> Option<bool> {
+        let was_skewed = Self::is_offset_skewed(state.median_offset);
+
+        // The samples are ordered by their age, since a peer's new sample replaces its previous one at the back.
+        while let Some(sample) = state.samples.front() {
+            if now - sample.sampled_at <= crate::MAX_CLOCK_SAMPLE_AGE_SECS as i64 {
+                break;
+            }
+            state.samples.pop_front();
+        }
+
+        state.median_offset = if state.samples.len() >= crate::MIN_CLOCK_SAMPLES {
+            let mut offsets = state.samples.iter().map(|sample| sample.offset).collect::<Vec<_>>();
+            offsets.sort_unstable();
+            offsets[offsets.len() / 2]
+        } else {
+            0
+        };
+
+        let is_skewed = Self::is_offset_skewed(state.median_offset);
+        if is_skewed != was_skewed {
+            Some(is_skewed)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the median offset of the peers' clocks from the node's, in seconds; it's 0 until there are
+    /// `MIN_CLOCK_SAMPLES` of them.
+    pub fn median_offset(&self) -> i64 {
+        self.0.lock().unwrap().median_offset
+    }
+
+    /// Returns the offset applied to the node's clock in order to obtain the network-adjusted time, in seconds.
+    pub fn offset(&self) -> i64 {
+        let median_offset = self.median_offset();
+
+        if median_offset.abs() <= crate::MAX_CLOCK_ADJUSTMENT_SECS as i64 {
+            median_offset
+        } else {
+            0
+        }
+    }
+
+    /// Returns the number of peers whose clocks were sampled.
+    pub fn num_samples(&self) -> usize {
+        self.0.lock().unwrap().samples.len()
+    }
+
+    /// Checks whether the node's clock deviates from the network's time by more than `CLOCK_SKEW_WARNING_SECS`.
+    pub fn is_skewed(&self) -> bool {
+        Self::is_offset_skewed(self.median_offset())
+    }
+
+    /// Returns the network-adjusted UNIX timestamp, in seconds.
+    pub fn adjusted_time(&self) -> i64 {
+        Utc::now().timestamp() + self.offset()
+    }
+
+    fn is_offset_skewed(offset: i64) -> bool {
+        offset.abs() > crate::CLOCK_SKEW_WARNING_SECS as i64
+    }
+}
+
+impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
+    /// Returns the network-adjusted UNIX timestamp, in seconds; it's used in the templates of the blocks.
+    pub fn adjusted_time(&self) -> i64 {
+        self.clock.adjusted_time()
+    }
+
+    /// Records the timestamp reported by a peer, warning if the node's clock became skewed as a result.
+    pub(crate) fn record_peer_time(&self, peer: SocketAddr, timestamp: i64) {
+        let change = self.clock.add_sample(peer, timestamp, Utc::now().timestamp());
+        self.report_clock_change(change);
+    }
+
+    /// Forgets the timestamp reported by a peer that disconnected.
+    pub(crate) fn forget_peer_time(&self, peer: SocketAddr) {
+        let change = self.clock.remove_sample(peer, Utc::now().timestamp());
+        self.report_clock_change(change);
+    }
+
+    fn report_clock_change(&self, change: Option<bool>) {
+        match change {
+            Some(true) => {
+                let median_offset = self.clock.median_offset();
+                warn!(
+                    "The local clock is {}s off from the network's time; please check the system clock",
+                    -median_offset
+                );
+                if self.clock.offset() == 0 {
+                    warn!("The offset is too large to be trusted; the local clock is used as it is");
+                }
+                self.journal.record(
+                    EventCategory::Lifecycle,
+                    format!("The local clock is {}s off from the network's time", -median_offset),
+                );
+            }
+            Some(false) => {
+                info!("The local clock is in sync with the network's time again");
+                self.journal.record(
+                    EventCategory::Lifecycle,
+                    "The local clock is in sync with the network's time again",
+                );
+            }
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(i: usize) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], 4000 + i as u16))
+    }
+
+    #[test]
+    fn offset_requires_enough_samples() {
+        let clock = NetworkClock::default();
+        let now = 1_600_000_000;
+
+        for i in 0..crate::MIN_CLOCK_SAMPLES - 1 {
+            assert_eq!(clock.add_sample(peer(i), now + 600, now), None);
+        }
+        assert_eq!(clock.offset(), 0);
+
+        // the skew is reported once there are enough samples
+        assert_eq!(clock.add_sample(peer(99), now + 600, now), Some(true));
+        assert_eq!(clock.offset(), 600);
+        assert!(clock.is_skewed());
+
+        // the samples of the same peer replace each other, and missing timestamps are ignored
+        assert_eq!(clock.add_sample(peer(99), now + 600, now), None);
+        assert_eq!(clock.add_sample(peer(100), 0, now), None);
+        assert_eq!(clock.num_samples(), crate::MIN_CLOCK_SAMPLES);
+    }
+
+    #[test]
+    fn median_resists_outliers() {
+        let clock = NetworkClock::default();
+        let now = 1_600_000_000;
+
+        // a minority of peers with wrong clocks doesn't shift the network's time
+        let offsets: [i64; 7] = [-100_000, 2, 1, 0, 3, 100_000, -1];
+        for (i, offset) in offsets.iter().enumerate() {
+            assert_eq!(clock.add_sample(peer(i), now + offset, now), None);
+        }
+        assert_eq!(clock.offset(), 1);
+        assert!(!clock.is_skewed());
+    }
+
+    #[test]
+    fn excessive_offsets_are_not_applied() {
+        let clock = NetworkClock::default();
+        let now = 1_600_000_000;
+        let offset = crate::MAX_CLOCK_ADJUSTMENT_SECS as i64 + 1;
+
+        for i in 0..crate::MIN_CLOCK_SAMPLES {
+            clock.add_sample(peer(i), now - offset, now);
+        }
+        assert!(clock.is_skewed());
+        assert_eq!(clock.median_offset(), -offset);
+        assert_eq!(clock.offset(), 0);
+
+        // the skew is cleared once the peers agree with the local clock
+        let mut cleared = false;
+        for i in 0..crate::MIN_CLOCK_SAMPLES {
+            cleared |= clock.add_sample(peer(i), now, now) == Some(false);
+        }
+        assert!(cleared);
+        assert!(!clock.is_skewed());
+    }
+
+    #[test]
+    fn samples_of_gone_peers_are_dropped() {
+        let clock = NetworkClock::default();
+        let now = 1_600_000_000;
+
+        for i in 0..crate::MIN_CLOCK_SAMPLES {
+            clock.add_sample(peer(i), now + 600, now);
+        }
+        assert!(clock.is_skewed());
+
+        // a disconnected peer's sample no longer counts
+        assert_eq!(clock.remove_sample(peer(0), now), Some(false));
+        assert_eq!(clock.num_samples(), crate::MIN_CLOCK_SAMPLES - 1);
+        assert_eq!(clock.offset(), 0);
+
+        // the renewed samples are kept, while the ones that weren't renewed expire
+        let later = now + crate::MAX_CLOCK_SAMPLE_AGE_SECS as i64 + 1;
+        assert_eq!(clock.add_sample(peer(1), later + 600, later), None);
+        assert_eq!(clock.num_samples(), 1);
+        assert!(!clock.is_skewed());
+    }
+}
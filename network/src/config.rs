@@ -14,44 +14,170 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::NetworkError;
+use crate::{
+    AddressFamily,
+    AlarmRules,
+    BanScope,
+    DefaultPeerSelection,
+    JournalConfig,
+    MessagePolicy,
+    NetworkError,
+    NetworkId,
+    NodeIdentity,
+    NodeRole,
+    PeerEvictionPolicy,
+    PeerScoreWeights,
+    PeerSelectionStrategy,
+    PortPolicy,
+    RecommendedPeersConfig,
+    RelayPolicy,
+    Subnet,
+    SyncResponseShaping,
+    SyncServingQuota,
+    WebhookConfig,
+};
 
 use arc_swap::ArcSwap;
 use std::{
     net::SocketAddr,
-    sync::Arc,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU16, AtomicU64, Ordering},
+        Arc,
+    },
     time::Duration,
     {self},
 };
 
 /// A core data structure containing the pre-configured parameters for the node.
 pub struct Config {
+    /// The network the node is a part of; it only connects to the peers on the same network.
+    pub network_id: NetworkId,
     /// The pre-configured desired address of this node.
     pub desired_address: SocketAddr,
+    /// The additional addresses the node accepts inbound connections on, e.g. other interfaces.
+    pub additional_listen_addresses: Vec<SocketAddr>,
+    /// The address advertised to other nodes; if not provided, the listening port is advertised.
+    pub advertised_address: Option<SocketAddr>,
     /// The minimum number of peers required to maintain connections with.
-    minimum_number_of_connected_peers: u16,
+    minimum_number_of_connected_peers: AtomicU16,
     /// The maximum number of peers permitted to maintain connections with.
-    maximum_number_of_connected_peers: u16,
+    maximum_number_of_connected_peers: AtomicU16,
     /// The default bootnodes of the network.
     pub bootnodes: ArcSwap<Vec<SocketAddr>>,
+    /// The IPs and subnets banned in the node's configuration, in addition to the ones banned at runtime.
+    pub banned_subnets: ArcSwap<Vec<Subnet>>,
+    /// The range of addresses the ban of a peer covers; the peer's whole IP by default.
+    pub peer_ban_scope: BanScope,
     /// If `true`, initializes this node as a bootnode and forgoes connecting
     /// to the default bootnodes or saved peers in the peer book.
     is_bootnode: bool,
+    /// If `true`, initializes this node as a crawler that cycles through short-lived connections
+    /// in order to map the network and doesn't participate in consensus.
+    is_crawler: bool,
+    /// The interval between each peer sync, in milliseconds.
+    peer_sync_interval: AtomicU64,
+    /// If `true`, attempts to map the listening port on the local gateway via NAT-PMP or UPnP.
+    nat_traversal: bool,
+    /// The address of the SOCKS5 proxy all the outbound connections are routed through, if any.
+    socks5_proxy: Option<SocketAddr>,
+    /// If `true`, the node doesn't accept any inbound connections.
+    no_listen: bool,
+    /// If `true`, the IPv6 listeners also accept IPv4 connections, and the node connects to peers of both families.
+    dual_stack: bool,
+    /// The weights used to score the quality of the connected peers.
+    pub peer_score_weights: PeerScoreWeights,
+    /// The persistent key and the alias of the node, if it advertises one to its peers.
+    pub identity: Option<NodeIdentity>,
+    /// The webhook the node's notable events are reported to, if any.
+    pub webhook: Option<WebhookConfig>,
+    /// The thresholds at which the node raises alarms about its state.
+    pub alarms: AlarmRules,
+    /// The policy picking the peers to connect to, sync blocks from and relay blocks and transactions to.
+    pub peer_selection: Arc<dyn PeerSelectionStrategy>,
+    /// The ports the addresses gossiped by peers may use; the configured bootnodes are exempt from it.
+    pub gossip_port_policy: PortPolicy,
+    /// If `true`, the addresses gossiped by peers are probed for reachability in the background, and deprioritized
+    /// as connection candidates until they pass a probe; it has no effect if a SOCKS5 proxy is used.
+    pub reachability_probes: bool,
+    /// The policy deciding which inbound messages are processed and relayed; if not provided, the standard
+    /// policy of the node's role is used.
+    pub message_policy: Option<Arc<dyn MessagePolicy>>,
+    /// The number of most recent blocks whose transactions are kept; the older ones are pruned from the storage,
+    /// keeping only their headers. All the blocks are kept if not provided.
+    pub prune_depth: Option<u32>,
+    /// The number of connection slots reserved for the peers this node connects to; at most half of them are reserved.
+    pub reserved_outbound_slots: u16,
+    /// The maximum number of outbound handshakes performed at the same time; the other connection attempts wait.
+    pub max_concurrent_handshakes: usize,
+    /// The signed list of recommended peers the node falls back on when it has no peers, if any.
+    pub recommended_peers: Option<RecommendedPeersConfig>,
+    /// The path the crawler periodically exports its signed list of recommended peers to, if any.
+    pub recommended_peers_export: Option<PathBuf>,
+    /// The directory the ledger snapshots requested via RPC are written to; they can't be requested without one.
+    pub snapshot_dir: Option<PathBuf>,
+    /// The shaping of the `Sync` responses to the peers' `GetSync` requests.
+    pub sync_response_shaping: SyncResponseShaping,
+    /// The per-peer caps on the blocks and block hashes served to the syncing peers.
+    pub sync_serving_quota: SyncServingQuota,
+    /// The rules deciding which of the transactions received from peers are accepted and relayed.
+    pub relay_policy: RelayPolicy,
+    /// The categories of events each of the sinks of the node's journal receives.
+    pub journal: JournalConfig,
+    /// The number of peers shared at once in response to a `GetPeers` message; at most `SHARED_PEER_COUNT`.
+    pub shared_peer_count: usize,
+    /// The cap on the bandwidth used by the payloads written to all the peers, in bytes per second, if any.
+    pub upload_limit: Option<u64>,
+    /// The cap on the bandwidth used by the payloads written to each peer, in bytes per second, if any.
+    pub peer_upload_limit: Option<u64>,
+    /// The rules for forgetting the disconnected peers that aren't worth keeping.
+    pub peer_eviction: PeerEvictionPolicy,
+}
+
+/// The parts of the node's configuration that can be changed while it's running.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReloadableConfig {
+    /// The minimum number of peers required to maintain connections with.
+    pub min_peers: u16,
+    /// The maximum number of peers permitted to maintain connections with.
+    pub max_peers: u16,
+    /// The default bootnodes of the network.
+    pub bootnodes: Vec<SocketAddr>,
+    /// The IPs and subnets banned in the node's configuration.
+    pub banned_subnets: Vec<Subnet>,
     /// The interval between each peer sync.
-    peer_sync_interval: Duration,
+    pub peer_sync_interval: Duration,
+    /// The interval between each block sync; ignored if the node doesn't sync blocks.
+    pub block_sync_interval: Duration,
+    /// The interval between each memory pool sync; ignored if the node doesn't sync blocks.
+    pub mempool_sync_interval: Duration,
 }
 
+/// The source of the node's reloadable configuration, e.g. its configuration file.
+pub type ConfigSource = Box<dyn Fn() -> Result<ReloadableConfig, NetworkError> + Send + core::marker::Sync>;
+
 impl Config {
     /// Creates a new instance of `Environment`.
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         desired_address: SocketAddr,
+        additional_listen_addresses: Vec<SocketAddr>,
+        advertised_address: Option<SocketAddr>,
         minimum_number_of_connected_peers: u16,
         maximum_number_of_connected_peers: u16,
         bootnodes_addresses: Vec<String>,
         is_bootnode: bool,
+        is_crawler: bool,
         peer_sync_interval: Duration,
+        nat_traversal: bool,
+        socks5_proxy: Option<SocketAddr>,
+        no_listen: bool,
+        dual_stack: bool,
     ) -> Result<Self, NetworkError> {
+        if is_crawler && !cfg!(feature = "crawler") {
+            return Err(NetworkError::CrawlerUnavailable);
+        }
+
         // Convert the given bootnodes into socket addresses.
         let mut bootnodes = Vec::with_capacity(bootnodes_addresses.len());
         for bootnode_address in bootnodes_addresses.iter() {
@@ -61,12 +187,44 @@ impl Config {
         }
 
         Ok(Self {
+            network_id: Default::default(),
             desired_address,
-            minimum_number_of_connected_peers,
-            maximum_number_of_connected_peers,
+            additional_listen_addresses,
+            advertised_address,
+            minimum_number_of_connected_peers: AtomicU16::new(minimum_number_of_connected_peers),
+            maximum_number_of_connected_peers: AtomicU16::new(maximum_number_of_connected_peers),
             bootnodes: ArcSwap::new(Arc::new(bootnodes)),
+            banned_subnets: Default::default(),
+            peer_ban_scope: Default::default(),
             is_bootnode,
-            peer_sync_interval,
+            is_crawler,
+            peer_sync_interval: AtomicU64::new(peer_sync_interval.as_millis() as u64),
+            nat_traversal,
+            socks5_proxy,
+            no_listen,
+            dual_stack,
+            peer_score_weights: Default::default(),
+            identity: None,
+            webhook: None,
+            alarms: Default::default(),
+            peer_selection: Arc::new(DefaultPeerSelection),
+            gossip_port_policy: Default::default(),
+            reachability_probes: false,
+            message_policy: None,
+            prune_depth: None,
+            reserved_outbound_slots: crate::RESERVED_OUTBOUND_SLOTS,
+            max_concurrent_handshakes: crate::MAX_CONCURRENT_HANDSHAKES,
+            recommended_peers: None,
+            recommended_peers_export: None,
+            snapshot_dir: None,
+            sync_response_shaping: Default::default(),
+            sync_serving_quota: Default::default(),
+            relay_policy: Default::default(),
+            journal: Default::default(),
+            shared_peer_count: crate::SHARED_PEER_COUNT,
+            upload_limit: None,
+            peer_upload_limit: None,
+            peer_eviction: Default::default(),
         })
     }
 
@@ -76,26 +234,108 @@ impl Config {
         self.bootnodes.load_full()
     }
 
+    /// Returns the IPs and subnets banned in the node's configuration.
+    #[inline]
+    pub fn banned_subnets(&self) -> Arc<Vec<Subnet>> {
+        self.banned_subnets.load_full()
+    }
+
     /// Returns `true` if this node is a bootnode. Otherwise, returns `false`.
     #[inline]
     pub fn is_bootnode(&self) -> bool {
         self.is_bootnode
     }
 
+    /// Returns `true` if this node is a crawler. Otherwise, returns `false`; this is always
+    /// the case if the `crawler` feature is disabled, in which case a crawler can't be configured.
+    #[inline]
+    pub fn is_crawler(&self) -> bool {
+        cfg!(feature = "crawler") && self.is_crawler
+    }
+
+    /// Returns the role of the node as set in its configuration; watcher nodes are only recognized as
+    /// such by `Node::role`, as they become watchers once they're given a `Watcher`.
+    pub fn role(&self) -> NodeRole {
+        if self.is_bootnode() {
+            NodeRole::Bootnode
+        } else if self.is_crawler() {
+            NodeRole::Crawler
+        } else {
+            NodeRole::Full
+        }
+    }
+
     /// Returns the minimum number of peers this node maintains a connection with.
     #[inline]
     pub fn minimum_number_of_connected_peers(&self) -> u16 {
-        self.minimum_number_of_connected_peers
+        self.minimum_number_of_connected_peers.load(Ordering::Relaxed)
     }
 
     /// Returns the maximum number of peers this node maintains a connection with.
     #[inline]
     pub fn maximum_number_of_connected_peers(&self) -> u16 {
-        self.maximum_number_of_connected_peers
+        self.maximum_number_of_connected_peers.load(Ordering::Relaxed)
     }
 
     /// Returns the interval between each peer sync.
     pub fn peer_sync_interval(&self) -> Duration {
+        Duration::from_millis(self.peer_sync_interval.load(Ordering::Relaxed))
+    }
+
+    /// Applies the reloaded peer limits, peer sync interval, bootnodes and bans; the sync intervals are
+    /// applied to the sync layer by `Node::reload_config`.
+    pub fn reload(&self, reloaded: &ReloadableConfig) -> Result<(), NetworkError> {
+        if reloaded.min_peers == 0 || reloaded.max_peers == 0 || reloaded.min_peers > reloaded.max_peers {
+            return Err(NetworkError::PeerCountInvalid);
+        }
+
+        if reloaded.peer_sync_interval == Duration::from_secs(0) {
+            return Err(NetworkError::SyncIntervalInvalid);
+        }
+
+        self.minimum_number_of_connected_peers
+            .store(reloaded.min_peers, Ordering::Relaxed);
+        self.maximum_number_of_connected_peers
+            .store(reloaded.max_peers, Ordering::Relaxed);
         self.peer_sync_interval
+            .store(reloaded.peer_sync_interval.as_millis() as u64, Ordering::Relaxed);
+        self.bootnodes.store(Arc::new(reloaded.bootnodes.clone()));
+        self.banned_subnets.store(Arc::new(reloaded.banned_subnets.clone()));
+
+        Ok(())
+    }
+
+    /// Returns `true` if this node should attempt to map its listening port on the local gateway.
+    #[inline]
+    pub fn nat_traversal(&self) -> bool {
+        self.nat_traversal && !self.no_listen
+    }
+
+    /// Returns the address of the SOCKS5 proxy the outbound connections are routed through, if any.
+    #[inline]
+    pub fn socks5_proxy(&self) -> Option<SocketAddr> {
+        self.socks5_proxy
+    }
+
+    /// Returns `true` if this node doesn't accept any inbound connections.
+    #[inline]
+    pub fn no_listen(&self) -> bool {
+        self.no_listen
+    }
+
+    /// Returns `true` if the IPv6 listeners of this node also accept IPv4 connections.
+    #[inline]
+    pub fn dual_stack(&self) -> bool {
+        self.dual_stack
+    }
+
+    /// Returns `true` if this node is able to connect to peers of the given address family.
+    pub fn supports_address_family(&self, family: AddressFamily) -> bool {
+        // The proxy resolves the route to the peers on the node's behalf.
+        if self.socks5_proxy.is_some() || (self.dual_stack && self.desired_address.is_ipv6()) {
+            return true;
+        }
+
+        AddressFamily::of(self.desired_address) == family
     }
 }
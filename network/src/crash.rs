@@ -0,0 +1,173 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{JournalEvent, Node, WebhookEvent};
+use snarkvm_dpc::Storage;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::{
+    fs,
+    panic::{self, PanicInfo},
+    path::{Path, PathBuf},
+    thread,
+};
+
+/// The state of the node at the time of a crash; it doesn't include any keys.
+#[derive(Clone, Debug, Serialize)]
+pub struct NodeCrashState {
+    /// The random numeric identifier of the node.
+    pub node_id: u64,
+    /// The role the node plays in the network.
+    pub role: String,
+    /// The state of the node's block sync, e.g. whether it was syncing blocks; it's `unknown` if the state was
+    /// being updated at the time of the crash.
+    pub sync_state: String,
+    /// The height of the chain followed by the node.
+    pub block_height: u32,
+    /// The number of connected peers, including the connections still being established.
+    pub connected_peers: u32,
+    /// The number of connected peers that connected to this node.
+    pub inbound_peers: u32,
+    /// The number of connected peers whose connections were initiated by this node.
+    pub outbound_peers: u32,
+    /// The number of known peers the node isn't connected to.
+    pub disconnected_peers: u32,
+    /// The number of seconds the node had been running for.
+    pub uptime_secs: i64,
+    /// The most recent notable events in the life of the node.
+    pub recent_events: Vec<JournalEvent>,
+}
+
+/// A report on a panic of the node, written to its crash reports directory.
+#[derive(Clone, Debug, Serialize)]
+pub struct CrashReport {
+    /// The time at which the node panicked.
+    pub time: DateTime<Utc>,
+    /// The version of snarkOS the node was running.
+    pub version: String,
+    /// The name of the thread that panicked, if it had one.
+    pub thread: Option<String>,
+    /// The panic message.
+    pub message: String,
+    /// The location in the source code the panic originated from, if known.
+    pub location: Option<String>,
+    /// The backtrace of the thread that panicked.
+    pub backtrace: String,
+    /// The state of the node at the time of the panic.
+    pub node: NodeCrashState,
+}
+
+impl CrashReport {
+    /// Writes the report to a new file in the given directory, and returns its path.
+    pub fn write_to(&self, dir: &Path) -> std::io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+
+        let path = dir.join(format!("crash-{}.json", self.time.format("%Y%m%d-%H%M%S%.3f")));
+        fs::write(&path, serde_json::to_vec_pretty(self)?)?;
+
+        Ok(path)
+    }
+
+    /// Returns the details of the webhook notification of the crash, pointing to the report's file, if it was written.
+    fn webhook_details(&self, path: Option<&Path>) -> serde_json::Value {
+        serde_json::json!({
+            "message": self.message,
+            "location": self.location,
+            "thread": self.thread,
+            "report": path,
+        })
+    }
+}
+
+/// Returns the message the panic was raised with.
+fn panic_message(info: &PanicInfo) -> String {
+    if let Some(message) = info.payload().downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = info.payload().downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic payload".into()
+    }
+}
+
+impl<S: Storage + core::marker::Sync + Send + 'static> Node<S> {
+    /// Returns the state of the node to include in a crash report; it doesn't wait for any locks that the
+    /// thread that panicked could be holding.
+    pub fn crash_state(&self) -> NodeCrashState {
+        #[cfg(feature = "consensus")]
+        let sync_state = match self.sync() {
+            Some(sync) => sync
+                .try_sync_state()
+                .map(|state| state.to_string())
+                .unwrap_or_else(|| "unknown".into()),
+            None if self.watcher().is_some() => "watching".into(),
+            None => "not syncing".into(),
+        };
+        #[cfg(not(feature = "consensus"))]
+        let sync_state = "not syncing".into();
+
+        NodeCrashState {
+            node_id: self.id,
+            role: self.role().to_string(),
+            sync_state,
+            block_height: self.current_block_height(),
+            connected_peers: self.peer_book.get_active_peer_count(),
+            inbound_peers: self.peer_book.get_inbound_peer_count(),
+            outbound_peers: self.peer_book.get_outbound_peer_count(),
+            disconnected_peers: self.peer_book.get_disconnected_peer_count(),
+            uptime_secs: (Utc::now() - self.launched).num_seconds(),
+            recent_events: self.journal.try_recent(),
+        }
+    }
+
+    /// Installs a panic hook writing a crash report with the state of the node to the given directory and
+    /// journaling it, e.g. for the webhook, before running the previously installed hook.
+    pub fn install_panic_hook(&self, dir: PathBuf) {
+        let node = self.clone();
+        let previous_hook = panic::take_hook();
+
+        panic::set_hook(Box::new(move |info| {
+            let report = CrashReport {
+                time: Utc::now(),
+                version: env!("CARGO_PKG_VERSION").into(),
+                thread: thread::current().name().map(|name| name.to_owned()),
+                message: panic_message(info),
+                location: info.location().map(|location| location.to_string()),
+                backtrace: format!("{:?}", backtrace::Backtrace::new()),
+                node: node.crash_state(),
+            };
+
+            let path = match report.write_to(&dir) {
+                Ok(path) => {
+                    error!("The node panicked; a crash report was written to {}", path.display());
+                    Some(path)
+                }
+                Err(e) => {
+                    error!("The node panicked, but its crash report couldn't be written: {}", e);
+                    None
+                }
+            };
+
+            // The journal's sinks don't wait for the locks the thread that panicked could be holding; the webhook
+            // notification is only delivered if the runtime survives the panic.
+            node.journal
+                .notify(WebhookEvent::Crash, report.webhook_details(path.as_deref()));
+
+            previous_hook(info);
+        }));
+    }
+}
@@ -14,7 +14,8 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::Message;
+use crate::{Message, NetworkId};
+#[cfg(feature = "consensus")]
 use snarkos_consensus::error::ConsensusError;
 use snarkvm_dpc::{BlockError, StorageError};
 
@@ -25,23 +26,52 @@ pub enum NetworkError {
     Bincode(Box<bincode::ErrorKind>),
     BlockError(BlockError),
     CapnProto(capnp::Error),
+    #[cfg(feature = "consensus")]
     ConsensusError(ConsensusError),
+    ConfigReload(String),
+    CrawlerUnavailable,
     HandshakeTimeout,
     Io(std::io::Error),
+    Journal(String),
+    InvalidEventCategory(String),
     InvalidHandshake,
+    InvalidNetworkId(String),
+    InvalidPeerAnnotation(String),
+    InvalidPeerList(String),
+    InvalidPeerScoreWeights(String),
+    InvalidPortPolicy(String),
+    InvalidRelayPolicy(String),
+    InvalidRecommendedPeers(String),
+    InvalidServices(String),
+    InvalidSyncResponseShaping(String),
+    InvalidSyncServingQuota(String),
+    InvalidSubnet(String),
     MessageTooBig(usize),
+    MiningUnavailable,
+    NatTraversal(String),
+    NetworkMismatch(NetworkId),
+    NodeIdentity(String),
     Noise(snow::error::Error),
+    NotListening,
     PeerAlreadyConnected,
     PeerAlreadyConnecting,
     PeerAlreadyDisconnected,
+    PeerBanned,
     PeerBookFailedToLoad,
     PeerBookIsCorrupt,
     PeerBookMissingPeer,
     PeerCountInvalid,
     PeerIsDisconnected,
+    PayloadTooBig(&'static str, usize),
+    ProtocolViolation(String),
+    Proxy(String),
+    RpcAuthFailed,
+    RpcUnavailable,
     SelfConnectAttempt,
+    ServiceFailed(&'static str, String),
     SenderError(tokio::sync::mpsc::error::SendError<Message>),
     TooManyConnections,
+    Webhook(String),
     OutboundChannelMissing,
     ReceiverFailedToParse,
     StorageError(StorageError),
@@ -59,7 +89,12 @@ impl NetworkError {
             ]
             .contains(&err.kind()),
             // other critical errors
-            Self::CapnProto(_) | Self::MessageTooBig(..) | Self::ZeroLengthMessage | Self::Noise(_) => true,
+            Self::CapnProto(_)
+            | Self::MessageTooBig(..)
+            | Self::PayloadTooBig(..)
+            | Self::ZeroLengthMessage
+            | Self::Noise(_)
+            | Self::ProtocolViolation(_) => true,
             _ => false,
         }
     }
@@ -99,6 +134,7 @@ impl From<BlockError> for NetworkError {
     }
 }
 
+#[cfg(feature = "consensus")]
 impl From<ConsensusError> for NetworkError {
     fn from(error: ConsensusError) -> Self {
         NetworkError::ConsensusError(error)
@@ -0,0 +1,141 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+//! Artificial faults injected into the links between nodes, so that their behavior can be tested under
+//! adverse network conditions; only available with the `fault-injection` feature.
+
+use rand::{thread_rng, Rng};
+use std::{collections::HashMap, net::SocketAddr, sync::RwLock, time::Duration};
+
+/// The faults injected into the outbound messages sent over the link with a single peer; the rates are
+/// probabilities between `0.0` and `1.0`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LinkFaults {
+    /// The probability of a message being dropped instead of sent.
+    pub drop_rate: f64,
+    /// The delay applied before sending every message; it also holds up the other messages to and
+    /// from the peer, preserving their order.
+    pub delay: Option<Duration>,
+    /// The probability of a message being sent twice.
+    pub duplicate_rate: f64,
+    /// The probability of the connection being dropped abruptly instead of sending a message, without
+    /// flushing the ones queued before it.
+    pub disconnect_rate: f64,
+}
+
+/// A fault drawn for a single outbound message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum InjectedFault {
+    Drop,
+    Duplicate,
+    Disconnect,
+}
+
+impl LinkFaults {
+    /// Returns `true` if no faults are injected.
+    pub fn is_none(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Draws the fault to inject into a single outbound message, if any.
+    pub(crate) fn draw(&self) -> Option<InjectedFault> {
+        let mut rng = thread_rng();
+
+        if rng.gen_bool(self.disconnect_rate.max(0.0).min(1.0)) {
+            Some(InjectedFault::Disconnect)
+        } else if rng.gen_bool(self.drop_rate.max(0.0).min(1.0)) {
+            Some(InjectedFault::Drop)
+        } else if rng.gen_bool(self.duplicate_rate.max(0.0).min(1.0)) {
+            Some(InjectedFault::Duplicate)
+        } else {
+            None
+        }
+    }
+}
+
+/// The faults injected into the node's links, keyed by the addresses of its peers.
+#[derive(Debug, Default)]
+pub struct FaultInjector {
+    links: RwLock<HashMap<SocketAddr, LinkFaults>>,
+}
+
+impl FaultInjector {
+    /// Sets the faults injected into the messages sent to the given peer, replacing any previous ones.
+    pub fn set_link(&self, peer: SocketAddr, faults: LinkFaults) {
+        let mut links = self.links.write().unwrap();
+
+        if faults.is_none() {
+            links.remove(&peer);
+        } else {
+            links.insert(peer, faults);
+        }
+    }
+
+    /// Stops injecting faults into the messages sent to the given peer.
+    pub fn clear_link(&self, peer: SocketAddr) {
+        self.links.write().unwrap().remove(&peer);
+    }
+
+    /// Stops injecting faults into all the node's links.
+    pub fn clear(&self) {
+        self.links.write().unwrap().clear();
+    }
+
+    /// Returns the faults injected into the messages sent to the given peer, if any.
+    pub fn faults_for(&self, peer: SocketAddr) -> Option<LinkFaults> {
+        self.links.read().unwrap().get(&peer).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn certain_faults_are_always_drawn() {
+        let faults = LinkFaults {
+            drop_rate: 1.0,
+            duplicate_rate: 1.0,
+            ..Default::default()
+        };
+        assert_eq!(faults.draw(), Some(InjectedFault::Drop));
+
+        let faults = LinkFaults {
+            drop_rate: 1.0,
+            disconnect_rate: 1.0,
+            ..Default::default()
+        };
+        assert_eq!(faults.draw(), Some(InjectedFault::Disconnect));
+
+        assert_eq!(LinkFaults::default().draw(), None);
+    }
+
+    #[test]
+    fn faultless_links_are_cleared() {
+        let injector = FaultInjector::default();
+        let peer: SocketAddr = "127.0.0.1:4131".parse().unwrap();
+
+        let faults = LinkFaults {
+            delay: Some(Duration::from_millis(100)),
+            ..Default::default()
+        };
+        injector.set_link(peer, faults.clone());
+        assert_eq!(injector.faults_for(peer), Some(faults));
+
+        injector.set_link(peer, LinkFaults::default());
+        assert_eq!(injector.faults_for(peer), None);
+    }
+}
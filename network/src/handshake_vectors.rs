@@ -0,0 +1,197 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+//! Deterministic test vectors for the noise handshake, produced by running both of its sides against keys
+//! derived from fixed seeds; only available with the `test-vectors` feature.
+
+use std::{
+    io,
+    mem,
+    net::SocketAddr,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWrite;
+
+use crate::{
+    peers::peer::{initiator_handshake, responder_handshake, HandshakeKeys},
+    Cipher,
+    NetworkError,
+    Version,
+};
+
+/// The payload sent by the initiator once the handshake is complete.
+pub const INITIATOR_TEST_PAYLOAD: &[u8] = b"snarkOS handshake test vector: initiator";
+/// The payload sent by the responder once the handshake is complete.
+pub const RESPONDER_TEST_PAYLOAD: &[u8] = b"snarkOS handshake test vector: responder";
+
+/// The bytes written to the wire by both sides of a handshake performed with fixed keys; as long as the same
+/// seeds and versions are used, they only change if the wire format of the handshake changes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HandshakeVector {
+    /// The seed the initiator's keys are derived from.
+    pub initiator_seed: u64,
+    /// The seed the responder's keys are derived from.
+    pub responder_seed: u64,
+    /// The hex-encoded frames of the handshake, in order: `-> e`, `<- e, ee, s, es` and `-> s, se, psk`.
+    pub handshake_messages: Vec<String>,
+    /// The hex-encoded hash of the handshake, unique to the connection.
+    pub handshake_hash: String,
+    /// The hex-encoded frames carrying `INITIATOR_TEST_PAYLOAD` and `RESPONDER_TEST_PAYLOAD`, in that order,
+    /// encrypted with the transport keys established by the handshake.
+    pub transport_messages: Vec<String>,
+}
+
+impl HandshakeVector {
+    /// Performs a handshake between an initiator and a responder using keys derived from the given seeds,
+    /// followed by an exchange of the test payloads, and records everything they write.
+    pub async fn generate(
+        initiator_seed: u64,
+        initiator_version: &Version,
+        responder_seed: u64,
+        responder_version: &Version,
+    ) -> Result<Self, NetworkError> {
+        let (initiator_stream, responder_stream) = tokio::io::duplex(crate::NOISE_BUF_LEN);
+        let (mut initiator_reader, initiator_writer) = tokio::io::split(initiator_stream);
+        let (mut responder_reader, responder_writer) = tokio::io::split(responder_stream);
+        let mut initiator_writer = RecordingWriter::new(initiator_writer);
+        let mut responder_writer = RecordingWriter::new(responder_writer);
+
+        let initiator_keys = HandshakeKeys::from_seed(initiator_seed);
+        let responder_keys = HandshakeKeys::from_seed(responder_seed);
+
+        let (initiator, responder) = tokio::try_join!(
+            initiator_handshake(
+                SocketAddr::from(([127, 0, 0, 1], responder_version.listening_port)),
+                initiator_version,
+                Some(&initiator_keys),
+                &mut initiator_writer,
+                &mut initiator_reader,
+            ),
+            responder_handshake(
+                SocketAddr::from(([127, 0, 0, 1], initiator_version.listening_port)),
+                responder_version,
+                Some(&responder_keys),
+                &mut responder_writer,
+                &mut responder_reader,
+            ),
+        )?;
+
+        if initiator.handshake_hash != responder.handshake_hash {
+            return Err(NetworkError::InvalidHandshake);
+        }
+        let handshake_hash = hex::encode(&initiator.handshake_hash);
+
+        let mut initiator_cipher = Cipher::new(initiator.noise, initiator.buffer, initiator.noise_buffer);
+        let mut responder_cipher = Cipher::new(responder.noise, responder.buffer, responder.noise_buffer);
+
+        let outbound = initiator_cipher.encrypt_packet(INITIATOR_TEST_PAYLOAD)?.to_vec();
+        if responder_cipher.read_packet_with(&outbound[4..], |_| Ok(()))? != INITIATOR_TEST_PAYLOAD {
+            return Err(NetworkError::InvalidHandshake);
+        }
+        let inbound = responder_cipher.encrypt_packet(RESPONDER_TEST_PAYLOAD)?.to_vec();
+        if initiator_cipher.read_packet_with(&inbound[4..], |_| Ok(()))? != RESPONDER_TEST_PAYLOAD {
+            return Err(NetworkError::InvalidHandshake);
+        }
+
+        let mut initiator_messages = initiator_writer.messages.into_iter();
+        let mut responder_messages = responder_writer.messages.into_iter();
+        let handshake_messages = vec![
+            initiator_messages.next(),
+            responder_messages.next(),
+            initiator_messages.next(),
+        ]
+        .into_iter()
+        .map(|message| message.map(hex::encode).ok_or(NetworkError::InvalidHandshake))
+        .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            initiator_seed,
+            responder_seed,
+            handshake_messages,
+            handshake_hash,
+            transport_messages: vec![hex::encode(outbound), hex::encode(inbound)],
+        })
+    }
+
+    /// Exports the vector as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("test vectors are always serializable")
+    }
+}
+
+impl HandshakeKeys {
+    /// Derives the static and ephemeral private keys from the given seed.
+    fn from_seed(seed: u64) -> Self {
+        let derive = |label: &[u8]| {
+            let mut hasher = Sha256::new();
+            hasher.update(label);
+            hasher.update(&seed.to_le_bytes());
+            hasher.finalize().to_vec()
+        };
+
+        Self {
+            static_key: derive(b"snarkos-handshake-static-key"),
+            ephemeral_key: derive(b"snarkos-handshake-ephemeral-key"),
+        }
+    }
+}
+
+/// A writer recording the bytes written between consecutive flushes as separate messages.
+struct RecordingWriter<W> {
+    inner: W,
+    pending: Vec<u8>,
+    messages: Vec<Vec<u8>>,
+}
+
+impl<W> RecordingWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            pending: Vec::new(),
+            messages: Vec::new(),
+        }
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for RecordingWriter<W> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = poll {
+            this.pending.extend_from_slice(&buf[..written]);
+        }
+        poll
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_flush(cx);
+        if let Poll::Ready(Ok(())) = poll {
+            if !this.pending.is_empty() {
+                this.messages.push(mem::take(&mut this.pending));
+            }
+        }
+        poll
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
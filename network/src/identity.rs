@@ -0,0 +1,372 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+//! Optional human-readable node aliases, signed with a persistent node key. The key isn't vouched
+//! for by any authority, so a valid signature only proves that the peer holds the key that signed
+//! the alias, not who runs it; aliases must always be presented as unverified.
+
+use crate::NetworkError;
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use snarkvm_algorithms::SignatureScheme;
+use snarkvm_dpc::{
+    testnet1::{instantiated::Components, parameters::SystemParameters},
+    DPCComponents,
+};
+use snarkvm_utilities::{to_bytes, FromBytes, ToBytes};
+
+use std::{fs, io::ErrorKind, net::SocketAddr, path::Path};
+
+type NodeSignatureScheme = <Components as DPCComponents>::AccountSignature;
+type NodePrivateKey = <NodeSignatureScheme as SignatureScheme>::PrivateKey;
+type NodePublicKey = <NodeSignatureScheme as SignatureScheme>::PublicKey;
+type NodeSignature = <NodeSignatureScheme as SignatureScheme>::Output;
+
+/// The maximum length of an alias, in bytes.
+pub const MAX_ALIAS_LEN: usize = 64;
+
+/// Prepended to the signed messages, so that the signatures can't be reused in another context.
+const ALIAS_SIGNATURE_DOMAIN: &[u8] = b"snarkOS node alias";
+/// Prepended to the signed address changes, for the same reason.
+const ADDRESS_CHANGE_SIGNATURE_DOMAIN: &[u8] = b"snarkOS address change";
+
+/// A human-readable alias signed by the sending node, sent right after the handshake to the peers
+/// that support it.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct NodeAlias {
+    /// The alias itself.
+    pub alias: String,
+    /// The serialized public key of the node that signed the alias.
+    pub public_key: Vec<u8>,
+    /// The serialized signature of the alias and the hash of the handshake of the connection
+    /// it was sent over; the latter prevents it from being replayed by other nodes.
+    pub signature: Vec<u8>,
+}
+
+impl NodeAlias {
+    /// Returns `true` if the alias is well-formed and was signed for the connection with the
+    /// given handshake hash.
+    pub fn verify(&self, handshake_hash: &[u8]) -> bool {
+        if validate_alias(&self.alias).is_err() {
+            return false;
+        }
+
+        let scheme = match signature_scheme() {
+            Ok(scheme) => scheme,
+            Err(e) => {
+                error!("Couldn't load the node signature parameters: {}", e);
+                return false;
+            }
+        };
+        let (public_key, signature) = match (
+            NodePublicKey::read(&self.public_key[..]),
+            NodeSignature::read(&self.signature[..]),
+        ) {
+            (Ok(public_key), Ok(signature)) => (public_key, signature),
+            _ => return false,
+        };
+
+        scheme
+            .verify(&public_key, &alias_message(&self.alias, handshake_hash), &signature)
+            .unwrap_or(false)
+    }
+}
+
+/// An announcement that the sending node is no longer reachable at its previous listening address, sent
+/// right after the handshake to the peers that support it, so that they stop gossiping the stale address.
+/// An unspecified IP in either address stands for the IP the node's connection comes from.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct AddressChange {
+    /// The address the node was reachable at before.
+    pub previous: SocketAddr,
+    /// The address the node is reachable at now.
+    pub address: SocketAddr,
+    /// The serialized public key of the node that signed the change.
+    pub public_key: Vec<u8>,
+    /// The serialized signature of both addresses and the hash of the handshake of the connection
+    /// the change was sent over.
+    pub signature: Vec<u8>,
+}
+
+impl AddressChange {
+    /// Returns `true` if the change was signed for the connection with the given handshake hash.
+    pub fn verify(&self, handshake_hash: &[u8]) -> bool {
+        let message = match address_change_message(self.previous, self.address, handshake_hash) {
+            Ok(message) => message,
+            Err(_) => return false,
+        };
+
+        verify_signature(
+            &self.public_key,
+            ADDRESS_CHANGE_SIGNATURE_DOMAIN,
+            &message,
+            &self.signature,
+        )
+    }
+}
+
+/// An alias a peer has sent along with a valid signature; see the module documentation for why
+/// it is still unverified.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerAlias {
+    /// The alias itself.
+    pub alias: String,
+    /// The hex-encoded public key of the peer's node key; it stays the same across connections.
+    pub public_key: String,
+}
+
+impl From<NodeAlias> for PeerAlias {
+    fn from(node_alias: NodeAlias) -> Self {
+        Self {
+            alias: node_alias.alias,
+            public_key: hex::encode(node_alias.public_key),
+        }
+    }
+}
+
+/// The persistent key of a node, used to sign its alias.
+pub struct NodeIdentity {
+    alias: String,
+    private_key: NodePrivateKey,
+    public_key: NodePublicKey,
+}
+
+impl NodeIdentity {
+    /// Loads the node key stored at the given path, or generates a new one and stores it there
+    /// if there isn't one yet.
+    pub fn load_or_generate<P: AsRef<Path>>(path: P, alias: String) -> Result<Self, NetworkError> {
+        validate_alias(&alias)?;
+
+        let scheme = signature_scheme()?;
+        let private_key = match fs::read(path.as_ref()) {
+            Ok(bytes) => NodePrivateKey::read(&bytes[..])?,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                let private_key = scheme
+                    .generate_private_key(&mut rand::thread_rng())
+                    .map_err(|e| NetworkError::NodeIdentity(e.to_string()))?;
+                write_private_key(path.as_ref(), &to_bytes![private_key]?)?;
+                info!("Generated a new node key at {}", path.as_ref().display());
+
+                private_key
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let public_key = scheme
+            .generate_public_key(&private_key)
+            .map_err(|e| NetworkError::NodeIdentity(e.to_string()))?;
+
+        Ok(Self {
+            alias,
+            private_key,
+            public_key,
+        })
+    }
+
+    /// Returns the alias of the node.
+    pub fn alias(&self) -> &str {
+        &self.alias
+    }
+
+    /// Signs the alias for the connection with the given handshake hash.
+    pub fn sign_alias(&self, handshake_hash: &[u8]) -> Result<NodeAlias, NetworkError> {
+        let signature = signature_scheme()?
+            .sign(
+                &self.private_key,
+                &alias_message(&self.alias, handshake_hash),
+                &mut rand::thread_rng(),
+            )
+            .map_err(|e| NetworkError::NodeIdentity(e.to_string()))?;
+
+        Ok(NodeAlias {
+            alias: self.alias.clone(),
+            public_key: to_bytes![self.public_key]?,
+            signature: to_bytes![signature]?,
+        })
+    }
+
+    /// Signs the change of the node's address for the connection with the given handshake hash.
+    pub fn sign_address_change(
+        &self,
+        previous: SocketAddr,
+        address: SocketAddr,
+        handshake_hash: &[u8],
+    ) -> Result<AddressChange, NetworkError> {
+        let signature = self.sign(
+            ADDRESS_CHANGE_SIGNATURE_DOMAIN,
+            &address_change_message(previous, address, handshake_hash)?,
+        )?;
+
+        Ok(AddressChange {
+            previous,
+            address,
+            public_key: to_bytes![self.public_key]?,
+            signature,
+        })
+    }
+
+    /// Returns the hex-encoded public key of the node key.
+    pub fn public_key_hex(&self) -> Result<String, NetworkError> {
+        Ok(hex::encode(to_bytes![self.public_key]?))
+    }
+
+    /// Signs the given message in the given domain, returning the serialized signature.
+    pub(crate) fn sign(&self, domain: &[u8], message: &[u8]) -> Result<Vec<u8>, NetworkError> {
+        let signature = signature_scheme()?
+            .sign(&self.private_key, &[domain, message].concat(), &mut rand::thread_rng())
+            .map_err(|e| NetworkError::NodeIdentity(e.to_string()))?;
+
+        Ok(to_bytes![signature]?)
+    }
+}
+
+/// Returns `true` if the given serialized signature of the message in the given domain was made
+/// with the node key matching the given serialized public key.
+pub(crate) fn verify_signature(public_key: &[u8], domain: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    let scheme = match signature_scheme() {
+        Ok(scheme) => scheme,
+        Err(e) => {
+            error!("Couldn't load the node signature parameters: {}", e);
+            return false;
+        }
+    };
+    let (public_key, signature) = match (NodePublicKey::read(public_key), NodeSignature::read(signature)) {
+        (Ok(public_key), Ok(signature)) => (public_key, signature),
+        _ => return false,
+    };
+
+    scheme
+        .verify(&public_key, &[domain, message].concat(), &signature)
+        .unwrap_or(false)
+}
+
+/// Returns the signature scheme used for the node keys; it's the same as the one used for accounts.
+fn signature_scheme() -> Result<&'static NodeSignatureScheme, NetworkError> {
+    static SIGNATURE_SCHEME: OnceCell<NodeSignatureScheme> = OnceCell::new();
+
+    SIGNATURE_SCHEME.get_or_try_init(|| Ok(SystemParameters::<Components>::load()?.account_signature))
+}
+
+/// Checks that the given alias can be advertised to other nodes.
+fn validate_alias(alias: &str) -> Result<(), NetworkError> {
+    if alias.is_empty() || alias.len() > MAX_ALIAS_LEN || alias.chars().any(char::is_control) {
+        return Err(NetworkError::NodeIdentity(format!(
+            "the alias must be between 1 and {} bytes long, without control characters",
+            MAX_ALIAS_LEN
+        )));
+    }
+
+    Ok(())
+}
+
+/// Returns the message that is signed in order to attest the given alias for a single connection.
+fn alias_message(alias: &str, handshake_hash: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(ALIAS_SIGNATURE_DOMAIN.len() + handshake_hash.len() + alias.len());
+    message.extend_from_slice(ALIAS_SIGNATURE_DOMAIN);
+    message.extend_from_slice(handshake_hash);
+    message.extend_from_slice(alias.as_bytes());
+
+    message
+}
+
+/// Returns the message that is signed in order to attest a change of address for a single connection.
+fn address_change_message(
+    previous: SocketAddr,
+    address: SocketAddr,
+    handshake_hash: &[u8],
+) -> Result<Vec<u8>, NetworkError> {
+    Ok(bincode::serialize(&(handshake_hash, previous, address))?)
+}
+
+/// Stores the given private key, readable only by the current user where supported.
+fn write_private_key(path: &Path, bytes: &[u8]) -> Result<(), NetworkError> {
+    use std::io::Write;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(bytes)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_identity(alias: &str) -> NodeIdentity {
+        let path = std::env::temp_dir().join(format!("snarkos_node_key_{}", rand::random::<u64>()));
+        let identity = NodeIdentity::load_or_generate(&path, alias.into()).unwrap();
+
+        // The key is persisted, so the same one is loaded again.
+        let reloaded = NodeIdentity::load_or_generate(&path, alias.into()).unwrap();
+        assert_eq!(identity.public_key, reloaded.public_key);
+        fs::remove_file(path).unwrap();
+
+        identity
+    }
+
+    #[test]
+    fn signed_alias_is_bound_to_the_connection() {
+        let identity = test_identity("bootnode-1.example.org");
+        let handshake_hash = [7u8; 32];
+
+        let node_alias = identity.sign_alias(&handshake_hash).unwrap();
+        assert!(node_alias.verify(&handshake_hash));
+
+        // A replay over another connection or a tampered alias is rejected.
+        assert!(!node_alias.verify(&[8u8; 32]));
+        let tampered = NodeAlias {
+            alias: "bootnode-2.example.org".into(),
+            ..node_alias
+        };
+        assert!(!tampered.verify(&handshake_hash));
+    }
+
+    #[test]
+    fn signed_address_change_is_bound_to_the_connection() {
+        let identity = test_identity("bootnode-1.example.org");
+        let handshake_hash = [7u8; 32];
+        let previous = "0.0.0.0:4131".parse().unwrap();
+        let address = "0.0.0.0:4141".parse().unwrap();
+
+        let change = identity
+            .sign_address_change(previous, address, &handshake_hash)
+            .unwrap();
+        assert!(change.verify(&handshake_hash));
+        assert_eq!(hex::encode(&change.public_key), identity.public_key_hex().unwrap());
+
+        // A replay over another connection or a redirection to another address is rejected.
+        assert!(!change.verify(&[8u8; 32]));
+        let redirected = AddressChange {
+            address: "203.0.113.1:4141".parse().unwrap(),
+            ..change
+        };
+        assert!(!redirected.verify(&handshake_hash));
+    }
+
+    #[test]
+    fn invalid_aliases_are_rejected() {
+        assert!(validate_alias("").is_err());
+        assert!(validate_alias(&"a".repeat(MAX_ALIAS_LEN + 1)).is_err());
+        assert!(validate_alias("line\nbreak").is_err());
+        assert!(validate_alias("aleo-bootnode").is_ok());
+    }
+}
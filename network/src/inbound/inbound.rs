@@ -14,18 +14,31 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use std::time::Duration;
+use std::{net::SocketAddr, time::Duration};
 
 use snarkvm_dpc::Storage;
+use socket2::{Domain, Protocol, Socket, Type};
 use tokio::{
     net::TcpListener,
     sync::{mpsc::error::TrySendError, Mutex},
     task,
 };
+use tracing::Span;
+use tracing_futures::Instrument;
 
 use snarkos_metrics::{self as metrics, connections, inbound, queues};
 
-use crate::{errors::NetworkError, message::*, Cache, Node, Receiver, Sender, State};
+use crate::{
+    errors::NetworkError,
+    message::*,
+    normalize_address,
+    Cache,
+    Node,
+    Receiver,
+    Sender,
+};
+#[cfg(feature = "consensus")]
+use crate::serialized_block_hash;
 
 /// A stateless component for handling inbound network traffic.
 #[derive(Debug)]
@@ -62,22 +75,85 @@ impl Inbound {
 impl<S: Storage + Send + Sync + 'static> Node<S> {
     /// This method handles new inbound connection requests.
     pub async fn listen(&self) -> Result<(), NetworkError> {
-        let listener = TcpListener::bind(&self.config.desired_address).await?;
+        if self.config.no_listen() {
+            // The desired address still serves to detect attempts to connect to self.
+            self.set_local_address(self.config.desired_address);
+            info!("Not accepting inbound connections for node ({:x})", self.id);
+
+            return Ok(());
+        }
+
+        let listener = self.bind_listener(self.config.desired_address).await?;
         let own_listener_address = listener.local_addr()?;
 
         self.set_local_address(own_listener_address);
         info!("Initializing listener for node ({:x})", self.id);
 
+        self.start_listener(listener, own_listener_address);
+
+        // The additional listeners feed into the same inbound channel as the main one.
+        for address in &self.config.additional_listen_addresses {
+            let listener = self.bind_listener(*address).await?;
+            let listener_address = listener.local_addr()?;
+            let listener_handle = self.spawn_listener(listener, listener_address);
+            self.register_task(listener_handle);
+        }
+
+        if self.config.nat_traversal() {
+            self.map_listening_port(own_listener_address.port());
+        }
+
+        Ok(())
+    }
+
+    /// Binds a listener to the given address; in dual-stack mode, IPv6 listeners also accept IPv4 connections.
+    pub(crate) async fn bind_listener(&self, address: SocketAddr) -> Result<TcpListener, NetworkError> {
+        if !(self.config.dual_stack() && address.is_ipv6()) {
+            return Ok(TcpListener::bind(address).await?);
+        }
+
+        let socket = Socket::new(Domain::IPV6, Type::STREAM, Some(Protocol::TCP))?;
+        socket.set_only_v6(false)?;
+        socket.set_reuse_address(true)?;
+        socket.bind(&address.into())?;
+        socket.listen(1024)?;
+        socket.set_nonblocking(true)?;
+
+        Ok(TcpListener::from_std(socket.into())?)
+    }
+
+    /// Spawns the task accepting inbound connections on the given listener, stopping the
+    /// previous one if the listener is being replaced.
+    pub(crate) fn start_listener(&self, listener: TcpListener, own_listener_address: SocketAddr) {
+        let listener_handle = self.spawn_listener(listener, own_listener_address);
+
+        if let Some(previous_listener) = self.listener.lock().unwrap().replace(listener_handle) {
+            previous_listener.abort();
+        }
+    }
+
+    /// Spawns a task accepting inbound connections on the given listener.
+    fn spawn_listener(&self, listener: TcpListener, own_listener_address: SocketAddr) -> task::JoinHandle<()> {
         let node_clone = self.clone();
-        let listener_handle = task::spawn(async move {
+        task::spawn(async move {
             info!("Listening for nodes at {}", own_listener_address);
 
             loop {
                 match listener.accept().await {
                     Ok((stream, remote_address)) => {
+                        // IPv4 peers show up under mapped addresses on dual-stack listeners.
+                        let remote_address = normalize_address(remote_address);
+                        if node_clone.is_banned(remote_address.ip()) {
+                            debug!("Rejected a connection from banned address {}", remote_address);
+                            continue;
+                        }
                         if !node_clone.can_connect() {
                             continue;
                         }
+                        if !node_clone.can_accept_inbound() {
+                            metrics::increment_counter!(connections::ALL_REJECTED);
+                            continue;
+                        }
                         let node_clone = node_clone.clone();
                         tokio::spawn(async move {
                             match node_clone
@@ -99,11 +175,7 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
                 }
                 metrics::increment_counter!(connections::ALL_ACCEPTED);
             }
-        });
-
-        self.register_task(listener_handle);
-
-        Ok(())
+        })
     }
 
     pub async fn process_incoming_messages(
@@ -111,7 +183,11 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
         receiver: &mut Receiver,
         cache: &mut Cache,
     ) -> Result<(), NetworkError> {
-        let Message { direction, payload } = receiver.recv().await.ok_or(NetworkError::ReceiverFailedToParse)?;
+        let Message {
+            direction,
+            payload,
+            correlation,
+        } = receiver.recv().await.ok_or(NetworkError::ReceiverFailedToParse)?;
 
         metrics::decrement_gauge!(queues::INBOUND, 1.0);
 
@@ -121,107 +197,213 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
             unreachable!("All messages processed sent to the inbound receiver are Inbound");
         };
 
+        // The messages are counted before any of them are dropped, so that the counters cover all the inbound traffic.
+        count_inbound_payload(&payload);
+
         // Check if the message hasn't already been processed recently if it's a `Block`.
         // The node should also reject them while syncing, as it is bound to receive them later.
-        if matches!(payload, Payload::Block(..)) && (self.state() == State::Syncing || cache.contains(&payload)) {
+        #[cfg(feature = "consensus")]
+        let is_syncing_blocks = self.is_syncing_blocks();
+        #[cfg(not(feature = "consensus"))]
+        let is_syncing_blocks = false;
+        if matches!(payload, Payload::Block(..)) && (is_syncing_blocks || cache.contains(&payload)) {
             return Ok(());
         }
 
+        // Drop the messages the node isn't interested in, given its role, or can't handle.
+        if !self.accepts_message(&payload) {
+            trace!(
+                "Ignoring a '{}' message from {} ({} node)",
+                payload,
+                source,
+                self.role()
+            );
+            return Ok(());
+        }
+
+        // The handling of the messages of a request/response pair is traced under the pair's ID.
+        let span = match correlation {
+            Some(id) => debug_span!("dispatch", correlation = %id, peer = %source),
+            None => Span::none(),
+        };
+
+        self.dispatch_inbound_payload(source, payload).instrument(span).await
+    }
+
+    /// Handles a payload received from the given peer.
+    async fn dispatch_inbound_payload(&self, source: SocketAddr, payload: Payload) -> Result<(), NetworkError> {
         match payload {
+            #[cfg(feature = "consensus")]
             Payload::Transaction(transaction) => {
-                metrics::increment_counter!(inbound::TRANSACTIONS);
-
                 if self.sync().is_some() {
                     self.received_memory_pool_transaction(source, transaction).await?;
+                } else if self.watcher().is_some() {
+                    self.received_watched_transactions(vec![transaction]);
                 }
             }
+            #[cfg(feature = "consensus")]
             Payload::Block(block) => {
-                metrics::increment_counter!(inbound::BLOCKS);
-
                 if self.sync().is_some() {
                     self.received_block(source, block, true).await?;
+                } else if self.watcher().is_some() {
+                    self.received_watched_block(source, block).await?;
                 }
             }
+            #[cfg(feature = "consensus")]
+            Payload::SyncBlock(block) if block.is_empty() => {
+                // The peer declined to serve one of the requested blocks, e.g. as it exceeds its serving quota.
+                if let Some(peer) = self.peer_book.get_peer_handle(source) {
+                    peer.declined_sync_block().await;
+                }
+                if self.sync().is_some() {
+                    self.declined_sync_block(source).await;
+                }
+            }
+            #[cfg(feature = "consensus")]
             Payload::SyncBlock(block) => {
-                metrics::increment_counter!(inbound::SYNCBLOCKS);
+                let hash = serialized_block_hash(&block);
 
                 if self.sync().is_some() {
                     self.received_block(source, block, false).await?;
 
                     // Update the peer and possibly finish the sync process.
                     if let Some(peer) = self.peer_book.get_peer_handle(source) {
-                        peer.got_sync_block().await;
+                        peer.got_sync_block(hash).await;
+                    }
+                } else if self.watcher().is_some() {
+                    // Update the peer first, as the block may be refused.
+                    if let Some(peer) = self.peer_book.get_peer_handle(source) {
+                        peer.got_sync_block(hash).await;
                     }
+
+                    self.received_watched_block(source, block).await?;
                 }
             }
+            #[cfg(feature = "consensus")]
             Payload::GetBlocks(hashes) => {
-                metrics::increment_counter!(inbound::GETBLOCKS);
-
+                self.received_get_blocks(source, hashes).await?;
+            }
+            #[cfg(feature = "consensus")]
+            Payload::BlockHash(hash) => {
                 if self.sync().is_some() {
-                    self.received_get_blocks(source, hashes).await?;
+                    self.received_block_hash(source, hash).await;
+                } else if self.watcher().is_some() {
+                    self.received_watched_block_hash(source, hash).await;
                 }
             }
+            #[cfg(feature = "consensus")]
+            Payload::GetBlock(hash) => {
+                self.received_get_block(source, hash).await?;
+            }
+            #[cfg(feature = "consensus")]
             Payload::GetMemoryPool => {
-                metrics::increment_counter!(inbound::GETMEMORYPOOL);
-
-                if self.sync().is_some() {
-                    self.received_get_memory_pool(source).await;
-                }
+                self.received_get_memory_pool(source).await;
             }
+            #[cfg(feature = "consensus")]
             Payload::MemoryPool(mempool) => {
-                metrics::increment_counter!(inbound::MEMORYPOOL);
-
                 if self.sync().is_some() {
                     self.received_memory_pool(mempool).await?;
+                } else if self.watcher().is_some() {
+                    self.received_watched_transactions(mempool);
                 }
             }
+            #[cfg(feature = "consensus")]
             Payload::GetSync(getsync) => {
-                metrics::increment_counter!(inbound::GETSYNC);
-
-                if self.sync().is_some() {
-                    self.received_get_sync(source, getsync).await?;
-                }
+                self.received_get_sync(source, getsync).await?;
             }
+            #[cfg(feature = "consensus")]
             Payload::Sync(sync) => {
-                metrics::increment_counter!(inbound::SYNCS);
-
                 if self.sync().is_some() {
-                    if sync.is_empty() {
-                        // An empty `Sync` is unexpected, as `GetSync` requests are only
-                        // sent to peers that declare a greater block height.
-                        warn!("{} doesn't have sync blocks to share", source);
-                        if let Some(peer) = self.peer_book.get_peer_handle(source) {
-                            peer.fail().await;
-                        }
-                    } else {
-                        trace!("Received {} sync block hashes from {}", sync.len(), source);
-                        self.received_sync(source, sync).await;
-                    }
+                    // Empty and oversized responses are also forwarded, so that the sync
+                    // process can move on to other peers right away.
+                    trace!("Received {} sync block hashes from {}", sync.len(), source);
+                    self.received_sync(source, sync).await;
+                } else if self.watcher().is_some() {
+                    self.received_watched_block_hashes(source, sync).await;
                 }
             }
+            #[cfg(not(feature = "consensus"))]
+            Payload::Transaction(_)
+            | Payload::Block(_)
+            | Payload::SyncBlock(_)
+            | Payload::GetBlocks(_)
+            | Payload::BlockHash(_)
+            | Payload::GetBlock(_)
+            | Payload::GetMemoryPool
+            | Payload::MemoryPool(_)
+            | Payload::GetSync(_)
+            | Payload::Sync(_) => {
+                // Without the consensus feature, these are already dropped by `accepts_message`.
+                unreachable!()
+            }
             Payload::GetPeers => {
-                metrics::increment_counter!(inbound::GETPEERS);
-
                 self.send_peers(source).await;
             }
             Payload::Peers(peers) => {
-                metrics::increment_counter!(inbound::PEERS);
+                #[cfg(feature = "crawler")]
+                if let Some(known_network) = self.known_network() {
+                    known_network.update(source, &peers);
+                }
 
-                self.process_inbound_peers(peers).await;
+                self.process_inbound_peers(source, peers).await;
             }
-            Payload::Ping(_) | Payload::Pong => {
+            Payload::Ping(..) | Payload::Pong | Payload::NodeAlias(_) | Payload::Rekey | Payload::AddressChange(_) => {
                 // Skip as this case is already handled with priority in inbound_handler
                 unreachable!()
             }
-            Payload::Unknown => {
-                metrics::increment_counter!(inbound::UNKNOWN);
-                warn!("Unknown payload received; this could indicate that the client you're using is out-of-date");
+            Payload::RpcAuth(_) | Payload::RpcRequest(_) | Payload::RpcResponse(_) => {
+                // The RPC channel is only served over the connections of management clients.
+                debug!("Ignoring a '{}' message from peer {}", payload, source);
+            }
+            Payload::Unknown(type_id, _) => {
+                // The critical ones are refused by the protocol state, so the rest can be skipped.
+                debug!(
+                    "Skipping a payload of unknown type {} from {}; this could indicate that the client you're using \
+                     is out-of-date",
+                    type_id, source
+                );
             }
         }
 
         Ok(())
     }
 
+    /// Checks whether the node processes the given inbound payload: its message policy has to accept it, and the
+    /// node needs the components handling it, i.e. the sync layer to serve any blocks or transactions, and either
+    /// the sync layer or a watcher to follow them.
+    pub fn accepts_message(&self, payload: &Payload) -> bool {
+        #[cfg(feature = "consensus")]
+        let can_handle = match payload {
+            Payload::GetBlock(_) | Payload::GetBlocks(_) | Payload::GetMemoryPool | Payload::GetSync(_) => {
+                self.sync().is_some()
+            }
+            Payload::Block(_)
+            | Payload::SyncBlock(_)
+            | Payload::BlockHash(_)
+            | Payload::Sync(_)
+            | Payload::Transaction(_)
+            | Payload::MemoryPool(_) => self.sync().is_some() || self.watcher().is_some(),
+            _ => true,
+        };
+        // Without the consensus feature, the node has neither a sync layer nor a watcher.
+        #[cfg(not(feature = "consensus"))]
+        let can_handle = !matches!(
+            payload,
+            Payload::GetBlock(_)
+                | Payload::GetBlocks(_)
+                | Payload::GetMemoryPool
+                | Payload::GetSync(_)
+                | Payload::Block(_)
+                | Payload::SyncBlock(_)
+                | Payload::BlockHash(_)
+                | Payload::Sync(_)
+                | Payload::Transaction(_)
+                | Payload::MemoryPool(_)
+        );
+
+        can_handle && self.message_policy().accepts(payload)
+    }
+
     #[inline]
     pub(crate) fn route(&self, response: Message) {
         match self.inbound.sender.try_send(response) {
@@ -239,3 +421,24 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
         }
     }
 }
+
+/// Counts the given inbound payload in the metrics of its type.
+fn count_inbound_payload(payload: &Payload) {
+    match payload {
+        Payload::Transaction(_) => metrics::increment_counter!(inbound::TRANSACTIONS),
+        Payload::Block(_) => metrics::increment_counter!(inbound::BLOCKS),
+        Payload::SyncBlock(_) => metrics::increment_counter!(inbound::SYNCBLOCKS),
+        Payload::GetBlocks(_) => metrics::increment_counter!(inbound::GETBLOCKS),
+        Payload::BlockHash(_) => metrics::increment_counter!(inbound::BLOCKHASHES),
+        Payload::GetBlock(_) => metrics::increment_counter!(inbound::GETBLOCK),
+        Payload::GetMemoryPool => metrics::increment_counter!(inbound::GETMEMORYPOOL),
+        Payload::MemoryPool(_) => metrics::increment_counter!(inbound::MEMORYPOOL),
+        Payload::GetSync(_) => metrics::increment_counter!(inbound::GETSYNC),
+        Payload::Sync(_) => metrics::increment_counter!(inbound::SYNCS),
+        Payload::GetPeers => metrics::increment_counter!(inbound::GETPEERS),
+        Payload::Peers(_) => metrics::increment_counter!(inbound::PEERS),
+        Payload::Unknown(..) => metrics::increment_counter!(inbound::UNKNOWN),
+        // The other messages are counted by the peers handling them, if at all.
+        _ => {}
+    }
+}
@@ -19,3 +19,6 @@ pub use cache::*;
 
 pub mod inbound;
 pub use inbound::*;
+
+pub mod rebind;
+pub use rebind::*;
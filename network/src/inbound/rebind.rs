@@ -0,0 +1,134 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::Duration,
+};
+
+use snarkvm_dpc::Storage;
+use tokio::{task, time::sleep};
+
+use crate::{nat, EventCategory, NetworkError, Node, Payload};
+
+impl<S: Storage + Send + Sync + 'static> Node<S> {
+    /// Returns the IP address this machine currently uses to reach the rest of the network.
+    pub async fn current_local_ip(&self) -> Option<IpAddr> {
+        // No packets are sent; the target is only used to select a route. A documentation-only
+        // address is used as a fallback, as it is routed via the default gateway.
+        let ipv6 = self.config.desired_address.is_ipv6();
+        let target = self
+            .config
+            .bootnodes()
+            .iter()
+            .find(|bootnode| bootnode.is_ipv6() == ipv6)
+            .copied()
+            .unwrap_or_else(|| {
+                if ipv6 {
+                    SocketAddr::from((Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1), 9))
+                } else {
+                    SocketAddr::from((Ipv4Addr::new(192, 0, 2, 1), 9))
+                }
+            });
+
+        nat::local_ip_towards(target).await.ok()
+    }
+
+    /// Rebinds the listener if it is bound to a specific IP that is no longer the local one,
+    /// refreshes the NAT mapping if applicable, and announces the resulting address to the
+    /// connected peers. Returns the address the node is now reachable at.
+    pub async fn rebind(&self) -> Result<SocketAddr, NetworkError> {
+        if self.config.no_listen() {
+            return Err(NetworkError::NotListening);
+        }
+
+        let desired_address = self.config.desired_address;
+        let port = self
+            .local_address()
+            .map(|addr| addr.port())
+            .unwrap_or_else(|| desired_address.port());
+        let current_ip = self.current_local_ip().await;
+
+        // A listener bound to an unspecified IP keeps accepting connections on all interfaces,
+        // while a loopback one is unaffected by changes to the network.
+        let bind_ip = if desired_address.ip().is_unspecified() || desired_address.ip().is_loopback() {
+            desired_address.ip()
+        } else {
+            current_ip.unwrap_or_else(|| desired_address.ip())
+        };
+        let bind_address = SocketAddr::new(bind_ip, port);
+
+        if self.local_address() != Some(bind_address) {
+            // The new listener is started before the old one is stopped, so that there is no
+            // window in which inbound connections are refused.
+            let listener = self.bind_listener(bind_address).await?;
+            let new_address = listener.local_addr()?;
+            self.set_local_address(new_address);
+            self.start_listener(listener, new_address);
+            info!("Rebound the listener to {}", new_address);
+            self.journal.record(
+                EventCategory::Lifecycle,
+                format!("rebound the listener to {}", new_address),
+            );
+        }
+
+        if self.config.nat_traversal() {
+            match nat::map_port(port).await {
+                Ok(mapping) => self.set_external_address(mapping.external_address),
+                Err(e) => warn!("Couldn't refresh the port mapping after a rebind: {}", e),
+            }
+        }
+
+        let advertised_address = self
+            .advertised_address()
+            .unwrap_or_else(|| SocketAddr::new(current_ip.unwrap_or(bind_ip), port));
+        self.peer_book.broadcast(Payload::Peers(vec![advertised_address])).await;
+
+        Ok(advertised_address)
+    }
+
+    /// Spawns a task that periodically checks whether the local IP has changed, e.g. due to
+    /// DHCP or a VPN, and rebinds the listener when it does.
+    pub(crate) fn watch_local_address(&self) {
+        let node = self.clone();
+        let watcher_task = task::spawn(async move {
+            let mut last_ip = node.current_local_ip().await;
+
+            loop {
+                sleep(Duration::from_secs(crate::LOCAL_ADDRESS_CHECK_INTERVAL_SECS as u64)).await;
+
+                let current_ip = node.current_local_ip().await;
+                if current_ip.is_none() || current_ip == last_ip {
+                    continue;
+                }
+
+                info!(
+                    "The local IP has changed from {} to {}",
+                    last_ip.map(|ip| ip.to_string()).unwrap_or_default(),
+                    current_ip.map(|ip| ip.to_string()).unwrap_or_default(),
+                );
+
+                match node.rebind().await {
+                    Ok(address) => info!("Announced the new address {} to peers", address),
+                    Err(e) => error!("Couldn't rebind the listener: {}", e),
+                }
+                last_ip = current_ip;
+            }
+        });
+
+        self.register_task(watcher_task);
+    }
+}
@@ -0,0 +1,610 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+//! The node's journal of notable events, e.g. reorgs, bans or reloads of its configuration. The events are
+//! dispatched to a set of sinks, each of which receives the categories of events it's configured with: an
+//! in-memory ring of the most recent ones included in crash reports, a journal persisted in storage, the
+//! configured webhook and a stream the live subscribers, e.g. WebSocket clients, receive them from.
+
+use crate::{NetworkError, Node, Webhook, WebhookEvent};
+#[cfg(feature = "consensus")]
+use snarkos_consensus::MerkleTreeLedger;
+use snarkvm_dpc::Storage;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeSet, VecDeque},
+    fmt,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc,
+        Arc,
+        Mutex,
+        PoisonError,
+        RwLock,
+        RwLockReadGuard,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tokio::sync::broadcast;
+
+/// The category of a journaled event; each sink receives the categories it's configured with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventCategory {
+    /// The node's services were started or stopped, or its configuration or address changed.
+    Lifecycle,
+    /// The node's peers, e.g. a ban or a low peer count.
+    Peers,
+    /// The node's chain, e.g. a fork, a reorg or the node falling out of sync.
+    Chain,
+    /// One of the configured alarms was raised or cleared.
+    Alarms,
+    /// The node panicked.
+    Crashes,
+}
+
+impl EventCategory {
+    /// All the categories of events.
+    pub const ALL: [EventCategory; 5] = [
+        EventCategory::Lifecycle,
+        EventCategory::Peers,
+        EventCategory::Chain,
+        EventCategory::Alarms,
+        EventCategory::Crashes,
+    ];
+}
+
+impl fmt::Display for EventCategory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Lifecycle => write!(f, "lifecycle"),
+            Self::Peers => write!(f, "peers"),
+            Self::Chain => write!(f, "chain"),
+            Self::Alarms => write!(f, "alarms"),
+            Self::Crashes => write!(f, "crashes"),
+        }
+    }
+}
+
+impl FromStr for EventCategory {
+    type Err = NetworkError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|category| category.to_string() == s.trim())
+            .ok_or_else(|| NetworkError::InvalidEventCategory(s.to_string()))
+    }
+}
+
+/// A notable event in the life of the node.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct JournalEvent {
+    /// The sequence number of the event; it's unique within a single run of the node.
+    pub seq: u64,
+    /// The time at which the event occurred.
+    pub time: DateTime<Utc>,
+    /// The category of the event.
+    pub category: EventCategory,
+    /// The webhook notification the event corresponds to, if any.
+    pub notification: Option<WebhookEvent>,
+    /// A description of the event.
+    pub description: String,
+    /// The event-specific details, if any.
+    #[serde(default)]
+    pub details: serde_json::Value,
+}
+
+/// A destination of the journaled events. The events are written to the sinks synchronously, including from the
+/// panic hook, so the sinks mustn't block; the ones that don't handle them quickly need to queue them instead.
+pub trait JournalSink: Send + core::marker::Sync {
+    /// Handles an event of one of the categories the sink was registered for.
+    fn write(&self, event: &JournalEvent);
+}
+
+/// The categories of events received by each of the journal's sinks, and how long they're kept for.
+#[derive(Clone, Debug)]
+pub struct JournalConfig {
+    /// The categories of events kept in memory and included in crash reports.
+    pub memory: Vec<EventCategory>,
+    /// The number of the most recent events kept in memory.
+    pub memory_capacity: usize,
+    /// The categories of events persisted in storage; none by default.
+    pub storage: Vec<EventCategory>,
+    /// The amount of time the events persisted in storage are kept for.
+    pub storage_retention: Duration,
+    /// The categories of events reported to the configured webhook, if any; the webhook's own event filter applies
+    /// in addition to them.
+    pub webhook: Vec<EventCategory>,
+    /// The categories of events streamed to the live subscribers.
+    pub stream: Vec<EventCategory>,
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self {
+            memory: EventCategory::ALL.to_vec(),
+            memory_capacity: crate::JOURNAL_MEMORY_CAPACITY,
+            storage: vec![],
+            storage_retention: Duration::from_secs(crate::JOURNAL_STORAGE_RETENTION_SECS.into()),
+            webhook: EventCategory::ALL.to_vec(),
+            stream: EventCategory::ALL.to_vec(),
+        }
+    }
+}
+
+/// A bounded in-memory ring of the most recent events.
+#[derive(Debug)]
+pub struct MemorySink {
+    capacity: usize,
+    events: Mutex<VecDeque<JournalEvent>>,
+}
+
+impl MemorySink {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            events: Default::default(),
+        }
+    }
+
+    /// Returns the kept events, from the oldest to the most recent one.
+    pub fn recent(&self) -> Vec<JournalEvent> {
+        self.events.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Returns the kept events without waiting for the ring to become available; it's empty if the ring is
+    /// being written to, e.g. by the thread that panicked.
+    pub fn try_recent(&self) -> Vec<JournalEvent> {
+        match self.events.try_lock() {
+            Ok(events) => events.iter().cloned().collect(),
+            Err(_) => vec![],
+        }
+    }
+}
+
+impl JournalSink for MemorySink {
+    fn write(&self, event: &JournalEvent) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        // While panicking, the ring could be held by the very thread that panicked.
+        let events = if thread::panicking() {
+            self.events.try_lock().ok()
+        } else {
+            Some(self.events.lock().unwrap_or_else(PoisonError::into_inner))
+        };
+        let mut events = match events {
+            Some(events) => events,
+            None => return,
+        };
+        if events.len() == self.capacity {
+            events.pop_front();
+        }
+        events.push_back(event.clone());
+    }
+}
+
+/// A sink sending the events to the live subscribers; the ones lagging behind miss some of them.
+#[derive(Debug)]
+pub struct StreamSink {
+    sender: broadcast::Sender<JournalEvent>,
+}
+
+impl Default for StreamSink {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(crate::JOURNAL_STREAM_CHANNEL_DEPTH);
+
+        Self { sender }
+    }
+}
+
+impl StreamSink {
+    /// Subscribes to the events written to the sink from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<JournalEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl JournalSink for StreamSink {
+    fn write(&self, event: &JournalEvent) {
+        // An error only means that there are no subscribers at the moment.
+        let _ = self.sender.send(event.clone());
+    }
+}
+
+/// A sink reporting the events corresponding to webhook notifications to the configured webhook.
+pub struct WebhookSink(pub Webhook);
+
+impl JournalSink for WebhookSink {
+    fn write(&self, event: &JournalEvent) {
+        if let Some(notification) = event.notification {
+            self.0.notify(notification, event.details.clone());
+        }
+    }
+}
+
+/// The UNIX timestamps in milliseconds and the sequence numbers of the events persisted in storage, i.e. the keys
+/// they're stored under, in their order.
+#[cfg(feature = "consensus")]
+type JournalIndex = Arc<Mutex<BTreeSet<(i64, u64)>>>;
+
+/// A sink persisting the events in storage; they're queued for a dedicated thread, which also removes the events
+/// older than the retention period periodically. The persisted events are indexed in memory, so that the queries
+/// only read the matching ones from storage.
+#[cfg(feature = "consensus")]
+pub struct StorageSink<S: Storage> {
+    storage: Arc<MerkleTreeLedger<S>>,
+    index: JournalIndex,
+    sender: mpsc::SyncSender<JournalEvent>,
+}
+
+#[cfg(feature = "consensus")]
+impl<S: Storage + Send + core::marker::Sync + 'static> StorageSink<S> {
+    /// Indexes the events already persisted in storage and starts the thread persisting the new ones.
+    pub fn new(storage: Arc<MerkleTreeLedger<S>>, retention: Duration) -> Self {
+        let index = match storage.get_journal_keys() {
+            Ok(keys) => keys.into_iter().collect(),
+            Err(e) => {
+                warn!("Couldn't index the stored journal events: {}", e);
+                Default::default()
+            }
+        };
+        let index = Arc::new(Mutex::new(index));
+
+        let (sender, receiver) = mpsc::sync_channel(crate::JOURNAL_STORAGE_QUEUE_DEPTH);
+        let mut writer = StorageWriter {
+            storage: storage.clone(),
+            index: index.clone(),
+            retention,
+            last_pruned: None,
+        };
+        // The thread stops once the sink, and with it the sender, is dropped.
+        thread::spawn(move || {
+            for event in receiver {
+                writer.store(&event);
+                writer.prune();
+            }
+        });
+
+        Self { storage, index, sender }
+    }
+
+    /// Returns up to `limit` of the persisted events that occurred at or after the given time, from the oldest to
+    /// the most recent one.
+    pub fn events(&self, since: DateTime<Utc>, limit: usize) -> Result<Vec<JournalEvent>, NetworkError> {
+        let keys: Vec<(i64, u64)> = self
+            .index
+            .lock()
+            .unwrap()
+            .range((since.timestamp_millis(), u64::MIN)..)
+            .take(limit)
+            .copied()
+            .collect();
+
+        let mut events = Vec::with_capacity(keys.len());
+        for (timestamp_millis, seq) in keys {
+            // The event could have been removed as outdated in the meantime.
+            if let Some(bytes) = self.storage.get_journal_event(timestamp_millis, seq)? {
+                events.push(serde_json::from_slice(&bytes).map_err(|e| NetworkError::Journal(e.to_string()))?);
+            }
+        }
+
+        Ok(events)
+    }
+}
+
+#[cfg(feature = "consensus")]
+impl<S: Storage + Send + core::marker::Sync> JournalSink for StorageSink<S> {
+    fn write(&self, event: &JournalEvent) {
+        if let Err(mpsc::TrySendError::Full(event)) = self.sender.try_send(event.clone()) {
+            warn!(
+                "The journal storage queue is full; dropping a '{}' event",
+                event.description
+            );
+        }
+    }
+}
+
+/// The state of the thread persisting the events queued by the storage sink.
+#[cfg(feature = "consensus")]
+struct StorageWriter<S: Storage> {
+    storage: Arc<MerkleTreeLedger<S>>,
+    index: JournalIndex,
+    retention: Duration,
+    last_pruned: Option<Instant>,
+}
+
+#[cfg(feature = "consensus")]
+impl<S: Storage> StorageWriter<S> {
+    /// Persists and indexes an event.
+    fn store(&self, event: &JournalEvent) {
+        let serialized = match serde_json::to_vec(event) {
+            Ok(serialized) => serialized,
+            Err(e) => {
+                warn!("Couldn't serialize a journal event: {}", e);
+                return;
+            }
+        };
+
+        let timestamp_millis = event.time.timestamp_millis();
+        match self
+            .storage
+            .store_journal_event(timestamp_millis, event.seq, serialized)
+        {
+            Ok(()) => {
+                self.index.lock().unwrap().insert((timestamp_millis, event.seq));
+            }
+            Err(e) => warn!("Couldn't store a journal event: {}", e),
+        }
+    }
+
+    /// Removes the events older than the retention period, unless it was done recently.
+    fn prune(&mut self) {
+        let interval = Duration::from_secs(crate::JOURNAL_PRUNING_INTERVAL_SECS.into());
+        if matches!(self.last_pruned, Some(time) if time.elapsed() < interval) {
+            return;
+        }
+        self.last_pruned = Some(Instant::now());
+
+        let horizon = Utc::now()
+            .timestamp_millis()
+            .saturating_sub(self.retention.as_millis() as i64);
+        let outdated: Vec<(i64, u64)> = {
+            let mut index = self.index.lock().unwrap();
+            let retained = index.split_off(&(horizon, u64::MIN));
+            std::mem::replace(&mut *index, retained).into_iter().collect()
+        };
+        if outdated.is_empty() {
+            return;
+        }
+
+        match self.storage.remove_journal_events(&outdated) {
+            Ok(()) => debug!("Removed {} outdated journal events", outdated.len()),
+            Err(e) => {
+                warn!("Couldn't remove the outdated journal events: {}", e);
+                // They're still in storage, so they're kept in the index until the next attempt.
+                self.index.lock().unwrap().extend(outdated);
+            }
+        }
+    }
+}
+
+/// A sink registered with the journal, along with the categories of events it receives.
+struct RegisteredSink {
+    name: &'static str,
+    categories: Vec<EventCategory>,
+    sink: Arc<dyn JournalSink>,
+}
+
+/// The journal of the node's notable events, dispatching them to the registered sinks.
+pub struct Journal {
+    /// The most recent events, included in crash reports.
+    memory: Arc<MemorySink>,
+    /// The events sent to the live subscribers.
+    stream: Arc<StreamSink>,
+    /// All the sinks, including the ones above.
+    sinks: RwLock<Vec<RegisteredSink>>,
+    /// The sequence number of the next event.
+    next_seq: AtomicU64,
+}
+
+impl Journal {
+    /// Creates a journal with the in-memory and stream sinks; the other ones are added once they're available.
+    pub fn new(config: &JournalConfig) -> Self {
+        let journal = Self {
+            memory: Arc::new(MemorySink::new(config.memory_capacity)),
+            stream: Default::default(),
+            sinks: Default::default(),
+            next_seq: Default::default(),
+        };
+        journal.add_sink("memory", &config.memory, journal.memory.clone());
+        journal.add_sink("stream", &config.stream, journal.stream.clone());
+
+        journal
+    }
+
+    /// Registers a sink receiving the given categories of events; it isn't registered if there are none.
+    pub fn add_sink(&self, name: &'static str, categories: &[EventCategory], sink: Arc<dyn JournalSink>) {
+        if categories.is_empty() {
+            return;
+        }
+
+        debug!("Journaling the {:?} events to the {} sink", categories, name);
+        self.sinks.write().unwrap().push(RegisteredSink {
+            name,
+            categories: categories.to_vec(),
+            sink,
+        });
+    }
+
+    /// Checks whether a sink with the given name is registered.
+    pub fn has_sink(&self, name: &str) -> bool {
+        self.sinks.read().unwrap().iter().any(|sink| sink.name == name)
+    }
+
+    /// Records an event with the given description and no details.
+    pub fn record<T: Into<String>>(&self, category: EventCategory, description: T) {
+        self.write(category, None, description.into(), serde_json::Value::Null);
+    }
+
+    /// Records an event corresponding to the given webhook notification.
+    pub fn notify(&self, notification: WebhookEvent, details: serde_json::Value) {
+        self.write(
+            notification.category(),
+            Some(notification),
+            notification.to_string(),
+            details,
+        );
+    }
+
+    fn write(
+        &self,
+        category: EventCategory,
+        notification: Option<WebhookEvent>,
+        description: String,
+        details: serde_json::Value,
+    ) {
+        let event = JournalEvent {
+            seq: self.next_seq.fetch_add(1, Ordering::Relaxed),
+            time: Utc::now(),
+            category,
+            notification,
+            description,
+            details,
+        };
+
+        let sinks = match self.sinks() {
+            Some(sinks) => sinks,
+            None => return,
+        };
+        for registered in sinks.iter() {
+            if registered.categories.contains(&category) {
+                registered.sink.write(&event);
+            }
+        }
+    }
+
+    /// Returns the registered sinks; while panicking, they're only returned if they can be read without waiting,
+    /// as a sink could be in the middle of being registered by the thread that panicked.
+    fn sinks(&self) -> Option<RwLockReadGuard<'_, Vec<RegisteredSink>>> {
+        if thread::panicking() {
+            self.sinks.try_read().ok()
+        } else {
+            Some(self.sinks.read().unwrap_or_else(PoisonError::into_inner))
+        }
+    }
+
+    /// Returns the events kept in memory, from the oldest to the most recent one.
+    pub fn recent(&self) -> Vec<JournalEvent> {
+        self.memory.recent()
+    }
+
+    /// Returns the events kept in memory without waiting for any locks, e.g. while the node is panicking.
+    pub fn try_recent(&self) -> Vec<JournalEvent> {
+        self.memory.try_recent()
+    }
+
+    /// Subscribes to the streamed events.
+    pub fn subscribe(&self) -> broadcast::Receiver<JournalEvent> {
+        self.stream.subscribe()
+    }
+}
+
+impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
+    /// Returns the storage the journal can be persisted in, if there is one.
+    #[cfg(feature = "consensus")]
+    fn journal_ledger(&self) -> Option<Arc<MerkleTreeLedger<S>>> {
+        let storage = self.sync().map(|sync| sync.consensus.ledger.clone());
+        #[cfg(feature = "crawler")]
+        let storage = storage.or_else(|| self.crawler_storage.get().cloned());
+
+        storage
+    }
+
+    /// Starts persisting the configured categories of events in storage, if the node has one.
+    #[cfg(feature = "consensus")]
+    pub(crate) fn attach_journal_storage(&self) {
+        let categories = &self.config.journal.storage;
+        if categories.is_empty() || self.journal_storage.get().is_some() {
+            return;
+        }
+
+        if let Some(storage) = self.journal_ledger() {
+            let sink = Arc::new(StorageSink::new(storage, self.config.journal.storage_retention));
+            self.journal.add_sink("storage", categories, sink.clone());
+            let _ = self.journal_storage.set(sink);
+        }
+    }
+
+    /// Returns up to `limit` events that occurred at or after the given time, from the oldest to the most recent one;
+    /// they're read from storage if the journal is persisted there, and from memory otherwise.
+    pub fn journal_events(&self, since: DateTime<Utc>, limit: usize) -> Result<Vec<JournalEvent>, NetworkError> {
+        #[cfg(feature = "consensus")]
+        if let Some(storage) = self.journal_storage.get() {
+            return storage.events(since, limit);
+        }
+
+        Ok(self
+            .journal
+            .recent()
+            .into_iter()
+            .filter(|event| event.time >= since)
+            .take(limit)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_are_dispatched_by_category() {
+        let journal = Journal::new(&JournalConfig {
+            memory: vec![EventCategory::Lifecycle, EventCategory::Chain],
+            stream: vec![EventCategory::Chain],
+            ..Default::default()
+        });
+        let mut stream = journal.subscribe();
+
+        journal.record(EventCategory::Lifecycle, "started the services");
+        journal.record(EventCategory::Peers, "ignored");
+        journal.notify(WebhookEvent::Reorg, serde_json::json!({ "depth": 2 }));
+
+        let events = journal.recent();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].description, "started the services");
+        assert_eq!(events[1].category, EventCategory::Chain);
+        assert_eq!(events[1].notification, Some(WebhookEvent::Reorg));
+        assert!(events[0].seq < events[1].seq);
+
+        let streamed = stream.try_recv().unwrap();
+        assert_eq!(streamed, events[1]);
+        assert!(stream.try_recv().is_err());
+    }
+
+    #[test]
+    fn memory_keeps_the_most_recent_events() {
+        let journal = Journal::new(&Default::default());
+        for i in 0..crate::JOURNAL_MEMORY_CAPACITY + 2 {
+            journal.record(EventCategory::Lifecycle, format!("event {}", i));
+        }
+
+        let events = journal.recent();
+        assert_eq!(events.len(), crate::JOURNAL_MEMORY_CAPACITY);
+        assert_eq!(events[0].description, "event 2");
+        assert_eq!(
+            events.last().unwrap().description,
+            format!("event {}", crate::JOURNAL_MEMORY_CAPACITY + 1)
+        );
+        assert_eq!(journal.try_recent().len(), crate::JOURNAL_MEMORY_CAPACITY);
+    }
+
+    #[test]
+    fn categories_can_be_parsed() {
+        for category in EventCategory::ALL.iter() {
+            assert_eq!(category.to_string().parse::<EventCategory>().unwrap(), *category);
+        }
+        assert!("blocks".parse::<EventCategory>().is_err());
+    }
+}
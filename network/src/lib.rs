@@ -27,30 +27,212 @@ extern crate derivative;
 #[macro_use]
 extern crate tracing;
 
+pub use alarms::*;
+pub use builder::*;
+pub use callbacks::*;
+pub use clock::*;
 pub use config::*;
+pub use crash::*;
 pub use drop_join::*;
 pub use errors::*;
+#[cfg(feature = "fault-injection")]
+pub use fault_injection::*;
+#[cfg(feature = "test-vectors")]
+pub use handshake_vectors::*;
+pub use identity::*;
 pub use inbound::*;
+pub use journal::*;
 pub use message::*;
+pub use nat::*;
+pub use network_id::*;
 pub use node::*;
 pub use peers::*;
+pub use proxy::*;
+pub use recommended_peers::*;
+pub use remote_rpc::*;
+pub use roles::*;
+pub use services::*;
 pub use snarkos_metrics::stats::*;
 pub use sync::*;
+#[cfg(feature = "crawler")]
+pub use topology::*;
+#[cfg(feature = "consensus")]
+pub use watcher::*;
+pub use webhooks::*;
 
+pub mod alarms;
+pub mod builder;
+pub mod callbacks;
+pub mod clock;
 pub mod config;
+pub mod crash;
 mod drop_join;
 pub mod errors;
+#[cfg(feature = "fault-injection")]
+pub mod fault_injection;
+#[cfg(feature = "test-vectors")]
+pub mod handshake_vectors;
+pub mod identity;
 pub mod inbound;
+pub mod journal;
 pub mod message;
+pub mod nat;
+pub mod network_id;
 pub mod node;
 pub mod peers;
+pub mod proxy;
+pub mod recommended_peers;
+pub mod remote_rpc;
+pub mod roles;
+pub mod services;
 pub mod sync;
+#[cfg(feature = "crawler")]
+pub mod topology;
+#[cfg(feature = "consensus")]
+pub mod watcher;
+pub mod webhooks;
+
+/// The height of a block; the same as the one of `snarkos-storage`, which is only a dependency of the `consensus`
+/// feature.
+pub(crate) type BlockHeight = u32;
 
 /// The maximum number of block hashes that can be requested or provided in a single batch.
 pub const MAX_BLOCK_SYNC_COUNT: u32 = 64;
+/// The maximum number of blocks read from storage concurrently while serving a `GetBlocks` request.
+pub const MAX_CONCURRENT_BLOCK_READS: usize = 8;
 /// The maximum amount of time allowed to process a single batch of sync blocks. It should be aligned
 /// with `MAX_BLOCK_SYNC_COUNT`.
 pub const BLOCK_SYNC_EXPIRATION_SECS: u8 = 30;
+/// The amount of time after which a block requested in response to a `BlockHash` announcement can be
+/// requested again, e.g. from another peer that announced it.
+pub const BLOCK_REQUEST_EXPIRATION_SECS: u8 = 10;
+/// The maximum number of peers sync blocks are requested from at once.
+pub const MAX_SYNC_NODES: usize = 4;
+/// The difference in reported block heights below which sync nodes are considered equally far ahead, and are
+/// preferred based on their failures and RTT instead.
+pub const SYNC_NODE_HEIGHT_TOLERANCE: u32 = 4;
+/// The amount of time a sync node that stalled mid-batch is avoided for, unless there are no other candidates.
+pub const STALLED_SYNC_NODE_COOLDOWN_SECS: u16 = 300;
+/// The amount of time after which a sync node that hasn't delivered any of its outstanding blocks is considered
+/// stalled, and the blocks are requested from other sync nodes that can serve them.
+pub const SYNC_BLOCK_STALL_SECS: u8 = 4;
+/// The maximum number of times the interval between memory pool syncs is doubled while they keep turning up
+/// no new transactions.
+pub const MAX_MEMPOOL_SYNC_BACKOFF: u32 = 5;
+/// The number of blocks below the node's tip past which a peer's `GetSync` request is considered deep, i.e.
+/// more likely to be scraping the chain than catching up with it.
+pub const DEEP_SYNC_QUERY_DEPTH: u32 = 1024;
+/// The amount of time after a change of the node's announced address during which it's announced to the peers the
+/// node connects to, so that they replace its stale address.
+pub const ADDRESS_CHANGE_ANNOUNCEMENT_SECS: u32 = 7 * 24 * 60 * 60;
+/// The minimum number of most recent blocks a pruned node keeps in full, so that it can still reorganize.
+pub const MIN_PRUNE_DEPTH: u32 = 1024;
+/// The interval between each pruning of the blocks deeper than the configured depth.
+pub const PRUNING_INTERVAL_SECS: u8 = 60;
+
+/// The size of a payload above which it is decoded on the blocking thread pool, instead of
+/// in the peer's own task.
+pub const BLOCKING_DECODE_THRESHOLD: usize = 64 * 1024; // 64KiB
+/// The maximum number of payloads from a single peer that can be decoded at once; reads from
+/// the peer are paused until one of them is done.
+pub const MAX_IN_FLIGHT_DECODES_PER_PEER: usize = 4;
+/// The maximum size of a payload that can be queued in order to be written in a single batch with
+/// other small payloads; larger ones are written right away.
+pub const MAX_COALESCED_PAYLOAD_SIZE: usize = 4 * 1024; // 4KiB
+/// The size of the queued small payloads above which they are written without waiting for more.
+pub const MAX_WRITE_QUEUE_SIZE: usize = 64 * 1024; // 64KiB
+/// The total size of the payloads deferred by the bandwidth caps above which the new ones wait for room.
+pub const MAX_DEFERRED_SIZE: usize = 16 * 1024 * 1024; // 16MiB
+/// The maximum amount of time a small payload can be queued for before it's written.
+pub const WRITE_COALESCING_DELAY_MS: u8 = 5;
+
+/// The amount of time after which a transaction relayed by a peer that hasn't been included in a block
+/// is considered rejected.
+pub const RELAYED_TRANSACTION_EXPIRY_SECS: u16 = 3600;
+/// The maximum number of relayed transactions whose inclusion in a block is awaited at once.
+pub const MAX_TRACKED_RELAYED_TRANSACTIONS: usize = 16 * 1024;
+
+/// The interval between each snapshot of the node's stats kept in its history.
+pub const STATS_HISTORY_INTERVAL_SECS: u8 = 10;
+/// The number of snapshots of the node's stats kept in its history; an hour's worth.
+pub const STATS_HISTORY_LEN: usize = 360;
+
+/// The default number of most recent notable events in the life of the node kept in memory and included in its
+/// crash reports.
+pub const JOURNAL_MEMORY_CAPACITY: usize = 64;
+/// The default amount of time the journaled events persisted in storage are kept for; a week.
+pub const JOURNAL_STORAGE_RETENTION_SECS: u32 = 7 * 24 * 60 * 60;
+/// The minimum interval between the removals of the outdated journal events from storage.
+pub const JOURNAL_PRUNING_INTERVAL_SECS: u16 = 3600;
+/// The number of journaled events that can await being persisted in storage before the new ones are dropped.
+pub const JOURNAL_STORAGE_QUEUE_DEPTH: usize = 256;
+/// The number of journaled events a live subscriber can lag behind by before missing some of them.
+pub const JOURNAL_STREAM_CHANNEL_DEPTH: usize = 256;
+/// The maximum number of journaled events returned by a single query.
+pub const JOURNAL_QUERY_LIMIT: usize = 1000;
+
+/// The number of most recently received full blocks kept by a watcher node.
+pub const WATCHER_BLOCK_WINDOW: usize = 16;
+/// The maximum number of transactions in the memory pool of a watcher node.
+pub const WATCHER_MEMORY_POOL_SIZE: usize = 4096;
+/// The amount of time after which a transaction that wasn't included in a block is dropped from the memory
+/// pool of a watcher node.
+pub const WATCHER_TRANSACTION_EXPIRY_SECS: u16 = 3600;
+/// The interval between each update of the chain and the memory pool followed by a watcher node.
+pub const WATCHER_SYNC_INTERVAL_SECS: u8 = 10;
+/// The number of events a subscriber of a watcher node can lag behind by before missing some of them.
+pub const WATCHER_EVENT_CHANNEL_DEPTH: usize = 1024;
+
+/// The maximum number of webhook notifications awaiting delivery; any further ones are dropped.
+pub const WEBHOOK_QUEUE_DEPTH: usize = 256;
+/// The number of times the delivery of a webhook notification is attempted before giving up on it.
+pub const WEBHOOK_MAX_ATTEMPTS: u8 = 5;
+/// The delay before the first retry of a failed webhook delivery; it doubles with every subsequent retry.
+pub const WEBHOOK_RETRY_BASE_DELAY_SECS: u8 = 2;
+/// The maximum amount of time a single webhook delivery attempt can take.
+pub const WEBHOOK_REQUEST_TIMEOUT_SECS: u8 = 10;
+/// The minimum amount of time between two webhook notifications about the same ongoing condition,
+/// e.g. a low peer count.
+pub const WEBHOOK_CONDITION_COOLDOWN_SECS: u16 = 600;
+/// The number of blocks the connected peers need to be ahead of the node by for it to be considered out of sync.
+pub const OUT_OF_SYNC_BLOCK_THRESHOLD: u32 = 50;
+
+/// The minimum number of peers whose clocks need to be sampled for the network-adjusted time to deviate from
+/// the node's own time.
+pub const MIN_CLOCK_SAMPLES: usize = 5;
+/// The maximum number of peers whose clock samples are kept; the ones sampled the longest ago are dropped.
+pub const MAX_CLOCK_SAMPLES: usize = 200;
+/// The age after which a peer's clock sample expires; the connected peers renew theirs with each `Ping`.
+pub const MAX_CLOCK_SAMPLE_AGE_SECS: u16 = 10 * 60;
+/// The offset of the network's time from the node's clock above which the node warns about its clock.
+pub const CLOCK_SKEW_WARNING_SECS: u16 = 60;
+/// The maximum offset applied to the node's clock in order to obtain the network-adjusted time; larger
+/// offsets aren't applied, since blocks timestamped more than 2 hours into the future are rejected anyway.
+pub const MAX_CLOCK_ADJUSTMENT_SECS: u16 = 70 * 60;
+
+/// The interval between each evaluation of the configured alarm rules.
+pub const ALARM_CHECK_INTERVAL_SECS: u8 = 30;
+/// The number of alarm events a subscriber can lag behind by before missing some of them.
+pub const ALARM_EVENT_CHANNEL_DEPTH: usize = 64;
+
+/// The amount of time the crawler waits for its peers to respond with their own peers before
+/// dropping the connections; it should be lower than the `peer_sync_interval`.
+#[cfg(feature = "crawler")]
+pub const CRAWLER_RESPONSE_TIMEOUT_SECS: u8 = 5;
+/// The amount of time after which a connection that hasn't been reported again is dropped
+/// from the crawler's view of the network.
+#[cfg(feature = "crawler")]
+pub const KNOWN_NETWORK_CONNECTION_TTL_SECS: u16 = 3600;
+/// The number of most recent connection attempts the crawler takes into account when estimating
+/// the responsiveness of a node.
+#[cfg(feature = "crawler")]
+pub const CRAWL_ATTEMPT_HISTORY_LEN: usize = 16;
+/// The interval at which the crawler stores a snapshot of its view of the network, for historical analysis.
+#[cfg(feature = "crawler")]
+pub const NETWORK_SNAPSHOT_INTERVAL_SECS: u32 = 3600;
+/// The amount of time the crawler keeps the snapshots of the network for.
+#[cfg(feature = "crawler")]
+pub const NETWORK_SNAPSHOT_RETENTION_SECS: u32 = 30 * 24 * 3600;
 
 /// The noise handshake pattern.
 pub const HANDSHAKE_PATTERN: &str = "Noise_XXpsk3_25519_ChaChaPoly_SHA256";
@@ -60,6 +242,11 @@ pub const HANDSHAKE_PSK: &[u8] = b"b765e427e836e0029a1e2a22ba60c52a"; // the PSK
 pub const NOISE_BUF_LEN: usize = 65535;
 /// The spec-compliant size of the noise tag field.
 pub const NOISE_TAG_LEN: usize = 16;
+/// The amount of time after which the key used to encrypt the payloads sent to a peer is replaced.
+pub const REKEY_INTERVAL_SECS: u16 = 3600;
+/// The number of bytes encrypted with a single key after which the key used to encrypt the payloads sent
+/// to a peer is replaced.
+pub const REKEY_BYTES_THRESHOLD: u64 = 1024 * 1024 * 1024; // 1GiB
 
 /// The maximum amount of time in which a handshake with a bootnode can conclude before dropping the
 /// connection; it should be no greater than the `peer_sync_interval`.
@@ -67,14 +254,104 @@ pub const HANDSHAKE_BOOTNODE_TIMEOUT_SECS: u8 = 10;
 /// The maximum amount of time in which a handshake with a regular node can conclude before dropping the
 /// connection; it should be no greater than the `peer_sync_interval`.
 pub const HANDSHAKE_PEER_TIMEOUT_SECS: u8 = 5;
+/// The amount of time after which a management client using the RPC over the P2P port is disconnected from if
+/// it hasn't sent any requests in the meantime.
+pub const RPC_CLIENT_IDLE_TIMEOUT_SECS: u16 = 600;
 /// The amount of time after which a peer will be considered inactive an disconnected from if they have
 /// not sent any messages in the meantime.
 pub const MAX_PEER_INACTIVITY_SECS: u8 = 30;
+/// The default number of connection slots reserved for the peers this node connects to, so that the peers connecting
+/// to it can't occupy all of them and eclipse it.
+pub const RESERVED_OUTBOUND_SLOTS: u16 = 8;
+/// The default maximum number of outbound handshakes performed at the same time, so that a long list of peers
+/// to connect to doesn't exhaust the node's file descriptors.
+pub const MAX_CONCURRENT_HANDSHAKES: usize = 10;
+/// The number of reachability probes of gossiped addresses performed at the same time.
+pub const REACHABILITY_PROBE_WORKERS: usize = 8;
+/// The maximum number of gossiped addresses awaiting a reachability probe; any further ones aren't probed.
+pub const REACHABILITY_PROBE_QUEUE_DEPTH: usize = 256;
+/// The time a gossiped address has to accept a TCP connection within to pass its reachability probe.
+pub const REACHABILITY_PROBE_TIMEOUT_MS: u64 = 2000;
+/// The interval between the `Ping` messages sent to the connected peers; it is independent of the peer syncs, as
+/// it needs to stay well below `MAX_PEER_INACTIVITY_SECS`.
+pub const PEER_PING_INTERVAL_SECS: u8 = 10;
+/// The maximum number of times the interval between peer syncs is doubled while the set of connected peers
+/// stays the same.
+pub const MAX_PEER_SYNC_BACKOFF: u32 = 2;
+/// The maximum deviation of the interval between peer syncs from its base value, as a percentage of the latter.
+pub const PEER_SYNC_JITTER_PERCENT: u8 = 20;
+/// The number of most recent round-trip time samples kept for every peer.
+pub const MAX_RTT_SAMPLES: usize = 64;
+/// The number of most recent misbehavior events kept for every peer.
+pub const MAX_MISBEHAVIOR_EVENTS: usize = 32;
+
+/// The interval between checks of whether the local IP of the node has changed.
+pub const LOCAL_ADDRESS_CHECK_INTERVAL_SECS: u8 = 30;
 
 /// The maximum size of a message that can be transmitted in the network.
 pub const MAX_MESSAGE_SIZE: usize = 8 * 1024 * 1024; // 8MiB
 /// The maximum number of peers shared at once in response to a `GetPeers` message.
 pub const SHARED_PEER_COUNT: usize = 25;
+/// The number of `Peers` messages with addresses disallowed by the gossip port policy a peer can send
+/// before it is penalized for them.
+pub const OUT_OF_POLICY_GOSSIP_TOLERANCE: u8 = 3;
+/// The number of `GetBlocks` and `GetSync` requests exceeding its serving quota a peer can send within a single
+/// window of the quota before it is penalized for them.
+pub const SYNC_QUOTA_ABUSE_TOLERANCE: u8 = 3;
+/// The maximum number of announced blocks served to a peer in response to its `GetBlock` requests within a single
+/// window of its serving quota; they're counted apart from the blocks served while syncing.
+pub const MAX_ANNOUNCED_BLOCKS_SERVED: u32 = 32;
+/// The maximum size of a `Transaction` payload.
+pub const MAX_TRANSACTION_PAYLOAD_SIZE: usize = 1024 * 1024; // 1MiB
+/// The maximum size of the payloads with lists of block hashes, i.e. `GetBlocks`, `GetSync` and `Sync`.
+pub const MAX_BLOCK_HASHES_PAYLOAD_SIZE: usize = 64 * 1024; // 64KiB
+/// The maximum size of a `Peers` payload; it should accommodate `SHARED_PEER_COUNT` addresses.
+pub const MAX_PEERS_PAYLOAD_SIZE: usize = 4 * 1024; // 4KiB
+/// The maximum size of the payloads carrying little or no data, e.g. `Ping` or `BlockHash`.
+pub const MAX_CONTROL_PAYLOAD_SIZE: usize = 1024; // 1KiB
+
+/// The number of buckets in the address manager's table of addresses that weren't connected to yet.
+pub const NEW_ADDRESS_BUCKETS: usize = 64;
+/// The number of buckets in the new table a single source network group can place addresses in.
+pub const NEW_BUCKETS_PER_SOURCE_GROUP: u64 = 8;
+/// The number of buckets in the address manager's table of addresses that were successfully connected to.
+pub const TRIED_ADDRESS_BUCKETS: usize = 16;
+/// The number of buckets in the tried table the addresses of a single network group can be placed in.
+pub const TRIED_BUCKETS_PER_GROUP: u64 = 4;
+/// The maximum number of addresses in a single bucket of the address manager.
+pub const ADDRESS_BUCKET_SIZE: usize = 32;
+/// The number of failed connection attempts after which an address that was never connected to can be evicted.
+pub const MAX_ADDRESS_FAILURES: u32 = 3;
+/// The amount of time after which an address that wasn't successfully connected to can be evicted.
+pub const ADDRESS_HORIZON_SECS: u32 = 30 * 24 * 3600;
+/// The default maximum number of disconnected peers retained in the peer book.
+pub const MAX_DISCONNECTED_PEERS: usize = 2048;
+/// The default number of consecutive failed connection attempts after which a disconnected peer is forgotten.
+pub const MAX_PEER_FAILURES: u32 = 10;
+/// The maximum number of peers the node's operator can annotate.
+pub const MAX_ANNOTATED_PEERS: usize = 1024;
+/// The maximum number of labels attached to a single peer.
+pub const MAX_PEER_LABELS: usize = 8;
+/// The maximum length of a single peer label.
+pub const MAX_PEER_LABEL_LEN: usize = 32;
+/// The maximum length of the note attached to a peer.
+pub const MAX_PEER_NOTE_LEN: usize = 256;
+
+/// The maximum number of peers in a list of recommended peers.
+pub const MAX_RECOMMENDED_PEERS: usize = 64;
+/// The age above which a list of recommended peers is considered stale and rejected.
+pub const RECOMMENDED_PEERS_MAX_AGE_SECS: u32 = 7 * 24 * 3600;
+/// The minimum interval between each fetch of the recommended peers by a node without any peers, and between
+/// each export of the list by a crawler.
+pub const RECOMMENDED_PEERS_REFRESH_INTERVAL_SECS: u16 = 600;
+/// The maximum amount of time fetching the recommended peers from a URL can take.
+pub const RECOMMENDED_PEERS_FETCH_TIMEOUT_SECS: u8 = 10;
+/// The minimum fraction of the recent crawl attempts a node needs to have responded to in order to be recommended.
+#[cfg(feature = "crawler")]
+pub const RECOMMENDED_PEER_MIN_RESPONSIVENESS: f64 = 0.75;
+/// The minimum amount of time a node needs to have been continuously reachable for in order to be recommended.
+#[cfg(feature = "crawler")]
+pub const RECOMMENDED_PEER_MIN_UPTIME_SECS: u16 = 3600;
 
 /// The depth of the common inbound channel.
 pub const INBOUND_CHANNEL_DEPTH: usize = 16 * 1024;
@@ -85,6 +362,14 @@ pub const OUTBOUND_CHANNEL_DEPTH: usize = 1024;
 /// FIXME: probably doesn't need to be a u64, could also be more informative than just a number
 // TODO (raychu86): Establish a formal node version.
 pub const PROTOCOL_VERSION: u64 = 2;
+/// The earliest version of the network protocol that is still compatible with this node; peers
+/// running an older version are rejected during the handshake.
+pub const MIN_PROTOCOL_VERSION: u64 = 2;
+/// The optional protocol extensions this node supports.
+pub const SUPPORTED_FEATURES: Features = Features::NODE_ALIAS
+    .union(Features::COMPACT_BLOCKS)
+    .union(Features::REKEY)
+    .union(Features::ADDRESS_CHANGE);
 
 pub(crate) type Sender = tokio::sync::mpsc::Sender<Message>;
 
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use snarkos_storage::BlockHeight;
+use crate::{AddressChange, BlockHeight, CorrelationId, NodeAlias};
 use snarkvm_dpc::BlockHeaderHash;
 
 use std::{fmt, net::SocketAddr};
@@ -38,11 +38,17 @@ impl fmt::Display for Direction {
 pub struct Message {
     pub direction: Direction,
     pub payload: Payload,
+    /// The ID of the request/response pair the message belongs to, if any.
+    pub correlation: Option<CorrelationId>,
 }
 
 impl Message {
     pub fn new(direction: Direction, payload: Payload) -> Self {
-        Self { direction, payload }
+        Self {
+            direction,
+            payload,
+            correlation: None,
+        }
     }
 
     pub fn receiver(&self) -> SocketAddr {
@@ -59,6 +65,44 @@ impl fmt::Display for Message {
     }
 }
 
+/// The flags of a payload, which tell a receiver that doesn't know the payload's type how to treat it. Together
+/// with the type of the payload and the length of the frame carrying it, they let the payloads introduced by newer
+/// versions of the protocol be skipped by the older nodes, so that the upgrades can be rolled out gradually.
+#[derive(Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub struct PayloadFlags(u16);
+
+impl PayloadFlags {
+    /// The payload can't be skipped: a receiver that doesn't know its type disconnects instead, as it wouldn't be
+    /// able to follow the rest of the conversation, e.g. if the payload changes the state of the connection.
+    pub const CRITICAL: PayloadFlags = PayloadFlags(1 << 0);
+
+    /// Returns a set containing no flags.
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Returns the set corresponding to the given bitfield.
+    pub const fn from_bits(bits: u16) -> Self {
+        Self(bits)
+    }
+
+    /// Returns the bitfield representation of the set.
+    pub const fn bits(self) -> u16 {
+        self.0
+    }
+
+    /// Returns `true` if all the given flags are in the set.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl fmt::Debug for PayloadFlags {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PayloadFlags({:#b})", self.0)
+    }
+}
+
 /// The actual message transmitted over the network.
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum Payload {
@@ -85,7 +129,7 @@ pub enum Payload {
     Peers(Vec<SocketAddr>),
     // todo: readd in Rust 1.54
     // #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/ping.md"))]
-    Ping(BlockHeight),
+    Ping(BlockHeight, i64),
     // todo: readd in Rust 1.54
     // #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/pong.md"))]
     Pong,
@@ -98,10 +142,46 @@ pub enum Payload {
     // todo: readd in Rust 1.54
     // #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/transaction.md"))]
     Transaction(Vec<u8>),
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/node_alias.md"))]
+    NodeAlias(NodeAlias),
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/block_hash.md"))]
+    BlockHash(BlockHeaderHash),
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/get_block.md"))]
+    GetBlock(BlockHeaderHash),
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/rekey.md"))]
+    Rekey,
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/rpc_auth.md"))]
+    RpcAuth(Vec<u8>),
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/rpc_request.md"))]
+    RpcRequest(Vec<u8>),
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/rpc_response.md"))]
+    RpcResponse(Vec<u8>),
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/address_change.md"))]
+    AddressChange(AddressChange),
 
-    // a placeholder indicating the introduction of a new payload type; used for forward compatibility
+    // a placeholder indicating the introduction of a new payload type, with its type id and flags; used for
+    // forward compatibility
     #[doc(hidden)]
-    Unknown,
+    Unknown(u16, PayloadFlags),
+}
+
+impl Payload {
+    /// Returns the flags the payload is sent with. The known payloads are only sent to the peers that support
+    /// them, so they don't need any; the new ones that aren't negotiated as a `Features` extension may.
+    pub fn flags(&self) -> PayloadFlags {
+        match self {
+            Self::Unknown(_, flags) => *flags,
+            _ => PayloadFlags::empty(),
+        }
+    }
 }
 
 impl fmt::Display for Payload {
@@ -119,7 +199,15 @@ impl fmt::Display for Payload {
             Self::Sync(..) => "sync",
             Self::SyncBlock(..) => "syncblock",
             Self::Transaction(..) => "transaction",
-            Self::Unknown => "unknown",
+            Self::NodeAlias(..) => "nodealias",
+            Self::BlockHash(..) => "blockhash",
+            Self::GetBlock(..) => "getblock",
+            Self::Rekey => "rekey",
+            Self::RpcAuth(..) => "rpcauth",
+            Self::RpcRequest(..) => "rpcrequest",
+            Self::RpcResponse(..) => "rpcresponse",
+            Self::AddressChange(..) => "addresschange",
+            Self::Unknown(..) => "unknown",
         };
 
         f.write_str(str)
@@ -1162,6 +1162,185 @@ pub mod transaction {
   }
 }
 
+pub mod node_alias {
+  #[derive(Copy, Clone)]
+  pub struct Owned(());
+  impl <'a> ::capnp::traits::Owned<'a> for Owned { type Reader = Reader<'a>; type Builder = Builder<'a>; }
+  impl <'a> ::capnp::traits::OwnedStruct<'a> for Owned { type Reader = Reader<'a>; type Builder = Builder<'a>; }
+  impl ::capnp::traits::Pipelined for Owned { type Pipeline = Pipeline; }
+
+  #[derive(Clone, Copy)]
+  pub struct Reader<'a> { reader: ::capnp::private::layout::StructReader<'a> }
+
+  impl <'a,> ::capnp::traits::HasTypeId for Reader<'a,>  {
+    #[inline]
+    fn type_id() -> u64 { _private::TYPE_ID }
+  }
+  impl <'a,> ::capnp::traits::FromStructReader<'a> for Reader<'a,>  {
+    fn new(reader: ::capnp::private::layout::StructReader<'a>) -> Reader<'a,> {
+      Reader { reader,  }
+    }
+  }
+
+  impl <'a,> ::capnp::traits::FromPointerReader<'a> for Reader<'a,>  {
+    fn get_from_pointer(reader: &::capnp::private::layout::PointerReader<'a>, default: ::core::option::Option<&'a [capnp::Word]>) -> ::capnp::Result<Reader<'a,>> {
+      ::core::result::Result::Ok(::capnp::traits::FromStructReader::new(reader.get_struct(default)?))
+    }
+  }
+
+  impl <'a,> ::capnp::traits::IntoInternalStructReader<'a> for Reader<'a,>  {
+    fn into_internal_struct_reader(self) -> ::capnp::private::layout::StructReader<'a> {
+      self.reader
+    }
+  }
+
+  impl <'a,> ::capnp::traits::Imbue<'a> for Reader<'a,>  {
+    fn imbue(&mut self, cap_table: &'a ::capnp::private::layout::CapTable) {
+      self.reader.imbue(::capnp::private::layout::CapTableReader::Plain(cap_table))
+    }
+  }
+
+  impl <'a,> Reader<'a,>  {
+    pub fn reborrow(&self) -> Reader<'_,> {
+      Reader { .. *self }
+    }
+
+    pub fn total_size(&self) -> ::capnp::Result<::capnp::MessageSize> {
+      self.reader.total_size()
+    }
+    #[inline]
+    pub fn get_alias(self) -> ::capnp::Result<::capnp::text::Reader<'a>> {
+      ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
+    }
+    pub fn has_alias(&self) -> bool {
+      !self.reader.get_pointer_field(0).is_null()
+    }
+    #[inline]
+    pub fn get_public_key(self) -> ::capnp::Result<::capnp::data::Reader<'a>> {
+      ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(1), ::core::option::Option::None)
+    }
+    pub fn has_public_key(&self) -> bool {
+      !self.reader.get_pointer_field(1).is_null()
+    }
+    #[inline]
+    pub fn get_signature(self) -> ::capnp::Result<::capnp::data::Reader<'a>> {
+      ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(2), ::core::option::Option::None)
+    }
+    pub fn has_signature(&self) -> bool {
+      !self.reader.get_pointer_field(2).is_null()
+    }
+  }
+
+  pub struct Builder<'a> { builder: ::capnp::private::layout::StructBuilder<'a> }
+  impl <'a,> ::capnp::traits::HasStructSize for Builder<'a,>  {
+    #[inline]
+    fn struct_size() -> ::capnp::private::layout::StructSize { _private::STRUCT_SIZE }
+  }
+  impl <'a,> ::capnp::traits::HasTypeId for Builder<'a,>  {
+    #[inline]
+    fn type_id() -> u64 { _private::TYPE_ID }
+  }
+  impl <'a,> ::capnp::traits::FromStructBuilder<'a> for Builder<'a,>  {
+    fn new(builder: ::capnp::private::layout::StructBuilder<'a>) -> Builder<'a, > {
+      Builder { builder,  }
+    }
+  }
+
+  impl <'a,> ::capnp::traits::ImbueMut<'a> for Builder<'a,>  {
+    fn imbue_mut(&mut self, cap_table: &'a mut ::capnp::private::layout::CapTable) {
+      self.builder.imbue(::capnp::private::layout::CapTableBuilder::Plain(cap_table))
+    }
+  }
+
+  impl <'a,> ::capnp::traits::FromPointerBuilder<'a> for Builder<'a,>  {
+    fn init_pointer(builder: ::capnp::private::layout::PointerBuilder<'a>, _size: u32) -> Builder<'a,> {
+      ::capnp::traits::FromStructBuilder::new(builder.init_struct(_private::STRUCT_SIZE))
+    }
+    fn get_from_pointer(builder: ::capnp::private::layout::PointerBuilder<'a>, default: ::core::option::Option<&'a [capnp::Word]>) -> ::capnp::Result<Builder<'a,>> {
+      ::core::result::Result::Ok(::capnp::traits::FromStructBuilder::new(builder.get_struct(_private::STRUCT_SIZE, default)?))
+    }
+  }
+
+  impl <'a,> ::capnp::traits::SetPointerBuilder for Reader<'a,>  {
+    fn set_pointer_builder<'b>(pointer: ::capnp::private::layout::PointerBuilder<'b>, value: Reader<'a,>, canonicalize: bool) -> ::capnp::Result<()> { pointer.set_struct(&value.reader, canonicalize) }
+  }
+
+  impl <'a,> Builder<'a,>  {
+    pub fn into_reader(self) -> Reader<'a,> {
+      ::capnp::traits::FromStructReader::new(self.builder.into_reader())
+    }
+    pub fn reborrow(&mut self) -> Builder<'_,> {
+      Builder { .. *self }
+    }
+    pub fn reborrow_as_reader(&self) -> Reader<'_,> {
+      ::capnp::traits::FromStructReader::new(self.builder.into_reader())
+    }
+
+    pub fn total_size(&self) -> ::capnp::Result<::capnp::MessageSize> {
+      self.builder.into_reader().total_size()
+    }
+    #[inline]
+    pub fn get_alias(self) -> ::capnp::Result<::capnp::text::Builder<'a>> {
+      ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
+    }
+    #[inline]
+    pub fn set_alias(&mut self, value: ::capnp::text::Reader<'_>)  {
+      self.builder.get_pointer_field(0).set_text(value);
+    }
+    #[inline]
+    pub fn init_alias(self, size: u32) -> ::capnp::text::Builder<'a> {
+      self.builder.get_pointer_field(0).init_text(size)
+    }
+    pub fn has_alias(&self) -> bool {
+      !self.builder.get_pointer_field(0).is_null()
+    }
+    #[inline]
+    pub fn get_public_key(self) -> ::capnp::Result<::capnp::data::Builder<'a>> {
+      ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(1), ::core::option::Option::None)
+    }
+    #[inline]
+    pub fn set_public_key(&mut self, value: ::capnp::data::Reader<'_>)  {
+      self.builder.get_pointer_field(1).set_data(value);
+    }
+    #[inline]
+    pub fn init_public_key(self, size: u32) -> ::capnp::data::Builder<'a> {
+      self.builder.get_pointer_field(1).init_data(size)
+    }
+    pub fn has_public_key(&self) -> bool {
+      !self.builder.get_pointer_field(1).is_null()
+    }
+    #[inline]
+    pub fn get_signature(self) -> ::capnp::Result<::capnp::data::Builder<'a>> {
+      ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(2), ::core::option::Option::None)
+    }
+    #[inline]
+    pub fn set_signature(&mut self, value: ::capnp::data::Reader<'_>)  {
+      self.builder.get_pointer_field(2).set_data(value);
+    }
+    #[inline]
+    pub fn init_signature(self, size: u32) -> ::capnp::data::Builder<'a> {
+      self.builder.get_pointer_field(2).init_data(size)
+    }
+    pub fn has_signature(&self) -> bool {
+      !self.builder.get_pointer_field(2).is_null()
+    }
+  }
+
+  pub struct Pipeline { _typeless: ::capnp::any_pointer::Pipeline }
+  impl ::capnp::capability::FromTypelessPipeline for Pipeline {
+    fn new(typeless: ::capnp::any_pointer::Pipeline) -> Pipeline {
+      Pipeline { _typeless: typeless,  }
+    }
+  }
+  impl Pipeline  {
+  }
+  mod _private {
+    use capnp::private::layout;
+    pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 0, pointers: 3 };
+    pub const TYPE_ID: u64 = 0xe3a1_5c7d_9b42_f086;
+  }
+}
+
 pub mod block {
   #[derive(Copy, Clone)]
   pub struct Owned(());
@@ -1347,6 +1526,10 @@ pub mod ping {
     pub fn get_block_height(self) -> u32 {
       self.reader.get_data_field::<u32>(0)
     }
+    #[inline]
+    pub fn get_timestamp(self) -> i64 {
+      self.reader.get_data_field::<i64>(1)
+    }
   }
 
   pub struct Builder<'a> { builder: ::capnp::private::layout::StructBuilder<'a> }
@@ -1405,6 +1588,14 @@ pub mod ping {
     pub fn set_block_height(&mut self, value: u32)  {
       self.builder.set_data_field::<u32>(0, value);
     }
+    #[inline]
+    pub fn get_timestamp(self) -> i64 {
+      self.builder.get_data_field::<i64>(1)
+    }
+    #[inline]
+    pub fn set_timestamp(&mut self, value: i64)  {
+      self.builder.set_data_field::<i64>(1, value);
+    }
   }
 
   pub struct Pipeline { _typeless: ::capnp::any_pointer::Pipeline }
@@ -1417,12 +1608,384 @@ pub mod ping {
   }
   mod _private {
     use capnp::private::layout;
-    pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 1, pointers: 0 };
+    pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 2, pointers: 0 };
     pub const TYPE_ID: u64 = 0x87ca_2c1e_0607_67fe;
   }
 }
 
-pub mod get_memory_pool {
+pub mod get_memory_pool {
+  #[derive(Copy, Clone)]
+  pub struct Owned(());
+  impl <'a> ::capnp::traits::Owned<'a> for Owned { type Reader = Reader<'a>; type Builder = Builder<'a>; }
+  impl <'a> ::capnp::traits::OwnedStruct<'a> for Owned { type Reader = Reader<'a>; type Builder = Builder<'a>; }
+  impl ::capnp::traits::Pipelined for Owned { type Pipeline = Pipeline; }
+
+  #[derive(Clone, Copy)]
+  pub struct Reader<'a> { reader: ::capnp::private::layout::StructReader<'a> }
+
+  impl <'a,> ::capnp::traits::HasTypeId for Reader<'a,>  {
+    #[inline]
+    fn type_id() -> u64 { _private::TYPE_ID }
+  }
+  impl <'a,> ::capnp::traits::FromStructReader<'a> for Reader<'a,>  {
+    fn new(reader: ::capnp::private::layout::StructReader<'a>) -> Reader<'a,> {
+      Reader { reader,  }
+    }
+  }
+
+  impl <'a,> ::capnp::traits::FromPointerReader<'a> for Reader<'a,>  {
+    fn get_from_pointer(reader: &::capnp::private::layout::PointerReader<'a>, default: ::core::option::Option<&'a [capnp::Word]>) -> ::capnp::Result<Reader<'a,>> {
+      ::core::result::Result::Ok(::capnp::traits::FromStructReader::new(reader.get_struct(default)?))
+    }
+  }
+
+  impl <'a,> ::capnp::traits::IntoInternalStructReader<'a> for Reader<'a,>  {
+    fn into_internal_struct_reader(self) -> ::capnp::private::layout::StructReader<'a> {
+      self.reader
+    }
+  }
+
+  impl <'a,> ::capnp::traits::Imbue<'a> for Reader<'a,>  {
+    fn imbue(&mut self, cap_table: &'a ::capnp::private::layout::CapTable) {
+      self.reader.imbue(::capnp::private::layout::CapTableReader::Plain(cap_table))
+    }
+  }
+
+  impl <'a,> Reader<'a,>  {
+    pub fn reborrow(&self) -> Reader<'_,> {
+      Reader { .. *self }
+    }
+
+    pub fn total_size(&self) -> ::capnp::Result<::capnp::MessageSize> {
+      self.reader.total_size()
+    }
+    #[inline]
+    pub fn get_placeholder(self)  {
+      
+    }
+  }
+
+  pub struct Builder<'a> { builder: ::capnp::private::layout::StructBuilder<'a> }
+  impl <'a,> ::capnp::traits::HasStructSize for Builder<'a,>  {
+    #[inline]
+    fn struct_size() -> ::capnp::private::layout::StructSize { _private::STRUCT_SIZE }
+  }
+  impl <'a,> ::capnp::traits::HasTypeId for Builder<'a,>  {
+    #[inline]
+    fn type_id() -> u64 { _private::TYPE_ID }
+  }
+  impl <'a,> ::capnp::traits::FromStructBuilder<'a> for Builder<'a,>  {
+    fn new(builder: ::capnp::private::layout::StructBuilder<'a>) -> Builder<'a, > {
+      Builder { builder,  }
+    }
+  }
+
+  impl <'a,> ::capnp::traits::ImbueMut<'a> for Builder<'a,>  {
+    fn imbue_mut(&mut self, cap_table: &'a mut ::capnp::private::layout::CapTable) {
+      self.builder.imbue(::capnp::private::layout::CapTableBuilder::Plain(cap_table))
+    }
+  }
+
+  impl <'a,> ::capnp::traits::FromPointerBuilder<'a> for Builder<'a,>  {
+    fn init_pointer(builder: ::capnp::private::layout::PointerBuilder<'a>, _size: u32) -> Builder<'a,> {
+      ::capnp::traits::FromStructBuilder::new(builder.init_struct(_private::STRUCT_SIZE))
+    }
+    fn get_from_pointer(builder: ::capnp::private::layout::PointerBuilder<'a>, default: ::core::option::Option<&'a [capnp::Word]>) -> ::capnp::Result<Builder<'a,>> {
+      ::core::result::Result::Ok(::capnp::traits::FromStructBuilder::new(builder.get_struct(_private::STRUCT_SIZE, default)?))
+    }
+  }
+
+  impl <'a,> ::capnp::traits::SetPointerBuilder for Reader<'a,>  {
+    fn set_pointer_builder<'b>(pointer: ::capnp::private::layout::PointerBuilder<'b>, value: Reader<'a,>, canonicalize: bool) -> ::capnp::Result<()> { pointer.set_struct(&value.reader, canonicalize) }
+  }
+
+  impl <'a,> Builder<'a,>  {
+    pub fn into_reader(self) -> Reader<'a,> {
+      ::capnp::traits::FromStructReader::new(self.builder.into_reader())
+    }
+    pub fn reborrow(&mut self) -> Builder<'_,> {
+      Builder { .. *self }
+    }
+    pub fn reborrow_as_reader(&self) -> Reader<'_,> {
+      ::capnp::traits::FromStructReader::new(self.builder.into_reader())
+    }
+
+    pub fn total_size(&self) -> ::capnp::Result<::capnp::MessageSize> {
+      self.builder.into_reader().total_size()
+    }
+    #[inline]
+    pub fn get_placeholder(self)  {
+      
+    }
+    #[inline]
+    pub fn set_placeholder(&mut self, _value: ())  {
+    }
+  }
+
+  pub struct Pipeline { _typeless: ::capnp::any_pointer::Pipeline }
+  impl ::capnp::capability::FromTypelessPipeline for Pipeline {
+    fn new(typeless: ::capnp::any_pointer::Pipeline) -> Pipeline {
+      Pipeline { _typeless: typeless,  }
+    }
+  }
+  impl Pipeline  {
+  }
+  mod _private {
+    use capnp::private::layout;
+    pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 0, pointers: 0 };
+    pub const TYPE_ID: u64 = 0xfba1_7045_02d0_44f3;
+  }
+}
+
+pub mod get_peers {
+  #[derive(Copy, Clone)]
+  pub struct Owned(());
+  impl <'a> ::capnp::traits::Owned<'a> for Owned { type Reader = Reader<'a>; type Builder = Builder<'a>; }
+  impl <'a> ::capnp::traits::OwnedStruct<'a> for Owned { type Reader = Reader<'a>; type Builder = Builder<'a>; }
+  impl ::capnp::traits::Pipelined for Owned { type Pipeline = Pipeline; }
+
+  #[derive(Clone, Copy)]
+  pub struct Reader<'a> { reader: ::capnp::private::layout::StructReader<'a> }
+
+  impl <'a,> ::capnp::traits::HasTypeId for Reader<'a,>  {
+    #[inline]
+    fn type_id() -> u64 { _private::TYPE_ID }
+  }
+  impl <'a,> ::capnp::traits::FromStructReader<'a> for Reader<'a,>  {
+    fn new(reader: ::capnp::private::layout::StructReader<'a>) -> Reader<'a,> {
+      Reader { reader,  }
+    }
+  }
+
+  impl <'a,> ::capnp::traits::FromPointerReader<'a> for Reader<'a,>  {
+    fn get_from_pointer(reader: &::capnp::private::layout::PointerReader<'a>, default: ::core::option::Option<&'a [capnp::Word]>) -> ::capnp::Result<Reader<'a,>> {
+      ::core::result::Result::Ok(::capnp::traits::FromStructReader::new(reader.get_struct(default)?))
+    }
+  }
+
+  impl <'a,> ::capnp::traits::IntoInternalStructReader<'a> for Reader<'a,>  {
+    fn into_internal_struct_reader(self) -> ::capnp::private::layout::StructReader<'a> {
+      self.reader
+    }
+  }
+
+  impl <'a,> ::capnp::traits::Imbue<'a> for Reader<'a,>  {
+    fn imbue(&mut self, cap_table: &'a ::capnp::private::layout::CapTable) {
+      self.reader.imbue(::capnp::private::layout::CapTableReader::Plain(cap_table))
+    }
+  }
+
+  impl <'a,> Reader<'a,>  {
+    pub fn reborrow(&self) -> Reader<'_,> {
+      Reader { .. *self }
+    }
+
+    pub fn total_size(&self) -> ::capnp::Result<::capnp::MessageSize> {
+      self.reader.total_size()
+    }
+    #[inline]
+    pub fn get_placeholder(self)  {
+      
+    }
+  }
+
+  pub struct Builder<'a> { builder: ::capnp::private::layout::StructBuilder<'a> }
+  impl <'a,> ::capnp::traits::HasStructSize for Builder<'a,>  {
+    #[inline]
+    fn struct_size() -> ::capnp::private::layout::StructSize { _private::STRUCT_SIZE }
+  }
+  impl <'a,> ::capnp::traits::HasTypeId for Builder<'a,>  {
+    #[inline]
+    fn type_id() -> u64 { _private::TYPE_ID }
+  }
+  impl <'a,> ::capnp::traits::FromStructBuilder<'a> for Builder<'a,>  {
+    fn new(builder: ::capnp::private::layout::StructBuilder<'a>) -> Builder<'a, > {
+      Builder { builder,  }
+    }
+  }
+
+  impl <'a,> ::capnp::traits::ImbueMut<'a> for Builder<'a,>  {
+    fn imbue_mut(&mut self, cap_table: &'a mut ::capnp::private::layout::CapTable) {
+      self.builder.imbue(::capnp::private::layout::CapTableBuilder::Plain(cap_table))
+    }
+  }
+
+  impl <'a,> ::capnp::traits::FromPointerBuilder<'a> for Builder<'a,>  {
+    fn init_pointer(builder: ::capnp::private::layout::PointerBuilder<'a>, _size: u32) -> Builder<'a,> {
+      ::capnp::traits::FromStructBuilder::new(builder.init_struct(_private::STRUCT_SIZE))
+    }
+    fn get_from_pointer(builder: ::capnp::private::layout::PointerBuilder<'a>, default: ::core::option::Option<&'a [capnp::Word]>) -> ::capnp::Result<Builder<'a,>> {
+      ::core::result::Result::Ok(::capnp::traits::FromStructBuilder::new(builder.get_struct(_private::STRUCT_SIZE, default)?))
+    }
+  }
+
+  impl <'a,> ::capnp::traits::SetPointerBuilder for Reader<'a,>  {
+    fn set_pointer_builder<'b>(pointer: ::capnp::private::layout::PointerBuilder<'b>, value: Reader<'a,>, canonicalize: bool) -> ::capnp::Result<()> { pointer.set_struct(&value.reader, canonicalize) }
+  }
+
+  impl <'a,> Builder<'a,>  {
+    pub fn into_reader(self) -> Reader<'a,> {
+      ::capnp::traits::FromStructReader::new(self.builder.into_reader())
+    }
+    pub fn reborrow(&mut self) -> Builder<'_,> {
+      Builder { .. *self }
+    }
+    pub fn reborrow_as_reader(&self) -> Reader<'_,> {
+      ::capnp::traits::FromStructReader::new(self.builder.into_reader())
+    }
+
+    pub fn total_size(&self) -> ::capnp::Result<::capnp::MessageSize> {
+      self.builder.into_reader().total_size()
+    }
+    #[inline]
+    pub fn get_placeholder(self)  {
+      
+    }
+    #[inline]
+    pub fn set_placeholder(&mut self, _value: ())  {
+    }
+  }
+
+  pub struct Pipeline { _typeless: ::capnp::any_pointer::Pipeline }
+  impl ::capnp::capability::FromTypelessPipeline for Pipeline {
+    fn new(typeless: ::capnp::any_pointer::Pipeline) -> Pipeline {
+      Pipeline { _typeless: typeless,  }
+    }
+  }
+  impl Pipeline  {
+  }
+  mod _private {
+    use capnp::private::layout;
+    pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 0, pointers: 0 };
+    pub const TYPE_ID: u64 = 0xc075_4205_a0c7_dafa;
+  }
+}
+
+pub mod pong {
+  #[derive(Copy, Clone)]
+  pub struct Owned(());
+  impl <'a> ::capnp::traits::Owned<'a> for Owned { type Reader = Reader<'a>; type Builder = Builder<'a>; }
+  impl <'a> ::capnp::traits::OwnedStruct<'a> for Owned { type Reader = Reader<'a>; type Builder = Builder<'a>; }
+  impl ::capnp::traits::Pipelined for Owned { type Pipeline = Pipeline; }
+
+  #[derive(Clone, Copy)]
+  pub struct Reader<'a> { reader: ::capnp::private::layout::StructReader<'a> }
+
+  impl <'a,> ::capnp::traits::HasTypeId for Reader<'a,>  {
+    #[inline]
+    fn type_id() -> u64 { _private::TYPE_ID }
+  }
+  impl <'a,> ::capnp::traits::FromStructReader<'a> for Reader<'a,>  {
+    fn new(reader: ::capnp::private::layout::StructReader<'a>) -> Reader<'a,> {
+      Reader { reader,  }
+    }
+  }
+
+  impl <'a,> ::capnp::traits::FromPointerReader<'a> for Reader<'a,>  {
+    fn get_from_pointer(reader: &::capnp::private::layout::PointerReader<'a>, default: ::core::option::Option<&'a [capnp::Word]>) -> ::capnp::Result<Reader<'a,>> {
+      ::core::result::Result::Ok(::capnp::traits::FromStructReader::new(reader.get_struct(default)?))
+    }
+  }
+
+  impl <'a,> ::capnp::traits::IntoInternalStructReader<'a> for Reader<'a,>  {
+    fn into_internal_struct_reader(self) -> ::capnp::private::layout::StructReader<'a> {
+      self.reader
+    }
+  }
+
+  impl <'a,> ::capnp::traits::Imbue<'a> for Reader<'a,>  {
+    fn imbue(&mut self, cap_table: &'a ::capnp::private::layout::CapTable) {
+      self.reader.imbue(::capnp::private::layout::CapTableReader::Plain(cap_table))
+    }
+  }
+
+  impl <'a,> Reader<'a,>  {
+    pub fn reborrow(&self) -> Reader<'_,> {
+      Reader { .. *self }
+    }
+
+    pub fn total_size(&self) -> ::capnp::Result<::capnp::MessageSize> {
+      self.reader.total_size()
+    }
+    #[inline]
+    pub fn get_placeholder(self)  {
+      
+    }
+  }
+
+  pub struct Builder<'a> { builder: ::capnp::private::layout::StructBuilder<'a> }
+  impl <'a,> ::capnp::traits::HasStructSize for Builder<'a,>  {
+    #[inline]
+    fn struct_size() -> ::capnp::private::layout::StructSize { _private::STRUCT_SIZE }
+  }
+  impl <'a,> ::capnp::traits::HasTypeId for Builder<'a,>  {
+    #[inline]
+    fn type_id() -> u64 { _private::TYPE_ID }
+  }
+  impl <'a,> ::capnp::traits::FromStructBuilder<'a> for Builder<'a,>  {
+    fn new(builder: ::capnp::private::layout::StructBuilder<'a>) -> Builder<'a, > {
+      Builder { builder,  }
+    }
+  }
+
+  impl <'a,> ::capnp::traits::ImbueMut<'a> for Builder<'a,>  {
+    fn imbue_mut(&mut self, cap_table: &'a mut ::capnp::private::layout::CapTable) {
+      self.builder.imbue(::capnp::private::layout::CapTableBuilder::Plain(cap_table))
+    }
+  }
+
+  impl <'a,> ::capnp::traits::FromPointerBuilder<'a> for Builder<'a,>  {
+    fn init_pointer(builder: ::capnp::private::layout::PointerBuilder<'a>, _size: u32) -> Builder<'a,> {
+      ::capnp::traits::FromStructBuilder::new(builder.init_struct(_private::STRUCT_SIZE))
+    }
+    fn get_from_pointer(builder: ::capnp::private::layout::PointerBuilder<'a>, default: ::core::option::Option<&'a [capnp::Word]>) -> ::capnp::Result<Builder<'a,>> {
+      ::core::result::Result::Ok(::capnp::traits::FromStructBuilder::new(builder.get_struct(_private::STRUCT_SIZE, default)?))
+    }
+  }
+
+  impl <'a,> ::capnp::traits::SetPointerBuilder for Reader<'a,>  {
+    fn set_pointer_builder<'b>(pointer: ::capnp::private::layout::PointerBuilder<'b>, value: Reader<'a,>, canonicalize: bool) -> ::capnp::Result<()> { pointer.set_struct(&value.reader, canonicalize) }
+  }
+
+  impl <'a,> Builder<'a,>  {
+    pub fn into_reader(self) -> Reader<'a,> {
+      ::capnp::traits::FromStructReader::new(self.builder.into_reader())
+    }
+    pub fn reborrow(&mut self) -> Builder<'_,> {
+      Builder { .. *self }
+    }
+    pub fn reborrow_as_reader(&self) -> Reader<'_,> {
+      ::capnp::traits::FromStructReader::new(self.builder.into_reader())
+    }
+
+    pub fn total_size(&self) -> ::capnp::Result<::capnp::MessageSize> {
+      self.builder.into_reader().total_size()
+    }
+    #[inline]
+    pub fn get_placeholder(self)  {
+      
+    }
+    #[inline]
+    pub fn set_placeholder(&mut self, _value: ())  {
+    }
+  }
+
+  pub struct Pipeline { _typeless: ::capnp::any_pointer::Pipeline }
+  impl ::capnp::capability::FromTypelessPipeline for Pipeline {
+    fn new(typeless: ::capnp::any_pointer::Pipeline) -> Pipeline {
+      Pipeline { _typeless: typeless,  }
+    }
+  }
+  impl Pipeline  {
+  }
+  mod _private {
+    use capnp::private::layout;
+    pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 0, pointers: 0 };
+    pub const TYPE_ID: u64 = 0xfedd_3465_2295_4326;
+  }
+}
+
+pub mod rekey {
   #[derive(Copy, Clone)]
   pub struct Owned(());
   impl <'a> ::capnp::traits::Owned<'a> for Owned { type Reader = Reader<'a>; type Builder = Builder<'a>; }
@@ -1542,11 +2105,11 @@ pub mod get_memory_pool {
   mod _private {
     use capnp::private::layout;
     pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 0, pointers: 0 };
-    pub const TYPE_ID: u64 = 0xfba1_7045_02d0_44f3;
+    pub const TYPE_ID: u64 = 0x80df_e7e2_b35c_41a2;
   }
 }
 
-pub mod get_peers {
+pub mod rpc_message {
   #[derive(Copy, Clone)]
   pub struct Owned(());
   impl <'a> ::capnp::traits::Owned<'a> for Owned { type Reader = Reader<'a>; type Builder = Builder<'a>; }
@@ -1593,8 +2156,11 @@ pub mod get_peers {
       self.reader.total_size()
     }
     #[inline]
-    pub fn get_placeholder(self)  {
-      
+    pub fn get_data(self) -> ::capnp::Result<::capnp::data::Reader<'a>> {
+      ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
+    }
+    pub fn has_data(&self) -> bool {
+      !self.reader.get_pointer_field(0).is_null()
     }
   }
 
@@ -1647,11 +2213,19 @@ pub mod get_peers {
       self.builder.into_reader().total_size()
     }
     #[inline]
-    pub fn get_placeholder(self)  {
-      
+    pub fn get_data(self) -> ::capnp::Result<::capnp::data::Builder<'a>> {
+      ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
     }
     #[inline]
-    pub fn set_placeholder(&mut self, _value: ())  {
+    pub fn set_data(&mut self, value: ::capnp::data::Reader<'_>)  {
+      self.builder.get_pointer_field(0).set_data(value);
+    }
+    #[inline]
+    pub fn init_data(self, size: u32) -> ::capnp::data::Builder<'a> {
+      self.builder.get_pointer_field(0).init_data(size)
+    }
+    pub fn has_data(&self) -> bool {
+      !self.builder.get_pointer_field(0).is_null()
     }
   }
 
@@ -1665,12 +2239,12 @@ pub mod get_peers {
   }
   mod _private {
     use capnp::private::layout;
-    pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 0, pointers: 0 };
-    pub const TYPE_ID: u64 = 0xc075_4205_a0c7_dafa;
+    pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 0, pointers: 1 };
+    pub const TYPE_ID: u64 = 0xd3a1_6c0e_94b7_2f58;
   }
 }
 
-pub mod pong {
+pub mod address_change {
   #[derive(Copy, Clone)]
   pub struct Owned(());
   impl <'a> ::capnp::traits::Owned<'a> for Owned { type Reader = Reader<'a>; type Builder = Builder<'a>; }
@@ -1717,8 +2291,32 @@ pub mod pong {
       self.reader.total_size()
     }
     #[inline]
-    pub fn get_placeholder(self)  {
-      
+    pub fn get_previous(self) -> ::capnp::Result<crate::payload_capnp::socket_addr::Reader<'a>> {
+      ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
+    }
+    pub fn has_previous(&self) -> bool {
+      !self.reader.get_pointer_field(0).is_null()
+    }
+    #[inline]
+    pub fn get_address(self) -> ::capnp::Result<crate::payload_capnp::socket_addr::Reader<'a>> {
+      ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(1), ::core::option::Option::None)
+    }
+    pub fn has_address(&self) -> bool {
+      !self.reader.get_pointer_field(1).is_null()
+    }
+    #[inline]
+    pub fn get_public_key(self) -> ::capnp::Result<::capnp::data::Reader<'a>> {
+      ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(2), ::core::option::Option::None)
+    }
+    pub fn has_public_key(&self) -> bool {
+      !self.reader.get_pointer_field(2).is_null()
+    }
+    #[inline]
+    pub fn get_signature(self) -> ::capnp::Result<::capnp::data::Reader<'a>> {
+      ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(3), ::core::option::Option::None)
+    }
+    pub fn has_signature(&self) -> bool {
+      !self.reader.get_pointer_field(3).is_null()
     }
   }
 
@@ -1771,11 +2369,64 @@ pub mod pong {
       self.builder.into_reader().total_size()
     }
     #[inline]
-    pub fn get_placeholder(self)  {
-      
+    pub fn get_previous(self) -> ::capnp::Result<crate::payload_capnp::socket_addr::Builder<'a>> {
+      ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
     }
     #[inline]
-    pub fn set_placeholder(&mut self, _value: ())  {
+    pub fn set_previous(&mut self, value: crate::payload_capnp::socket_addr::Reader<'_>) -> ::capnp::Result<()> {
+      ::capnp::traits::SetPointerBuilder::set_pointer_builder(self.builder.get_pointer_field(0), value, false)
+    }
+    #[inline]
+    pub fn init_previous(self, ) -> crate::payload_capnp::socket_addr::Builder<'a> {
+      ::capnp::traits::FromPointerBuilder::init_pointer(self.builder.get_pointer_field(0), 0)
+    }
+    pub fn has_previous(&self) -> bool {
+      !self.builder.get_pointer_field(0).is_null()
+    }
+    #[inline]
+    pub fn get_address(self) -> ::capnp::Result<crate::payload_capnp::socket_addr::Builder<'a>> {
+      ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(1), ::core::option::Option::None)
+    }
+    #[inline]
+    pub fn set_address(&mut self, value: crate::payload_capnp::socket_addr::Reader<'_>) -> ::capnp::Result<()> {
+      ::capnp::traits::SetPointerBuilder::set_pointer_builder(self.builder.get_pointer_field(1), value, false)
+    }
+    #[inline]
+    pub fn init_address(self, ) -> crate::payload_capnp::socket_addr::Builder<'a> {
+      ::capnp::traits::FromPointerBuilder::init_pointer(self.builder.get_pointer_field(1), 0)
+    }
+    pub fn has_address(&self) -> bool {
+      !self.builder.get_pointer_field(1).is_null()
+    }
+    #[inline]
+    pub fn get_public_key(self) -> ::capnp::Result<::capnp::data::Builder<'a>> {
+      ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(2), ::core::option::Option::None)
+    }
+    #[inline]
+    pub fn set_public_key(&mut self, value: ::capnp::data::Reader<'_>)  {
+      self.builder.get_pointer_field(2).set_data(value);
+    }
+    #[inline]
+    pub fn init_public_key(self, size: u32) -> ::capnp::data::Builder<'a> {
+      self.builder.get_pointer_field(2).init_data(size)
+    }
+    pub fn has_public_key(&self) -> bool {
+      !self.builder.get_pointer_field(2).is_null()
+    }
+    #[inline]
+    pub fn get_signature(self) -> ::capnp::Result<::capnp::data::Builder<'a>> {
+      ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(3), ::core::option::Option::None)
+    }
+    #[inline]
+    pub fn set_signature(&mut self, value: ::capnp::data::Reader<'_>)  {
+      self.builder.get_pointer_field(3).set_data(value);
+    }
+    #[inline]
+    pub fn init_signature(self, size: u32) -> ::capnp::data::Builder<'a> {
+      self.builder.get_pointer_field(3).init_data(size)
+    }
+    pub fn has_signature(&self) -> bool {
+      !self.builder.get_pointer_field(3).is_null()
     }
   }
 
@@ -1786,11 +2437,17 @@ pub mod pong {
     }
   }
   impl Pipeline  {
+    pub fn get_previous(&self) -> crate::payload_capnp::socket_addr::Pipeline {
+      ::capnp::capability::FromTypelessPipeline::new(self._typeless.get_pointer_field(0))
+    }
+    pub fn get_address(&self) -> crate::payload_capnp::socket_addr::Pipeline {
+      ::capnp::capability::FromTypelessPipeline::new(self._typeless.get_pointer_field(1))
+    }
   }
   mod _private {
     use capnp::private::layout;
-    pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 0, pointers: 0 };
-    pub const TYPE_ID: u64 = 0xfedd_3465_2295_4326;
+    pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 0, pointers: 4 };
+    pub const TYPE_ID: u64 = 0xc47e_0b2a_d5f1_9e63;
   }
 }
 
@@ -1844,6 +2501,10 @@ pub mod payload {
     pub fn get_payload_type(self) -> crate::payload_capnp::payload::payload_type::Reader<'a> {
       ::capnp::traits::FromStructReader::new(self.reader)
     }
+    #[inline]
+    pub fn get_flags(self) -> u16 {
+      self.reader.get_data_field::<u16>(1)
+    }
   }
 
   pub struct Builder<'a> { builder: ::capnp::private::layout::StructBuilder<'a> }
@@ -1904,6 +2565,14 @@ pub mod payload {
       self.builder.get_pointer_field(0).clear();
       ::capnp::traits::FromStructBuilder::new(self.builder)
     }
+    #[inline]
+    pub fn get_flags(self) -> u16 {
+      self.builder.get_data_field::<u16>(1)
+    }
+    #[inline]
+    pub fn set_flags(&mut self, value: u16)  {
+      self.builder.set_data_field::<u16>(1, value);
+    }
   }
 
   pub struct Pipeline { _typeless: ::capnp::any_pointer::Pipeline }
@@ -1924,7 +2593,7 @@ pub mod payload {
   }
 
   pub mod payload_type {
-    pub use self::Which::{Block,GetBlocks,GetMemoryPool,GetPeers,GetSync,MemoryPool,Peers,Ping,Pong,Sync,SyncBlock,Transaction};
+    pub use self::Which::{Block,GetBlocks,GetMemoryPool,GetPeers,GetSync,MemoryPool,Peers,Ping,Pong,Sync,SyncBlock,Transaction,NodeAlias,BlockHash,GetBlock,Rekey,RpcAuth,RpcRequest,RpcResponse,AddressChange};
 
     #[derive(Copy, Clone)]
     pub struct Owned(());
@@ -2019,6 +2688,38 @@ pub mod payload {
         if self.reader.get_data_field::<u16>(0) != 11 { return false; }
         !self.reader.get_pointer_field(0).is_null()
       }
+      pub fn has_node_alias(&self) -> bool {
+        if self.reader.get_data_field::<u16>(0) != 12 { return false; }
+        !self.reader.get_pointer_field(0).is_null()
+      }
+      pub fn has_block_hash(&self) -> bool {
+        if self.reader.get_data_field::<u16>(0) != 13 { return false; }
+        !self.reader.get_pointer_field(0).is_null()
+      }
+      pub fn has_get_block(&self) -> bool {
+        if self.reader.get_data_field::<u16>(0) != 14 { return false; }
+        !self.reader.get_pointer_field(0).is_null()
+      }
+      pub fn has_rekey(&self) -> bool {
+        if self.reader.get_data_field::<u16>(0) != 15 { return false; }
+        !self.reader.get_pointer_field(0).is_null()
+      }
+      pub fn has_rpc_auth(&self) -> bool {
+        if self.reader.get_data_field::<u16>(0) != 16 { return false; }
+        !self.reader.get_pointer_field(0).is_null()
+      }
+      pub fn has_rpc_request(&self) -> bool {
+        if self.reader.get_data_field::<u16>(0) != 17 { return false; }
+        !self.reader.get_pointer_field(0).is_null()
+      }
+      pub fn has_rpc_response(&self) -> bool {
+        if self.reader.get_data_field::<u16>(0) != 18 { return false; }
+        !self.reader.get_pointer_field(0).is_null()
+      }
+      pub fn has_address_change(&self) -> bool {
+        if self.reader.get_data_field::<u16>(0) != 19 { return false; }
+        !self.reader.get_pointer_field(0).is_null()
+      }
       #[inline]
       pub fn which(self) -> ::core::result::Result<WhichReader<'a,>, ::capnp::NotInSchema> {
         match self.reader.get_data_field::<u16>(0) {
@@ -2082,6 +2783,46 @@ pub mod payload {
               ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
             ))
           }
+          12 => {
+            ::core::result::Result::Ok(NodeAlias(
+              ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
+          13 => {
+            ::core::result::Result::Ok(BlockHash(
+              ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
+          14 => {
+            ::core::result::Result::Ok(GetBlock(
+              ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
+          15 => {
+            ::core::result::Result::Ok(Rekey(
+              ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
+          16 => {
+            ::core::result::Result::Ok(RpcAuth(
+              ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
+          17 => {
+            ::core::result::Result::Ok(RpcRequest(
+              ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
+          18 => {
+            ::core::result::Result::Ok(RpcResponse(
+              ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
+          19 => {
+            ::core::result::Result::Ok(AddressChange(
+              ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
           x => ::core::result::Result::Err(::capnp::NotInSchema(x))
         }
       }
@@ -2304,6 +3045,118 @@ pub mod payload {
         !self.builder.get_pointer_field(0).is_null()
       }
       #[inline]
+      pub fn set_node_alias(&mut self, value: crate::payload_capnp::node_alias::Reader<'_>) -> ::capnp::Result<()> {
+        self.builder.set_data_field::<u16>(0, 12);
+        ::capnp::traits::SetPointerBuilder::set_pointer_builder(self.builder.get_pointer_field(0), value, false)
+      }
+      #[inline]
+      pub fn init_node_alias(self, ) -> crate::payload_capnp::node_alias::Builder<'a> {
+        self.builder.set_data_field::<u16>(0, 12);
+        ::capnp::traits::FromPointerBuilder::init_pointer(self.builder.get_pointer_field(0), 0)
+      }
+      pub fn has_node_alias(&self) -> bool {
+        if self.builder.get_data_field::<u16>(0) != 12 { return false; }
+        !self.builder.get_pointer_field(0).is_null()
+      }
+      #[inline]
+      pub fn set_block_hash(&mut self, value: crate::payload_capnp::block_hash::Reader<'_>) -> ::capnp::Result<()> {
+        self.builder.set_data_field::<u16>(0, 13);
+        ::capnp::traits::SetPointerBuilder::set_pointer_builder(self.builder.get_pointer_field(0), value, false)
+      }
+      #[inline]
+      pub fn init_block_hash(self, ) -> crate::payload_capnp::block_hash::Builder<'a> {
+        self.builder.set_data_field::<u16>(0, 13);
+        ::capnp::traits::FromPointerBuilder::init_pointer(self.builder.get_pointer_field(0), 0)
+      }
+      pub fn has_block_hash(&self) -> bool {
+        if self.builder.get_data_field::<u16>(0) != 13 { return false; }
+        !self.builder.get_pointer_field(0).is_null()
+      }
+      #[inline]
+      pub fn set_get_block(&mut self, value: crate::payload_capnp::block_hash::Reader<'_>) -> ::capnp::Result<()> {
+        self.builder.set_data_field::<u16>(0, 14);
+        ::capnp::traits::SetPointerBuilder::set_pointer_builder(self.builder.get_pointer_field(0), value, false)
+      }
+      #[inline]
+      pub fn init_get_block(self, ) -> crate::payload_capnp::block_hash::Builder<'a> {
+        self.builder.set_data_field::<u16>(0, 14);
+        ::capnp::traits::FromPointerBuilder::init_pointer(self.builder.get_pointer_field(0), 0)
+      }
+      pub fn has_get_block(&self) -> bool {
+        if self.builder.get_data_field::<u16>(0) != 14 { return false; }
+        !self.builder.get_pointer_field(0).is_null()
+      }
+      #[inline]
+      pub fn set_rekey(&mut self, value: crate::payload_capnp::rekey::Reader<'_>) -> ::capnp::Result<()> {
+        self.builder.set_data_field::<u16>(0, 15);
+        ::capnp::traits::SetPointerBuilder::set_pointer_builder(self.builder.get_pointer_field(0), value, false)
+      }
+      #[inline]
+      pub fn init_rekey(self, ) -> crate::payload_capnp::rekey::Builder<'a> {
+        self.builder.set_data_field::<u16>(0, 15);
+        ::capnp::traits::FromPointerBuilder::init_pointer(self.builder.get_pointer_field(0), 0)
+      }
+      pub fn has_rekey(&self) -> bool {
+        if self.builder.get_data_field::<u16>(0) != 15 { return false; }
+        !self.builder.get_pointer_field(0).is_null()
+      }
+      #[inline]
+      pub fn set_rpc_auth(&mut self, value: crate::payload_capnp::rpc_message::Reader<'_>) -> ::capnp::Result<()> {
+        self.builder.set_data_field::<u16>(0, 16);
+        ::capnp::traits::SetPointerBuilder::set_pointer_builder(self.builder.get_pointer_field(0), value, false)
+      }
+      #[inline]
+      pub fn init_rpc_auth(self, ) -> crate::payload_capnp::rpc_message::Builder<'a> {
+        self.builder.set_data_field::<u16>(0, 16);
+        ::capnp::traits::FromPointerBuilder::init_pointer(self.builder.get_pointer_field(0), 0)
+      }
+      pub fn has_rpc_auth(&self) -> bool {
+        if self.builder.get_data_field::<u16>(0) != 16 { return false; }
+        !self.builder.get_pointer_field(0).is_null()
+      }
+      #[inline]
+      pub fn set_rpc_request(&mut self, value: crate::payload_capnp::rpc_message::Reader<'_>) -> ::capnp::Result<()> {
+        self.builder.set_data_field::<u16>(0, 17);
+        ::capnp::traits::SetPointerBuilder::set_pointer_builder(self.builder.get_pointer_field(0), value, false)
+      }
+      #[inline]
+      pub fn init_rpc_request(self, ) -> crate::payload_capnp::rpc_message::Builder<'a> {
+        self.builder.set_data_field::<u16>(0, 17);
+        ::capnp::traits::FromPointerBuilder::init_pointer(self.builder.get_pointer_field(0), 0)
+      }
+      pub fn has_rpc_request(&self) -> bool {
+        if self.builder.get_data_field::<u16>(0) != 17 { return false; }
+        !self.builder.get_pointer_field(0).is_null()
+      }
+      #[inline]
+      pub fn set_rpc_response(&mut self, value: crate::payload_capnp::rpc_message::Reader<'_>) -> ::capnp::Result<()> {
+        self.builder.set_data_field::<u16>(0, 18);
+        ::capnp::traits::SetPointerBuilder::set_pointer_builder(self.builder.get_pointer_field(0), value, false)
+      }
+      #[inline]
+      pub fn init_rpc_response(self, ) -> crate::payload_capnp::rpc_message::Builder<'a> {
+        self.builder.set_data_field::<u16>(0, 18);
+        ::capnp::traits::FromPointerBuilder::init_pointer(self.builder.get_pointer_field(0), 0)
+      }
+      pub fn has_rpc_response(&self) -> bool {
+        if self.builder.get_data_field::<u16>(0) != 18 { return false; }
+        !self.builder.get_pointer_field(0).is_null()
+      }
+      #[inline]
+      pub fn set_address_change(&mut self, value: crate::payload_capnp::address_change::Reader<'_>) -> ::capnp::Result<()> {
+        self.builder.set_data_field::<u16>(0, 19);
+        ::capnp::traits::SetPointerBuilder::set_pointer_builder(self.builder.get_pointer_field(0), value, false)
+      }
+      #[inline]
+      pub fn init_address_change(self, ) -> crate::payload_capnp::address_change::Builder<'a> {
+        self.builder.set_data_field::<u16>(0, 19);
+        ::capnp::traits::FromPointerBuilder::init_pointer(self.builder.get_pointer_field(0), 0)
+      }
+      pub fn has_address_change(&self) -> bool {
+        if self.builder.get_data_field::<u16>(0) != 19 { return false; }
+        !self.builder.get_pointer_field(0).is_null()
+      }
+      #[inline]
       pub fn which(self) -> ::core::result::Result<WhichBuilder<'a,>, ::capnp::NotInSchema> {
         match self.builder.get_data_field::<u16>(0) {
           0 => {
@@ -2366,6 +3219,46 @@ pub mod payload {
               ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
             ))
           }
+          12 => {
+            ::core::result::Result::Ok(NodeAlias(
+              ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
+          13 => {
+            ::core::result::Result::Ok(BlockHash(
+              ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
+          14 => {
+            ::core::result::Result::Ok(GetBlock(
+              ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
+          15 => {
+            ::core::result::Result::Ok(Rekey(
+              ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
+          16 => {
+            ::core::result::Result::Ok(RpcAuth(
+              ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
+          17 => {
+            ::core::result::Result::Ok(RpcRequest(
+              ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
+          18 => {
+            ::core::result::Result::Ok(RpcResponse(
+              ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
+          19 => {
+            ::core::result::Result::Ok(AddressChange(
+              ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
           x => ::core::result::Result::Err(::capnp::NotInSchema(x))
         }
       }
@@ -2384,7 +3277,7 @@ pub mod payload {
       pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 1, pointers: 1 };
       pub const TYPE_ID: u64 = 0xb8b4_27fe_5891_d61c;
     }
-    pub enum Which<A0,A1,A2,A3,A4,A5,A6,A7,A8,A9,A10,A11> {
+    pub enum Which<A0,A1,A2,A3,A4,A5,A6,A7,A8,A9,A10,A11,A12,A13,A14,A15,A16,A17,A18,A19> {
       Block(A0),
       GetBlocks(A1),
       GetMemoryPool(A2),
@@ -2397,9 +3290,17 @@ pub mod payload {
       Sync(A9),
       SyncBlock(A10),
       Transaction(A11),
-    }
-    pub type WhichReader<'a,> = Which<::capnp::Result<crate::payload_capnp::block::Reader<'a>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<crate::payload_capnp::get_memory_pool::Reader<'a>>,::capnp::Result<crate::payload_capnp::get_peers::Reader<'a>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::transaction::Owned>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::socket_addr::Owned>>,::capnp::Result<crate::payload_capnp::ping::Reader<'a>>,::capnp::Result<crate::payload_capnp::pong::Reader<'a>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<crate::payload_capnp::block::Reader<'a>>,::capnp::Result<crate::payload_capnp::transaction::Reader<'a>>>;
-    pub type WhichBuilder<'a,> = Which<::capnp::Result<crate::payload_capnp::block::Builder<'a>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<crate::payload_capnp::get_memory_pool::Builder<'a>>,::capnp::Result<crate::payload_capnp::get_peers::Builder<'a>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::transaction::Owned>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::socket_addr::Owned>>,::capnp::Result<crate::payload_capnp::ping::Builder<'a>>,::capnp::Result<crate::payload_capnp::pong::Builder<'a>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<crate::payload_capnp::block::Builder<'a>>,::capnp::Result<crate::payload_capnp::transaction::Builder<'a>>>;
+      NodeAlias(A12),
+      BlockHash(A13),
+      GetBlock(A14),
+      Rekey(A15),
+      RpcAuth(A16),
+      RpcRequest(A17),
+      RpcResponse(A18),
+      AddressChange(A19),
+    }
+    pub type WhichReader<'a,> = Which<::capnp::Result<crate::payload_capnp::block::Reader<'a>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<crate::payload_capnp::get_memory_pool::Reader<'a>>,::capnp::Result<crate::payload_capnp::get_peers::Reader<'a>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::transaction::Owned>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::socket_addr::Owned>>,::capnp::Result<crate::payload_capnp::ping::Reader<'a>>,::capnp::Result<crate::payload_capnp::pong::Reader<'a>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<crate::payload_capnp::block::Reader<'a>>,::capnp::Result<crate::payload_capnp::transaction::Reader<'a>>,::capnp::Result<crate::payload_capnp::node_alias::Reader<'a>>,::capnp::Result<crate::payload_capnp::block_hash::Reader<'a>>,::capnp::Result<crate::payload_capnp::block_hash::Reader<'a>>,::capnp::Result<crate::payload_capnp::rekey::Reader<'a>>,::capnp::Result<crate::payload_capnp::rpc_message::Reader<'a>>,::capnp::Result<crate::payload_capnp::rpc_message::Reader<'a>>,::capnp::Result<crate::payload_capnp::rpc_message::Reader<'a>>,::capnp::Result<crate::payload_capnp::address_change::Reader<'a>>>;
+    pub type WhichBuilder<'a,> = Which<::capnp::Result<crate::payload_capnp::block::Builder<'a>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<crate::payload_capnp::get_memory_pool::Builder<'a>>,::capnp::Result<crate::payload_capnp::get_peers::Builder<'a>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::transaction::Owned>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::socket_addr::Owned>>,::capnp::Result<crate::payload_capnp::ping::Builder<'a>>,::capnp::Result<crate::payload_capnp::pong::Builder<'a>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<crate::payload_capnp::block::Builder<'a>>,::capnp::Result<crate::payload_capnp::transaction::Builder<'a>>,::capnp::Result<crate::payload_capnp::node_alias::Builder<'a>>,::capnp::Result<crate::payload_capnp::block_hash::Builder<'a>>,::capnp::Result<crate::payload_capnp::block_hash::Builder<'a>>,::capnp::Result<crate::payload_capnp::rekey::Builder<'a>>,::capnp::Result<crate::payload_capnp::rpc_message::Builder<'a>>,::capnp::Result<crate::payload_capnp::rpc_message::Builder<'a>>,::capnp::Result<crate::payload_capnp::rpc_message::Builder<'a>>,::capnp::Result<crate::payload_capnp::address_change::Builder<'a>>>;
   }
 }
 
@@ -2461,6 +3362,29 @@ pub mod version {
     pub fn get_node_id(self) -> u64 {
       self.reader.get_data_field::<u64>(2)
     }
+    #[inline]
+    pub fn get_external_address(self) -> ::capnp::Result<crate::payload_capnp::socket_addr::Reader<'a>> {
+      ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
+    }
+    pub fn has_external_address(&self) -> bool {
+      !self.reader.get_pointer_field(0).is_null()
+    }
+    #[inline]
+    pub fn get_earliest_block_height(self) -> u32 {
+      self.reader.get_data_field::<u32>(3)
+    }
+    #[inline]
+    pub fn get_features(self) -> u64 {
+      self.reader.get_data_field::<u64>(3)
+    }
+    #[inline]
+    pub fn get_network_id(self) -> u8 {
+      self.reader.get_data_field_mask::<u8>(10, 1u8)
+    }
+    #[inline]
+    pub fn get_timestamp(self) -> i64 {
+      self.reader.get_data_field::<i64>(4)
+    }
   }
 
   pub struct Builder<'a> { builder: ::capnp::private::layout::StructBuilder<'a> }
@@ -2535,6 +3459,53 @@ pub mod version {
     pub fn set_node_id(&mut self, value: u64)  {
       self.builder.set_data_field::<u64>(2, value);
     }
+    #[inline]
+    pub fn get_external_address(self) -> ::capnp::Result<crate::payload_capnp::socket_addr::Builder<'a>> {
+      ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
+    }
+    #[inline]
+    pub fn set_external_address(&mut self, value: crate::payload_capnp::socket_addr::Reader<'_>) -> ::capnp::Result<()> {
+      ::capnp::traits::SetPointerBuilder::set_pointer_builder(self.builder.get_pointer_field(0), value, false)
+    }
+    #[inline]
+    pub fn init_external_address(self, ) -> crate::payload_capnp::socket_addr::Builder<'a> {
+      ::capnp::traits::FromPointerBuilder::init_pointer(self.builder.get_pointer_field(0), 0)
+    }
+    pub fn has_external_address(&self) -> bool {
+      !self.builder.get_pointer_field(0).is_null()
+    }
+    #[inline]
+    pub fn get_earliest_block_height(self) -> u32 {
+      self.builder.get_data_field::<u32>(3)
+    }
+    #[inline]
+    pub fn set_earliest_block_height(&mut self, value: u32)  {
+      self.builder.set_data_field::<u32>(3, value);
+    }
+    #[inline]
+    pub fn get_features(self) -> u64 {
+      self.builder.get_data_field::<u64>(3)
+    }
+    #[inline]
+    pub fn set_features(&mut self, value: u64)  {
+      self.builder.set_data_field::<u64>(3, value);
+    }
+    #[inline]
+    pub fn get_network_id(self) -> u8 {
+      self.builder.get_data_field_mask::<u8>(10, 1u8)
+    }
+    #[inline]
+    pub fn set_network_id(&mut self, value: u8)  {
+      self.builder.set_data_field_mask::<u8>(10, value, 1u8);
+    }
+    #[inline]
+    pub fn get_timestamp(self) -> i64 {
+      self.builder.get_data_field::<i64>(4)
+    }
+    #[inline]
+    pub fn set_timestamp(&mut self, value: i64)  {
+      self.builder.set_data_field::<i64>(4, value);
+    }
   }
 
   pub struct Pipeline { _typeless: ::capnp::any_pointer::Pipeline }
@@ -2544,10 +3515,13 @@ pub mod version {
     }
   }
   impl Pipeline  {
+    pub fn get_external_address(&self) -> crate::payload_capnp::socket_addr::Pipeline {
+      ::capnp::capability::FromTypelessPipeline::new(self._typeless.get_pointer_field(0))
+    }
   }
   mod _private {
     use capnp::private::layout;
-    pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 3, pointers: 0 };
+    pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 5, pointers: 1 };
     pub const TYPE_ID: u64 = 0xf6b9_300e_617a_79e5;
   }
 }
@@ -16,7 +16,12 @@
 
 use snarkvm_dpc::BlockHeaderHash;
 
-use crate::message::{Payload, Version};
+use crate::{
+    message::{Features, Payload, PayloadFlags, Version},
+    AddressChange,
+    NetworkId,
+    NodeAlias,
+};
 use payload_capnp::{
     block,
     block_hash,
@@ -49,10 +54,21 @@ impl Version {
 
         let version = message_reader.get_root::<version::Reader>()?;
 
+        let external_address = if version.has_external_address() {
+            Some(deserialize_address(version.get_external_address()?)?)
+        } else {
+            None
+        };
+
         Ok(Version {
             version: version.get_version(),
             listening_port: version.get_listening_port(),
             node_id: version.get_node_id(),
+            external_address,
+            earliest_block_height: version.get_earliest_block_height(),
+            features: Features::from_bits(version.get_features()),
+            network_id: NetworkId::from_id(version.get_network_id()),
+            timestamp: version.get_timestamp(),
         })
     }
 
@@ -62,6 +78,13 @@ impl Version {
         builder.set_version(self.version);
         builder.set_listening_port(self.listening_port);
         builder.set_node_id(self.node_id);
+        if let Some(ref addr) = self.external_address {
+            serialize_address(builder.init_external_address(), addr);
+        }
+        builder.set_earliest_block_height(self.earliest_block_height);
+        builder.set_features(self.features.bits());
+        builder.set_network_id(self.network_id.id());
+        builder.set_timestamp(self.timestamp);
 
         let mut writer = Vec::new();
         capnp::serialize_packed::write_message(&mut writer, &message)?;
@@ -74,11 +97,15 @@ impl Payload {
         let mut cursor = io::Cursor::new(bytes);
         let message_reader = capnp::serialize_packed::read_message(&mut cursor, capnp::message::ReaderOptions::new())?;
 
-        let payload = message_reader.get_root::<payload::Reader>()?.get_payload_type();
+        let root = message_reader.get_root::<payload::Reader>()?;
+        let payload = root.get_payload_type();
 
         let payload_type = match payload.which() {
             Ok(pt) => pt,
-            Err(capnp::NotInSchema(_)) => return Ok(Payload::Unknown), // future-proofing
+            // future-proofing: the payload's boundary is known from its frame, so it can be skipped
+            Err(capnp::NotInSchema(type_id)) => {
+                return Ok(Payload::Unknown(type_id, PayloadFlags::from_bits(root.get_flags())));
+            }
         };
 
         match payload_type {
@@ -89,19 +116,86 @@ impl Payload {
             payload_type::Which::GetSync(hashes) => Ok(Payload::GetSync(deserialize_block_hashes(hashes?)?)),
             payload_type::Which::MemoryPool(txs) => deserialize_transactions(txs?),
             payload_type::Which::Peers(peers) => Ok(Payload::Peers(deserialize_addresses(peers?)?)),
-            payload_type::Which::Ping(ping) => Ok(Payload::Ping(ping?.get_block_height())),
+            payload_type::Which::Ping(ping) => {
+                let ping = ping?;
+                Ok(Payload::Ping(ping.get_block_height(), ping.get_timestamp()))
+            }
             payload_type::Which::Pong(_) => Ok(Payload::Pong),
             payload_type::Which::Sync(hashes) => Ok(Payload::Sync(deserialize_block_hashes(hashes?)?)),
             payload_type::Which::SyncBlock(block) => deserialize_block(block?, true),
             payload_type::Which::Transaction(tx) => Ok(Payload::Transaction(tx?.get_data()?.to_vec())),
+            payload_type::Which::BlockHash(hash) => Ok(Payload::BlockHash(deserialize_block_hash(hash?)?)),
+            payload_type::Which::GetBlock(hash) => Ok(Payload::GetBlock(deserialize_block_hash(hash?)?)),
+            payload_type::Which::Rekey(_) => Ok(Payload::Rekey),
+            payload_type::Which::RpcAuth(auth) => Ok(Payload::RpcAuth(auth?.get_data()?.to_vec())),
+            payload_type::Which::RpcRequest(request) => Ok(Payload::RpcRequest(request?.get_data()?.to_vec())),
+            payload_type::Which::RpcResponse(response) => Ok(Payload::RpcResponse(response?.get_data()?.to_vec())),
+            payload_type::Which::NodeAlias(node_alias) => {
+                let node_alias = node_alias?;
+                Ok(Payload::NodeAlias(NodeAlias {
+                    alias: node_alias.get_alias()?.to_owned(),
+                    public_key: node_alias.get_public_key()?.to_vec(),
+                    signature: node_alias.get_signature()?.to_vec(),
+                }))
+            }
+            payload_type::Which::AddressChange(change) => {
+                let change = change?;
+                Ok(Payload::AddressChange(AddressChange {
+                    previous: deserialize_address(change.get_previous()?)?,
+                    address: deserialize_address(change.get_address()?)?,
+                    public_key: change.get_public_key()?.to_vec(),
+                    signature: change.get_signature()?.to_vec(),
+                }))
+            }
         }
     }
 
+    ///
+    /// Returns the name and the maximum size of the serialized payload starting with the given bytes, so that
+    /// oversized payloads can be rejected before they're decrypted and deserialized in full. Returns `None` if
+    /// the type of the payload can't be determined, in which case only `MAX_MESSAGE_SIZE` applies.
+    ///
+    pub fn size_limit(head: &[u8]) -> Option<(&'static str, usize)> {
+        let limit = match peek_payload_type(head)? {
+            0 => ("block", crate::MAX_MESSAGE_SIZE),
+            1 => ("getblocks", crate::MAX_BLOCK_HASHES_PAYLOAD_SIZE),
+            2 => ("getmempool", crate::MAX_CONTROL_PAYLOAD_SIZE),
+            3 => ("getpeers", crate::MAX_CONTROL_PAYLOAD_SIZE),
+            4 => ("getsync", crate::MAX_BLOCK_HASHES_PAYLOAD_SIZE),
+            5 => ("memorypool", crate::MAX_MESSAGE_SIZE),
+            6 => ("peers", crate::MAX_PEERS_PAYLOAD_SIZE),
+            7 => ("ping", crate::MAX_CONTROL_PAYLOAD_SIZE),
+            8 => ("pong", crate::MAX_CONTROL_PAYLOAD_SIZE),
+            9 => ("sync", crate::MAX_BLOCK_HASHES_PAYLOAD_SIZE),
+            10 => ("syncblock", crate::MAX_MESSAGE_SIZE),
+            11 => ("transaction", crate::MAX_TRANSACTION_PAYLOAD_SIZE),
+            12 => ("nodealias", crate::MAX_CONTROL_PAYLOAD_SIZE),
+            13 => ("blockhash", crate::MAX_CONTROL_PAYLOAD_SIZE),
+            14 => ("getblock", crate::MAX_CONTROL_PAYLOAD_SIZE),
+            15 => ("rekey", crate::MAX_CONTROL_PAYLOAD_SIZE),
+            16 => ("rpcauth", crate::MAX_CONTROL_PAYLOAD_SIZE),
+            17 => ("rpcrequest", crate::MAX_TRANSACTION_PAYLOAD_SIZE),
+            18 => ("rpcresponse", crate::MAX_MESSAGE_SIZE),
+            19 => ("addresschange", crate::MAX_CONTROL_PAYLOAD_SIZE),
+            _ => return None,
+        };
+
+        Some(limit)
+    }
+
+    /// Returns `true` if the serialized payload starting with the given bytes is a `Rekey`; it needs to be
+    /// recognized as soon as it's decrypted, as the payloads following it are encrypted with a new key.
+    pub fn is_rekey(head: &[u8]) -> bool {
+        peek_payload_type(head) == Some(15)
+    }
+
     pub fn serialize(&self) -> capnp::Result<Vec<u8>> {
         let mut message = capnp::message::Builder::new_default();
 
         {
-            let builder = message.init_root::<payload::Builder>().init_payload_type();
+            let mut root = message.init_root::<payload::Builder>();
+            root.set_flags(self.flags().bits());
+            let builder = root.init_payload_type();
 
             match self {
                 Payload::Block(bytes) => {
@@ -140,33 +234,13 @@ impl Payload {
                 Payload::Peers(addrs) => {
                     let mut builder = builder.init_peers(addrs.len() as u32);
                     for (i, addr) in addrs.iter().enumerate() {
-                        let elem_builder = builder.reborrow().get(i as u32);
-                        let elem_builder = elem_builder.init_addr_type();
-                        match addr {
-                            SocketAddr::V4(addr) => {
-                                let mut addr_builder = elem_builder.init_v4();
-                                addr_builder.set_port(addr.port());
-                                let addr_builder = addr_builder.init_addr();
-                                let mut addr_builder = addr_builder.init_octets(4);
-                                for (i, octet) in addr.ip().octets().iter().enumerate() {
-                                    addr_builder.set(i as u32, *octet);
-                                }
-                            }
-                            SocketAddr::V6(addr) => {
-                                let mut addr_builder = elem_builder.init_v6();
-                                addr_builder.set_port(addr.port());
-                                let addr_builder = addr_builder.init_addr();
-                                let mut addr_builder = addr_builder.init_octets(16);
-                                for (i, octet) in addr.ip().octets().iter().enumerate() {
-                                    addr_builder.set(i as u32, *octet);
-                                }
-                            }
-                        }
+                        serialize_address(builder.reborrow().get(i as u32), addr);
                     }
                 }
-                Payload::Ping(block_height) => {
+                Payload::Ping(block_height, timestamp) => {
                     let mut builder = builder.init_ping();
                     builder.set_block_height(*block_height);
+                    builder.set_timestamp(*timestamp);
                 }
                 Payload::Pong => {
                     let mut builder = builder.init_pong();
@@ -187,6 +261,43 @@ impl Payload {
                     let mut builder = builder.init_transaction();
                     builder.set_data(bytes);
                 }
+                Payload::NodeAlias(node_alias) => {
+                    let mut builder = builder.init_node_alias();
+                    builder.set_alias(&node_alias.alias);
+                    builder.set_public_key(&node_alias.public_key);
+                    builder.set_signature(&node_alias.signature);
+                }
+                Payload::BlockHash(hash) => {
+                    let mut builder = builder.init_block_hash();
+                    builder.set_hash(&hash.0);
+                }
+                Payload::GetBlock(hash) => {
+                    let mut builder = builder.init_get_block();
+                    builder.set_hash(&hash.0);
+                }
+                Payload::Rekey => {
+                    let mut builder = builder.init_rekey();
+                    builder.set_placeholder(());
+                }
+                Payload::RpcAuth(credentials) => {
+                    let mut builder = builder.init_rpc_auth();
+                    builder.set_data(credentials);
+                }
+                Payload::RpcRequest(bytes) => {
+                    let mut builder = builder.init_rpc_request();
+                    builder.set_data(bytes);
+                }
+                Payload::RpcResponse(bytes) => {
+                    let mut builder = builder.init_rpc_response();
+                    builder.set_data(bytes);
+                }
+                Payload::AddressChange(change) => {
+                    let mut builder = builder.init_address_change();
+                    serialize_address(builder.reborrow().init_previous(), &change.previous);
+                    serialize_address(builder.reborrow().init_address(), &change.address);
+                    builder.set_public_key(&change.public_key);
+                    builder.set_signature(&change.signature);
+                }
                 _ => unreachable!(),
             }
         }
@@ -213,63 +324,104 @@ fn deserialize_block_hashes(hashes: BlockHashes<'_>) -> capnp::Result<Vec<BlockH
     let mut vec = Vec::with_capacity(hashes.len() as usize);
 
     for hash in hashes.iter() {
-        let bytes = hash.get_hash()?;
-        let mut block_hash = [0u8; 32];
-        block_hash.copy_from_slice(bytes);
-        vec.push(BlockHeaderHash(block_hash));
+        vec.push(deserialize_block_hash(hash)?);
     }
 
     Ok(vec)
 }
 
+fn deserialize_block_hash(hash: block_hash::Reader<'_>) -> capnp::Result<BlockHeaderHash> {
+    let bytes = hash.get_hash()?;
+    if bytes.len() != 32 {
+        return Err(capnp::Error::failed(format!(
+            "invalid block hash length: {}",
+            bytes.len()
+        )));
+    }
+
+    let mut block_hash = [0u8; 32];
+    block_hash.copy_from_slice(bytes);
+
+    Ok(BlockHeaderHash(block_hash))
+}
+
 fn deserialize_addresses(addrs: SocketAddrs<'_>) -> capnp::Result<Vec<SocketAddr>> {
     let mut vec = Vec::with_capacity(addrs.len() as usize);
 
     for addr in addrs.iter() {
-        let addr = addr.get_addr_type();
-        let addr = match addr.which()? {
-            // TODO(ljedrz/nkls): deduplicate the branches using a macro
-            socket_addr::addr_type::V4(addr) => {
-                let addr = addr?;
-                let ip = addr.get_addr()?;
-                let mut octets = [0u8; 4];
-                for (i, octet) in ip.get_octets()?.iter().enumerate() {
-                    if i > 3 {
-                        return Err(capnp::Error {
-                            kind: capnp::ErrorKind::Failed,
-                            description: "invalid IPv4 address: too many octets".to_owned(),
-                        });
-                    }
-                    octets[i] = octet;
-                }
-                let ip = Ipv4Addr::from(octets);
-                let port = addr.get_port();
+        vec.push(deserialize_address(addr)?);
+    }
+
+    Ok(vec)
+}
 
-                SocketAddr::from((ip, port))
+fn deserialize_address(addr: socket_addr::Reader<'_>) -> capnp::Result<SocketAddr> {
+    let addr = addr.get_addr_type();
+    let addr = match addr.which()? {
+        // TODO(ljedrz/nkls): deduplicate the branches using a macro
+        socket_addr::addr_type::V4(addr) => {
+            let addr = addr?;
+            let ip = addr.get_addr()?;
+            let mut octets = [0u8; 4];
+            for (i, octet) in ip.get_octets()?.iter().enumerate() {
+                if i > 3 {
+                    return Err(capnp::Error {
+                        kind: capnp::ErrorKind::Failed,
+                        description: "invalid IPv4 address: too many octets".to_owned(),
+                    });
+                }
+                octets[i] = octet;
             }
-            socket_addr::addr_type::V6(addr) => {
-                let addr = addr?;
-                let ip = addr.get_addr()?;
-                let mut octets = [0u8; 16];
-                for (i, octet) in ip.get_octets()?.iter().enumerate() {
-                    if i > 15 {
-                        return Err(capnp::Error {
-                            kind: capnp::ErrorKind::Failed,
-                            description: "invalid IPv6 address: too many octets".to_owned(),
-                        });
-                    }
-                    octets[i] = octet;
+            let ip = Ipv4Addr::from(octets);
+            let port = addr.get_port();
+
+            SocketAddr::from((ip, port))
+        }
+        socket_addr::addr_type::V6(addr) => {
+            let addr = addr?;
+            let ip = addr.get_addr()?;
+            let mut octets = [0u8; 16];
+            for (i, octet) in ip.get_octets()?.iter().enumerate() {
+                if i > 15 {
+                    return Err(capnp::Error {
+                        kind: capnp::ErrorKind::Failed,
+                        description: "invalid IPv6 address: too many octets".to_owned(),
+                    });
                 }
-                let ip = Ipv6Addr::from(octets);
-                let port = addr.get_port();
+                octets[i] = octet;
+            }
+            let ip = Ipv6Addr::from(octets);
+            let port = addr.get_port();
 
-                SocketAddr::from((ip, port))
+            SocketAddr::from((ip, port))
+        }
+    };
+
+    Ok(addr)
+}
+
+fn serialize_address(builder: socket_addr::Builder<'_>, addr: &SocketAddr) {
+    let builder = builder.init_addr_type();
+    match addr {
+        SocketAddr::V4(addr) => {
+            let mut addr_builder = builder.init_v4();
+            addr_builder.set_port(addr.port());
+            let addr_builder = addr_builder.init_addr();
+            let mut addr_builder = addr_builder.init_octets(4);
+            for (i, octet) in addr.ip().octets().iter().enumerate() {
+                addr_builder.set(i as u32, *octet);
             }
-        };
-        vec.push(addr);
+        }
+        SocketAddr::V6(addr) => {
+            let mut addr_builder = builder.init_v6();
+            addr_builder.set_port(addr.port());
+            let addr_builder = addr_builder.init_addr();
+            let mut addr_builder = addr_builder.init_octets(16);
+            for (i, octet) in addr.ip().octets().iter().enumerate() {
+                addr_builder.set(i as u32, *octet);
+            }
+        }
     }
-
-    Ok(vec)
 }
 
 fn deserialize_transactions(txs: Transactions<'_>) -> capnp::Result<Payload> {
@@ -283,13 +435,83 @@ fn deserialize_transactions(txs: Transactions<'_>) -> capnp::Result<Payload> {
     Ok(Payload::MemoryPool(vec))
 }
 
+/// Unpacks the first `count` words of a packed capnp message; returns `None` if there are fewer of them.
+fn unpack_words(packed: &[u8], count: usize) -> Option<Vec<u8>> {
+    let mut words = Vec::with_capacity(count * 8 + 8);
+    let mut idx = 0;
+
+    while words.len() < count * 8 {
+        // Every word starts with a tag byte whose bits indicate which of its bytes are non-zero.
+        let tag = *packed.get(idx)?;
+        idx += 1;
+        for bit in 0..8 {
+            if tag & (1 << bit) != 0 {
+                words.push(*packed.get(idx)?);
+                idx += 1;
+            } else {
+                words.push(0);
+            }
+        }
+
+        // A zero word is followed by the number of additional zero words, and a word without zeros
+        // by the number of additional words that are stored unpacked.
+        if tag == 0x00 {
+            let run = *packed.get(idx)? as usize;
+            idx += 1;
+            words.resize(words.len() + run * 8, 0);
+        } else if tag == 0xff {
+            let run = *packed.get(idx)? as usize * 8;
+            idx += 1;
+            let available = run.min(packed.len() - idx);
+            words.extend_from_slice(&packed[idx..][..available]);
+            idx += available;
+        }
+    }
+
+    Some(words)
+}
+
+/// Reads the discriminant of the `payloadType` union from the first bytes of a serialized `Payload`.
+fn peek_payload_type(head: &[u8]) -> Option<u16> {
+    let read_u32 = |words: &[u8], offset: usize| {
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(&words[offset..][..4]);
+        u32::from_le_bytes(bytes)
+    };
+
+    // The segment table is followed by the root pointer, at the beginning of the first segment.
+    let words = unpack_words(head, 1)?;
+    let segment_count = read_u32(&words, 0) as usize + 1;
+    if segment_count > 512 {
+        return None;
+    }
+    let table_len = (4 + 4 * segment_count + 7) / 8;
+
+    let words = unpack_words(head, table_len + 1)?;
+    let root_pointer = read_u32(&words, table_len * 8);
+    let data_len = u16::from_le_bytes([words[table_len * 8 + 4], words[table_len * 8 + 5]]);
+    // Only struct pointers with small offsets are expected; capnp places the root struct right after it.
+    if root_pointer & 0b11 != 0 || (root_pointer as i32) < 0 || root_pointer >> 2 > 8 {
+        return None;
+    }
+    // Omitted data sections imply the default discriminant.
+    if data_len == 0 {
+        return Some(0);
+    }
+
+    let data_start = table_len + 1 + (root_pointer >> 2) as usize;
+    let words = unpack_words(head, data_start + 1)?;
+
+    Some(u16::from_le_bytes([words[data_start * 8], words[data_start * 8 + 1]]))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn serialize_deserialize_empty_payloads() {
-        for payload in &[Payload::GetMemoryPool, Payload::GetPeers, Payload::Pong] {
+        for payload in &[Payload::GetMemoryPool, Payload::GetPeers, Payload::Pong, Payload::Rekey] {
             assert_eq!(
                 Payload::deserialize(&Payload::serialize(payload).unwrap()).unwrap(),
                 *payload
@@ -321,7 +543,9 @@ mod tests {
         for payload in &[
             Payload::GetBlocks(hashes.clone()),
             Payload::GetSync(hashes.clone()),
-            Payload::Sync(hashes),
+            Payload::Sync(hashes.clone()),
+            Payload::BlockHash(hashes[0].clone()),
+            Payload::GetBlock(hashes[1].clone()),
         ] {
             assert_eq!(
                 Payload::deserialize(&Payload::serialize(payload).unwrap()).unwrap(),
@@ -330,6 +554,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn size_limit_matches_payload_type() {
+        let hash = BlockHeaderHash::new(vec![1; 32]);
+        // The large block spans multiple segments, so the segment table is longer.
+        let large_block = Payload::Block(vec![1u8; 1024 * 1024]);
+
+        for (payload, expected) in &[
+            (
+                Payload::Ping(u32::MAX, i64::MAX),
+                ("ping", crate::MAX_CONTROL_PAYLOAD_SIZE),
+            ),
+            (Payload::Pong, ("pong", crate::MAX_CONTROL_PAYLOAD_SIZE)),
+            (
+                Payload::Peers(vec!["127.0.0.1:4131".parse().unwrap()]),
+                ("peers", crate::MAX_PEERS_PAYLOAD_SIZE),
+            ),
+            (
+                Payload::Sync(vec![hash.clone(); 64]),
+                ("sync", crate::MAX_BLOCK_HASHES_PAYLOAD_SIZE),
+            ),
+            (Payload::GetBlock(hash), ("getblock", crate::MAX_CONTROL_PAYLOAD_SIZE)),
+            (Payload::Rekey, ("rekey", crate::MAX_CONTROL_PAYLOAD_SIZE)),
+            (large_block, ("block", crate::MAX_MESSAGE_SIZE)),
+        ] {
+            let serialized = Payload::serialize(payload).unwrap();
+            assert!(serialized.len() <= expected.1);
+            assert_eq!(Payload::size_limit(&serialized), Some(*expected));
+            assert_eq!(Payload::is_rekey(&serialized), *payload == Payload::Rekey);
+        }
+
+        assert_eq!(Payload::size_limit(&[]), None);
+    }
+
     #[test]
     fn serialize_deserialize_peers() {
         let addrs: Vec<SocketAddr> = [
@@ -351,10 +608,41 @@ mod tests {
         );
     }
 
+    #[test]
+    fn serialize_deserialize_node_alias() {
+        let payload = Payload::NodeAlias(NodeAlias {
+            alias: "aleo-bootnode".into(),
+            public_key: vec![1; 32],
+            signature: vec![2; 64],
+        });
+
+        assert_eq!(
+            Payload::deserialize(&Payload::serialize(&payload).unwrap()).unwrap(),
+            payload
+        );
+    }
+
+    #[test]
+    fn serialize_deserialize_address_change() {
+        let payload = Payload::AddressChange(AddressChange {
+            previous: "0.0.0.0:4131".parse().unwrap(),
+            address: "[2001:db8::1]:4141".parse().unwrap(),
+            public_key: vec![1; 32],
+            signature: vec![2; 64],
+        });
+        let serialized = Payload::serialize(&payload).unwrap();
+
+        assert_eq!(Payload::deserialize(&serialized).unwrap(), payload);
+        assert_eq!(
+            Payload::size_limit(&serialized),
+            Some(("addresschange", crate::MAX_CONTROL_PAYLOAD_SIZE))
+        );
+    }
+
     #[test]
     fn serialize_deserialize_ping() {
         for i in 0u8..255 {
-            let payload = Payload::Ping(i as u32);
+            let payload = Payload::Ping(i as u32, i as i64);
 
             assert_eq!(
                 Payload::deserialize(&Payload::serialize(&payload).unwrap()).unwrap(),
@@ -365,11 +653,72 @@ mod tests {
 
     #[test]
     fn serialize_deserialize_version() {
-        let version = Version::new(crate::PROTOCOL_VERSION, 4141, 0);
+        for external_address in &[None, Some("203.0.113.7:4141".parse().unwrap())] {
+            let version = Version::new(crate::PROTOCOL_VERSION, 4141, 0)
+                .with_external_address(*external_address)
+                .with_earliest_block_height(1000)
+                .with_features(Features::from_bits(0b101))
+                .with_network_id(NetworkId::Devnet)
+                .with_timestamp(1_600_000_000);
 
-        assert_eq!(
-            Version::deserialize(&Version::serialize(&version).unwrap()).unwrap(),
-            version
-        );
+            assert_eq!(
+                Version::deserialize(&Version::serialize(&version).unwrap()).unwrap(),
+                version
+            );
+        }
+    }
+
+    #[test]
+    fn deserialize_version_without_features() {
+        // Peers predating the feature negotiation, the network ids and the timestamps don't set the fields at all.
+        let mut message = capnp::message::Builder::new_default();
+        let mut builder = message.init_root::<version::Builder>();
+        builder.set_version(crate::PROTOCOL_VERSION);
+        builder.set_listening_port(4141);
+        let mut bytes = Vec::new();
+        capnp::serialize_packed::write_message(&mut bytes, &message).unwrap();
+
+        let version = Version::deserialize(&bytes).unwrap();
+        assert_eq!(version.features, Features::empty());
+        assert_eq!(version.network_id, NetworkId::Testnet1);
+        assert_eq!(version.timestamp, 0);
+        assert_eq!(version.listening_port, 4141);
+    }
+
+    /// Serializes a payload of a type introduced by a newer version of the protocol.
+    fn future_payload(type_id: u16, flags: PayloadFlags) -> Vec<u8> {
+        let mut message = capnp::message::Builder::new_default();
+        let mut root = message.init_root::<payload::Builder>();
+        root.set_flags(flags.bits());
+        root.init_payload_type().init_pong();
+
+        // The segment table of the single segment is followed by the root pointer and the data section of the
+        // root struct, which starts with the discriminant of the payload type.
+        let mut words = Vec::new();
+        capnp::serialize::write_message(&mut words, &message).unwrap();
+        words[16..18].copy_from_slice(&type_id.to_le_bytes());
+
+        let reader = capnp::serialize::read_message(&mut &words[..], capnp::message::ReaderOptions::new()).unwrap();
+        let mut message = capnp::message::Builder::new_default();
+        message
+            .set_root(reader.get_root::<capnp::any_pointer::Reader>().unwrap())
+            .unwrap();
+        let mut bytes = Vec::new();
+        capnp::serialize_packed::write_message(&mut bytes, &message).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn deserialize_unknown_payloads() {
+        for flags in [PayloadFlags::empty(), PayloadFlags::CRITICAL] {
+            let bytes = future_payload(42, flags);
+
+            assert_eq!(Payload::size_limit(&bytes), None);
+            assert_eq!(Payload::deserialize(&bytes).unwrap(), Payload::Unknown(42, flags));
+        }
+
+        // The known payloads don't need any flags.
+        let pong = Payload::deserialize(&Payload::serialize(&Payload::Pong).unwrap()).unwrap();
+        assert_eq!(pong.flags(), PayloadFlags::empty());
     }
 }
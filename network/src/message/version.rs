@@ -14,6 +14,79 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
+use std::{fmt, net::SocketAddr, ops::BitOr};
+
+use crate::NetworkId;
+
+/// A set of optional protocol extensions, e.g. new payload types, that a node supports. Only the
+/// extensions supported by both sides of a connection are used, and unknown flags are ignored, so
+/// that new extensions don't break compatibility with older peers.
+#[derive(Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub struct Features(u64);
+
+impl Features {
+    /// The support for the `NodeAlias` payload.
+    pub const NODE_ALIAS: Features = Features(1 << 0);
+    /// The support for block propagation via `BlockHash` announcements and `GetBlock` requests.
+    pub const COMPACT_BLOCKS: Features = Features(1 << 1);
+    /// Not an extension, but a capability: the sender is a watcher node that only follows the block headers
+    /// and the memory pool, so it can't serve blocks or transactions. It isn't subject to negotiation.
+    pub const HEADERS_ONLY: Features = Features(1 << 2);
+    /// The support for the `Rekey` payload, which lets either side periodically replace its transport key.
+    pub const REKEY: Features = Features(1 << 3);
+    /// The support for the RPC channel over the P2P port; it's only advertised by the nodes serving it.
+    pub const RPC: Features = Features(1 << 4);
+    /// Not an extension, but a capability: the sender is a management client that only uses the RPC channel, so
+    /// it isn't a peer. It isn't subject to negotiation.
+    pub const RPC_CLIENT: Features = Features(1 << 5);
+    /// The support for the `AddressChange` payload, which lets a node announce that its listening address changed.
+    pub const ADDRESS_CHANGE: Features = Features(1 << 6);
+
+    /// Returns a set containing no extensions.
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Returns the set corresponding to the given bitfield.
+    pub const fn from_bits(bits: u64) -> Self {
+        Self(bits)
+    }
+
+    /// Returns the bitfield representation of the set.
+    pub const fn bits(self) -> u64 {
+        self.0
+    }
+
+    /// Returns `true` if all the given extensions are in the set.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns the extensions present in both sets.
+    pub const fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    /// Returns the extensions present in either set.
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl BitOr for Features {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        self.union(other)
+    }
+}
+
+impl fmt::Debug for Features {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Features({:#b})", self.0)
+    }
+}
+
 // todo: readd in Rust 1.54
 // #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/version.md"))]
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
@@ -24,14 +97,61 @@ pub struct Version {
     pub listening_port: u16,
     /// The node id of the sender.
     pub node_id: u64,
+    /// The address the sender is reachable at, if it's configured or was discovered via NAT traversal.
+    pub external_address: Option<SocketAddr>,
+    /// The height of the earliest block the sender is able to serve; it's non-zero for pruned nodes.
+    pub earliest_block_height: u32,
+    /// The optional protocol extensions the sender supports.
+    pub features: Features,
+    /// The network the sender is a part of.
+    pub network_id: NetworkId,
+    /// The sender's UNIX timestamp, in seconds; it's 0 for the nodes that don't report it.
+    pub timestamp: i64,
 }
 
 impl Version {
+    /// Creates a `Version` of an unpruned node of the default network, without an external address, optional
+    /// extensions or a timestamp; the `with_*` methods set the remaining fields.
     pub fn new(version: u64, listening_port: u16, node_id: u64) -> Self {
         Self {
             version,
             listening_port,
             node_id,
+            external_address: None,
+            earliest_block_height: 0,
+            features: Features::empty(),
+            network_id: NetworkId::default(),
+            timestamp: 0,
         }
     }
+
+    /// Sets the address the sender is reachable at.
+    pub fn with_external_address(mut self, external_address: Option<SocketAddr>) -> Self {
+        self.external_address = external_address;
+        self
+    }
+
+    /// Sets the height of the earliest block the sender is able to serve.
+    pub fn with_earliest_block_height(mut self, earliest_block_height: u32) -> Self {
+        self.earliest_block_height = earliest_block_height;
+        self
+    }
+
+    /// Sets the optional protocol extensions the sender supports.
+    pub fn with_features(mut self, features: Features) -> Self {
+        self.features = features;
+        self
+    }
+
+    /// Sets the network the sender is a part of.
+    pub fn with_network_id(mut self, network_id: NetworkId) -> Self {
+        self.network_id = network_id;
+        self
+    }
+
+    /// Sets the sender's UNIX timestamp, in seconds.
+    pub fn with_timestamp(mut self, timestamp: i64) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
 }
@@ -0,0 +1,160 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+pub mod natpmp;
+pub mod upnp;
+
+use crate::{NetworkError, Node};
+use snarkvm_dpc::Storage;
+
+use std::{
+    fs,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::Duration,
+};
+use tokio::{net::UdpSocket, task, time::sleep};
+
+/// The requested lifetime of a port mapping; it is renewed halfway through.
+pub const NAT_MAPPING_LIFETIME_SECS: u32 = 3600;
+/// The maximum amount of time a single NAT traversal request can take.
+pub const NAT_REQUEST_TIMEOUT_SECS: u8 = 3;
+/// The delay before the first retry of a failed port mapping; it's doubled after each consecutive failure.
+const NAT_RETRY_INITIAL_DELAY: Duration = Duration::from_secs(30);
+/// The longest delay between the retries of a failed port mapping.
+const NAT_RETRY_MAX_DELAY: Duration = Duration::from_secs(NAT_MAPPING_LIFETIME_SECS as u64);
+
+/// The protocol that was used to establish a port mapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NatProtocol {
+    NatPmp,
+    Upnp,
+}
+
+/// A port mapping established on the local gateway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PortMapping {
+    /// The protocol used to establish the mapping.
+    pub protocol: NatProtocol,
+    /// The publicly routable address of this node.
+    pub external_address: SocketAddr,
+    /// The lifetime of the mapping granted by the gateway.
+    pub lifetime: Duration,
+}
+
+/// Attempts to map the given local listening port on the gateway, trying NAT-PMP first and
+/// falling back to UPnP if the gateway doesn't support it.
+pub async fn map_port(internal_port: u16) -> Result<PortMapping, NetworkError> {
+    if let Some(gateway) = default_gateway() {
+        match natpmp::map_port(gateway, internal_port, NAT_MAPPING_LIFETIME_SECS).await {
+            Ok(mapping) => return Ok(mapping),
+            Err(e) => debug!("NAT-PMP port mapping via {} failed: {}", gateway, e),
+        }
+    }
+
+    upnp::map_port(internal_port, NAT_MAPPING_LIFETIME_SECS).await
+}
+
+impl<S: Storage + Send + Sync + 'static> Node<S> {
+    /// Spawns a task that maps the given listening port on the local gateway and keeps renewing
+    /// the mapping; the discovered external address is then advertised in the handshake. Failed
+    /// mappings and renewals are retried with an exponential backoff.
+    pub(crate) fn map_listening_port(&self, port: u16) {
+        let node = self.clone();
+        let mapping_task = task::spawn(async move {
+            let mut retry_delay = NAT_RETRY_INITIAL_DELAY;
+            loop {
+                let mapping = match map_port(port).await {
+                    Ok(mapping) => mapping,
+                    Err(e) => {
+                        warn!(
+                            "Couldn't map the listening port on the gateway: {}; retrying in {}s",
+                            e,
+                            retry_delay.as_secs()
+                        );
+                        sleep(retry_delay).await;
+                        retry_delay = (retry_delay * 2).min(NAT_RETRY_MAX_DELAY);
+                        continue;
+                    }
+                };
+                retry_delay = NAT_RETRY_INITIAL_DELAY;
+
+                if node.external_address() != Some(mapping.external_address) {
+                    node.set_external_address(mapping.external_address);
+                    info!(
+                        "Mapped the listening port via {:?}; the external address is {}",
+                        mapping.protocol, mapping.external_address
+                    );
+                }
+
+                sleep((mapping.lifetime / 2).max(Duration::from_secs(60))).await;
+            }
+        });
+
+        self.register_task(mapping_task);
+    }
+}
+
+/// Returns the address of this machine that is used to reach the given gateway.
+pub(crate) async fn local_ip_towards(gateway: SocketAddr) -> Result<IpAddr, NetworkError> {
+    let unspecified = match gateway {
+        SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        SocketAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+    };
+    let socket = UdpSocket::bind((unspecified, 0)).await?;
+    socket.connect(gateway).await?;
+
+    Ok(socket.local_addr()?.ip())
+}
+
+/// Returns the IPv4 address of the default gateway, if it can be determined.
+pub fn default_gateway() -> Option<Ipv4Addr> {
+    // The routing table is only readily available on Linux; other platforms fall back to UPnP discovery.
+    let routes = fs::read_to_string("/proc/net/route").ok()?;
+
+    parse_default_gateway(&routes)
+}
+
+fn parse_default_gateway(routes: &str) -> Option<Ipv4Addr> {
+    for line in routes.lines().skip(1) {
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        if fields.len() < 3 || fields[1] != "00000000" {
+            continue;
+        }
+        // The gateway is stored as a little-endian hex-encoded u32.
+        if let Ok(gateway) = u32::from_str_radix(fields[2], 16) {
+            if gateway != 0 {
+                return Some(Ipv4Addr::from(gateway.swap_bytes()));
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_route_table() {
+        let routes = "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT\n\
+                      eth0\t00000000\t0101A8C0\t0003\t0\t0\t100\t00000000\t0\t0\t0\n\
+                      eth0\t0001A8C0\t00000000\t0001\t0\t0\t100\t00FFFFFF\t0\t0\t0\n";
+
+        assert_eq!(parse_default_gateway(routes), Some(Ipv4Addr::new(192, 168, 1, 1)));
+        assert_eq!(parse_default_gateway(""), None);
+    }
+}
@@ -0,0 +1,101 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+//! A minimal NAT-PMP (RFC 6886) client.
+
+use super::{NatProtocol, PortMapping, NAT_REQUEST_TIMEOUT_SECS};
+use crate::NetworkError;
+
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+use tokio::{net::UdpSocket, time::timeout};
+
+/// The port NAT-PMP gateways listen on.
+const NATPMP_PORT: u16 = 5351;
+/// The only NAT-PMP protocol version.
+const NATPMP_VERSION: u8 = 0;
+/// The opcode requesting the gateway's external address.
+const OP_EXTERNAL_ADDRESS: u8 = 0;
+/// The opcode requesting a TCP port mapping.
+const OP_MAP_TCP: u8 = 2;
+/// The offset added to an opcode in the gateway's response.
+const OP_RESPONSE: u8 = 128;
+/// The number of times a request is sent before giving up.
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Maps the given internal TCP port via NAT-PMP and returns the resulting external address.
+pub async fn map_port(gateway: Ipv4Addr, internal_port: u16, lifetime_secs: u32) -> Result<PortMapping, NetworkError> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    socket.connect(SocketAddr::from((gateway, NATPMP_PORT))).await?;
+
+    // Request the external address.
+    let response = request(&socket, &[NATPMP_VERSION, OP_EXTERNAL_ADDRESS], 12).await?;
+    check_response(&response, OP_EXTERNAL_ADDRESS)?;
+    let external_ip = Ipv4Addr::new(response[8], response[9], response[10], response[11]);
+
+    // Request the port mapping; the same external port is suggested for simplicity.
+    let mut mapping_request = [0u8; 12];
+    mapping_request[0] = NATPMP_VERSION;
+    mapping_request[1] = OP_MAP_TCP;
+    mapping_request[4..6].copy_from_slice(&internal_port.to_be_bytes());
+    mapping_request[6..8].copy_from_slice(&internal_port.to_be_bytes());
+    mapping_request[8..12].copy_from_slice(&lifetime_secs.to_be_bytes());
+
+    let response = request(&socket, &mapping_request, 16).await?;
+    check_response(&response, OP_MAP_TCP)?;
+    let external_port = u16::from_be_bytes([response[10], response[11]]);
+    let granted_lifetime = u32::from_be_bytes([response[12], response[13], response[14], response[15]]);
+
+    Ok(PortMapping {
+        protocol: NatProtocol::NatPmp,
+        external_address: SocketAddr::from((external_ip, external_port)),
+        lifetime: Duration::from_secs(granted_lifetime.into()),
+    })
+}
+
+/// Sends the request to the gateway, retrying with an increasing delay as recommended by the RFC.
+async fn request(socket: &UdpSocket, request: &[u8], response_len: usize) -> Result<Vec<u8>, NetworkError> {
+    let mut buffer = vec![0u8; response_len];
+    let mut wait = Duration::from_millis(250);
+
+    for _ in 0..MAX_ATTEMPTS {
+        socket.send(request).await?;
+
+        match timeout(wait, socket.recv(&mut buffer)).await {
+            Ok(Ok(len)) if len >= response_len => return Ok(buffer),
+            Ok(Ok(_)) => return Err(NetworkError::NatTraversal("truncated NAT-PMP response".into())),
+            Ok(Err(e)) => return Err(e.into()),
+            Err(_) => wait = (wait * 2).min(Duration::from_secs(NAT_REQUEST_TIMEOUT_SECS as u64)),
+        }
+    }
+
+    Err(NetworkError::NatTraversal(
+        "the gateway didn't respond to NAT-PMP".into(),
+    ))
+}
+
+fn check_response(response: &[u8], opcode: u8) -> Result<(), NetworkError> {
+    if response[0] != NATPMP_VERSION || response[1] != OP_RESPONSE + opcode {
+        return Err(NetworkError::NatTraversal("unexpected NAT-PMP response".into()));
+    }
+
+    match u16::from_be_bytes([response[2], response[3]]) {
+        0 => Ok(()),
+        code => Err(NetworkError::NatTraversal(format!("NAT-PMP result code {}", code))),
+    }
+}
@@ -0,0 +1,247 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+//! A minimal UPnP Internet Gateway Device client, supporting only what is needed to map a TCP port.
+
+use super::{local_ip_towards, NatProtocol, PortMapping, NAT_REQUEST_TIMEOUT_SECS};
+use crate::NetworkError;
+
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpStream, UdpSocket},
+    time::timeout,
+};
+
+/// The SSDP multicast address.
+const SSDP_IP: Ipv4Addr = Ipv4Addr::new(239, 255, 255, 250);
+/// The SSDP multicast port.
+const SSDP_PORT: u16 = 1900;
+/// The SSDP search target for gateway devices.
+const SEARCH_TARGET: &str = "urn:schemas-upnp-org:device:InternetGatewayDevice:1";
+/// The WAN services capable of mapping ports, in order of preference.
+const WAN_SERVICES: [&str; 2] = [
+    "urn:schemas-upnp-org:service:WANIPConnection:1",
+    "urn:schemas-upnp-org:service:WANPPPConnection:1",
+];
+/// The maximum size of a response from the gateway.
+const MAX_RESPONSE_SIZE: usize = 64 * 1024;
+
+/// The control endpoint of a gateway's WAN service.
+struct Gateway {
+    address: SocketAddr,
+    control_path: String,
+    service: &'static str,
+}
+
+/// Maps the given internal TCP port via UPnP and returns the resulting external address.
+pub async fn map_port(internal_port: u16, lifetime_secs: u32) -> Result<PortMapping, NetworkError> {
+    let gateway = discover_gateway().await?;
+    let internal_ip = local_ip_towards(gateway.address).await?;
+
+    let response = gateway.soap_request("GetExternalIPAddress", "").await?;
+    let external_ip = xml_value(&response, "NewExternalIPAddress")
+        .and_then(|ip| ip.parse::<IpAddr>().ok())
+        .ok_or_else(|| NetworkError::NatTraversal("the gateway didn't provide its external address".into()))?;
+
+    let arguments = format!(
+        "<NewRemoteHost></NewRemoteHost>\
+         <NewExternalPort>{port}</NewExternalPort>\
+         <NewProtocol>TCP</NewProtocol>\
+         <NewInternalPort>{port}</NewInternalPort>\
+         <NewInternalClient>{client}</NewInternalClient>\
+         <NewEnabled>1</NewEnabled>\
+         <NewPortMappingDescription>snarkOS</NewPortMappingDescription>\
+         <NewLeaseDuration>{lifetime}</NewLeaseDuration>",
+        port = internal_port,
+        client = internal_ip,
+        lifetime = lifetime_secs,
+    );
+    gateway.soap_request("AddPortMapping", &arguments).await?;
+
+    Ok(PortMapping {
+        protocol: NatProtocol::Upnp,
+        external_address: SocketAddr::new(external_ip, internal_port),
+        lifetime: Duration::from_secs(lifetime_secs.into()),
+    })
+}
+
+/// Finds a gateway via SSDP and locates the control endpoint of its WAN service.
+async fn discover_gateway() -> Result<Gateway, NetworkError> {
+    let ssdp_address = SocketAddr::from((SSDP_IP, SSDP_PORT));
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    let search = format!(
+        "M-SEARCH * HTTP/1.1\r\nHOST: {}\r\nST: {}\r\nMAN: \"ssdp:discover\"\r\nMX: {}\r\n\r\n",
+        ssdp_address, SEARCH_TARGET, NAT_REQUEST_TIMEOUT_SECS
+    );
+    socket.send_to(search.as_bytes(), ssdp_address).await?;
+
+    let mut buffer = vec![0u8; 2048];
+    let (len, _) = timeout(
+        Duration::from_secs(NAT_REQUEST_TIMEOUT_SECS as u64),
+        socket.recv_from(&mut buffer),
+    )
+    .await
+    .map_err(|_| NetworkError::NatTraversal("no UPnP gateway was found".into()))??;
+    let response = String::from_utf8_lossy(&buffer[..len]);
+
+    let location = response
+        .lines()
+        .find_map(|line| {
+            let (name, value) = line.split_at(line.find(':')?);
+            if name.trim().eq_ignore_ascii_case("location") {
+                Some(value[1..].trim().to_owned())
+            } else {
+                None
+            }
+        })
+        .ok_or_else(|| NetworkError::NatTraversal("the UPnP gateway didn't provide its location".into()))?;
+    let (address, description_path) = parse_url(&location)?;
+
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+        description_path, address
+    );
+    let description = http_request(address, &request).await?;
+
+    for service in WAN_SERVICES.iter().copied() {
+        if let Some(service_start) = description.find(service) {
+            if let Some(control_url) = xml_value(&description[service_start..], "controlURL") {
+                let control_path = if control_url.starts_with("http://") {
+                    parse_url(&control_url)?.1
+                } else {
+                    control_url
+                };
+
+                return Ok(Gateway {
+                    address,
+                    control_path,
+                    service,
+                });
+            }
+        }
+    }
+
+    Err(NetworkError::NatTraversal("the UPnP gateway has no WAN service".into()))
+}
+
+impl Gateway {
+    /// Invokes the given action on the gateway's WAN service and returns the response body.
+    async fn soap_request(&self, action: &str, arguments: &str) -> Result<String, NetworkError> {
+        let body = format!(
+            "<?xml version=\"1.0\"?>\
+             <s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" \
+             s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\">\
+             <s:Body><u:{action} xmlns:u=\"{service}\">{arguments}</u:{action}></s:Body></s:Envelope>",
+            action = action,
+            service = self.service,
+            arguments = arguments,
+        );
+        let request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: text/xml; charset=\"utf-8\"\r\n\
+             SOAPAction: \"{}#{}\"\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.control_path,
+            self.address,
+            self.service,
+            action,
+            body.len(),
+            body
+        );
+
+        let response = http_request(self.address, &request).await?;
+        if !response.starts_with("HTTP/1.1 200") && !response.starts_with("HTTP/1.0 200") {
+            return Err(NetworkError::NatTraversal(format!(
+                "UPnP action {} was rejected",
+                action
+            )));
+        }
+
+        Ok(response)
+    }
+}
+
+/// Sends a raw HTTP request and returns the full response.
+async fn http_request(address: SocketAddr, request: &str) -> Result<String, NetworkError> {
+    let exchange = async {
+        let mut stream = TcpStream::connect(address).await?;
+        stream.write_all(request.as_bytes()).await?;
+
+        let mut response = Vec::new();
+        stream.take(MAX_RESPONSE_SIZE as u64).read_to_end(&mut response).await?;
+
+        Ok::<_, NetworkError>(String::from_utf8_lossy(&response).into_owned())
+    };
+
+    timeout(Duration::from_secs(NAT_REQUEST_TIMEOUT_SECS as u64), exchange)
+        .await
+        .map_err(|_| NetworkError::NatTraversal("the UPnP gateway timed out".into()))?
+}
+
+/// Splits an `http://host:port/path` URL into a socket address and a path.
+fn parse_url(url: &str) -> Result<(SocketAddr, String), NetworkError> {
+    let invalid = || NetworkError::NatTraversal(format!("invalid UPnP URL: {}", url));
+
+    let url = url.strip_prefix("http://").ok_or_else(invalid)?;
+    let (host, path) = match url.find('/') {
+        Some(idx) => (&url[..idx], url[idx..].to_owned()),
+        None => (url, "/".to_owned()),
+    };
+    let address = match host.parse::<SocketAddr>() {
+        Ok(address) => address,
+        Err(_) => SocketAddr::new(host.parse::<IpAddr>().map_err(|_| invalid())?, 80),
+    };
+
+    Ok((address, path))
+}
+
+/// Returns the contents of the first occurrence of the given XML element.
+fn xml_value(xml: &str, element: &str) -> Option<String> {
+    let start_tag = format!("<{}>", element);
+    let start = xml.find(&start_tag)? + start_tag.len();
+    let end = start + xml[start..].find(&format!("</{}>", element))?;
+
+    Some(xml[start..end].trim().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_gateway_urls() {
+        assert_eq!(
+            parse_url("http://192.168.1.1:5000/rootDesc.xml").unwrap(),
+            ("192.168.1.1:5000".parse().unwrap(), "/rootDesc.xml".to_owned())
+        );
+        assert_eq!(
+            parse_url("http://192.168.1.1").unwrap(),
+            ("192.168.1.1:80".parse().unwrap(), "/".to_owned())
+        );
+        assert!(parse_url("https://192.168.1.1/").is_err());
+    }
+
+    #[test]
+    fn extract_xml_values() {
+        let xml = "<service><serviceType>urn:schemas-upnp-org:service:WANIPConnection:1</serviceType>\
+                   <controlURL>/ctl/IPConn</controlURL></service>";
+
+        assert_eq!(xml_value(xml, "controlURL"), Some("/ctl/IPConn".to_owned()));
+        assert_eq!(xml_value(xml, "eventSubURL"), None);
+    }
+}
@@ -0,0 +1,110 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{fmt, str::FromStr};
+
+use crate::NetworkError;
+
+/// The network a node is a part of; nodes only connect to the peers on the same network, so that one binary can
+/// safely run against several of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NetworkId {
+    Mainnet,
+    Testnet1,
+    /// A network meant for local development, e.g. a handful of nodes with a fresh chain.
+    Devnet,
+    /// Any other network, identified by its numeric id alone.
+    Custom(u8),
+}
+
+impl NetworkId {
+    /// Returns the network with the given numeric id.
+    pub const fn from_id(id: u8) -> Self {
+        match id {
+            0 => Self::Mainnet,
+            1 => Self::Testnet1,
+            2 => Self::Devnet,
+            id => Self::Custom(id),
+        }
+    }
+
+    /// Returns the numeric id of the network, which is exchanged in the handshake.
+    pub const fn id(self) -> u8 {
+        match self {
+            Self::Mainnet => 0,
+            Self::Testnet1 => 1,
+            Self::Devnet => 2,
+            Self::Custom(id) => id,
+        }
+    }
+}
+
+/// The nodes predating the network ids are all on `Testnet1`, which is also the id assumed for the peers that
+/// don't advertise one.
+impl Default for NetworkId {
+    fn default() -> Self {
+        Self::Testnet1
+    }
+}
+
+impl fmt::Display for NetworkId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Mainnet => write!(f, "mainnet"),
+            Self::Testnet1 => write!(f, "testnet1"),
+            Self::Devnet => write!(f, "devnet"),
+            Self::Custom(id) => write!(f, "custom{}", id),
+        }
+    }
+}
+
+/// Parses either the name of a network, e.g. `devnet` or `custom7`, or its numeric id.
+impl FromStr for NetworkId {
+    type Err = NetworkError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s {
+            "mainnet" => Ok(Self::Mainnet),
+            "testnet1" => Ok(Self::Testnet1),
+            "devnet" => Ok(Self::Devnet),
+            _ => s
+                .strip_prefix("custom")
+                .unwrap_or(s)
+                .parse::<u8>()
+                .map(Self::from_id)
+                .map_err(|_| NetworkError::InvalidNetworkId(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn network_ids_round_trip() {
+        for id in 0..=u8::MAX {
+            let network = NetworkId::from_id(id);
+            assert_eq!(network.id(), id);
+            assert_eq!(network.to_string().parse::<NetworkId>().unwrap(), network);
+            assert_eq!(id.to_string().parse::<NetworkId>().unwrap(), network);
+        }
+
+        assert!("testnet2".parse::<NetworkId>().is_err());
+        assert!("custom256".parse::<NetworkId>().is_err());
+    }
+}
@@ -14,26 +14,39 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{master::SyncInbound, sync::master::SyncMaster, *};
-use snarkos_metrics::{self as metrics, inbound, misc};
+use crate::*;
+#[cfg(feature = "consensus")]
+use crate::{master::SyncInbound, sync::master::SyncMaster};
+#[cfg(feature = "crawler")]
+use snarkos_consensus::MerkleTreeLedger;
+#[cfg(feature = "consensus")]
+use snarkos_consensus::MiningController;
+use snarkos_metrics::{self as metrics, history::StatsHistory, inbound, misc, snapshots::NodeStats, stats::NODE_STATS};
 use snarkvm_dpc::Storage;
 
+use arc_swap::ArcSwapOption;
 use chrono::{DateTime, Utc};
 use once_cell::sync::OnceCell;
 use rand::{thread_rng, Rng};
+#[cfg(not(feature = "consensus"))]
+use std::marker::PhantomData;
 use std::{
     net::SocketAddr,
     ops::Deref,
     sync::{
         atomic::{AtomicBool, AtomicU8, Ordering},
         Arc,
+        Mutex,
     },
     thread,
+    time::{Duration, Instant},
 };
+#[cfg(feature = "consensus")]
+use tokio::sync::{broadcast, mpsc, RwLock};
 use tokio::{
-    sync::{mpsc, RwLock},
+    sync::{Notify, Semaphore},
     task,
-    time::sleep,
+    time::{sleep, timeout},
 };
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
@@ -41,7 +54,6 @@ use tokio::{
 pub enum State {
     Idle = 0,
     Mining,
-    Syncing,
 }
 
 #[derive(Default)]
@@ -54,24 +66,90 @@ pub struct InnerNode<S: Storage + core::marker::Sync + Send + 'static> {
     /// The current state of the node.
     state: StateCode,
     /// The local address of this node.
-    pub local_address: OnceCell<SocketAddr>,
+    pub local_address: ArcSwapOption<SocketAddr>,
+    /// The external address of this node, if it was discovered via NAT traversal.
+    pub external_address: ArcSwapOption<SocketAddr>,
     /// The pre-configured parameters of this node.
     pub config: Config,
     /// The inbound handler of this node.
     pub inbound: Inbound,
     /// The list of connected and disconnected peers of this node.
     pub peer_book: PeerBook,
+    /// The IPs and subnets banned at runtime; they're kept apart from the peer book and persisted in storage,
+    /// so they survive the removal of the peers and restarts.
+    pub bans: BanList,
     /// The sync handler of this node.
+    #[cfg(feature = "consensus")]
     pub sync: OnceCell<Arc<Sync<S>>>,
+    /// The controller of the mining threads; only present if the node is allowed to mine.
+    #[cfg(feature = "consensus")]
+    pub mining: OnceCell<Arc<MiningController<S>>>,
+    /// The headers and memory pool followed by this node; only present in watcher mode.
+    #[cfg(feature = "consensus")]
+    pub watcher: OnceCell<Arc<Watcher>>,
+    /// The handler of the RPC requests sent over the P2P port; only present if the node serves them.
+    pub rpc_handler: OnceCell<Arc<dyn RpcHandler>>,
     /// The node's start-up timestamp.
     pub launched: DateTime<Utc>,
+    /// The recent periodic snapshots of the node's stats.
+    pub stats_history: StatsHistory,
+    /// The schedule of the peer maintenance rounds.
+    pub peer_sync_schedule: PeerSyncSchedule,
+    /// The connections between other nodes discovered while crawling; only present in crawler mode.
+    #[cfg(feature = "crawler")]
+    pub known_network: Option<KnownNetwork>,
+    /// The storage the crawled network is persisted in; crawlers don't have a sync layer to provide it.
+    #[cfg(feature = "crawler")]
+    pub crawler_storage: OnceCell<Arc<MerkleTreeLedger<S>>>,
+    /// The time the last snapshot of the crawled network was stored at.
+    #[cfg(feature = "crawler")]
+    pub(crate) last_network_snapshot: Mutex<Option<DateTime<Utc>>>,
+    /// The webhook the node's notable events are reported to, if one is configured.
+    pub webhook: Option<Webhook>,
+    /// The state of the alarms raised according to the configured rules.
+    pub alarms: Alarms,
+    /// The journal of the notable events in the life of the node; the most recent ones are included in its
+    /// crash reports.
+    pub journal: Journal,
+    /// The sink persisting the journal in storage, if it's persisted there; the journal's queries are served from it.
+    #[cfg(feature = "consensus")]
+    pub(crate) journal_storage: OnceCell<Arc<StorageSink<S>>>,
+    /// The callbacks registered by the applications embedding the node; they're shared with the peer book.
+    pub callbacks: Arc<NodeCallbacks>,
+    /// The estimate of the network's time, based on the clocks of the peers.
+    pub clock: NetworkClock,
+    /// The last time the recommended peers were fetched, or exported in crawler mode.
+    pub(crate) recommended_peers_updated: Mutex<Option<Instant>>,
+    /// The latest change of the node's announced address, detected on start-up.
+    pub(crate) address_change: Mutex<Option<PendingAddressChange>>,
+    /// The permits for the outbound handshakes; there are `max_concurrent_handshakes` of them.
+    pub(crate) handshake_permits: Semaphore,
+    /// The pool probing the reachability of the gossiped addresses, if the probes are enabled.
+    pub(crate) reachability_verifier: Option<ReachabilityVerifier>,
+    /// The node-wide cap on the bandwidth used by the payloads written to the peers, if any.
+    pub(crate) upload_limit: Option<Arc<BandwidthLimit>>,
+    /// The faults injected into the node's links with its peers; only used in tests.
+    #[cfg(feature = "fault-injection")]
+    pub fault_injector: FaultInjector,
+    /// The source of the reloadable configuration of the node, if it can be reloaded.
+    config_source: OnceCell<ConfigSource>,
+    /// Notifies the periodic tasks of the node that its configuration was reloaded.
+    config_reloaded: Notify,
     /// The tasks spawned by the node.
     tasks: DropJoin<task::JoinHandle<()>>,
+    /// The services started by `start_services`; they're stopped when the node shuts down.
+    started_services: tokio::sync::Mutex<ServiceRegistry>,
     /// The threads spawned by the node.
     threads: DropJoin<thread::JoinHandle<()>>,
     /// An indicator of whether the node is shutting down.
     shutting_down: AtomicBool,
+    /// The task accepting inbound connections; it is replaced whenever the listener is rebound.
+    pub(crate) listener: Mutex<Option<task::JoinHandle<()>>>,
+    #[cfg(feature = "consensus")]
     pub(crate) master_dispatch: RwLock<Option<mpsc::Sender<SyncInbound>>>,
+    /// Without the `consensus` feature, there's no sync layer to hold the storage.
+    #[cfg(not(feature = "consensus"))]
+    storage: PhantomData<S>,
 }
 
 /// A core data structure for operating the networking stack of this node.
@@ -94,7 +172,6 @@ impl<S: Storage + core::marker::Sync + Send + 'static> Node<S> {
         match self.state.0.load(Ordering::SeqCst) {
             0 => State::Idle,
             1 => State::Mining,
-            2 => State::Syncing,
             _ => unreachable!(),
         }
     }
@@ -111,47 +188,289 @@ impl<S: Storage + core::marker::Sync + Send + 'static> Node<S> {
 impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
     /// Creates a new instance of `Node`.
     pub async fn new(config: Config) -> Result<Self, NetworkError> {
-        Ok(Self(Arc::new(InnerNode {
-            id: thread_rng().gen(),
+        #[cfg(feature = "crawler")]
+        let known_network = if config.is_crawler() {
+            Some(Default::default())
+        } else {
+            None
+        };
+
+        let id = thread_rng().gen();
+        let (webhook, webhook_task) = match config.webhook.clone() {
+            Some(webhook_config) => {
+                let (webhook, task) = Webhook::spawn(webhook_config, id);
+                (Some(webhook), Some(task))
+            }
+            None => (None, None),
+        };
+
+        let handshake_permits = Semaphore::new(config.max_concurrent_handshakes.max(1));
+        let upload_limit = config.upload_limit.map(|limit| Arc::new(BandwidthLimit::new(limit)));
+
+        let journal = Journal::new(&config.journal);
+        if let Some(webhook) = &webhook {
+            journal.add_sink(
+                "webhook",
+                &config.journal.webhook,
+                Arc::new(WebhookSink(webhook.clone())),
+            );
+        }
+
+        let callbacks = Arc::new(NodeCallbacks::default());
+        let peer_book = PeerBook::spawn(callbacks.clone());
+
+        // Probes bypassing the proxy would reveal the node's address to the probed hosts.
+        let probe_reachability = config.reachability_probes && config.socks5_proxy().is_none();
+        let (reachability_verifier, reachability_task) = if probe_reachability {
+            let (verifier, task) = ReachabilityVerifier::spawn(
+                peer_book.address_updates(),
+                crate::REACHABILITY_PROBE_WORKERS,
+                Duration::from_millis(crate::REACHABILITY_PROBE_TIMEOUT_MS),
+            );
+            (Some(verifier), Some(task))
+        } else {
+            (None, None)
+        };
+
+        let node = Self(Arc::new(InnerNode {
+            id,
             state: Default::default(),
             local_address: Default::default(),
+            external_address: Default::default(),
             config,
             inbound: Default::default(),
-            peer_book: PeerBook::spawn(),
+            peer_book,
+            bans: Default::default(),
+            #[cfg(feature = "consensus")]
             sync: Default::default(),
+            #[cfg(feature = "consensus")]
+            mining: Default::default(),
+            #[cfg(feature = "consensus")]
+            watcher: Default::default(),
+            rpc_handler: Default::default(),
             launched: Utc::now(),
+            stats_history: StatsHistory::new(crate::STATS_HISTORY_LEN),
+            peer_sync_schedule: Default::default(),
+            #[cfg(feature = "crawler")]
+            known_network,
+            #[cfg(feature = "crawler")]
+            crawler_storage: Default::default(),
+            #[cfg(feature = "crawler")]
+            last_network_snapshot: Default::default(),
+            webhook,
+            alarms: Default::default(),
+            journal,
+            #[cfg(feature = "consensus")]
+            journal_storage: Default::default(),
+            callbacks,
+            clock: Default::default(),
+            recommended_peers_updated: Default::default(),
+            address_change: Default::default(),
+            handshake_permits,
+            reachability_verifier,
+            upload_limit,
+            #[cfg(feature = "fault-injection")]
+            fault_injector: Default::default(),
+            config_source: Default::default(),
+            config_reloaded: Default::default(),
             tasks: Default::default(),
+            started_services: Default::default(),
             threads: Default::default(),
             shutting_down: Default::default(),
+            listener: Default::default(),
+            #[cfg(feature = "consensus")]
             master_dispatch: RwLock::new(None),
-        })))
+            #[cfg(not(feature = "consensus"))]
+            storage: PhantomData,
+        }));
+
+        if let Some(task) = webhook_task {
+            node.register_task(task);
+        }
+        if let Some(task) = reachability_task {
+            node.register_task(task);
+        }
+
+        Ok(node)
     }
 
+    #[cfg(feature = "consensus")]
     pub fn set_sync(&mut self, sync: Sync<S>) {
+        if self.watcher().is_some() {
+            panic!("a watcher node can't have a sync layer!");
+        }
         if self.sync.set(Arc::new(sync)).is_err() {
             panic!("sync was set more than once!");
         }
     }
 
     /// Returns a reference to the sync objects.
+    #[cfg(feature = "consensus")]
     #[inline]
     pub fn sync(&self) -> Option<&Arc<Sync<S>>> {
         self.sync.get()
     }
 
     /// Returns a reference to the sync objects, expecting them to be available.
+    #[cfg(feature = "consensus")]
     #[inline]
     pub fn expect_sync(&self) -> &Sync<S> {
         self.sync().expect("no sync!")
     }
 
+    #[cfg(feature = "consensus")]
     #[inline]
     #[doc(hidden)]
     pub fn has_sync(&self) -> bool {
         self.sync().is_some()
     }
 
-    pub async fn start_services(&self) {
+    /// Returns the role the node plays in the network.
+    pub fn role(&self) -> NodeRole {
+        #[cfg(feature = "consensus")]
+        if self.watcher().is_some() {
+            return NodeRole::Light;
+        }
+
+        self.config.role()
+    }
+
+    /// Returns the policy deciding which inbound messages the node processes and relays.
+    pub fn message_policy(&self) -> &dyn MessagePolicy {
+        match &self.config.message_policy {
+            Some(policy) => policy.as_ref(),
+            None => self.role().default_policy(),
+        }
+    }
+
+    /// Returns a reference to the crawled network, if the node is a crawler.
+    #[cfg(feature = "crawler")]
+    #[inline]
+    pub fn known_network(&self) -> Option<&KnownNetwork> {
+        self.known_network.as_ref()
+    }
+
+    /// Sets the storage the crawled network is persisted in.
+    #[cfg(feature = "crawler")]
+    pub fn set_crawler_storage(&mut self, storage: Arc<MerkleTreeLedger<S>>) {
+        if self.crawler_storage.set(storage).is_err() {
+            panic!("the crawler storage was set more than once!");
+        }
+    }
+
+    /// Records the given event in the journal, which reports it to the configured webhook, if any.
+    pub fn notify_webhook(&self, event: WebhookEvent, details: serde_json::Value) {
+        self.journal.notify(event, details);
+    }
+
+    /// Returns the registry of the node's services, declaring the order they're started in: the known peers
+    /// are loaded before any messages are processed, and the sync layer only starts once the node is looking
+    /// for peers. Stopping a service aborts the tasks it spawned; the stop hooks don't hold on to the node, as
+    /// the started services are kept in it.
+    pub fn services(&self) -> ServiceRegistry {
+        let mut services = ServiceRegistry::new();
+
+        let node = self.clone();
+        services.register("peer book", &[], move || async move {
+            #[cfg(feature = "consensus")]
+            node.attach_journal_storage();
+            node.load_peer_book().await;
+            #[cfg(feature = "consensus")]
+            node.load_bans();
+            #[cfg(feature = "crawler")]
+            node.load_known_network();
+            #[cfg(feature = "consensus")]
+            node.check_announced_address();
+            Ok(())
+        });
+
+        let node = self.clone();
+        let tasks = DropJoin::new();
+        let service_tasks = tasks.clone();
+        services
+            .register("inbound", &["peer book"], move || async move {
+                node.start_inbound_processing(&service_tasks).await;
+                Ok(())
+            })
+            .on_stop(move || async move { tasks.flush() });
+
+        let node = self.clone();
+        let tasks = DropJoin::new();
+        let service_tasks = tasks.clone();
+        services
+            .register("peering", &["inbound"], move || async move {
+                node.start_peering(&service_tasks);
+                Ok(())
+            })
+            .on_stop(move || async move { tasks.flush() });
+
+        let node = self.clone();
+        let tasks = DropJoin::new();
+        let service_tasks = tasks.clone();
+        services
+            .register("monitoring", &[], move || async move {
+                node.start_monitoring(&service_tasks);
+                Ok(())
+            })
+            .on_stop(move || async move { tasks.flush() });
+
+        #[cfg(feature = "consensus")]
+        if self.sync().is_some() {
+            let node = self.clone();
+            let tasks = DropJoin::new();
+            let service_tasks = tasks.clone();
+            services
+                .register("sync", &["peering"], move || async move {
+                    node.start_sync(&service_tasks);
+                    Ok(())
+                })
+                .on_stop(move || async move { tasks.flush() });
+        }
+
+        #[cfg(feature = "consensus")]
+        if self.watcher().is_some() {
+            let node = self.clone();
+            let tasks = DropJoin::new();
+            let service_tasks = tasks.clone();
+            services
+                .register("watcher", &["peering"], move || async move {
+                    node.start_watcher(&service_tasks);
+                    Ok(())
+                })
+                .on_stop(move || async move { tasks.flush() });
+        }
+
+        services
+    }
+
+    /// Starts the node's services in the order of their dependencies; the ones that started are stopped when
+    /// the node shuts down, even if another one failed to start.
+    pub async fn start_services(&self) -> Result<(), NetworkError> {
+        let mut services = self.started_services.lock().await;
+        services.stop().await;
+        *services = self.services();
+
+        match services.start().await {
+            Ok(()) => {
+                self.journal.record(EventCategory::Lifecycle, "started the services");
+                Ok(())
+            }
+            Err(e) => {
+                error!("Couldn't start the node's services: {}", e);
+                self.journal
+                    .record(EventCategory::Lifecycle, format!("couldn't start the services: {}", e));
+                Err(e)
+            }
+        }
+    }
+
+    /// Stops the node's services that were started, in the reverse order.
+    pub async fn stop_services(&self) {
+        self.started_services.lock().await.stop().await;
+    }
+
+    /// Starts processing the messages received from the peers.
+    async fn start_inbound_processing(&self, tasks: &DropJoin<task::JoinHandle<()>>) {
         let node_clone = self.clone();
         let mut receiver = self.inbound.take_receiver().await;
         let incoming_task = task::spawn(async move {
@@ -166,21 +485,60 @@ impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
                 }
             }
         });
-        self.register_task(incoming_task);
+        tasks.append(incoming_task);
+    }
 
+    /// Starts connecting to peers, or crawling them.
+    fn start_peering(&self, tasks: &DropJoin<task::JoinHandle<()>>) {
         let node_clone: Node<S> = self.clone();
-        let peer_sync_interval = self.config.peer_sync_interval();
         let peering_task = task::spawn(async move {
             loop {
-                info!("Updating peers");
+                if node_clone.config.is_crawler() {
+                    info!("Crawling peers");
+
+                    #[cfg(feature = "crawler")]
+                    node_clone.crawl_peers().await;
+                } else {
+                    info!("Updating peers");
 
-                node_clone.update_peers().await;
+                    node_clone.update_peers().await;
+                }
 
-                sleep(peer_sync_interval).await;
+                // Crawlers keep a steady pace, as their peers are replaced every round.
+                let peer_sync_interval = node_clone.config.peer_sync_interval();
+                let peer_sync_interval = if node_clone.config.is_crawler() {
+                    jitter(peer_sync_interval)
+                } else {
+                    node_clone.peer_sync_schedule.next_interval(peer_sync_interval)
+                };
+                node_clone.wait_for_interval(peer_sync_interval).await;
             }
         });
-        self.register_task(peering_task);
+        tasks.append(peering_task);
+
+        // The peers are pinged on a fixed schedule of their own, as the peer syncs can be far apart.
+        if !self.config.is_crawler() {
+            let node_clone = self.clone();
+            let ping_interval = Duration::from_secs(PEER_PING_INTERVAL_SECS.into());
+            let pinging_task = task::spawn(async move {
+                loop {
+                    sleep(jitter(ping_interval)).await;
+
+                    if node_clone.peer_book.get_active_peer_count() != 0 {
+                        node_clone.broadcast_pings().await;
+                    }
+                }
+            });
+            tasks.append(pinging_task);
+        }
 
+        if !self.config.no_listen() {
+            self.watch_local_address();
+        }
+    }
+
+    /// Starts tracking the node's state and statistics, and checking its alarms.
+    fn start_monitoring(&self, tasks: &DropJoin<task::JoinHandle<()>>) {
         let node_clone = self.clone();
         let state_tracking_task = task::spawn(async move {
             loop {
@@ -190,79 +548,168 @@ impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
                 trace!("Node state: {:?}", node_clone.state());
             }
         });
-        self.register_task(state_tracking_task);
+        tasks.append(state_tracking_task);
 
-        if self.sync().is_some() {
-            let bootnodes = self.config.bootnodes();
+        let node_clone = self.clone();
+        let stats_history_interval = Duration::from_secs(STATS_HISTORY_INTERVAL_SECS.into());
+        let stats_history_task = task::spawn(async move {
+            loop {
+                node_clone.stats_history.push(node_clone.stats_snapshot());
 
+                sleep(stats_history_interval).await;
+            }
+        });
+        tasks.append(stats_history_task);
+
+        if !self.config.alarms.is_empty() {
             let node_clone = self.clone();
-            let mempool_sync_interval = node_clone.expect_sync().mempool_sync_interval();
-            let sync_mempool_task = task::spawn(async move {
+            let alarm_check_interval = Duration::from_secs(ALARM_CHECK_INTERVAL_SECS.into());
+            let alarms_task = task::spawn(async move {
                 loop {
-                    if !node_clone.is_syncing_blocks() {
-                        // TODO (howardwu): Add some random sync nodes beyond this approach
-                        //  to ensure some diversity in mempool state that is fetched.
-                        //  For now, this is acceptable because we propogate the mempool to
-                        //  all of our connected peers anyways.
-
-                        // The order of preference for the sync node is as follows:
-                        //   1. Iterate (in declared order) through the bootnodes:
-                        //      a. Check if this node is connected to the specified bootnode in the peer book.
-                        //      b. Select the specified bootnode as the sync node if this node is connected to it.
-                        //   2. If this node is not connected to any bootnode,
-                        //      then select the last seen peer as the sync node.
-
-                        // Step 1.
-                        let mut sync_node = None;
-                        for bootnode in bootnodes.iter() {
-                            if node_clone.peer_book.is_connected(*bootnode) {
-                                sync_node = Some(*bootnode);
-                                break;
-                            }
-                        }
+                    sleep(alarm_check_interval).await;
+
+                    node_clone.check_alarms();
+                }
+            });
+            tasks.append(alarms_task);
+        }
+    }
 
-                        // Step 2.
-                        if sync_node.is_none() {
-                            // Select last seen node as block sync node.
-                            sync_node = node_clone.peer_book.last_seen().await;
+    /// Starts syncing the memory pool and the blocks, and reporting reorganizations.
+    #[cfg(feature = "consensus")]
+    fn start_sync(&self, tasks: &DropJoin<task::JoinHandle<()>>) {
+        let node_clone = self.clone();
+        let sync_mempool_task = task::spawn(async move {
+            loop {
+                let mut is_syncing_mempool = false;
+                if !node_clone.is_syncing_blocks() {
+                    // TODO (howardwu): Add some random sync nodes beyond this approach
+                    //  to ensure some diversity in mempool state that is fetched.
+                    //  For now, this is acceptable because we propogate the mempool to
+                    //  all of our connected peers anyways.
+
+                    // The order of preference for the sync node is as follows:
+                    //   1. Iterate (in declared order) through the bootnodes:
+                    //      a. Check if this node is connected to the specified bootnode in the peer book.
+                    //      b. Select the specified bootnode as the sync node if this node is connected to it.
+                    //   2. If this node is not connected to any bootnode,
+                    //      then select the most preferred peer as the sync node.
+
+                    // Step 1.
+                    let mut sync_node = None;
+                    for bootnode in node_clone.config.bootnodes().iter() {
+                        if node_clone.peer_book.is_connected(*bootnode) {
+                            sync_node = Some(*bootnode);
+                            break;
                         }
+                    }
 
-                        node_clone.update_memory_pool(sync_node).await;
+                    // Step 2.
+                    if sync_node.is_none() {
+                        // Select the most preferred peer as the sync node.
+                        sync_node = node_clone.select_sync_node().await;
                     }
 
-                    sleep(mempool_sync_interval).await;
+                    is_syncing_mempool = sync_node.is_some();
+                    node_clone.update_memory_pool(sync_node).await;
                 }
-            });
-            self.register_task(sync_mempool_task);
 
-            let node_clone = self.clone();
-            let block_sync_interval = node_clone.expect_sync().block_sync_interval();
-            let sync_block_task = task::spawn(async move {
-                loop {
-                    let is_syncing_blocks = node_clone.is_syncing_blocks();
+                // Back off while the memory pool syncs keep turning up nothing new, e.g. when the network is idle.
+                let mempool_sync_interval = node_clone.expect_sync().current_mempool_sync_interval();
+                node_clone.wait_for_interval(mempool_sync_interval).await;
+                if is_syncing_mempool {
+                    node_clone.expect_sync().conclude_mempool_sync_round();
+                }
+            }
+        });
+        tasks.append(sync_mempool_task);
 
-                    if !is_syncing_blocks {
-                        node_clone.register_block_sync_attempt();
-                        if let Err(e) = node_clone.run_sync().await {
-                            error!("failed sync process: {:?}", e);
-                        }
-                        node_clone.finished_syncing_blocks();
+        let node_clone = self.clone();
+        let sync_block_task = task::spawn(async move {
+            loop {
+                if node_clone.start_block_sync() {
+                    if let Err(e) = node_clone.run_sync(None).await {
+                        error!("failed sync process: {:?}", e);
+                    }
+                    node_clone.finish_block_sync();
+                }
+
+                let block_sync_interval = node_clone.expect_sync().block_sync_interval();
+                node_clone.wait_for_interval(block_sync_interval).await;
+            }
+        });
+        tasks.append(sync_block_task);
+
+        let node_clone = self.clone();
+        let mut reorgs = self.expect_sync().consensus.subscribe_reorgs();
+        let reorg_reporting_task = task::spawn(async move {
+            loop {
+                match reorgs.recv().await {
+                    Ok(reorg) => node_clone.report_reorg(&reorg),
+                    // Only the latest reorganizations are reported if they happen in a quick succession.
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+        tasks.append(reorg_reporting_task);
+
+        if let Some(prune_depth) = self.config.prune_depth {
+            let ledger = self.expect_sync().consensus.ledger.clone();
+            let pruning_interval = Duration::from_secs(PRUNING_INTERVAL_SECS.into());
+            let pruning_task = task::spawn(async move {
+                loop {
+                    let ledger = ledger.clone();
+                    match task::spawn_blocking(move || ledger.prune_blocks(prune_depth)).await {
+                        Ok(Ok(0)) => {}
+                        Ok(Ok(num_pruned)) => debug!("Pruned {} blocks deeper than {}", num_pruned, prune_depth),
+                        Ok(Err(e)) => error!("Failed to prune the blocks: {}", e),
+                        Err(e) => error!("The pruning task failed: {}", e),
                     }
 
-                    sleep(block_sync_interval).await;
+                    sleep(pruning_interval).await;
                 }
             });
-            self.register_task(sync_block_task);
+            tasks.append(pruning_task);
         }
     }
 
+    /// Starts following the chain as a watcher.
+    #[cfg(feature = "consensus")]
+    fn start_watcher(&self, tasks: &DropJoin<task::JoinHandle<()>>) {
+        let node_clone = self.clone();
+        let watcher_sync_interval = Duration::from_secs(WATCHER_SYNC_INTERVAL_SECS.into());
+        let watcher_task = task::spawn(async move {
+            loop {
+                node_clone.update_watched_chain().await;
+
+                sleep(watcher_sync_interval).await;
+            }
+        });
+        tasks.append(watcher_task);
+    }
+
     pub async fn shut_down(&self) {
         debug!("Shutting down");
+        self.journal.record(EventCategory::Lifecycle, "shutting down");
+
+        self.shutting_down.store(true, Ordering::Relaxed);
+
+        #[cfg(feature = "consensus")]
+        self.stop_mining();
+
+        self.stop_services().await;
 
         for addr in self.connected_peers() {
             self.disconnect_from_peer(addr).await;
         }
 
+        self.save_peer_book();
+
+        if let Some(listener) = self.listener.lock().unwrap().take() {
+            listener.abort();
+        }
+
         self.threads.flush();
 
         self.tasks.flush();
@@ -276,9 +723,61 @@ impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
         self.threads.append(handle);
     }
 
+    /// Sets the source the configuration of the node is reloaded from, e.g. its configuration file.
+    pub fn set_config_source<F>(&self, source: F)
+    where
+        F: Fn() -> Result<ReloadableConfig, NetworkError> + Send + core::marker::Sync + 'static,
+    {
+        if self.config_source.set(Box::new(source)).is_err() {
+            panic!("config source was set more than once!");
+        }
+    }
+
+    /// Reloads the peer limits, the bootnodes and the sync intervals from the config source, and
+    /// applies them to the running node without waiting for the current intervals to pass.
+    pub fn reload_config(&self) -> Result<ReloadableConfig, NetworkError> {
+        let source = self
+            .config_source
+            .get()
+            .ok_or_else(|| NetworkError::ConfigReload("the node has no config source".into()))?;
+        let reloaded = source()?;
+
+        self.config.reload(&reloaded)?;
+        #[cfg(feature = "consensus")]
+        if let Some(sync) = self.sync() {
+            sync.set_sync_intervals(reloaded.block_sync_interval, reloaded.mempool_sync_interval);
+        }
+        self.peer_sync_schedule.reset();
+        self.config_reloaded.notify_waiters();
+        self.journal.record(EventCategory::Lifecycle, "reloaded the config");
+
+        info!(
+            "Reloaded the config: {}-{} peers, {} bootnodes, sync intervals: {:?} (peers), {:?} (blocks), {:?} (memory pool)",
+            reloaded.min_peers,
+            reloaded.max_peers,
+            reloaded.bootnodes.len(),
+            reloaded.peer_sync_interval,
+            reloaded.block_sync_interval,
+            reloaded.mempool_sync_interval,
+        );
+
+        Ok(reloaded)
+    }
+
+    /// Waits for the given interval to pass, or for the configuration to be reloaded in the meantime.
+    async fn wait_for_interval(&self, interval: Duration) {
+        timeout(interval, self.config_reloaded.notified()).await.ok();
+    }
+
     #[inline]
     pub fn local_address(&self) -> Option<SocketAddr> {
-        self.local_address.get().copied()
+        self.local_address.load().as_deref().copied()
+    }
+
+    /// Returns the external address of the node, if it was discovered via NAT traversal.
+    #[inline]
+    pub fn external_address(&self) -> Option<SocketAddr> {
+        self.external_address.load().as_deref().copied()
     }
 
     #[inline]
@@ -289,32 +788,119 @@ impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
     /// Sets the local address of the node to the given value.
     #[inline]
     pub fn set_local_address(&self, addr: SocketAddr) {
-        self.local_address
-            .set(addr)
-            .expect("local address was set more than once!");
+        self.local_address.store(Some(Arc::new(addr)));
+    }
+
+    /// Sets the external address of the node to the given value.
+    #[inline]
+    pub fn set_external_address(&self, addr: SocketAddr) {
+        self.external_address.store(Some(Arc::new(addr)));
     }
 
+    /// Installs the node's metrics recorder, which its stats are collected with. Embedders that install their
+    /// own recorder don't call it.
     pub fn initialize_metrics(&self) {
         debug!("Initializing metrics");
         let metrics_task = snarkos_metrics::initialize();
         self.register_task(metrics_task);
 
         // The node can already be at some non-zero height.
+        #[cfg(feature = "consensus")]
         if let Some(sync) = self.sync() {
             metrics::counter!(misc::BLOCK_HEIGHT, sync.current_block_height() as u64);
         }
     }
 
+    /// Returns the address other nodes should use to connect to this node, if it's known;
+    /// a configured advertised address takes precedence over one discovered via NAT traversal.
+    pub fn advertised_address(&self) -> Option<SocketAddr> {
+        self.config.advertised_address.or_else(|| self.external_address())
+    }
+
+    /// Returns a snapshot of the node's stats.
+    pub fn stats_snapshot(&self) -> NodeStats {
+        let mut stats = NODE_STATS.snapshot();
+
+        // Note: Temporarily overriding node metrics here, as they aren't all correctly updated
+        stats.connections.connected_peers = self.peer_book.get_active_peer_count();
+        stats.connections.disconnected_peers = self.peer_book.get_disconnected_peer_count();
+        stats.misc.block_height = self.current_block_height() as u64;
+        #[cfg(feature = "consensus")]
+        if let Some(mining) = self.mining() {
+            stats.misc.mining_threads = mining.num_threads() as u64;
+            stats.misc.proofs_per_hour = mining.proofs_per_hour();
+        }
+
+        stats
+    }
+
     pub fn version(&self) -> Version {
-        Version::new(
-            crate::PROTOCOL_VERSION,
-            self.local_address().map(|x| x.port()).unwrap_or_default(),
-            self.id,
-        )
+        // A node that doesn't listen announces no port, so that its peers don't try to connect to it.
+        let (listening_port, advertised_address) = if self.config.no_listen() {
+            (0, None)
+        } else {
+            let listening_port = match self.config.advertised_address {
+                Some(address) => address.port(),
+                None => self.local_address().map(|x| x.port()).unwrap_or_default(),
+            };
+            (listening_port, self.advertised_address())
+        };
+
+        // Watchers don't serve any blocks, so the earliest one they could serve is past their tip.
+        #[cfg(feature = "consensus")]
+        let (earliest_block_height, mut features) = match self.watcher() {
+            Some(watcher) => (
+                watcher.block_height() + 1,
+                crate::SUPPORTED_FEATURES | Features::HEADERS_ONLY,
+            ),
+            None => (
+                self.sync().map(|sync| sync.earliest_block_height()).unwrap_or(0),
+                crate::SUPPORTED_FEATURES,
+            ),
+        };
+        #[cfg(not(feature = "consensus"))]
+        let (earliest_block_height, mut features) = (0, crate::SUPPORTED_FEATURES);
+        if self.rpc_handler().is_some() {
+            features = features | Features::RPC;
+        }
+
+        Version::new(crate::PROTOCOL_VERSION, listening_port, self.id)
+            .with_external_address(advertised_address)
+            .with_earliest_block_height(earliest_block_height)
+            .with_features(features)
+            .with_network_id(self.config.network_id)
+            .with_timestamp(Utc::now().timestamp())
+    }
+
+    /// Returns the bandwidth caps the payloads written to a newly connected peer are subject to.
+    pub(crate) fn outbound_throttle(&self) -> OutboundThrottle {
+        OutboundThrottle {
+            peer: self.config.peer_upload_limit.map(BandwidthLimit::new),
+            node: self.upload_limit.clone(),
+        }
+    }
+
+    /// Returns the height of the chain followed by the node: the one in its ledger or, in watcher mode, the one
+    /// of its headers; it's 0 if the node has neither, e.g. in peering tests that don't use the sync layer.
+    #[cfg(feature = "consensus")]
+    pub fn current_block_height(&self) -> BlockHeight {
+        match (self.sync(), self.watcher()) {
+            (Some(sync), _) => sync.current_block_height(),
+            (None, Some(watcher)) => watcher.block_height(),
+            (None, None) => 0,
+        }
+    }
+
+    /// Returns the height of the chain followed by the node; it's always 0 without the `consensus` feature, as the
+    /// node doesn't follow the chain.
+    #[cfg(not(feature = "consensus"))]
+    pub fn current_block_height(&self) -> BlockHeight {
+        0
     }
 
-    pub async fn run_sync(&self) -> Result<(), NetworkError> {
-        let (master, sender) = SyncMaster::new(self.clone());
+    #[cfg(feature = "consensus")]
+    pub async fn run_sync(&self, target: Option<SocketAddr>) -> Result<(), NetworkError> {
+        let (master, sender) = SyncMaster::new(self.clone(), target);
         *self.master_dispatch.write().await = Some(sender);
         master.run().await
     }
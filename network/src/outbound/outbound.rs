@@ -20,6 +20,7 @@ use snarkvm_dpc::Storage;
 
 use std::{collections::HashMap, net::SocketAddr};
 
+use chrono::Utc;
 use parking_lot::RwLock;
 use tokio::sync::mpsc::{error::TrySendError, Receiver, Sender};
 
@@ -87,18 +88,13 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
     }
 
     pub fn send_ping(&self, remote_address: SocketAddr) {
-        // Consider peering tests that don't use the sync layer.
-        let current_block_height = if let Some(ref sync) = self.sync() {
-            sync.current_block_height()
-        } else {
-            0
-        };
+        let current_block_height = self.current_block_height();
 
         self.peer_book.sending_ping(remote_address);
 
         self.send_request(Message::new(
             Direction::Outbound(remote_address),
-            Payload::Ping(current_block_height),
+            Payload::Ping(current_block_height, Utc::now().timestamp()),
         ));
     }
 
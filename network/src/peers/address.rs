@@ -0,0 +1,117 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+/// The IP version of a peer address; peers are kept in separate buckets per family, since a node
+/// can only connect to the families its network stack supports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AddressFamily {
+    Ipv4,
+    Ipv6,
+}
+
+impl AddressFamily {
+    /// Returns the family of the given address; IPv4-mapped IPv6 addresses count as IPv4.
+    pub fn of(address: SocketAddr) -> Self {
+        match normalize_address(address) {
+            SocketAddr::V4(_) => Self::Ipv4,
+            SocketAddr::V6(_) => Self::Ipv6,
+        }
+    }
+}
+
+/// Converts an IPv4-mapped IPv6 address, as reported by a dual-stack listener for IPv4 peers,
+/// into the plain IPv4 one, so that the same peer doesn't show up under two addresses.
+pub fn normalize_address(address: SocketAddr) -> SocketAddr {
+    match address.ip() {
+        IpAddr::V6(ip) => match ip.octets() {
+            [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, a, b, c, d] => {
+                SocketAddr::new(IpAddr::V4(Ipv4Addr::new(a, b, c, d)), address.port())
+            }
+            _ => address,
+        },
+        IpAddr::V4(_) => address,
+    }
+}
+
+/// Returns `true` if the given address can only be reached from the same host or local network, e.g. a loopback,
+/// private or link-local one; such addresses are of no use to peers on other networks.
+pub fn is_local_address(address: SocketAddr) -> bool {
+    match normalize_address(address).ip() {
+        IpAddr::V4(ip) => ip.is_loopback() || ip.is_unspecified() || ip.is_private() || ip.is_link_local(),
+        IpAddr::V6(ip) => {
+            let first_segment = ip.segments()[0];
+            ip.is_loopback()
+                || ip.is_unspecified()
+                // unique local addresses, i.e. fc00::/7
+                || (first_segment & 0xfe00) == 0xfc00
+                // link-local addresses, i.e. fe80::/10
+                || (first_segment & 0xffc0) == 0xfe80
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mapped_addresses_are_normalized() {
+        let mapped: SocketAddr = "[::ffff:192.0.2.1]:4131".parse().unwrap();
+        let plain: SocketAddr = "192.0.2.1:4131".parse().unwrap();
+
+        assert_eq!(normalize_address(mapped), plain);
+        assert_eq!(AddressFamily::of(mapped), AddressFamily::Ipv4);
+    }
+
+    #[test]
+    fn native_ipv6_addresses_are_kept() {
+        let address: SocketAddr = "[2001:db8::1]:4131".parse().unwrap();
+        let loopback: SocketAddr = "[::1]:4131".parse().unwrap();
+
+        assert_eq!(normalize_address(address), address);
+        assert_eq!(normalize_address(loopback), loopback);
+        assert_eq!(AddressFamily::of(address), AddressFamily::Ipv6);
+    }
+
+    #[test]
+    fn local_addresses_are_recognized() {
+        let is_local = |address: &str| is_local_address(address.parse().unwrap());
+
+        for address in &[
+            "127.0.0.1:4131",
+            "10.1.2.3:4131",
+            "172.16.0.1:4131",
+            "192.168.1.1:4131",
+            "169.254.0.1:4131",
+            "[::1]:4131",
+            "[fd00::1]:4131",
+            "[fe80::1]:4131",
+            "[::ffff:192.168.1.1]:4131",
+        ] {
+            assert!(is_local(address), "{} should be local", address);
+        }
+        for address in &[
+            "192.0.2.1:4131",
+            "172.32.0.1:4131",
+            "[2001:db8::1]:4131",
+            "[::ffff:192.0.2.1]:4131",
+        ] {
+            assert!(!is_local(address), "{} shouldn't be local", address);
+        }
+    }
+}
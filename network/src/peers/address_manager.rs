@@ -0,0 +1,951 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    fs,
+    hash::{Hash, Hasher},
+    net::{IpAddr, SocketAddr},
+    path::Path,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::{normalize_address, NetworkError};
+
+/// Returns the network group of the given address; addresses from the same group are likely to be
+/// controlled by the same entity, so their influence over the address manager is limited.
+pub fn address_group(address: SocketAddr) -> Vec<u8> {
+    match normalize_address(address).ip() {
+        ip if ip.is_loopback() || ip.is_unspecified() => vec![0],
+        IpAddr::V4(ip) => {
+            let octets = ip.octets();
+            vec![4, octets[0], octets[1]]
+        }
+        IpAddr::V6(ip) => {
+            let octets = ip.octets();
+            vec![6, octets[0], octets[1], octets[2], octets[3]]
+        }
+    }
+}
+
+/// The outcome of adding an address to the address manager.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Insertion {
+    /// The address was added, possibly evicting the given one to make room for it.
+    Inserted(Option<SocketAddr>),
+    /// The address was already known.
+    Known,
+    /// The address wasn't added, as its bucket is full of addresses that are worth keeping.
+    Full,
+}
+
+/// The reason an address was evicted from the address manager.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Eviction {
+    /// The address wasn't connected to or learned of within the maximum age.
+    Expired,
+    /// Too many consecutive connection attempts to the address failed.
+    Failing,
+    /// The address made way for another one, or the number of known addresses exceeded the limit.
+    Excess,
+}
+
+/// The rules for forgetting the disconnected peers that aren't worth keeping, so that the peer book of a
+/// long-running node doesn't accumulate the stale addresses gossiped to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerEvictionPolicy {
+    /// The maximum number of disconnected peers, not counting the bootnodes.
+    pub max_peers: usize,
+    /// The amount of time after which a peer that wasn't successfully connected to is forgotten; it's counted
+    /// from the time its address was learned if it never was.
+    pub max_age: Duration,
+    /// The number of consecutive failed connection attempts after which a peer is forgotten.
+    pub max_failures: u32,
+}
+
+impl Default for PeerEvictionPolicy {
+    fn default() -> Self {
+        Self {
+            max_peers: crate::MAX_DISCONNECTED_PEERS,
+            max_age: Duration::from_secs(crate::ADDRESS_HORIZON_SECS.into()),
+            max_failures: crate::MAX_PEER_FAILURES,
+        }
+    }
+}
+
+/// The information the address manager keeps about an address.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddressInfo {
+    /// The network group of the peer that shared the address; it's empty for local sources.
+    pub source_group: Vec<u8>,
+    /// Indicates whether the address is in the tried table.
+    pub tried: bool,
+    /// The number of failed connection attempts since the last successful one.
+    pub failed_attempts: u32,
+    /// The time of the last connection attempt.
+    pub last_attempt: Option<DateTime<Utc>>,
+    /// The time of the last successful connection.
+    pub last_success: Option<DateTime<Utc>>,
+    /// The time the address was added at.
+    pub added: DateTime<Utc>,
+}
+
+impl AddressInfo {
+    fn new(source_group: Vec<u8>) -> Self {
+        Self {
+            source_group,
+            tried: false,
+            failed_attempts: 0,
+            last_attempt: None,
+            last_success: None,
+            added: Utc::now(),
+        }
+    }
+
+    /// Checks whether the address isn't worth keeping, so that it can be evicted to make room for another one.
+    fn is_terrible(&self, now: DateTime<Utc>) -> bool {
+        // Addresses are given some time to be connected to after an attempt.
+        if let Some(last_attempt) = self.last_attempt {
+            if now - last_attempt < chrono::Duration::minutes(1) {
+                return false;
+            }
+        }
+
+        let horizon = chrono::Duration::seconds(crate::ADDRESS_HORIZON_SECS.into());
+        match self.last_success {
+            None => self.failed_attempts >= crate::MAX_ADDRESS_FAILURES || now - self.added > horizon,
+            Some(last_success) => self.failed_attempts >= crate::MAX_ADDRESS_FAILURES && now - last_success > horizon,
+        }
+    }
+}
+
+/// A known address along with its connection history, as exported to and imported from peer lists.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KnownPeer {
+    pub address: SocketAddr,
+    /// Indicates whether the address was ever successfully connected to.
+    pub tried: bool,
+    /// The number of failed connection attempts since the last successful one.
+    pub failed_attempts: u32,
+    /// The time of the last connection attempt.
+    pub last_attempt: Option<DateTime<Utc>>,
+    /// The time of the last successful connection.
+    pub last_success: Option<DateTime<Utc>>,
+    /// The time the address was first learned of.
+    pub added: DateTime<Utc>,
+}
+
+impl KnownPeer {
+    /// Checks whether the connection history of the address makes it not worth importing.
+    pub fn is_terrible(&self, now: DateTime<Utc>) -> bool {
+        AddressInfo {
+            source_group: vec![],
+            tried: self.tried,
+            failed_attempts: self.failed_attempts,
+            last_attempt: self.last_attempt,
+            last_success: self.last_success,
+            added: self.added,
+        }
+        .is_terrible(now)
+    }
+}
+
+/// The labels and the note attached to a peer by the node's operator, e.g. to keep track of their own nodes or
+/// of the incidents involving it; they're persisted along with the known addresses.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerAnnotation {
+    /// Short tags consisting of alphanumeric characters, dashes and underscores, e.g. `my-backup-node`.
+    pub labels: Vec<String>,
+    /// A free-form note.
+    pub note: Option<String>,
+}
+
+impl PeerAnnotation {
+    /// Returns `true` if there are neither labels nor a note.
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty() && self.note.is_none()
+    }
+
+    /// Checks whether the labels are well-formed and the annotation is within the size limits.
+    pub fn validate(&self) -> Result<(), NetworkError> {
+        let invalid = |reason: String| Err(NetworkError::InvalidPeerAnnotation(reason));
+
+        if self.labels.len() > crate::MAX_PEER_LABELS {
+            return invalid(format!("more than {} labels", crate::MAX_PEER_LABELS));
+        }
+        for label in &self.labels {
+            if label.is_empty() || label.len() > crate::MAX_PEER_LABEL_LEN {
+                return invalid(format!(
+                    "the label '{}' isn't between 1 and {} characters long",
+                    label,
+                    crate::MAX_PEER_LABEL_LEN
+                ));
+            }
+            if !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                return invalid(format!("the label '{}' contains invalid characters", label));
+            }
+        }
+        if let Some(note) = &self.note {
+            if note.len() > crate::MAX_PEER_NOTE_LEN {
+                return invalid(format!("the note is longer than {} bytes", crate::MAX_PEER_NOTE_LEN));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the given peers to a file at the given path as a JSON list.
+pub fn write_peer_list(path: &Path, peers: &[KnownPeer]) -> Result<(), NetworkError> {
+    let json = serde_json::to_vec_pretty(peers).map_err(|e| NetworkError::InvalidPeerList(e.to_string()))?;
+    fs::write(path, json)?;
+
+    Ok(())
+}
+
+/// Reads the JSON list of peers from a file at the given path.
+pub fn read_peer_list(path: &Path) -> Result<Vec<KnownPeer>, NetworkError> {
+    let json = fs::read(path)?;
+
+    serde_json::from_slice(&json).map_err(|e| NetworkError::InvalidPeerList(e.to_string()))
+}
+
+/// The persisted form of the address manager; the buckets are recreated from it.
+#[derive(Serialize, Deserialize)]
+struct PersistedAddresses {
+    key: u64,
+    addresses: Vec<(SocketAddr, AddressInfo)>,
+    annotations: Vec<(SocketAddr, PeerAnnotation)>,
+}
+
+/// The persisted form of the address manager from before the peers could be annotated.
+#[derive(Deserialize)]
+struct LegacyPersistedAddresses {
+    key: u64,
+    addresses: Vec<(SocketAddr, AddressInfo)>,
+}
+
+///
+/// A bounded store of peer addresses, split into a table of addresses that weren't connected to yet and a table
+/// of ones that were. Both are divided into buckets, and the placement of an address depends on its network
+/// group, the group of the peer it was received from, and a secret key; a single peer can only fill a few
+/// buckets of the new table, and a few nodes can't take over the tried table, which makes eclipse attacks harder.
+///
+#[derive(Debug)]
+pub struct AddressManager {
+    /// The secret randomizing the placement of addresses, so that it can't be predicted by other nodes.
+    key: u64,
+    /// All the known addresses.
+    addresses: HashMap<SocketAddr, AddressInfo>,
+    /// The buckets of the addresses that weren't connected to yet.
+    new_buckets: Vec<Vec<SocketAddr>>,
+    /// The buckets of the addresses that were successfully connected to.
+    tried_buckets: Vec<Vec<SocketAddr>>,
+    /// The annotations of the peers; they're kept when their addresses are removed.
+    annotations: HashMap<SocketAddr, PeerAnnotation>,
+    /// The gossiped addresses that haven't passed a reachability probe yet; they're selected last. It isn't
+    /// persisted, as the addresses restored on start-up were kept for a reason.
+    unverified: HashSet<SocketAddr>,
+}
+
+impl Default for AddressManager {
+    fn default() -> Self {
+        Self::with_key(rand::random())
+    }
+}
+
+impl AddressManager {
+    fn with_key(key: u64) -> Self {
+        Self {
+            key,
+            addresses: Default::default(),
+            new_buckets: vec![Vec::new(); crate::NEW_ADDRESS_BUCKETS],
+            tried_buckets: vec![Vec::new(); crate::TRIED_ADDRESS_BUCKETS],
+            annotations: Default::default(),
+            unverified: Default::default(),
+        }
+    }
+
+    /// Returns the number of known addresses.
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    /// Checks whether there are no known addresses.
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+
+    /// Checks whether the given address is known.
+    pub fn contains(&self, address: SocketAddr) -> bool {
+        self.addresses.contains_key(&address)
+    }
+
+    /// Checks whether the given address is in the tried table.
+    pub fn is_tried(&self, address: SocketAddr) -> bool {
+        self.addresses.get(&address).map(|info| info.tried).unwrap_or(false)
+    }
+
+    /// Checks whether the given address is awaiting a successful reachability probe.
+    pub fn is_unverified(&self, address: SocketAddr) -> bool {
+        self.unverified.contains(&address)
+    }
+
+    /// Returns the number of addresses awaiting a successful reachability probe.
+    pub fn unverified_count(&self) -> usize {
+        self.unverified.len()
+    }
+
+    /// Returns all the known addresses.
+    pub fn addresses(&self) -> impl Iterator<Item = SocketAddr> + '_ {
+        self.addresses.keys().copied()
+    }
+
+    /// Returns all the known addresses along with their connection history, ordered by address.
+    pub fn known_peers(&self) -> Vec<KnownPeer> {
+        let mut peers = self
+            .addresses
+            .iter()
+            .map(|(&address, info)| KnownPeer {
+                address,
+                tried: info.tried,
+                failed_attempts: info.failed_attempts,
+                last_attempt: info.last_attempt,
+                last_success: info.last_success,
+                added: info.added,
+            })
+            .collect::<Vec<_>>();
+        peers.sort_by_key(|peer| peer.address);
+
+        peers
+    }
+
+    /// Adds the given peers to the new table, skipping the ones with a terrible connection history;
+    /// returns the number of added addresses.
+    pub fn import(&mut self, peers: &[KnownPeer]) -> usize {
+        let now = Utc::now();
+        let mut imported = 0;
+        for peer in peers.iter().filter(|peer| !peer.is_terrible(now)) {
+            if let Insertion::Inserted(_) = self.add(peer.address, None) {
+                imported += 1;
+            }
+        }
+
+        imported
+    }
+
+    fn hash<T: Hash>(&self, value: T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.key.hash(&mut hasher);
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn new_bucket(&self, address: SocketAddr, source_group: &[u8]) -> usize {
+        let slot = self.hash((address_group(address), source_group)) % crate::NEW_BUCKETS_PER_SOURCE_GROUP;
+        (self.hash((source_group, slot)) % crate::NEW_ADDRESS_BUCKETS as u64) as usize
+    }
+
+    fn tried_bucket(&self, address: SocketAddr) -> usize {
+        let slot = self.hash(address) % crate::TRIED_BUCKETS_PER_GROUP;
+        (self.hash((address_group(address), slot)) % crate::TRIED_ADDRESS_BUCKETS as u64) as usize
+    }
+
+    ///
+    /// Adds the given address to the new table, unless it's already known; `source` is the peer that shared it,
+    /// if there is one.
+    ///
+    pub fn add(&mut self, address: SocketAddr, source: Option<SocketAddr>) -> Insertion {
+        if self.contains(address) {
+            return Insertion::Known;
+        }
+
+        let source_group = source.map(address_group).unwrap_or_default();
+        self.insert_new(address, AddressInfo::new(source_group))
+    }
+
+    /// Places the address in its bucket of the new table, evicting a terrible one if the bucket is full.
+    fn insert_new(&mut self, address: SocketAddr, mut info: AddressInfo) -> Insertion {
+        let bucket = self.new_bucket(address, &info.source_group);
+
+        let mut evicted = None;
+        if self.new_buckets[bucket].len() >= crate::ADDRESS_BUCKET_SIZE {
+            let now = Utc::now();
+            let addresses = &self.addresses;
+            let terrible_position = self.new_buckets[bucket]
+                .iter()
+                .position(|known| addresses[known].is_terrible(now));
+
+            match terrible_position {
+                Some(position) => {
+                    let terrible = self.new_buckets[bucket].swap_remove(position);
+                    self.addresses.remove(&terrible);
+                    self.unverified.remove(&terrible);
+                    evicted = Some(terrible);
+                }
+                None => return Insertion::Full,
+            }
+        }
+
+        info.tried = false;
+        self.new_buckets[bucket].push(address);
+        self.addresses.insert(address, info);
+
+        Insertion::Inserted(evicted)
+    }
+
+    ///
+    /// Places the address in its bucket of the tried table; if the bucket is full, the address that was
+    /// successfully connected to the longest time ago is moved back to the new table. Returns the address
+    /// that had to be dropped as a result, if any.
+    ///
+    fn insert_tried(&mut self, address: SocketAddr, mut info: AddressInfo) -> Option<SocketAddr> {
+        let bucket = self.tried_bucket(address);
+
+        let mut dropped = None;
+        if self.tried_buckets[bucket].len() >= crate::ADDRESS_BUCKET_SIZE {
+            let addresses = &self.addresses;
+            let stalest = *self.tried_buckets[bucket]
+                .iter()
+                .min_by_key(|&known| addresses[known].last_success)
+                .expect("the bucket is full");
+
+            if let Some(stalest_info) = self.detach(stalest) {
+                dropped = match self.insert_new(stalest, stalest_info) {
+                    Insertion::Inserted(evicted) => evicted,
+                    _ => Some(stalest),
+                };
+            }
+        }
+
+        info.tried = true;
+        self.tried_buckets[bucket].push(address);
+        self.addresses.insert(address, info);
+
+        dropped
+    }
+
+    /// Removes the given address from its table, returning its information.
+    fn detach(&mut self, address: SocketAddr) -> Option<AddressInfo> {
+        let info = self.addresses.remove(&address)?;
+        self.unverified.remove(&address);
+
+        if info.tried {
+            let bucket = self.tried_bucket(address);
+            self.tried_buckets[bucket].retain(|&known| known != address);
+        } else {
+            let bucket = self.new_bucket(address, &info.source_group);
+            self.new_buckets[bucket].retain(|&known| known != address);
+        }
+
+        Some(info)
+    }
+
+    /// Removes the given address; returns `true` if it was known.
+    pub fn remove(&mut self, address: SocketAddr) -> bool {
+        self.detach(address).is_some()
+    }
+
+    ///
+    /// Evicts the addresses that are deemed evictable and expired, failing, or in excess of the maximum number
+    /// of evictable addresses, starting with the ones that weren't connected to for the longest time; the
+    /// addresses are given some time to be connected to after an attempt. Returns the evicted addresses.
+    ///
+    pub fn cull<F: Fn(SocketAddr) -> bool>(
+        &mut self,
+        policy: &PeerEvictionPolicy,
+        is_evictable: F,
+        now: DateTime<Utc>,
+    ) -> Vec<(SocketAddr, Eviction)> {
+        let max_age = chrono::Duration::from_std(policy.max_age).unwrap_or_else(|_| chrono::Duration::max_value());
+
+        let mut evicted = Vec::new();
+        let mut retained = Vec::new();
+        for (&address, info) in self.addresses.iter().filter(|(&address, _)| is_evictable(address)) {
+            let is_pending = match info.last_attempt {
+                Some(last_attempt) => now - last_attempt < chrono::Duration::minutes(1),
+                None => false,
+            };
+            let last_success = info.last_success.unwrap_or(info.added);
+
+            if !is_pending && info.failed_attempts >= policy.max_failures {
+                evicted.push((address, Eviction::Failing));
+            } else if !is_pending && now - last_success > max_age {
+                evicted.push((address, Eviction::Expired));
+            } else {
+                retained.push((address, info.tried, info.failed_attempts, last_success));
+            }
+        }
+
+        // The addresses that were never connected to go first, followed by the ones failing the most.
+        retained.sort_unstable_by_key(|&(_, tried, failed_attempts, last_success)| {
+            (tried, std::cmp::Reverse(failed_attempts), last_success)
+        });
+        let excess = retained.len().saturating_sub(policy.max_peers);
+        evicted.extend(
+            retained[..excess]
+                .iter()
+                .map(|&(address, ..)| (address, Eviction::Excess)),
+        );
+
+        for &(address, _) in &evicted {
+            self.detach(address);
+        }
+
+        evicted
+    }
+
+    /// Registers an attempt to connect to the given address; it's considered failed until marked as good.
+    pub fn mark_attempt(&mut self, address: SocketAddr) {
+        if let Some(info) = self.addresses.get_mut(&address) {
+            info.last_attempt = Some(Utc::now());
+            info.failed_attempts += 1;
+        }
+    }
+
+    /// Marks the given known address as awaiting a reachability probe, so that it's deprioritized until it passes one.
+    pub fn mark_unverified(&mut self, address: SocketAddr) {
+        if self.contains(address) {
+            self.unverified.insert(address);
+        }
+    }
+
+    ///
+    /// Registers the outcome of a reachability probe of the given address; a failed one counts as a failed
+    /// connection attempt, and the address remains deprioritized.
+    ///
+    pub fn mark_probed(&mut self, address: SocketAddr, is_reachable: bool) {
+        if is_reachable {
+            self.unverified.remove(&address);
+        } else {
+            self.mark_attempt(address);
+        }
+    }
+
+    ///
+    /// Registers a successful connection to the given address, moving it to the tried table. Returns the
+    /// address that had to be dropped to make room for it, if any.
+    ///
+    pub fn mark_good(&mut self, address: SocketAddr) -> Option<SocketAddr> {
+        let mut info = self.detach(address).unwrap_or_else(|| AddressInfo::new(vec![]));
+        info.failed_attempts = 0;
+        info.last_success = Some(Utc::now());
+
+        self.insert_tried(address, info)
+    }
+
+    ///
+    /// Selects up to `count` eligible addresses to connect to, alternating between the tried and new tables,
+    /// and preferring the network groups that aren't represented among the `occupied` addresses yet; the
+    /// unverified addresses are only selected if there aren't enough other ones.
+    ///
+    pub fn select<F: Fn(SocketAddr) -> bool>(
+        &self,
+        count: usize,
+        is_eligible: F,
+        occupied: &[SocketAddr],
+    ) -> Vec<SocketAddr> {
+        let mut rng = rand::thread_rng();
+        let now = Utc::now();
+
+        let (unverified, verified): (Vec<_>, Vec<_>) = self
+            .addresses
+            .iter()
+            .filter(|(address, info)| !info.is_terrible(now) && is_eligible(**address))
+            .partition(|(address, _)| self.unverified.contains(*address));
+        let (mut tried, mut new): (Vec<_>, Vec<_>) = verified.into_iter().partition(|(_, info)| info.tried);
+        tried.shuffle(&mut rng);
+        new.shuffle(&mut rng);
+
+        let mut candidates = Vec::with_capacity(tried.len() + new.len());
+        let (mut tried, mut new) = (tried.into_iter(), new.into_iter());
+        loop {
+            match (tried.next(), new.next()) {
+                (None, None) => break,
+                (tried, new) => candidates.extend(tried.into_iter().chain(new).map(|(&address, _)| address)),
+            }
+        }
+
+        let mut groups = occupied
+            .iter()
+            .map(|&address| address_group(address))
+            .collect::<HashSet<_>>();
+        let mut selected = Vec::with_capacity(count);
+        let mut deferred = vec![];
+        for address in candidates {
+            if selected.len() == count {
+                break;
+            }
+            if groups.insert(address_group(address)) {
+                selected.push(address);
+            } else {
+                deferred.push(address);
+            }
+        }
+
+        // Fall back to the represented groups if there aren't enough distinct ones, then to the unverified addresses.
+        let missing = count - selected.len();
+        selected.extend(deferred.into_iter().take(missing));
+        let missing = count - selected.len();
+        selected.extend(
+            unverified
+                .choose_multiple(&mut rng, missing)
+                .map(|&(&address, _)| address),
+        );
+
+        selected
+    }
+
+    /// Returns the annotation of the given peer, if it has one.
+    pub fn annotation(&self, address: SocketAddr) -> Option<&PeerAnnotation> {
+        self.annotations.get(&address)
+    }
+
+    /// Returns all the annotated peers along with their annotations.
+    pub fn annotations(&self) -> impl Iterator<Item = (SocketAddr, &PeerAnnotation)> + '_ {
+        self.annotations
+            .iter()
+            .map(|(&address, annotation)| (address, annotation))
+    }
+
+    /// Replaces the annotation of the given peer; an empty one removes it.
+    pub fn annotate(&mut self, address: SocketAddr, annotation: PeerAnnotation) -> Result<(), NetworkError> {
+        annotation.validate()?;
+
+        if annotation.is_empty() {
+            self.annotations.remove(&address);
+        } else if self.annotations.len() >= crate::MAX_ANNOTATED_PEERS && !self.annotations.contains_key(&address) {
+            return Err(NetworkError::InvalidPeerAnnotation(format!(
+                "more than {} peers are annotated",
+                crate::MAX_ANNOTATED_PEERS
+            )));
+        } else {
+            self.annotations.insert(address, annotation);
+        }
+
+        Ok(())
+    }
+
+    pub fn serialize(&self) -> Result<Vec<u8>, NetworkError> {
+        let persisted = PersistedAddresses {
+            key: self.key,
+            addresses: self
+                .addresses
+                .iter()
+                .map(|(&address, info)| (address, info.clone()))
+                .collect(),
+            annotations: self
+                .annotations
+                .iter()
+                .map(|(&address, annotation)| (address, annotation.clone()))
+                .collect(),
+        };
+
+        Ok(bincode::serialize(&persisted)?)
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, NetworkError> {
+        let persisted: PersistedAddresses = match bincode::deserialize(bytes) {
+            Ok(persisted) => persisted,
+            Err(e) => match bincode::deserialize::<LegacyPersistedAddresses>(bytes) {
+                Ok(legacy) => PersistedAddresses {
+                    key: legacy.key,
+                    addresses: legacy.addresses,
+                    annotations: vec![],
+                },
+                Err(_) => return Err(e.into()),
+            },
+        };
+
+        let mut manager = Self::with_key(persisted.key);
+        for (address, info) in persisted.addresses {
+            // A duplicate would end up in two buckets at once.
+            if manager.contains(address) {
+                continue;
+            }
+            if info.tried {
+                manager.insert_tried(address, info);
+            } else {
+                manager.insert_new(address, info);
+            }
+        }
+        for (address, annotation) in persisted.annotations {
+            // The malformed annotations are skipped.
+            let _ = manager.annotate(address, annotation);
+        }
+
+        Ok(manager)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(a: u8, b: u8, c: u8) -> SocketAddr {
+        SocketAddr::new([a, b, c, 1].into(), 4131)
+    }
+
+    #[test]
+    fn addresses_are_added_once() {
+        let mut manager = AddressManager::default();
+        let source = Some(address(1, 1, 1));
+
+        assert_eq!(manager.add(address(2, 2, 2), source), Insertion::Inserted(None));
+        assert_eq!(manager.add(address(2, 2, 2), source), Insertion::Known);
+        assert_eq!(manager.len(), 1);
+        assert!(!manager.is_tried(address(2, 2, 2)));
+    }
+
+    #[test]
+    fn a_single_source_fills_few_buckets() {
+        let mut manager = AddressManager::default();
+        let source = Some(address(1, 1, 1));
+
+        for a in 2..=255 {
+            for b in 0..8 {
+                manager.add(address(a, b, 0), source);
+            }
+        }
+
+        let used_buckets = manager.new_buckets.iter().filter(|bucket| !bucket.is_empty()).count();
+        assert!(used_buckets as u64 <= crate::NEW_BUCKETS_PER_SOURCE_GROUP);
+        assert!(manager.len() <= crate::NEW_BUCKETS_PER_SOURCE_GROUP as usize * crate::ADDRESS_BUCKET_SIZE);
+    }
+
+    #[test]
+    fn full_buckets_only_evict_terrible_addresses() {
+        let mut manager = AddressManager::default();
+        let source = Some(address(1, 1, 1));
+
+        // Addresses from the same group and source share a bucket.
+        for c in 0..crate::ADDRESS_BUCKET_SIZE as u8 {
+            assert_eq!(manager.add(address(2, 2, c), source), Insertion::Inserted(None));
+        }
+        assert_eq!(manager.add(address(2, 2, 200), source), Insertion::Full);
+
+        // An address that couldn't be connected to can be replaced.
+        let terrible = address(2, 2, 0);
+        manager.addresses.get_mut(&terrible).unwrap().failed_attempts = crate::MAX_ADDRESS_FAILURES;
+        assert_eq!(
+            manager.add(address(2, 2, 200), source),
+            Insertion::Inserted(Some(terrible))
+        );
+        assert!(!manager.contains(terrible));
+    }
+
+    #[test]
+    fn culling_evicts_dead_addresses() {
+        let mut manager = AddressManager::default();
+        let source = Some(address(1, 1, 1));
+        let policy = PeerEvictionPolicy {
+            max_peers: 2,
+            max_age: Duration::from_secs(3600),
+            max_failures: 5,
+        };
+        let now = Utc::now();
+
+        for c in 0..6 {
+            manager.add(address(2, c, 0), source);
+        }
+        manager.mark_good(address(2, 0, 0));
+        manager.addresses.get_mut(&address(2, 1, 0)).unwrap().failed_attempts = 5;
+        manager.addresses.get_mut(&address(2, 2, 0)).unwrap().added = now - chrono::Duration::hours(2);
+        manager.addresses.get_mut(&address(2, 3, 0)).unwrap().added = now - chrono::Duration::minutes(30);
+
+        // The address that isn't evictable stays even though it's failing too.
+        manager.addresses.get_mut(&address(2, 5, 0)).unwrap().failed_attempts = 5;
+        let mut evicted = manager.cull(&policy, |known| known != address(2, 5, 0), now);
+        evicted.sort_unstable_by_key(|&(evicted, _)| evicted);
+
+        assert_eq!(
+            evicted,
+            vec![
+                (address(2, 1, 0), Eviction::Failing),
+                (address(2, 2, 0), Eviction::Expired),
+                (address(2, 3, 0), Eviction::Excess),
+            ]
+        );
+        assert_eq!(manager.len(), 3);
+        assert!(manager.is_tried(address(2, 0, 0)) && manager.contains(address(2, 4, 0)));
+    }
+
+    #[test]
+    fn good_addresses_are_moved_to_the_tried_table() {
+        let mut manager = AddressManager::default();
+        let peer = address(2, 2, 2);
+
+        manager.add(peer, Some(address(1, 1, 1)));
+        manager.mark_attempt(peer);
+        assert_eq!(manager.mark_good(peer), None);
+
+        assert!(manager.is_tried(peer));
+        assert_eq!(manager.addresses[&peer].failed_attempts, 0);
+        assert!(manager.new_buckets.iter().all(|bucket| bucket.is_empty()));
+        assert_eq!(
+            manager.tried_buckets.iter().map(|bucket| bucket.len()).sum::<usize>(),
+            1
+        );
+    }
+
+    #[test]
+    fn selection_prefers_diverse_groups() {
+        let mut manager = AddressManager::default();
+        let source = Some(address(1, 1, 1));
+
+        for c in 0..10 {
+            manager.add(address(2, 2, c), source);
+        }
+        manager.add(address(3, 3, 3), source);
+        manager.add(address(4, 4, 4), source);
+
+        // The addresses from an occupied group are only selected if there are no others.
+        let selected = manager.select(2, |_| true, &[address(2, 2, 100)]);
+        assert_eq!(selected.len(), 2);
+        assert!(selected.contains(&address(3, 3, 3)) && selected.contains(&address(4, 4, 4)));
+
+        let selected = manager.select(5, |address| address.ip() != IpAddr::from([3, 3, 3, 1]), &[]);
+        assert_eq!(selected.len(), 5);
+        assert!(!selected.contains(&address(3, 3, 3)));
+    }
+
+    #[test]
+    fn unverified_addresses_are_selected_last() {
+        let mut manager = AddressManager::default();
+        let source = Some(address(1, 1, 1));
+
+        for c in 2..6 {
+            manager.add(address(c, c, c), source);
+        }
+        manager.mark_unverified(address(2, 2, 2));
+        manager.mark_unverified(address(3, 3, 3));
+        assert_eq!(manager.unverified_count(), 2);
+
+        let selected = manager.select(2, |_| true, &[]);
+        assert!(selected.contains(&address(4, 4, 4)) && selected.contains(&address(5, 5, 5)));
+        assert_eq!(manager.select(4, |_| true, &[]).len(), 4);
+
+        // A failed probe counts as a failed attempt, while a successful one lifts the deprioritization.
+        manager.mark_probed(address(2, 2, 2), false);
+        assert!(manager.is_unverified(address(2, 2, 2)));
+        assert_eq!(manager.addresses[&address(2, 2, 2)].failed_attempts, 1);
+        manager.mark_probed(address(3, 3, 3), true);
+        assert!(!manager.is_unverified(address(3, 3, 3)));
+
+        manager.remove(address(2, 2, 2));
+        assert_eq!(manager.unverified_count(), 0);
+    }
+
+    #[test]
+    fn serialization_preserves_the_tables() {
+        let mut manager = AddressManager::default();
+        let source = Some(address(1, 1, 1));
+
+        for c in 0..10 {
+            manager.add(address(2, c, 0), source);
+        }
+        manager.mark_good(address(2, 0, 0));
+
+        let restored = AddressManager::deserialize(&manager.serialize().unwrap()).unwrap();
+        assert_eq!(restored.len(), manager.len());
+        assert!(restored.is_tried(address(2, 0, 0)));
+        // The order of the addresses within the buckets may differ.
+        let sorted = |buckets: &[Vec<SocketAddr>]| {
+            buckets
+                .iter()
+                .map(|bucket| {
+                    let mut bucket = bucket.clone();
+                    bucket.sort_unstable();
+                    bucket
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(sorted(&restored.new_buckets), sorted(&manager.new_buckets));
+        assert_eq!(sorted(&restored.tried_buckets), sorted(&manager.tried_buckets));
+    }
+
+    #[test]
+    fn annotations_are_validated_and_persisted() {
+        let mut manager = AddressManager::default();
+        let annotation = PeerAnnotation {
+            labels: vec!["my-backup-node".into()],
+            note: Some("hosted in the second datacenter".into()),
+        };
+
+        let malformed = PeerAnnotation {
+            labels: vec!["not a label".into()],
+            note: None,
+        };
+        assert!(manager.annotate(address(2, 2, 2), malformed).is_err());
+
+        manager.annotate(address(2, 2, 2), annotation.clone()).unwrap();
+        let restored = AddressManager::deserialize(&manager.serialize().unwrap()).unwrap();
+        assert_eq!(restored.annotation(address(2, 2, 2)), Some(&annotation));
+
+        manager.annotate(address(2, 2, 2), PeerAnnotation::default()).unwrap();
+        assert!(manager.annotation(address(2, 2, 2)).is_none());
+    }
+
+    #[test]
+    fn peer_books_without_annotations_can_be_restored() {
+        #[derive(Serialize)]
+        struct Legacy {
+            key: u64,
+            addresses: Vec<(SocketAddr, AddressInfo)>,
+        }
+
+        let legacy = Legacy {
+            key: 1,
+            addresses: vec![(address(2, 2, 2), AddressInfo::new(vec![]))],
+        };
+        let restored = AddressManager::deserialize(&bincode::serialize(&legacy).unwrap()).unwrap();
+        assert!(restored.contains(address(2, 2, 2)));
+        assert_eq!(restored.annotations().count(), 0);
+    }
+
+    #[test]
+    fn known_peers_can_be_imported() {
+        let mut manager = AddressManager::default();
+        let source = Some(address(1, 1, 1));
+
+        manager.add(address(2, 2, 2), source);
+        manager.add(address(3, 3, 3), source);
+        manager.mark_good(address(3, 3, 3));
+
+        let mut peers = manager.known_peers();
+        assert_eq!(peers.len(), 2);
+        assert!(!peers[0].tried && peers[1].tried);
+
+        // Addresses that kept failing are skipped.
+        let mut failing = peers[0].clone();
+        failing.address = address(4, 4, 4);
+        failing.failed_attempts = crate::MAX_ADDRESS_FAILURES;
+        peers.push(failing);
+
+        let mut imported = AddressManager::default();
+        assert_eq!(imported.import(&peers), 2);
+        assert!(imported.contains(address(2, 2, 2)) && imported.contains(address(3, 3, 3)));
+        assert!(!imported.contains(address(4, 4, 4)));
+        // Imported addresses need to be connected to before they're trusted.
+        assert!(!imported.is_tried(address(3, 3, 3)));
+
+        assert_eq!(imported.import(&peers), 0);
+    }
+}
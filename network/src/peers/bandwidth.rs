@@ -0,0 +1,177 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::Payload;
+
+/// A cap on the rate at which bytes are written, enforced with a token bucket holding up to a second's worth of
+/// them.
+#[derive(Debug)]
+pub struct BandwidthLimit {
+    /// The maximum sustained rate, in bytes per second.
+    bytes_per_sec: u64,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// The number of bytes that can be written right away; it's negative while the cap is exceeded.
+    available: f64,
+    /// The time at which the bucket was last refilled.
+    refilled: Instant,
+}
+
+impl BandwidthLimit {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        let bytes_per_sec = bytes_per_sec.max(1);
+
+        Self {
+            bytes_per_sec,
+            bucket: Mutex::new(Bucket {
+                available: bytes_per_sec as f64,
+                refilled: Instant::now(),
+            }),
+        }
+    }
+
+    /// Returns the maximum sustained rate, in bytes per second.
+    pub fn bytes_per_sec(&self) -> u64 {
+        self.bytes_per_sec
+    }
+
+    /// Counts the given number of bytes as written, and returns the amount of time after which writing them keeps
+    /// the rate within the cap; it's zero if they fit in the current budget.
+    pub fn consume(&self, bytes: usize) -> Duration {
+        let rate = self.bytes_per_sec as f64;
+        let mut bucket = self.bucket.lock().unwrap();
+
+        let now = Instant::now();
+        let refill = now.saturating_duration_since(bucket.refilled).as_secs_f64() * rate;
+        bucket.available = (bucket.available + refill).min(rate) - bytes as f64;
+        bucket.refilled = now;
+
+        if bucket.available >= 0.0 {
+            Duration::from_secs(0)
+        } else {
+            Duration::from_secs_f64(-bucket.available / rate)
+        }
+    }
+}
+
+/// The bandwidth caps the payloads written to a peer are subject to.
+#[derive(Debug, Default)]
+pub struct OutboundThrottle {
+    /// The cap of the connection with the peer.
+    pub peer: Option<BandwidthLimit>,
+    /// The node-wide cap, shared by the connections with all the peers.
+    pub node: Option<Arc<BandwidthLimit>>,
+}
+
+impl OutboundThrottle {
+    /// Returns `true` if the throttle doesn't enforce any cap.
+    pub fn is_unlimited(&self) -> bool {
+        self.peer.is_none() && self.node.is_none()
+    }
+
+    /// Returns `true` if the given payload can be deferred in order to stay within the caps. Only the bulk payloads
+    /// served to syncing peers are; the ones that matter to consensus, e.g. new blocks and transactions, are written
+    /// right away, but still count towards the caps.
+    pub fn is_deferrable(payload: &Payload) -> bool {
+        matches!(
+            payload,
+            Payload::MemoryPool(_) | Payload::Peers(_) | Payload::Sync(_) | Payload::SyncBlock(_)
+        )
+    }
+
+    /// Counts the given number of bytes as written to the peer, and returns the amounts of time after which writing
+    /// them keeps the rate within each of the caps.
+    pub fn consume(&self, bytes: usize) -> ThrottleDelay {
+        let peer_delay = self.peer.as_ref().map(|limit| limit.consume(bytes));
+        let node_delay = self.node.as_ref().map(|limit| limit.consume(bytes));
+
+        ThrottleDelay {
+            peer: peer_delay.unwrap_or_default(),
+            node: node_delay.unwrap_or_default(),
+        }
+    }
+}
+
+/// The amounts of time the bytes written to a peer need to wait for in order to stay within each of the caps.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ThrottleDelay {
+    /// The delay imposed by the cap of the connection with the peer.
+    pub peer: Duration,
+    /// The delay imposed by the node-wide cap.
+    pub node: Duration,
+}
+
+impl ThrottleDelay {
+    /// Returns the delay that keeps the rate within all the caps.
+    pub fn total(&self) -> Duration {
+        self.peer.max(self.node)
+    }
+
+    /// Returns `true` if the cap of the connection with the peer is the one imposing the delay.
+    pub fn is_due_to_peer(&self) -> bool {
+        self.peer > Duration::from_secs(0) && self.peer >= self.node
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bandwidth_limit_delays_excess_bytes() {
+        let limit = BandwidthLimit::new(1000);
+
+        // A second's worth of bytes fits in the initial budget.
+        assert_eq!(limit.consume(1000), Duration::from_secs(0));
+        // The excess needs to wait for the bucket to refill.
+        let delay = limit.consume(500);
+        assert!(delay > Duration::from_millis(400) && delay <= Duration::from_millis(500));
+        let delay = limit.consume(500);
+        assert!(delay > Duration::from_millis(900) && delay <= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn throttle_applies_the_stricter_cap() {
+        let throttle = OutboundThrottle {
+            peer: Some(BandwidthLimit::new(100)),
+            node: Some(Arc::new(BandwidthLimit::new(10_000))),
+        };
+
+        assert_eq!(throttle.consume(100).total(), Duration::from_secs(0));
+        let delay = throttle.consume(100);
+        assert!(delay.total() > Duration::from_millis(900));
+        assert!(delay.is_due_to_peer());
+
+        // The delays imposed by the node-wide cap aren't attributed to the peer.
+        let throttle = OutboundThrottle {
+            peer: Some(BandwidthLimit::new(10_000)),
+            node: Some(Arc::new(BandwidthLimit::new(100))),
+        };
+        throttle.consume(100);
+        assert!(!throttle.consume(100).is_due_to_peer());
+        assert!(OutboundThrottle::default().is_unlimited());
+        assert!(!OutboundThrottle::is_deferrable(&Payload::Ping(0, 0)));
+        assert!(OutboundThrottle::is_deferrable(&Payload::SyncBlock(vec![])));
+    }
+}
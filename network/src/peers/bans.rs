@@ -0,0 +1,409 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+    sync::RwLock,
+    time::Duration,
+};
+
+use chrono::{DateTime, TimeZone, Utc};
+use snarkvm_dpc::Storage;
+
+use crate::{normalize_address, NetworkError, Node, WebhookEvent};
+
+/// A range of IP addresses, e.g. `10.0.0.0/8`; a single address is a subnet with the full prefix length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Subnet {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl Subnet {
+    /// Creates the subnet of the given prefix length that contains the given address.
+    pub fn new(ip: IpAddr, prefix_len: u8) -> Result<Self, NetworkError> {
+        let ip = normalize_address(SocketAddr::new(ip, 0)).ip();
+        let network = match ip {
+            IpAddr::V4(ip) if prefix_len <= 32 => {
+                IpAddr::V4((u32::from(ip) & Self::mask(prefix_len, 32) as u32).into())
+            }
+            IpAddr::V6(ip) if prefix_len <= 128 => IpAddr::V6((u128::from(ip) & Self::mask(prefix_len, 128)).into()),
+            _ => {
+                return Err(NetworkError::InvalidSubnet(format!(
+                    "the prefix length of {} can't be {}",
+                    ip, prefix_len
+                )));
+            }
+        };
+
+        Ok(Self { network, prefix_len })
+    }
+
+    /// Creates a subnet containing only the given address.
+    pub fn host(ip: IpAddr) -> Self {
+        let prefix_len = if ip.is_ipv4() { 32 } else { 128 };
+        Self::new(ip, prefix_len).expect("the full prefix length is always valid")
+    }
+
+    /// Returns the mask of the given prefix length for addresses of the given number of bits.
+    fn mask(prefix_len: u8, bits: u8) -> u128 {
+        if prefix_len == 0 {
+            0
+        } else {
+            (u128::MAX << (128 - prefix_len)) >> (128 - bits)
+        }
+    }
+
+    /// Checks whether the given address belongs to the subnet.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        // IPv4 addresses of peers can show up mapped to IPv6 on dual-stack listeners.
+        match (self.network, normalize_address(SocketAddr::new(ip, 0)).ip()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                u32::from(ip) & Self::mask(self.prefix_len, 32) as u32 == u32::from(network)
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                u128::from(ip) & Self::mask(self.prefix_len, 128) == u128::from(network)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for Subnet {
+    type Err = NetworkError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || NetworkError::InvalidSubnet(format!("'{}' is not an IP address or a subnet", s));
+
+        let mut parts = s.splitn(2, '/');
+        let ip = parts.next().unwrap_or_default().parse().map_err(|_| invalid())?;
+
+        match parts.next() {
+            Some(prefix_len) => Self::new(ip, prefix_len.parse().map_err(|_| invalid())?),
+            None => Ok(Self::host(ip)),
+        }
+    }
+}
+
+impl fmt::Display for Subnet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if *self == Self::host(self.network) {
+            write!(f, "{}", self.network)
+        } else {
+            write!(f, "{}/{}", self.network, self.prefix_len)
+        }
+    }
+}
+
+///
+/// The range of addresses the ban of a peer covers. Peers are told apart by their IPs, as the ports of their inbound
+/// connections are ephemeral, so a ban covers the peer's whole IP by default, along with any other nodes behind it,
+/// e.g. behind the same NAT. It can be widened to the subnet of the given prefix length the peer belongs to, e.g. to
+/// keep a peer from coming back from another address of its IPv6 `/64`.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BanScope {
+    ipv4_prefix_len: u8,
+    ipv6_prefix_len: u8,
+}
+
+impl Default for BanScope {
+    fn default() -> Self {
+        Self {
+            ipv4_prefix_len: 32,
+            ipv6_prefix_len: 128,
+        }
+    }
+}
+
+impl BanScope {
+    /// Creates a scope covering the subnets of the given prefix lengths.
+    pub fn new(ipv4_prefix_len: u8, ipv6_prefix_len: u8) -> Result<Self, NetworkError> {
+        if ipv4_prefix_len > 32 || ipv6_prefix_len > 128 {
+            return Err(NetworkError::InvalidSubnet(format!(
+                "the prefix lengths can't be {} for IPv4 and {} for IPv6",
+                ipv4_prefix_len, ipv6_prefix_len
+            )));
+        }
+
+        Ok(Self {
+            ipv4_prefix_len,
+            ipv6_prefix_len,
+        })
+    }
+
+    /// Returns the subnet a ban of the given IP covers.
+    pub fn subnet_of(&self, ip: IpAddr) -> Subnet {
+        let prefix_len = match normalize_address(SocketAddr::new(ip, 0)).ip() {
+            IpAddr::V4(_) => self.ipv4_prefix_len,
+            IpAddr::V6(_) => self.ipv6_prefix_len,
+        };
+
+        Subnet::new(ip, prefix_len).expect("the prefix lengths were checked")
+    }
+}
+
+///
+/// The IPs and subnets that can't be connected to or accept connections from until the associated time.
+///
+#[derive(Debug, Default)]
+pub struct BanList {
+    bans: RwLock<HashMap<Subnet, DateTime<Utc>>>,
+}
+
+impl BanList {
+    /// Bans the given subnet until the given time, replacing any previous ban.
+    pub fn insert(&self, subnet: Subnet, expiry: DateTime<Utc>) {
+        self.bans.write().unwrap().insert(subnet, expiry);
+    }
+
+    /// Lifts the ban of the given subnet; returns `false` if it wasn't banned.
+    pub fn remove(&self, subnet: Subnet) -> bool {
+        self.bans.write().unwrap().remove(&subnet).is_some()
+    }
+
+    /// Checks whether the given address belongs to any currently banned subnet.
+    pub fn is_banned(&self, ip: IpAddr) -> bool {
+        let now = Utc::now();
+        self.bans
+            .read()
+            .unwrap()
+            .iter()
+            .any(|(subnet, expiry)| *expiry > now && subnet.contains(ip))
+    }
+
+    /// Returns the currently banned subnets along with the times their bans expire at.
+    pub fn active(&self) -> Vec<(Subnet, DateTime<Utc>)> {
+        let now = Utc::now();
+        let mut bans = self
+            .bans
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, expiry)| **expiry > now)
+            .map(|(subnet, expiry)| (*subnet, *expiry))
+            .collect::<Vec<_>>();
+        bans.sort_unstable();
+
+        bans
+    }
+
+    /// Forgets the expired bans, returning their subnets.
+    pub fn prune(&self) -> Vec<Subnet> {
+        let now = Utc::now();
+        let mut expired = vec![];
+        self.bans.write().unwrap().retain(|subnet, expiry| {
+            let is_active = *expiry > now;
+            if !is_active {
+                expired.push(*subnet);
+            }
+            is_active
+        });
+
+        expired
+    }
+}
+
+impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
+    /// Checks whether the given address is banned, either via the config or at runtime.
+    pub fn is_banned(&self, ip: IpAddr) -> bool {
+        self.config.banned_subnets().iter().any(|subnet| subnet.contains(ip)) || self.bans.is_banned(ip)
+    }
+
+    ///
+    /// Refuses connections with the given subnet for the given duration, disconnecting from its peers first.
+    /// The ban is persisted, so it survives restarts; if that fails, the ban is still applied until the node
+    /// shuts down, and the error is returned.
+    ///
+    pub async fn ban(&self, subnet: Subnet, duration: Duration) -> Result<(), NetworkError> {
+        let expiry = chrono::Duration::from_std(duration)
+            .ok()
+            .and_then(|duration| Utc::now().checked_add_signed(duration))
+            .unwrap_or(chrono::MAX_DATETIME);
+        self.bans.insert(subnet, expiry);
+
+        // Without the `consensus` feature, there's no storage to persist the ban in.
+        #[cfg(feature = "consensus")]
+        let persisted = match self.sync() {
+            Some(sync) => sync.storage().store_ban(&subnet.to_string(), expiry.timestamp()),
+            None => Ok(()),
+        };
+        #[cfg(not(feature = "consensus"))]
+        let persisted = Ok::<(), NetworkError>(());
+
+        for address in self.connected_peers() {
+            if subnet.contains(address.ip()) {
+                self.disconnect_from_peer(address).await;
+            }
+        }
+
+        debug!("Banned {} until {}", subnet, expiry);
+
+        self.notify_webhook(
+            WebhookEvent::PeerBanned,
+            serde_json::json!({
+                "address": subnet.to_string(),
+                "duration_secs": duration.as_secs(),
+            }),
+        );
+
+        persisted?;
+
+        Ok(())
+    }
+
+    ///
+    /// Lifts the ban of the given subnet; returns `false` if it wasn't banned at runtime. The bans from the
+    /// config can only be lifted by changing it.
+    ///
+    pub fn unban(&self, subnet: Subnet) -> bool {
+        if !self.bans.remove(subnet) {
+            return false;
+        }
+
+        #[cfg(feature = "consensus")]
+        if let Some(sync) = self.sync() {
+            if let Err(e) = sync.storage().remove_bans(&[subnet.to_string()]) {
+                warn!("Couldn't remove the persisted ban of {}: {}", subnet, e);
+            }
+        }
+
+        debug!("Lifted the ban of {}", subnet);
+
+        true
+    }
+
+    /// Restores the bans persisted in storage, dropping the ones that expired in the meantime.
+    #[cfg(feature = "consensus")]
+    pub(crate) fn load_bans(&self) {
+        let sync = match self.sync() {
+            Some(sync) => sync,
+            None => return,
+        };
+
+        let bans = match sync.storage().get_bans() {
+            Ok(bans) => bans,
+            Err(e) => {
+                warn!("Couldn't load the bans: {}", e);
+                return;
+            }
+        };
+
+        let mut expired = vec![];
+        for (subnet, expiry) in bans {
+            match (subnet.parse::<Subnet>(), Utc.timestamp_opt(expiry, 0).single()) {
+                (Ok(parsed), Some(expiry)) if expiry > Utc::now() => self.bans.insert(parsed, expiry),
+                _ => expired.push(subnet),
+            }
+        }
+
+        if !expired.is_empty() {
+            if let Err(e) = sync.storage().remove_bans(&expired) {
+                warn!("Couldn't remove the expired bans: {}", e);
+            }
+        }
+
+        debug!("Restored {} bans from storage", self.bans.active().len());
+    }
+
+    /// Forgets the expired bans, also removing them from storage.
+    pub(crate) fn prune_bans(&self) {
+        let expired = self.bans.prune().iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        #[cfg(feature = "consensus")]
+        if let (Some(sync), false) = (self.sync(), expired.is_empty()) {
+            if let Err(e) = sync.storage().remove_bans(&expired) {
+                warn!("Couldn't remove the expired bans: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subnets_are_parsed_and_displayed() {
+        let subnet = "10.1.2.3/8".parse::<Subnet>().unwrap();
+        assert_eq!(subnet.to_string(), "10.0.0.0/8");
+
+        let host = "192.168.1.1".parse::<Subnet>().unwrap();
+        assert_eq!(host, Subnet::host("192.168.1.1".parse().unwrap()));
+        assert_eq!(host.to_string(), "192.168.1.1");
+
+        let subnet = "2001:db8::1/32".parse::<Subnet>().unwrap();
+        assert_eq!(subnet.to_string(), "2001:db8::/32");
+
+        assert!("10.0.0.0/33".parse::<Subnet>().is_err());
+        assert!("10.0.0.0/".parse::<Subnet>().is_err());
+        assert!("localhost".parse::<Subnet>().is_err());
+    }
+
+    #[test]
+    fn ban_scopes_cover_ips_or_their_subnets() {
+        let ip = "192.168.1.1".parse().unwrap();
+        assert_eq!(BanScope::default().subnet_of(ip), Subnet::host(ip));
+
+        let scope = BanScope::new(24, 64).unwrap();
+        assert_eq!(scope.subnet_of(ip).to_string(), "192.168.1.0/24");
+        assert_eq!(
+            scope.subnet_of("2001:db8::1".parse().unwrap()).to_string(),
+            "2001:db8::/64"
+        );
+
+        assert!(BanScope::new(33, 64).is_err());
+    }
+
+    #[test]
+    fn subnets_contain_their_addresses() {
+        let subnet = "10.0.0.0/8".parse::<Subnet>().unwrap();
+        assert!(subnet.contains("10.255.0.1".parse().unwrap()));
+        assert!(!subnet.contains("11.0.0.1".parse().unwrap()));
+        // mapped addresses are treated like the IPv4 ones
+        assert!(subnet.contains("::ffff:10.0.0.1".parse().unwrap()));
+
+        let everything = "0.0.0.0/0".parse::<Subnet>().unwrap();
+        assert!(everything.contains("1.2.3.4".parse().unwrap()));
+        assert!(!everything.contains("2001:db8::1".parse().unwrap()));
+
+        let subnet = "2001:db8::/32".parse::<Subnet>().unwrap();
+        assert!(subnet.contains("2001:db8:ffff::1".parse().unwrap()));
+        assert!(!subnet.contains("2001:db9::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn expired_bans_are_ignored_and_pruned() {
+        let bans = BanList::default();
+        let active = "10.0.0.0/8".parse::<Subnet>().unwrap();
+        let expired = "192.168.1.1".parse::<Subnet>().unwrap();
+
+        bans.insert(active, Utc::now() + chrono::Duration::hours(1));
+        bans.insert(expired, Utc::now() - chrono::Duration::hours(1));
+
+        assert!(bans.is_banned("10.0.0.1".parse().unwrap()));
+        assert!(!bans.is_banned("192.168.1.1".parse().unwrap()));
+        assert_eq!(bans.active().len(), 1);
+
+        assert_eq!(bans.prune(), vec![expired]);
+        assert!(bans.remove(active));
+        assert!(!bans.remove(active));
+        assert!(!bans.is_banned("10.0.0.1".parse().unwrap()));
+    }
+}
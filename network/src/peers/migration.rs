@@ -0,0 +1,188 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+//! The migration of peer book entries when a node's listening address changes: the node announces the
+//! change to the peers it connects to, so that they replace its stale address instead of gossiping it.
+
+use crate::{EventCategory, Node};
+use snarkvm_dpc::Storage;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// A change of the node's announced address, which is announced to the peers it connects to for
+/// `ADDRESS_CHANGE_ANNOUNCEMENT_SECS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingAddressChange {
+    /// The address the node announced before.
+    pub previous: SocketAddr,
+    /// The address the node announces now.
+    pub address: SocketAddr,
+    /// The time the change was detected at.
+    pub since: DateTime<Utc>,
+}
+
+impl PendingAddressChange {
+    /// Checks whether the change is still being announced to the peers.
+    pub fn is_pending(&self) -> bool {
+        Utc::now() - self.since < chrono::Duration::seconds(crate::ADDRESS_CHANGE_ANNOUNCEMENT_SECS.into())
+    }
+}
+
+/// The address the node announced on its previous run, persisted along with the change that led to it, so that
+/// the change keeps being announced after a restart.
+#[cfg(feature = "consensus")]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct AnnouncedAddress {
+    address: SocketAddr,
+    change: Option<PendingAddressChange>,
+}
+
+#[cfg(feature = "consensus")]
+impl AnnouncedAddress {
+    /// Deserializes the persisted address; the storages written before the changes were persisted only hold the
+    /// address itself.
+    fn deserialize(bytes: &[u8]) -> Option<Self> {
+        bincode::deserialize::<Self>(bytes).ok().or_else(|| {
+            bincode::deserialize::<SocketAddr>(bytes)
+                .ok()
+                .map(|address| Self { address, change: None })
+        })
+    }
+}
+
+impl<S: Storage + Send + Sync + 'static> Node<S> {
+    /// Returns the address the node announces to its peers: the configured advertised address or, if there's
+    /// none, its listening port on an unspecified IP, which stands for the IP its connections come from.
+    pub fn announced_address(&self) -> Option<SocketAddr> {
+        if self.config.no_listen() {
+            return None;
+        }
+
+        match self.config.advertised_address {
+            Some(address) => Some(address),
+            None => self.local_address().map(|address| {
+                let ip = match address.ip() {
+                    IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+                    IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+                };
+                SocketAddr::new(ip, address.port())
+            }),
+        }
+    }
+
+    /// Compares the announced address with the one persisted in storage on the previous run, and registers
+    /// the change to be announced to the peers if they differ; a change detected on an earlier run keeps being
+    /// announced until its announcement period is over.
+    #[cfg(feature = "consensus")]
+    pub(crate) fn check_announced_address(&self) {
+        let (sync, address) = match (self.sync(), self.announced_address()) {
+            (Some(sync), Some(address)) => (sync, address),
+            _ => return,
+        };
+
+        let persisted = match sync.storage().get_announced_address() {
+            Ok(Some(bytes)) => AnnouncedAddress::deserialize(&bytes),
+            Ok(None) => None,
+            Err(e) => {
+                warn!("Couldn't load the previously announced address: {}", e);
+                return;
+            }
+        };
+
+        let change = match persisted {
+            Some(persisted) if persisted.address == address => persisted.change,
+            Some(AnnouncedAddress { address: previous, .. }) => {
+                info!(
+                    "The node's address changed from {} to {}; announcing it to peers",
+                    previous, address
+                );
+                self.journal.record(
+                    EventCategory::Lifecycle,
+                    format!("changed the announced address from {} to {}", previous, address),
+                );
+
+                Some(PendingAddressChange {
+                    previous,
+                    address,
+                    since: Utc::now(),
+                })
+            }
+            None => None,
+        };
+        let change = change.filter(|change| change.is_pending());
+
+        if persisted.map(|persisted| (persisted.address, persisted.change)) != Some((address, change)) {
+            match bincode::serialize(&AnnouncedAddress { address, change }) {
+                Ok(bytes) => {
+                    if let Err(e) = sync.storage().save_announced_address(bytes) {
+                        warn!("Couldn't save the announced address: {}", e);
+                    }
+                }
+                Err(e) => warn!("Couldn't serialize the announced address: {}", e),
+            }
+        }
+
+        *self.address_change.lock().unwrap() = change;
+    }
+
+    /// Returns the change of the node's address that is still being announced to the peers, if any.
+    pub fn pending_address_change(&self) -> Option<PendingAddressChange> {
+        self.address_change.lock().unwrap().filter(|change| change.is_pending())
+    }
+
+    ///
+    /// Migrates the peer book entries of the connected peer at `source`, which announced with a valid signature
+    /// that it moved from `previous` to `address`; `public_key` is the hex-encoded node key it was signed with.
+    /// The stale address is only dropped if it shares the peer's IP, or if it was last seen with the same node key,
+    /// so that peers can't evict the addresses of other nodes.
+    ///
+    pub(crate) async fn migrate_peer_address(
+        &self,
+        source: SocketAddr,
+        previous: SocketAddr,
+        address: SocketAddr,
+        public_key: String,
+    ) {
+        let resolve = |addr: SocketAddr| {
+            if addr.ip().is_unspecified() {
+                SocketAddr::new(source.ip(), addr.port())
+            } else {
+                addr
+            }
+        };
+        let (previous, address) = (resolve(previous), resolve(address));
+        if previous == address {
+            return;
+        }
+
+        if previous != source && !self.peer_book.is_connected(previous) {
+            let same_key = self
+                .peer_book
+                .get_disconnected_peer(previous)
+                .and_then(|peer| peer.quality.alias)
+                .map(|alias| alias.public_key.eq_ignore_ascii_case(&public_key))
+                .unwrap_or(false);
+
+            if (previous.ip() == source.ip() || same_key) && self.peer_book.remove_peer(previous).await {
+                debug!("Peer {} moved from {} to {}", source, previous, address);
+            }
+        }
+
+        self.process_inbound_peers(source, vec![address]).await;
+    }
+}
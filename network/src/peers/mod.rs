@@ -14,11 +14,38 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
+pub mod address;
+pub use address::*;
+
+pub mod address_manager;
+pub use address_manager::*;
+
+pub mod bandwidth;
+pub use bandwidth::*;
+
+pub mod bans;
+pub use bans::*;
+
+pub mod migration;
+pub use migration::*;
+
 pub mod peers;
 pub use peers::*;
 
 pub mod peer_book;
 pub use peer_book::*;
 
+pub mod port_policy;
+pub use port_policy::*;
+
+pub mod reachability;
+pub use reachability::*;
+
 pub mod peer;
 pub use peer::*;
+
+pub mod strategy;
+pub use strategy::*;
+
+pub mod sync_schedule;
+pub use sync_schedule::*;
@@ -45,6 +45,14 @@ impl Cipher {
         writer: &mut W,
         data: &[u8],
     ) -> Result<(), NetworkError> {
+        let frame = self.encrypt_packet(data)?;
+        writer.write_all(frame).await?;
+        writer.flush().await?;
+        Ok(())
+    }
+
+    /// Encrypts the given data into a frame ready to be written to the stream, i.e. prefixed with its length.
+    pub fn encrypt_packet(&mut self, data: &[u8]) -> Result<&[u8], NetworkError> {
         if data.len() > self.buffer.len() {
             return Err(NetworkError::MessageTooBig(data.len()));
         }
@@ -58,7 +66,7 @@ impl Cipher {
             );
             let chunk = &data[processed_len..][..chunk_len];
 
-            encrypted_len += self.state.write_message(chunk, &mut self.buffer[encrypted_len..])?;
+            encrypted_len += self.state.write_message(chunk, &mut self.buffer[4 + encrypted_len..])?;
             processed_len += chunk_len;
         }
 
@@ -68,13 +76,20 @@ impl Cipher {
         if encrypted_len > crate::MAX_MESSAGE_SIZE {
             return Err(NetworkError::MessageTooBig(encrypted_len));
         }
-        writer.write_all(&network_len.to_be_bytes()[..]).await?;
-        writer.write_all(&self.buffer[..encrypted_len]).await?;
-        writer.flush().await?;
-        Ok(())
+        // The first 4 bytes are reserved for the length prefix.
+        self.buffer[..4].copy_from_slice(&network_len.to_be_bytes()[..]);
+
+        Ok(&self.buffer[..4 + encrypted_len])
     }
 
-    pub fn read_packet(&mut self, payload: &[u8]) -> Result<&[u8], NetworkError> {
+    /// Decrypts the given frame, passing its first decrypted chunk to `check_head` first, so that the frame
+    /// can be rejected before the rest of it is decrypted.
+    pub fn read_packet_with<F: FnOnce(&[u8]) -> Result<(), NetworkError>>(
+        &mut self,
+        payload: &[u8],
+        check_head: F,
+    ) -> Result<&[u8], NetworkError> {
+        let mut check_head = Some(check_head);
         let mut decrypted_len = 0;
         let mut processed_len = 0;
 
@@ -84,11 +99,30 @@ impl Cipher {
 
             decrypted_len += self.state.read_message(chunk, &mut self.buffer[decrypted_len..])?;
             processed_len += chunk_len;
+
+            if let Some(check_head) = check_head.take() {
+                check_head(&self.buffer[..decrypted_len])?;
+            }
         }
 
         Ok(&self.buffer[..decrypted_len])
     }
 
+    /// Returns the data decrypted by the last call to `read_packet_with`, given its length.
+    pub fn decrypted(&self, len: usize) -> &[u8] {
+        &self.buffer[..len]
+    }
+
+    /// Replaces the key the outgoing packets are encrypted with.
+    pub fn rekey_outgoing(&mut self) {
+        self.state.rekey_outgoing();
+    }
+
+    /// Replaces the key the incoming packets are decrypted with.
+    pub fn rekey_incoming(&mut self) {
+        self.state.rekey_incoming();
+    }
+
     #[cfg(test)]
     pub async fn read_packet_stream<R: AsyncRead + Unpin>(&mut self, reader: &mut R) -> Result<&[u8], NetworkError> {
         let length = reader.read_u32().await? as usize;
@@ -100,6 +134,6 @@ impl Cipher {
         reader.read_exact(&mut self.buffer[..length]).await?;
         // only used in tests, so this is fine
         let copied = self.buffer[..length].to_vec();
-        self.read_packet(&copied[..])
+        self.read_packet_with(&copied[..], |_| Ok(()))
     }
 }
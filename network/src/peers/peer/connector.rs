@@ -16,6 +16,7 @@
 
 use std::{
     io::{Error as IoError, ErrorKind},
+    net::SocketAddr,
     time::Duration,
 };
 
@@ -25,18 +26,33 @@ use tokio::{net::TcpStream, sync::mpsc};
 
 use snarkos_metrics::{self as metrics, connections::*};
 
-use crate::{NetworkError, Node, Peer, PeerEvent, PeerEventData, PeerHandle, Version};
+use crate::{socks5_connect, NetworkError, Node, Peer, PeerEvent, PeerEventData, PeerHandle, Version};
 
 use super::{network::PeerIOHandle, PeerAction};
 
 const CONNECTION_TIMEOUT_SECS: u64 = 3;
+/// Connections routed through a proxy, e.g. Tor, can take considerably longer to establish.
+const PROXIED_CONNECTION_TIMEOUT_SECS: u64 = 15;
 
 impl Peer {
     pub fn connect<S: Storage + Send + Sync + 'static>(mut self, node: Node<S>, event_target: mpsc::Sender<PeerEvent>) {
         let (sender, receiver) = mpsc::channel::<PeerAction>(64);
         tokio::spawn(async move {
-            self.set_connecting();
-            match self.inner_connect(node.version()).await {
+            let result = {
+                // The connection attempts beyond the limit wait for the ongoing handshakes to conclude; the peer is
+                // only considered to be connecting, and seen, once its own attempt starts.
+                let _permit = node.handshake_permits.acquire().await;
+                self.set_connecting();
+                self.inner_connect(node.version(), node.config.socks5_proxy()).await
+            };
+
+            #[cfg(feature = "crawler")]
+            if let Some(known_network) = node.known_network() {
+                let outcome = result.as_ref().map(|_| ()).map_err(Into::into);
+                known_network.record_handshake(self.address, outcome);
+            }
+
+            match result {
                 Err(e) => {
                     self.fail();
                     if !e.is_trivial() {
@@ -53,15 +69,17 @@ impl Peer {
                 }
                 Ok(network) => {
                     self.set_connected();
+                    self.quality.is_outbound = true;
                     metrics::increment_gauge!(CONNECTED, 1.0);
+                    let preferred = self.is_preferred_connection(node.id, true);
                     event_target
                         .send(PeerEvent {
                             address: self.address,
-                            data: PeerEventData::Connected(PeerHandle { sender: sender.clone() }),
+                            data: PeerEventData::Connected(PeerHandle { sender: sender.clone() }, true, preferred),
                         })
                         .await
                         .ok();
-                    if let Err(e) = self.run(node, network, receiver).await {
+                    if let Err(e) = self.run(node.clone(), network, receiver).await {
                         if !e.is_trivial() {
                             self.fail();
                             error!(
@@ -76,6 +94,7 @@ impl Peer {
                         }
                     }
                     metrics::decrement_gauge!(CONNECTED, 1.0);
+                    node.forget_peer_time(self.address);
                 }
             }
             let state = self.status;
@@ -83,23 +102,39 @@ impl Peer {
             event_target
                 .send(PeerEvent {
                     address: self.address,
-                    data: PeerEventData::Disconnect(self, state),
+                    data: PeerEventData::Disconnect(self, state, PeerHandle { sender }),
                 })
                 .await
                 .ok();
         });
     }
 
-    async fn inner_connect(&mut self, our_version: Version) -> Result<PeerIOHandle, NetworkError> {
+    async fn inner_connect(
+        &mut self,
+        our_version: Version,
+        socks5_proxy: Option<SocketAddr>,
+    ) -> Result<PeerIOHandle, NetworkError> {
         metrics::increment_gauge!(CONNECTING, 1.0);
         let _x = defer::defer(|| metrics::decrement_gauge!(CONNECTING, 1.0));
 
+        let connection_timeout = match socks5_proxy {
+            Some(_) => Duration::from_secs(PROXIED_CONNECTION_TIMEOUT_SECS),
+            None => Duration::from_secs(CONNECTION_TIMEOUT_SECS),
+        };
+        let address = self.address;
+        let connect = async move {
+            match socks5_proxy {
+                Some(proxy) => socks5_connect(proxy, address).await,
+                None => Ok(TcpStream::connect(address).await?),
+            }
+        };
+
         let tcp_stream;
         select! {
-            stream = TcpStream::connect(self.address).fuse() => {
+            stream = connect.fuse() => {
                 tcp_stream = stream?;
             },
-            _ = tokio::time::sleep(Duration::from_secs(CONNECTION_TIMEOUT_SECS)).fuse() => {
+            _ = tokio::time::sleep(connection_timeout).fuse() => {
                 return Err(NetworkError::Io(IoError::new(ErrorKind::TimedOut, "connection timed out")));
             },
         }
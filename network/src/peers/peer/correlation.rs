@@ -0,0 +1,243 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+//! The correlation of the request/response message pairs exchanged with a peer. Each pair is assigned an ID and
+//! traced in a span lasting from the request to its response, so that a request can be followed through the peer's
+//! task, the inbound dispatch and the outbound response in the tracing output.
+
+use crate::Payload;
+
+use std::{
+    collections::HashMap,
+    fmt,
+    net::SocketAddr,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Instant,
+};
+use tracing::{field, Span};
+
+/// The source of the correlation IDs; they're unique within a single run of the node.
+static NEXT_CORRELATION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// The ID shared by the messages of a request/response pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CorrelationId(pub u64);
+
+impl CorrelationId {
+    /// Returns a new, unique ID.
+    pub fn next() -> Self {
+        Self(NEXT_CORRELATION_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl fmt::Display for CorrelationId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:x}", self.0)
+    }
+}
+
+/// The kinds of requests that are answered with a dedicated response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RequestKind {
+    /// A `GetPeers` answered with `Peers`.
+    GetPeers,
+    /// A `GetSync` answered with `Sync`.
+    GetSync,
+    /// A `Ping` answered with `Pong`.
+    Ping,
+}
+
+impl RequestKind {
+    /// Returns the kind of request the given payload is, if it's one.
+    pub fn of_request(payload: &Payload) -> Option<Self> {
+        match payload {
+            Payload::GetPeers => Some(Self::GetPeers),
+            Payload::GetSync(_) => Some(Self::GetSync),
+            Payload::Ping(..) => Some(Self::Ping),
+            _ => None,
+        }
+    }
+
+    /// Returns the kind of request the given payload responds to, if it's a response.
+    pub fn of_response(payload: &Payload) -> Option<Self> {
+        match payload {
+            Payload::Peers(_) => Some(Self::GetPeers),
+            Payload::Sync(_) => Some(Self::GetSync),
+            Payload::Pong => Some(Self::Ping),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RequestKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let str = match self {
+            Self::GetPeers => "getpeers",
+            Self::GetSync => "getsync",
+            Self::Ping => "ping",
+        };
+
+        f.write_str(str)
+    }
+}
+
+/// The side of the connection that sent a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RequestOrigin {
+    /// The node sent the request to the peer.
+    Local,
+    /// The peer sent the request to the node.
+    Remote,
+}
+
+impl RequestOrigin {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Local => "local",
+            Self::Remote => "remote",
+        }
+    }
+}
+
+/// A request awaiting its response.
+#[derive(Debug, Clone)]
+struct PendingRequest {
+    id: CorrelationId,
+    span: Span,
+    started: Instant,
+}
+
+/// The requests exchanged with a peer that are awaiting their responses; there's at most one of each kind in
+/// each direction, as a new request replaces an unanswered one.
+#[derive(Debug, Default, Clone)]
+pub struct Correlations {
+    pending: HashMap<(RequestOrigin, RequestKind), PendingRequest>,
+}
+
+impl Correlations {
+    /// Registers a payload sent to the peer, returning the ID and the span of the pair it belongs to, if any.
+    pub fn sent(&mut self, peer: SocketAddr, payload: &Payload) -> Option<(CorrelationId, Span)> {
+        if let Some(kind) = RequestKind::of_request(payload) {
+            Some(self.start(peer, RequestOrigin::Local, kind))
+        } else if let Some(kind) = RequestKind::of_response(payload) {
+            self.complete(RequestOrigin::Remote, kind)
+        } else {
+            None
+        }
+    }
+
+    /// Registers a payload received from the peer, returning the ID and the span of the pair it belongs to, if any.
+    pub fn received(&mut self, peer: SocketAddr, payload: &Payload) -> Option<(CorrelationId, Span)> {
+        if let Some(kind) = RequestKind::of_request(payload) {
+            Some(self.start(peer, RequestOrigin::Remote, kind))
+        } else if let Some(kind) = RequestKind::of_response(payload) {
+            self.complete(RequestOrigin::Local, kind)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the number of requests awaiting their responses.
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    fn start(&mut self, peer: SocketAddr, origin: RequestOrigin, kind: RequestKind) -> (CorrelationId, Span) {
+        let id = CorrelationId::next();
+        let span = debug_span!(
+            "request",
+            correlation = %id,
+            kind = %kind,
+            origin = origin.as_str(),
+            peer = %peer,
+            latency_ms = field::Empty,
+        );
+        span.in_scope(|| trace!("Started the '{}' request {}", kind, id));
+
+        let pending = PendingRequest {
+            id,
+            span: span.clone(),
+            started: Instant::now(),
+        };
+        if let Some(replaced) = self.pending.insert((origin, kind), pending) {
+            replaced
+                .span
+                .in_scope(|| trace!("The '{}' request {} was superseded", kind, replaced.id));
+        }
+
+        (id, span)
+    }
+
+    fn complete(&mut self, origin: RequestOrigin, kind: RequestKind) -> Option<(CorrelationId, Span)> {
+        let pending = self.pending.remove(&(origin, kind))?;
+        let latency_ms = pending.started.elapsed().as_millis() as u64;
+
+        pending.span.record("latency_ms", &latency_ms);
+        pending
+            .span
+            .in_scope(|| trace!("Completed the '{}' request {} in {}ms", kind, pending.id, latency_ms));
+
+        Some((pending.id, pending.span))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sent(correlations: &mut Correlations, payload: Payload) -> Option<CorrelationId> {
+        correlations
+            .sent("127.0.0.1:4131".parse().unwrap(), &payload)
+            .map(|(id, _)| id)
+    }
+
+    fn received(correlations: &mut Correlations, payload: Payload) -> Option<CorrelationId> {
+        correlations
+            .received("127.0.0.1:4131".parse().unwrap(), &payload)
+            .map(|(id, _)| id)
+    }
+
+    #[test]
+    fn requests_are_paired_with_their_responses() {
+        let mut correlations = Correlations::default();
+
+        let local = sent(&mut correlations, Payload::GetPeers).unwrap();
+        let remote = received(&mut correlations, Payload::GetSync(vec![])).unwrap();
+        assert_ne!(local, remote);
+        assert_eq!(correlations.pending_count(), 2);
+
+        // unrelated payloads and unsolicited responses don't belong to any pair
+        assert_eq!(received(&mut correlations, Payload::Transaction(vec![])), None);
+        assert_eq!(received(&mut correlations, Payload::Pong), None);
+
+        assert_eq!(received(&mut correlations, Payload::Peers(vec![])), Some(local));
+        assert_eq!(sent(&mut correlations, Payload::Sync(vec![])), Some(remote));
+        assert_eq!(correlations.pending_count(), 0);
+    }
+
+    #[test]
+    fn unanswered_requests_are_superseded() {
+        let mut correlations = Correlations::default();
+
+        let first = sent(&mut correlations, Payload::Ping(0, 0)).unwrap();
+        let second = sent(&mut correlations, Payload::Ping(1, 0)).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(correlations.pending_count(), 1);
+
+        assert_eq!(received(&mut correlations, Payload::Pong), Some(second));
+        assert_eq!(received(&mut correlations, Payload::Pong), None);
+    }
+}
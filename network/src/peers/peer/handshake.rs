@@ -16,7 +16,7 @@
 
 use std::net::SocketAddr;
 
-use snow::TransportState;
+use snow::{HandshakeState, TransportState};
 use tokio::{
     io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
     net::TcpStream,
@@ -26,6 +26,7 @@ use snarkos_metrics::{self as metrics, handshakes::*};
 
 use crate::{
     peer::{cipher::Cipher, network::PeerIOHandle},
+    Features,
     NetworkError,
     Peer,
     Version,
@@ -33,26 +34,56 @@ use crate::{
 
 pub struct HandshakeData {
     pub version: Version,
+    /// The hash of the handshake, unique to the connection.
+    pub handshake_hash: Vec<u8>,
     pub noise: TransportState,
     pub buffer: Box<[u8]>,
     pub noise_buffer: Box<[u8]>,
 }
 
-async fn responder_handshake<W: AsyncWrite + Unpin, R: AsyncRead + Unpin>(
-    remote_address: SocketAddr,
-    own_version: &Version,
-    writer: &mut W,
-    reader: &mut R,
-) -> Result<HandshakeData, NetworkError> {
+/// The fixed private keys used by one side of the handshake instead of randomly generated ones; they are only
+/// used to produce deterministic test vectors.
+#[cfg_attr(not(feature = "test-vectors"), allow(dead_code))]
+pub(crate) struct HandshakeKeys {
+    pub static_key: Vec<u8>,
+    pub ephemeral_key: Vec<u8>,
+}
+
+/// Prepares one side of the noise handshake, using random keys unless fixed ones are provided.
+fn handshake_state(keys: Option<&HandshakeKeys>, initiator: bool) -> Result<HandshakeState, NetworkError> {
     let builder = snow::Builder::with_resolver(
         crate::HANDSHAKE_PATTERN
             .parse()
             .expect("Invalid noise handshake pattern!"),
         Box::new(snow::resolvers::SodiumResolver),
     );
-    let static_key = builder.generate_keypair()?.private;
-    let noise_builder = builder.local_private_key(&static_key).psk(3, crate::HANDSHAKE_PSK);
-    let mut noise = noise_builder.build_responder()?;
+    let generated_key;
+    let noise_builder = match keys {
+        Some(keys) => builder
+            .local_private_key(&keys.static_key)
+            .fixed_ephemeral_key_for_testing_only(&keys.ephemeral_key),
+        None => {
+            generated_key = builder.generate_keypair()?.private;
+            builder.local_private_key(&generated_key)
+        }
+    }
+    .psk(3, crate::HANDSHAKE_PSK);
+
+    if initiator {
+        Ok(noise_builder.build_initiator()?)
+    } else {
+        Ok(noise_builder.build_responder()?)
+    }
+}
+
+pub(crate) async fn responder_handshake<W: AsyncWrite + Unpin, R: AsyncRead + Unpin>(
+    remote_address: SocketAddr,
+    own_version: &Version,
+    keys: Option<&HandshakeKeys>,
+    writer: &mut W,
+    reader: &mut R,
+) -> Result<HandshakeData, NetworkError> {
+    let mut noise = handshake_state(keys, false)?;
     let mut buffer: Box<[u8]> = vec![0u8; crate::MAX_MESSAGE_SIZE + 4096].into();
     let mut noise_buffer: Box<[u8]> = vec![0u8; crate::NOISE_BUF_LEN].into();
     // <- e
@@ -84,37 +115,44 @@ async fn responder_handshake<W: AsyncWrite + Unpin, R: AsyncRead + Unpin>(
     let peer_version = Version::deserialize(&noise_buffer[..len])?;
     trace!("received s, se, psk (XX handshake part 3/3) from {}", remote_address);
 
-    if peer_version.node_id == own_version.node_id {
-        return Err(NetworkError::SelfConnectAttempt);
-    }
-    if peer_version.version != crate::PROTOCOL_VERSION {
-        return Err(NetworkError::InvalidHandshake);
-    }
+    check_peer_version(own_version, &peer_version)?;
 
     metrics::increment_counter!(SUCCESSES_RESP);
     Ok(HandshakeData {
         version: peer_version,
+        handshake_hash: noise.get_handshake_hash().to_vec(),
         noise: noise.into_transport_mode()?,
         buffer,
         noise_buffer,
     })
 }
 
-async fn initiator_handshake<W: AsyncWrite + Unpin, R: AsyncRead + Unpin>(
+/// Checks the version received from the other side of a handshake against the node's own one.
+fn check_peer_version(own_version: &Version, peer_version: &Version) -> Result<(), NetworkError> {
+    if peer_version.node_id == own_version.node_id {
+        return Err(NetworkError::SelfConnectAttempt);
+    }
+    if peer_version.version < crate::MIN_PROTOCOL_VERSION {
+        return Err(NetworkError::InvalidHandshake);
+    }
+    // Management clients aren't peers, so they can connect to a node regardless of its network.
+    let is_rpc_client =
+        own_version.features.contains(Features::RPC_CLIENT) || peer_version.features.contains(Features::RPC_CLIENT);
+    if !is_rpc_client && peer_version.network_id != own_version.network_id {
+        return Err(NetworkError::NetworkMismatch(peer_version.network_id));
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn initiator_handshake<W: AsyncWrite + Unpin, R: AsyncRead + Unpin>(
     remote_address: SocketAddr,
     own_version: &Version,
+    keys: Option<&HandshakeKeys>,
     writer: &mut W,
     reader: &mut R,
 ) -> Result<HandshakeData, NetworkError> {
-    let builder = snow::Builder::with_resolver(
-        crate::HANDSHAKE_PATTERN
-            .parse()
-            .expect("Invalid noise handshake pattern!"),
-        Box::new(snow::resolvers::SodiumResolver),
-    );
-    let static_key = builder.generate_keypair()?.private;
-    let noise_builder = builder.local_private_key(&static_key).psk(3, crate::HANDSHAKE_PSK);
-    let mut noise = noise_builder.build_initiator()?;
+    let mut noise = handshake_state(keys, true)?;
     let mut buffer: Box<[u8]> = vec![0u8; crate::MAX_MESSAGE_SIZE + 4096].into();
     let mut noise_buffer: Box<[u8]> = vec![0u8; crate::NOISE_BUF_LEN].into();
     // -> e
@@ -135,12 +173,7 @@ async fn initiator_handshake<W: AsyncWrite + Unpin, R: AsyncRead + Unpin>(
     let version = Version::deserialize(&buffer[..len])?;
     trace!("received e, ee, s, es (XX handshake part 2/3) from {}", remote_address);
 
-    if version.node_id == own_version.node_id {
-        return Err(NetworkError::SelfConnectAttempt);
-    }
-    if version.version != crate::PROTOCOL_VERSION {
-        return Err(NetworkError::InvalidHandshake);
-    }
+    check_peer_version(own_version, &version)?;
 
     // -> s, se, psk
     let own_version = Version::serialize(own_version)?;
@@ -153,13 +186,59 @@ async fn initiator_handshake<W: AsyncWrite + Unpin, R: AsyncRead + Unpin>(
     metrics::increment_counter!(SUCCESSES_INIT);
     Ok(HandshakeData {
         version,
+        handshake_hash: noise.get_handshake_hash().to_vec(),
         noise: noise.into_transport_mode()?,
         buffer,
         noise_buffer,
     })
 }
 
+/// Performs the handshake with a node on behalf of a client that isn't a peer, e.g. a management client using
+/// the RPC over the P2P port; returns the version of the node along with the established connection.
+pub(crate) async fn client_handshake(
+    address: SocketAddr,
+    stream: TcpStream,
+    own_version: &Version,
+) -> Result<(Version, PeerIOHandle), NetworkError> {
+    let (mut reader, mut writer) = stream.into_split();
+
+    let data = tokio::time::timeout(
+        Peer::peer_handshake_timeout(),
+        initiator_handshake(address, own_version, None, &mut writer, &mut reader),
+    )
+    .await
+    .map_err(|_| NetworkError::HandshakeTimeout)??;
+
+    let network = PeerIOHandle::new(
+        reader,
+        writer,
+        Cipher::new(data.noise, data.buffer, data.noise_buffer),
+        data.handshake_hash,
+    );
+
+    Ok((data.version, network))
+}
+
 impl Peer {
+    /// Applies the outcome of a successful handshake: the older of the two protocol versions is
+    /// used, along with the optional extensions supported by both sides.
+    fn negotiate(&mut self, own_version: &Version, peer_version: &Version) {
+        self.quality.earliest_block_height = peer_version.earliest_block_height;
+        self.quality.protocol_version = own_version.version.min(peer_version.version);
+        self.quality.features = own_version.features.intersection(peer_version.features);
+        self.quality.is_watcher = peer_version.features.contains(Features::HEADERS_ONLY);
+        self.quality.is_rpc_client = peer_version.features.contains(Features::RPC_CLIENT);
+        self.quality.node_id = peer_version.node_id;
+        self.quality.handshake_timestamp = peer_version.timestamp;
+
+        trace!(
+            "Negotiated protocol version {} and {:?} with {}",
+            self.quality.protocol_version,
+            self.quality.features,
+            self.address
+        );
+    }
+
     pub(super) async fn inner_handshake_initiator(
         &mut self,
         stream: TcpStream,
@@ -169,7 +248,7 @@ impl Peer {
 
         let result = tokio::time::timeout(
             self.handshake_timeout(),
-            initiator_handshake(self.address, &our_version, &mut writer, &mut reader),
+            initiator_handshake(self.address, &our_version, None, &mut writer, &mut reader),
         )
         .await;
 
@@ -185,16 +264,19 @@ impl Peer {
             }
         };
 
+        self.negotiate(&our_version, &data.version);
+
         match self.is_bootnode {
             true => info!("Connected to bootnode {}", self.address),
             false => info!("Connected to peer {}", self.address),
         };
 
-        Ok(PeerIOHandle {
-            reader: Some(reader),
+        Ok(PeerIOHandle::new(
+            reader,
             writer,
-            cipher: Cipher::new(data.noise, data.buffer, data.noise_buffer),
-        })
+            Cipher::new(data.noise, data.buffer, data.noise_buffer),
+            data.handshake_hash,
+        ))
     }
 
     pub(super) async fn inner_handshake_responder(
@@ -206,7 +288,7 @@ impl Peer {
 
         let result = tokio::time::timeout(
             Peer::peer_handshake_timeout(),
-            responder_handshake(address, &our_version, &mut writer, &mut reader),
+            responder_handshake(address, &our_version, None, &mut writer, &mut reader),
         )
         .await;
 
@@ -223,16 +305,27 @@ impl Peer {
         };
 
         let mut peer_address = address;
-        peer_address.set_port(data.version.listening_port);
-        let peer = Peer::new(peer_address, false);
+        match data.version.external_address {
+            // A peer behind a NAT is reachable at its mapped port rather than its listening port.
+            Some(external_address) if external_address.ip() == address.ip() => {
+                peer_address.set_port(external_address.port())
+            }
+            // A peer that doesn't listen is only known by the address of its connection.
+            _ if data.version.listening_port == 0 => {}
+            _ => peer_address.set_port(data.version.listening_port),
+        }
+        let mut peer = Peer::new(peer_address, false);
+        peer.negotiate(&our_version, &data.version);
+        peer.quality.no_listen = data.version.listening_port == 0 && data.version.external_address.is_none();
 
         info!("Connected to peer {}", peer_address);
 
-        let network = PeerIOHandle {
-            reader: Some(reader),
+        let network = PeerIOHandle::new(
+            reader,
             writer,
-            cipher: Cipher::new(data.noise, data.buffer, data.noise_buffer),
-        };
+            Cipher::new(data.noise, data.buffer, data.noise_buffer),
+            data.handshake_hash,
+        );
         Ok((peer, network))
     }
 }
@@ -242,6 +335,7 @@ mod tests {
     use rand::Rng;
 
     use super::*;
+    use crate::{Features, NetworkId};
 
     #[tokio::test(flavor = "multi_thread")]
     async fn test_handshake() {
@@ -254,7 +348,10 @@ mod tests {
             let (mut read, mut write) = tokio::io::split(responder);
             let data = responder_handshake(
                 "127.0.0.1:1010".parse().unwrap(),
-                &Version::new(crate::PROTOCOL_VERSION, 0, 0),
+                &Version::new(crate::PROTOCOL_VERSION, 0, 0)
+                    .with_features(Features::from_bits(0b011))
+                    .with_timestamp(1_600_000_000),
+                None,
                 &mut write,
                 &mut read,
             )
@@ -272,12 +369,15 @@ mod tests {
         let (mut read, mut write) = tokio::io::split(initiator);
         let data = initiator_handshake(
             "127.0.0.1:1020".parse().unwrap(),
-            &Version::new(crate::PROTOCOL_VERSION, 0, 1),
+            &Version::new(crate::PROTOCOL_VERSION, 0, 1).with_features(Features::from_bits(0b110)),
+            None,
             &mut write,
             &mut read,
         )
         .await
         .unwrap();
+        assert_eq!(data.version.features, Features::from_bits(0b011));
+        assert_eq!(data.version.timestamp, 1_600_000_000);
         let mut cipher = Cipher::new(data.noise, data.buffer, data.noise_buffer);
         cipher
             .write_packet(&mut write, "test packet out".as_bytes())
@@ -286,4 +386,24 @@ mod tests {
         let bytes = cipher.read_packet_stream(&mut read).await.unwrap();
         assert_eq!(String::from_utf8_lossy(bytes).as_ref(), "test packet in");
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_handshake_network_mismatch() {
+        let (responder, initiator) = tokio::io::duplex(8192);
+
+        let responder = tokio::spawn(async move {
+            let (mut read, mut write) = tokio::io::split(responder);
+            let version = Version::new(crate::PROTOCOL_VERSION, 0, 0).with_network_id(NetworkId::Devnet);
+            responder_handshake("127.0.0.1:1010".parse().unwrap(), &version, None, &mut write, &mut read).await
+        });
+
+        let (mut read, mut write) = tokio::io::split(initiator);
+        let version = Version::new(crate::PROTOCOL_VERSION, 0, 1).with_network_id(NetworkId::Mainnet);
+        let result =
+            initiator_handshake("127.0.0.1:1020".parse().unwrap(), &version, None, &mut write, &mut read).await;
+        assert!(matches!(result, Err(NetworkError::NetworkMismatch(NetworkId::Devnet))));
+        // The initiator drops the connection without completing the handshake.
+        drop((read, write));
+        assert!(responder.await.unwrap().is_err());
+    }
 }
@@ -14,14 +14,23 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
+use futures::{
+    future::{self, BoxFuture},
+    FutureExt,
+};
 use snarkvm_dpc::Storage;
+use std::io;
+use tokio::task;
 
-use snarkos_metrics::{self as metrics, inbound::*};
+use snarkos_metrics::{self as metrics, inbound::*, latency::PEER_RTT};
 
-use crate::{Direction, Message, NetworkError, Node, Payload, Peer};
+use crate::{Direction, Message, Misbehavior, NetworkError, Node, Payload, Peer, PeerAlias, Transition};
 
 use super::network::PeerIOHandle;
 
+/// A payload that is being decoded, either in place or on the blocking thread pool.
+pub(super) type PendingDecode = BoxFuture<'static, Result<Payload, NetworkError>>;
+
 impl Peer {
     pub(super) async fn inner_dispatch_payload<S: Storage + Sync + Send + 'static>(
         &mut self,
@@ -29,9 +38,17 @@ impl Peer {
         network: &mut PeerIOHandle,
         payload: Result<Payload, NetworkError>,
     ) -> Result<(), NetworkError> {
-        let payload = payload?;
+        // The failure itself is counted once the connection is dropped.
+        let payload = payload.map_err(|e| {
+            if !e.is_trivial() {
+                self.quality
+                    .record_misbehavior(Misbehavior::MalformedPayload, e.to_string());
+            }
+            e
+        })?;
         self.quality.see();
         self.quality.num_messages_received += 1;
+        self.quality.connection_messages_received += 1;
 
         // If message is a `SyncBlock` message, log it as a trace.
         match payload {
@@ -39,31 +56,123 @@ impl Peer {
             _ => debug!("Received a '{}' message from {}", payload, self.address),
         }
 
+        // Only process the payloads that are valid in the current state of the protocol.
+        match self.quality.protocol_state.received(&payload, self.quality.features) {
+            Transition::Valid => (),
+            Transition::Stale => {
+                trace!("Ignoring a stale '{}' message from {}", payload, self.address);
+                return Ok(());
+            }
+            Transition::Unexpected => {
+                self.misbehaved(
+                    Misbehavior::UnsolicitedMessage,
+                    format!("an unexpected '{}' message", payload),
+                );
+                return Ok(());
+            }
+            Transition::Forbidden => {
+                return Err(NetworkError::ProtocolViolation(format!(
+                    "'{}' messages weren't negotiated",
+                    payload
+                )));
+            }
+        }
+
+        let correlation = self
+            .quality
+            .correlations
+            .received(self.address, &payload)
+            .map(|(id, span)| {
+                span.in_scope(|| trace!("Received the '{}' message {} from {}", payload, id, self.address));
+                id
+            });
+
+        // Only the responses to the node's own requests list the peer's connections; the unsolicited `Peers`
+        // messages announce single addresses.
+        if let (Payload::Peers(peers), Some(_)) = (&payload, correlation) {
+            self.quality.reported_degree = Some(peers.len());
+        }
+
         match payload {
             Payload::Pong => {
-                if self.quality.expecting_pong {
-                    let rtt = self
-                        .quality
-                        .last_ping_sent
-                        .map(|x| x.elapsed().as_millis() as u64)
-                        .unwrap_or(u64::MAX);
+                if let Some(ping_sent) = self.quality.last_ping_sent {
+                    let rtt = ping_sent.elapsed().as_millis() as u64;
                     trace!("RTT for {} is {}ms", self.address, rtt);
-                    self.quality.expecting_pong = false;
-                    self.quality.rtt_ms = rtt;
+                    self.quality.record_rtt(rtt);
+                    // The RTTs of all the peers share a single series, as a label per peer address would be unbounded.
+                    metrics::histogram!(PEER_RTT, rtt as f64);
                 } else {
-                    self.fail();
+                    self.quality.rtt_ms = u64::MAX;
                 }
                 metrics::increment_counter!(PONGS);
             }
-            Payload::Ping(block_height) => {
+            Payload::Ping(block_height, timestamp) => {
                 network.write_payload(&Payload::Pong).await?;
+                self.quality.correlations.sent(self.address, &Payload::Pong);
                 self.quality.block_height = block_height;
+                if self.quality.is_outbound {
+                    node.record_peer_time(self.address, timestamp);
+                }
                 metrics::increment_counter!(PINGS);
             }
+            Payload::NodeAlias(node_alias) => {
+                // The protocol state only lets through a single alias per connection.
+                if !node_alias.verify(&network.handshake_hash) {
+                    self.misbehaved(Misbehavior::InvalidSignature, "an invalid alias".into());
+                } else {
+                    debug!("Peer {} calls itself '{}' (unverified)", self.address, node_alias.alias);
+                    let alias = PeerAlias::from(node_alias);
+
+                    #[cfg(feature = "crawler")]
+                    if let Some(known_network) = node.known_network() {
+                        known_network.set_alias(self.address, alias.clone());
+                    }
+
+                    self.quality.alias = Some(alias);
+                }
+            }
+            Payload::AddressChange(change) => {
+                // The protocol state only lets through a single address change per connection; it must be signed
+                // with the same key as the peer's alias, if it sent one.
+                let public_key = hex::encode(&change.public_key);
+                let same_key = self
+                    .quality
+                    .alias
+                    .as_ref()
+                    .map(|alias| alias.public_key.eq_ignore_ascii_case(&public_key))
+                    .unwrap_or(true);
+
+                if !same_key || !change.verify(&network.handshake_hash) {
+                    self.misbehaved(Misbehavior::InvalidSignature, "an invalid address change".into());
+                } else {
+                    debug!(
+                        "Peer {} announced that it moved from {} to {}",
+                        self.address, change.previous, change.address
+                    );
+                    // The peer book may need to reach this peer's task, so the migration is done apart from it.
+                    let node = node.clone();
+                    let source = self.address;
+                    task::spawn(async move {
+                        node.migrate_peer_address(source, change.previous, change.address, public_key)
+                            .await;
+                    });
+                }
+            }
+            Payload::Rekey => {
+                // The incoming key was already replaced when the payload was decrypted.
+                trace!("Peer {} replaced its transport key", self.address);
+            }
+            Payload::Transaction(_) if !self.has_relay_privileges() => {
+                debug!(
+                    "Ignoring a transaction from {}; too many of its transactions were rejected",
+                    self.address
+                );
+            }
             payload => {
                 node.route(Message {
                     direction: Direction::Inbound(self.address),
                     payload,
+                    correlation,
                 });
             }
         }
@@ -91,9 +200,46 @@ impl Peer {
         Ok(())
     }
 
-    pub(super) fn deserialize_payload(&self, payload: Result<&[u8], NetworkError>) -> Result<Payload, NetworkError> {
+    pub(super) fn deserialize_payload(payload: Result<&[u8], NetworkError>) -> Result<Payload, NetworkError> {
         let payload = payload?;
         let payload = Payload::deserialize(payload)?;
         Ok(payload)
     }
+
+    /// Decodes the given payload; large ones are decoded on the blocking thread pool, so
+    /// that they don't stall the reads from the peer.
+    pub(super) fn decode_payload(payload: Result<&[u8], NetworkError>) -> PendingDecode {
+        match payload {
+            Ok(payload) if payload.len() >= crate::BLOCKING_DECODE_THRESHOLD => {
+                let payload = payload.to_vec();
+
+                async move {
+                    let payload = task::spawn_blocking(move || Payload::deserialize(&payload))
+                        .await
+                        .map_err(io::Error::from)??;
+                    Ok(payload)
+                }
+                .boxed()
+            }
+            payload => future::ready(Self::deserialize_payload(payload)).boxed(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn small_and_large_payloads_are_decoded() {
+        let small_blob = (0u8..255).collect::<Vec<_>>();
+        let large_blob = (0..2 * crate::BLOCKING_DECODE_THRESHOLD)
+            .map(|i| (i % 255) as u8 + 1)
+            .collect::<Vec<_>>();
+
+        for payload in &[Payload::Block(small_blob), Payload::SyncBlock(large_blob)] {
+            let serialized = Payload::serialize(payload).unwrap();
+            assert_eq!(Peer::decode_payload(Ok(&serialized[..])).await.unwrap(), *payload);
+        }
+    }
 }
@@ -0,0 +1,70 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A kind of misbehavior a peer is penalized for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Misbehavior {
+    /// The peer sent a block that failed validation.
+    InvalidBlock,
+    /// The peer sent a payload that couldn't be decrypted or decoded.
+    MalformedPayload,
+    /// The peer sent a response that wasn't requested, e.g. a `Pong` without a preceding `Ping`.
+    UnsolicitedMessage,
+    /// The peer didn't deliver most of the sync blocks requested from it in time.
+    SyncTimeout,
+    /// The peer sent more sync block hashes than allowed.
+    OversizedSync,
+    /// The peer sent an alias or an address change with an invalid signature.
+    InvalidSignature,
+    /// The peer keeps sharing addresses disallowed by the gossip port policy.
+    DisallowedGossip,
+    /// The peer keeps requesting more blocks or block hashes than its serving quota allows.
+    ExcessiveSyncRequests,
+    /// The peer keeps requesting more than the bandwidth caps allow sending to it.
+    ExcessiveRequests,
+}
+
+impl fmt::Display for Misbehavior {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidBlock => write!(f, "invalid block"),
+            Self::MalformedPayload => write!(f, "malformed payload"),
+            Self::UnsolicitedMessage => write!(f, "unsolicited message"),
+            Self::SyncTimeout => write!(f, "sync timeout"),
+            Self::OversizedSync => write!(f, "oversized sync"),
+            Self::InvalidSignature => write!(f, "invalid signature"),
+            Self::DisallowedGossip => write!(f, "disallowed gossip"),
+            Self::ExcessiveSyncRequests => write!(f, "excessive sync requests"),
+            Self::ExcessiveRequests => write!(f, "excessive requests"),
+        }
+    }
+}
+
+/// A recorded instance of a peer's misbehavior.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MisbehaviorEvent {
+    /// The time at which the misbehavior was noticed.
+    pub time: DateTime<Utc>,
+    /// The kind of the misbehavior.
+    pub kind: Misbehavior,
+    /// A description of the specific instance, e.g. the reason a block was invalid.
+    pub details: String,
+}
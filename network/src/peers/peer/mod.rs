@@ -23,13 +23,25 @@ mod outbound_handler;
 mod peer_events;
 mod receiver;
 
+pub mod correlation;
+pub mod misbehavior;
 pub mod peer;
 pub mod peer_quality;
+pub mod peer_score;
+pub mod protocol_state;
 
+pub use correlation::*;
+pub use misbehavior::*;
 pub use outbound_handler::*;
 pub use peer::*;
 pub use peer_events::*;
 pub use peer_quality::*;
+pub use peer_score::*;
+pub use protocol_state::*;
+
+pub(crate) use handshake::client_handshake;
+#[cfg(feature = "test-vectors")]
+pub(crate) use handshake::{initiator_handshake, responder_handshake, HandshakeKeys};
 
 // used in integration tests
 #[doc(hidden)]
@@ -14,12 +14,18 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
+use std::{
+    collections::VecDeque,
+    io::{self, IoSlice},
+    time::{Duration, Instant},
+};
+
 use tokio::{
-    io::{AsyncRead, AsyncReadExt},
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
     net::tcp::{OwnedReadHalf, OwnedWriteHalf},
 };
 
-use crate::{NetworkError, Payload};
+use crate::{NetworkError, OutboundThrottle, Payload};
 
 use super::cipher::Cipher;
 
@@ -29,19 +35,243 @@ pub struct PeerIOHandle {
     pub reader: Option<OwnedReadHalf>,
     pub writer: OwnedWriteHalf,
     pub cipher: Cipher,
+    /// The hash of the handshake, unique to the connection.
+    pub handshake_hash: Vec<u8>,
+    /// The encrypted frames of small payloads waiting to be written in a single batch.
+    write_queue: Vec<u8>,
+    /// The time at which the oldest frame in the write queue was queued.
+    queued_since: Option<Instant>,
+    /// Indicates whether the peer supports replacing the transport keys.
+    rekeying: bool,
+    /// The time at which the key the payloads are encrypted with was last replaced.
+    last_rekey: Instant,
+    /// The number of bytes encrypted with the current key.
+    bytes_since_rekey: u64,
+    /// The bandwidth caps the written payloads are subject to.
+    throttle: OutboundThrottle,
+    /// The serialized payloads deferred in order to stay within the bandwidth caps, in the order they were queued in;
+    /// the first one is already accounted for.
+    deferred: VecDeque<Vec<u8>>,
+    /// The total size of the deferred payloads; it's capped at `MAX_DEFERRED_SIZE`.
+    deferred_size: usize,
+    /// The time at which the first deferred payload can be written.
+    deferred_until: Option<Instant>,
+    /// Indicates whether the first deferred payload waits for the cap of the connection with the peer, as opposed to
+    /// the node-wide one.
+    deferred_by_peer: bool,
 }
 
 impl PeerIOHandle {
+    pub fn new(reader: OwnedReadHalf, writer: OwnedWriteHalf, cipher: Cipher, handshake_hash: Vec<u8>) -> Self {
+        Self {
+            reader: Some(reader),
+            writer,
+            cipher,
+            handshake_hash,
+            write_queue: Vec::new(),
+            queued_since: None,
+            rekeying: false,
+            last_rekey: Instant::now(),
+            bytes_since_rekey: 0,
+            throttle: Default::default(),
+            deferred: Default::default(),
+            deferred_size: 0,
+            deferred_until: None,
+            deferred_by_peer: false,
+        }
+    }
+
+    /// Subjects the written payloads to the given bandwidth caps.
+    pub fn set_throttle(&mut self, throttle: OutboundThrottle) {
+        self.throttle = throttle;
+    }
+
+    /// Makes the key the payloads are encrypted with be replaced periodically; the peer needs to support it.
+    pub fn enable_rekeying(&mut self) {
+        self.rekeying = true;
+    }
+
+    /// Writes the given payload right away, along with any queued ones; it counts towards the bandwidth caps, but
+    /// isn't deferred.
     pub async fn write_payload(&mut self, payload: &Payload) -> Result<(), NetworkError> {
         let serialized_payload = Payload::serialize(payload)?;
-        self.cipher
-            .write_packet(&mut self.writer, &serialized_payload[..])
-            .await?;
+        self.throttle.consume(serialized_payload.len());
+
+        self.write_serialized(&serialized_payload).await
+    }
+
+    async fn write_serialized(&mut self, serialized_payload: &[u8]) -> Result<(), NetworkError> {
+        let frame = self.cipher.encrypt_packet(serialized_payload)?;
+        self.bytes_since_rekey += frame.len() as u64;
+
+        if self.write_queue.is_empty() {
+            self.writer.write_all(frame).await?;
+        } else {
+            // Keep the order of the payloads by writing the queued ones first, in the same syscall if possible.
+            write_all_vectored(&mut self.writer, &self.write_queue, frame).await?;
+            self.write_queue.clear();
+            self.queued_since = None;
+        }
+        self.writer.flush().await?;
+
+        self.rekey_if_due()
+    }
+
+    /// Queues the given payload, so that it's written in a single batch with other small payloads;
+    /// large payloads are written right away. Bulk payloads exceeding the bandwidth caps are deferred
+    /// until they're within them, while the other ones are never delayed. Once too many payloads are
+    /// deferred, queueing another one waits for them to make room for it. Returns `true` if it had to
+    /// wait because of the cap of the connection with the peer, as opposed to the node-wide one.
+    pub async fn queue_payload(&mut self, payload: &Payload) -> Result<bool, NetworkError> {
+        let serialized_payload = Payload::serialize(payload)?;
+
+        if OutboundThrottle::is_deferrable(payload) && !self.throttle.is_unlimited() {
+            // Waiting, instead of dropping the payload, also holds back the processing of the peer's further requests.
+            let mut waited_for_peer_cap = false;
+            while !self.deferred.is_empty() && self.deferred_size + serialized_payload.len() > crate::MAX_DEFERRED_SIZE
+            {
+                waited_for_peer_cap |= self.deferred_by_peer;
+                if let Some(deferred_until) = self.deferred_until {
+                    tokio::time::sleep_until(deferred_until.into()).await;
+                }
+                self.release_deferred().await?;
+            }
+
+            // Keep the order of the deferred payloads.
+            if self.deferred.is_empty() {
+                let delay = self.throttle.consume(serialized_payload.len());
+                if delay.total() == Duration::from_secs(0) {
+                    self.queue_serialized(serialized_payload).await?;
+                    return Ok(waited_for_peer_cap);
+                }
+                self.deferred_until = Some(Instant::now() + delay.total());
+                self.deferred_by_peer = delay.is_due_to_peer();
+            }
+            self.deferred_size += serialized_payload.len();
+            self.deferred.push_back(serialized_payload);
+            return Ok(waited_for_peer_cap);
+        }
+
+        self.throttle.consume(serialized_payload.len());
+        self.queue_serialized(serialized_payload).await?;
+        Ok(false)
+    }
+
+    async fn queue_serialized(&mut self, serialized_payload: Vec<u8>) -> Result<(), NetworkError> {
+        if serialized_payload.len() > crate::MAX_COALESCED_PAYLOAD_SIZE {
+            return self.write_serialized(&serialized_payload).await;
+        }
+
+        let frame = self.cipher.encrypt_packet(&serialized_payload[..])?;
+        self.bytes_since_rekey += frame.len() as u64;
+        self.write_queue.extend_from_slice(frame);
+        self.queued_since.get_or_insert_with(Instant::now);
+        self.rekey_if_due()?;
+
+        if self.write_queue.len() >= crate::MAX_WRITE_QUEUE_SIZE {
+            self.flush_queue().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the key the payloads are encrypted with if it was used for too long or for too much data; the
+    /// peer is notified with a `Rekey` encrypted with the old key, queued after all the preceding payloads.
+    fn rekey_if_due(&mut self) -> Result<(), NetworkError> {
+        let interval = Duration::from_secs(crate::REKEY_INTERVAL_SECS as u64);
+        let is_due = self.bytes_since_rekey >= crate::REKEY_BYTES_THRESHOLD || self.last_rekey.elapsed() >= interval;
+        if !self.rekeying || !is_due {
+            return Ok(());
+        }
+
+        let serialized_payload = Payload::serialize(&Payload::Rekey)?;
+        let frame = self.cipher.encrypt_packet(&serialized_payload[..])?;
+        self.write_queue.extend_from_slice(frame);
+        self.queued_since.get_or_insert_with(Instant::now);
+
+        self.cipher.rekey_outgoing();
+        self.last_rekey = Instant::now();
+        self.bytes_since_rekey = 0;
+
+        Ok(())
+    }
+
+    /// Returns the time by which the queued payloads need to be written, if there are any.
+    pub fn flush_deadline(&self) -> Option<Instant> {
+        self.queued_since
+            .map(|queued_since| queued_since + Duration::from_millis(crate::WRITE_COALESCING_DELAY_MS as u64))
+    }
+
+    /// Returns the time at which the first of the payloads deferred to stay within the bandwidth caps can be
+    /// written, if there are any.
+    pub fn deferred_deadline(&self) -> Option<Instant> {
+        if self.deferred.is_empty() {
+            None
+        } else {
+            self.deferred_until
+        }
+    }
+
+    /// Queues the deferred payloads that are within the bandwidth caps by now.
+    pub async fn release_deferred(&mut self) -> Result<(), NetworkError> {
+        while let Some(serialized_payload) = self.deferred.pop_front() {
+            self.deferred_size -= serialized_payload.len();
+            self.queue_serialized(serialized_payload).await?;
+
+            if let Some(next_payload) = self.deferred.front() {
+                let delay = self.throttle.consume(next_payload.len());
+                if delay.total() > Duration::from_secs(0) {
+                    self.deferred_until = Some(Instant::now() + delay.total());
+                    self.deferred_by_peer = delay.is_due_to_peer();
+                    return Ok(());
+                }
+            }
+        }
+        self.deferred_until = None;
+
+        Ok(())
+    }
+
+    /// Writes all the queued payloads.
+    pub async fn flush_queue(&mut self) -> Result<(), NetworkError> {
+        if self.write_queue.is_empty() {
+            return Ok(());
+        }
+
+        self.writer.write_all(&self.write_queue).await?;
+        self.writer.flush().await?;
+        self.write_queue.clear();
+        self.queued_since = None;
+
         Ok(())
     }
 
+    /// Decrypts the given payload, rejecting it as soon as it's found to exceed the size limit of its type.
     pub fn read_payload(&mut self, payload: &[u8]) -> Result<&[u8], NetworkError> {
-        self.cipher.read_packet(payload)
+        // Every encrypted chunk carries a tag, which isn't a part of the payload.
+        let chunk_count = (payload.len() + crate::NOISE_BUF_LEN - 1) / crate::NOISE_BUF_LEN;
+        let payload_len = payload.len().saturating_sub(chunk_count * crate::NOISE_TAG_LEN);
+
+        let mut is_rekey = false;
+        let decrypted_len = self
+            .cipher
+            .read_packet_with(payload, |head| {
+                is_rekey = Payload::is_rekey(head);
+                match Payload::size_limit(head) {
+                    Some((payload_type, max_size)) if payload_len > max_size => {
+                        Err(NetworkError::PayloadTooBig(payload_type, payload_len))
+                    }
+                    _ => Ok(()),
+                }
+            })?
+            .len();
+
+        // The peer encrypts the payloads following a `Rekey` with its next key.
+        if is_rekey {
+            self.cipher.rekey_incoming();
+        }
+
+        Ok(self.cipher.decrypted(decrypted_len))
     }
 
     pub fn take_reader(&mut self) -> PeerReader<OwnedReadHalf> {
@@ -70,3 +300,212 @@ impl<R: AsyncRead + Unpin + 'static> PeerReader<R> {
         Ok(&self.buffer[..length])
     }
 }
+
+/// Writes both buffers in full, using vectored writes.
+async fn write_all_vectored<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    mut first: &[u8],
+    mut second: &[u8],
+) -> io::Result<()> {
+    while !first.is_empty() {
+        let written = writer
+            .write_vectored(&[IoSlice::new(first), IoSlice::new(second)])
+            .await?;
+        if written == 0 {
+            return Err(io::ErrorKind::WriteZero.into());
+        }
+
+        if written < first.len() {
+            first = &first[written..];
+        } else {
+            second = &second[written - first.len()..];
+            first = &[];
+        }
+    }
+
+    writer.write_all(second).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snow::TransportState;
+    use std::{net::SocketAddr, sync::Arc};
+    use tokio::net::{TcpListener, TcpStream};
+
+    fn transport_states() -> (TransportState, TransportState) {
+        let builder = || {
+            snow::Builder::with_resolver(
+                "Noise_NN_25519_ChaChaPoly_SHA256".parse().unwrap(),
+                Box::new(snow::resolvers::SodiumResolver),
+            )
+        };
+        let mut initiator = builder().build_initiator().unwrap();
+        let mut responder = builder().build_responder().unwrap();
+        let (mut buffer, mut payload) = ([0u8; 1024], [0u8; 1024]);
+
+        let len = initiator.write_message(&[], &mut buffer).unwrap();
+        responder.read_message(&buffer[..len], &mut payload).unwrap();
+        let len = responder.write_message(&[], &mut buffer).unwrap();
+        initiator.read_message(&buffer[..len], &mut payload).unwrap();
+
+        (
+            initiator.into_transport_mode().unwrap(),
+            responder.into_transport_mode().unwrap(),
+        )
+    }
+
+    fn io_handle(stream: TcpStream, noise: TransportState) -> PeerIOHandle {
+        let (reader, writer) = stream.into_split();
+        let cipher = Cipher::new(
+            noise,
+            vec![0u8; crate::MAX_MESSAGE_SIZE + 4096].into(),
+            vec![0u8; crate::NOISE_BUF_LEN].into(),
+        );
+
+        PeerIOHandle::new(reader, writer, cipher, vec![])
+    }
+
+    #[tokio::test]
+    async fn queued_payloads_are_written_in_order() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let initiator_stream = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (responder_stream, _) = listener.accept().await.unwrap();
+
+        let (initiator_noise, responder_noise) = transport_states();
+        let mut writer = io_handle(initiator_stream, initiator_noise);
+        let mut reader = io_handle(responder_stream, responder_noise);
+        let mut peer_reader = reader.take_reader();
+
+        let payloads = vec![
+            Payload::Ping(1, 0),
+            Payload::Pong,
+            Payload::Peers(vec!["127.0.0.1:4131".parse().unwrap()]),
+            Payload::Block(vec![1u8; 2 * crate::MAX_COALESCED_PAYLOAD_SIZE]),
+            Payload::GetPeers,
+        ];
+
+        // The small payloads are queued until the large one or an explicit flush.
+        for payload in &payloads[..3] {
+            writer.queue_payload(payload).await.unwrap();
+        }
+        assert!(writer.flush_deadline().is_some());
+        writer.queue_payload(&payloads[3]).await.unwrap();
+        assert!(writer.flush_deadline().is_none());
+        writer.queue_payload(&payloads[4]).await.unwrap();
+        writer.flush_queue().await.unwrap();
+        assert!(writer.flush_deadline().is_none());
+
+        for payload in &payloads {
+            let raw = peer_reader.read_raw_payload().await.unwrap();
+            let decrypted = reader.read_payload(raw).unwrap();
+            assert_eq!(Payload::deserialize(decrypted).unwrap(), *payload);
+        }
+    }
+
+    #[tokio::test]
+    async fn payloads_are_readable_across_rekeys() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let initiator_stream = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (responder_stream, _) = listener.accept().await.unwrap();
+
+        let (initiator_noise, responder_noise) = transport_states();
+        let mut writer = io_handle(initiator_stream, initiator_noise);
+        let mut reader = io_handle(responder_stream, responder_noise);
+        let mut peer_reader = reader.take_reader();
+        writer.enable_rekeying();
+
+        writer.write_payload(&Payload::Ping(1, 0)).await.unwrap();
+        // Pretend that the current key was used for too much data; the next payload triggers a rekey.
+        writer.bytes_since_rekey = crate::REKEY_BYTES_THRESHOLD;
+        writer.write_payload(&Payload::Ping(2, 0)).await.unwrap();
+        assert_eq!(writer.bytes_since_rekey, 0);
+        writer.queue_payload(&Payload::Ping(3, 0)).await.unwrap();
+        writer.flush_queue().await.unwrap();
+
+        let expected = [
+            Payload::Ping(1, 0),
+            Payload::Ping(2, 0),
+            Payload::Rekey,
+            Payload::Ping(3, 0),
+        ];
+        for payload in &expected {
+            let raw = peer_reader.read_raw_payload().await.unwrap().to_vec();
+            let decrypted = reader.read_payload(&raw).unwrap();
+            assert_eq!(Payload::deserialize(decrypted).unwrap(), *payload);
+        }
+    }
+
+    #[tokio::test]
+    async fn oversized_payloads_are_rejected() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let initiator_stream = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (responder_stream, _) = listener.accept().await.unwrap();
+
+        let (initiator_noise, responder_noise) = transport_states();
+        let mut writer = io_handle(initiator_stream, initiator_noise);
+        let mut reader = io_handle(responder_stream, responder_noise);
+        let mut peer_reader = reader.take_reader();
+
+        let addresses = (0..crate::MAX_PEERS_PAYLOAD_SIZE as u32)
+            .map(|i| SocketAddr::from((i.to_be_bytes(), 4131)))
+            .collect::<Vec<_>>();
+        writer.write_payload(&Payload::Peers(addresses)).await.unwrap();
+        // Larger payloads of other types are still accepted.
+        let block = Payload::Block(vec![1u8; 2 * crate::MAX_PEERS_PAYLOAD_SIZE]);
+        writer.write_payload(&block).await.unwrap();
+
+        let raw = peer_reader.read_raw_payload().await.unwrap().to_vec();
+        assert!(matches!(
+            reader.read_payload(&raw),
+            Err(NetworkError::PayloadTooBig("peers", _))
+        ));
+        let raw = peer_reader.read_raw_payload().await.unwrap().to_vec();
+        assert_eq!(Payload::deserialize(reader.read_payload(&raw).unwrap()).unwrap(), block);
+    }
+
+    /// Queues payloads exceeding the given caps; returns whether the last one waited because of the peer's cap.
+    async fn exceed_deferred_payloads_cap(throttle: OutboundThrottle) -> bool {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let initiator_stream = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (_responder_stream, _) = listener.accept().await.unwrap();
+
+        let (initiator_noise, _) = transport_states();
+        let mut writer = io_handle(initiator_stream, initiator_noise);
+        writer.set_throttle(throttle);
+
+        // The second payload exceeds the cap, so it and the ones after it are deferred.
+        let small = Payload::SyncBlock(vec![1u8; 600]);
+        assert!(!writer.queue_payload(&small).await.unwrap());
+        assert!(writer.deferred_deadline().is_none());
+        assert!(!writer.queue_payload(&small).await.unwrap());
+        assert!(writer.deferred_deadline().is_some());
+
+        // The payloads exceeding the cap on the size of the deferred ones wait for room instead of being dropped.
+        let large = Payload::SyncBlock(vec![1u8; crate::MAX_DEFERRED_SIZE]);
+        let waited_for_peer_cap = writer.queue_payload(&large).await.unwrap();
+        assert_eq!(writer.deferred.len(), 1);
+
+        // The payloads that aren't deferrable are never delayed.
+        assert!(!writer.queue_payload(&Payload::Block(vec![1u8; 1024])).await.unwrap());
+        assert_eq!(writer.deferred.len(), 1);
+
+        waited_for_peer_cap
+    }
+
+    #[tokio::test]
+    async fn deferred_payloads_are_capped() {
+        let peer_cap = OutboundThrottle {
+            peer: Some(crate::BandwidthLimit::new(1000)),
+            node: None,
+        };
+        assert!(exceed_deferred_payloads_cap(peer_cap).await);
+
+        // The waits caused by the node-wide cap aren't attributed to the peer.
+        let node_cap = OutboundThrottle {
+            peer: None,
+            node: Some(Arc::new(crate::BandwidthLimit::new(1000))),
+        };
+        assert!(!exceed_deferred_payloads_cap(node_cap).await);
+    }
+}
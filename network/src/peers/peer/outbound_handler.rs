@@ -14,13 +14,25 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use std::time::Instant;
+use std::{sync::Arc, time::Instant};
 
 use tokio::sync::{mpsc, oneshot};
+use tracing::Span;
 
-use snarkos_metrics::{self as metrics, queues::*};
+use snarkos_metrics::{self as metrics, inbound, queues::*};
+use snarkvm_dpc::BlockHeaderHash;
 
-use crate::{NetworkError, Payload, Peer};
+use crate::{
+    BlockHeight,
+    Features,
+    Misbehavior,
+    NetworkError,
+    Payload,
+    Peer,
+    PeerScoreWeights,
+    ServedItems,
+    SyncServingQuota,
+};
 
 use super::network::PeerIOHandle;
 
@@ -28,11 +40,19 @@ pub(super) enum PeerAction {
     Disconnect,
     Send(Payload),
     Get(oneshot::Sender<Peer>),
-    QualityJudgement,
+    QualityJudgement(PeerScoreWeights),
     CancelSync,
-    GotSyncBlock,
-    ExpectingSyncBlocks(u32),
+    GotSyncBlock(Option<BlockHeaderHash>),
+    DeclinedSyncBlock,
+    ExpectingSyncBlocks(Vec<BlockHeaderHash>),
+    SyncedWithUs(BlockHeight),
     SoftFail,
+    Misbehaved(Misbehavior, String),
+    GossipedOutOfPolicy,
+    RelayedTransactionSettled(bool),
+    SyncQuery(BlockHeight, u32),
+    RequestServing(ServedItems, u32, SyncServingQuota, oneshot::Sender<u32>),
+    AnnounceBlock(BlockHeaderHash, Arc<Vec<u8>>),
 }
 
 #[derive(Clone, Debug)]
@@ -48,9 +68,14 @@ impl PeerHandle {
         receiver.await.ok()
     }
 
-    pub async fn judge_bad(&self) {
+    pub async fn judge_bad(&self, weights: PeerScoreWeights) {
         metrics::increment_gauge!(OUTBOUND, 1.0);
-        self.sender.send(PeerAction::QualityJudgement).await.ok();
+        self.sender.send(PeerAction::QualityJudgement(weights)).await.ok();
+    }
+
+    /// Returns `true` if both handles refer to the same connection.
+    pub fn is_same_connection(&self, other: &PeerHandle) -> bool {
+        self.sender.same_channel(&other.sender)
     }
 
     /// returns true if disconnected, false if not connected anymore
@@ -69,20 +94,86 @@ impl PeerHandle {
         self.sender.send(PeerAction::CancelSync).await.ok();
     }
 
-    pub async fn got_sync_block(&self) {
+    /// Registers a sync block delivered by the peer; its hash is unknown if the block is malformed.
+    pub async fn got_sync_block(&self, hash: Option<BlockHeaderHash>) {
+        metrics::increment_gauge!(OUTBOUND, 1.0);
+        self.sender.send(PeerAction::GotSyncBlock(hash)).await.ok();
+    }
+
+    /// Registers that the peer declined to serve one of the sync blocks requested from it.
+    pub async fn declined_sync_block(&self) {
+        metrics::increment_gauge!(OUTBOUND, 1.0);
+        self.sender.send(PeerAction::DeclinedSyncBlock).await.ok();
+    }
+
+    /// Registers the sync blocks requested from the peer, in addition to any it hasn't delivered yet.
+    pub async fn expecting_sync_blocks(&self, hashes: Vec<BlockHeaderHash>) {
         metrics::increment_gauge!(OUTBOUND, 1.0);
-        self.sender.send(PeerAction::GotSyncBlock).await.ok();
+        self.sender.send(PeerAction::ExpectingSyncBlocks(hashes)).await.ok();
     }
 
-    pub async fn expecting_sync_blocks(&self, amount: u32) {
+    /// Registers that the peer has no sync blocks past the given height to share.
+    pub async fn synced_with_us(&self, block_height: BlockHeight) {
         metrics::increment_gauge!(OUTBOUND, 1.0);
-        self.sender.send(PeerAction::ExpectingSyncBlocks(amount)).await.ok();
+        self.sender.send(PeerAction::SyncedWithUs(block_height)).await.ok();
     }
 
     pub async fn fail(&self) {
         metrics::increment_gauge!(OUTBOUND, 1.0);
         self.sender.send(PeerAction::SoftFail).await.ok();
     }
+
+    /// Records an instance of the peer's misbehavior, which also counts as a failure.
+    pub async fn misbehaved(&self, kind: Misbehavior, details: String) {
+        metrics::increment_gauge!(OUTBOUND, 1.0);
+        self.sender.send(PeerAction::Misbehaved(kind, details)).await.ok();
+    }
+
+    /// Registers that the peer has shared addresses disallowed by the gossip port policy; it's penalized
+    /// once it does so repeatedly.
+    pub async fn gossiped_out_of_policy(&self) {
+        metrics::increment_gauge!(OUTBOUND, 1.0);
+        self.sender.send(PeerAction::GossipedOutOfPolicy).await.ok();
+    }
+
+    /// Registers whether a transaction relayed by the peer was included in a block or rejected.
+    pub async fn relayed_transaction_settled(&self, accepted: bool) {
+        metrics::increment_gauge!(OUTBOUND, 1.0);
+        self.sender
+            .send(PeerAction::RelayedTransactionSettled(accepted))
+            .await
+            .ok();
+    }
+
+    /// Registers a `GetSync` request from the peer whose latest shared block is at the given height and depth
+    /// below the node's tip.
+    pub async fn received_sync_query(&self, shared_height: BlockHeight, depth: u32) {
+        metrics::increment_gauge!(OUTBOUND, 1.0);
+        self.sender.send(PeerAction::SyncQuery(shared_height, depth)).await.ok();
+    }
+
+    /// Counts the given number of blocks or block hashes requested by the peer against its serving quota, and
+    /// returns the number of them that can be served; it's zero if the peer is gone.
+    pub async fn request_serving(&self, items: ServedItems, requested: u32, quota: SyncServingQuota) -> u32 {
+        metrics::increment_gauge!(OUTBOUND, 1.0);
+        let (sender, receiver) = oneshot::channel();
+        if self
+            .sender
+            .send(PeerAction::RequestServing(items, requested, quota, sender))
+            .await
+            .is_err()
+        {
+            return 0;
+        }
+        receiver.await.unwrap_or(0)
+    }
+
+    /// Propagates a new block to the peer; peers supporting compact blocks only receive its hash, and
+    /// can request the full block if they don't have it yet.
+    pub async fn announce_block(&self, hash: BlockHeaderHash, block: Arc<Vec<u8>>) {
+        metrics::increment_gauge!(OUTBOUND, 1.0);
+        self.sender.send(PeerAction::AnnounceBlock(hash, block)).await.ok();
+    }
 }
 
 pub(super) enum PeerResponse {
@@ -100,23 +191,35 @@ impl Peer {
         match message {
             PeerAction::Disconnect => Ok(PeerResponse::Disconnect),
             PeerAction::Send(message) => {
-                if matches!(message, Payload::Ping(_)) {
-                    self.quality.expecting_pong = true;
+                self.quality.protocol_state.sent(&message);
+                let span = match self.quality.correlations.sent(self.address, &message) {
+                    Some((_, span)) => span,
+                    None => Span::none(),
+                };
+                if matches!(message, Payload::Ping(..)) {
                     self.quality.last_ping_sent = Some(Instant::now());
+                    // Pings aren't coalesced, so that they don't skew the RTT measurements.
+                    network.write_payload(&message).await?;
+                } else if network.queue_payload(&message).await? {
+                    // Only the peer's own bandwidth cap is held against it, as the node-wide one is shared by all
+                    // the peers.
+                    self.misbehaved(
+                        Misbehavior::ExcessiveRequests,
+                        format!("a '{}' message exceeded the deferred payloads' cap", message),
+                    );
                 }
-                network.write_payload(&message).await?;
-                match &message {
+                span.in_scope(|| match &message {
                     Payload::SyncBlock(_) => trace!("Sent a '{}' message to {}", &message, self.address),
                     _ => debug!("Sent a '{}' message to {}", &message, self.address),
-                }
+                });
                 Ok(PeerResponse::None)
             }
             PeerAction::Get(sender) => {
                 sender.send(self.clone()).ok();
                 Ok(PeerResponse::None)
             }
-            PeerAction::QualityJudgement => {
-                if self.judge_bad() {
+            PeerAction::QualityJudgement(weights) => {
+                if self.judge_bad(&weights) {
                     warn!("Peer {} has a low quality score; disconnecting.", self.address);
                     Ok(PeerResponse::Disconnect)
                 } else {
@@ -124,14 +227,15 @@ impl Peer {
                 }
             }
             PeerAction::CancelSync => {
+                self.quality.protocol_state.cancel_sync();
                 if self.quality.remaining_sync_blocks > self.quality.total_sync_blocks / 2 {
-                    warn!(
-                        "Was expecting {} more sync blocks from {}",
-                        self.quality.remaining_sync_blocks, self.address,
+                    let details = format!(
+                        "{} of the {} requested sync blocks weren't delivered",
+                        self.quality.remaining_sync_blocks, self.quality.total_sync_blocks,
                     );
                     self.quality.remaining_sync_blocks = 0;
                     self.quality.total_sync_blocks = 0;
-                    self.fail();
+                    self.misbehaved(Misbehavior::SyncTimeout, details);
                 } else if self.quality.remaining_sync_blocks > 0 {
                     trace!(
                         "Was expecting {} more sync blocks from {}",
@@ -141,26 +245,117 @@ impl Peer {
                     self.quality.remaining_sync_blocks = 0;
                     self.quality.total_sync_blocks = 0;
                 }
+                self.quality.outstanding_sync_blocks.clear();
                 Ok(PeerResponse::None)
                 //todo: should we notify the peer we are no longer expecting anything from them?
             }
-            PeerAction::GotSyncBlock => {
-                if self.quality.remaining_sync_blocks > 0 {
+            PeerAction::GotSyncBlock(hash) => {
+                let was_outstanding = hash
+                    .map(|hash| self.quality.outstanding_sync_blocks.remove(&hash))
+                    .unwrap_or(false);
+                if was_outstanding && self.quality.remaining_sync_blocks > 0 {
                     self.quality.remaining_sync_blocks -= 1;
+                    self.quality.sync_blocks_received += 1;
                 } else {
                     warn!("received unexpected or late sync block from {}", self.address);
                 }
                 Ok(PeerResponse::None)
             }
-            PeerAction::ExpectingSyncBlocks(amount) => {
-                self.quality.remaining_sync_blocks = amount;
-                self.quality.total_sync_blocks = amount;
+            PeerAction::DeclinedSyncBlock => {
+                // a declined block is no longer expected, so it doesn't count as undelivered once the sync is over
+                if self.quality.remaining_sync_blocks > 0 {
+                    self.quality.remaining_sync_blocks -= 1;
+                    self.quality.total_sync_blocks -= 1;
+                }
+                Ok(PeerResponse::None)
+            }
+            PeerAction::ExpectingSyncBlocks(hashes) => {
+                // the blocks may be requested in several rounds, e.g. if another peer stalled
+                let amount = hashes.len() as u32;
+                self.quality.outstanding_sync_blocks.extend(hashes);
+                self.quality.remaining_sync_blocks += amount;
+                self.quality.total_sync_blocks += amount;
+                self.quality.sync_blocks_requested += amount as u64;
+                Ok(PeerResponse::None)
+            }
+            PeerAction::SyncedWithUs(block_height) => {
+                // The height will be updated again with the next `Ping` from the peer.
+                self.quality.block_height = self.quality.block_height.min(block_height);
                 Ok(PeerResponse::None)
             }
             PeerAction::SoftFail => {
                 self.fail();
                 Ok(PeerResponse::None)
             }
+            PeerAction::Misbehaved(kind, details) => {
+                self.misbehaved(kind, details);
+                Ok(PeerResponse::None)
+            }
+            PeerAction::GossipedOutOfPolicy => {
+                self.quality.out_of_policy_gossip += 1;
+                if self.quality.out_of_policy_gossip >= crate::OUT_OF_POLICY_GOSSIP_TOLERANCE {
+                    self.quality.out_of_policy_gossip = 0;
+                    self.misbehaved(
+                        Misbehavior::DisallowedGossip,
+                        "kept sharing addresses with disallowed ports".into(),
+                    );
+                }
+                Ok(PeerResponse::None)
+            }
+            PeerAction::RelayedTransactionSettled(accepted) => {
+                if accepted {
+                    self.quality.relayed_transactions_accepted += 1;
+                } else {
+                    self.quality.relayed_transactions_rejected += 1;
+                }
+                Ok(PeerResponse::None)
+            }
+            PeerAction::SyncQuery(shared_height, depth) => {
+                let (is_deep, is_repeated) = self.quality.record_sync_query(shared_height, depth);
+                if is_deep {
+                    metrics::increment_counter!(inbound::GETSYNC_DEEP);
+                }
+                if is_repeated {
+                    metrics::increment_counter!(inbound::GETSYNC_REPEATED);
+                    debug!(
+                        "{} repeated its GetSync request from height {}",
+                        self.address, shared_height
+                    );
+                }
+                Ok(PeerResponse::None)
+            }
+            PeerAction::RequestServing(items, requested, quota, sender) => {
+                let (granted, is_abusive) = self
+                    .quality
+                    .serving_window
+                    .grant(&quota, items, requested, Instant::now());
+                if granted < requested {
+                    self.quality.throttled_sync_requests += 1;
+                    metrics::increment_counter!(inbound::SYNC_REQUESTS_THROTTLED);
+                    debug!(
+                        "Serving {} of the {} {} requested by {} within its quota",
+                        granted, requested, items, self.address
+                    );
+                }
+                if is_abusive {
+                    self.misbehaved(
+                        Misbehavior::ExcessiveSyncRequests,
+                        "kept requesting more than its serving quota".into(),
+                    );
+                }
+                sender.send(granted).ok();
+                Ok(PeerResponse::None)
+            }
+            PeerAction::AnnounceBlock(hash, block) => {
+                let message = if self.quality.features.contains(Features::COMPACT_BLOCKS) {
+                    Payload::BlockHash(hash)
+                } else {
+                    Payload::Block(block.to_vec())
+                };
+                network.queue_payload(&message).await?;
+                debug!("Sent a '{}' message to {}", &message, self.address);
+                Ok(PeerResponse::None)
+            }
         }
     }
 }
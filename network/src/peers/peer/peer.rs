@@ -16,16 +16,20 @@
 
 use anyhow::*;
 use chrono::Utc;
-use futures::{select, FutureExt};
+use futures::{future, pin_mut, select, stream::FuturesOrdered, FutureExt, StreamExt};
 use serde::{Deserialize, Serialize};
 use snarkvm_dpc::Storage;
 use std::{net::SocketAddr, time::Duration};
-use tokio::sync::mpsc;
+use tokio::{sync::mpsc, time};
 
-use super::PeerQuality;
-use crate::{NetworkError, Node};
+use super::{PeerQuality, PeerScoreWeights};
+use crate::{Features, Misbehavior, NetworkError, Node, Payload};
+#[cfg(feature = "fault-injection")]
+use crate::{InjectedFault, LinkFaults};
+#[cfg(feature = "fault-injection")]
+use snarkos_metrics::{self as metrics, queues::OUTBOUND};
 
-use super::{network::*, outbound_handler::*};
+use super::{inbound_handler::PendingDecode, network::*, outbound_handler::*};
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
 pub enum PeerStatus {
@@ -51,7 +55,7 @@ pub struct Peer {
 }
 
 const FAILURE_EXPIRY_TIME: Duration = Duration::from_secs(15 * 60);
-const FAILURE_THRESHOLD: usize = 5;
+pub(super) const FAILURE_THRESHOLD: usize = 5;
 
 impl Peer {
     pub fn new(address: SocketAddr, is_bootnode: bool) -> Self {
@@ -63,10 +67,12 @@ impl Peer {
         }
     }
 
-    pub fn judge_bad(&mut self) -> bool {
-        let f = self.failures();
-        // self.quality.rtt_ms > 1500 ||
-        f >= FAILURE_THRESHOLD || self.quality.is_inactive(chrono::Utc::now())
+    /// Checks whether the connected peer should be disconnected from; the peers that keep failing are, regardless of
+    /// how well they score otherwise.
+    pub fn judge_bad(&mut self, weights: &PeerScoreWeights) -> bool {
+        self.failures() >= FAILURE_THRESHOLD
+            || self.score(weights).total < weights.min_score
+            || self.quality.is_inactive(chrono::Utc::now())
     }
 
     pub fn judge_bad_offline(&mut self) -> bool {
@@ -77,6 +83,13 @@ impl Peer {
         self.quality.failures.push(Utc::now());
     }
 
+    /// Records an instance of the peer's misbehavior and counts it as a failure.
+    pub fn misbehaved(&mut self, kind: Misbehavior, details: String) {
+        warn!("Peer {} misbehaved ({}): {}", self.address, kind, details);
+        self.quality.record_misbehavior(kind, details);
+        self.fail();
+    }
+
     pub fn failures(&mut self) -> usize {
         let now = Utc::now();
         if self.quality.failures.len() >= FAILURE_THRESHOLD {
@@ -111,6 +124,31 @@ impl Peer {
     ) -> Result<(), NetworkError> {
         let mut reader = network.take_reader();
 
+        if self.quality.features.contains(Features::REKEY) {
+            network.enable_rekeying();
+        }
+        network.set_throttle(node.outbound_throttle());
+
+        // The clock estimate doesn't need to wait for the peer's first `Ping`.
+        if self.quality.is_outbound {
+            node.record_peer_time(self.address, self.quality.handshake_timestamp);
+        }
+
+        if let Some(identity) = &node.config.identity {
+            if self.quality.features.contains(Features::NODE_ALIAS) {
+                let node_alias = identity.sign_alias(&network.handshake_hash)?;
+                network.write_payload(&Payload::NodeAlias(node_alias)).await?;
+            }
+
+            if let Some(change) = node.pending_address_change() {
+                if self.quality.features.contains(Features::ADDRESS_CHANGE) {
+                    let change =
+                        identity.sign_address_change(change.previous, change.address, &network.handshake_hash)?;
+                    network.write_payload(&Payload::AddressChange(change)).await?;
+                }
+            }
+        }
+
         let (sender, mut read_receiver) = mpsc::channel::<Result<Vec<u8>, NetworkError>>(8);
         tokio::spawn(async move {
             loop {
@@ -124,19 +162,68 @@ impl Peer {
             }
         });
 
+        // The payloads being decoded, in the order they were received in.
+        let mut pending_decodes: FuturesOrdered<PendingDecode> = FuturesOrdered::new();
+
         loop {
+            // Stop reading from the peer while too many of its payloads are still being decoded.
+            let can_read = pending_decodes.len() < crate::MAX_IN_FLIGHT_DECODES_PER_PEER;
+            let next_data = async {
+                if can_read {
+                    read_receiver.recv().await
+                } else {
+                    future::pending().await
+                }
+            }
+            .fuse();
+            pin_mut!(next_data);
+
+            // Make sure the queued payloads don't wait for more of them for too long.
+            let flush_deadline = network.flush_deadline();
+            let flush_timer = async {
+                match flush_deadline {
+                    Some(deadline) => time::sleep_until(deadline.into()).await,
+                    None => future::pending().await,
+                }
+            }
+            .fuse();
+            pin_mut!(flush_timer);
+
+            // Release the bulk payloads deferred by the bandwidth caps once they're within them.
+            let deferred_deadline = network.deferred_deadline();
+            let deferred_timer = async {
+                match deferred_deadline {
+                    Some(deadline) => time::sleep_until(deadline.into()).await,
+                    None => future::pending().await,
+                }
+            }
+            .fuse();
+            pin_mut!(deferred_timer);
+
             select! {
                 message = receiver.recv().fuse() => {
                     if message.is_none() {
                         break;
                     }
                     let message = message.unwrap();
+
+                    #[cfg(feature = "fault-injection")]
+                    if let Some(faults) = node.fault_injector.faults_for(self.address) {
+                        match self.process_faulty_message(&mut network, message, faults).await? {
+                            Some(PeerResponse::Disconnect) => break,
+                            Some(PeerResponse::None) => (),
+                            // The connection is dropped abruptly, without flushing the queued payloads.
+                            None => return Ok(()),
+                        }
+                        continue;
+                    }
+
                     match self.process_message(&mut network, message).await? {
                         PeerResponse::Disconnect => break,
                         PeerResponse::None => (),
                     }
                 },
-                data = read_receiver.recv().fuse() => {
+                data = next_data => {
                     if data.is_none() {
                         break;
                     }
@@ -146,14 +233,77 @@ impl Peer {
                         Err(e) => Err(e)
                     };
 
-                    let deserialized = self.deserialize_payload(data);
+                    pending_decodes.push(Self::decode_payload(data));
+                },
+                deserialized = pending_decodes.select_next_some() => {
                     self.dispatch_payload(&node, &mut network, deserialized).await?;
                 },
+                _ = flush_timer => {
+                    network.flush_queue().await?;
+                },
+                _ = deferred_timer => {
+                    network.release_deferred().await?;
+                },
             }
         }
+
+        // Don't drop the payloads queued before a disconnect.
+        network.flush_queue().await.ok();
+
         Ok(())
     }
 
+    /// Processes an outbound message subject to the faults injected into the link with the peer; returns
+    /// `None` if the connection is to be dropped abruptly.
+    #[cfg(feature = "fault-injection")]
+    async fn process_faulty_message(
+        &mut self,
+        network: &mut PeerIOHandle,
+        message: PeerAction,
+        faults: LinkFaults,
+    ) -> Result<Option<PeerResponse>, NetworkError> {
+        // Only the messages actually written to the peer are subject to faults.
+        if !matches!(message, PeerAction::Send(_) | PeerAction::AnnounceBlock(..)) {
+            return self.process_message(network, message).await.map(Some);
+        }
+
+        if let Some(delay) = faults.delay {
+            time::sleep(delay).await;
+        }
+
+        match faults.draw() {
+            Some(InjectedFault::Disconnect) => {
+                warn!("Injected fault: dropping the connection with {}", self.address);
+                Ok(None)
+            }
+            Some(InjectedFault::Drop) => {
+                // Account for the message as if it was processed.
+                metrics::decrement_gauge!(OUTBOUND, 1.0);
+                trace!("Injected fault: dropped a message to {}", self.address);
+                Ok(Some(PeerResponse::None))
+            }
+            Some(InjectedFault::Duplicate) => {
+                let duplicate = match &message {
+                    PeerAction::Send(payload) => PeerAction::Send(payload.clone()),
+                    PeerAction::AnnounceBlock(hash, block) => PeerAction::AnnounceBlock(hash.clone(), block.clone()),
+                    _ => unreachable!(),
+                };
+                metrics::increment_gauge!(OUTBOUND, 1.0);
+                trace!("Injected fault: duplicated a message to {}", self.address);
+                self.process_message(network, duplicate).await?;
+                self.process_message(network, message).await.map(Some)
+            }
+            None => self.process_message(network, message).await.map(Some),
+        }
+    }
+
+    /// Returns `true` if the current connection is the one to keep in case of a duplicate connection with the peer,
+    /// e.g. when both sides dial each other simultaneously; the connection initiated by the node with the lower ID
+    /// is kept, so that both sides make the same choice.
+    pub(super) fn is_preferred_connection(&self, own_id: u64, outbound: bool) -> bool {
+        outbound == (own_id < self.quality.node_id)
+    }
+
     pub(super) fn set_connected(&mut self) {
         self.quality.connected();
         self.status = PeerStatus::Connected;
@@ -19,9 +19,14 @@ use std::net::SocketAddr;
 use crate::{Peer, PeerHandle, PeerStatus};
 
 pub enum PeerEventData {
-    Connected(PeerHandle),
-    Disconnect(Peer, PeerStatus),
+    /// A connection was established; the flags are `true` if it was initiated by this node, and if it's the one
+    /// to keep in case of a duplicate connection with the peer, respectively.
+    Connected(PeerHandle, bool, bool),
+    /// A connection was closed, or failed to be established; the handle refers to that connection.
+    Disconnect(Peer, PeerStatus, PeerHandle),
     FailHandshake,
+    /// The connection was made by a management client using the RPC over the P2P port; it isn't a peer.
+    RpcClient,
 }
 
 pub struct PeerEvent {
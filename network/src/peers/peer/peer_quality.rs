@@ -14,28 +14,110 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use std::time::Instant;
+use std::{
+    collections::{HashSet, VecDeque},
+    time::Instant,
+};
 
 use chrono::{DateTime, Utc};
-use snarkos_storage::BlockHeight;
+use snarkvm_dpc::BlockHeaderHash;
+
+use crate::{BlockHeight, Correlations, Features, Misbehavior, MisbehaviorEvent, PeerAlias, ProtocolState, ServingWindow};
 
 #[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
 pub struct PeerQuality {
     pub block_height: BlockHeight,
+    /// The height of the earliest block the peer is able to serve; it's non-zero for pruned peers.
+    pub earliest_block_height: BlockHeight,
+    /// The version of the network protocol used with the peer; the lower one of the two sides.
+    #[serde(skip)]
+    pub protocol_version: u64,
+    /// The optional protocol extensions supported by both this node and the peer.
+    #[serde(skip)]
+    pub features: Features,
+    /// Indicates whether the peer is a watcher node, which can't serve blocks or transactions.
+    #[serde(skip)]
+    pub is_watcher: bool,
+    /// Indicates whether the connection was made by a management client using the RPC over the P2P port.
+    #[serde(skip)]
+    pub is_rpc_client: bool,
+    /// Indicates whether the peer announced no listening port, i.e. it doesn't accept any connections.
+    #[serde(skip)]
+    pub no_listen: bool,
+    /// Indicates whether the current connection was initiated by this node.
+    #[serde(skip)]
+    pub is_outbound: bool,
+    /// The random identifier the peer announced in the current connection's handshake.
+    #[serde(skip)]
+    pub node_id: u64,
+    /// The UNIX timestamp the peer announced in the current connection's handshake, in seconds; 0 if it didn't.
+    #[serde(skip)]
+    pub handshake_timestamp: i64,
+    /// The unverified alias the peer has last sent.
+    pub alias: Option<PeerAlias>,
     pub last_seen: Option<DateTime<Utc>>,
+    /// The state of the protocol in the current connection.
+    #[serde(skip)]
+    pub protocol_state: ProtocolState,
+    /// The requests exchanged in the current connection that are awaiting their responses.
     #[serde(skip)]
-    pub expecting_pong: bool,
+    pub correlations: Correlations,
     #[serde(skip)]
     pub last_ping_sent: Option<Instant>,
     /// The time it took to send a `Ping` to the peer and for it to respond with a `Pong`.
     pub rtt_ms: u64,
+    /// The most recent round-trip times, from the oldest to the newest; at most `MAX_RTT_SAMPLES` of them.
+    #[serde(skip)]
+    pub rtt_samples: VecDeque<u64>,
+    /// The number of connected peers the peer shared in its last response to a `GetPeers` request during the current
+    /// connection; a lower bound of its degree, as the shared peers are capped and limited to one address family.
+    #[serde(skip)]
+    pub reported_degree: Option<usize>,
     /// The number of failures associated with the peer; grounds for dismissal.
     pub failures: Vec<DateTime<Utc>>,
+    /// The most recent misbehavior of the peer, from the oldest to the newest; at most `MAX_MISBEHAVIOR_EVENTS`
+    /// of them.
+    #[serde(skip)]
+    pub misbehavior: VecDeque<MisbehaviorEvent>,
     /// number of requested sync blocks
     pub total_sync_blocks: u32,
     /// The number of remaining blocks to sync with.
     pub remaining_sync_blocks: u32,
+    /// The hashes of the requested sync blocks the peer hasn't delivered yet.
+    #[serde(skip)]
+    pub outstanding_sync_blocks: HashSet<BlockHeaderHash>,
+    /// The number of `GetSync` requests received from the peer.
+    pub sync_queries: u64,
+    /// The number of `GetSync` requests from the peer whose latest shared block was more than
+    /// `DEEP_SYNC_QUERY_DEPTH` blocks below the node's tip.
+    pub deep_sync_queries: u64,
+    /// The number of `GetSync` requests from the peer that shared the same latest block as its previous one,
+    /// even though the node had newer blocks to share.
+    pub repeated_sync_queries: u64,
+    /// The height of the latest block shared with the peer in its previous `GetSync` request.
+    #[serde(skip)]
+    pub last_sync_query_height: Option<BlockHeight>,
+    /// The number of `GetBlocks` and `GetSync` requests from the peer that exceeded its serving quota.
+    pub throttled_sync_requests: u64,
+    /// The blocks and block hashes served to the peer in the current window of its serving quota.
+    #[serde(skip)]
+    pub serving_window: ServingWindow,
+    /// The number of sync blocks ever requested from the peer.
+    pub sync_blocks_requested: u64,
+    /// The number of requested sync blocks the peer has delivered.
+    pub sync_blocks_received: u64,
+    /// The number of `Peers` messages with addresses disallowed by the gossip port policy the peer has sent
+    /// since it was last penalized for them.
+    #[serde(skip)]
+    pub out_of_policy_gossip: u8,
+    /// The number of transactions relayed by the peer that were later included in a block.
+    pub relayed_transactions_accepted: u64,
+    /// The number of transactions relayed by the peer that were invalid or expired before being included in a block.
+    pub relayed_transactions_rejected: u64,
     pub num_messages_received: u64,
+    /// The number of messages received from the peer during the current connection.
+    #[serde(skip)]
+    pub connection_messages_received: u64,
     pub first_seen: Option<DateTime<Utc>>,
     pub last_connected: Option<DateTime<Utc>>,
     pub last_disconnected: Option<DateTime<Utc>>,
@@ -44,6 +126,34 @@ pub struct PeerQuality {
     pub disconnected_count: u64,
 }
 
+/// The percentiles of a set of round-trip times.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RttPercentiles {
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub p99_ms: u64,
+}
+
+impl RttPercentiles {
+    /// Calculates the percentiles of the given round-trip times using the nearest-rank method;
+    /// returns `None` if there aren't any.
+    pub fn from_samples<I: IntoIterator<Item = u64>>(samples: I) -> Option<Self> {
+        let mut samples = samples.into_iter().collect::<Vec<_>>();
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort_unstable();
+
+        let percentile = |p: usize| samples[(samples.len() * p + 99) / 100 - 1];
+
+        Some(Self {
+            p50_ms: percentile(50),
+            p95_ms: percentile(95),
+            p99_ms: percentile(99),
+        })
+    }
+}
+
 impl PeerQuality {
     pub fn is_inactive(&self, now: DateTime<Utc>) -> bool {
         let last_seen = self.last_seen;
@@ -55,6 +165,56 @@ impl PeerQuality {
         }
     }
 
+    /// Registers a new round-trip time, replacing the oldest sample if there are already `MAX_RTT_SAMPLES` of them.
+    pub fn record_rtt(&mut self, rtt_ms: u64) {
+        self.rtt_ms = rtt_ms;
+        if self.rtt_samples.len() == crate::MAX_RTT_SAMPLES {
+            self.rtt_samples.pop_front();
+        }
+        self.rtt_samples.push_back(rtt_ms);
+    }
+
+    /// Registers an instance of the peer's misbehavior, replacing the oldest one if there are already
+    /// `MAX_MISBEHAVIOR_EVENTS` of them.
+    pub fn record_misbehavior(&mut self, kind: Misbehavior, details: String) {
+        if self.misbehavior.len() == crate::MAX_MISBEHAVIOR_EVENTS {
+            self.misbehavior.pop_front();
+        }
+        self.misbehavior.push_back(MisbehaviorEvent {
+            time: Utc::now(),
+            kind,
+            details,
+        });
+    }
+
+    /// Returns up to `limit` of the most recent misbehavior events, from the newest to the oldest.
+    pub fn recent_misbehavior(&self, limit: usize) -> Vec<MisbehaviorEvent> {
+        self.misbehavior.iter().rev().take(limit).cloned().collect()
+    }
+
+    /// Registers a `GetSync` request whose latest shared block is at the given height and depth below the
+    /// node's tip; returns whether the request is deep and whether it repeats the previous one.
+    pub fn record_sync_query(&mut self, shared_height: BlockHeight, depth: u32) -> (bool, bool) {
+        let is_deep = depth > crate::DEEP_SYNC_QUERY_DEPTH;
+        let is_repeated = depth > 0 && self.last_sync_query_height == Some(shared_height);
+
+        self.sync_queries += 1;
+        if is_deep {
+            self.deep_sync_queries += 1;
+        }
+        if is_repeated {
+            self.repeated_sync_queries += 1;
+        }
+        self.last_sync_query_height = Some(shared_height);
+
+        (is_deep, is_repeated)
+    }
+
+    /// Returns the percentiles of the recent round-trip times, if any were measured.
+    pub fn rtt_percentiles(&self) -> Option<RttPercentiles> {
+        RttPercentiles::from_samples(self.rtt_samples.iter().copied())
+    }
+
     pub fn see(&mut self) {
         let now = chrono::Utc::now();
         if self.first_seen.is_none() {
@@ -67,14 +227,89 @@ impl PeerQuality {
         self.see();
         self.last_connected = Some(chrono::Utc::now());
         self.connected_count += 1;
+        self.connection_messages_received = 0;
+        self.reported_degree = None;
+        self.last_sync_query_height = None;
+        self.protocol_state = Default::default();
+        self.correlations = Default::default();
     }
 
     pub fn disconnected(&mut self) {
         self.see();
         self.last_disconnected = Some(chrono::Utc::now());
         self.disconnected_count += 1;
-        self.expecting_pong = false;
+        self.protocol_state = Default::default();
+        self.correlations = Default::default();
         self.remaining_sync_blocks = 0;
         self.total_sync_blocks = 0;
+        self.outstanding_sync_blocks.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rtt_percentiles() {
+        assert_eq!(RttPercentiles::from_samples(vec![]), None);
+
+        let single = RttPercentiles::from_samples(vec![42]).unwrap();
+        assert_eq!((single.p50_ms, single.p95_ms, single.p99_ms), (42, 42, 42));
+
+        // The samples 1..=100 in reverse, so that their order doesn't matter.
+        let percentiles = RttPercentiles::from_samples((1..=100).rev()).unwrap();
+        assert_eq!(percentiles.p50_ms, 50);
+        assert_eq!(percentiles.p95_ms, 95);
+        assert_eq!(percentiles.p99_ms, 99);
+    }
+
+    #[test]
+    fn rtt_samples_are_bounded() {
+        let mut quality = PeerQuality::default();
+        for rtt in 0..crate::MAX_RTT_SAMPLES as u64 + 10 {
+            quality.record_rtt(rtt);
+        }
+
+        assert_eq!(quality.rtt_samples.len(), crate::MAX_RTT_SAMPLES);
+        assert_eq!(quality.rtt_samples.front(), Some(&10));
+        assert_eq!(quality.rtt_ms, crate::MAX_RTT_SAMPLES as u64 + 9);
+    }
+
+    #[test]
+    fn misbehavior_is_bounded() {
+        let mut quality = PeerQuality::default();
+        for i in 0..crate::MAX_MISBEHAVIOR_EVENTS + 2 {
+            quality.record_misbehavior(Misbehavior::UnsolicitedMessage, i.to_string());
+        }
+
+        assert_eq!(quality.misbehavior.len(), crate::MAX_MISBEHAVIOR_EVENTS);
+        assert_eq!(quality.misbehavior.front().unwrap().details, "2");
+
+        let recent = quality.recent_misbehavior(2);
+        let details = recent.iter().map(|event| event.details.as_str()).collect::<Vec<_>>();
+        assert_eq!(details, vec![
+            (crate::MAX_MISBEHAVIOR_EVENTS + 1).to_string(),
+            crate::MAX_MISBEHAVIOR_EVENTS.to_string()
+        ]);
+    }
+
+    #[test]
+    fn sync_query_patterns() {
+        let mut quality = PeerQuality::default();
+
+        assert_eq!(quality.record_sync_query(100, 10), (false, false));
+        assert_eq!(quality.record_sync_query(100, 10), (false, true));
+        // A peer that is synced with the node can keep asking from its tip.
+        assert_eq!(quality.record_sync_query(110, 0), (false, false));
+        assert_eq!(quality.record_sync_query(110, 0), (false, false));
+        assert_eq!(
+            quality.record_sync_query(0, crate::DEEP_SYNC_QUERY_DEPTH + 1),
+            (true, false)
+        );
+
+        assert_eq!(quality.sync_queries, 5);
+        assert_eq!(quality.deep_sync_queries, 1);
+        assert_eq!(quality.repeated_sync_queries, 1);
     }
 }
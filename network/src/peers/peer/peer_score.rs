@@ -0,0 +1,289 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::{peer::FAILURE_THRESHOLD, Peer};
+use crate::NetworkError;
+
+/// The round-trip time at and above which a peer's RTT component is zero.
+const RTT_REFERENCE_MS: u64 = 1500;
+/// The rate of received messages at and above which a peer's throughput component is maxed out;
+/// a peer that only responds to the periodic `Ping`s stays just below it.
+const THROUGHPUT_REFERENCE_PER_MIN: f64 = 5.0;
+/// The length of a connection at and above which a peer's uptime component is maxed out.
+const UPTIME_REFERENCE_SECS: i64 = 3600;
+/// The number of settled relayed transactions below which a peer's relay acceptance ratio isn't held against it.
+const MIN_RELAY_OUTCOMES: u64 = 10;
+/// The relay acceptance ratio below which the transactions sent by a peer are ignored; a peer loses this
+/// privilege well before its relay component alone could get it disconnected.
+const MIN_RELAY_ACCEPTANCE_RATIO: f64 = 0.5;
+
+/// The weights of the components of a `PeerScore`; they don't need to add up to 1, as the total
+/// score is normalized by their sum. The ones missing from the config file take their default values.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PeerScoreWeights {
+    pub rtt: f64,
+    pub failures: f64,
+    pub throughput: f64,
+    pub sync: f64,
+    pub uptime: f64,
+    pub relay: f64,
+    /// The total score below which a connected peer is disconnected from.
+    pub min_score: f64,
+}
+
+impl Default for PeerScoreWeights {
+    fn default() -> Self {
+        Self {
+            rtt: 1.0,
+            failures: 2.0,
+            throughput: 0.5,
+            sync: 1.0,
+            uptime: 0.5,
+            relay: 1.0,
+            min_score: 0.5,
+        }
+    }
+}
+
+impl PeerScoreWeights {
+    /// Checks that the weights are non-negative numbers and that the minimum score is between 0 and 1.
+    pub fn check(&self) -> Result<(), NetworkError> {
+        let weights = [
+            ("rtt", self.rtt),
+            ("failures", self.failures),
+            ("throughput", self.throughput),
+            ("sync", self.sync),
+            ("uptime", self.uptime),
+            ("relay", self.relay),
+        ];
+        for &(name, weight) in weights.iter() {
+            if !weight.is_finite() || weight < 0.0 {
+                return Err(NetworkError::InvalidPeerScoreWeights(format!(
+                    "the {} weight of {} isn't a non-negative number",
+                    name, weight
+                )));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.min_score) {
+            return Err(NetworkError::InvalidPeerScoreWeights(format!(
+                "the minimum score of {} isn't between 0 and 1",
+                self.min_score
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// The quality of a peer; every component, as well as the total, is between 0 (worst) and 1 (best).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PeerScore {
+    /// Based on the last measured round-trip time.
+    pub rtt: f64,
+    /// Based on the number of recent failures.
+    pub failures: f64,
+    /// Based on the rate of messages received during the current connection.
+    pub throughput: f64,
+    /// Based on the share of requested sync blocks that were delivered.
+    pub sync: f64,
+    /// Based on the length of the current connection.
+    pub uptime: f64,
+    /// Based on the share of relayed transactions that were included in a block.
+    pub relay: f64,
+    /// The weighted average of the components.
+    pub total: f64,
+}
+
+impl Peer {
+    /// Returns the number of the peer's relayed transactions that were either included in a block or rejected.
+    fn settled_relayed_transactions(&self) -> u64 {
+        self.quality.relayed_transactions_accepted + self.quality.relayed_transactions_rejected
+    }
+
+    /// Returns the share of the peer's settled relayed transactions that were included in a block, if any.
+    pub fn relay_acceptance_ratio(&self) -> Option<f64> {
+        match self.settled_relayed_transactions() {
+            0 => None,
+            settled => Some(self.quality.relayed_transactions_accepted as f64 / settled as f64),
+        }
+    }
+
+    /// Checks whether the transactions relayed by the peer should still be processed.
+    pub fn has_relay_privileges(&self) -> bool {
+        match self.relay_acceptance_ratio() {
+            Some(ratio) if self.settled_relayed_transactions() >= MIN_RELAY_OUTCOMES => {
+                ratio >= MIN_RELAY_ACCEPTANCE_RATIO
+            }
+            _ => true,
+        }
+    }
+
+    /// Computes the peer's current score using the given weights.
+    pub fn score(&mut self, weights: &PeerScoreWeights) -> PeerScore {
+        let connected_secs = self
+            .quality
+            .last_connected
+            .map(|last_connected| (Utc::now() - last_connected).num_seconds().max(0))
+            .unwrap_or(0);
+
+        // An RTT of 0 means it hasn't been measured yet.
+        let rtt = 1.0 - self.quality.rtt_ms.min(RTT_REFERENCE_MS) as f64 / RTT_REFERENCE_MS as f64;
+
+        let failures = 1.0 - self.failures().min(FAILURE_THRESHOLD) as f64 / FAILURE_THRESHOLD as f64;
+
+        let throughput = if connected_secs > 0 {
+            let per_min = self.quality.connection_messages_received as f64 * 60.0 / connected_secs as f64;
+            (per_min / THROUGHPUT_REFERENCE_PER_MIN).min(1.0)
+        } else {
+            0.0
+        };
+
+        // Peers that weren't asked for any blocks yet are given the benefit of the doubt.
+        let sync = if self.quality.sync_blocks_requested > 0 {
+            (self.quality.sync_blocks_received as f64 / self.quality.sync_blocks_requested as f64).min(1.0)
+        } else {
+            1.0
+        };
+
+        let uptime = (connected_secs.min(UPTIME_REFERENCE_SECS) as f64) / UPTIME_REFERENCE_SECS as f64;
+
+        // Like with sync, peers with only a few settled relayed transactions are given the benefit of the doubt.
+        let relay = match self.relay_acceptance_ratio() {
+            Some(ratio) if self.settled_relayed_transactions() >= MIN_RELAY_OUTCOMES => ratio,
+            _ => 1.0,
+        };
+
+        let weight_sum =
+            weights.rtt + weights.failures + weights.throughput + weights.sync + weights.uptime + weights.relay;
+        let total = if weight_sum > 0.0 {
+            (rtt * weights.rtt
+                + failures * weights.failures
+                + throughput * weights.throughput
+                + sync * weights.sync
+                + uptime * weights.uptime
+                + relay * weights.relay)
+                / weight_sum
+        } else {
+            1.0
+        };
+
+        PeerScore {
+            rtt,
+            failures,
+            throughput,
+            sync,
+            uptime,
+            relay,
+            total,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fresh_peer_is_acceptable() {
+        let mut peer = Peer::new("127.0.0.1:4131".parse().unwrap(), false);
+        peer.quality.connected();
+
+        let weights = PeerScoreWeights::default();
+        assert!(peer.score(&weights).total >= weights.min_score);
+    }
+
+    #[test]
+    fn failing_peer_is_not_acceptable() {
+        let mut peer = Peer::new("127.0.0.1:4131".parse().unwrap(), false);
+        peer.quality.connected();
+        for _ in 0..FAILURE_THRESHOLD {
+            peer.fail();
+        }
+
+        let weights = PeerScoreWeights::default();
+        let score = peer.score(&weights);
+        assert_eq!(score.failures, 0.0);
+        assert!(score.total < weights.min_score);
+    }
+
+    #[test]
+    fn failing_peer_is_judged_bad_despite_a_high_score() {
+        let mut peer = Peer::new("127.0.0.1:4131".parse().unwrap(), false);
+        peer.quality.connected();
+        // A long-lived, fast and busy connection maxes out every other component.
+        peer.quality.last_connected = Some(Utc::now() - chrono::Duration::seconds(2 * UPTIME_REFERENCE_SECS));
+        peer.quality.connection_messages_received = 1_000;
+        peer.quality.rtt_ms = 10;
+        for _ in 0..FAILURE_THRESHOLD {
+            peer.fail();
+        }
+
+        let weights = PeerScoreWeights::default();
+        let score = peer.score(&weights);
+        assert_eq!(score.failures, 0.0);
+        assert_eq!(score.uptime, 1.0);
+        assert_eq!(score.throughput, 1.0);
+        assert!(score.total >= weights.min_score);
+        assert!(peer.judge_bad(&weights));
+    }
+
+    #[test]
+    fn invalid_weights_are_rejected() {
+        assert!(PeerScoreWeights::default().check().is_ok());
+
+        let negative = PeerScoreWeights {
+            rtt: -1.0,
+            ..Default::default()
+        };
+        assert!(negative.check().is_err());
+
+        let unreachable = PeerScoreWeights {
+            min_score: 1.5,
+            ..Default::default()
+        };
+        assert!(unreachable.check().is_err());
+    }
+
+    #[test]
+    fn undelivered_sync_blocks_lower_the_score() {
+        let mut peer = Peer::new("127.0.0.1:4131".parse().unwrap(), false);
+        peer.quality.connected();
+        peer.quality.sync_blocks_requested = 10;
+        peer.quality.sync_blocks_received = 5;
+
+        assert_eq!(peer.score(&Default::default()).sync, 0.5);
+    }
+
+    #[test]
+    fn rejected_relayed_transactions_revoke_relay_privileges_first() {
+        let mut peer = Peer::new("127.0.0.1:4131".parse().unwrap(), false);
+        peer.quality.connected();
+        peer.quality.relayed_transactions_accepted = 2;
+        assert!(peer.has_relay_privileges());
+
+        peer.quality.relayed_transactions_rejected = 8;
+        assert!(!peer.has_relay_privileges());
+
+        let weights = PeerScoreWeights::default();
+        let score = peer.score(&weights);
+        assert_eq!(score.relay, 0.2);
+        assert!(score.total >= weights.min_score);
+    }
+}
@@ -0,0 +1,315 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{Features, Payload, PayloadFlags};
+
+/// The phase of the block sync with a peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncPhase {
+    /// No blocks are being synced with the peer.
+    Idle,
+    /// A `GetSync` was sent to the peer, which is expected to respond with a `Sync`.
+    AwaitingSync,
+    /// Blocks were requested from the peer; the given number of `SyncBlock`s is still expected.
+    AwaitingSyncBlocks(u32),
+    /// The sync with the peer was cancelled; late responses to it are ignored.
+    Cancelled,
+}
+
+impl Default for SyncPhase {
+    fn default() -> Self {
+        Self::Idle
+    }
+}
+
+/// The outcome of receiving a payload from a peer in a given protocol state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    /// The payload is valid in the current state and can be processed.
+    Valid,
+    /// The payload is a late response to a cancelled request; it's dropped without consequences.
+    Stale,
+    /// The payload wasn't expected in the current state; it's dropped and the peer is penalized.
+    Unexpected,
+    /// The payload is not allowed over the connection at all; the peer is disconnected from.
+    Forbidden,
+}
+
+/// The per-connection state of the protocol, which determines the payloads the peer is allowed to send.
+#[derive(Debug, Default, Clone)]
+pub struct ProtocolState {
+    /// The phase of the block sync with the peer.
+    pub sync: SyncPhase,
+    /// Indicates whether a `Ping` was sent to the peer and its `Pong` is expected.
+    pub awaiting_pong: bool,
+    /// Indicates whether a `GetMemoryPool` was sent to the peer and its `MemoryPool` is expected.
+    pub awaiting_memory_pool: bool,
+    /// Indicates whether the peer has already sent its alias.
+    pub alias_received: bool,
+    /// Indicates whether the peer has already announced a change of its address.
+    pub address_change_received: bool,
+}
+
+impl ProtocolState {
+    /// Registers a payload sent to the peer, which may make a response from it valid.
+    pub fn sent(&mut self, payload: &Payload) {
+        match payload {
+            Payload::Ping(..) => self.awaiting_pong = true,
+            Payload::GetMemoryPool => self.awaiting_memory_pool = true,
+            Payload::GetSync(_) => self.sync = SyncPhase::AwaitingSync,
+            Payload::GetBlocks(hashes) => {
+                let requested = hashes.len() as u32;
+                self.sync = match self.sync {
+                    SyncPhase::AwaitingSyncBlocks(remaining) => SyncPhase::AwaitingSyncBlocks(remaining + requested),
+                    _ => SyncPhase::AwaitingSyncBlocks(requested),
+                };
+            }
+            _ => {}
+        }
+    }
+
+    /// Registers that no more blocks are expected from the peer.
+    pub fn cancel_sync(&mut self) {
+        if self.sync != SyncPhase::Idle {
+            self.sync = SyncPhase::Cancelled;
+        }
+    }
+
+    /// Checks whether the given payload received from the peer is valid in the current state,
+    /// and moves to the next state if it is.
+    pub fn received(&mut self, payload: &Payload, features: Features) -> Transition {
+        match payload {
+            Payload::Pong if self.awaiting_pong => {
+                self.awaiting_pong = false;
+                Transition::Valid
+            }
+            Payload::Pong => Transition::Unexpected,
+            Payload::MemoryPool(_) if self.awaiting_memory_pool => {
+                self.awaiting_memory_pool = false;
+                Transition::Valid
+            }
+            Payload::MemoryPool(_) => Transition::Unexpected,
+            Payload::NodeAlias(_) if !features.contains(Features::NODE_ALIAS) => Transition::Forbidden,
+            Payload::NodeAlias(_) if self.alias_received => Transition::Unexpected,
+            Payload::NodeAlias(_) => {
+                self.alias_received = true;
+                Transition::Valid
+            }
+            Payload::BlockHash(_) | Payload::GetBlock(_) if !features.contains(Features::COMPACT_BLOCKS) => {
+                Transition::Forbidden
+            }
+            Payload::Rekey if !features.contains(Features::REKEY) => Transition::Forbidden,
+            Payload::AddressChange(_) if !features.contains(Features::ADDRESS_CHANGE) => Transition::Forbidden,
+            Payload::AddressChange(_) if self.address_change_received => Transition::Unexpected,
+            Payload::AddressChange(_) => {
+                self.address_change_received = true;
+                Transition::Valid
+            }
+            // The payloads of unknown types are skipped, unless the sender doesn't allow it.
+            Payload::Unknown(_, flags) if flags.contains(PayloadFlags::CRITICAL) => Transition::Forbidden,
+            Payload::Sync(_) => match self.sync {
+                SyncPhase::AwaitingSync => {
+                    // The sync blocks are expected once they are requested.
+                    self.sync = SyncPhase::Idle;
+                    Transition::Valid
+                }
+                SyncPhase::Cancelled => Transition::Stale,
+                _ => Transition::Unexpected,
+            },
+            Payload::SyncBlock(_) => match self.sync {
+                SyncPhase::AwaitingSyncBlocks(remaining) => {
+                    self.sync = if remaining > 1 {
+                        SyncPhase::AwaitingSyncBlocks(remaining - 1)
+                    } else {
+                        SyncPhase::Idle
+                    };
+                    Transition::Valid
+                }
+                SyncPhase::Cancelled => Transition::Stale,
+                _ => Transition::Unexpected,
+            },
+            // Requests and announcements can be sent at any time.
+            _ => Transition::Valid,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AddressChange, NodeAlias};
+    use snarkvm_dpc::BlockHeaderHash;
+
+    fn node_alias() -> Payload {
+        Payload::NodeAlias(NodeAlias {
+            alias: "alias".into(),
+            public_key: vec![],
+            signature: vec![],
+        })
+    }
+
+    fn address_change() -> Payload {
+        Payload::AddressChange(AddressChange {
+            previous: "0.0.0.0:4131".parse().unwrap(),
+            address: "0.0.0.0:4141".parse().unwrap(),
+            public_key: vec![],
+            signature: vec![],
+        })
+    }
+
+    #[test]
+    fn unsolicited_responses_are_unexpected() {
+        let mut state = ProtocolState::default();
+        let features = Features::empty();
+
+        assert_eq!(state.received(&Payload::Pong, features), Transition::Unexpected);
+        assert_eq!(
+            state.received(&Payload::MemoryPool(vec![]), features),
+            Transition::Unexpected
+        );
+        assert_eq!(state.received(&Payload::Sync(vec![]), features), Transition::Unexpected);
+        assert_eq!(
+            state.received(&Payload::SyncBlock(vec![]), features),
+            Transition::Unexpected
+        );
+    }
+
+    #[test]
+    fn requests_and_announcements_are_always_valid() {
+        let mut state = ProtocolState::default();
+        let features = Features::empty();
+        let hash = BlockHeaderHash([0u8; 32]);
+
+        for payload in &[
+            Payload::Ping(0, 0),
+            Payload::GetPeers,
+            Payload::Peers(vec![]),
+            Payload::GetMemoryPool,
+            Payload::GetSync(vec![hash.clone()]),
+            Payload::GetBlocks(vec![hash]),
+            Payload::Block(vec![]),
+            Payload::Transaction(vec![]),
+            Payload::Unknown(42, PayloadFlags::empty()),
+        ] {
+            assert_eq!(state.received(payload, features), Transition::Valid);
+        }
+    }
+
+    #[test]
+    fn responses_are_valid_once_per_request() {
+        let mut state = ProtocolState::default();
+        let features = Features::empty();
+
+        state.sent(&Payload::Ping(0, 0));
+        assert_eq!(state.received(&Payload::Pong, features), Transition::Valid);
+        assert_eq!(state.received(&Payload::Pong, features), Transition::Unexpected);
+
+        state.sent(&Payload::GetMemoryPool);
+        assert_eq!(
+            state.received(&Payload::MemoryPool(vec![]), features),
+            Transition::Valid
+        );
+        assert_eq!(
+            state.received(&Payload::MemoryPool(vec![]), features),
+            Transition::Unexpected
+        );
+    }
+
+    #[test]
+    fn sync_transitions() {
+        let mut state = ProtocolState::default();
+        let features = Features::empty();
+        let hashes = vec![BlockHeaderHash([0u8; 32]), BlockHeaderHash([1u8; 32])];
+
+        state.sent(&Payload::GetSync(vec![]));
+        assert_eq!(state.sync, SyncPhase::AwaitingSync);
+        assert_eq!(
+            state.received(&Payload::SyncBlock(vec![]), features),
+            Transition::Unexpected
+        );
+        assert_eq!(
+            state.received(&Payload::Sync(hashes.clone()), features),
+            Transition::Valid
+        );
+        assert_eq!(state.sync, SyncPhase::Idle);
+
+        state.sent(&Payload::GetBlocks(hashes));
+        assert_eq!(state.sync, SyncPhase::AwaitingSyncBlocks(2));
+        assert_eq!(state.received(&Payload::Sync(vec![]), features), Transition::Unexpected);
+        assert_eq!(state.received(&Payload::SyncBlock(vec![]), features), Transition::Valid);
+        assert_eq!(state.received(&Payload::SyncBlock(vec![]), features), Transition::Valid);
+        assert_eq!(state.sync, SyncPhase::Idle);
+        assert_eq!(
+            state.received(&Payload::SyncBlock(vec![]), features),
+            Transition::Unexpected
+        );
+    }
+
+    #[test]
+    fn late_responses_to_a_cancelled_sync_are_stale() {
+        let mut state = ProtocolState::default();
+        let features = Features::empty();
+
+        // Cancelling a sync that isn't in progress has no effect.
+        state.cancel_sync();
+        assert_eq!(state.sync, SyncPhase::Idle);
+
+        state.sent(&Payload::GetBlocks(vec![BlockHeaderHash([0u8; 32])]));
+        state.cancel_sync();
+        assert_eq!(state.received(&Payload::SyncBlock(vec![]), features), Transition::Stale);
+        assert_eq!(state.received(&Payload::Sync(vec![]), features), Transition::Stale);
+
+        state.sent(&Payload::GetSync(vec![]));
+        assert_eq!(state.received(&Payload::Sync(vec![]), features), Transition::Valid);
+    }
+
+    #[test]
+    fn extensions_require_negotiation() {
+        let mut state = ProtocolState::default();
+        let hash = BlockHeaderHash([0u8; 32]);
+
+        for payload in &[
+            node_alias(),
+            Payload::BlockHash(hash.clone()),
+            Payload::GetBlock(hash.clone()),
+            address_change(),
+        ] {
+            assert_eq!(state.received(payload, Features::empty()), Transition::Forbidden);
+        }
+
+        let features = Features::NODE_ALIAS | Features::COMPACT_BLOCKS | Features::ADDRESS_CHANGE;
+        assert_eq!(state.received(&node_alias(), features), Transition::Valid);
+        assert_eq!(state.received(&node_alias(), features), Transition::Unexpected);
+        assert_eq!(state.received(&address_change(), features), Transition::Valid);
+        assert_eq!(state.received(&address_change(), features), Transition::Unexpected);
+        assert_eq!(
+            state.received(&Payload::BlockHash(hash.clone()), features),
+            Transition::Valid
+        );
+        assert_eq!(state.received(&Payload::GetBlock(hash), features), Transition::Valid);
+    }
+
+    #[test]
+    fn only_skippable_unknown_payloads_are_valid() {
+        let mut state = ProtocolState::default();
+
+        let skippable = Payload::Unknown(42, PayloadFlags::empty());
+        assert_eq!(state.received(&skippable, Features::empty()), Transition::Valid);
+
+        let critical = Payload::Unknown(42, PayloadFlags::CRITICAL);
+        assert_eq!(state.received(&critical, Features::empty()), Transition::Forbidden);
+    }
+}
@@ -52,16 +52,34 @@ impl Peer {
                 Ok(x) => x,
             };
 
+            // Management clients only use the RPC channel, so they're served apart from the peers.
+            if peer.quality.is_rpc_client {
+                event_target
+                    .send(PeerEvent {
+                        address: remote_address,
+                        data: PeerEventData::RpcClient,
+                    })
+                    .await
+                    .ok();
+                match node.serve_rpc_client(remote_address, network).await {
+                    Err(e) if !e.is_trivial() => warn!("Disconnected from RPC client {}: {}", remote_address, e),
+                    _ => debug!("RPC client {} disconnected", remote_address),
+                }
+                return;
+            }
+
             peer.set_connected();
+            peer.quality.is_outbound = false;
             metrics::increment_gauge!(CONNECTED, 1.0);
+            let preferred = peer.is_preferred_connection(node.id, false);
             event_target
                 .send(PeerEvent {
                     address: peer.address,
-                    data: PeerEventData::Connected(PeerHandle { sender: sender.clone() }),
+                    data: PeerEventData::Connected(PeerHandle { sender: sender.clone() }, false, preferred),
                 })
                 .await
                 .ok();
-            if let Err(e) = peer.run(node, network, receiver).await {
+            if let Err(e) = peer.run(node.clone(), network, receiver).await {
                 if !e.is_trivial() {
                     peer.fail();
                     error!(
@@ -76,11 +94,12 @@ impl Peer {
                 }
             }
             metrics::decrement_gauge!(CONNECTED, 1.0);
+            node.forget_peer_time(peer.address);
             peer.set_disconnected();
             event_target
                 .send(PeerEvent {
                     address: peer.address,
-                    data: PeerEventData::Disconnect(peer, PeerStatus::Connected),
+                    data: PeerEventData::Disconnect(peer, PeerStatus::Connected, PeerHandle { sender }),
                 })
                 .await
                 .ok();
@@ -15,85 +15,482 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use std::{
+    collections::HashSet,
     net::SocketAddr,
     sync::{
         atomic::{AtomicU32, Ordering},
         Arc,
+        RwLock,
+        RwLockWriteGuard,
     },
 };
 
+use chrono::{DateTime, Utc};
 use futures::Future;
 use mpmc_map::MpmcMap;
 use rand::prelude::IteratorRandom;
 use snarkvm_dpc::Storage;
-use tokio::{net::TcpStream, sync::mpsc};
+use tokio::{
+    net::TcpStream,
+    sync::{mpsc, oneshot},
+};
 
 use snarkos_metrics::{self as metrics, connections::*};
-use snarkos_storage::BlockHeight;
 
-use crate::{NetworkError, Node, Payload, Peer, PeerEvent, PeerEventData, PeerHandle, PeerStatus};
+use crate::{
+    AddressFamily,
+    AddressManager,
+    BlockHeight,
+    Eviction,
+    Insertion,
+    KnownPeer,
+    NetworkError,
+    Node,
+    NodeCallbacks,
+    Payload,
+    Peer,
+    PeerAnnotation,
+    PeerConnected,
+    PeerEvent,
+    PeerEventData,
+    PeerEvictionPolicy,
+    PeerHandle,
+    PeerScoreWeights,
+    PeerStatus,
+};
+
+/// The buckets the disconnected peers are sorted into by how long ago they were last seen: their labels and the
+/// maximum ages of the peers in them, in seconds; the remaining peers are either older or were never seen.
+const PEER_AGE_BUCKETS: [(&str, i64); 4] = [("1h", 3600), ("1d", 86_400), ("1w", 604_800), ("30d", 2_592_000)];
+
+/// The number of disconnected peers last seen within a given period.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerAgeBucket {
+    /// The period the peers were last seen within, e.g. `1d`, or `older` or `never`.
+    pub last_seen_within: &'static str,
+    /// The number of disconnected peers in the bucket.
+    pub peers: u32,
+    /// The number of those peers whose connection history makes them not worth connecting to anymore.
+    pub unroutable: u32,
+}
+
+/// Returns the index of the age bucket of a peer that was last seen at the given time, if ever.
+fn peer_age_bucket(last_seen: Option<DateTime<Utc>>, now: DateTime<Utc>) -> usize {
+    match last_seen {
+        Some(last_seen) => {
+            let age = (now - last_seen).num_seconds();
+            PEER_AGE_BUCKETS
+                .iter()
+                .position(|&(_, max_age)| age <= max_age)
+                .unwrap_or(PEER_AGE_BUCKETS.len())
+        }
+        None => PEER_AGE_BUCKETS.len() + 1,
+    }
+}
+
+/// Returns the median of the given block heights, or `None` if there are none; with an even number of
+/// heights, the lower of the middle two is picked, so that a single peer can't inflate the estimate.
+fn median_block_height(mut heights: Vec<BlockHeight>) -> Option<BlockHeight> {
+    if heights.is_empty() {
+        return None;
+    }
+
+    heights.sort_unstable();
+
+    Some(heights[(heights.len() - 1) / 2])
+}
 
 ///
 /// A data structure for storing the history of all peers with this node server.
 ///
+/// The maps of peers are copy-on-write: reading them takes a snapshot without blocking, while their insertions and
+/// removals are applied one at a time by a single updater task. The state of each connected peer is owned by the
+/// peer's own task, so the per-message updates, e.g. of its last-seen time, don't touch the book at all. The address
+/// manager is only ever modified by its own task, which applies the updates in the order they were sent in, along
+/// with their effects on the disconnected peers; everything else, e.g. the selection of the peers to connect to and
+/// the RPC, only takes its read lock, so neither the peer events nor the gossiped addresses wait for one another.
+///
 #[derive(Debug)]
 pub struct PeerBook {
     disconnected_peers: MpmcMap<SocketAddr, Peer>,
     connected_peers: MpmcMap<SocketAddr, PeerHandle>,
+    /// Peers that are being disconnected from and shouldn't be retained afterwards.
+    removed_peers: MpmcMap<SocketAddr, ()>,
+    /// The connected peers whose connections were initiated by this node; the other ones are inbound.
+    outbound_peers: MpmcMap<SocketAddr, ()>,
     pending_connections: Arc<AtomicU32>,
+    /// The bucketed tables of known addresses, which govern the retention of disconnected peers.
+    address_manager: Arc<RwLock<AddressManager>>,
+    /// The updates applied to the address manager by its own task.
+    address_updates: mpsc::Sender<AddressUpdate>,
     peer_events: mpsc::Sender<PeerEvent>,
 }
 
+/// An update of the address manager; the ones that need to report their outcome carry the sender of its reply.
+#[derive(Debug)]
+pub(crate) enum AddressUpdate {
+    /// The address is added to the disconnected peers if there's room for it; `source` is the peer that shared it,
+    /// if any. Replies with `true` if the address wasn't known yet.
+    Add {
+        address: SocketAddr,
+        is_bootnode: bool,
+        source: Option<SocketAddr>,
+        added: oneshot::Sender<bool>,
+    },
+    /// The node connected to the peer, which proves that its address is reachable.
+    Good(SocketAddr),
+    /// The node is attempting to connect to the address.
+    Attempt(SocketAddr),
+    /// The address is deprioritized until it passes a reachability probe.
+    Unverified(SocketAddr),
+    /// The address was probed, and found to be reachable or not.
+    Probed(SocketAddr, bool),
+    /// The address is removed from the peer book; it's forgotten along with the peer if it was retained already.
+    /// Replies with `true` if either of them was known.
+    Remove(SocketAddr, Option<oneshot::Sender<bool>>),
+    /// The peer disconnected and was added to the disconnected peers; it's forgotten again unless there's room for
+    /// its address.
+    Retain(SocketAddr),
+    /// The disconnected peers are evicted according to the policy; replies with the number of evicted ones.
+    Cull(PeerEvictionPolicy, oneshot::Sender<usize>),
+    /// The annotation of the peer is replaced; replies with the outcome.
+    Annotate(SocketAddr, PeerAnnotation, oneshot::Sender<Result<(), NetworkError>>),
+    /// The known addresses are replaced with the restored ones, which are added to the disconnected peers; the
+    /// addresses and annotations learned in the meantime are retained. Replies once it's done.
+    Restore(Box<AddressManager>, Vec<SocketAddr>, oneshot::Sender<()>),
+}
+
+/// Applies the updates of the address manager in the order they were sent in, along with their effects on the
+/// disconnected peers; it's terminated once the `PeerBook` and its peer event handler are dropped.
+struct AddressUpdater {
+    address_manager: Arc<RwLock<AddressManager>>,
+    disconnected_peers: MpmcMap<SocketAddr, Peer>,
+    connected_peers: MpmcMap<SocketAddr, PeerHandle>,
+}
+
+impl AddressUpdater {
+    async fn run(self, mut receiver: mpsc::Receiver<AddressUpdate>) {
+        while let Some(update) = receiver.recv().await {
+            self.apply(update).await;
+        }
+    }
+
+    /// The write lock is never held across an `.await`, so it's only ever taken for the duration of a single update
+    /// of the tables.
+    fn write(&self) -> RwLockWriteGuard<'_, AddressManager> {
+        self.address_manager.write().unwrap()
+    }
+
+    async fn apply(&self, update: AddressUpdate) {
+        match update {
+            AddressUpdate::Add {
+                address,
+                is_bootnode,
+                source,
+                added,
+            } => {
+                let is_new = self.add(address, is_bootnode, source).await;
+                added.send(is_new).ok();
+            }
+            AddressUpdate::Good(address) => {
+                let dropped = self.write().mark_good(address);
+                if let Some(dropped) = dropped {
+                    forget_evicted_peer(&self.disconnected_peers, dropped, Eviction::Excess).await;
+                }
+            }
+            AddressUpdate::Attempt(address) => self.write().mark_attempt(address),
+            AddressUpdate::Unverified(address) => self.write().mark_unverified(address),
+            AddressUpdate::Probed(address, is_reachable) => self.write().mark_probed(address, is_reachable),
+            AddressUpdate::Remove(address, removed) => {
+                let was_known = self.write().remove(address);
+                let was_disconnected = self.disconnected_peers.remove(address).await.is_some();
+                if was_disconnected {
+                    metrics::decrement_gauge!(DISCONNECTED, 1.0);
+                }
+                if let Some(removed) = removed {
+                    removed.send(was_known || was_disconnected).ok();
+                }
+            }
+            AddressUpdate::Retain(address) => self.retain(address).await,
+            AddressUpdate::Cull(policy, evicted) => {
+                let evicted_count = self.cull(&policy).await;
+                evicted.send(evicted_count).ok();
+            }
+            AddressUpdate::Annotate(address, annotation, result) => {
+                let outcome = self.write().annotate(address, annotation);
+                result.send(outcome).ok();
+            }
+            AddressUpdate::Restore(restored, bootnodes, done) => {
+                self.restore(*restored, &bootnodes).await;
+                done.send(()).ok();
+            }
+        }
+    }
+
+    async fn add(&self, address: SocketAddr, is_bootnode: bool, source: Option<SocketAddr>) -> bool {
+        if self.connected_peers.contains_key(&address) || self.disconnected_peers.contains_key(&address) {
+            return false;
+        }
+
+        let insertion = self.write().add(address, source);
+        match insertion {
+            Insertion::Inserted(evicted) => {
+                if let Some(evicted) = evicted {
+                    forget_evicted_peer(&self.disconnected_peers, evicted, Eviction::Excess).await;
+                }
+            }
+            Insertion::Known => {}
+            Insertion::Full => {
+                trace!("Not adding {} to the peer book; its buckets are full", address);
+                return false;
+            }
+        }
+
+        // Add the given address to the map of disconnected peers.
+        self.disconnected_peers
+            .insert(address, Peer::new(address, is_bootnode))
+            .await;
+
+        metrics::increment_gauge!(DISCONNECTED, 1.0);
+
+        debug!("Added {} to the peer book", address);
+
+        true
+    }
+
+    async fn retain(&self, address: SocketAddr) {
+        // The peer may have reconnected or been removed in the meantime.
+        if self.connected_peers.contains_key(&address) || !self.disconnected_peers.contains_key(&address) {
+            return;
+        }
+
+        let insertion = self.write().add(address, Some(address));
+        match insertion {
+            Insertion::Known | Insertion::Inserted(None) => {}
+            Insertion::Inserted(Some(evicted)) => {
+                forget_evicted_peer(&self.disconnected_peers, evicted, Eviction::Excess).await;
+            }
+            // There's no room for the address; the peer isn't retained.
+            Insertion::Full => {
+                if self.disconnected_peers.remove(address).await.is_some() {
+                    metrics::decrement_gauge!(DISCONNECTED, 1.0);
+                }
+            }
+        }
+    }
+
+    async fn cull(&self, policy: &PeerEvictionPolicy) -> usize {
+        let evicted = {
+            let disconnected_peers = self.disconnected_peers.inner();
+            let is_evictable = |address| {
+                disconnected_peers
+                    .get(&address)
+                    .map(|peer| !peer.is_bootnode)
+                    .unwrap_or(false)
+            };
+            self.write().cull(policy, is_evictable, Utc::now())
+        };
+
+        for &(address, eviction) in &evicted {
+            forget_evicted_peer(&self.disconnected_peers, address, eviction).await;
+        }
+
+        evicted.len()
+    }
+
+    async fn restore(&self, mut address_manager: AddressManager, bootnodes: &[SocketAddr]) {
+        let addresses = address_manager.addresses().collect::<Vec<_>>();
+        {
+            // Retain the addresses and annotations that were learned before the restoration.
+            let mut current = self.write();
+            for address in current.addresses().collect::<Vec<_>>() {
+                address_manager.add(address, None);
+            }
+            for (address, annotation) in current.annotations() {
+                if address_manager.annotation(address).is_none() {
+                    let _ = address_manager.annotate(address, annotation.clone());
+                }
+            }
+            *current = address_manager;
+        }
+
+        for address in addresses {
+            if self.connected_peers.contains_key(&address) || self.disconnected_peers.contains_key(&address) {
+                continue;
+            }
+            self.disconnected_peers
+                .insert(address, Peer::new(address, bootnodes.contains(&address)))
+                .await;
+            metrics::increment_gauge!(DISCONNECTED, 1.0);
+        }
+    }
+}
+
+/// Adds a peer that disconnected to the disconnected peers right away, so that it's never missing from the peer book,
+/// and queues the insertion of its address, which forgets the peer again if there's no room for it.
+async fn retain_disconnected_peer(
+    disconnected_peers: &MpmcMap<SocketAddr, Peer>,
+    address_updates: &mpsc::Sender<AddressUpdate>,
+    peer: Peer,
+) {
+    let address = peer.address;
+    if !disconnected_peers.contains_key(&address) {
+        metrics::increment_gauge!(DISCONNECTED, 1.0);
+    }
+    disconnected_peers.insert(address, peer).await;
+    address_updates.send(AddressUpdate::Retain(address)).await.ok();
+}
+
+/// Forgets a disconnected peer whose address was evicted from the address manager.
+async fn forget_evicted_peer(disconnected_peers: &MpmcMap<SocketAddr, Peer>, address: SocketAddr, eviction: Eviction) {
+    if disconnected_peers.remove(address).await.is_some() {
+        metrics::decrement_gauge!(DISCONNECTED, 1.0);
+        match eviction {
+            Eviction::Excess => metrics::increment_counter!(EVICTED_EXCESS),
+            Eviction::Expired => metrics::increment_counter!(EVICTED_EXPIRED),
+            Eviction::Failing => metrics::increment_counter!(EVICTED_FAILING),
+        }
+        trace!("Evicted {} from the peer book ({:?})", address, eviction);
+    }
+}
+
 // to avoid circular reference to peer_events
 struct PeerBookRef {
     disconnected_peers: MpmcMap<SocketAddr, Peer>,
     connected_peers: MpmcMap<SocketAddr, PeerHandle>,
+    removed_peers: MpmcMap<SocketAddr, ()>,
+    outbound_peers: MpmcMap<SocketAddr, ()>,
     pending_connections: Arc<AtomicU32>,
+    address_updates: mpsc::Sender<AddressUpdate>,
+    callbacks: Arc<NodeCallbacks>,
 }
 
 impl PeerBookRef {
+    /// Exports the numbers of the inbound and outbound connections.
+    fn update_direction_gauges(&self) {
+        let outbound = self.outbound_peers.len();
+        let inbound = self.connected_peers.len().saturating_sub(outbound);
+        metrics::gauge!(INBOUND, inbound as f64);
+        metrics::gauge!(OUTBOUND, outbound as f64);
+    }
+
+    /// Registers a newly established connection with a peer. If the peer is already connected in the opposite
+    /// direction, e.g. because both sides dialed each other simultaneously, only the preferred connection is kept, so
+    /// that both sides keep the same one; otherwise the previous connection is considered stale and replaced.
+    async fn set_connected(&self, address: SocketAddr, handle: PeerHandle, outbound: bool, preferred: bool) {
+        if let Some(existing) = self.connected_peers.get(&address) {
+            let is_opposite = self.outbound_peers.contains_key(&address) != outbound;
+            if is_opposite && !preferred {
+                debug!(
+                    "Dropping a duplicate connection with {} in favor of the existing one",
+                    address
+                );
+                handle.disconnect().await;
+                return;
+            }
+            if is_opposite {
+                debug!(
+                    "Dropping a duplicate connection with {} in favor of the new one",
+                    address
+                );
+            } else {
+                warn!("disconnecting stale/duplicate peer: {}", address);
+            }
+            existing.disconnect().await;
+        }
+
+        // Only outbound connections prove that the address is reachable.
+        if outbound {
+            self.address_updates.send(AddressUpdate::Good(address)).await.ok();
+            self.outbound_peers.insert(address, ()).await;
+        } else {
+            self.outbound_peers.remove(address).await;
+        }
+        self.connected_peers.insert(address, handle).await;
+        self.update_direction_gauges();
+
+        self.callbacks.peer_connected(&PeerConnected { address, outbound });
+    }
+
     // gets terminated when sender is dropped from PeerBook
     async fn handle_peer_events(self, mut receiver: mpsc::Receiver<PeerEvent>) {
         while let Some(event) = receiver.recv().await {
             match event.data {
-                PeerEventData::Connected(handle) => {
+                PeerEventData::Connected(handle, outbound, preferred) => {
                     self.pending_connections.fetch_sub(1, Ordering::SeqCst);
-                    if let Some(old_peer) = self.connected_peers.insert(event.address, handle).await {
-                        warn!("disconnecting stale/duplicate peer: {}", event.address);
-                        old_peer.disconnect().await;
-                    }
+                    self.set_connected(event.address, handle, outbound, preferred).await;
                 }
-                PeerEventData::Disconnect(peer, status) => {
-                    self.connected_peers.remove(peer.address).await;
-                    self.disconnected_peers.insert(peer.address, peer).await;
+                PeerEventData::Disconnect(peer, status, handle) => {
                     if status == PeerStatus::Connecting {
                         self.pending_connections.fetch_sub(1, Ordering::SeqCst);
                     }
-                    metrics::increment_gauge!(DISCONNECTED, 1.0);
+                    // A dropped duplicate connection leaves the peer's entries to the one that was kept.
+                    if let Some(current) = self.connected_peers.get(&peer.address) {
+                        if !current.is_same_connection(&handle) {
+                            continue;
+                        }
+                    }
+                    self.connected_peers.remove(peer.address).await;
+                    self.outbound_peers.remove(peer.address).await;
+                    self.update_direction_gauges();
+                    if self.removed_peers.remove(peer.address).await.is_some() {
+                        self.address_updates
+                            .send(AddressUpdate::Remove(peer.address, None))
+                            .await
+                            .ok();
+                        continue;
+                    }
+                    // A peer that doesn't listen can't be connected to, so it isn't retained.
+                    if peer.quality.no_listen {
+                        continue;
+                    }
+
+                    retain_disconnected_peer(&self.disconnected_peers, &self.address_updates, peer).await;
                 }
                 PeerEventData::FailHandshake => {
                     self.pending_connections.fetch_sub(1, Ordering::SeqCst);
                     metrics::increment_gauge!(DISCONNECTED, 1.0);
                 }
+                PeerEventData::RpcClient => {
+                    self.pending_connections.fetch_sub(1, Ordering::SeqCst);
+                }
             }
         }
     }
 }
 
 impl PeerBook {
-    pub fn spawn() -> Self {
+    /// Spawns the task processing the peer events; the established connections are reported to the given callbacks.
+    pub fn spawn(callbacks: Arc<NodeCallbacks>) -> Self {
         let (sender, receiver) = mpsc::channel(256);
+        let (address_updates, address_update_receiver) = mpsc::channel(256);
         let peers = PeerBook {
             disconnected_peers: Default::default(),
             connected_peers: Default::default(),
+            removed_peers: Default::default(),
+            outbound_peers: Default::default(),
             pending_connections: Default::default(),
+            address_manager: Default::default(),
+            address_updates,
             peer_events: sender,
         };
+        tokio::spawn(
+            AddressUpdater {
+                address_manager: peers.address_manager.clone(),
+                disconnected_peers: peers.disconnected_peers.clone(),
+                connected_peers: peers.connected_peers.clone(),
+            }
+            .run(address_update_receiver),
+        );
         tokio::spawn(
             PeerBookRef {
                 disconnected_peers: peers.disconnected_peers.clone(),
                 connected_peers: peers.connected_peers.clone(),
+                removed_peers: peers.removed_peers.clone(),
+                outbound_peers: peers.outbound_peers.clone(),
                 pending_connections: peers.pending_connections.clone(),
+                address_updates: peers.address_updates.clone(),
+                callbacks,
             }
             .handle_peer_events(receiver),
         );
@@ -113,10 +510,35 @@ impl PeerBook {
         self.connected_peers.inner().keys().copied().collect()
     }
 
+    /// Returns the connected peers in the bucket of the given address family.
+    pub fn connected_peers_in(&self, family: AddressFamily) -> Vec<SocketAddr> {
+        self.connected_peers
+            .inner()
+            .keys()
+            .filter(|&&addr| AddressFamily::of(addr) == family)
+            .copied()
+            .collect()
+    }
+
     pub fn get_active_peer_count(&self) -> u32 {
         self.connected_peers.len() as u32 + self.pending_connections()
     }
 
+    /// Returns `true` if the connection with the given peer was initiated by this node.
+    pub fn is_outbound(&self, address: SocketAddr) -> bool {
+        self.outbound_peers.contains_key(&address)
+    }
+
+    /// Returns the number of connected peers whose connections were initiated by this node.
+    pub fn get_outbound_peer_count(&self) -> u32 {
+        self.outbound_peers.len() as u32
+    }
+
+    /// Returns the number of connected peers that connected to this node.
+    pub fn get_inbound_peer_count(&self) -> u32 {
+        (self.connected_peers.len() as u32).saturating_sub(self.get_outbound_peer_count())
+    }
+
     pub fn get_disconnected_peer_count(&self) -> u32 {
         self.disconnected_peers.len() as u32
     }
@@ -133,10 +555,28 @@ impl PeerBook {
         self.disconnected_peers.get(&address)
     }
 
+    /// Returns the given peer, whether it's connected or not.
+    pub async fn get_peer(&self, address: SocketAddr) -> Option<Peer> {
+        match self.get_active_peer(address).await {
+            Some(peer) => Some(peer),
+            None => self.get_disconnected_peer(address),
+        }
+    }
+
     pub fn disconnected_peers(&self) -> Vec<SocketAddr> {
         self.disconnected_peers.inner().keys().copied().collect()
     }
 
+    /// Returns the disconnected peers in the bucket of the given address family.
+    pub fn disconnected_peers_in(&self, family: AddressFamily) -> Vec<SocketAddr> {
+        self.disconnected_peers
+            .inner()
+            .keys()
+            .filter(|&&addr| AddressFamily::of(addr) == family)
+            .copied()
+            .collect()
+    }
+
     async fn take_disconnected_peer(&self, address: SocketAddr) -> Option<Peer> {
         metrics::decrement_gauge!(DISCONNECTED, 1.0);
         self.disconnected_peers.remove(address).await
@@ -176,6 +616,7 @@ impl PeerBook {
             } else {
                 Peer::new(address, node.config.bootnodes().contains(&address))
             };
+            self.address_updates.send(AddressUpdate::Attempt(address)).await.ok();
             self.pending_connections.fetch_add(1, Ordering::SeqCst);
             peer.connect(node, self.peer_events.clone());
             Ok(None)
@@ -203,9 +644,9 @@ impl PeerBook {
         futures::future::join_all(futures).await.into_iter().flatten().collect()
     }
 
-    pub async fn judge_peers(&self) {
+    pub async fn judge_peers(&self, weights: PeerScoreWeights) {
         self.for_each_peer(move |peer| async move {
-            peer.judge_bad().await;
+            peer.judge_bad(weights).await;
         })
         .await;
     }
@@ -230,21 +671,187 @@ impl PeerBook {
     }
 
     ///
-    /// Adds the given address to the disconnected peers in this `PeerBook`.
+    /// Adds the given address to the disconnected peers in this `PeerBook`, provided that the address
+    /// manager has room for it; `source` is the peer that shared the address, if any. Returns `true` if
+    /// the address wasn't known yet.
     ///
-    pub async fn add_peer(&self, address: SocketAddr, is_bootnode: bool) {
-        if self.connected_peers.contains_key(&address) || self.disconnected_peers.contains_key(&address) {
-            return;
+    pub async fn add_peer(&self, address: SocketAddr, is_bootnode: bool, source: Option<SocketAddr>) -> bool {
+        let (added, is_new) = oneshot::channel();
+        self.address_updates
+            .send(AddressUpdate::Add {
+                address,
+                is_bootnode,
+                source,
+                added,
+            })
+            .await
+            .ok();
+
+        is_new.await.unwrap_or(false)
+    }
+
+    ///
+    /// Forgets the disconnected peers that are expired, failing, or in excess of the maximum number of them
+    /// according to the given policy; the bootnodes are always retained. Returns the number of evicted peers.
+    ///
+    pub async fn evict_dead_peers(&self, policy: &PeerEvictionPolicy) -> usize {
+        let (sender, evicted) = oneshot::channel();
+        self.address_updates
+            .send(AddressUpdate::Cull(*policy, sender))
+            .await
+            .ok();
+
+        evicted.await.unwrap_or(0)
+    }
+
+    /// Deprioritizes the given known address until it passes a reachability probe.
+    pub async fn mark_unverified(&self, address: SocketAddr) {
+        self.address_updates.send(AddressUpdate::Unverified(address)).await.ok();
+    }
+
+    /// Returns the sender of the address manager's updates, so that the outcomes of reachability probes can be
+    /// registered with it.
+    pub(crate) fn address_updates(&self) -> mpsc::Sender<AddressUpdate> {
+        self.address_updates.clone()
+    }
+
+    ///
+    /// Removes the given address from this `PeerBook`, disconnecting from it first if needed.
+    /// Returns `true` if the peer was known.
+    ///
+    pub async fn remove_peer(&self, address: SocketAddr) -> bool {
+        if let Some(handle) = self.get_peer_handle(address) {
+            // The peer event handler will drop the peer instead of retaining it as disconnected.
+            self.removed_peers.insert(address, ()).await;
+            if handle.disconnect().await {
+                debug!("Removed {} from the peer book", address);
+                return true;
+            }
+            self.removed_peers.remove(address).await;
         }
 
-        // Add the given address to the map of disconnected peers.
-        self.disconnected_peers
-            .insert(address, Peer::new(address, is_bootnode))
-            .await;
+        // It's applied after any pending retention of the peer, so that the peer isn't retained afterwards.
+        let (removed, was_known) = oneshot::channel();
+        self.address_updates
+            .send(AddressUpdate::Remove(address, Some(removed)))
+            .await
+            .ok();
 
-        metrics::increment_gauge!(DISCONNECTED, 1.0);
+        if was_known.await.unwrap_or(false) {
+            debug!("Removed {} from the peer book", address);
+            true
+        } else {
+            false
+        }
+    }
 
-        debug!("Added {} to the peer book", address);
+    ///
+    /// Selects up to `count` disconnected peers to connect to out of the ones deemed eligible, preferring
+    /// the network groups that aren't among the connected peers yet.
+    ///
+    pub fn select_peers<F: Fn(SocketAddr) -> bool>(&self, count: usize, is_eligible: F) -> Vec<SocketAddr> {
+        let connected_peers = self.connected_peers();
+        self.address_manager.read().unwrap().select(
+            count,
+            |address| self.disconnected_peers.contains_key(&address) && is_eligible(address),
+            &connected_peers,
+        )
+    }
+
+    /// Serializes the known addresses, so that they can be persisted.
+    pub fn serialize_addresses(&self) -> Result<Vec<u8>, NetworkError> {
+        self.address_manager.read().unwrap().serialize()
+    }
+
+    /// Returns the known addresses along with their connection history.
+    pub fn known_peers(&self) -> Vec<KnownPeer> {
+        self.address_manager.read().unwrap().known_peers()
+    }
+
+    /// Returns the annotation the node's operator attached to the given peer, if any.
+    pub fn peer_annotation(&self, address: SocketAddr) -> Option<PeerAnnotation> {
+        self.address_manager.read().unwrap().annotation(address).cloned()
+    }
+
+    /// Replaces the annotation of the given peer; an empty one removes it.
+    pub async fn annotate_peer(&self, address: SocketAddr, annotation: PeerAnnotation) -> Result<(), NetworkError> {
+        let (sender, result) = oneshot::channel();
+        self.address_updates
+            .send(AddressUpdate::Annotate(address, annotation, sender))
+            .await
+            .ok();
+
+        result.await.unwrap_or_else(|_| {
+            Err(NetworkError::ServiceFailed(
+                "peer book",
+                "the address manager isn't updated anymore".into(),
+            ))
+        })
+    }
+
+    /// Sorts the disconnected peers into buckets by how long ago they were last connected to or seen, from the most
+    /// recent to the ones that were never seen, counting the unroutable ones in each of them.
+    pub fn disconnected_peer_ages(&self) -> Vec<PeerAgeBucket> {
+        let now = Utc::now();
+        let unroutable = self
+            .known_peers()
+            .into_iter()
+            .filter(|peer| peer.is_terrible(now))
+            .map(|peer| peer.address)
+            .collect::<HashSet<_>>();
+
+        let labels = PEER_AGE_BUCKETS
+            .iter()
+            .map(|&(label, _)| label)
+            .chain(vec!["older", "never"]);
+        let mut buckets = labels
+            .map(|label| PeerAgeBucket {
+                last_seen_within: label,
+                peers: 0,
+                unroutable: 0,
+            })
+            .collect::<Vec<_>>();
+
+        for (address, peer) in self.disconnected_peers.inner().iter() {
+            let last_seen = peer.quality.last_seen.max(peer.quality.last_connected);
+            let bucket = &mut buckets[peer_age_bucket(last_seen, now)];
+            bucket.peers += 1;
+            if unroutable.contains(address) {
+                bucket.unroutable += 1;
+            }
+        }
+
+        buckets
+    }
+
+    /// Adds the given peers to the disconnected peers, skipping the ones with a terrible connection history;
+    /// returns the number of added peers.
+    pub async fn import_peers(&self, peers: &[KnownPeer], bootnodes: &[SocketAddr]) -> usize {
+        let now = chrono::Utc::now();
+        let mut imported = 0;
+        for peer in peers.iter().filter(|peer| !peer.is_terrible(now)) {
+            if self.is_connected(peer.address) || self.is_disconnected(peer.address) {
+                continue;
+            }
+            self.add_peer(peer.address, bootnodes.contains(&peer.address), None)
+                .await;
+            if self.is_disconnected(peer.address) {
+                imported += 1;
+            }
+        }
+
+        imported
+    }
+
+    /// Restores the known addresses from their serialized form, adding them to the disconnected peers.
+    pub async fn restore_addresses(&self, bytes: &[u8], bootnodes: &[SocketAddr]) -> Result<(), NetworkError> {
+        let address_manager = AddressManager::deserialize(bytes)?;
+        let (sender, restored) = oneshot::channel();
+        let update = AddressUpdate::Restore(Box::new(address_manager), bootnodes.to_vec(), sender);
+        self.address_updates.send(update).await.ok();
+        restored.await.ok();
+
+        Ok(())
     }
 
     ///
@@ -254,6 +861,8 @@ impl PeerBook {
         self.connected_peers_snapshot()
             .await
             .into_iter()
+            // Watcher nodes don't serve their memory pool.
+            .filter(|x| !x.quality.is_watcher)
             .max_by(|a, b| a.quality.last_seen.cmp(&b.quality.last_seen))
             .map(|x| x.address)
     }
@@ -271,6 +880,23 @@ impl PeerBook {
         Some((peers.into_iter().choose(&mut rand::thread_rng())?, count_total_higher))
     }
 
+    ///
+    /// Returns an estimate of the height of the network's chain, i.e. the median of the block heights
+    /// last reported by the connected peers, or `None` if there are no peers to ask.
+    ///
+    pub async fn network_height(&self) -> Option<BlockHeight> {
+        let heights = self
+            .connected_peers_snapshot()
+            .await
+            .into_iter()
+            // Management clients don't follow the chain.
+            .filter(|x| !x.quality.is_rpc_client)
+            .map(|x| x.quality.block_height)
+            .collect();
+
+        median_block_height(heights)
+    }
+
     /// Cancels any expected sync block counts from all peers.
     pub async fn cancel_any_unfinished_syncing(&self) {
         self.for_each_peer(move |peer| async move {
@@ -279,3 +905,54 @@ impl PeerBook {
         .await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peers_are_bucketed_by_age() {
+        let now = Utc::now();
+        let ago = |secs| Some(now - chrono::Duration::seconds(secs));
+
+        assert_eq!(peer_age_bucket(ago(60), now), 0);
+        assert_eq!(peer_age_bucket(ago(3600), now), 0);
+        assert_eq!(peer_age_bucket(ago(3601), now), 1);
+        assert_eq!(peer_age_bucket(ago(3 * 86_400), now), 2);
+        assert_eq!(peer_age_bucket(ago(10 * 86_400), now), 3);
+        assert_eq!(peer_age_bucket(ago(100 * 86_400), now), 4);
+        assert_eq!(peer_age_bucket(None, now), 5);
+    }
+
+    #[test]
+    fn network_height_is_the_median() {
+        assert_eq!(median_block_height(vec![]), None);
+        assert_eq!(median_block_height(vec![7]), Some(7));
+        assert_eq!(median_block_height(vec![100, 3, 5]), Some(5));
+        // a single outlier doesn't move the estimate
+        assert_eq!(median_block_height(vec![10, 10, 11, u32::MAX]), Some(10));
+        assert_eq!(median_block_height(vec![12, 10]), Some(10));
+    }
+
+    #[tokio::test]
+    async fn peers_removed_while_being_retained_are_forgotten() {
+        let peer_book = PeerBook::spawn(Default::default());
+        let address: SocketAddr = "127.0.0.1:4131".parse().unwrap();
+
+        // the peer disconnected; it's visible right away, while the insertion of its address is still queued
+        retain_disconnected_peer(
+            &peer_book.disconnected_peers,
+            &peer_book.address_updates,
+            Peer::new(address, false),
+        )
+        .await;
+        assert!(peer_book.is_disconnected(address));
+
+        assert!(peer_book.remove_peer(address).await);
+
+        // the removal is applied after the retention, so the peer isn't retained afterwards
+        assert!(!peer_book.is_disconnected(address));
+        assert!(peer_book.known_peers().iter().all(|peer| peer.address != address));
+        assert!(!peer_book.remove_peer(address).await);
+    }
+}
@@ -14,24 +14,56 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use std::{cmp, net::SocketAddr, time::Duration};
+use std::{
+    net::SocketAddr,
+    path::Path,
+    time::{Duration, Instant},
+};
 
 use rand::seq::IteratorRandom;
 use snarkvm_dpc::Storage;
 use tokio::task;
 
-use snarkos_metrics::{self as metrics, connections::*};
-
-use crate::{message::*, NetworkError, Node};
+use snarkos_metrics::{self as metrics, connections::*, latency};
+
+use crate::{
+    is_local_address,
+    message::*,
+    normalize_address,
+    read_peer_list,
+    write_peer_list,
+    AddressFamily,
+    NetworkError,
+    Node,
+    PeerAnnotation,
+    RttPercentiles,
+    WebhookEvent,
+};
 
 impl<S: Storage + core::marker::Sync + Send> Node<S> {
     /// Obtain a list of addresses of connected peers for this node.
     pub(crate) fn connected_peers(&self) -> Vec<SocketAddr> {
         self.peer_book.connected_peers()
     }
+
+    /// Returns the disconnected peers from the buckets of the address families this node can connect to.
+    pub(crate) fn reachable_disconnected_peers(&self) -> Vec<SocketAddr> {
+        [AddressFamily::Ipv4, AddressFamily::Ipv6]
+            .iter()
+            .filter(|&&family| self.config.supports_address_family(family))
+            .flat_map(|&family| self.peer_book.disconnected_peers_in(family))
+            .collect()
+    }
 }
 
 impl<S: Storage + Send + Sync + 'static> Node<S> {
+    /// Returns the percentiles of the recent round-trip times of all the connected peers combined.
+    pub async fn rtt_percentiles(&self) -> Option<RttPercentiles> {
+        let peers = self.peer_book.connected_peers_snapshot().await;
+
+        RttPercentiles::from_samples(peers.iter().flat_map(|peer| peer.quality.rtt_samples.iter().copied()))
+    }
+
     ///
     /// Broadcasts updates with connected peers and maintains a permitted number of connected peers.
     ///
@@ -45,14 +77,34 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
             if active_peer_count == 1 { "" } else { "s" }
         );
 
-        // Drop peers whose RTT is too high or have too many failures.
-        self.peer_book.judge_peers().await;
+        // Drop peers whose quality score is too low.
+        self.peer_book.judge_peers(self.config.peer_score_weights).await;
+
+        // Export the latency of the connected peers, as measured during the previous rounds.
+        if let Some(rtt) = self.rtt_percentiles().await {
+            metrics::gauge!(latency::RTT_P50, rtt.p50_ms as f64);
+            metrics::gauge!(latency::RTT_P95, rtt.p95_ms as f64);
+            metrics::gauge!(latency::RTT_P99, rtt.p99_ms as f64);
+        }
+
+        // Drop peers banned in the meantime, e.g. via a reloaded config, and forget the expired bans.
+        self.prune_bans();
+        for address in self.connected_peers() {
+            if self.is_banned(address.ip()) {
+                self.disconnect_from_peer(address).await;
+            }
+        }
         // give us 100ms to close some negatively judge_badd connections (probably less needed, but we have time)
         tokio::time::sleep(Duration::from_millis(100)).await;
 
         // Attempt to connect to the default bootnodes of the network.
         self.connect_to_bootnodes().await;
 
+        // Without any peers, the bootnodes may well be down; fall back on the recommended peers.
+        if active_peer_count == 0 && !self.config.is_bootnode() {
+            self.add_recommended_peers().await;
+        }
+
         // Attempt to connect to each disconnected peer saved in the peer book.
         if !self.config.is_bootnode() {
             self.connect_to_disconnected_peers().await;
@@ -75,8 +127,10 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
 
             // Bootnodes will disconnect from random peers...
             if !self.config.is_bootnode() {
-                // ...while regular peers from the most recently connected.
-                current_peers.sort_unstable_by_key(|peer| peer.quality.last_connected);
+                // ...while regular peers from the most recently connected, starting with the inbound ones.
+                current_peers.sort_unstable_by_key(|peer| {
+                    (!self.peer_book.is_outbound(peer.address), peer.quality.last_connected)
+                });
             }
 
             for _ in 0..number_to_disconnect {
@@ -86,13 +140,249 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
             }
         }
 
-        if new_active_peer_count != 0 {
-            // Send a `Ping` to every connected peer.
-            self.broadcast_pings().await;
+        // Report the conditions that may need the attention of the node's operator.
+        self.check_webhook_conditions(new_active_peer_count).await;
+
+        // Stretch the interval until the next round if the connected peers haven't changed since the previous one.
+        let min_peers = self.config.minimum_number_of_connected_peers() as usize;
+        let connected_peers = self.connected_peers();
+        self.peer_sync_schedule
+            .conclude_round(&connected_peers, connected_peers.len() >= min_peers);
+
+        // Forget the peers that are unlikely to ever be connected to, so that they don't accumulate.
+        let evicted = self.peer_book.evict_dead_peers(&self.config.peer_eviction).await;
+        if evicted != 0 {
+            debug!("Evicted {} dead peers from the peer book", evicted);
+        }
+
+        // Persist the known addresses, so that they survive a restart.
+        self.save_peer_book();
+    }
+
+    ///
+    /// Maps the network by cycling through short-lived connections: the peers connected in the previous
+    /// round are asked for their peers and disconnected from, making room for a new random selection.
+    ///
+    #[cfg(feature = "crawler")]
+    pub(crate) async fn crawl_peers(&self) {
+        let known_network = match self.known_network() {
+            Some(known_network) => known_network,
+            None => return,
+        };
+
+        // Forget the connections that haven't been reported in a while.
+        known_network.prune(Duration::from_secs(crate::KNOWN_NETWORK_CONNECTION_TTL_SECS.into()));
+
+        let crawled_peers = self.connected_peers();
+        if !crawled_peers.is_empty() {
+            trace!("Crawling {} peers", crawled_peers.len());
+
+            let crawl_start = chrono::Utc::now();
+            self.peer_book.broadcast(Payload::GetPeers).await;
+
+            // Give the peers some time to respond before dropping the connections.
+            tokio::time::sleep(Duration::from_secs(crate::CRAWLER_RESPONSE_TIMEOUT_SECS.into())).await;
+
+            for remote_address in &crawled_peers {
+                known_network.check_responded(*remote_address, crawl_start);
+                self.disconnect_from_peer(*remote_address).await;
+            }
+        }
+
+        // Local address must be known by now.
+        let own_address = self.local_address().unwrap();
+
+        // The bootnodes are always candidates, as they are the starting point of the crawl.
+        let mut candidates = self.reachable_disconnected_peers();
+        candidates.extend(
+            self.config
+                .bootnodes()
+                .iter()
+                .filter(|&&bootnode| self.config.supports_address_family(AddressFamily::of(bootnode))),
+        );
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        let count = self.config.maximum_number_of_connected_peers() as usize;
+        let next_peers = candidates
+            .into_iter()
+            .filter(|peer| *peer != own_address && !crawled_peers.contains(peer) && !self.is_banned(peer.ip()))
+            .choose_multiple(&mut rand::thread_rng(), count);
+
+        trace!("Connecting to {} peers to crawl", next_peers.len());
+
+        self.export_recommended_peers();
+        self.save_known_network();
+
+        for remote_address in next_peers {
+            let node = self.clone();
+            task::spawn(async move {
+                match node.initiate_connection(remote_address).await {
+                    Err(NetworkError::PeerAlreadyConnecting) | Err(NetworkError::PeerAlreadyConnected) => {
+                        // no issue here, already connecting
+                    }
+                    Err(e @ NetworkError::TooManyConnections) | Err(e @ NetworkError::SelfConnectAttempt) => {
+                        debug!("Couldn't connect to peer {}: {}", remote_address, e);
+                        // the connection hasn't been established, no need to disconnect
+                    }
+                    Err(e) => {
+                        debug!("Couldn't connect to peer {}: {}", remote_address, e);
+                        node.disconnect_from_peer(remote_address).await;
+                    }
+                    Ok(_) => {}
+                }
+            });
+        }
+    }
+
+    /// Fetches the configured list of recommended peers, unless it was fetched recently, and adds the peers to the
+    /// peer book if the list is signed by one of the trusted keys.
+    async fn add_recommended_peers(&self) {
+        let config = match &self.config.recommended_peers {
+            Some(config) => config,
+            None => return,
+        };
+        if !self.recommended_peers_due() {
+            return;
+        }
+
+        let list = match config.source.fetch().await {
+            Ok(list) => list,
+            Err(e) => {
+                warn!("Couldn't fetch the recommended peers from {}: {}", config.source, e);
+                return;
+            }
+        };
+        if let Err(e) = list.verify(&config.trusted_keys) {
+            warn!("Rejected the recommended peers from {}: {}", config.source, e);
+            return;
+        }
+
+        info!("Adding {} recommended peers from {}", list.peers.len(), config.source);
+        let own_address = self.local_address();
+        for peer in list.peers {
+            if Some(peer) != own_address && !self.is_banned(peer.ip()) {
+                let is_bootnode = self.config.bootnodes().contains(&peer);
+                self.peer_book.add_peer(peer, is_bootnode, None).await;
+            }
+        }
+    }
+
+    /// Writes the signed list of the peers the crawler has found reliable to the configured path, unless it was
+    /// written recently.
+    #[cfg(feature = "crawler")]
+    fn export_recommended_peers(&self) {
+        let (path, identity, known_network) = match (
+            &self.config.recommended_peers_export,
+            &self.config.identity,
+            self.known_network(),
+        ) {
+            (Some(path), Some(identity), Some(known_network)) => (path, identity, known_network),
+            _ => return,
+        };
+        if !self.recommended_peers_due() {
+            return;
+        }
+
+        match crate::RecommendedPeers::from_known_network(known_network, identity).and_then(|list| {
+            list.write_to(path)?;
+            Ok(list)
+        }) {
+            Ok(list) => debug!("Exported {} recommended peers to {}", list.peers.len(), path.display()),
+            Err(e) => warn!("Couldn't export the recommended peers: {}", e),
+        }
+    }
+
+    /// Restores the crawled network persisted in storage, and resumes the schedule of its snapshots.
+    #[cfg(feature = "crawler")]
+    pub(crate) fn load_known_network(&self) {
+        use chrono::TimeZone;
+
+        let (known_network, storage) = match (self.known_network(), self.crawler_storage.get()) {
+            (Some(known_network), Some(storage)) => (known_network, storage),
+            _ => return,
+        };
+
+        match storage.get_known_network() {
+            Ok(Some(bytes)) => match known_network.restore(&bytes) {
+                Ok(()) => debug!(
+                    "Restored {} crawled connections from storage",
+                    known_network.connections().len()
+                ),
+                Err(e) => warn!("Couldn't restore the crawled network: {}", e),
+            },
+            Ok(None) => {}
+            Err(e) => warn!("Couldn't load the crawled network: {}", e),
+        }
+
+        match storage.get_network_snapshot_times() {
+            Ok(times) => {
+                *self.last_network_snapshot.lock().unwrap() = times
+                    .last()
+                    .and_then(|&time| chrono::Utc.timestamp_opt(time, 0).single())
+            }
+            Err(e) => warn!("Couldn't load the times of the network snapshots: {}", e),
+        }
+    }
+
+    /// Persists the crawled network in storage, along with a snapshot of it if one is due; the snapshots older
+    /// than their retention period are removed.
+    #[cfg(feature = "crawler")]
+    fn save_known_network(&self) {
+        let (known_network, storage) = match (self.known_network(), self.crawler_storage.get()) {
+            (Some(known_network), Some(storage)) => (known_network, storage),
+            _ => return,
+        };
+
+        let bytes = match known_network.serialize() {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                warn!("Couldn't serialize the crawled network: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = storage.save_known_network(bytes.clone()) {
+            warn!("Couldn't save the crawled network: {}", e);
+        }
+
+        let now = chrono::Utc::now();
+        let interval = chrono::Duration::seconds(crate::NETWORK_SNAPSHOT_INTERVAL_SECS.into());
+        let mut last_snapshot = self.last_network_snapshot.lock().unwrap();
+        if matches!(*last_snapshot, Some(time) if now - time < interval) {
+            return;
+        }
+        if let Err(e) = storage.store_network_snapshot(now.timestamp(), bytes) {
+            warn!("Couldn't store a snapshot of the crawled network: {}", e);
+            return;
+        }
+        *last_snapshot = Some(now);
+        drop(last_snapshot);
+
+        let retention = chrono::Duration::seconds(crate::NETWORK_SNAPSHOT_RETENTION_SECS.into());
+        match storage.prune_network_snapshots((now - retention).timestamp()) {
+            Ok(0) => {}
+            Ok(count) => debug!("Removed {} outdated network snapshots", count),
+            Err(e) => warn!("Couldn't remove the outdated network snapshots: {}", e),
+        }
+    }
+
+    /// Returns `true` if the recommended peers are due to be fetched or exported again, marking them as such.
+    fn recommended_peers_due(&self) -> bool {
+        let interval = Duration::from_secs(crate::RECOMMENDED_PEERS_REFRESH_INTERVAL_SECS.into());
+        let mut updated = self.recommended_peers_updated.lock().unwrap();
+        if matches!(*updated, Some(last_update) if last_update.elapsed() < interval) {
+            return false;
         }
+        *updated = Some(Instant::now());
+
+        true
     }
 
     async fn initiate_connection(&self, remote_address: SocketAddr) -> Result<(), NetworkError> {
+        if self.is_banned(remote_address.ip()) {
+            return Err(NetworkError::PeerBanned);
+        }
+
         debug!("Connecting to {}...", remote_address);
 
         // Local address must be known by now.
@@ -120,6 +410,111 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
         Ok(())
     }
 
+    ///
+    /// Adds the given address to the peer book and attempts to connect to it.
+    ///
+    pub async fn add_peer(&self, remote_address: SocketAddr) -> Result<(), NetworkError> {
+        if self.is_banned(remote_address.ip()) {
+            return Err(NetworkError::PeerBanned);
+        }
+
+        self.peer_book
+            .add_peer(remote_address, self.config.bootnodes().contains(&remote_address), None)
+            .await;
+
+        self.initiate_connection(remote_address).await
+    }
+
+    ///
+    /// Disconnects from the given address if needed and removes it from the peer book.
+    /// Returns `true` if the peer was known.
+    ///
+    pub async fn remove_peer(&self, remote_address: SocketAddr) -> bool {
+        self.peer_book.remove_peer(remote_address).await
+    }
+
+    ///
+    /// Writes the known peer addresses along with their connection history to a JSON file at the given path.
+    /// Returns the number of exported peers.
+    ///
+    pub fn export_peers(&self, path: &Path) -> Result<usize, NetworkError> {
+        let peers = self.peer_book.known_peers();
+        write_peer_list(path, &peers)?;
+
+        Ok(peers.len())
+    }
+
+    ///
+    /// Adds the peers listed in a JSON file at the given path to the peer book, e.g. one exported by another node.
+    /// Returns the number of peers that weren't known yet.
+    ///
+    pub async fn import_peers(&self, path: &Path) -> Result<usize, NetworkError> {
+        let mut peers = read_peer_list(path)?;
+        peers.retain(|peer| !self.is_banned(peer.address.ip()));
+
+        let imported = self.peer_book.import_peers(&peers, &self.config.bootnodes()).await;
+        if imported != 0 {
+            self.save_peer_book();
+        }
+
+        Ok(imported)
+    }
+
+    ///
+    /// Replaces the labels and the note attached to the given peer and persists them along with the peer book;
+    /// empty ones remove the annotation.
+    ///
+    pub async fn annotate_peer(&self, address: SocketAddr, annotation: PeerAnnotation) -> Result<(), NetworkError> {
+        self.peer_book.annotate_peer(address, annotation).await?;
+        self.save_peer_book();
+
+        Ok(())
+    }
+
+    ///
+    /// Disconnects from the given address if needed and refuses connections with its IP, or with its subnet if
+    /// the node's ban scope is wider, for the given duration.
+    ///
+    pub async fn ban_peer(&self, remote_address: SocketAddr, duration: Duration) {
+        let subnet = self.config.peer_ban_scope.subnet_of(remote_address.ip());
+        if let Err(e) = self.ban(subnet, duration).await {
+            warn!("Couldn't persist the ban of {}: {}", subnet, e);
+        }
+    }
+
+    ///
+    /// Reports a low number of connected peers or a chain lagging behind the peers' to the configured webhook.
+    ///
+    async fn check_webhook_conditions(&self, active_peer_count: usize) {
+        if self.webhook.is_none() || self.config.is_crawler() {
+            return;
+        }
+
+        // Bootnodes don't seek out peers on their own, so their peer count isn't indicative of a problem.
+        let min_peers = self.config.minimum_number_of_connected_peers() as usize;
+        if !self.config.is_bootnode() && active_peer_count < min_peers {
+            self.notify_webhook(
+                WebhookEvent::LowPeerCount,
+                serde_json::json!({
+                    "connected_peers": active_peer_count,
+                    "minimum_peers": min_peers,
+                }),
+            );
+        }
+
+        // Without any peers, the node is reported as having too few of them instead.
+        #[cfg(feature = "consensus")]
+        if self.sync().is_some() && active_peer_count != 0 && !self.is_synced().await {
+            self.notify_webhook(
+                WebhookEvent::OutOfSync,
+                serde_json::json!({
+                    "block_height": self.current_block_height(),
+                    "network_height": self.peer_book.network_height().await,
+                }),
+            );
+        }
+    }
+
     ///
     /// Broadcasts a connection request to all default bootnodes of the network.
     ///
@@ -138,7 +533,7 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
             .config
             .bootnodes()
             .iter()
-            .filter(|peer| **peer != own_address)
+            .filter(|peer| **peer != own_address && self.config.supports_address_family(AddressFamily::of(**peer)))
             .copied()
         {
             let node = self.clone();
@@ -190,23 +585,24 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
                 return;
             }
 
-            let disconnected_peers = self.peer_book.disconnected_peers();
-
-            trace!(
-                "Connecting to {} disconnected peers",
-                cmp::min(count, disconnected_peers.len())
-            );
-
             let bootnodes = self.config.bootnodes();
 
-            // Iterate through a selection of random peers and attempt to connect.
-            disconnected_peers
-                .iter()
-                .filter(|peer| **peer != own_address && !bootnodes.contains(peer))
-                .copied()
-                .choose_multiple(&mut rand::thread_rng(), count)
+            // Let the address manager order the candidates, favoring a diverse set of network groups.
+            let disconnected_count = self.peer_book.get_disconnected_peer_count() as usize;
+            let candidates = self.peer_book.select_peers(disconnected_count, |peer| {
+                peer != own_address
+                    && !bootnodes.contains(&peer)
+                    && !self.is_banned(peer.ip())
+                    && self.config.supports_address_family(AddressFamily::of(peer))
+            });
+
+            let mut selected = self.config.peer_selection.select_connections(candidates, count);
+            selected.truncate(count);
+            selected
         };
 
+        trace!("Connecting to {} disconnected peers", random_peers.len());
+
         for remote_address in random_peers {
             let node = self.clone();
             task::spawn(async move {
@@ -248,17 +644,16 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
     }
 
     /// Broadcasts a `Ping` message to all connected peers.
-    async fn broadcast_pings(&self) {
+    pub(crate) async fn broadcast_pings(&self) {
         trace!("Broadcasting `Ping` messages");
 
-        // Consider peering tests that don't use the sync layer.
-        let current_block_height = if let Some(sync) = self.sync() {
-            sync.current_block_height()
-        } else {
-            0
-        };
+        let current_block_height = self.current_block_height();
+
+        let timestamp = chrono::Utc::now().timestamp();
 
-        self.peer_book.broadcast(Payload::Ping(current_block_height)).await;
+        self.peer_book
+            .broadcast(Payload::Ping(current_block_height, timestamp))
+            .await;
     }
 
     ///
@@ -274,14 +669,36 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
         }
     }
 
+    ///
+    /// Returns the addresses that can be shared with the given peer in response to its `GetPeers` request, i.e.
+    /// the connected peers that are still active, except for the requester, the management clients and the peers that
+    /// don't listen. Only the ones of the requester's address family are shared, as it might not be able to reach the
+    /// others, and local addresses are only shared with peers that are local too.
+    ///
+    pub async fn shareable_peers(&self, requester: SocketAddr) -> Vec<SocketAddr> {
+        let family = AddressFamily::of(requester);
+        let is_local_requester = is_local_address(requester);
+        let now = chrono::Utc::now();
+
+        self.peer_book
+            .connected_peers_snapshot()
+            .await
+            .into_iter()
+            .filter(|peer| !peer.quality.is_rpc_client && !peer.quality.no_listen && !peer.quality.is_inactive(now))
+            .map(|peer| peer.address)
+            .filter(|&addr| addr != requester && AddressFamily::of(addr) == family)
+            .filter(|&addr| is_local_requester || !is_local_address(addr))
+            .collect()
+    }
+
     pub(crate) async fn send_peers(&self, remote_address: SocketAddr) {
-        // Broadcast the sanitized list of connected peers back to the requesting peer.
+        // Send a random selection of the shareable peers, so that the requester learns about different ones over time.
+        let shared_peer_count = self.config.shared_peer_count.min(crate::SHARED_PEER_COUNT);
         let peers = self
-            .peer_book
-            .connected_peers()
+            .shareable_peers(remote_address)
+            .await
             .into_iter()
-            .filter(|&addr| addr != remote_address)
-            .choose_multiple(&mut rand::thread_rng(), crate::SHARED_PEER_COUNT);
+            .choose_multiple(&mut rand::thread_rng(), shared_peer_count);
 
         self.peer_book.send_to(remote_address, Payload::Peers(peers)).await;
     }
@@ -289,16 +706,106 @@ impl<S: Storage + Send + Sync + 'static> Node<S> {
     /// A node has sent their list of peer addresses.
     /// Add all new/updated addresses to our disconnected.
     /// The connection handler will be responsible for sending out handshake requests to them.
-    pub(crate) async fn process_inbound_peers(&self, peers: Vec<SocketAddr>) {
+    pub(crate) async fn process_inbound_peers(&self, source: SocketAddr, peers: Vec<SocketAddr>) {
         let local_address = self.local_address().unwrap(); // the address must be known by now
+        let advertised_address = self.advertised_address();
+        let bootnodes = self.config.bootnodes();
+
+        // Addresses with unusual ports, e.g. 22 or 443, most likely belong to unrelated services.
+        let (peers, disallowed): (Vec<_>, Vec<_>) = peers.into_iter().map(normalize_address).partition(|peer_addr| {
+            bootnodes.contains(peer_addr) || self.config.gossip_port_policy.allows(peer_addr.port())
+        });
+        if !disallowed.is_empty() {
+            debug!(
+                "Ignoring {} address(es) with disallowed ports shared by {}",
+                disallowed.len(),
+                source
+            );
+            if let Some(peer) = self.peer_book.get_peer_handle(source) {
+                peer.gossiped_out_of_policy().await;
+            }
+        }
 
-        for peer_address in peers.into_iter().filter(|&peer_addr| peer_addr != local_address) {
+        for peer_address in peers
+            .into_iter()
+            .filter(|&peer_addr| peer_addr != local_address && Some(peer_addr) != advertised_address)
+            .filter(|peer_addr| !self.is_banned(peer_addr.ip()))
+        {
             // Inform the peer book that we found a peer.
             // The peer book will determine if we have seen the peer before,
             // and include the peer if it is new.
-            self.peer_book
-                .add_peer(peer_address, self.config.bootnodes().contains(&peer_address))
-                .await;
+            let is_bootnode = bootnodes.contains(&peer_address);
+            let is_new = self.peer_book.add_peer(peer_address, is_bootnode, Some(source)).await;
+
+            // New addresses are deprioritized until they're found to be reachable.
+            if let (true, false, Some(verifier)) = (is_new, is_bootnode, &self.reachability_verifier) {
+                self.peer_book.mark_unverified(peer_address).await;
+                verifier.verify(peer_address);
+            }
+        }
+    }
+
+    /// Restores the known peer addresses persisted in storage.
+    pub(crate) async fn load_peer_book(&self) {
+        // Without the consensus feature, there's no storage to restore the peers from.
+        #[cfg(feature = "consensus")]
+        if let Some(sync) = self.sync() {
+            match sync.storage().get_peer_book() {
+                Ok(Some(bytes)) => {
+                    if let Err(e) = self.peer_book.restore_addresses(&bytes, &self.config.bootnodes()).await {
+                        warn!("Couldn't restore the peer book: {}", e);
+                    } else {
+                        debug!(
+                            "Restored {} peers from storage",
+                            self.peer_book.get_disconnected_peer_count()
+                        );
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => warn!("Couldn't load the peer book: {}", e),
+            }
+        }
+    }
+
+    /// Persists the known peer addresses in storage.
+    pub(crate) fn save_peer_book(&self) {
+        #[cfg(feature = "consensus")]
+        if let Some(sync) = self.sync() {
+            match self.peer_book.serialize_addresses() {
+                Ok(bytes) => {
+                    if let Err(e) = sync.storage().save_peer_book_to_storage(bytes) {
+                        warn!("Couldn't save the peer book: {}", e);
+                    }
+                }
+                Err(e) => warn!("Couldn't serialize the peer book: {}", e),
+            }
+        }
+    }
+
+    /// Returns the maximum number of peers that can connect to this node; some of the connection slots are reserved
+    /// for the peers this node connects to, except on bootnodes, which only accept connections.
+    pub fn max_inbound_peers(&self) -> usize {
+        let max_peers = self.config.maximum_number_of_connected_peers() as usize;
+        if self.config.is_bootnode() {
+            return max_peers;
+        }
+
+        max_peers - (self.config.reserved_outbound_slots as usize).min(max_peers / 2)
+    }
+
+    /// Checks whether another peer can connect to this node without taking a slot reserved for outbound connections.
+    pub fn can_accept_inbound(&self) -> bool {
+        let num_inbound = self.peer_book.get_inbound_peer_count() as usize;
+        let max_inbound = self.max_inbound_peers();
+
+        if num_inbound >= max_inbound {
+            debug!(
+                "Max number of inbound connections ({} connected; max: {}) reached",
+                num_inbound, max_inbound
+            );
+            false
+        } else {
+            true
         }
     }
 
@@ -0,0 +1,129 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{fmt, ops::RangeInclusive, str::FromStr};
+
+use crate::NetworkError;
+
+/// The ports the nodes of the Aleo networks listen on by default: 4130 for the mainnet and 4130 + id for the testnets.
+pub const STANDARD_PORTS: RangeInclusive<u16> = 4130..=4139;
+
+/// The ports the addresses gossiped by other peers may use; addresses with any other port are ignored, so that the
+/// node can't be tricked into hammering unrelated services, e.g. SSH or web servers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PortPolicy {
+    /// Addresses with any port are accepted.
+    Any,
+    /// Only addresses with a port within one of the given ranges are accepted.
+    Ranges(Vec<RangeInclusive<u16>>),
+}
+
+impl Default for PortPolicy {
+    fn default() -> Self {
+        Self::Ranges(vec![STANDARD_PORTS])
+    }
+}
+
+impl PortPolicy {
+    /// Returns `true` if addresses with the given port are accepted.
+    pub fn allows(&self, port: u16) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Ranges(ranges) => ranges.iter().any(|range| range.contains(&port)),
+        }
+    }
+}
+
+/// Parses either `any` or a comma-separated list of ports and inclusive port ranges, e.g. `4130-4139, 5000`.
+impl FromStr for PortPolicy {
+    type Err = NetworkError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().eq_ignore_ascii_case("any") {
+            return Ok(Self::Any);
+        }
+
+        let mut ranges = vec![];
+        for part in s.split(',').map(str::trim) {
+            let invalid = || NetworkError::InvalidPortPolicy(format!("'{}' is not a port or a range of ports", part));
+
+            let mut bounds = part.splitn(2, '-').map(str::trim);
+            let start: u16 = bounds.next().unwrap_or_default().parse().map_err(|_| invalid())?;
+            let end: u16 = match bounds.next() {
+                Some(end) => end.parse().map_err(|_| invalid())?,
+                None => start,
+            };
+            if start == 0 || start > end {
+                return Err(invalid());
+            }
+            ranges.push(start..=end);
+        }
+
+        Ok(Self::Ranges(ranges))
+    }
+}
+
+impl fmt::Display for PortPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Any => write!(f, "any"),
+            Self::Ranges(ranges) => {
+                for (i, range) in ranges.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    if range.start() == range.end() {
+                        write!(f, "{}", range.start())?;
+                    } else {
+                        write!(f, "{}-{}", range.start(), range.end())?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_policy_only_allows_standard_ports() {
+        let policy = PortPolicy::default();
+
+        assert!(policy.allows(4130));
+        assert!(policy.allows(4131));
+        for &port in &[22, 80, 443, 8080] {
+            assert!(!policy.allows(port));
+        }
+    }
+
+    #[test]
+    fn parse_port_policy() {
+        assert_eq!("any".parse::<PortPolicy>().unwrap(), PortPolicy::Any);
+
+        let policy: PortPolicy = "4130-4139, 5000".parse().unwrap();
+        assert_eq!(policy, PortPolicy::Ranges(vec![4130..=4139, 5000..=5000]));
+        assert_eq!(policy.to_string(), "4130-4139, 5000");
+        assert!(policy.allows(5000));
+        assert!(!policy.allows(5001));
+
+        for invalid in &["", "0", "4139-4130", "ssh", "4130-"] {
+            assert!(invalid.parse::<PortPolicy>().is_err(), "'{}' was accepted", invalid);
+        }
+    }
+}
@@ -0,0 +1,103 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::AddressUpdate;
+
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{
+    net::TcpStream,
+    sync::{mpsc, Semaphore},
+    task,
+    time::timeout,
+};
+
+/// Checks whether a TCP connection can be established with the given address within the given time; no handshake
+/// is performed, and the connection is closed right away.
+pub async fn probe_reachability(address: SocketAddr, probe_timeout: Duration) -> bool {
+    matches!(timeout(probe_timeout, TcpStream::connect(address)).await, Ok(Ok(_)))
+}
+
+///
+/// A pool of background workers probing the reachability of the addresses gossiped by peers before they're
+/// connected to; the addresses remain deprioritized by the connection scheduler until they pass a probe.
+///
+#[derive(Debug, Clone)]
+pub struct ReachabilityVerifier {
+    sender: mpsc::Sender<SocketAddr>,
+}
+
+impl ReachabilityVerifier {
+    /// Spawns the task dispatching the probes to at most `workers` concurrent ones, registering their outcomes
+    /// through the given updates of the address manager.
+    pub(crate) fn spawn(
+        address_updates: mpsc::Sender<AddressUpdate>,
+        workers: usize,
+        probe_timeout: Duration,
+    ) -> (Self, task::JoinHandle<()>) {
+        let (sender, mut receiver) = mpsc::channel::<SocketAddr>(crate::REACHABILITY_PROBE_QUEUE_DEPTH);
+        let permits = Arc::new(Semaphore::new(workers.max(1)));
+
+        let handle = task::spawn(async move {
+            while let Some(address) = receiver.recv().await {
+                let permit = match permits.clone().acquire_owned().await {
+                    Ok(permit) => permit,
+                    Err(_) => break,
+                };
+                let address_updates = address_updates.clone();
+
+                task::spawn(async move {
+                    let is_reachable = probe_reachability(address, probe_timeout).await;
+                    if is_reachable {
+                        trace!("Verified the reachability of {}", address);
+                    } else {
+                        debug!("Couldn't reach the gossiped address {}", address);
+                    }
+                    address_updates
+                        .send(AddressUpdate::Probed(address, is_reachable))
+                        .await
+                        .ok();
+                    drop(permit);
+                });
+            }
+        });
+
+        (Self { sender }, handle)
+    }
+
+    /// Queues a probe of the given address; it's dropped if too many probes are already pending, in which case
+    /// the address simply remains deprioritized.
+    pub fn verify(&self, address: SocketAddr) {
+        if self.sender.try_send(address).is_err() {
+            trace!("Not probing {}; too many probes are pending", address);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn probes_only_pass_for_listening_addresses() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        assert!(probe_reachability(address, Duration::from_secs(1)).await);
+
+        drop(listener);
+        assert!(!probe_reachability(address, Duration::from_secs(1)).await);
+    }
+}
@@ -0,0 +1,170 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{rank_sync_nodes, Peer};
+
+use std::{cmp::Reverse, net::SocketAddr};
+
+/// A connected peer a new transaction is about to be relayed to, along with what's known about its quality.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RelayCandidate {
+    pub address: SocketAddr,
+    /// The last measured round-trip time to the peer; zero if it hasn't been measured yet.
+    pub rtt_ms: u64,
+    /// The number of connections of the peer according to the crawled network or, failing that, to the peer's own
+    /// responses to `GetPeers` requests, if it's known.
+    pub degree: Option<usize>,
+}
+
+///
+/// A policy deciding which peers the node connects to, syncs blocks from and relays new blocks and
+/// transactions to. Each method receives the eligible candidates and returns the ones to use, from the
+/// most to the least preferred one; the defaults implement the node's standard behavior, so a custom
+/// strategy only needs to override the decisions it's concerned with.
+///
+pub trait PeerSelectionStrategy: Send + Sync {
+    /// Picks up to `count` of the candidate disconnected peers to connect to. The candidates come ordered
+    /// by the address manager, which favors the network groups that the node isn't connected to yet.
+    fn select_connections(&self, mut candidates: Vec<SocketAddr>, count: usize) -> Vec<SocketAddr> {
+        candidates.truncate(count);
+        candidates
+    }
+
+    /// Orders the candidate sync nodes; `is_stalled` indicates whether a peer recently stalled while
+    /// serving a batch of sync blocks. Only the first `MAX_SYNC_NODES` of them are synced with.
+    fn rank_sync_nodes(&self, mut candidates: Vec<Peer>, is_stalled: &dyn Fn(SocketAddr) -> bool) -> Vec<Peer> {
+        rank_sync_nodes(&mut candidates, is_stalled);
+        candidates
+    }
+
+    /// Picks the connected peers a new block or transaction is relayed to; the source of the block or
+    /// transaction is never among the candidates.
+    fn select_relays(&self, candidates: Vec<SocketAddr>) -> Vec<SocketAddr> {
+        candidates
+    }
+
+    /// Orders the peers picked by `select_relays` that a new transaction is sent to, so that the ones most
+    /// likely to propagate it quickly receive it first. By default, the best-connected peers go first, and
+    /// the peers with equal or unknown degrees are ordered by their round-trip times.
+    fn order_transaction_relays(&self, mut candidates: Vec<RelayCandidate>) -> Vec<SocketAddr> {
+        candidates.sort_by_key(|candidate| {
+            let rtt_ms = Some(candidate.rtt_ms).filter(|&rtt| rtt != 0).unwrap_or(u64::MAX);
+            (Reverse(candidate.degree), rtt_ms)
+        });
+        candidates.into_iter().map(|candidate| candidate.address).collect()
+    }
+}
+
+/// The node's standard peer selection; see `PeerSelectionStrategy`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultPeerSelection;
+
+impl PeerSelectionStrategy for DefaultPeerSelection {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Only connects to and relays to IPv6 peers, and prefers the sync nodes with the lowest ports.
+    struct Ipv6Only;
+
+    impl PeerSelectionStrategy for Ipv6Only {
+        fn select_connections(&self, candidates: Vec<SocketAddr>, count: usize) -> Vec<SocketAddr> {
+            candidates
+                .into_iter()
+                .filter(|addr| addr.is_ipv6())
+                .take(count)
+                .collect()
+        }
+
+        fn rank_sync_nodes(&self, mut candidates: Vec<Peer>, _is_stalled: &dyn Fn(SocketAddr) -> bool) -> Vec<Peer> {
+            candidates.sort_by_key(|peer| peer.address.port());
+            candidates
+        }
+
+        fn select_relays(&self, candidates: Vec<SocketAddr>) -> Vec<SocketAddr> {
+            candidates.into_iter().filter(|addr| addr.is_ipv6()).collect()
+        }
+    }
+
+    fn addresses() -> Vec<SocketAddr> {
+        vec![
+            "127.0.0.1:4131".parse().unwrap(),
+            "[::1]:4131".parse().unwrap(),
+            "127.0.0.2:4131".parse().unwrap(),
+            "[::2]:4131".parse().unwrap(),
+        ]
+    }
+
+    #[test]
+    fn default_selection_keeps_the_order_of_the_candidates() {
+        let strategy = DefaultPeerSelection;
+
+        assert_eq!(strategy.select_connections(addresses(), 3), addresses()[..3].to_vec());
+        assert_eq!(strategy.select_connections(addresses(), 10), addresses());
+        assert_eq!(strategy.select_relays(addresses()), addresses());
+    }
+
+    #[test]
+    fn default_selection_ranks_sync_nodes_by_height() {
+        let mut behind = Peer::new(addresses()[0], false);
+        behind.quality.block_height = 10;
+        let mut ahead = Peer::new(addresses()[1], false);
+        ahead.quality.block_height = 100;
+
+        let ranked = DefaultPeerSelection.rank_sync_nodes(vec![behind, ahead], &|_| false);
+        assert_eq!(ranked[0].address, addresses()[1]);
+    }
+
+    #[test]
+    fn default_selection_relays_transactions_to_the_best_peers_first() {
+        let candidate = |i: usize, rtt_ms, degree| RelayCandidate {
+            address: addresses()[i],
+            rtt_ms,
+            degree,
+        };
+        let candidates = vec![
+            candidate(0, 0, None),
+            candidate(1, 200, None),
+            candidate(2, 50, None),
+            candidate(3, 300, Some(20)),
+        ];
+
+        let ordered = DefaultPeerSelection.order_transaction_relays(candidates);
+        assert_eq!(
+            ordered,
+            vec![addresses()[3], addresses()[2], addresses()[1], addresses()[0]]
+        );
+    }
+
+    #[test]
+    fn custom_strategies_override_the_defaults() {
+        let strategy: Box<dyn PeerSelectionStrategy> = Box::new(Ipv6Only);
+
+        assert_eq!(strategy.select_connections(addresses(), 1), vec![addresses()[1]]);
+        assert_eq!(
+            strategy.select_relays(addresses()),
+            vec![addresses()[1], addresses()[3]]
+        );
+
+        let peers = vec![
+            Peer::new("127.0.0.1:2".parse().unwrap(), false),
+            Peer::new("127.0.0.1:1".parse().unwrap(), false),
+        ];
+        let ranked = strategy.rank_sync_nodes(peers, &|_| false);
+        assert_eq!(ranked[0].address.port(), 1);
+    }
+}
@@ -0,0 +1,123 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use rand::Rng;
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    net::SocketAddr,
+    sync::atomic::{AtomicU32, AtomicU64, Ordering},
+    time::Duration,
+};
+
+/// The schedule of the node's peer maintenance rounds. The interval between them is randomized, so that the nodes
+/// started at the same time don't keep sending their `GetPeers` and `Ping` messages in bursts, and stretched while
+/// the set of connected peers stays the same, as there is little to maintain then.
+#[derive(Debug, Default)]
+pub struct PeerSyncSchedule {
+    /// The number of times the interval is currently doubled.
+    backoff: AtomicU32,
+    /// The fingerprint of the peers that were connected at the end of the previous round.
+    peer_set: AtomicU64,
+}
+
+impl PeerSyncSchedule {
+    /// Returns the randomized interval until the next round, based on the configured one.
+    pub fn next_interval(&self, peer_sync_interval: Duration) -> Duration {
+        jitter(peer_sync_interval * 2u32.pow(self.backoff.load(Ordering::Relaxed)))
+    }
+
+    /// Concludes the current round given the peers connected at its end: the interval is stretched if they're the
+    /// same as at the end of the previous round and there are enough of them, and returns to the configured one
+    /// otherwise.
+    pub fn conclude_round(&self, connected_peers: &[SocketAddr], has_enough_peers: bool) {
+        let peer_set = fingerprint(connected_peers);
+
+        if self.peer_set.swap(peer_set, Ordering::Relaxed) == peer_set && has_enough_peers {
+            let _ = self
+                .backoff
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |backoff| {
+                    Some((backoff + 1).min(crate::MAX_PEER_SYNC_BACKOFF))
+                });
+        } else {
+            self.reset();
+        }
+    }
+
+    /// Returns to the configured interval, e.g. once the node loses a peer.
+    pub fn reset(&self) {
+        self.backoff.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Randomizes the given interval by up to `PEER_SYNC_JITTER_PERCENT` in either direction.
+pub fn jitter(interval: Duration) -> Duration {
+    let jitter = crate::PEER_SYNC_JITTER_PERCENT as f64 / 100.0;
+
+    interval.mul_f64(rand::thread_rng().gen_range(1.0 - jitter..=1.0 + jitter))
+}
+
+/// Returns a fingerprint of the given set of peers that doesn't depend on their order.
+fn fingerprint(peers: &[SocketAddr]) -> u64 {
+    let mut peers = peers.to_vec();
+    peers.sort_unstable();
+
+    let mut hasher = DefaultHasher::new();
+    peers.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intervals_are_jittered_within_bounds() {
+        let interval = Duration::from_secs(10);
+        let jitter = interval.mul_f64(crate::PEER_SYNC_JITTER_PERCENT as f64 / 100.0);
+
+        for _ in 0..100 {
+            let jittered = super::jitter(interval);
+            assert!(jittered >= interval - jitter && jittered <= interval + jitter);
+        }
+    }
+
+    #[test]
+    fn interval_stretches_while_peers_are_stable() {
+        let schedule = PeerSyncSchedule::default();
+        let peers: Vec<SocketAddr> = vec!["1.2.3.4:4131".parse().unwrap(), "5.6.7.8:4131".parse().unwrap()];
+        let backoff = |schedule: &PeerSyncSchedule| schedule.backoff.load(Ordering::Relaxed);
+
+        // The first round has nothing to compare with.
+        schedule.conclude_round(&peers, true);
+        assert_eq!(backoff(&schedule), 0);
+
+        // The same peers in another order are still stable, up to the maximum backoff.
+        let reordered: Vec<SocketAddr> = peers.iter().rev().copied().collect();
+        for expected in 1..=crate::MAX_PEER_SYNC_BACKOFF + 1 {
+            schedule.conclude_round(&reordered, true);
+            assert_eq!(backoff(&schedule), expected.min(crate::MAX_PEER_SYNC_BACKOFF));
+        }
+
+        // Too few peers or a change in them return to the configured interval.
+        schedule.conclude_round(&reordered, false);
+        assert_eq!(backoff(&schedule), 0);
+        schedule.conclude_round(&reordered, true);
+        assert_eq!(backoff(&schedule), 1);
+        schedule.conclude_round(&peers[..1], true);
+        assert_eq!(backoff(&schedule), 0);
+    }
+}
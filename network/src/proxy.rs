@@ -0,0 +1,170 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+//! A minimal SOCKS5 client (RFC 1928), supporting only unauthenticated `CONNECT` requests.
+
+use crate::NetworkError;
+
+use std::net::SocketAddr;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+/// The version of the SOCKS protocol.
+const SOCKS_VERSION: u8 = 0x05;
+/// The authentication method requiring no authentication.
+const NO_AUTHENTICATION: u8 = 0x00;
+/// The `CONNECT` command.
+const CONNECT: u8 = 0x01;
+/// The address types of the target.
+const ADDRESS_TYPE_IPV4: u8 = 0x01;
+const ADDRESS_TYPE_DOMAIN: u8 = 0x03;
+const ADDRESS_TYPE_IPV6: u8 = 0x04;
+/// The reply code signifying success.
+const SUCCEEDED: u8 = 0x00;
+
+/// Connects to the given target address via the SOCKS5 proxy at the given address; the
+/// returned stream can be used as if it was connected to the target directly.
+pub async fn socks5_connect(proxy: SocketAddr, target: SocketAddr) -> Result<TcpStream, NetworkError> {
+    let mut stream = TcpStream::connect(proxy).await?;
+
+    // Offer only the unauthenticated method.
+    stream.write_all(&[SOCKS_VERSION, 1, NO_AUTHENTICATION]).await?;
+    let mut method_selection = [0u8; 2];
+    stream.read_exact(&mut method_selection).await?;
+    if method_selection != [SOCKS_VERSION, NO_AUTHENTICATION] {
+        return Err(NetworkError::Proxy("the SOCKS5 proxy requires authentication".into()));
+    }
+
+    stream.write_all(&connect_request(target)).await?;
+
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply).await?;
+    if reply[0] != SOCKS_VERSION {
+        return Err(NetworkError::Proxy("the proxy isn't a SOCKS5 proxy".into()));
+    }
+    if reply[1] != SUCCEEDED {
+        return Err(NetworkError::Proxy(format!(
+            "the SOCKS5 proxy couldn't connect to {}: {}",
+            target,
+            reply_description(reply[1])
+        )));
+    }
+
+    // The address the proxy bound for the connection is of no use, but it needs to be consumed.
+    let bound_address_len = match reply[3] {
+        ADDRESS_TYPE_IPV4 => 4,
+        ADDRESS_TYPE_IPV6 => 16,
+        ADDRESS_TYPE_DOMAIN => stream.read_u8().await? as usize,
+        address_type => {
+            return Err(NetworkError::Proxy(format!(
+                "the SOCKS5 proxy replied with an unknown address type {}",
+                address_type
+            )));
+        }
+    };
+    let mut bound_address = vec![0u8; bound_address_len + 2];
+    stream.read_exact(&mut bound_address).await?;
+
+    Ok(stream)
+}
+
+/// Serializes a `CONNECT` request for the given target address.
+fn connect_request(target: SocketAddr) -> Vec<u8> {
+    let mut request = vec![SOCKS_VERSION, CONNECT, 0x00];
+    match target {
+        SocketAddr::V4(address) => {
+            request.push(ADDRESS_TYPE_IPV4);
+            request.extend_from_slice(&address.ip().octets());
+        }
+        SocketAddr::V6(address) => {
+            request.push(ADDRESS_TYPE_IPV6);
+            request.extend_from_slice(&address.ip().octets());
+        }
+    }
+    request.extend_from_slice(&target.port().to_be_bytes());
+
+    request
+}
+
+/// Returns the description of the given SOCKS5 reply code.
+fn reply_description(code: u8) -> &'static str {
+    match code {
+        0x01 => "general failure",
+        0x02 => "connection not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unknown error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn serialize_connect_request() {
+        let target = SocketAddr::from((Ipv4Addr::new(10, 0, 0, 1), 4131));
+        assert_eq!(connect_request(target), vec![5, 1, 0, 1, 10, 0, 0, 1, 0x10, 0x23]);
+
+        let target = SocketAddr::from((Ipv6Addr::LOCALHOST, 4131));
+        let request = connect_request(target);
+        assert_eq!(request.len(), 4 + 16 + 2);
+        assert_eq!(request[3], ADDRESS_TYPE_IPV6);
+    }
+
+    #[tokio::test]
+    async fn connect_via_proxy() {
+        let proxy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_address = proxy.local_addr().unwrap();
+        let target = SocketAddr::from((Ipv4Addr::new(10, 0, 0, 1), 4131));
+
+        let proxy_task = tokio::spawn(async move {
+            let (mut stream, _) = proxy.accept().await.unwrap();
+
+            let mut greeting = [0u8; 3];
+            stream.read_exact(&mut greeting).await.unwrap();
+            assert_eq!(greeting, [SOCKS_VERSION, 1, NO_AUTHENTICATION]);
+            stream.write_all(&[SOCKS_VERSION, NO_AUTHENTICATION]).await.unwrap();
+
+            let mut request = [0u8; 10];
+            stream.read_exact(&mut request).await.unwrap();
+            assert_eq!(request.to_vec(), connect_request(target));
+            stream
+                .write_all(&[SOCKS_VERSION, SUCCEEDED, 0, ADDRESS_TYPE_IPV4, 0, 0, 0, 0, 0, 0])
+                .await
+                .unwrap();
+
+            // Relay a single message in order to confirm the stream is usable.
+            stream.write_all(b"hello").await.unwrap();
+        });
+
+        let mut stream = socks5_connect(proxy_address, target).await.unwrap();
+        let mut message = [0u8; 5];
+        stream.read_exact(&mut message).await.unwrap();
+        assert_eq!(&message, b"hello");
+
+        proxy_task.await.unwrap();
+    }
+}
@@ -0,0 +1,285 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+//! Signed lists of healthy peers, exported by crawlers from their view of the network, that regular
+//! nodes can bootstrap from when none of their bootnodes are reachable.
+
+#[cfg(feature = "crawler")]
+use crate::{address_group, KnownNetwork};
+use crate::{identity::verify_signature, NetworkError, NodeIdentity};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "crawler")]
+use std::collections::HashSet;
+use std::{
+    fmt,
+    fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+/// Prepended to the signed lists, so that the signatures can't be reused in another context.
+const RECOMMENDED_PEERS_SIGNATURE_DOMAIN: &[u8] = b"snarkOS recommended peers";
+
+/// A list of peers recommended by a crawler, signed with its node key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecommendedPeers {
+    /// The recommended peers, the most reliable first.
+    pub peers: Vec<SocketAddr>,
+    /// The time the list was generated at.
+    pub generated_at: DateTime<Utc>,
+    /// The hex-encoded public key of the node key that signed the list.
+    pub public_key: String,
+    /// The hex-encoded signature of the peers and the time the list was generated at.
+    pub signature: String,
+}
+
+impl RecommendedPeers {
+    /// Signs the given list of peers with the given node key.
+    pub fn sign(mut peers: Vec<SocketAddr>, identity: &NodeIdentity) -> Result<Self, NetworkError> {
+        peers.truncate(crate::MAX_RECOMMENDED_PEERS);
+        let generated_at = Utc::now();
+        let signature = identity.sign(
+            RECOMMENDED_PEERS_SIGNATURE_DOMAIN,
+            &signed_message(&peers, generated_at)?,
+        )?;
+
+        Ok(Self {
+            peers,
+            generated_at,
+            public_key: identity.public_key_hex()?,
+            signature: hex::encode(signature),
+        })
+    }
+
+    /// Selects the nodes that have been reliably reachable by the crawler, at most one per network group, and
+    /// signs the list with the given node key.
+    #[cfg(feature = "crawler")]
+    pub fn from_known_network(known_network: &KnownNetwork, identity: &NodeIdentity) -> Result<Self, NetworkError> {
+        let min_uptime = Duration::from_secs(crate::RECOMMENDED_PEER_MIN_UPTIME_SECS.into());
+
+        let mut candidates = known_network
+            .nodes()
+            .into_iter()
+            .filter_map(|node| known_network.reliability(node).map(|reliability| (node, reliability)))
+            .filter(|(_, reliability)| {
+                reliability.last_error().is_none()
+                    && reliability.uptime() >= min_uptime
+                    && reliability.responsiveness().unwrap_or(0.0) >= crate::RECOMMENDED_PEER_MIN_RESPONSIVENESS
+            })
+            .map(|(node, reliability)| (node, reliability.uptime()))
+            .collect::<Vec<_>>();
+        // The longest reachable nodes first; the address breaks the ties, so that the order is stable.
+        candidates.sort_unstable_by(|(a, a_uptime), (b, b_uptime)| b_uptime.cmp(a_uptime).then(a.cmp(b)));
+
+        // A single operator can't fill the list with the nodes of a single network.
+        let mut groups = HashSet::new();
+        let peers = candidates
+            .into_iter()
+            .map(|(node, _)| node)
+            .filter(|&node| groups.insert(address_group(node)))
+            .collect();
+
+        Self::sign(peers, identity)
+    }
+
+    /// Checks that the list was signed by one of the given hex-encoded public keys, and that it isn't stale.
+    pub fn verify(&self, trusted_keys: &[String]) -> Result<(), NetworkError> {
+        if !trusted_keys
+            .iter()
+            .any(|key| key.eq_ignore_ascii_case(&self.public_key))
+        {
+            return Err(NetworkError::InvalidRecommendedPeers(format!(
+                "the list was signed by an untrusted key {}",
+                self.public_key
+            )));
+        }
+
+        if self.peers.len() > crate::MAX_RECOMMENDED_PEERS {
+            return Err(NetworkError::InvalidRecommendedPeers(format!(
+                "the list has more than {} peers",
+                crate::MAX_RECOMMENDED_PEERS
+            )));
+        }
+
+        let age = Utc::now() - self.generated_at;
+        if age > chrono::Duration::seconds(crate::RECOMMENDED_PEERS_MAX_AGE_SECS.into()) {
+            return Err(NetworkError::InvalidRecommendedPeers(format!(
+                "the list is stale; it was generated at {}",
+                self.generated_at
+            )));
+        }
+
+        let (public_key, signature) = match (hex::decode(&self.public_key), hex::decode(&self.signature)) {
+            (Ok(public_key), Ok(signature)) => (public_key, signature),
+            _ => {
+                return Err(NetworkError::InvalidRecommendedPeers(
+                    "the public key or the signature isn't valid hex".into(),
+                ));
+            }
+        };
+        let message = signed_message(&self.peers, self.generated_at)?;
+        if !verify_signature(&public_key, RECOMMENDED_PEERS_SIGNATURE_DOMAIN, &message, &signature) {
+            return Err(NetworkError::InvalidRecommendedPeers("the signature is invalid".into()));
+        }
+
+        Ok(())
+    }
+
+    /// Parses a list of recommended peers from its JSON form; it still needs to be verified.
+    pub fn from_json(json: &[u8]) -> Result<Self, NetworkError> {
+        serde_json::from_slice(json).map_err(|e| NetworkError::InvalidRecommendedPeers(e.to_string()))
+    }
+
+    /// Writes the list as JSON to a file at the given path, replacing it at once, so that it's never
+    /// served partially written.
+    pub fn write_to(&self, path: &Path) -> Result<(), NetworkError> {
+        let json = serde_json::to_vec_pretty(self).map_err(|e| NetworkError::InvalidRecommendedPeers(e.to_string()))?;
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, json)?;
+        fs::rename(temp_path, path)?;
+
+        Ok(())
+    }
+}
+
+/// Returns the message that is signed in order to attest the given list of peers.
+fn signed_message(peers: &[SocketAddr], generated_at: DateTime<Utc>) -> Result<Vec<u8>, NetworkError> {
+    Ok(bincode::serialize(&(peers, generated_at.timestamp()))?)
+}
+
+/// Where a node fetches its recommended peers from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecommendedPeersSource {
+    /// A local file, e.g. one distributed along with the node's configuration.
+    File(PathBuf),
+    /// An HTTP(S) URL, e.g. one a crawler's export is served at.
+    Url(String),
+}
+
+impl RecommendedPeersSource {
+    /// Fetches the list of recommended peers; it still needs to be verified.
+    pub async fn fetch(&self) -> Result<RecommendedPeers, NetworkError> {
+        let json = match self {
+            Self::File(path) => tokio::fs::read(path).await?,
+            Self::Url(url) => {
+                let fetch = async {
+                    reqwest::Client::new()
+                        .get(url.as_str())
+                        .timeout(Duration::from_secs(crate::RECOMMENDED_PEERS_FETCH_TIMEOUT_SECS.into()))
+                        .send()
+                        .await?
+                        .error_for_status()?
+                        .bytes()
+                        .await
+                };
+
+                fetch
+                    .await
+                    .map_err(|e| NetworkError::InvalidRecommendedPeers(e.to_string()))?
+                    .to_vec()
+            }
+        };
+
+        RecommendedPeers::from_json(&json)
+    }
+}
+
+impl FromStr for RecommendedPeersSource {
+    type Err = NetworkError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        if source.is_empty() {
+            return Err(NetworkError::InvalidRecommendedPeers("the source is empty".into()));
+        }
+
+        if source.starts_with("http://") || source.starts_with("https://") {
+            Ok(Self::Url(source.to_owned()))
+        } else {
+            Ok(Self::File(source.into()))
+        }
+    }
+}
+
+impl fmt::Display for RecommendedPeersSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::File(path) => write!(f, "{}", path.display()),
+            Self::Url(url) => write!(f, "{}", url),
+        }
+    }
+}
+
+/// The configuration of the recommended peers a node falls back on when it has no peers.
+#[derive(Debug, Clone)]
+pub struct RecommendedPeersConfig {
+    /// Where the list is fetched from.
+    pub source: RecommendedPeersSource,
+    /// The hex-encoded public keys of the crawlers whose lists are trusted.
+    pub trusted_keys: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_identity() -> NodeIdentity {
+        let path = std::env::temp_dir().join(format!("snarkos_crawler_key_{}", rand::random::<u64>()));
+        let identity = NodeIdentity::load_or_generate(&path, "crawler".into()).unwrap();
+        fs::remove_file(path).unwrap();
+
+        identity
+    }
+
+    #[test]
+    fn recommended_peers_are_verified() {
+        let identity = test_identity();
+        let trusted_keys = vec![identity.public_key_hex().unwrap()];
+        let peers = vec!["1.2.3.4:4131".parse().unwrap(), "5.6.7.8:4131".parse().unwrap()];
+
+        let list = RecommendedPeers::sign(peers, &identity).unwrap();
+        let json = serde_json::to_vec(&list).unwrap();
+        let list = RecommendedPeers::from_json(&json).unwrap();
+        assert!(list.verify(&trusted_keys).is_ok());
+
+        // A list signed by another key, tampered with or stale is rejected.
+        assert!(list.verify(&[test_identity().public_key_hex().unwrap()]).is_err());
+        let mut tampered = list.clone();
+        tampered.peers.push("9.9.9.9:4131".parse().unwrap());
+        assert!(tampered.verify(&trusted_keys).is_err());
+        let mut stale = list;
+        stale.generated_at = stale.generated_at - chrono::Duration::days(30);
+        assert!(stale.verify(&trusted_keys).is_err());
+    }
+
+    #[test]
+    fn recommended_peers_sources_are_parsed() {
+        assert_eq!(
+            "https://crawler.example.org/peers.json"
+                .parse::<RecommendedPeersSource>()
+                .unwrap(),
+            RecommendedPeersSource::Url("https://crawler.example.org/peers.json".into())
+        );
+        assert_eq!(
+            "/etc/snarkos/peers.json".parse::<RecommendedPeersSource>().unwrap(),
+            RecommendedPeersSource::File("/etc/snarkos/peers.json".into())
+        );
+        assert!("".parse::<RecommendedPeersSource>().is_err());
+    }
+}
@@ -0,0 +1,180 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    peers::peer::client_handshake,
+    Features,
+    NetworkError,
+    Node,
+    Payload,
+    Peer,
+    PeerIOHandle,
+    PeerReader,
+    Version,
+};
+use snarkvm_dpc::Storage;
+
+use futures::future::BoxFuture;
+use rand::{thread_rng, Rng};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{
+    net::{tcp::OwnedReadHalf, TcpStream},
+    time::timeout,
+};
+
+///
+/// Serves the RPC to the management clients connected to the node's P2P port. It's implemented by the RPC
+/// server, so that the requests are handled just like the ones it receives over HTTP.
+///
+pub trait RpcHandler: Send + Sync {
+    /// Returns `true` if the given credentials, in the `username:password` format, grant access to the RPC.
+    fn authenticate(&self, credentials: &[u8]) -> bool;
+
+    /// Handles a serialized JSON-RPC request, or a batch of them, returning the serialized response.
+    fn handle(&self, request: Vec<u8>) -> BoxFuture<'static, Vec<u8>>;
+}
+
+impl<S: Storage + Send + Sync + 'static> Node<S> {
+    /// Makes the node serve the RPC over its P2P port using the given handler; the support for it is
+    /// advertised during the handshakes.
+    pub fn set_rpc_handler(&mut self, handler: Arc<dyn RpcHandler>) {
+        if self.rpc_handler.set(handler).is_err() {
+            panic!("the RPC handler was set more than once!");
+        }
+    }
+
+    /// Returns the handler of the RPC requests sent over the P2P port, if the node serves them.
+    #[inline]
+    pub fn rpc_handler(&self) -> Option<&Arc<dyn RpcHandler>> {
+        self.rpc_handler.get()
+    }
+
+    /// Serves the RPC requests of a management client connected to the P2P port until it disconnects or
+    /// stays idle for too long; the client has to authenticate first.
+    pub(crate) async fn serve_rpc_client(
+        &self,
+        address: SocketAddr,
+        mut network: PeerIOHandle,
+    ) -> Result<(), NetworkError> {
+        let handler = self.rpc_handler().cloned().ok_or(NetworkError::RpcUnavailable)?;
+        let mut reader = network.take_reader();
+
+        let auth = timeout(Peer::peer_handshake_timeout(), read_payload(&mut network, &mut reader))
+            .await
+            .map_err(|_| NetworkError::HandshakeTimeout)??;
+
+        match auth {
+            Payload::RpcAuth(credentials) if handler.authenticate(&credentials) => {
+                // An empty response acknowledges the credentials.
+                network.write_payload(&Payload::RpcResponse(vec![])).await?;
+                info!("RPC client {} authenticated", address);
+            }
+            Payload::RpcAuth(_) => return Err(NetworkError::RpcAuthFailed),
+            payload => {
+                return Err(NetworkError::ProtocolViolation(format!(
+                    "expected an 'rpcauth' message, got '{}'",
+                    payload
+                )));
+            }
+        }
+
+        let idle_timeout = Duration::from_secs(crate::RPC_CLIENT_IDLE_TIMEOUT_SECS as u64);
+        loop {
+            let payload = match timeout(idle_timeout, read_payload(&mut network, &mut reader)).await {
+                Ok(payload) => payload?,
+                Err(_) => {
+                    debug!("Disconnecting from idle RPC client {}", address);
+                    return Ok(());
+                }
+            };
+
+            match payload {
+                Payload::RpcRequest(request) => {
+                    let response = handler.handle(request).await;
+                    network.write_payload(&Payload::RpcResponse(response)).await?;
+                }
+                payload => {
+                    return Err(NetworkError::ProtocolViolation(format!(
+                        "expected an 'rpcrequest' message, got '{}'",
+                        payload
+                    )));
+                }
+            }
+        }
+    }
+}
+
+/// A management client using the RPC of a node over its P2P port, e.g. if it's the only port the node exposes.
+pub struct RpcClient {
+    network: PeerIOHandle,
+    reader: PeerReader<OwnedReadHalf>,
+}
+
+impl RpcClient {
+    /// Connects to the P2P port of the node at the given address and authenticates with its RPC credentials.
+    pub async fn connect(address: SocketAddr, username: &str, password: &str) -> Result<Self, NetworkError> {
+        let stream = timeout(Peer::peer_handshake_timeout(), TcpStream::connect(address))
+            .await
+            .map_err(|_| NetworkError::HandshakeTimeout)??;
+
+        // The client isn't a peer, so it has no listening port, supports no other extensions and isn't subject
+        // to the network check.
+        let version = Version::new(crate::PROTOCOL_VERSION, 0, thread_rng().gen()).with_features(Features::RPC_CLIENT);
+        let (node_version, mut network) = client_handshake(address, stream, &version).await?;
+        if !node_version.features.contains(Features::RPC) {
+            return Err(NetworkError::RpcUnavailable);
+        }
+        let mut reader = network.take_reader();
+
+        let credentials = format!("{}:{}", username, password).into_bytes();
+        network.write_payload(&Payload::RpcAuth(credentials)).await?;
+
+        match read_payload(&mut network, &mut reader).await {
+            Ok(Payload::RpcResponse(_)) => Ok(Self { network, reader }),
+            Ok(payload) => Err(NetworkError::ProtocolViolation(format!(
+                "expected an 'rpcresponse' message, got '{}'",
+                payload
+            ))),
+            // The node disconnects from clients with invalid credentials.
+            Err(e) if e.is_trivial() => Err(NetworkError::RpcAuthFailed),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Sends a serialized JSON-RPC request, or a batch of them, and returns the serialized response.
+    pub async fn request(&mut self, request: Vec<u8>) -> Result<Vec<u8>, NetworkError> {
+        self.network.write_payload(&Payload::RpcRequest(request)).await?;
+
+        match read_payload(&mut self.network, &mut self.reader).await? {
+            Payload::RpcResponse(response) => Ok(response),
+            payload => Err(NetworkError::ProtocolViolation(format!(
+                "expected an 'rpcresponse' message, got '{}'",
+                payload
+            ))),
+        }
+    }
+}
+
+/// Reads, decrypts and deserializes the next payload sent over the connection.
+async fn read_payload(
+    network: &mut PeerIOHandle,
+    reader: &mut PeerReader<OwnedReadHalf>,
+) -> Result<Payload, NetworkError> {
+    let payload = reader.read_raw_payload().await?;
+    let payload = network.read_payload(payload)?;
+
+    Ok(Payload::deserialize(payload)?)
+}
@@ -0,0 +1,195 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use std::fmt;
+
+use crate::Payload;
+
+/// The role a node plays in the network; it determines the node's standard `MessagePolicy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeRole {
+    /// A regular node that follows, serves and relays the full chain and the memory pool.
+    Full,
+    /// An entry point to the network for new nodes; it serves its peers and relays blocks and transactions like a
+    /// full node, as its peers may have no other route to the rest of the network.
+    Bootnode,
+    /// A node mapping the network; it doesn't participate in consensus, so it's only interested in peers.
+    Crawler,
+    /// A watcher node, which only follows the block headers and the memory pool.
+    Light,
+}
+
+impl NodeRole {
+    /// Returns the standard message policy of the role.
+    pub fn default_policy(self) -> &'static dyn MessagePolicy {
+        match self {
+            Self::Full => &FullNodePolicy,
+            Self::Bootnode => &BootnodePolicy,
+            Self::Crawler => &CrawlerPolicy,
+            Self::Light => &LightNodePolicy,
+        }
+    }
+}
+
+impl fmt::Display for NodeRole {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Full => "full",
+            Self::Bootnode => "bootnode",
+            Self::Crawler => "crawler",
+            Self::Light => "light",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+///
+/// A policy deciding which inbound messages the node processes and what it relays to its other peers.
+/// The messages it doesn't accept are dropped before reaching the node's components; the handshake
+/// and the `Ping`, `Pong` and `NodeAlias` messages are handled by the peers themselves and aren't
+/// subject to it. Regardless of the policy, the node also drops the messages it lacks the components
+/// to handle, e.g. the requests for blocks if it has no sync layer.
+///
+pub trait MessagePolicy: Send + Sync {
+    /// Decides whether the given inbound payload is processed.
+    fn accepts(&self, payload: &Payload) -> bool;
+
+    /// Decides whether new blocks received from a peer are relayed to the other peers.
+    fn relays_blocks(&self) -> bool {
+        false
+    }
+
+    /// Decides whether new memory pool transactions received from a peer are relayed to the other peers.
+    fn relays_transactions(&self) -> bool {
+        false
+    }
+}
+
+/// The message policy of `NodeRole::Full`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FullNodePolicy;
+
+impl MessagePolicy for FullNodePolicy {
+    fn accepts(&self, _payload: &Payload) -> bool {
+        true
+    }
+
+    fn relays_blocks(&self) -> bool {
+        true
+    }
+
+    fn relays_transactions(&self) -> bool {
+        true
+    }
+}
+
+/// The message policy of `NodeRole::Bootnode`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BootnodePolicy;
+
+impl MessagePolicy for BootnodePolicy {
+    fn accepts(&self, _payload: &Payload) -> bool {
+        true
+    }
+
+    fn relays_blocks(&self) -> bool {
+        true
+    }
+
+    fn relays_transactions(&self) -> bool {
+        true
+    }
+}
+
+/// The message policy of `NodeRole::Crawler`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CrawlerPolicy;
+
+impl MessagePolicy for CrawlerPolicy {
+    fn accepts(&self, payload: &Payload) -> bool {
+        matches!(payload, Payload::GetPeers | Payload::Peers(_))
+    }
+}
+
+/// The message policy of `NodeRole::Light`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LightNodePolicy;
+
+impl MessagePolicy for LightNodePolicy {
+    fn accepts(&self, payload: &Payload) -> bool {
+        // Light nodes can't serve any blocks or transactions.
+        !matches!(
+            payload,
+            Payload::GetBlock(_) | Payload::GetBlocks(_) | Payload::GetMemoryPool | Payload::GetSync(_)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payloads() -> Vec<Payload> {
+        vec![
+            Payload::Block(vec![]),
+            Payload::GetBlocks(vec![]),
+            Payload::GetMemoryPool,
+            Payload::GetPeers,
+            Payload::Peers(vec![]),
+            Payload::Transaction(vec![]),
+        ]
+    }
+
+    #[test]
+    fn full_nodes_accept_and_relay_everything() {
+        let policy = NodeRole::Full.default_policy();
+
+        assert!(payloads().iter().all(|payload| policy.accepts(payload)));
+        assert!(policy.relays_blocks());
+        assert!(policy.relays_transactions());
+    }
+
+    #[test]
+    fn bootnodes_accept_and_relay_everything() {
+        let policy = NodeRole::Bootnode.default_policy();
+
+        assert!(payloads().iter().all(|payload| policy.accepts(payload)));
+        assert!(policy.relays_blocks());
+        assert!(policy.relays_transactions());
+    }
+
+    #[test]
+    fn crawlers_only_accept_peers() {
+        let policy = NodeRole::Crawler.default_policy();
+
+        let accepted = payloads()
+            .into_iter()
+            .filter(|payload| policy.accepts(payload))
+            .collect::<Vec<_>>();
+        assert_eq!(accepted, vec![Payload::GetPeers, Payload::Peers(vec![])]);
+    }
+
+    #[test]
+    fn light_nodes_dont_serve_requests() {
+        let policy = NodeRole::Light.default_policy();
+
+        assert!(!policy.accepts(&Payload::GetBlocks(vec![])));
+        assert!(!policy.accepts(&Payload::GetMemoryPool));
+        assert!(policy.accepts(&Payload::Block(vec![])));
+        assert!(policy.accepts(&Payload::Transaction(vec![])));
+        assert!(!policy.relays_blocks());
+    }
+}
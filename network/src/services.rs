@@ -0,0 +1,270 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::NetworkError;
+
+use futures::future::BoxFuture;
+use std::{collections::HashSet, future::Future};
+
+type StartFn = Box<dyn FnOnce() -> BoxFuture<'static, Result<(), NetworkError>> + Send>;
+type StopFn = Box<dyn FnOnce() -> BoxFuture<'static, ()> + Send>;
+
+/// A service of the node, along with the ones that must be started before it.
+struct Service {
+    name: &'static str,
+    dependencies: Vec<&'static str>,
+    start: Option<StartFn>,
+    stop: Option<StopFn>,
+}
+
+///
+/// A registry of the services making up the node, e.g. its listener, its RPC server or its sync layer.
+///
+/// The services are started one at a time, each of them only after all of its dependencies, and stopped
+/// in the reverse order. Services that don't depend on one another start in the order they were
+/// registered in, so the startup sequence is the same on every boot.
+///
+#[derive(Default)]
+pub struct ServiceRegistry {
+    services: Vec<Service>,
+    /// The indices of the services that were started, in the order they were started in.
+    started: Vec<usize>,
+}
+
+impl ServiceRegistry {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Registers a service started by the given function once all of its dependencies have started.
+    pub fn register<F, Fut>(&mut self, name: &'static str, dependencies: &[&'static str], start: F) -> &mut Self
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), NetworkError>> + Send + 'static,
+    {
+        self.services.push(Service {
+            name,
+            dependencies: dependencies.to_vec(),
+            start: Some(Box::new(move || Box::pin(start()))),
+            stop: None,
+        });
+        self
+    }
+
+    /// Sets the function stopping the most recently registered service.
+    pub fn on_stop<F, Fut>(&mut self, stop: F) -> &mut Self
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        if let Some(service) = self.services.last_mut() {
+            service.stop = Some(Box::new(move || Box::pin(stop())));
+        }
+        self
+    }
+
+    /// Returns the names of the services in the order they're started in.
+    pub fn startup_order(&self) -> Result<Vec<&'static str>, NetworkError> {
+        Ok(self.ordered()?.into_iter().map(|i| self.services[i].name).collect())
+    }
+
+    /// Orders the services so that each of them comes after its dependencies, and otherwise in the order of
+    /// their registration; fails if any of the dependencies is unknown or if they're circular.
+    fn ordered(&self) -> Result<Vec<usize>, NetworkError> {
+        let mut names = HashSet::new();
+        for service in &self.services {
+            if !names.insert(service.name) {
+                return Err(NetworkError::InvalidServices(format!(
+                    "the '{}' service was registered twice",
+                    service.name
+                )));
+            }
+        }
+        for service in &self.services {
+            if let Some(dependency) = service.dependencies.iter().find(|name| !names.contains(*name)) {
+                return Err(NetworkError::InvalidServices(format!(
+                    "the '{}' service depends on the unknown '{}' service",
+                    service.name, dependency
+                )));
+            }
+        }
+
+        let mut order = Vec::with_capacity(self.services.len());
+        let mut ordered = HashSet::new();
+        while order.len() < self.services.len() {
+            // the first registered service whose dependencies are all ordered comes next
+            let next = (0..self.services.len()).find(|i| {
+                !order.contains(i) && self.services[*i].dependencies.iter().all(|name| ordered.contains(name))
+            });
+
+            match next {
+                Some(i) => {
+                    order.push(i);
+                    ordered.insert(self.services[i].name);
+                }
+                None => {
+                    let remaining = self
+                        .services
+                        .iter()
+                        .filter(|service| !ordered.contains(service.name))
+                        .map(|service| service.name)
+                        .collect::<Vec<_>>();
+                    return Err(NetworkError::InvalidServices(format!(
+                        "circular dependencies between the {:?} services",
+                        remaining
+                    )));
+                }
+            }
+        }
+
+        Ok(order)
+    }
+
+    /// Starts the services in the order of their dependencies; stops at the first one that fails to start,
+    /// leaving the ones that did start running, so that they can be stopped.
+    pub async fn start(&mut self) -> Result<(), NetworkError> {
+        let order = self.ordered()?;
+        let count = order.len();
+
+        for (phase, i) in order.into_iter().enumerate() {
+            let service = &mut self.services[i];
+            let start = match service.start.take() {
+                Some(start) => start,
+                // already started
+                None => continue,
+            };
+
+            info!("Starting the {} service ({}/{})", service.name, phase + 1, count);
+            if let Err(e) = start().await {
+                error!("The {} service failed to start: {}", service.name, e);
+                return Err(NetworkError::ServiceFailed(service.name, e.to_string()));
+            }
+            debug!("The {} service has started", service.name);
+
+            self.started.push(i);
+        }
+
+        Ok(())
+    }
+
+    /// Stops the services that were started, in the reverse order.
+    pub async fn stop(&mut self) {
+        let count = self.started.len();
+
+        for (phase, i) in self.started.drain(..).rev().enumerate() {
+            let service = &mut self.services[i];
+
+            info!("Stopping the {} service ({}/{})", service.name, phase + 1, count);
+            if let Some(stop) = service.stop.take() {
+                stop().await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    fn registry(services: &[(&'static str, &[&'static str])], log: &Arc<Mutex<Vec<String>>>) -> ServiceRegistry {
+        let mut registry = ServiceRegistry::new();
+        for &(name, dependencies) in services {
+            let (start_log, stop_log) = (log.clone(), log.clone());
+            registry
+                .register(name, dependencies, move || async move {
+                    start_log.lock().unwrap().push(format!("start {}", name));
+                    Ok(())
+                })
+                .on_stop(move || async move {
+                    stop_log.lock().unwrap().push(format!("stop {}", name));
+                });
+        }
+        registry
+    }
+
+    #[test]
+    fn services_start_after_their_dependencies() {
+        let log = Default::default();
+        let registry = registry(
+            &[
+                ("rpc", &["storage", "network"]),
+                ("sync", &["network"]),
+                ("storage", &[]),
+                ("network", &["storage"]),
+                ("metrics", &[]),
+            ],
+            &log,
+        );
+
+        assert_eq!(
+            registry.startup_order().unwrap(),
+            vec!["storage", "network", "rpc", "sync", "metrics"]
+        );
+    }
+
+    #[test]
+    fn invalid_dependencies_are_rejected() {
+        let log = Default::default();
+
+        let unknown = registry(&[("rpc", &["storage"])], &log);
+        assert!(matches!(unknown.startup_order(), Err(NetworkError::InvalidServices(_))));
+
+        let circular = registry(&[("a", &["b"]), ("b", &["a"]), ("c", &[])], &log);
+        assert!(matches!(
+            circular.startup_order(),
+            Err(NetworkError::InvalidServices(_))
+        ));
+
+        let duplicate = registry(&[("a", &[]), ("a", &[])], &log);
+        assert!(matches!(
+            duplicate.startup_order(),
+            Err(NetworkError::InvalidServices(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn services_stop_in_reverse_order() {
+        let log = Arc::new(Mutex::new(vec![]));
+        let mut registry = registry(&[("network", &["storage"]), ("storage", &[])], &log);
+
+        registry.start().await.unwrap();
+        registry.stop().await;
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["start storage", "start network", "stop network", "stop storage"]
+        );
+    }
+
+    #[tokio::test]
+    async fn startup_stops_at_the_first_failure() {
+        let log = Arc::new(Mutex::new(vec![]));
+        let mut registry = registry(&[("storage", &[])], &log);
+        registry.register("network", &["storage"], || async { Err(NetworkError::NotListening) });
+        registry.register("rpc", &["network"], || async { Ok(()) });
+
+        assert!(matches!(
+            registry.start().await,
+            Err(NetworkError::ServiceFailed("network", _))
+        ));
+
+        // only the services that started are stopped
+        registry.stop().await;
+        assert_eq!(*log.lock().unwrap(), vec!["start storage", "stop storage"]);
+    }
+}
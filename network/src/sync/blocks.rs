@@ -14,14 +14,16 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc};
 
+use futures::{stream, StreamExt};
 use snarkvm_dpc::{Block, BlockHeaderHash, Storage};
+use tokio::task;
 
-use snarkos_consensus::error::ConsensusError;
+use snarkos_consensus::{error::ConsensusError, BlockOutcome, ReorgEvent};
 use snarkos_metrics::{self as metrics, misc::*};
 
-use crate::{master::SyncInbound, message::*, NetworkError, Node};
+use crate::{master::SyncInbound, message::*, Misbehavior, NetworkError, Node, ServedItems, WebhookEvent};
 
 impl<S: Storage + Send + std::marker::Sync + 'static> Node<S> {
     ///
@@ -45,16 +47,20 @@ impl<S: Storage + Send + std::marker::Sync + 'static> Node<S> {
     }
 
     /// Broadcast block to connected peers
-    pub async fn propagate_block(&self, block_bytes: Vec<u8>, block_miner: SocketAddr) {
+    pub async fn propagate_block(&self, block_hash: BlockHeaderHash, block_bytes: Vec<u8>, block_miner: SocketAddr) {
         metrics::increment_counter!(BLOCK_HEIGHT);
         debug!("Propagating a block to peers");
 
-        for remote_address in self.connected_peers() {
-            if remote_address != block_miner {
-                // Send a `Block` message to the connected peer.
-                self.peer_book
-                    .send_to(remote_address, Payload::Block(block_bytes.clone()))
-                    .await;
+        let block_bytes = Arc::new(block_bytes);
+        let candidates = self
+            .connected_peers()
+            .into_iter()
+            .filter(|&remote_address| remote_address != block_miner)
+            .collect();
+        for remote_address in self.config.peer_selection.select_relays(candidates) {
+            // Send a `BlockHash` or a `Block` message to the connected peer, depending on its features.
+            if let Some(peer) = self.peer_book.get_peer_handle(remote_address) {
+                peer.announce_block(block_hash.clone(), block_bytes.clone()).await;
             }
         }
     }
@@ -117,43 +123,208 @@ impl<S: Storage + Send + std::marker::Sync + 'static> Node<S> {
         );
 
         // Verify the block and insert it into the storage.
-        let block_validity = self.expect_sync().consensus.receive_block(&block_struct).await;
-
-        if let Err(ConsensusError::PreExistingBlock) = block_validity {
-            if is_block_new {
-                metrics::increment_counter!(DUPLICATE_BLOCKS);
-            } else {
-                metrics::increment_counter!(DUPLICATE_SYNC_BLOCKS);
+        match self.expect_sync().consensus.receive_block(&block_struct).await {
+            Ok(BlockOutcome::Known) => {
+                if is_block_new {
+                    metrics::increment_counter!(DUPLICATE_BLOCKS);
+                } else {
+                    metrics::increment_counter!(DUPLICATE_SYNC_BLOCKS);
+                }
             }
-        }
+            Ok(BlockOutcome::Orphan) => {
+                // The orphan is kept in storage until its ancestors arrive; a new block whose parent is
+                // unknown means that the node fell behind the sender, so catch up with it right away.
+                debug!(
+                    "Block {} from {} is an orphan; its parent {} is unknown",
+                    block_struct.header.get_hash(),
+                    remote_address,
+                    block_struct.header.previous_block_hash,
+                );
+                if is_block_new {
+                    self.trigger_sync(Some(remote_address));
+                }
+            }
+            Ok(outcome @ BlockOutcome::Canon)
+            | Ok(outcome @ BlockOutcome::SideChain)
+            | Ok(outcome @ BlockOutcome::Reorganized) => {
+                self.report_fork_choice(&block_struct.header.get_hash());
+                self.report_accepted_block(block_struct.header.get_hash(), outcome, Some(remote_address));
+                self.settle_relayed_transactions(&block_struct).await;
+                self.expect_sync().reset_mempool_sync_backoff();
+
+                // This is a non-sync Block, send it to our peers.
+                if is_block_new && self.message_policy().relays_blocks() {
+                    self.propagate_block(block_struct.header.get_hash(), block, remote_address)
+                        .await;
+                } else {
+                    // If it's a valid SyncBlock or a block that isn't relayed, bump block height.
+                    metrics::increment_counter!(BLOCK_HEIGHT);
+                }
+            }
+            Err(e) if e.is_invalid_block() => {
+                warn!("Received an invalid block from {}: {}", remote_address, e);
 
-        if block_validity.is_ok() {
-            // This is a non-sync Block, send it to our peers.
-            if is_block_new {
-                self.propagate_block(block, remote_address).await;
-            } else {
-                // If it's a valid SyncBlock, bump block height.
-                metrics::increment_counter!(BLOCK_HEIGHT);
+                // Invalid blocks can't be relayed by honest peers, so penalize the sender.
+                if let Some(peer) = self.peer_book.get_peer_handle(remote_address) {
+                    peer.misbehaved(Misbehavior::InvalidBlock, e.to_string()).await;
+                }
+            }
+            Err(e) => {
+                error!("Failed to process a block from {}: {}", remote_address, e);
             }
         }
 
         Ok(())
     }
 
+    /// Records the fork choice caused by the given block in the journal, if it extended a side chain.
+    fn report_fork_choice(&self, block_hash: &BlockHeaderHash) {
+        let fork_choice = self
+            .expect_sync()
+            .consensus
+            .fork_choices
+            .lock()
+            .unwrap()
+            .back()
+            .filter(|fork_choice| fork_choice.block_hash == *block_hash)
+            .cloned();
+
+        if let Some(fork_choice) = fork_choice {
+            self.notify_webhook(
+                WebhookEvent::PotentialFork,
+                serde_json::json!({
+                    "block_hash": fork_choice.block_hash.to_string(),
+                    "shared_block_height": fork_choice.shared_block_height,
+                    "side_chain_height": fork_choice.side_chain_height,
+                    "canon_height": fork_choice.canon_height,
+                    "outcome": fork_choice.outcome.to_string(),
+                }),
+            );
+        }
+    }
+
+    /// Records a reorganization of the canon chain in the journal.
+    pub(crate) fn report_reorg(&self, reorg: &ReorgEvent) {
+        warn!(
+            "The canon chain was reorganized from block {} (height {}) to block {} (height {})",
+            reorg.old_tip, reorg.old_height, reorg.new_tip, reorg.new_height
+        );
+
+        self.notify_webhook(
+            WebhookEvent::Reorg,
+            serde_json::json!({
+                "old_tip": reorg.old_tip.to_string(),
+                "old_height": reorg.old_height,
+                "new_tip": reorg.new_tip.to_string(),
+                "new_height": reorg.new_height,
+                "shared_block_height": reorg.shared_block_height,
+            }),
+        );
+    }
+
     /// A peer has requested a block.
     pub(crate) async fn received_get_blocks(
         &self,
         remote_address: SocketAddr,
         header_hashes: Vec<BlockHeaderHash>,
     ) -> Result<(), NetworkError> {
-        for hash in header_hashes.into_iter().take(crate::MAX_BLOCK_SYNC_COUNT as usize) {
-            let block = self.expect_sync().storage().get_block(&hash)?;
+        let ledger = self.expect_sync().consensus.ledger.clone();
+        let requested = header_hashes.len().min(crate::MAX_BLOCK_SYNC_COUNT as usize) as u32;
+
+        // The pruned blocks can't be served; the peer should sync them from the nodes advertising that they have them.
+        // Otherwise, only the blocks within the peer's serving quota are served; the rest are rejected.
+        let granted = if header_hashes.iter().any(|hash| ledger.is_block_pruned(hash)) {
+            debug!("Refusing to serve pruned blocks requested by {}", remote_address);
+            0
+        } else {
+            self.grant_sync_serving(remote_address, ServedItems::Blocks, requested)
+                .await
+        };
+
+        // Read the blocks concurrently, but send them in the requested order as soon as they're available.
+        let mut blocks = stream::iter(header_hashes.into_iter().take(granted as usize))
+            .map(|hash| {
+                let ledger = ledger.clone();
+                task::spawn_blocking(move || -> Result<Vec<u8>, NetworkError> {
+                    Ok(ledger.get_block(&hash)?.serialize()?)
+                })
+            })
+            .buffered(crate::MAX_CONCURRENT_BLOCK_READS);
+
+        let mut served = 0;
+        while let Some(block) = blocks.next().await {
+            match block
+                .map_err(|e| NetworkError::from(std::io::Error::from(e)))
+                .and_then(|block| block)
+            {
+                Ok(block) => {
+                    // Send a `SyncBlock` message to the connected peer.
+                    self.peer_book.send_to(remote_address, Payload::SyncBlock(block)).await;
+                    served += 1;
+                }
+                Err(e) => {
+                    error!("Failed to read a block requested by {}: {}", remote_address, e);
+                    break;
+                }
+            }
+        }
+
+        // The blocks that aren't served are declined with empty `SyncBlock`s, so that the peer can request them from
+        // other peers right away instead of waiting for them, and doesn't consider this node to have stalled.
+        for _ in served..requested {
+            self.peer_book.send_to(remote_address, Payload::SyncBlock(vec![])).await;
+        }
+
+        Ok(())
+    }
+
+    /// A peer has announced a new block; request it unless it's already known or being requested.
+    pub(crate) async fn received_block_hash(&self, remote_address: SocketAddr, block_hash: BlockHeaderHash) {
+        let sync = self.expect_sync();
+
+        // While syncing, the block is bound to be received as a `SyncBlock` later.
+        if self.is_syncing_blocks() || sync.storage().block_hash_exists(&block_hash) {
+            return;
+        }
 
-            // Send a `SyncBlock` message to the connected peer.
+        if sync.register_block_request(block_hash.clone()) {
             self.peer_book
-                .send_to(remote_address, Payload::SyncBlock(block.serialize()?))
+                .send_to(remote_address, Payload::GetBlock(block_hash))
                 .await;
         }
+    }
+
+    /// A peer has requested a block it was announced.
+    pub(crate) async fn received_get_block(
+        &self,
+        remote_address: SocketAddr,
+        block_hash: BlockHeaderHash,
+    ) -> Result<(), NetworkError> {
+        let ledger = self.expect_sync().consensus.ledger.clone();
+
+        // A pruned block can't be served; the peer should fetch it from the nodes advertising that they have it.
+        if ledger.is_block_pruned(&block_hash) {
+            debug!("Refusing to serve a pruned block requested by {}", remote_address);
+            return Ok(());
+        }
+
+        // The announced blocks are counted against a cap of their own, so that they don't deplete the quota for the
+        // ones requested while syncing, and vice versa.
+        let granted = self
+            .grant_sync_serving(remote_address, ServedItems::AnnouncedBlocks, 1)
+            .await;
+        if granted == 0 {
+            return Ok(());
+        }
+
+        let block = task::spawn_blocking(move || -> Result<Vec<u8>, NetworkError> {
+            Ok(ledger.get_block(&block_hash)?.serialize()?)
+        })
+        .await
+        .map_err(std::io::Error::from)??;
+
+        // Send a `Block` message, so that the peer processes it as a new block.
+        self.peer_book.send_to(remote_address, Payload::Block(block)).await;
 
         Ok(())
     }
@@ -164,44 +335,67 @@ impl<S: Storage + Send + std::marker::Sync + 'static> Node<S> {
         remote_address: SocketAddr,
         block_locator_hashes: Vec<BlockHeaderHash>,
     ) -> Result<(), NetworkError> {
-        let sync = {
+        let (shared_height, current_height) = {
             let storage = self.expect_sync().storage();
 
             let latest_shared_hash = storage.get_latest_shared_hash(block_locator_hashes)?;
             let current_height = storage.get_current_block_height();
 
-            if let Ok(height) = storage.get_block_number(&latest_shared_hash) {
-                if height < current_height {
-                    let mut max_height = current_height;
-
-                    // if the requester is behind more than MAX_BLOCK_SYNC_COUNT blocks
-                    if current_height > height + crate::MAX_BLOCK_SYNC_COUNT {
-                        // send no more than MAX_BLOCK_SYNC_COUNT
-                        max_height = height + crate::MAX_BLOCK_SYNC_COUNT;
-                    }
+            (storage.get_block_number(&latest_shared_hash).ok(), current_height)
+        };
 
-                    let mut block_hashes = Vec::with_capacity((max_height - height) as usize);
+        let sync = match shared_height {
+            Some(height) if height < current_height => {
+                // send no more than the configured number of hashes, possibly fewer at random, and only
+                // the ones within the peer's serving quota
+                let len = self.config.sync_response_shaping.response_len(current_height - height);
+                let len = self.grant_sync_serving(remote_address, ServedItems::Hashes, len).await;
 
-                    for block_num in height + 1..=max_height {
-                        block_hashes.push(storage.get_block_hash(block_num)?);
-                    }
+                let storage = self.expect_sync().storage();
+                let mut block_hashes = Vec::with_capacity(len as usize);
 
-                    // send block hashes to requester
-                    block_hashes
-                } else {
-                    vec![]
+                for block_num in height + 1..=height + len {
+                    block_hashes.push(storage.get_block_hash(block_num)?);
                 }
-            } else {
-                vec![]
+
+                // send block hashes to requester
+                block_hashes
             }
+            _ => vec![],
         };
+        let shared_height = shared_height.map(|height| (height, current_height.saturating_sub(height)));
 
         // send a `Sync` message to the connected peer.
         self.peer_book.send_to(remote_address, Payload::Sync(sync)).await;
 
+        // the query patterns are tracked to detect peers fingerprinting or scraping the chain
+        if let (Some((height, depth)), Some(peer)) = (shared_height, self.peer_book.get_peer_handle(remote_address)) {
+            peer.received_sync_query(height, depth).await;
+        }
+
         Ok(())
     }
 
+    /// Counts the given number of blocks or block hashes requested by a peer against its serving quota, and
+    /// returns the number of them that can be served.
+    async fn grant_sync_serving(&self, remote_address: SocketAddr, items: ServedItems, requested: u32) -> u32 {
+        match self.peer_book.get_peer_handle(remote_address) {
+            Some(peer) => {
+                peer.request_serving(items, requested, self.config.sync_serving_quota)
+                    .await
+            }
+            None => 0,
+        }
+    }
+
+    /// A peer has declined to serve one of the sync blocks requested from it.
+    pub(crate) async fn declined_sync_block(&self, remote_address: SocketAddr) {
+        let sender = self.master_dispatch.read().await;
+        if let Some(sender) = &*sender {
+            sender.send(SyncInbound::DeclinedBlock(remote_address)).await.ok();
+        }
+    }
+
     /// A peer has sent us their chain state.
     pub(crate) async fn received_sync(&self, remote_address: SocketAddr, block_hashes: Vec<BlockHeaderHash>) {
         let sender = self.master_dispatch.read().await;
@@ -0,0 +1,74 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::Node;
+use snarkvm_dpc::{BlockHeaderHash, Storage};
+
+use serde::{Deserialize, Serialize};
+
+/// A record of an ongoing block sync, persisted so that the sync can be resumed after a restart.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncCheckpoint {
+    /// The hash of the latest canon block at the moment the current batch was requested.
+    pub last_verified: Option<BlockHeaderHash>,
+    /// The hashes of the blocks in the current batch that are yet to be processed.
+    pub pending: Vec<BlockHeaderHash>,
+}
+
+impl<S: Storage + Send + Sync + 'static> Node<S> {
+    /// Returns the stored sync checkpoint, if there is one.
+    pub fn sync_checkpoint(&self) -> Option<SyncCheckpoint> {
+        let serialized = match self.expect_sync().storage().get_sync_checkpoint() {
+            Ok(serialized) => serialized?,
+            Err(e) => {
+                error!("Couldn't load the sync checkpoint: {}", e);
+                return None;
+            }
+        };
+
+        match bincode::deserialize(&serialized) {
+            Ok(checkpoint) => Some(checkpoint),
+            Err(e) => {
+                warn!("Discarding a corrupt sync checkpoint: {}", e);
+                self.clear_sync_checkpoint();
+                None
+            }
+        }
+    }
+
+    /// Persists the given sync checkpoint, replacing the previous one.
+    pub(crate) fn save_sync_checkpoint(&self, checkpoint: &SyncCheckpoint) {
+        let result = bincode::serialize(checkpoint)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| {
+                self.expect_sync()
+                    .storage()
+                    .store_sync_checkpoint(bytes)
+                    .map_err(|e| e.to_string())
+            });
+
+        if let Err(e) = result {
+            error!("Couldn't store the sync checkpoint: {}", e);
+        }
+    }
+
+    /// Removes the stored sync checkpoint.
+    pub(crate) fn clear_sync_checkpoint(&self) {
+        if let Err(e) = self.expect_sync().storage().clear_sync_checkpoint() {
+            error!("Couldn't clear the sync checkpoint: {}", e);
+        }
+    }
+}
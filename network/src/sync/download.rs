@@ -0,0 +1,342 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkvm_algorithms::crh::double_sha256;
+use snarkvm_dpc::{BlockHeader, BlockHeaderHash};
+
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+/// Returns the hash of a serialized block, if it's long enough to contain a header.
+pub fn serialized_block_hash(block: &[u8]) -> Option<BlockHeaderHash> {
+    block
+        .get(..BlockHeader::size())
+        .map(|header| BlockHeaderHash(double_sha256(header)))
+}
+
+/// The blocks requested from a single peer during a download.
+#[derive(Debug)]
+struct PeerDownload {
+    /// The requested blocks the peer hasn't delivered yet.
+    outstanding: HashSet<BlockHeaderHash>,
+    /// The time of the latest request sent to the peer or block received from it.
+    last_progress: Instant,
+    /// Indicates whether the peer has stopped delivering its blocks.
+    is_stalled: bool,
+}
+
+/// Schedules the download of a batch of sync blocks across the peers that can serve them.
+///
+/// Each block is requested from the least busy of its sources. If a peer doesn't deliver any of its
+/// blocks for a while, it's considered stalled, and its outstanding blocks are requested from other sources.
+#[derive(Debug)]
+pub struct BlockDownload {
+    /// The blocks to download, in the order they're requested in.
+    order: Vec<BlockHeaderHash>,
+    /// The peers able to serve each of the blocks that weren't received yet.
+    sources: HashMap<BlockHeaderHash, Vec<SocketAddr>>,
+    /// The peer each of the blocks that weren't received yet was last requested from.
+    assignments: HashMap<BlockHeaderHash, SocketAddr>,
+    /// The state of the download from each of the peers that were sent a request.
+    peers: HashMap<SocketAddr, PeerDownload>,
+}
+
+impl BlockDownload {
+    /// Creates a download of the given blocks, which can be requested from the given peers.
+    pub fn new(blocks: &[BlockHeaderHash], block_peer_map: &HashMap<BlockHeaderHash, Vec<SocketAddr>>) -> Self {
+        let sources = blocks
+            .iter()
+            .filter_map(|hash| Some((hash.clone(), block_peer_map.get(hash)?.clone())))
+            .filter(|(_, peers)| !peers.is_empty())
+            .collect();
+
+        Self {
+            order: blocks.to_vec(),
+            sources,
+            assignments: Default::default(),
+            peers: Default::default(),
+        }
+    }
+
+    /// Assigns all the blocks to their sources, returning the requests to send to each of the peers.
+    pub fn schedule(&mut self) -> HashMap<SocketAddr, Vec<BlockHeaderHash>> {
+        let unassigned = self
+            .order
+            .iter()
+            .filter(|hash| self.sources.contains_key(hash) && !self.assignments.contains_key(hash))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        self.assign(unassigned, Instant::now())
+    }
+
+    /// Registers a block received from the given peer; returns `false` if it wasn't expected, e.g. if
+    /// it was already delivered by another peer it was requested from.
+    pub fn received(&mut self, address: SocketAddr, hash: &BlockHeaderHash) -> bool {
+        if let Some(peer) = self.peers.get_mut(&address) {
+            peer.last_progress = Instant::now();
+            // a late delivery means that the peer is making progress again
+            peer.is_stalled = false;
+        }
+
+        if self.sources.remove(hash).is_none() {
+            return false;
+        }
+        self.assignments.remove(hash);
+        // the block may have been requested from several peers
+        for peer in self.peers.values_mut() {
+            peer.outstanding.remove(hash);
+        }
+
+        true
+    }
+
+    /// Marks the peers that haven't delivered any of their blocks within the given time as stalled, and
+    /// reassigns their outstanding blocks to other sources, returning the new requests to send.
+    pub fn reassign_stalled(&mut self, stall_timeout: Duration) -> HashMap<SocketAddr, Vec<BlockHeaderHash>> {
+        let now = Instant::now();
+
+        let mut stalled = HashSet::new();
+        for (address, peer) in self.peers.iter_mut() {
+            if peer.is_stalled || peer.outstanding.is_empty() || now - peer.last_progress < stall_timeout {
+                continue;
+            }
+
+            warn!(
+                "{} didn't deliver any of its {} outstanding sync blocks in time; requesting them from other peers",
+                address,
+                peer.outstanding.len()
+            );
+            peer.is_stalled = true;
+            stalled.insert(*address);
+        }
+
+        self.reassign_from(&stalled, now)
+    }
+
+    /// Registers that the given peer declined to serve some of its blocks, e.g. as they exceed its serving quota.
+    /// It's treated like a stalled peer, so its outstanding blocks are requested from other sources right away;
+    /// returns the new requests to send.
+    pub fn declined(&mut self, address: SocketAddr) -> HashMap<SocketAddr, Vec<BlockHeaderHash>> {
+        match self.peers.get_mut(&address) {
+            Some(peer) if !peer.is_stalled => peer.is_stalled = true,
+            _ => return HashMap::new(),
+        }
+
+        let declining = [address].iter().copied().collect();
+        self.reassign_from(&declining, Instant::now())
+    }
+
+    /// Returns `true` if all the blocks were received.
+    pub fn is_complete(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// Returns `true` if none of the blocks that weren't received yet is requested from a peer that isn't stalled.
+    pub fn is_stuck(&self) -> bool {
+        self.assignments
+            .values()
+            .all(|address| self.peers.get(address).map(|peer| peer.is_stalled).unwrap_or(true))
+    }
+
+    /// Returns the peer the given block was last requested from, if it wasn't received yet.
+    pub fn assignee(&self, hash: &BlockHeaderHash) -> Option<SocketAddr> {
+        self.assignments.get(hash).copied()
+    }
+
+    /// Returns all the peers that were sent a request.
+    pub fn requested_peers(&self) -> Vec<SocketAddr> {
+        self.peers.keys().copied().collect()
+    }
+
+    /// Returns the peers that stalled during the download.
+    pub fn stalled_peers(&self) -> Vec<SocketAddr> {
+        self.peers
+            .iter()
+            .filter(|(_, peer)| peer.is_stalled)
+            .map(|(address, _)| *address)
+            .collect()
+    }
+
+    /// Reassigns the blocks last requested from any of the given peers to other sources.
+    fn reassign_from(
+        &mut self,
+        peers: &HashSet<SocketAddr>,
+        now: Instant,
+    ) -> HashMap<SocketAddr, Vec<BlockHeaderHash>> {
+        let reassigned = self
+            .order
+            .iter()
+            .filter(|hash| {
+                self.assignments
+                    .get(hash)
+                    .map(|address| peers.contains(address))
+                    .unwrap_or(false)
+            })
+            .cloned()
+            .collect();
+        self.assign(reassigned, now)
+    }
+
+    /// Assigns each of the given blocks to the least busy of its sources that didn't stall; blocks with
+    /// no other sources stay with their current peer.
+    fn assign(&mut self, blocks: Vec<BlockHeaderHash>, now: Instant) -> HashMap<SocketAddr, Vec<BlockHeaderHash>> {
+        let mut requests: HashMap<SocketAddr, Vec<BlockHeaderHash>> = HashMap::new();
+
+        for hash in blocks {
+            let sources = match self.sources.get(&hash) {
+                Some(sources) => sources,
+                None => continue,
+            };
+            let current = self.assignments.get(&hash).copied();
+
+            let peers = &self.peers;
+            let candidate = sources
+                .iter()
+                .filter(|address| Some(**address) != current)
+                .filter(|address| !peers.get(address).map(|peer| peer.is_stalled).unwrap_or(false))
+                // ties go to the first listed source
+                .min_by_key(|address| peers.get(address).map(|peer| peer.outstanding.len()).unwrap_or(0))
+                .copied();
+
+            let address = match candidate {
+                Some(address) => address,
+                None => continue,
+            };
+
+            // the block stays outstanding with the previous peer too, as it may still deliver it
+            self.assignments.insert(hash.clone(), address);
+            let peer = self.peers.entry(address).or_insert_with(|| PeerDownload {
+                outstanding: Default::default(),
+                last_progress: now,
+                is_stalled: false,
+            });
+            if peer.outstanding.is_empty() {
+                peer.last_progress = now;
+            }
+            peer.outstanding.insert(hash.clone());
+            requests.entry(address).or_default().push(hash);
+        }
+
+        requests
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(n: u8) -> BlockHeaderHash {
+        BlockHeaderHash([n; 32])
+    }
+
+    fn addr(port: u16) -> SocketAddr {
+        ([127, 0, 0, 1], port).into()
+    }
+
+    fn download(sources: &[(u8, &[u16])]) -> BlockDownload {
+        let blocks = sources.iter().map(|(n, _)| hash(*n)).collect::<Vec<_>>();
+        let block_peer_map = sources
+            .iter()
+            .map(|(n, ports)| (hash(*n), ports.iter().map(|port| addr(*port)).collect()))
+            .collect();
+
+        BlockDownload::new(&blocks, &block_peer_map)
+    }
+
+    #[test]
+    fn blocks_are_split_across_peers() {
+        let mut download = download(&[(1, &[1, 2]), (2, &[1, 2]), (3, &[1, 2]), (4, &[1, 2])]);
+        let requests = download.schedule();
+
+        assert_eq!(requests[&addr(1)], vec![hash(1), hash(3)]);
+        assert_eq!(requests[&addr(2)], vec![hash(2), hash(4)]);
+    }
+
+    #[test]
+    fn blocks_are_only_requested_from_their_sources() {
+        let mut download = download(&[(1, &[1]), (2, &[1]), (3, &[1, 2])]);
+        let requests = download.schedule();
+
+        assert_eq!(requests[&addr(1)], vec![hash(1), hash(2)]);
+        assert_eq!(requests[&addr(2)], vec![hash(3)]);
+    }
+
+    #[test]
+    fn stalled_peers_blocks_are_requested_from_others() {
+        let mut download = download(&[(1, &[1, 2]), (2, &[1, 2]), (3, &[1])]);
+        download.schedule();
+
+        assert!(download.received(addr(2), &hash(2)));
+        assert!(download.reassign_stalled(Duration::from_secs(60)).is_empty());
+
+        // the first peer delivered nothing; its only block with another source is requested again
+        let requests = download.reassign_stalled(Duration::from_secs(0));
+        assert_eq!(download.stalled_peers(), vec![addr(1)]);
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[&addr(2)], vec![hash(1)]);
+        assert_eq!(download.assignee(&hash(1)), Some(addr(2)));
+        assert_eq!(download.assignee(&hash(3)), Some(addr(1)));
+        assert!(!download.is_stuck());
+
+        // the block is accepted from the stalled peer too, but only once
+        assert!(download.received(addr(1), &hash(1)));
+        assert!(!download.received(addr(2), &hash(1)));
+        assert!(!download.is_complete());
+
+        // the remaining block can't be requested from anyone else
+        assert!(download.reassign_stalled(Duration::from_secs(0)).is_empty());
+        assert!(download.received(addr(1), &hash(3)));
+        assert!(download.is_complete());
+    }
+
+    #[test]
+    fn declined_blocks_are_requested_from_others() {
+        let mut download = download(&[(1, &[1, 2]), (2, &[1, 2]), (3, &[1])]);
+        download.schedule();
+
+        // the first peer declined its blocks; the ones with other sources are requested from them right away
+        let requests = download.declined(addr(1));
+        assert_eq!(download.stalled_peers(), vec![addr(1)]);
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[&addr(2)], vec![hash(1)]);
+        assert_eq!(download.assignee(&hash(3)), Some(addr(1)));
+        assert!(!download.is_stuck());
+
+        // further declines from the same peer, or from a peer that wasn't sent a request, change nothing
+        assert!(download.declined(addr(1)).is_empty());
+        assert!(download.declined(addr(3)).is_empty());
+    }
+
+    #[test]
+    fn download_is_stuck_if_all_assignees_stalled() {
+        let mut download = download(&[(1, &[1]), (2, &[1])]);
+        download.schedule();
+
+        assert!(!download.is_stuck());
+        download.reassign_stalled(Duration::from_secs(0));
+        assert!(download.is_stuck());
+    }
+
+    #[test]
+    fn serialized_block_hash_requires_a_header() {
+        assert!(serialized_block_hash(&[0u8; 10]).is_none());
+        assert!(serialized_block_hash(&vec![0u8; BlockHeader::size()]).is_some());
+    }
+}
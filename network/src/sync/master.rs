@@ -20,21 +20,56 @@ use std::{
     time::Duration,
 };
 
-use crate::{NetworkError, Node, Payload, Peer};
+use crate::{serialized_block_hash, BlockDownload, Misbehavior, NetworkError, Node, Payload, Peer, SyncCheckpoint};
 use futures::{pin_mut, select, FutureExt};
-use rand::prelude::SliceRandom;
-use snarkvm_algorithms::crh::double_sha256;
-use snarkvm_dpc::{BlockHeader, BlockHeaderHash, Storage};
-use tokio::{sync::mpsc, time::Instant};
+use snarkvm_dpc::{testnet1::instantiated::Tx, Block, BlockHeaderHash, Storage};
+use tokio::{sync::mpsc, task, time::Instant};
 
 pub enum SyncInbound {
     BlockHashes(SocketAddr, Vec<BlockHeaderHash>),
     Block(SocketAddr, Vec<u8>),
+    /// The peer declined to serve one of the requested blocks.
+    DeclinedBlock(SocketAddr),
+}
+
+/// The assessment of a peer's `Sync` response to a `GetSync` request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncResponse {
+    /// The peer has no blocks past our block locator hashes, i.e. it isn't ahead of us.
+    Empty,
+    /// The peer shared fewer than `MAX_BLOCK_SYNC_COUNT` hashes, i.e. it's close to our height.
+    Partial,
+    /// The peer shared a full batch of hashes.
+    Full,
+    /// The peer shared more hashes than the protocol permits.
+    Oversized,
+}
+
+impl SyncResponse {
+    pub fn classify(block_hashes: &[BlockHeaderHash]) -> Self {
+        let max_count = crate::MAX_BLOCK_SYNC_COUNT as usize;
+
+        match block_hashes.len() {
+            0 => Self::Empty,
+            n if n < max_count => Self::Partial,
+            n if n == max_count => Self::Full,
+            _ => Self::Oversized,
+        }
+    }
+
+    /// Returns `true` if the hashes can be used to request sync blocks.
+    pub fn is_usable(self) -> bool {
+        matches!(self, Self::Partial | Self::Full)
+    }
 }
 
 pub struct SyncMaster<S: Storage + Send + Sync + 'static> {
     node: Node<S>,
     incoming: mpsc::Receiver<SyncInbound>,
+    /// The peers that were already sent a `GetSync` during this sync attempt.
+    requested: HashSet<SocketAddr>,
+    /// The only peer to sync with, if the sync was requested with a specific one.
+    target: Option<SocketAddr>,
 }
 
 struct SyncBlock {
@@ -43,21 +78,48 @@ struct SyncBlock {
 }
 
 impl<S: Storage + Send + Sync + 'static> SyncMaster<S> {
-    pub fn new(node: Node<S>) -> (Self, mpsc::Sender<SyncInbound>) {
+    pub fn new(node: Node<S>, target: Option<SocketAddr>) -> (Self, mpsc::Sender<SyncInbound>) {
         let (sender, receiver) = mpsc::channel(256);
         let new = Self {
             node,
             incoming: receiver,
+            requested: HashSet::new(),
+            target,
         };
         (new, sender)
     }
 
     async fn find_sync_nodes(&mut self) -> Vec<Peer> {
         let our_block_height = self.node.expect_sync().current_block_height();
+
+        // the node only syncs once the network as a whole is ahead of it, so that a single peer reporting an
+        // inflated block height can't make it sync
+        if self.target.is_none() {
+            match self.node.peer_book.network_height().await {
+                Some(network_height) if network_height > our_block_height + 1 => {}
+                _ => return vec![],
+            }
+        }
+
         let mut interesting_peers = vec![];
         for mut node in self.node.peer_book.connected_peers_snapshot().await {
-            let judge_bad = node.judge_bad();
-            if !judge_bad && node.quality.block_height > our_block_height + 1 {
+            let already_requested = self.requested.contains(&node.address);
+            if let Some(target) = self.target {
+                // the requested peer is asked regardless of the block height it last reported
+                if node.address == target && !already_requested {
+                    interesting_peers.push(node);
+                }
+                continue;
+            }
+            let judge_bad = node.judge_bad(&self.node.config.peer_score_weights);
+            // pruned peers can't serve the blocks that directly follow ours
+            let has_next_blocks = node.quality.earliest_block_height <= our_block_height + 1;
+            if !judge_bad
+                && !already_requested
+                && has_next_blocks
+                && !node.quality.is_watcher
+                && node.quality.block_height > our_block_height + 1
+            {
                 interesting_peers.push(node);
             }
         }
@@ -71,6 +133,14 @@ impl<S: Storage + Send + Sync + 'static> SyncMaster<S> {
             interesting_peers.truncate(i + 1);
         }
 
+        // only the most preferred peers are synced with; the ones that stalled recently are rotated away from
+        let node = &self.node;
+        let mut interesting_peers = node
+            .config
+            .peer_selection
+            .rank_sync_nodes(interesting_peers, &|addr| node.is_stalled_sync_node(addr));
+        interesting_peers.truncate(crate::MAX_SYNC_NODES);
+
         info!("found {} interesting peers for sync", interesting_peers.len());
         debug!("sync interesting peers = {:?}", interesting_peers);
 
@@ -95,6 +165,7 @@ impl<S: Storage + Send + Sync + 'static> SyncMaster<S> {
         let mut future_set = vec![];
         for peer in sync_nodes.iter() {
             if let Some(handle) = self.node.peer_book.get_peer_handle(peer.address) {
+                self.requested.insert(peer.address);
                 let block_locator_hashes = block_locator_hashes.clone();
                 future_set.push(async move {
                     handle.send_payload(Payload::GetSync(block_locator_hashes)).await;
@@ -140,18 +211,28 @@ impl<S: Storage + Send + Sync + 'static> SyncMaster<S> {
     async fn receive_sync_hashes(&mut self, max_message_count: usize) -> HashMap<SocketAddr, Vec<BlockHeaderHash>> {
         const TIMEOUT: u64 = 5;
         let mut received_block_hashes = HashMap::new();
+        let mut unhelpful_peers = vec![];
 
         self.receive_messages(TIMEOUT, TIMEOUT, |msg| {
             match msg {
                 SyncInbound::BlockHashes(addr, hashes) => {
-                    received_block_hashes.insert(addr, hashes);
+                    let response = SyncResponse::classify(&hashes);
+                    if response.is_usable() {
+                        received_block_hashes.insert(addr, hashes);
+                    } else {
+                        unhelpful_peers.push((addr, response));
+                    }
                 }
                 SyncInbound::Block(_, _) => {
                     warn!("received sync block prematurely");
                 }
+                SyncInbound::DeclinedBlock(_) => {
+                    // late, ignored
+                }
             }
             //todo: fail if peer sends > 1 block hash packet
-            received_block_hashes.len() >= max_message_count
+            // unhelpful responses count too, so that there's no need to wait for the timeout
+            received_block_hashes.len() + unhelpful_peers.len() >= max_message_count
         })
         .await;
 
@@ -162,25 +243,82 @@ impl<S: Storage + Send + Sync + 'static> SyncMaster<S> {
             TIMEOUT
         );
 
+        let our_block_height = self.node.expect_sync().current_block_height();
+        for (addr, response) in unhelpful_peers {
+            let handle = match self.node.peer_book.get_peer_handle(addr) {
+                Some(handle) => handle,
+                None => continue,
+            };
+
+            if response == SyncResponse::Empty {
+                debug!("{} has no sync blocks to share; it is synced with us", addr);
+                handle.synced_with_us(our_block_height).await;
+            } else {
+                let details = format!("more than {} sync hashes", crate::MAX_BLOCK_SYNC_COUNT);
+                handle.misbehaved(Misbehavior::OversizedSync, details).await;
+            }
+        }
+
         received_block_hashes
     }
 
-    async fn receive_sync_blocks(&mut self, block_count: usize) -> Vec<SyncBlock> {
+    /// Receives the blocks of the download, requesting the ones assigned to stalled peers from other peers,
+    /// until all of them arrive, the peers asked for the remaining ones all stall, or the batch expires.
+    async fn receive_sync_blocks(&mut self, download: &mut BlockDownload) -> HashMap<BlockHeaderHash, SyncBlock> {
         const TIMEOUT: u64 = 30;
-        let mut blocks = vec![];
+        let end = Instant::now() + Duration::from_secs(TIMEOUT);
+        let stall_timeout = Duration::from_secs(crate::SYNC_BLOCK_STALL_SECS as u64);
+        let mut blocks = HashMap::new();
+
+        while !download.is_complete() {
+            let timeout = tokio::time::sleep_until(end.min(Instant::now() + Duration::from_secs(1))).fuse();
+            pin_mut!(timeout);
+            let msg = select! {
+                msg = self.incoming.recv().fuse() => match msg {
+                    Some(msg) => Some(msg),
+                    None => break,
+                },
+                _ = timeout => None,
+            };
 
-        self.receive_messages(TIMEOUT, 4, |msg| {
             match msg {
-                SyncInbound::BlockHashes(_, _) => {
+                Some(SyncInbound::Block(address, block)) => match serialized_block_hash(&block) {
+                    Some(hash) if download.received(address, &hash) => {
+                        blocks.insert(hash, SyncBlock { address, block });
+                    }
+                    _ => trace!("ignoring an unexpected or duplicate sync block from {}", address),
+                },
+                Some(SyncInbound::BlockHashes(_, _)) => {
                     // late, ignored
                 }
-                SyncInbound::Block(address, block) => {
-                    blocks.push(SyncBlock { address, block });
+                Some(SyncInbound::DeclinedBlock(address)) => {
+                    let requests = download.declined(address);
+                    if !requests.is_empty() {
+                        debug!(
+                            "{} declined to serve some sync blocks; requesting them from other peers",
+                            address
+                        );
+                    }
+                    self.request_blocks(requests).await;
+
+                    if download.is_stuck() {
+                        break;
+                    }
+                }
+                None => {
+                    if Instant::now() >= end {
+                        break;
+                    }
+
+                    let requests = download.reassign_stalled(stall_timeout);
+                    self.request_blocks(requests).await;
+
+                    if download.is_stuck() {
+                        break;
+                    }
                 }
             }
-            blocks.len() >= block_count
-        })
-        .await;
+        }
 
         info!("received {} blocks in {} seconds", blocks.len(), TIMEOUT);
 
@@ -221,36 +359,6 @@ impl<S: Storage + Send + Sync + 'static> SyncMaster<S> {
         block_peer_map
     }
 
-    fn get_peer_blocks(
-        &mut self,
-        blocks: &[BlockHeaderHash],
-        block_peer_map: &HashMap<BlockHeaderHash, Vec<SocketAddr>>,
-    ) -> (
-        Vec<SocketAddr>,
-        HashMap<BlockHeaderHash, SocketAddr>,
-        HashMap<SocketAddr, Vec<BlockHeaderHash>>,
-    ) {
-        let mut peer_block_requests: HashMap<SocketAddr, Vec<BlockHeaderHash>> = HashMap::new();
-        let mut block_peers = HashMap::new();
-        for block in blocks {
-            let peers = block_peer_map.get(block);
-            if peers.is_none() {
-                continue;
-            }
-            let random_peer = peers.unwrap().choose(&mut rand::thread_rng());
-            if random_peer.is_none() {
-                continue;
-            }
-            block_peers.insert(block.clone(), *random_peer.unwrap());
-            peer_block_requests
-                .entry(*random_peer.unwrap())
-                .or_insert_with(Vec::new)
-                .push(block.clone());
-        }
-        let addresses: Vec<SocketAddr> = peer_block_requests.keys().copied().collect();
-        (addresses, block_peers, peer_block_requests)
-    }
-
     async fn request_blocks(&mut self, peer_block_requests: HashMap<SocketAddr, Vec<BlockHeaderHash>>) -> usize {
         let mut sent = 0usize;
 
@@ -259,7 +367,7 @@ impl<S: Storage + Send + Sync + 'static> SyncMaster<S> {
             if let Some(peer) = self.node.peer_book.get_peer_handle(addr) {
                 sent += request.len();
                 future_set.push(async move {
-                    peer.expecting_sync_blocks(request.len() as u32).await;
+                    peer.expecting_sync_blocks(request.clone()).await;
                     peer.send_payload(Payload::GetBlocks(request)).await;
                 });
             }
@@ -280,19 +388,66 @@ impl<S: Storage + Send + Sync + 'static> SyncMaster<S> {
         futures::future::join_all(future_set).await;
     }
 
-    pub async fn run(mut self) -> Result<(), NetworkError> {
-        let hash_requests_sent = self.send_sync_messages().await;
+    /// Prepares the resumption of a sync that was interrupted, e.g. by a restart of the node;
+    /// the pending blocks are requested from any peers that are ahead of this node.
+    async fn resume_from_checkpoint(
+        &mut self,
+    ) -> Option<(Vec<BlockHeaderHash>, HashMap<BlockHeaderHash, Vec<SocketAddr>>)> {
+        let checkpoint = self.node.sync_checkpoint()?;
 
-        if hash_requests_sent == 0 {
-            return Ok(());
+        let ledger = &self.node.expect_sync().consensus.ledger;
+        let block_order: Vec<BlockHeaderHash> =
+            checkpoint.pending.into_iter().filter(|x| !ledger.is_canon(x)).collect();
+
+        if block_order.is_empty() {
+            self.node.clear_sync_checkpoint();
+            return None;
+        }
+
+        let sync_nodes: Vec<SocketAddr> = self.find_sync_nodes().await.into_iter().map(|x| x.address).collect();
+        if sync_nodes.is_empty() {
+            return None;
         }
 
-        let received_block_hashes = self.receive_sync_hashes(hash_requests_sent).await;
+        info!(
+            "resuming an interrupted sync of {} blocks after block {}",
+            block_order.len(),
+            checkpoint.last_verified.map(|x| x.to_string()).unwrap_or_default(),
+        );
 
-        if received_block_hashes.is_empty() {
-            return Ok(());
+        let block_peer_map = block_order
+            .iter()
+            .map(|hash| (hash.clone(), sync_nodes.clone()))
+            .collect();
+
+        Some((block_order, block_peer_map))
+    }
+
+    fn latest_canon_hash(&self) -> Option<BlockHeaderHash> {
+        let storage = self.node.expect_sync().storage();
+        storage.get_block_hash(storage.get_current_block_height()).ok()
+    }
+
+    pub async fn run(mut self) -> Result<(), NetworkError> {
+        if let Some((block_order, block_peer_map)) = self.resume_from_checkpoint().await {
+            return self.sync_blocks(block_order, block_peer_map).await;
         }
 
+        // If none of the candidates has any usable hashes, try the ones that weren't asked yet.
+        let received_block_hashes = loop {
+            let hash_requests_sent = self.send_sync_messages().await;
+
+            if hash_requests_sent == 0 {
+                return Ok(());
+            }
+
+            let received_block_hashes = self.receive_sync_hashes(hash_requests_sent).await;
+
+            if !received_block_hashes.is_empty() {
+                break received_block_hashes;
+            }
+        };
+
         let blocks = received_block_hashes.into_iter().collect::<Vec<_>>();
 
         let early_blocks = Self::order_block_hashes(&blocks[..]);
@@ -312,46 +467,98 @@ impl<S: Storage + Send + Sync + 'static> SyncMaster<S> {
 
         let block_peer_map = Self::block_peer_map(&blocks[..]);
 
-        let (peer_addresses, block_peers, peer_block_requests) =
-            self.get_peer_blocks(&block_order[..], &block_peer_map);
+        self.sync_blocks(block_order, block_peer_map).await
+    }
+
+    async fn sync_blocks(
+        &mut self,
+        block_order: Vec<BlockHeaderHash>,
+        block_peer_map: HashMap<BlockHeaderHash, Vec<SocketAddr>>,
+    ) -> Result<(), NetworkError> {
+        // Record the batch, so that it can be resumed if the node is stopped before it's processed.
+        let mut checkpoint = SyncCheckpoint {
+            last_verified: self.latest_canon_hash(),
+            pending: block_order.clone(),
+        };
+        self.node.save_sync_checkpoint(&checkpoint);
+
+        // The blocks are split across the peers that can serve them.
+        let mut download = BlockDownload::new(&block_order[..], &block_peer_map);
+        let requests = download.schedule();
 
-        let sent_block_requests = self.request_blocks(peer_block_requests).await;
+        if let Some(peer) = requests
+            .iter()
+            .max_by_key(|(_, hashes)| hashes.len())
+            .map(|(addr, _)| *addr)
+        {
+            self.node.expect_sync().syncing_blocks(peer, block_order.len() as u32);
+        }
 
-        let received_blocks = self.receive_sync_blocks(sent_block_requests).await;
+        let sent_block_requests = self.request_blocks(requests).await;
+
+        let mut blocks_by_hash = self.receive_sync_blocks(&mut download).await;
 
         info!(
             "received {}/{} blocks for sync",
-            received_blocks.len(),
+            blocks_by_hash.len(),
             sent_block_requests
         );
 
-        self.cancel_outstanding_syncs(&peer_addresses[..]).await;
-
-        let mut blocks_by_hash = HashMap::new();
+        self.cancel_outstanding_syncs(&download.requested_peers()).await;
 
-        for block in received_blocks {
-            let block_header = &block.block[..BlockHeader::size()];
-            let hash = BlockHeaderHash(double_sha256(block_header));
-            blocks_by_hash.insert(hash, block);
+        // Verify the transactions of the batch in parallel, ahead of processing the blocks in order.
+        let batch: Vec<Block<Tx>> = block_order
+            .iter()
+            .filter_map(|hash| blocks_by_hash.get(hash))
+            .filter_map(|block| Block::deserialize(&block.block).ok())
+            .collect();
+        let consensus = self.node.expect_sync().consensus.clone();
+        if let Err(e) = task::spawn_blocking(move || consensus.preverify_blocks(&batch)).await {
+            error!("Couldn't pre-verify the sync blocks: {}", e);
         }
 
+        let mut missing_blocks = vec![];
+        let mut stalled_sync_nodes = download.stalled_peers().into_iter().collect::<HashSet<_>>();
+
         for (i, hash) in block_order.iter().enumerate() {
             if let Some(block) = blocks_by_hash.remove(hash) {
                 self.node
                     .process_received_block(block.address, block.block, false)
                     .await?;
             } else {
+                missing_blocks.push(hash.clone());
+                stalled_sync_nodes.extend(download.assignee(hash));
                 warn!(
                     "did not receive block {}/{} '{}' by deadline for sync from {}",
                     i,
                     block_order.len(),
                     hash,
-                    block_peers.get(hash).map(|x| x.to_string()).unwrap_or_default(),
+                    download.assignee(hash).map(|x| x.to_string()).unwrap_or_default(),
                 );
             }
+            self.node.expect_sync().sync_block_settled();
+        }
+
+        self.node.expect_sync().consensus.clear_preverified_blocks();
+
+        // The missing blocks are requested from other sync nodes in the next attempt, if there are any.
+        for addr in stalled_sync_nodes {
+            warn!("{} stalled mid-batch; rotating away from it", addr);
+            self.node.expect_sync().register_stalled_sync_node(addr);
+        }
+
+        // The batch is complete, unless some of the blocks didn't arrive in time.
+        if missing_blocks.is_empty() {
+            self.node.clear_sync_checkpoint();
+            self.node.finish_block_sync();
+            self.node.report_completed_sync().await;
+        } else {
+            checkpoint.last_verified = self.latest_canon_hash();
+            checkpoint.pending = missing_blocks;
+            self.node.save_sync_checkpoint(&checkpoint);
+            self.node.expect_sync().sync_stalled();
         }
 
-        self.node.finished_syncing_blocks();
         Ok(())
     }
 }
@@ -14,15 +14,15 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{message::*, NetworkError, Node};
+use crate::{message::*, NetworkError, Node, RelayCandidate};
 use snarkos_consensus::memory_pool::Entry;
-use snarkvm_dpc::{testnet1::instantiated::Tx, Storage};
+use snarkvm_dpc::{testnet1::instantiated::Tx, Block, Storage, TransactionScheme};
 use snarkvm_utilities::{
     bytes::{FromBytes, ToBytes},
     to_bytes,
 };
 
-use std::net::SocketAddr;
+use std::{collections::HashMap, net::SocketAddr};
 
 impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
     ///
@@ -39,7 +39,8 @@ impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
     }
 
     ///
-    /// Broadcast memory pool transaction to connected peers.
+    /// Broadcast memory pool transaction to connected peers, starting with the ones
+    /// the peer selection strategy expects to propagate it the quickest.
     ///
     pub(crate) async fn propagate_memory_pool_transaction(
         &self,
@@ -50,58 +51,134 @@ impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
 
         let local_address = self.local_address().unwrap();
 
-        for remote_address in self.connected_peers() {
-            if remote_address != transaction_sender && remote_address != local_address {
-                // Send a `Transaction` message to the connected peer.
-                self.peer_book
-                    .send_to(remote_address, Payload::Transaction(transaction_bytes.clone()))
-                    .await;
-            }
+        let candidates = self
+            .connected_peers()
+            .into_iter()
+            .filter(|&remote_address| remote_address != transaction_sender && remote_address != local_address)
+            .collect();
+        let relays = self.config.peer_selection.select_relays(candidates);
+
+        // The degrees of the peers are known from the crawled network, if the node crawls it, and otherwise
+        // from the peers' responses to its `GetPeers` requests.
+        let crawled_degrees = self
+            .known_network()
+            .map(|network| network.degrees())
+            .unwrap_or_default();
+        let qualities: HashMap<SocketAddr, (u64, Option<usize>)> = self
+            .peer_book
+            .connected_peers_snapshot()
+            .await
+            .into_iter()
+            .map(|peer| (peer.address, (peer.quality.rtt_ms, peer.quality.reported_degree)))
+            .collect();
+        let relays = relays
+            .into_iter()
+            .map(|address| {
+                let (rtt_ms, reported_degree) = qualities.get(&address).copied().unwrap_or_default();
+                RelayCandidate {
+                    address,
+                    rtt_ms,
+                    degree: crawled_degrees.get(&address).copied().or(reported_degree),
+                }
+            })
+            .collect();
+
+        for remote_address in self.config.peer_selection.order_transaction_relays(relays) {
+            // Send a `Transaction` message to the connected peer.
+            self.peer_book
+                .send_to(remote_address, Payload::Transaction(transaction_bytes.clone()))
+                .await;
         }
     }
 
     ///
     /// Verifies a received memory pool transaction, adds it to the memory pool,
-    /// and propagates it to peers.
+    /// and propagates it to peers. The transactions refused by the relay policy
+    /// are dropped before they're verified.
     ///
     pub(crate) async fn received_memory_pool_transaction(
         &self,
         source: SocketAddr,
         transaction: Vec<u8>,
     ) -> Result<(), NetworkError> {
+        if let Err(rejection) = self.config.relay_policy.check_size(transaction.len()) {
+            debug!("Refused a transaction from {}, as {}", source, rejection);
+            return Ok(());
+        }
+
         if let Ok(tx) = Tx::read(&*transaction) {
+            if let Err(rejection) = self.config.relay_policy.check(&tx) {
+                debug!("Refused a transaction from {}, as {}", source, rejection);
+                return Ok(());
+            }
+
             let insertion = {
                 let storage = self.expect_sync().storage();
 
                 if !self.expect_sync().consensus.verify_transaction(&tx)? {
                     error!("Received a transaction that was invalid");
+                    self.relayed_transaction_settled(source, false).await;
                     return Ok(());
                 }
 
                 if tx.value_balance.is_negative() {
                     error!("Received a transaction that was a coinbase transaction");
+                    self.relayed_transaction_settled(source, false).await;
                     return Ok(());
                 }
 
-                let entry = Entry::<Tx> {
-                    size_in_bytes: transaction.len(),
-                    transaction: tx,
-                };
+                let entry = Entry::<Tx>::new(tx, transaction.len());
 
                 self.expect_sync().memory_pool().insert(storage, entry).await
             };
 
-            if let Ok(inserted) = insertion {
-                if inserted.is_some() {
+            match insertion {
+                Ok(Some(txid)) => {
                     info!("Transaction added to memory pool.");
-                    self.propagate_memory_pool_transaction(transaction, source).await;
+                    self.expect_sync().track_relayed_transaction(txid, source);
+                    self.expect_sync().reset_mempool_sync_backoff();
+                    if self.message_policy().relays_transactions() {
+                        self.propagate_memory_pool_transaction(transaction, source).await;
+                    }
                 }
+                // The transaction is already known, conflicts with another one, or doesn't fit in the pool.
+                Ok(None) => {}
+                Err(_) => self.relayed_transaction_settled(source, false).await,
             }
+        } else {
+            self.relayed_transaction_settled(source, false).await;
         }
 
         Ok(())
     }
 
+    /// Credits the peers that relayed the transactions included in the given block, and debits the ones
+    /// whose relayed transactions expired without being included in one.
+    pub(crate) async fn settle_relayed_transactions(&self, block: &Block<Tx>) {
+        let txids = block
+            .transactions
+            .iter()
+            .filter_map(|tx| tx.transaction_id().ok())
+            .map(|txid| txid.to_vec())
+            .collect::<Vec<_>>();
+
+        let (accepted, rejected) = self.expect_sync().settle_relayed_transactions(&txids);
+
+        for source in accepted {
+            self.relayed_transaction_settled(source, true).await;
+        }
+        for source in rejected {
+            self.relayed_transaction_settled(source, false).await;
+        }
+    }
+
+    /// Registers the outcome of a transaction relayed by the given peer, if it's still connected.
+    async fn relayed_transaction_settled(&self, source: SocketAddr, accepted: bool) {
+        if let Some(peer) = self.peer_book.get_peer_handle(source) {
+            peer.relayed_transaction_settled(accepted).await;
+        }
+    }
+
     /// A peer has requested our memory pool transactions.
     pub(crate) async fn received_get_memory_pool(&self, remote_address: SocketAddr) {
         // TODO (howardwu): This should have been written with Rayon - it is easily parallelizable.
@@ -125,25 +202,34 @@ impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
         }
     }
 
-    /// A peer has sent us their memory pool transactions.
+    /// A peer has sent us their memory pool transactions; the ones refused by the relay policy are skipped.
     pub(crate) async fn received_memory_pool(&self, transactions: Vec<Vec<u8>>) -> Result<(), NetworkError> {
         let memory_pool = self.expect_sync().memory_pool();
         let storage = self.expect_sync().storage();
 
+        let mut new_transactions = 0;
         for transaction_bytes in transactions {
+            if let Err(rejection) = self.config.relay_policy.check_size(transaction_bytes.len()) {
+                trace!("Skipped a memory pool transaction, as {}", rejection);
+                continue;
+            }
             let transaction: Tx = Tx::read(&transaction_bytes[..])?;
-            let entry = Entry::<Tx> {
-                size_in_bytes: transaction_bytes.len(),
-                transaction,
-            };
+            if let Err(rejection) = self.config.relay_policy.check(&transaction) {
+                trace!("Skipped a memory pool transaction, as {}", rejection);
+                continue;
+            }
+
+            let entry = Entry::<Tx>::new(transaction, transaction_bytes.len());
 
             if let Ok(Some(txid)) = memory_pool.insert(storage, entry).await {
                 debug!(
                     "Transaction added to memory pool with txid: {:?}",
                     hex::encode(txid.clone())
                 );
+                new_transactions += 1;
             }
         }
+        self.expect_sync().record_mempool_novelty(new_transactions);
 
         // Cleanse and store transactions once batch has been received.
         debug!("Cleansing memory pool transactions in database");
@@ -14,107 +14,115 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use std::{sync::Arc, thread, time::Duration};
+use std::sync::Arc;
 
-use futures::executor::block_on;
-use snarkvm_dpc::{testnet1::instantiated::*, AccountAddress, Storage};
-use tokio::task;
+use snarkvm_dpc::{testnet1::instantiated::*, AccountAddress, Block, Storage};
+use tokio::runtime::Handle;
 use tracing::*;
 
-use snarkos_consensus::Miner;
+use snarkos_consensus::{BlockOutcome, MiningController, MiningHooks};
 use snarkos_metrics::{self as metrics, misc::*};
 
-use crate::{Node, State};
+use crate::{NetworkError, Node, State};
 
-/// Parameters for spawning a miner that runs proof of work to find a block.
-pub struct MinerInstance<S: Storage + core::marker::Sync + Send + 'static> {
-    miner_address: AccountAddress<Components>,
+/// Integrates the mining threads with the node: they hold off while it's syncing, and the blocks they find
+/// are propagated to its peers.
+struct NodeMiningHooks<S: Storage + core::marker::Sync + Send + 'static> {
     node: Node<S>,
+    /// The runtime the node's async tasks are run by; the mining threads are outside of it.
+    runtime: Handle,
 }
 
-impl<S: Storage + Send + Sync + 'static> MinerInstance<S> {
-    /// Creates a new MinerInstance for spawning miners.
-    pub fn new(miner_address: AccountAddress<Components>, node: Node<S>) -> Self {
-        Self { miner_address, node }
+impl<S: Storage + Send + Sync + 'static> MiningHooks for NodeMiningHooks<S> {
+    fn is_paused(&self) -> bool {
+        // Don't mine if the node is currently syncing.
+        if self.node.is_shutting_down() || self.node.is_syncing_blocks() {
+            true
+        } else {
+            self.node.set_state(State::Mining);
+            false
+        }
     }
 
-    /// Spawns a new miner on a new thread using MinerInstance parameters.
-    /// Once a block is found, A block message is sent to all peers.
-    /// Calling this function multiple times will spawn additional listeners on separate threads.
-    pub fn spawn(self) -> task::JoinHandle<()> {
-        let local_address = self.node.local_address().unwrap();
-        info!("Initializing Aleo miner - Your miner address is {}", self.miner_address);
-        let miner = Miner::new(
-            self.miner_address.clone(),
-            Arc::clone(&self.node.expect_sync().consensus),
+    fn block_mined(&self, block: &Block<Tx>) {
+        metrics::increment_counter!(BLOCKS_MINED);
+
+        info!("Mined a new block: {:?}", hex::encode(block.header.get_hash().0));
+
+        self.node
+            .report_accepted_block(block.header.get_hash(), BlockOutcome::Canon, None);
+
+        self.runtime.block_on(self.node.settle_relayed_transactions(block));
+
+        let serialized_block = if let Ok(block) = block.serialize() {
+            block
+        } else {
+            error!("Our own miner baked an unserializable block!");
+            return;
+        };
+
+        if let Some(local_address) = self.node.local_address() {
+            self.runtime.block_on(
+                self.node
+                    .propagate_block(block.header.get_hash(), serialized_block, local_address),
+            );
+        }
+    }
+
+    fn time_offset(&self) -> i64 {
+        self.node.clock.offset()
+    }
+
+    fn threads_changed(&self, num_threads: usize) {
+        metrics::gauge!(MINING_THREADS, num_threads as f64);
+    }
+
+    fn proof_rate_changed(&self, proofs_per_hour: u64) {
+        metrics::gauge!(MINING_PROOF_RATE, proofs_per_hour as f64);
+    }
+}
+
+impl<S: Storage + Send + Sync + 'static> Node<S> {
+    /// Allows the node to mine blocks rewarded to the given address; the mining threads are started separately.
+    /// Must be called from within the node's runtime.
+    pub fn enable_mining(&self, miner_address: AccountAddress<Components>) {
+        let hooks = NodeMiningHooks {
+            node: self.clone(),
+            runtime: Handle::current(),
+        };
+        let controller = MiningController::new(
+            miner_address,
+            Arc::clone(&self.expect_sync().consensus),
+            Arc::new(hooks),
         );
-        info!("Miner instantiated; starting to mine blocks");
-
-        let mut mining_failure_count = 0;
-        let mining_failure_threshold = 10;
-
-        task::spawn_blocking(move || {
-            loop {
-                if self.node.is_shutting_down() {
-                    debug!("The node is shutting down, stopping mining");
-                    break;
-                }
-
-                // Don't mine if the node is currently syncing.
-                if self.node.state() == State::Syncing {
-                    thread::sleep(Duration::from_secs(15));
-                    continue;
-                } else {
-                    self.node.set_state(State::Mining);
-                }
-
-                info!("Starting to mine the next block");
-
-                let (block, _coinbase_records) = match block_on(miner.mine_block()) {
-                    Ok(mined_block) => mined_block,
-                    Err(error) => {
-                        // It's possible that the node realized that it needs to sync with another one in the
-                        // meantime; don't change to `Idle` if the current status isn't still `Mining`.
-                        if self.node.state() == State::Mining {
-                            self.node.set_state(State::Idle);
-                        }
-
-                        warn!(
-                            "Miner failed to mine a block {} time(s). (error message: {}).",
-                            mining_failure_count, error
-                        );
-                        mining_failure_count += 1;
-
-                        if mining_failure_count >= mining_failure_threshold {
-                            warn!(
-                                "Miner has failed to mine a block {} times. Shutting down miner.",
-                                mining_failure_count
-                            );
-                            break;
-                        } else {
-                            continue;
-                        }
-                    }
-                };
-
-                // See the `Err` path note above.
-                if self.node.state() == State::Mining {
-                    self.node.set_state(State::Idle);
-                }
-
-                metrics::increment_counter!(BLOCKS_MINED);
-
-                info!("Mined a new block: {:?}", hex::encode(block.header.get_hash().0));
-
-                let serialized_block = if let Ok(block) = block.serialize() {
-                    block
-                } else {
-                    error!("Our own miner baked an unserializable block!");
-                    continue;
-                };
-
-                block_on(self.node.propagate_block(serialized_block, local_address));
-            }
-        })
+
+        if self.mining.set(Arc::new(controller)).is_err() {
+            panic!("mining was enabled more than once!");
+        }
+    }
+
+    /// Returns the mining controller of the node, if mining is enabled.
+    #[inline]
+    pub fn mining(&self) -> Option<&Arc<MiningController<S>>> {
+        self.mining.get()
+    }
+
+    /// Starts mining with the given number of threads, replacing the current ones if the node is already mining.
+    pub fn start_mining(&self, num_threads: usize) -> Result<(), NetworkError> {
+        let mining = self.mining().ok_or(NetworkError::MiningUnavailable)?;
+        mining.start(num_threads)?;
+
+        Ok(())
+    }
+
+    /// Stops mining, interrupting the searches in progress; returns `false` if the node wasn't mining. A block
+    /// that was already found is still submitted.
+    pub fn stop_mining(&self) -> bool {
+        let stopped = self.mining().map(|mining| mining.stop()).unwrap_or(false);
+        if stopped {
+            self.set_state(State::Idle);
+        }
+
+        stopped
     }
 }
@@ -14,17 +14,49 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
+#[cfg(feature = "consensus")]
 pub mod blocks;
+#[cfg(feature = "consensus")]
 pub use blocks::*;
 
+#[cfg(feature = "consensus")]
+pub mod checkpoint;
+#[cfg(feature = "consensus")]
+pub use checkpoint::*;
+
+pub mod download;
+pub use download::*;
+
+#[cfg(feature = "consensus")]
 pub mod memory_pool;
+#[cfg(feature = "consensus")]
 pub use memory_pool::*;
 
 // TODO (howardwu): Move this out of network/sync. It should be on a much higher level.
+#[cfg(feature = "consensus")]
 pub mod miner;
+#[cfg(feature = "consensus")]
 pub use miner::*;
 
+pub mod relay_policy;
+pub use relay_policy::*;
+
+pub mod response_shaping;
+pub use response_shaping::*;
+
+pub mod serving_quota;
+pub use serving_quota::*;
+
+pub mod selection;
+pub use selection::*;
+
+#[cfg(feature = "consensus")]
 pub mod sync;
+#[cfg(feature = "consensus")]
 pub use sync::*;
 
+pub mod sync_state;
+pub use sync_state::*;
+
+#[cfg(feature = "consensus")]
 pub mod master;
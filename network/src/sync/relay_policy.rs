@@ -0,0 +1,161 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::NetworkError;
+use snarkvm_dpc::testnet1::instantiated::Tx;
+
+use std::{fmt, str::FromStr};
+
+/// A pattern of the memos of the transactions the node refuses to relay: a hex-encoded sequence of bytes that
+/// occurs anywhere in the memo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoPattern(Vec<u8>);
+
+impl MemoPattern {
+    /// Returns `true` if the given memo contains the pattern.
+    pub fn matches(&self, memo: &[u8]) -> bool {
+        memo.windows(self.0.len()).any(|window| window == &self.0[..])
+    }
+}
+
+impl FromStr for MemoPattern {
+    type Err = NetworkError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = hex::decode(s.trim())
+            .map_err(|e| NetworkError::InvalidRelayPolicy(format!("'{}' is not a hex memo pattern: {}", s, e)))?;
+        // A memo is 32 bytes long, so longer patterns would never match.
+        if bytes.is_empty() || bytes.len() > 32 {
+            return Err(NetworkError::InvalidRelayPolicy(format!(
+                "the memo pattern '{}' isn't between 1 and 32 bytes long",
+                s
+            )));
+        }
+
+        Ok(Self(bytes))
+    }
+}
+
+impl fmt::Display for MemoPattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.0))
+    }
+}
+
+/// The reason a transaction received from a peer was refused by the `RelayPolicy`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RelayRejection {
+    /// The transaction pays a lower fee than the minimum.
+    FeeTooLow { fee: i64, min_fee: i64 },
+    /// The transaction is larger than the maximum size.
+    TooLarge { size: usize, max_size: usize },
+    /// The memo of the transaction matches a blocked pattern.
+    BlockedMemo(MemoPattern),
+}
+
+impl fmt::Display for RelayRejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::FeeTooLow { fee, min_fee } => write!(f, "its fee of {} is lower than {}", fee, min_fee),
+            Self::TooLarge { size, max_size } => write!(f, "its size of {}B is larger than {}B", size, max_size),
+            Self::BlockedMemo(pattern) => write!(f, "its memo matches the blocked pattern {}", pattern),
+        }
+    }
+}
+
+/// The rules deciding which of the transactions received from peers the node accepts into its memory pool and
+/// relays; they're checked before the costly verification of the transactions. The transactions submitted over
+/// the RPC are exempt from them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RelayPolicy {
+    /// The minimum fee of the accepted transactions.
+    pub min_fee: i64,
+    /// The maximum size of the accepted transactions in bytes, if any.
+    pub max_transaction_size: Option<usize>,
+    /// The patterns of the memos of the refused transactions.
+    pub memo_blocklist: Vec<MemoPattern>,
+}
+
+impl RelayPolicy {
+    /// Checks the size of a serialized transaction, so that oversized ones aren't even deserialized.
+    pub fn check_size(&self, size: usize) -> Result<(), RelayRejection> {
+        match self.max_transaction_size {
+            Some(max_size) if size > max_size => Err(RelayRejection::TooLarge { size, max_size }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the fee and the memo of a transaction.
+    pub fn check(&self, transaction: &Tx) -> Result<(), RelayRejection> {
+        let fee = transaction.value_balance.0;
+        if fee < self.min_fee {
+            return Err(RelayRejection::FeeTooLow {
+                fee,
+                min_fee: self.min_fee,
+            });
+        }
+
+        if let Some(pattern) = self
+            .memo_blocklist
+            .iter()
+            .find(|pattern| pattern.matches(&transaction.memorandum))
+        {
+            return Err(RelayRejection::BlockedMemo(pattern.clone()));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memo_patterns_are_parsed_and_matched() {
+        let pattern: MemoPattern = "dead".parse().unwrap();
+
+        let mut memo = [0u8; 32];
+        assert!(!pattern.matches(&memo));
+        memo[10] = 0xde;
+        memo[11] = 0xad;
+        assert!(pattern.matches(&memo));
+        assert_eq!(pattern.to_string(), "dead");
+
+        assert!("".parse::<MemoPattern>().is_err());
+        assert!("xyz".parse::<MemoPattern>().is_err());
+        assert!("00".repeat(33).parse::<MemoPattern>().is_err());
+        assert!("00".repeat(32).parse::<MemoPattern>().is_ok());
+    }
+
+    #[test]
+    fn oversized_transactions_are_refused() {
+        assert!(RelayPolicy::default().check_size(usize::MAX).is_ok());
+
+        let policy = RelayPolicy {
+            max_transaction_size: Some(1000),
+            ..Default::default()
+        };
+        assert!(policy.check_size(1000).is_ok());
+        assert_eq!(
+            policy.check_size(1001),
+            Err(RelayRejection::TooLarge {
+                size: 1001,
+                max_size: 1000
+            })
+        );
+    }
+}
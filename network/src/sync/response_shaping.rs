@@ -0,0 +1,116 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::NetworkError;
+
+use rand::{thread_rng, Rng};
+
+/// The shaping of the `Sync` responses to the peers' `GetSync` requests. Responding with the exact number of
+/// hashes the node has past a requester's locator reveals its precise view of the chain, which makes it easy
+/// to fingerprint; responses can be capped and randomly shortened, within the limits of the protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncResponseShaping {
+    /// The maximum number of block hashes in a single `Sync` response; at most `MAX_BLOCK_SYNC_COUNT`.
+    max_hashes: u32,
+    /// The maximum number of block hashes a `Sync` response is randomly shortened by.
+    jitter: u32,
+}
+
+impl Default for SyncResponseShaping {
+    fn default() -> Self {
+        Self {
+            max_hashes: crate::MAX_BLOCK_SYNC_COUNT,
+            jitter: 0,
+        }
+    }
+}
+
+impl SyncResponseShaping {
+    /// Creates a new shaping of the `Sync` responses; the jitter needs to be lower than the cap, so that the
+    /// requesters always make progress.
+    pub fn new(max_hashes: u32, jitter: u32) -> Result<Self, NetworkError> {
+        if max_hashes == 0 || max_hashes > crate::MAX_BLOCK_SYNC_COUNT {
+            return Err(NetworkError::InvalidSyncResponseShaping(format!(
+                "the cap of {} hashes isn't between 1 and {}",
+                max_hashes,
+                crate::MAX_BLOCK_SYNC_COUNT
+            )));
+        }
+        if jitter >= max_hashes {
+            return Err(NetworkError::InvalidSyncResponseShaping(format!(
+                "the jitter of {} hashes isn't lower than the cap of {}",
+                jitter, max_hashes
+            )));
+        }
+
+        Ok(Self { max_hashes, jitter })
+    }
+
+    /// Returns the maximum number of block hashes in a single `Sync` response.
+    pub fn max_hashes(&self) -> u32 {
+        self.max_hashes
+    }
+
+    /// Returns the maximum number of block hashes a `Sync` response is randomly shortened by.
+    pub fn jitter(&self) -> u32 {
+        self.jitter
+    }
+
+    /// Returns the number of hashes to respond with, given the number of the ones the node has past the
+    /// requester's locator; it's non-zero if any are available.
+    pub fn response_len(&self, available: u32) -> u32 {
+        let len = available.min(self.max_hashes);
+        if len <= 1 || self.jitter == 0 {
+            return len;
+        }
+
+        len - thread_rng().gen_range(0..=self.jitter.min(len - 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn responses_are_capped() {
+        let shaping = SyncResponseShaping::default();
+        assert_eq!(shaping.response_len(0), 0);
+        assert_eq!(shaping.response_len(10), 10);
+        assert_eq!(shaping.response_len(1000), crate::MAX_BLOCK_SYNC_COUNT);
+
+        let shaping = SyncResponseShaping::new(16, 0).unwrap();
+        assert_eq!(shaping.response_len(1000), 16);
+
+        assert!(SyncResponseShaping::new(0, 0).is_err());
+        assert!(SyncResponseShaping::new(crate::MAX_BLOCK_SYNC_COUNT + 1, 0).is_err());
+        assert!(SyncResponseShaping::new(16, 16).is_err());
+    }
+
+    #[test]
+    fn jittered_responses_stay_within_bounds() {
+        let shaping = SyncResponseShaping::new(32, 8).unwrap();
+
+        for _ in 0..100 {
+            let len = shaping.response_len(1000);
+            assert!((24..=32).contains(&len));
+
+            // A requester that's a single block behind still gets its hash.
+            assert_eq!(shaping.response_len(1), 1);
+            assert!((1..=3).contains(&shaping.response_len(3)));
+        }
+    }
+}
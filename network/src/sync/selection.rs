@@ -0,0 +1,134 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::Peer;
+#[cfg(feature = "consensus")]
+use crate::Node;
+#[cfg(feature = "consensus")]
+use snarkvm_dpc::Storage;
+
+use std::net::SocketAddr;
+
+/// Orders the candidate sync nodes from the most to the least preferred one.
+///
+/// Peers that recently stalled mid-batch come last. Otherwise, the peers furthest ahead are preferred,
+/// with the ones within `SYNC_NODE_HEIGHT_TOLERANCE` blocks of one another considered equally far ahead;
+/// between those, the ones with the fewest recent failures come first, and then the ones with the lowest RTT.
+pub fn rank_sync_nodes<F: Fn(SocketAddr) -> bool>(candidates: &mut Vec<Peer>, is_stalled: F) {
+    let best_height = match candidates.iter().map(|peer| peer.quality.block_height).max() {
+        Some(height) => height,
+        None => return,
+    };
+
+    let mut ranked = candidates
+        .drain(..)
+        .map(|mut peer| {
+            let key = (
+                is_stalled(peer.address),
+                (best_height - peer.quality.block_height) / crate::SYNC_NODE_HEIGHT_TOLERANCE,
+                peer.failures(),
+                // the RTT is unknown until the first `Pong`
+                if peer.quality.rtt_ms == 0 {
+                    u64::MAX
+                } else {
+                    peer.quality.rtt_ms
+                },
+            );
+            (key, peer)
+        })
+        .collect::<Vec<_>>();
+    ranked.sort_by_key(|(key, _)| *key);
+
+    candidates.extend(ranked.into_iter().map(|(_, peer)| peer));
+}
+
+#[cfg(feature = "consensus")]
+impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
+    /// Returns the most preferred of the connected peers to sync with according to the node's peer selection
+    /// strategy, if there are any.
+    pub async fn select_sync_node(&self) -> Option<SocketAddr> {
+        let candidates = self
+            .peer_book
+            .connected_peers_snapshot()
+            .await
+            .into_iter()
+            // Watcher nodes don't serve blocks or their memory pool.
+            .filter(|peer| !peer.quality.is_watcher)
+            .collect();
+
+        let ranked = self
+            .config
+            .peer_selection
+            .rank_sync_nodes(candidates, &|addr| self.is_stalled_sync_node(addr));
+
+        ranked.first().map(|peer| peer.address)
+    }
+
+    /// Returns `true` if the given peer recently stalled while serving a batch of sync blocks.
+    pub fn is_stalled_sync_node(&self, addr: SocketAddr) -> bool {
+        self.sync().map(|sync| sync.is_stalled_sync_node(addr)) == Some(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BlockHeight;
+
+    fn peer(port: u16, block_height: BlockHeight, rtt_ms: u64, failures: usize) -> Peer {
+        let mut peer = Peer::new(([127, 0, 0, 1], port).into(), false);
+        peer.quality.block_height = block_height;
+        peer.quality.rtt_ms = rtt_ms;
+        for _ in 0..failures {
+            peer.fail();
+        }
+        peer
+    }
+
+    fn ports(peers: &[Peer]) -> Vec<u16> {
+        peers.iter().map(|peer| peer.address.port()).collect()
+    }
+
+    #[test]
+    fn peers_further_ahead_are_preferred() {
+        let mut candidates = vec![peer(1, 10, 10, 0), peer(2, 100, 500, 2), peer(3, 50, 10, 0)];
+        rank_sync_nodes(&mut candidates, |_| false);
+
+        assert_eq!(ports(&candidates), vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn equally_advanced_peers_are_ranked_by_failures_then_rtt() {
+        let mut candidates = vec![
+            peer(1, 100, 50, 1),
+            peer(2, 100, 0, 0),
+            peer(3, 100 - (crate::SYNC_NODE_HEIGHT_TOLERANCE - 1), 20, 0),
+            peer(4, 100, 80, 0),
+        ];
+        rank_sync_nodes(&mut candidates, |_| false);
+
+        // the RTT of the second peer is unknown
+        assert_eq!(ports(&candidates), vec![3, 4, 2, 1]);
+    }
+
+    #[test]
+    fn stalled_peers_come_last() {
+        let mut candidates = vec![peer(1, 100, 10, 0), peer(2, 10, 10, 0)];
+        rank_sync_nodes(&mut candidates, |addr| addr.port() == 1);
+
+        assert_eq!(ports(&candidates), vec![2, 1]);
+    }
+}
@@ -0,0 +1,190 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::NetworkError;
+
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// The per-peer caps on the number of blocks and block hashes served in response to the peers' `GetBlocks` and
+/// `GetSync` requests within a window of time, so that a single peer can't make the node saturate its disk and
+/// uplink. The requested items in excess of a cap are rejected; honest peers request them again later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncServingQuota {
+    /// The maximum number of blocks served to a peer within a window.
+    max_blocks: u32,
+    /// The maximum number of block hashes served to a peer within a window.
+    max_hashes: u32,
+    /// The duration of a window.
+    window: Duration,
+}
+
+impl Default for SyncServingQuota {
+    fn default() -> Self {
+        Self {
+            max_blocks: 64 * crate::MAX_BLOCK_SYNC_COUNT,
+            max_hashes: 128 * crate::MAX_BLOCK_SYNC_COUNT,
+            window: Duration::from_secs(60),
+        }
+    }
+}
+
+impl SyncServingQuota {
+    /// Creates a new serving quota; the caps need to accommodate at least a single full response, so that the
+    /// requesters always make progress.
+    pub fn new(max_blocks: u32, max_hashes: u32, window: Duration) -> Result<Self, NetworkError> {
+        if max_blocks < crate::MAX_BLOCK_SYNC_COUNT || max_hashes < crate::MAX_BLOCK_SYNC_COUNT {
+            return Err(NetworkError::InvalidSyncServingQuota(format!(
+                "the caps of {} blocks and {} hashes need to be at least {}",
+                max_blocks,
+                max_hashes,
+                crate::MAX_BLOCK_SYNC_COUNT
+            )));
+        }
+        if window.as_secs() == 0 {
+            return Err(NetworkError::InvalidSyncServingQuota(
+                "the window needs to last at least a second".into(),
+            ));
+        }
+
+        Ok(Self {
+            max_blocks,
+            max_hashes,
+            window,
+        })
+    }
+
+    /// Returns the maximum number of blocks served to a peer within a window.
+    pub fn max_blocks(&self) -> u32 {
+        self.max_blocks
+    }
+
+    /// Returns the maximum number of block hashes served to a peer within a window.
+    pub fn max_hashes(&self) -> u32 {
+        self.max_hashes
+    }
+
+    /// Returns the duration of a window.
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+}
+
+/// The kinds of items served to the syncing peers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServedItems {
+    /// The blocks served in response to `GetBlocks` requests.
+    Blocks,
+    /// The block hashes served in response to `GetSync` requests.
+    Hashes,
+    /// The announced blocks served in response to `GetBlock` requests; they have a fixed cap of
+    /// `MAX_ANNOUNCED_BLOCKS_SERVED` per window.
+    AnnouncedBlocks,
+}
+
+impl fmt::Display for ServedItems {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Blocks => write!(f, "blocks"),
+            Self::Hashes => write!(f, "block hashes"),
+            Self::AnnouncedBlocks => write!(f, "announced blocks"),
+        }
+    }
+}
+
+/// The items served to a peer in the current window of its serving quota.
+#[derive(Debug, Clone, Default)]
+pub struct ServingWindow {
+    /// The time the current window started at, if any items were requested yet.
+    started: Option<Instant>,
+    /// The number of blocks served in the current window.
+    blocks: u32,
+    /// The number of block hashes served in the current window.
+    hashes: u32,
+    /// The number of announced blocks served in the current window.
+    announced_blocks: u32,
+    /// The number of requests in the current window that were at least partially rejected.
+    rejections: u8,
+}
+
+impl ServingWindow {
+    /// Counts the given number of requested items against the quota, and returns the number of them that can be
+    /// served, along with whether the peer keeps exceeding the quota, i.e. `SYNC_QUOTA_ABUSE_TOLERANCE` of its
+    /// requests in the current window were rejected.
+    pub fn grant(&mut self, quota: &SyncServingQuota, items: ServedItems, requested: u32, now: Instant) -> (u32, bool) {
+        let is_expired = self
+            .started
+            .map(|started| now.saturating_duration_since(started) >= quota.window)
+            .unwrap_or(true);
+        if is_expired {
+            *self = Self {
+                started: Some(now),
+                ..Default::default()
+            };
+        }
+
+        let (served, cap) = match items {
+            ServedItems::Blocks => (&mut self.blocks, quota.max_blocks),
+            ServedItems::Hashes => (&mut self.hashes, quota.max_hashes),
+            ServedItems::AnnouncedBlocks => (&mut self.announced_blocks, crate::MAX_ANNOUNCED_BLOCKS_SERVED),
+        };
+        let granted = requested.min(cap.saturating_sub(*served));
+        *served += granted;
+
+        if granted == requested {
+            return (granted, false);
+        }
+
+        self.rejections += 1;
+        let is_abusive = self.rejections >= crate::SYNC_QUOTA_ABUSE_TOLERANCE;
+        if is_abusive {
+            self.rejections = 0;
+        }
+
+        (granted, is_abusive)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serving_is_capped_per_window() {
+        let quota = SyncServingQuota::new(100, 64, Duration::from_secs(60)).unwrap();
+        let mut window = ServingWindow::default();
+        let now = Instant::now();
+
+        assert_eq!(window.grant(&quota, ServedItems::Blocks, 64, now), (64, false));
+        // the excess is rejected, and the caps are separate
+        assert_eq!(window.grant(&quota, ServedItems::Blocks, 64, now), (36, false));
+        assert_eq!(window.grant(&quota, ServedItems::Hashes, 64, now), (64, false));
+        assert_eq!(window.grant(&quota, ServedItems::Blocks, 64, now), (0, false));
+        assert_eq!(window.grant(&quota, ServedItems::AnnouncedBlocks, 1, now), (1, false));
+
+        // the third rejected request in the window is reported as abuse
+        assert_eq!(window.grant(&quota, ServedItems::Hashes, 1, now), (0, true));
+
+        // the quota is replenished in the next window
+        let later = now + Duration::from_secs(60);
+        assert_eq!(window.grant(&quota, ServedItems::Blocks, 64, later), (64, false));
+
+        assert!(SyncServingQuota::new(10, 64, Duration::from_secs(60)).is_err());
+        assert!(SyncServingQuota::new(64, 64, Duration::from_secs(0)).is_err());
+    }
+}
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{Node, State};
+use crate::{Node, SyncState, SyncStateMachine, SyncStatus};
 use snarkos_consensus::{ConsensusParameters, MemoryPool, MerkleTreeLedger};
 use snarkos_storage::BlockHeight;
 use snarkvm_dpc::{
@@ -22,11 +22,20 @@ use snarkvm_dpc::{
         instantiated::{Components, Tx},
         parameters::PublicParameters,
     },
-    Storage,
+    BlockHeaderHash, Storage,
 };
 
-use atomic_instant::AtomicInstant;
-use std::{sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Arc,
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tokio::task;
 
 /// The sync handler of this node.
 pub struct Sync<S: Storage> {
@@ -34,12 +43,22 @@ pub struct Sync<S: Storage> {
     pub consensus: Arc<snarkos_consensus::Consensus<S>>,
     /// If `true`, initializes a mining task on this node.
     is_miner: bool,
-    /// The interval between each block sync.
-    block_sync_interval: Duration,
-    /// The interval between each memory pool sync.
-    mempool_sync_interval: Duration,
-    /// The last time a block sync was initiated.
-    last_block_sync: AtomicInstant,
+    /// The interval between each block sync, in milliseconds.
+    block_sync_interval: AtomicU64,
+    /// The interval between each memory pool sync, in milliseconds.
+    mempool_sync_interval: AtomicU64,
+    /// The number of times the memory pool sync interval is currently doubled, due to fruitless syncs.
+    mempool_sync_backoff: AtomicU32,
+    /// The number of new transactions learned since the start of the current memory pool sync round.
+    mempool_sync_novelty: AtomicU64,
+    /// The state of the block sync.
+    sync_state: SyncStateMachine,
+    /// The ids of the relayed transactions awaiting inclusion in a block, with their sources and arrival times.
+    relayed_transactions: Mutex<HashMap<Vec<u8>, (SocketAddr, Instant)>>,
+    /// The hashes of the announced blocks that were requested from peers, with the times of the requests.
+    requested_blocks: Mutex<HashMap<BlockHeaderHash, Instant>>,
+    /// The sync nodes that failed to deliver some of the requested blocks, with the times they stalled at.
+    stalled_sync_nodes: Mutex<HashMap<SocketAddr, Instant>>,
 }
 
 impl<S: Storage + core::marker::Sync + Send + 'static> Sync<S> {
@@ -53,9 +72,14 @@ impl<S: Storage + core::marker::Sync + Send + 'static> Sync<S> {
         Self {
             consensus,
             is_miner,
-            block_sync_interval,
-            mempool_sync_interval,
-            last_block_sync: AtomicInstant::empty(),
+            block_sync_interval: AtomicU64::new(block_sync_interval.as_millis() as u64),
+            mempool_sync_interval: AtomicU64::new(mempool_sync_interval.as_millis() as u64),
+            mempool_sync_backoff: Default::default(),
+            mempool_sync_novelty: Default::default(),
+            sync_state: Default::default(),
+            relayed_transactions: Default::default(),
+            requested_blocks: Default::default(),
+            stalled_sync_nodes: Default::default(),
         }
     }
 
@@ -95,50 +119,210 @@ impl<S: Storage + core::marker::Sync + Send + 'static> Sync<S> {
         self.consensus.ledger.get_current_block_height()
     }
 
+    /// Returns the height of the earliest block that can be served to peers, i.e. the earliest one that wasn't pruned.
+    #[inline]
+    pub fn earliest_block_height(&self) -> BlockHeight {
+        self.consensus.ledger.get_earliest_block_height().unwrap_or(0)
+    }
+
     /// Checks whether any previous sync attempt has expired.
     pub fn has_block_sync_expired(&self) -> bool {
-        let last_block_sync = self.last_block_sync.as_millis();
+        self.sync_state
+            .has_attempt_expired(Duration::from_secs(crate::BLOCK_SYNC_EXPIRATION_SECS as u64))
+    }
 
-        // due to double load, this can technically return twice, but shouldnt happen in practice
-        if last_block_sync > 0 {
-            self.last_block_sync.elapsed() > Duration::from_secs(crate::BLOCK_SYNC_EXPIRATION_SECS as u64)
-        } else {
-            // this means it's the very first sync attempt
-            true
-        }
+    /// Returns the current state of the block sync.
+    pub fn sync_state(&self) -> SyncState {
+        self.sync_state.state()
+    }
+
+    /// Returns the current state of the block sync, unless it's being updated at the moment.
+    pub fn try_sync_state(&self) -> Option<SyncState> {
+        self.sync_state.try_state()
+    }
+
+    /// Returns the current state of the block sync along with its timing.
+    pub fn sync_status(&self) -> SyncStatus {
+        self.sync_state.status()
+    }
+
+    /// Registers the start of the download of a batch of blocks, mostly from the given sync node.
+    pub(crate) fn syncing_blocks(&self, peer: SocketAddr, remaining: u32) {
+        self.sync_state.syncing_blocks(peer, remaining);
+    }
+
+    /// Registers that one of the blocks of the current sync batch was either processed or given up on.
+    pub(crate) fn sync_block_settled(&self) {
+        self.sync_state.block_settled();
+    }
+
+    /// Registers that some of the blocks of the current sync batch didn't arrive in time.
+    pub(crate) fn sync_stalled(&self) {
+        self.sync_state.stall();
     }
 
     /// Returns the interval between each block sync.
     pub fn block_sync_interval(&self) -> Duration {
-        self.block_sync_interval
+        Duration::from_millis(self.block_sync_interval.load(Ordering::Relaxed))
     }
 
     /// Returns the interval between each memory pool sync.
     pub fn mempool_sync_interval(&self) -> Duration {
+        Duration::from_millis(self.mempool_sync_interval.load(Ordering::Relaxed))
+    }
+
+    /// Returns the interval until the next memory pool sync; it's doubled after each sync that didn't turn up
+    /// any new transactions, up to `MAX_MEMPOOL_SYNC_BACKOFF` times.
+    pub fn current_mempool_sync_interval(&self) -> Duration {
+        self.mempool_sync_interval() * 2u32.pow(self.mempool_sync_backoff.load(Ordering::Relaxed))
+    }
+
+    /// Registers the given number of new transactions learned during the current memory pool sync round.
+    pub fn record_mempool_novelty(&self, new_transactions: u64) {
+        self.mempool_sync_novelty.fetch_add(new_transactions, Ordering::Relaxed);
+    }
+
+    /// Concludes the current memory pool sync round, backing off further if it was fruitless, or returning to the
+    /// configured interval otherwise.
+    pub fn conclude_mempool_sync_round(&self) {
+        if self.mempool_sync_novelty.swap(0, Ordering::Relaxed) > 0 {
+            self.reset_mempool_sync_backoff();
+        } else {
+            let _ = self
+                .mempool_sync_backoff
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |backoff| {
+                    Some((backoff + 1).min(crate::MAX_MEMPOOL_SYNC_BACKOFF))
+                });
+        }
+    }
+
+    /// Returns to the configured memory pool sync interval, e.g. once the node learns of a new block or transaction.
+    pub fn reset_mempool_sync_backoff(&self) {
+        self.mempool_sync_backoff.store(0, Ordering::Relaxed);
+    }
+
+    /// Changes the intervals between each block sync and memory pool sync.
+    pub(crate) fn set_sync_intervals(&self, block_sync_interval: Duration, mempool_sync_interval: Duration) {
+        self.block_sync_interval
+            .store(block_sync_interval.as_millis() as u64, Ordering::Relaxed);
         self.mempool_sync_interval
+            .store(mempool_sync_interval.as_millis() as u64, Ordering::Relaxed);
     }
 
     pub fn max_block_size(&self) -> usize {
         self.consensus.parameters.max_block_size
     }
+
+    /// Registers a transaction relayed by the given peer, so that it can be credited once the transaction
+    /// is included in a block.
+    pub(crate) fn track_relayed_transaction(&self, txid: Vec<u8>, source: SocketAddr) {
+        let mut relayed_transactions = self.relayed_transactions.lock().unwrap();
+
+        if relayed_transactions.len() < crate::MAX_TRACKED_RELAYED_TRANSACTIONS {
+            relayed_transactions.entry(txid).or_insert((source, Instant::now()));
+        }
+    }
+
+    /// Settles the tracked relayed transactions against the ids of the transactions included in a new block;
+    /// returns the sources of the included transactions and of the ones that expired in the meantime.
+    pub(crate) fn settle_relayed_transactions(&self, txids: &[Vec<u8>]) -> (Vec<SocketAddr>, Vec<SocketAddr>) {
+        let mut relayed_transactions = self.relayed_transactions.lock().unwrap();
+
+        let accepted = txids
+            .iter()
+            .filter_map(|txid| relayed_transactions.remove(txid))
+            .map(|(source, _)| source)
+            .collect();
+
+        let expiry = Duration::from_secs(crate::RELAYED_TRANSACTION_EXPIRY_SECS as u64);
+        let mut rejected = vec![];
+        relayed_transactions.retain(|_, (source, received)| {
+            let is_pending = received.elapsed() < expiry;
+            if !is_pending {
+                rejected.push(*source);
+            }
+            is_pending
+        });
+
+        (accepted, rejected)
+    }
+
+    /// Registers a request for an announced block; returns `false` if the block was already requested
+    /// recently, in which case it shouldn't be requested again.
+    pub(crate) fn register_block_request(&self, block_hash: BlockHeaderHash) -> bool {
+        let mut requested_blocks = self.requested_blocks.lock().unwrap();
+
+        let expiry = Duration::from_secs(crate::BLOCK_REQUEST_EXPIRATION_SECS as u64);
+        requested_blocks.retain(|_, requested| requested.elapsed() < expiry);
+
+        if requested_blocks.contains_key(&block_hash) {
+            false
+        } else {
+            requested_blocks.insert(block_hash, Instant::now());
+            true
+        }
+    }
+
+    /// Registers that the given sync node failed to deliver some of the requested blocks in time, so that
+    /// others are preferred in the following sync attempts.
+    pub(crate) fn register_stalled_sync_node(&self, addr: SocketAddr) {
+        self.stalled_sync_nodes.lock().unwrap().insert(addr, Instant::now());
+    }
+
+    /// Returns `true` if the given sync node recently stalled mid-batch.
+    pub fn is_stalled_sync_node(&self, addr: SocketAddr) -> bool {
+        let mut stalled_sync_nodes = self.stalled_sync_nodes.lock().unwrap();
+
+        let cooldown = Duration::from_secs(crate::STALLED_SYNC_NODE_COOLDOWN_SECS as u64);
+        stalled_sync_nodes.retain(|_, stalled| stalled.elapsed() < cooldown);
+
+        stalled_sync_nodes.contains_key(&addr)
+    }
 }
 
 impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
     /// Checks whether the node is currently syncing blocks.
     pub fn is_syncing_blocks(&self) -> bool {
-        self.state() == State::Syncing
+        self.sync().map(|sync| sync.sync_state().is_syncing()).unwrap_or(false)
     }
 
-    /// Register that the node is no longer syncing blocks.
-    pub fn finished_syncing_blocks(&self) {
-        self.set_state(State::Idle);
+    /// Checks whether the node's chain is within `OUT_OF_SYNC_BLOCK_THRESHOLD` blocks of the network's,
+    /// as estimated from the block heights reported by the connected peers.
+    pub async fn is_synced(&self) -> bool {
+        match self.peer_book.network_height().await {
+            Some(network_height) => network_height <= self.current_block_height() + crate::OUT_OF_SYNC_BLOCK_THRESHOLD,
+            // Without any peers, the node can't tell how far behind the network it is.
+            None => false,
+        }
     }
 
-    /// Register that the node attempted to sync blocks.
-    pub fn register_block_sync_attempt(&self) {
+    /// Starts a block sync attempt; returns `false` if the node doesn't sync blocks or is already syncing them.
+    pub(crate) fn start_block_sync(&self) -> bool {
+        self.sync().map(|sync| sync.sync_state.start()).unwrap_or(false)
+    }
+
+    /// Concludes the current block sync attempt, unless it already stalled.
+    pub(crate) fn finish_block_sync(&self) {
         if let Some(sync) = self.sync() {
-            sync.last_block_sync.set_now();
+            sync.sync_state.finish();
         }
-        self.set_state(State::Syncing);
+    }
+
+    /// Starts a block sync right away instead of waiting for the next sync interval, optionally only
+    /// with the given peer. Returns `false` if the node doesn't sync blocks or is already syncing them.
+    pub fn trigger_sync(&self, peer: Option<SocketAddr>) -> bool {
+        if !self.start_block_sync() {
+            return false;
+        }
+
+        let node = self.clone();
+        task::spawn(async move {
+            if let Err(e) = node.run_sync(peer).await {
+                error!("failed triggered sync process: {:?}", e);
+            }
+            node.finish_block_sync();
+        });
+
+        true
     }
 }
@@ -0,0 +1,258 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkos_metrics::{self as metrics, misc};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    net::SocketAddr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// The state of the node's block sync.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum SyncState {
+    /// The node isn't syncing blocks.
+    Idle,
+    /// The node has asked its sync nodes for the hashes of the blocks it's missing and awaits them.
+    SyncingHeaders,
+    /// The node is downloading and processing a batch of blocks; `peer` is the sync node most of them
+    /// were requested from, and `remaining` the number of blocks of the batch that weren't processed yet.
+    SyncingBlocks { peer: SocketAddr, remaining: u32 },
+    /// The last batch of blocks wasn't completed, as some of the sync nodes didn't deliver their blocks
+    /// in time; the missing blocks are requested from other sync nodes in the next sync attempt.
+    Stalled,
+}
+
+impl SyncState {
+    /// Returns `true` if a sync attempt is in progress.
+    pub fn is_syncing(&self) -> bool {
+        matches!(self, Self::SyncingHeaders | Self::SyncingBlocks { .. })
+    }
+}
+
+impl fmt::Display for SyncState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Idle => write!(f, "idle"),
+            Self::SyncingHeaders => write!(f, "syncing headers"),
+            Self::SyncingBlocks { peer, remaining } => {
+                write!(f, "syncing blocks ({} remaining, mostly from {})", remaining, peer)
+            }
+            Self::Stalled => write!(f, "stalled"),
+        }
+    }
+}
+
+/// The current `SyncState` of the node, along with the time it was entered and that of the latest sync attempt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncStatus {
+    #[serde(flatten)]
+    pub state: SyncState,
+    pub since: DateTime<Utc>,
+    pub last_attempt: Option<DateTime<Utc>>,
+}
+
+struct Inner {
+    status: SyncStatus,
+    /// The start of the latest sync attempt, used to determine whether it expired.
+    attempt_started: Option<Instant>,
+}
+
+/// Tracks the `SyncState` of the node; the transitions between the states are logged and metered.
+pub struct SyncStateMachine(Mutex<Inner>);
+
+impl Default for SyncStateMachine {
+    fn default() -> Self {
+        Self(Mutex::new(Inner {
+            status: SyncStatus {
+                state: SyncState::Idle,
+                since: Utc::now(),
+                last_attempt: None,
+            },
+            attempt_started: None,
+        }))
+    }
+}
+
+impl SyncStateMachine {
+    /// Returns the current state.
+    pub fn state(&self) -> SyncState {
+        self.0.lock().unwrap().status.state
+    }
+
+    /// Returns the current state without waiting for it to become available, e.g. while the node is panicking.
+    pub fn try_state(&self) -> Option<SyncState> {
+        self.0.try_lock().ok().map(|inner| inner.status.state)
+    }
+
+    /// Returns the current state along with its timing.
+    pub fn status(&self) -> SyncStatus {
+        self.0.lock().unwrap().status.clone()
+    }
+
+    /// Checks whether the latest sync attempt started more than the given amount of time ago; it's also
+    /// the case if there weren't any attempts yet.
+    pub fn has_attempt_expired(&self, expiry: Duration) -> bool {
+        match self.0.lock().unwrap().attempt_started {
+            Some(started) => started.elapsed() > expiry,
+            None => true,
+        }
+    }
+
+    /// Starts a sync attempt; returns `false` if one is already in progress.
+    pub fn start(&self) -> bool {
+        let mut inner = self.0.lock().unwrap();
+        if inner.status.state.is_syncing() {
+            return false;
+        }
+
+        inner.attempt_started = Some(Instant::now());
+        inner.status.last_attempt = Some(Utc::now());
+        Self::transition(&mut inner, SyncState::SyncingHeaders);
+        metrics::increment_counter!(misc::SYNC_ATTEMPTS);
+
+        true
+    }
+
+    /// Registers the start of the download of a batch of blocks within the current sync attempt.
+    pub fn syncing_blocks(&self, peer: SocketAddr, remaining: u32) {
+        let mut inner = self.0.lock().unwrap();
+        if inner.status.state.is_syncing() {
+            Self::transition(&mut inner, SyncState::SyncingBlocks { peer, remaining });
+            metrics::increment_counter!(misc::SYNC_BATCHES);
+        }
+    }
+
+    /// Registers that one of the blocks of the current batch was either processed or given up on.
+    pub fn block_settled(&self) {
+        if let SyncState::SyncingBlocks { remaining, .. } = &mut self.0.lock().unwrap().status.state {
+            *remaining = remaining.saturating_sub(1);
+        }
+    }
+
+    /// Registers that some of the blocks of the current batch didn't arrive in time.
+    pub fn stall(&self) {
+        let mut inner = self.0.lock().unwrap();
+        if inner.status.state.is_syncing() {
+            Self::transition(&mut inner, SyncState::Stalled);
+            metrics::increment_counter!(misc::SYNC_STALLS);
+        }
+    }
+
+    /// Concludes the current sync attempt, unless it already stalled.
+    pub fn finish(&self) {
+        let mut inner = self.0.lock().unwrap();
+        if inner.status.state.is_syncing() {
+            Self::transition(&mut inner, SyncState::Idle);
+        }
+    }
+
+    fn transition(inner: &mut Inner, state: SyncState) {
+        debug!("Sync state: {} -> {}", inner.status.state, state);
+
+        inner.status.state = state;
+        inner.status.since = Utc::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer() -> SocketAddr {
+        "127.0.0.1:4131".parse().unwrap()
+    }
+
+    #[test]
+    fn complete_sync_attempt() {
+        let machine = SyncStateMachine::default();
+        assert_eq!(machine.state(), SyncState::Idle);
+        assert!(machine.has_attempt_expired(Duration::from_secs(30)));
+
+        assert!(machine.start());
+        assert_eq!(machine.state(), SyncState::SyncingHeaders);
+        assert!(machine.status().last_attempt.is_some());
+        assert!(!machine.has_attempt_expired(Duration::from_secs(30)));
+        // only a single attempt can be in progress
+        assert!(!machine.start());
+
+        machine.syncing_blocks(peer(), 2);
+        machine.block_settled();
+        assert_eq!(
+            machine.state(),
+            SyncState::SyncingBlocks {
+                peer: peer(),
+                remaining: 1
+            }
+        );
+        machine.block_settled();
+        machine.block_settled();
+        assert_eq!(
+            machine.state(),
+            SyncState::SyncingBlocks {
+                peer: peer(),
+                remaining: 0
+            }
+        );
+
+        machine.finish();
+        assert_eq!(machine.state(), SyncState::Idle);
+    }
+
+    #[test]
+    fn stalled_sync_attempt() {
+        let machine = SyncStateMachine::default();
+
+        assert!(machine.start());
+        machine.syncing_blocks(peer(), 64);
+        machine.stall();
+        // the stall outlives the end of the attempt
+        machine.finish();
+        assert_eq!(machine.state(), SyncState::Stalled);
+        assert!(!machine.state().is_syncing());
+
+        // the next attempt starts afresh
+        assert!(machine.start());
+        assert_eq!(machine.state(), SyncState::SyncingHeaders);
+    }
+
+    #[test]
+    fn transitions_require_an_attempt() {
+        let machine = SyncStateMachine::default();
+
+        machine.syncing_blocks(peer(), 64);
+        machine.stall();
+        assert_eq!(machine.state(), SyncState::Idle);
+    }
+
+    #[test]
+    fn status_serialization() {
+        let machine = SyncStateMachine::default();
+        machine.start();
+        machine.syncing_blocks(peer(), 3);
+
+        let status = serde_json::to_value(machine.status()).unwrap();
+        assert_eq!(status["state"], "syncing_blocks");
+        assert_eq!(status["peer"], "127.0.0.1:4131");
+        assert_eq!(status["remaining"], 3);
+        assert_eq!(serde_json::from_value::<SyncStatus>(status).unwrap(), machine.status());
+    }
+}
@@ -0,0 +1,328 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{NetworkError, PeerAlias};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+    io::ErrorKind,
+    net::SocketAddr,
+    sync::RwLock,
+    time::Duration,
+};
+
+/// A connection between two nodes of the network; the order of the addresses is not meaningful.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Connection {
+    pub source: SocketAddr,
+    pub target: SocketAddr,
+}
+
+impl Connection {
+    /// Creates a new `Connection` between the given nodes.
+    pub fn new(a: SocketAddr, b: SocketAddr) -> Self {
+        // Normalize the order, so that both sides of the connection map to the same entry.
+        let (source, target) = if a < b { (a, b) } else { (b, a) };
+
+        Self { source, target }
+    }
+}
+
+/// The category of the failure of the last attempt to crawl a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CrawlError {
+    /// The node couldn't be reached or didn't complete the handshake in time.
+    Timeout,
+    /// The node refused the connection.
+    Refused,
+    /// The handshake with the node failed.
+    Handshake,
+    /// The connection went through a proxy that failed to establish it.
+    Proxy,
+    /// The node was connected to, but didn't report its peers in time.
+    Unresponsive,
+    /// Any other failure.
+    Other,
+}
+
+impl From<&NetworkError> for CrawlError {
+    fn from(error: &NetworkError) -> Self {
+        match error {
+            NetworkError::HandshakeTimeout => Self::Timeout,
+            NetworkError::Io(e) if e.kind() == ErrorKind::TimedOut => Self::Timeout,
+            NetworkError::Io(e) if e.kind() == ErrorKind::ConnectionRefused => Self::Refused,
+            NetworkError::InvalidHandshake
+            | NetworkError::NetworkMismatch(_)
+            | NetworkError::Noise(_)
+            | NetworkError::NodeIdentity(_)
+            | NetworkError::SelfConnectAttempt => Self::Handshake,
+            NetworkError::Proxy(_) => Self::Proxy,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl fmt::Display for CrawlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let category = match self {
+            Self::Timeout => "timeout",
+            Self::Refused => "refused",
+            Self::Handshake => "handshake",
+            Self::Proxy => "proxy",
+            Self::Unresponsive => "unresponsive",
+            Self::Other => "other",
+        };
+
+        write!(f, "{}", category)
+    }
+}
+
+/// The reliability of a node, as observed by the crawler while connecting to it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NodeReliability {
+    /// The outcomes of the most recent handshakes with the node; `true` means success.
+    handshakes: VecDeque<bool>,
+    /// The time of the first successful handshake since the last failed one.
+    online_since: Option<DateTime<Utc>>,
+    /// The time the node last reported its peers.
+    last_report: Option<DateTime<Utc>>,
+    /// The category of the failure of the last crawl attempt, if it failed.
+    last_error: Option<CrawlError>,
+}
+
+impl NodeReliability {
+    fn record_handshake(&mut self, outcome: Result<(), CrawlError>) {
+        if self.handshakes.len() == crate::CRAWL_ATTEMPT_HISTORY_LEN {
+            self.handshakes.pop_front();
+        }
+        self.handshakes.push_back(outcome.is_ok());
+
+        match outcome {
+            Ok(()) => {
+                self.online_since.get_or_insert_with(Utc::now);
+                self.last_error = None;
+            }
+            Err(error) => {
+                self.online_since = None;
+                self.last_error = Some(error);
+            }
+        }
+    }
+
+    /// Returns the fraction of the recent handshakes with the node that were successful.
+    pub fn responsiveness(&self) -> Option<f64> {
+        if self.handshakes.is_empty() {
+            return None;
+        }
+
+        let successes = self.handshakes.iter().filter(|&&success| success).count();
+
+        Some(successes as f64 / self.handshakes.len() as f64)
+    }
+
+    /// Returns the estimated amount of time the node has been continuously reachable for.
+    pub fn uptime(&self) -> Duration {
+        self.uptime_at(Utc::now())
+    }
+
+    /// Returns the estimated amount of time the node had been continuously reachable for at the given time.
+    pub fn uptime_at(&self, time: DateTime<Utc>) -> Duration {
+        self.online_since
+            .and_then(|since| (time - since).to_std().ok())
+            .unwrap_or_default()
+    }
+
+    /// Returns the category of the failure of the last crawl attempt, if it failed.
+    pub fn last_error(&self) -> Option<CrawlError> {
+        self.last_error
+    }
+}
+
+/// The persisted form of the `KnownNetwork`.
+#[derive(Serialize, Deserialize)]
+struct PersistedNetwork {
+    connections: Vec<(Connection, DateTime<Utc>)>,
+    aliases: Vec<(SocketAddr, PeerAlias)>,
+    reliability: Vec<(SocketAddr, NodeReliability)>,
+}
+
+/// The connections between the nodes of the network, as reported by the nodes themselves.
+#[derive(Debug, Default)]
+pub struct KnownNetwork {
+    /// The known connections along with the time they were last reported.
+    connections: RwLock<HashMap<Connection, DateTime<Utc>>>,
+    /// The unverified aliases the nodes have sent when connected to.
+    aliases: RwLock<HashMap<SocketAddr, PeerAlias>>,
+    /// The reliability of the nodes the crawler has attempted to connect to.
+    reliability: RwLock<HashMap<SocketAddr, NodeReliability>>,
+}
+
+impl KnownNetwork {
+    /// Registers the peers the given node has reported to be connected to.
+    pub fn update(&self, source: SocketAddr, peers: &[SocketAddr]) {
+        let now = Utc::now();
+        let mut connections = self.connections.write().unwrap();
+
+        for peer in peers.iter().filter(|&&peer| peer != source) {
+            connections.insert(Connection::new(source, *peer), now);
+        }
+        drop(connections);
+
+        self.reliability.write().unwrap().entry(source).or_default().last_report = Some(now);
+    }
+
+    /// Registers the outcome of a handshake with the given node.
+    pub fn record_handshake(&self, node: SocketAddr, outcome: Result<(), CrawlError>) {
+        self.reliability
+            .write()
+            .unwrap()
+            .entry(node)
+            .or_default()
+            .record_handshake(outcome);
+    }
+
+    /// Marks the given node as unresponsive if it hasn't reported its peers since the given time.
+    pub fn check_responded(&self, node: SocketAddr, since: DateTime<Utc>) {
+        let mut reliability = self.reliability.write().unwrap();
+        let entry = reliability.entry(node).or_default();
+
+        if entry.last_report.map(|time| time < since).unwrap_or(true) {
+            entry.last_error = Some(CrawlError::Unresponsive);
+        }
+    }
+
+    /// Returns the observed reliability of the given node, if it has been crawled.
+    pub fn reliability(&self, node: SocketAddr) -> Option<NodeReliability> {
+        self.reliability.read().unwrap().get(&node).cloned()
+    }
+
+    /// Registers the unverified alias the given node has sent.
+    pub fn set_alias(&self, node: SocketAddr, alias: PeerAlias) {
+        self.aliases.write().unwrap().insert(node, alias);
+    }
+
+    /// Returns the unverified alias of the given node, if it has sent one.
+    pub fn alias(&self, node: SocketAddr) -> Option<PeerAlias> {
+        self.aliases.read().unwrap().get(&node).cloned()
+    }
+
+    /// Forgets the connections that haven't been reported within the given amount of time.
+    pub fn prune(&self, max_age: Duration) {
+        let max_age = chrono::Duration::from_std(max_age).unwrap_or_else(|_| chrono::Duration::max_value());
+        let cutoff = Utc::now() - max_age;
+
+        self.connections
+            .write()
+            .unwrap()
+            .retain(|_, last_seen| *last_seen >= cutoff);
+
+        let nodes = self.nodes();
+        self.aliases.write().unwrap().retain(|node, _| nodes.contains(node));
+        self.reliability.write().unwrap().retain(|node, _| nodes.contains(node));
+    }
+
+    /// Returns the known connections.
+    pub fn connections(&self) -> HashSet<Connection> {
+        self.connections.read().unwrap().keys().copied().collect()
+    }
+
+    /// Serializes the known connections along with the metadata of the nodes, e.g. to persist them or to take
+    /// a snapshot of the network.
+    pub fn serialize(&self) -> Result<Vec<u8>, NetworkError> {
+        let persisted = PersistedNetwork {
+            connections: self
+                .connections
+                .read()
+                .unwrap()
+                .iter()
+                .map(|(&connection, &last_seen)| (connection, last_seen))
+                .collect(),
+            aliases: self
+                .aliases
+                .read()
+                .unwrap()
+                .iter()
+                .map(|(&node, alias)| (node, alias.clone()))
+                .collect(),
+            reliability: self
+                .reliability
+                .read()
+                .unwrap()
+                .iter()
+                .map(|(&node, reliability)| (node, reliability.clone()))
+                .collect(),
+        };
+
+        Ok(bincode::serialize(&persisted)?)
+    }
+
+    /// Deserializes a `KnownNetwork` produced by `serialize`.
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, NetworkError> {
+        let network = Self::default();
+        network.restore(bytes)?;
+
+        Ok(network)
+    }
+
+    /// Adds the connections and the metadata of the nodes from a serialized `KnownNetwork`; the ones that
+    /// were already registered take precedence, as they are more recent.
+    pub fn restore(&self, bytes: &[u8]) -> Result<(), NetworkError> {
+        let persisted: PersistedNetwork = bincode::deserialize(bytes)?;
+
+        let mut connections = self.connections.write().unwrap();
+        for (connection, last_seen) in persisted.connections {
+            connections.entry(connection).or_insert(last_seen);
+        }
+        drop(connections);
+
+        let mut aliases = self.aliases.write().unwrap();
+        for (node, alias) in persisted.aliases {
+            aliases.entry(node).or_insert(alias);
+        }
+        drop(aliases);
+
+        let mut reliability = self.reliability.write().unwrap();
+        for (node, node_reliability) in persisted.reliability {
+            reliability.entry(node).or_insert(node_reliability);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the addresses of the nodes participating in the known connections.
+    pub fn nodes(&self) -> HashSet<SocketAddr> {
+        self.connections
+            .read()
+            .unwrap()
+            .keys()
+            .flat_map(|connection| vec![connection.source, connection.target])
+            .collect()
+    }
+
+    /// Returns the number of known connections of each of the nodes.
+    pub fn degrees(&self) -> HashMap<SocketAddr, usize> {
+        let mut degrees = HashMap::new();
+        for connection in self.connections.read().unwrap().keys() {
+            *degrees.entry(connection.source).or_default() += 1;
+            *degrees.entry(connection.target).or_default() += 1;
+        }
+
+        degrees
+    }
+}
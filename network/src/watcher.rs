@@ -0,0 +1,543 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{message::*, NetworkError, Node};
+use snarkos_consensus::{error::ConsensusError, ConsensusParameters};
+use snarkos_storage::BlockHeight;
+use snarkvm_dpc::{testnet1::instantiated::Tx, Block, BlockHeader, BlockHeaderHash, Storage, TransactionScheme};
+use snarkvm_utilities::bytes::FromBytes;
+
+use std::{
+    collections::{HashMap, VecDeque},
+    net::SocketAddr,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant},
+};
+use tokio::{sync::broadcast, task};
+
+/// An event observed by a watcher node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WatcherEvent {
+    /// A block became the new tip of the canon chain.
+    NewTip { hash: BlockHeaderHash, height: BlockHeight },
+    /// The canon chain was reorganized; the blocks above the given height were replaced.
+    Reorganized { fork_height: BlockHeight },
+    /// A transaction was added to the memory pool.
+    NewTransaction { txid: Vec<u8> },
+    /// A transaction was included in a block that became part of the canon chain.
+    TransactionIncluded { txid: Vec<u8>, block_hash: BlockHeaderHash },
+}
+
+/// The outcome of a block received by a watcher node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatchedBlock {
+    /// The block was already known.
+    Known,
+    /// The parent of the block is unknown, so it couldn't be verified.
+    Orphan,
+    /// The block extended a side chain that is not longer than the canon chain.
+    SideChain,
+    /// The block became the tip of the canon chain.
+    Canon,
+}
+
+/// A transaction kept in the memory pool of a watcher node.
+struct WatchedTransaction {
+    transaction: Tx,
+    received: Instant,
+}
+
+/// The block headers known to a watcher node.
+struct HeaderChain {
+    /// The height of the header the chain starts with.
+    anchor_height: BlockHeight,
+    /// The hashes and headers of the canon chain, starting with the anchor.
+    canon: Vec<(BlockHeaderHash, BlockHeader)>,
+    /// The heights of the canon headers, by their hashes.
+    canon_heights: HashMap<BlockHeaderHash, BlockHeight>,
+    /// The recent headers that aren't part of the canon chain, with their heights.
+    side: HashMap<BlockHeaderHash, (BlockHeight, BlockHeader)>,
+    /// The most recently received full blocks, oldest first.
+    recent_blocks: VecDeque<Block<Tx>>,
+}
+
+impl HeaderChain {
+    fn new(anchor: BlockHeader, anchor_height: BlockHeight) -> Self {
+        let anchor_hash = anchor.get_hash();
+
+        let mut canon_heights = HashMap::new();
+        canon_heights.insert(anchor_hash.clone(), anchor_height);
+
+        Self {
+            anchor_height,
+            canon: vec![(anchor_hash, anchor)],
+            canon_heights,
+            side: Default::default(),
+            recent_blocks: Default::default(),
+        }
+    }
+
+    fn tip_height(&self) -> BlockHeight {
+        self.anchor_height + self.canon.len() as BlockHeight - 1
+    }
+
+    fn get(&self, hash: &BlockHeaderHash) -> Option<(BlockHeight, &BlockHeader)> {
+        match self.canon_heights.get(hash) {
+            Some(&height) => Some((height, &self.canon[(height - self.anchor_height) as usize].1)),
+            None => self.side.get(hash).map(|(height, header)| (*height, header)),
+        }
+    }
+
+    /// Inserts a header whose parent is known; if it extends the longest chain, it becomes the new canon tip,
+    /// and the height of the last block shared with the previous canon chain is returned, along with the hashes
+    /// of the headers that became canon, oldest first.
+    fn insert(&mut self, header: BlockHeader, height: BlockHeight) -> Option<(BlockHeight, Vec<BlockHeaderHash>)> {
+        let hash = header.get_hash();
+
+        if height <= self.tip_height() {
+            self.side.insert(hash, (height, header));
+            return None;
+        }
+
+        // Collect the branch leading to the new header, newest first.
+        let mut branch = vec![(hash, header)];
+        let mut parent_hash = branch[0].1.previous_block_hash.clone();
+        while !self.canon_heights.contains_key(&parent_hash) {
+            match self.side.remove(&parent_hash) {
+                Some((_, parent)) => {
+                    let grandparent_hash = parent.previous_block_hash.clone();
+                    branch.push((parent_hash, parent));
+                    parent_hash = grandparent_hash;
+                }
+                None => {
+                    // The beginning of the branch was already pruned, so it can't become canon.
+                    for (depth, (branch_hash, branch_header)) in branch.into_iter().enumerate() {
+                        self.side
+                            .insert(branch_hash, (height - depth as BlockHeight, branch_header));
+                    }
+                    return None;
+                }
+            }
+        }
+
+        // Replace the canon headers following the fork with the branch.
+        let fork_height = self.canon_heights[&parent_hash];
+        let replaced = self.canon.split_off((fork_height - self.anchor_height + 1) as usize);
+        for (offset, (replaced_hash, replaced_header)) in replaced.into_iter().enumerate() {
+            self.canon_heights.remove(&replaced_hash);
+            self.side.insert(
+                replaced_hash,
+                (fork_height + 1 + offset as BlockHeight, replaced_header),
+            );
+        }
+
+        let mut new_canon = Vec::with_capacity(branch.len());
+        for (branch_hash, branch_header) in branch.into_iter().rev() {
+            self.canon_heights.insert(branch_hash.clone(), self.tip_height() + 1);
+            self.canon.push((branch_hash.clone(), branch_header));
+            new_canon.push(branch_hash);
+        }
+
+        // Forget the side headers that are too deep to ever become canon again.
+        let tip_height = self.tip_height();
+        self.side
+            .retain(|_, (height, _)| *height + crate::WATCHER_BLOCK_WINDOW as BlockHeight > tip_height);
+
+        Some((fork_height, new_canon))
+    }
+
+    fn remember_block(&mut self, block: Block<Tx>) {
+        if self.recent_blocks.len() == crate::WATCHER_BLOCK_WINDOW {
+            self.recent_blocks.pop_front();
+        }
+        self.recent_blocks.push_back(block);
+    }
+
+    fn recent_block(&self, hash: &BlockHeaderHash) -> Option<&Block<Tx>> {
+        self.recent_blocks.iter().find(|block| block.header.get_hash() == *hash)
+    }
+}
+
+/// The state of a light node that only follows the block headers and the memory pool, and keeps
+/// only a shallow window of recent full blocks; it's meant to be embedded in wallets or monitoring
+/// services that need to observe the network with minimal resource usage.
+pub struct Watcher {
+    /// The parameters the received block headers are verified with.
+    parameters: ConsensusParameters,
+    /// The known block headers.
+    chain: RwLock<HeaderChain>,
+    /// The unconfirmed transactions, by their ids; they aren't verified against the ledger, which isn't kept.
+    memory_pool: Mutex<HashMap<Vec<u8>, WatchedTransaction>>,
+    /// The sender of the events observed by the watcher.
+    events: broadcast::Sender<WatcherEvent>,
+}
+
+impl Watcher {
+    /// Creates a watcher following the chain that starts with the given trusted header, e.g. the genesis
+    /// block's or a recent checkpoint's.
+    pub fn new(parameters: ConsensusParameters, anchor: BlockHeader, anchor_height: BlockHeight) -> Self {
+        let (events, _) = broadcast::channel(crate::WATCHER_EVENT_CHANNEL_DEPTH);
+
+        Self {
+            parameters,
+            chain: RwLock::new(HeaderChain::new(anchor, anchor_height)),
+            memory_pool: Default::default(),
+            events,
+        }
+    }
+
+    /// Returns a receiver of the events observed by the watcher from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<WatcherEvent> {
+        self.events.subscribe()
+    }
+
+    /// Returns the height of the tip of the canon chain.
+    pub fn block_height(&self) -> BlockHeight {
+        self.chain.read().unwrap().tip_height()
+    }
+
+    /// Returns the hash and the header of the tip of the canon chain.
+    pub fn tip(&self) -> (BlockHeaderHash, BlockHeader) {
+        self.chain.read().unwrap().canon.last().cloned().unwrap()
+    }
+
+    /// Returns the canon header at the given height, if it's known.
+    pub fn header_at(&self, height: BlockHeight) -> Option<BlockHeader> {
+        let chain = self.chain.read().unwrap();
+        let index = height.checked_sub(chain.anchor_height)?;
+
+        chain.canon.get(index as usize).map(|(_, header)| header.clone())
+    }
+
+    /// Returns the header with the given hash and its height, if it's known.
+    pub fn header(&self, hash: &BlockHeaderHash) -> Option<(BlockHeight, BlockHeader)> {
+        self.chain
+            .read()
+            .unwrap()
+            .get(hash)
+            .map(|(height, header)| (height, header.clone()))
+    }
+
+    /// Returns the height of the block with the given hash if it's part of the canon chain.
+    pub fn canon_height(&self, hash: &BlockHeaderHash) -> Option<BlockHeight> {
+        self.chain.read().unwrap().canon_heights.get(hash).copied()
+    }
+
+    /// Returns `true` if the header with the given hash is known.
+    pub fn is_known(&self, hash: &BlockHeaderHash) -> bool {
+        self.chain.read().unwrap().get(hash).is_some()
+    }
+
+    /// Returns the block with the given hash, if it's among the recently received ones.
+    pub fn recent_block(&self, hash: &BlockHeaderHash) -> Option<Block<Tx>> {
+        self.chain.read().unwrap().recent_block(hash).cloned()
+    }
+
+    /// Returns the transactions in the memory pool.
+    pub fn transactions(&self) -> Vec<Tx> {
+        self.memory_pool
+            .lock()
+            .unwrap()
+            .values()
+            .map(|entry| entry.transaction.clone())
+            .collect()
+    }
+
+    /// Returns the transaction with the given id, if it's in the memory pool.
+    pub fn transaction(&self, txid: &[u8]) -> Option<Tx> {
+        self.memory_pool
+            .lock()
+            .unwrap()
+            .get(txid)
+            .map(|entry| entry.transaction.clone())
+    }
+
+    /// Returns the hashes of the canon headers a full node can find the last shared block with; they
+    /// are dense near the tip and increasingly sparse towards the anchor, which is always included.
+    pub(crate) fn locator_hashes(&self) -> Vec<BlockHeaderHash> {
+        let chain = self.chain.read().unwrap();
+
+        let mut hashes = vec![];
+        let mut index = chain.canon.len() - 1;
+        let mut step = 1;
+        loop {
+            hashes.push(chain.canon[index].0.clone());
+            if index == 0 {
+                break;
+            }
+            if hashes.len() >= 10 {
+                step *= 2;
+            }
+            index = index.saturating_sub(step);
+        }
+
+        hashes
+    }
+
+    /// Verifies the header of the given block and adds it to the known ones; the block itself is only
+    /// kept while it's among the most recently received ones.
+    pub fn receive_block(&self, block: Block<Tx>) -> Result<WatchedBlock, NetworkError> {
+        let block_hash = block.header.get_hash();
+
+        let (parent_height, parent_header) = {
+            let chain = self.chain.read().unwrap();
+
+            if chain.get(&block_hash).is_some() {
+                return Ok(WatchedBlock::Known);
+            }
+            match chain.get(&block.header.previous_block_hash) {
+                Some((height, header)) => (height, header.clone()),
+                None => return Ok(WatchedBlock::Orphan),
+            }
+        };
+
+        self.parameters.verify_block_header(&block, &parent_header)?;
+
+        let mut chain = self.chain.write().unwrap();
+        if chain.get(&block_hash).is_some() {
+            return Ok(WatchedBlock::Known);
+        }
+
+        let old_tip_height = chain.tip_height();
+        let insertion = chain.insert(block.header.clone(), parent_height + 1);
+        chain.remember_block(block);
+
+        let (fork_height, new_canon) = match insertion {
+            Some(insertion) => insertion,
+            None => return Ok(WatchedBlock::SideChain),
+        };
+
+        // The transactions of the blocks that became canon are considered included, as long as the blocks are known.
+        let included = new_canon
+            .iter()
+            .filter_map(|hash| chain.recent_block(hash))
+            .flat_map(|block| {
+                let block_hash = block.header.get_hash();
+                block
+                    .transactions
+                    .iter()
+                    .filter_map(|tx| tx.transaction_id().ok())
+                    .map(move |txid| (txid.to_vec(), block_hash.clone()))
+            })
+            .collect::<Vec<_>>();
+        let tip_height = chain.tip_height();
+        drop(chain);
+
+        if fork_height < old_tip_height {
+            self.events.send(WatcherEvent::Reorganized { fork_height }).ok();
+        }
+
+        {
+            let mut memory_pool = self.memory_pool.lock().unwrap();
+            for (txid, _) in &included {
+                memory_pool.remove(txid);
+            }
+        }
+        for (txid, block_hash) in included {
+            self.events
+                .send(WatcherEvent::TransactionIncluded { txid, block_hash })
+                .ok();
+        }
+
+        self.events
+            .send(WatcherEvent::NewTip {
+                hash: block_hash,
+                height: tip_height,
+            })
+            .ok();
+
+        Ok(WatchedBlock::Canon)
+    }
+
+    /// Adds the given transaction to the memory pool; returns `false` if it's a coinbase transaction, if it's
+    /// already known, or if the memory pool is full. The transaction isn't verified against the ledger.
+    pub fn receive_transaction(&self, transaction: Tx) -> bool {
+        if transaction.value_balance.is_negative() {
+            return false;
+        }
+
+        let txid = match transaction.transaction_id() {
+            Ok(txid) => txid.to_vec(),
+            Err(_) => return false,
+        };
+
+        {
+            let mut memory_pool = self.memory_pool.lock().unwrap();
+            if memory_pool.contains_key(&txid) || memory_pool.len() >= crate::WATCHER_MEMORY_POOL_SIZE {
+                return false;
+            }
+            memory_pool.insert(
+                txid.clone(),
+                WatchedTransaction {
+                    transaction,
+                    received: Instant::now(),
+                },
+            );
+        }
+
+        self.events.send(WatcherEvent::NewTransaction { txid }).ok();
+
+        true
+    }
+
+    /// Removes the transactions that were kept in the memory pool for too long without being included in a block.
+    pub(crate) fn expire_transactions(&self) {
+        let expiry = Duration::from_secs(crate::WATCHER_TRANSACTION_EXPIRY_SECS as u64);
+
+        self.memory_pool
+            .lock()
+            .unwrap()
+            .retain(|_, entry| entry.received.elapsed() < expiry);
+    }
+}
+
+impl<S: Storage + Send + core::marker::Sync + 'static> Node<S> {
+    /// Sets the watcher of this node, turning it into a light node that only follows the block headers
+    /// and the memory pool; it can't be combined with the full sync layer.
+    pub fn set_watcher(&mut self, watcher: Watcher) {
+        if self.sync().is_some() {
+            panic!("a node with a sync layer can't be a watcher!");
+        }
+        if self.watcher.set(Arc::new(watcher)).is_err() {
+            panic!("watcher was set more than once!");
+        }
+    }
+
+    /// Returns a reference to the watcher, if the node is a watcher node.
+    #[inline]
+    pub fn watcher(&self) -> Option<&Arc<Watcher>> {
+        self.watcher.get()
+    }
+
+    ///
+    /// Requests the blocks following the watched chain and the memory pool from the connected full node
+    /// with the highest chain.
+    ///
+    pub(crate) async fn update_watched_chain(&self) {
+        let watcher = match self.watcher() {
+            Some(watcher) => watcher,
+            None => return,
+        };
+
+        watcher.expire_transactions();
+
+        let sync_node = self
+            .peer_book
+            .connected_peers_snapshot()
+            .await
+            .into_iter()
+            .filter(|peer| !peer.quality.is_watcher)
+            .max_by_key(|peer| peer.quality.block_height);
+
+        let sync_node = match sync_node {
+            Some(sync_node) => sync_node,
+            None => {
+                debug!("No full node is connected, the watched chain could not be updated");
+                return;
+            }
+        };
+
+        if sync_node.quality.block_height > watcher.block_height() {
+            info!("Updating the watched chain from {}", sync_node.address);
+
+            self.peer_book
+                .send_to(sync_node.address, Payload::GetSync(watcher.locator_hashes()))
+                .await;
+        }
+
+        self.peer_book.send_to(sync_node.address, Payload::GetMemoryPool).await;
+    }
+
+    /// A full node has sent the hashes of the blocks following the watched chain; request the unknown ones.
+    pub(crate) async fn received_watched_block_hashes(
+        &self,
+        remote_address: SocketAddr,
+        block_hashes: Vec<BlockHeaderHash>,
+    ) {
+        let watcher = match self.watcher() {
+            Some(watcher) => watcher,
+            None => return,
+        };
+
+        let request = block_hashes
+            .into_iter()
+            .filter(|hash| !watcher.is_known(hash))
+            .take(crate::MAX_BLOCK_SYNC_COUNT as usize)
+            .collect::<Vec<_>>();
+
+        if request.is_empty() {
+            return;
+        }
+
+        if let Some(peer) = self.peer_book.get_peer_handle(remote_address) {
+            peer.expecting_sync_blocks(request.clone()).await;
+            peer.send_payload(Payload::GetBlocks(request)).await;
+        }
+    }
+
+    /// A peer has announced a new block; request it unless its header is already known.
+    pub(crate) async fn received_watched_block_hash(&self, remote_address: SocketAddr, block_hash: BlockHeaderHash) {
+        if let Some(watcher) = self.watcher() {
+            if !watcher.is_known(&block_hash) {
+                self.peer_book
+                    .send_to(remote_address, Payload::GetBlock(block_hash))
+                    .await;
+            }
+        }
+    }
+
+    /// A peer has sent a block; verify its header and add it to the watched chain.
+    pub(crate) async fn received_watched_block(
+        &self,
+        remote_address: SocketAddr,
+        block: Vec<u8>,
+    ) -> Result<(), NetworkError> {
+        let watcher = match self.watcher() {
+            Some(watcher) => watcher.clone(),
+            None => return Ok(()),
+        };
+
+        let max_block_size = watcher.parameters.max_block_size;
+        if block.len() > max_block_size {
+            return Err(NetworkError::ConsensusError(ConsensusError::BlockTooLarge(
+                block.len(),
+                max_block_size,
+            )));
+        }
+
+        let block = Block::deserialize(&block)?;
+
+        // Verifying the proof of the header is relatively expensive, so it's done on the blocking thread pool.
+        let outcome = task::spawn_blocking(move || watcher.receive_block(block))
+            .await
+            .map_err(std::io::Error::from)??;
+
+        debug!("Received a block from {} ({:?})", remote_address, outcome);
+
+        Ok(())
+    }
+
+    /// A peer has sent transactions; add them to the watched memory pool.
+    pub(crate) fn received_watched_transactions(&self, transactions: Vec<Vec<u8>>) {
+        if let Some(watcher) = self.watcher() {
+            for transaction in transactions {
+                if let Ok(transaction) = Tx::read(&transaction[..]) {
+                    watcher.receive_transaction(transaction);
+                }
+            }
+        }
+    }
+}
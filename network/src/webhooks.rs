@@ -0,0 +1,329 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{EventCategory, NetworkError};
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac, NewMac};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::{
+    collections::HashMap,
+    fmt,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::{sync::mpsc, task, time::sleep};
+
+/// The name of the header carrying the event a notification is about.
+pub const WEBHOOK_EVENT_HEADER: &str = "X-Snarkos-Event";
+/// The name of the header carrying the signature of a notification, if a secret is configured.
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X-Snarkos-Signature";
+
+/// The events that can be reported to a webhook.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// The connected peers are considerably ahead of the node's chain.
+    OutOfSync,
+    /// The node is connected to fewer peers than its configured minimum.
+    LowPeerCount,
+    /// A block extending a side chain was received, possibly reorganizing the canon chain.
+    PotentialFork,
+    /// The canon chain was reorganized onto a longer side chain.
+    Reorg,
+    /// A peer was banned.
+    PeerBanned,
+    /// One of the configured alarms was raised or cleared.
+    Alarm,
+    /// The node panicked; the details point to its crash report.
+    Crash,
+}
+
+impl WebhookEvent {
+    /// All the events that can be reported to a webhook.
+    pub const ALL: [WebhookEvent; 7] = [
+        WebhookEvent::OutOfSync,
+        WebhookEvent::LowPeerCount,
+        WebhookEvent::PotentialFork,
+        WebhookEvent::Reorg,
+        WebhookEvent::PeerBanned,
+        WebhookEvent::Alarm,
+        WebhookEvent::Crash,
+    ];
+
+    /// Returns `true` if the event reports an ongoing condition that is checked periodically, rather than
+    /// a one-off occurrence; such events are subject to a cooldown, so that they aren't repeated on every check.
+    pub fn is_condition(&self) -> bool {
+        matches!(self, WebhookEvent::OutOfSync | WebhookEvent::LowPeerCount)
+    }
+
+    /// Returns the category the event is journaled under.
+    pub fn category(&self) -> EventCategory {
+        match self {
+            WebhookEvent::OutOfSync | WebhookEvent::PotentialFork | WebhookEvent::Reorg => EventCategory::Chain,
+            WebhookEvent::LowPeerCount | WebhookEvent::PeerBanned => EventCategory::Peers,
+            WebhookEvent::Alarm => EventCategory::Alarms,
+            WebhookEvent::Crash => EventCategory::Crashes,
+        }
+    }
+}
+
+impl fmt::Display for WebhookEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::OutOfSync => write!(f, "out_of_sync"),
+            Self::LowPeerCount => write!(f, "low_peer_count"),
+            Self::PotentialFork => write!(f, "potential_fork"),
+            Self::Reorg => write!(f, "reorg"),
+            Self::PeerBanned => write!(f, "peer_banned"),
+            Self::Alarm => write!(f, "alarm"),
+            Self::Crash => write!(f, "crash"),
+        }
+    }
+}
+
+impl FromStr for WebhookEvent {
+    type Err = NetworkError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|event| event.to_string() == s.trim())
+            .ok_or_else(|| NetworkError::Webhook(format!("unknown webhook event '{}'", s)))
+    }
+}
+
+/// The configuration of the webhook the node's events are reported to.
+#[derive(Clone, Debug)]
+pub struct WebhookConfig {
+    /// The URL the notifications are POSTed to.
+    pub url: String,
+    /// The secret the notifications are signed with, if any.
+    pub secret: Option<String>,
+    /// The events the webhook is notified of; all of them if empty.
+    pub events: Vec<WebhookEvent>,
+}
+
+/// A notification sent to the webhook.
+#[derive(Clone, Debug, Serialize)]
+pub struct WebhookNotification {
+    /// The event the notification is about.
+    pub event: WebhookEvent,
+    /// The random numeric identifier of the node.
+    pub node_id: u64,
+    /// A random number unique to the notification, which lets the webhook reject replayed requests.
+    pub nonce: u64,
+    /// The time at which the event occurred.
+    pub timestamp: DateTime<Utc>,
+    /// The event-specific details.
+    pub details: serde_json::Value,
+}
+
+/// A handle to the task delivering the node's notifications to a webhook.
+#[derive(Clone)]
+pub struct Webhook {
+    /// The events the webhook is notified of.
+    events: Vec<WebhookEvent>,
+    /// The random numeric identifier of the node.
+    node_id: u64,
+    /// The sender of the notifications to the delivery task.
+    sender: mpsc::Sender<WebhookNotification>,
+    /// The last time each condition event was reported.
+    last_notified: Arc<Mutex<HashMap<WebhookEvent, Instant>>>,
+}
+
+impl Webhook {
+    /// Spawns the task delivering the notifications to the configured webhook.
+    pub fn spawn(config: WebhookConfig, node_id: u64) -> (Self, task::JoinHandle<()>) {
+        let (sender, mut receiver) = mpsc::channel::<WebhookNotification>(crate::WEBHOOK_QUEUE_DEPTH);
+
+        let events = if config.events.is_empty() {
+            WebhookEvent::ALL.to_vec()
+        } else {
+            config.events.clone()
+        };
+
+        let handle = task::spawn(async move {
+            let client = reqwest::Client::new();
+
+            // Notifications are delivered one at a time, so that they arrive in order.
+            while let Some(notification) = receiver.recv().await {
+                deliver(&client, &config, &notification).await;
+            }
+        });
+
+        let webhook = Self {
+            events,
+            node_id,
+            sender,
+            last_notified: Default::default(),
+        };
+
+        (webhook, handle)
+    }
+
+    /// Returns `true` if the webhook is notified of the given event.
+    pub fn is_enabled(&self, event: WebhookEvent) -> bool {
+        self.events.contains(&event)
+    }
+
+    /// Queues a notification of the given event; it is dropped if the event isn't enabled, if it's a condition
+    /// that was reported recently, or if too many notifications are already awaiting delivery. It only waits for
+    /// a lock for the condition events, so the other ones can be reported from a panic hook.
+    pub fn notify(&self, event: WebhookEvent, details: serde_json::Value) {
+        if !self.is_enabled(event) {
+            return;
+        }
+
+        if event.is_condition() {
+            let mut last_notified = self.last_notified.lock().unwrap();
+            let cooldown = Duration::from_secs(crate::WEBHOOK_CONDITION_COOLDOWN_SECS as u64);
+
+            if matches!(last_notified.get(&event), Some(last) if last.elapsed() < cooldown) {
+                return;
+            }
+            last_notified.insert(event, Instant::now());
+        }
+
+        let notification = WebhookNotification {
+            event,
+            node_id: self.node_id,
+            nonce: rand::thread_rng().gen(),
+            timestamp: Utc::now(),
+            details,
+        };
+
+        if self.sender.try_send(notification).is_err() {
+            warn!(
+                "The webhook delivery queue is full; dropping a '{}' notification",
+                event
+            );
+        }
+    }
+}
+
+/// Delivers a single notification, retrying with an exponential backoff if the webhook fails to accept it.
+async fn deliver(client: &reqwest::Client, config: &WebhookConfig, notification: &WebhookNotification) {
+    let body = match serde_json::to_vec(notification) {
+        Ok(body) => body,
+        Err(e) => {
+            error!("Couldn't serialize a webhook notification: {}", e);
+            return;
+        }
+    };
+    let signature = config
+        .secret
+        .as_ref()
+        .map(|secret| format!("sha256={}", hex::encode(sign(secret.as_bytes(), &body))));
+
+    let mut delay = Duration::from_secs(crate::WEBHOOK_RETRY_BASE_DELAY_SECS as u64);
+
+    for attempt in 1..=crate::WEBHOOK_MAX_ATTEMPTS {
+        let mut request = client
+            .post(&config.url)
+            .timeout(Duration::from_secs(crate::WEBHOOK_REQUEST_TIMEOUT_SECS as u64))
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(WEBHOOK_EVENT_HEADER, notification.event.to_string())
+            .body(body.clone());
+        if let Some(signature) = &signature {
+            request = request.header(WEBHOOK_SIGNATURE_HEADER, signature.as_str());
+        }
+
+        match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                debug!("Delivered a '{}' notification to the webhook", notification.event);
+                return;
+            }
+            Ok(response) => warn!(
+                "The webhook rejected a '{}' notification with status {} (attempt {}/{})",
+                notification.event,
+                response.status(),
+                attempt,
+                crate::WEBHOOK_MAX_ATTEMPTS
+            ),
+            Err(e) => warn!(
+                "Couldn't deliver a '{}' notification to the webhook: {} (attempt {}/{})",
+                notification.event,
+                e,
+                attempt,
+                crate::WEBHOOK_MAX_ATTEMPTS
+            ),
+        }
+
+        if attempt < crate::WEBHOOK_MAX_ATTEMPTS {
+            sleep(delay).await;
+            delay *= 2;
+        }
+    }
+
+    error!(
+        "Giving up on delivering a '{}' notification to the webhook",
+        notification.event
+    );
+}
+
+/// Computes the HMAC-SHA256 of the given message with the given key.
+pub fn sign(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+
+    mac.finalize().into_bytes().into()
+}
+
+/// Checks the HMAC-SHA256 signature of the given message with the given key in constant time.
+pub fn verify_signature(key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+
+    mac.verify(signature).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signatures_match_hmac_sha256() {
+        // The test cases 1 and 6 from RFC 4231.
+        let signature = sign(&[0x0b; 20], b"Hi There");
+        assert_eq!(
+            hex::encode(signature),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+
+        let signature = sign(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First");
+        assert_eq!(
+            hex::encode(signature),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+
+        let signature = sign(&[0x0b; 20], b"Hi There");
+        assert!(verify_signature(&[0x0b; 20], b"Hi There", &signature));
+        assert!(!verify_signature(&[0x0b; 20], b"Hi there", &signature));
+    }
+
+    #[test]
+    fn events_round_trip_through_their_names() {
+        for event in WebhookEvent::ALL.iter() {
+            assert_eq!(event.to_string().parse::<WebhookEvent>().unwrap(), *event);
+        }
+        assert!("reorg".parse::<WebhookEvent>().is_err());
+    }
+}
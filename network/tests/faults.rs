@@ -0,0 +1,122 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkos_network::{LinkFaults, Node};
+use snarkos_storage::LedgerStorage;
+use snarkos_testing::{
+    network::{
+        faults::{clear_link_faults, set_link_faults},
+        test_node,
+        ConsensusSetup,
+        TestSetup,
+    },
+    sync::{BLOCK_1, BLOCK_2},
+    wait_until,
+};
+use snarkvm_dpc::Block;
+
+use std::time::Duration;
+
+/// Starts a node with the first two test blocks in its ledger.
+async fn synced_node() -> Node<LedgerStorage> {
+    let node = test_node(TestSetup::default()).await;
+
+    for block in [&*BLOCK_1, &*BLOCK_2].iter() {
+        let block = Block::deserialize(block).unwrap();
+        node.expect_sync().consensus.receive_block(&block).await.unwrap();
+    }
+
+    node
+}
+
+/// Starts a node that syncs blocks with the given bootnode every second.
+async fn syncing_node(bootnode: &Node<LedgerStorage>) -> Node<LedgerStorage> {
+    let setup = TestSetup {
+        consensus_setup: Some(ConsensusSetup {
+            block_sync_interval: 1,
+            ..Default::default()
+        }),
+        peer_sync_interval: 1,
+        bootnodes: vec![bootnode.local_address().unwrap().to_string()],
+        ..Default::default()
+    };
+
+    test_node(setup).await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sync_with_delayed_and_duplicated_messages() {
+    let node_alice = synced_node().await;
+    let node_bob = syncing_node(&node_alice).await;
+
+    let faults = LinkFaults {
+        delay: Some(Duration::from_millis(50)),
+        duplicate_rate: 0.5,
+        ..Default::default()
+    };
+    set_link_faults(&node_alice, &node_bob, faults);
+
+    wait_until!(20, node_bob.expect_sync().current_block_height() == 2, 100);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sync_with_dropped_messages() {
+    let node_alice = synced_node().await;
+    let node_bob = syncing_node(&node_alice).await;
+
+    let faults = LinkFaults {
+        drop_rate: 0.3,
+        ..Default::default()
+    };
+    set_link_faults(&node_alice, &node_bob, faults);
+
+    // the lost requests and blocks are made up for by the subsequent sync attempts
+    wait_until!(30, node_bob.expect_sync().current_block_height() == 2, 100);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn peers_reconnect_after_abrupt_disconnects() {
+    let setup = TestSetup {
+        consensus_setup: None,
+        ..Default::default()
+    };
+    let node_alice = test_node(setup).await;
+
+    let setup = TestSetup {
+        consensus_setup: None,
+        peer_sync_interval: 1,
+        bootnodes: vec![node_alice.local_address().unwrap().to_string()],
+        ..Default::default()
+    };
+    let node_bob = test_node(setup).await;
+
+    wait_until!(5, node_bob.peer_book.get_active_peer_count() == 1);
+
+    // the connection is dropped as soon as either node attempts to send anything
+    let faults = LinkFaults {
+        disconnect_rate: 1.0,
+        ..Default::default()
+    };
+    set_link_faults(&node_alice, &node_bob, faults);
+
+    wait_until!(5, node_alice.peer_book.get_active_peer_count() == 0);
+
+    // once the link is healthy again, the bootnode is reconnected to
+    clear_link_faults(&node_alice, &node_bob);
+
+    wait_until!(10, node_bob.peer_book.get_active_peer_count() == 1);
+    wait_until!(10, node_alice.peer_book.get_active_peer_count() == 1);
+}
@@ -14,17 +14,31 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use snarkos_network::{MessageHeader, Payload, Version};
+use snarkos_network::{
+    AddressManager,
+    MessageHeader,
+    Payload,
+    PeerReader,
+    Version,
+    MAX_MESSAGE_SIZE,
+};
 use snarkvm_dpc::BlockHeaderHash;
 
 use rand::{distributions::Standard, thread_rng, Rng};
 use snarkos_testing::{
-    network::{handshaken_node_and_peer, spawn_2_fake_nodes, test_node, TestSetup},
+    network::{
+        corpus::{capture_corpus, cipher_pair, synthetic_corpus},
+        handshaken_node_and_peer,
+        spawn_2_fake_nodes,
+        test_node,
+        TestSetup,
+    },
     wait_until,
 };
 use tokio::{io::AsyncWriteExt, net::TcpStream, sync::Mutex};
 
 use std::{
+    io::Cursor,
     net::SocketAddr,
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -353,7 +367,7 @@ async fn fuzzing_corrupted_payloads_with_bodies_pre_handshake() {
         Payload::SyncBlock(blob.clone()),
         Payload::Transaction(blob.clone()),
         Payload::Peers(addrs.clone()),
-        Payload::Ping(thread_rng().gen()),
+        Payload::Ping(thread_rng().gen(), thread_rng().gen()),
     ] {
         let serialized = Payload::serialize(payload).unwrap();
 
@@ -417,7 +431,7 @@ async fn fuzzing_corrupted_payloads_with_bodies_post_handshake() {
         Payload::SyncBlock(blob.clone()),
         Payload::Transaction(blob.clone()),
         Payload::Peers(addrs.clone()),
-        Payload::Ping(thread_rng().gen()),
+        Payload::Ping(thread_rng().gen(), thread_rng().gen()),
     ] {
         let serialized = Payload::serialize(payload).unwrap();
 
@@ -545,3 +559,74 @@ async fn connection_request_spam() {
         node.peer_book.get_active_peer_count() == 0
     );
 }
+
+#[tokio::test]
+async fn fuzzing_payload_deserialization() {
+    let mut corpus = capture_corpus(Default::default()).await;
+    corpus.extend(synthetic_corpus());
+
+    for serialized in &corpus {
+        for len in 0..serialized.len().min(1024) {
+            let _ = Payload::deserialize(&serialized[..len]);
+        }
+
+        for _ in 0..ITERATIONS {
+            let _ = Payload::deserialize(&corrupt_bytes(serialized));
+        }
+    }
+}
+
+#[tokio::test]
+async fn fuzzing_noise_frames() {
+    for serialized in synthetic_corpus() {
+        for _ in 0..ITERATIONS / 10 {
+            let (mut sender, mut receiver) = cipher_pair();
+            // The length prefix of the frame is corrupted too.
+            let frame = corrupt_bytes(sender.encrypt_packet(&serialized).unwrap());
+
+            let mut reader = PeerReader {
+                reader: Cursor::new(frame),
+                buffer: vec![0u8; MAX_MESSAGE_SIZE].into(),
+            };
+            if let Ok(encrypted) = reader.read_raw_payload().await {
+                let decrypted = receiver.read_packet_with(encrypted, |head| {
+                    let _ = Payload::size_limit(head);
+                    Ok(())
+                });
+                if let Ok(decrypted) = decrypted {
+                    let _ = Payload::deserialize(decrypted);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn fuzzing_peer_book_round_trip() {
+    let mut rng = thread_rng();
+
+    let mut manager = AddressManager::default();
+    for _ in 0..ITERATIONS {
+        let address = SocketAddr::from((rng.gen::<[u8; 4]>(), rng.gen::<u16>()));
+        let source = SocketAddr::from((rng.gen::<[u8; 4]>(), 4131));
+        manager.add(address, Some(source));
+        if rng.gen_bool(0.2) {
+            manager.mark_good(address);
+        }
+    }
+
+    let serialized = manager.serialize().unwrap();
+    let deserialized = AddressManager::deserialize(&serialized).unwrap();
+    assert_eq!(deserialized.known_peers(), manager.known_peers());
+
+    for _ in 0..ITERATIONS {
+        if let Ok(mut corrupted) = AddressManager::deserialize(&corrupt_bytes(&serialized)) {
+            // The recreated tables have to remain usable.
+            let addresses = corrupted.addresses().collect::<Vec<_>>();
+            for address in addresses {
+                corrupted.mark_good(address);
+            }
+            let _ = corrupted.select(8, |_| true, &[]);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkos_network::{NetworkError, Node, RpcClient, RpcHandler};
+use snarkos_storage::LedgerStorage;
+use snarkos_testing::{
+    network::{test_config, TestSetup},
+    wait_until,
+};
+
+use futures::future::BoxFuture;
+use std::sync::Arc;
+
+/// Echoes the requests of the clients authenticated as `user:pass`.
+struct EchoHandler;
+
+impl RpcHandler for EchoHandler {
+    fn authenticate(&self, credentials: &[u8]) -> bool {
+        credentials == b"user:pass"
+    }
+
+    fn handle(&self, request: Vec<u8>) -> BoxFuture<'static, Vec<u8>> {
+        Box::pin(async move { request })
+    }
+}
+
+async fn rpc_node(serves_rpc: bool) -> Node<LedgerStorage> {
+    let setup = TestSetup {
+        consensus_setup: None,
+        ..Default::default()
+    };
+    let mut node = Node::new(test_config(setup)).await.unwrap();
+    if serves_rpc {
+        node.set_rpc_handler(Arc::new(EchoHandler));
+    }
+
+    node.listen().await.unwrap();
+    node.start_services().await.unwrap();
+
+    node
+}
+
+#[tokio::test]
+async fn rpc_over_p2p() {
+    let node = rpc_node(true).await;
+    let node_addr = node.local_address().unwrap();
+
+    let mut client = RpcClient::connect(node_addr, "user", "pass").await.unwrap();
+
+    let request = br#"{"jsonrpc":"2.0","id":1,"method":"getblockcount"}"#.to_vec();
+    assert_eq!(client.request(request.clone()).await.unwrap(), request);
+
+    // the client is served apart from the peers
+    wait_until!(1, node.peer_book.get_active_peer_count() == 0);
+    assert!(node.peer_book.connected_peers().is_empty());
+}
+
+#[tokio::test]
+async fn rpc_over_p2p_invalid_credentials() {
+    let node = rpc_node(true).await;
+    let node_addr = node.local_address().unwrap();
+
+    assert!(matches!(
+        RpcClient::connect(node_addr, "user", "wrong").await,
+        Err(NetworkError::RpcAuthFailed)
+    ));
+}
+
+#[tokio::test]
+async fn rpc_over_p2p_unavailable() {
+    let node = rpc_node(false).await;
+    let node_addr = node.local_address().unwrap();
+
+    assert!(matches!(
+        RpcClient::connect(node_addr, "user", "pass").await,
+        Err(NetworkError::RpcUnavailable)
+    ));
+}
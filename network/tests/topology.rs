@@ -53,7 +53,7 @@ async fn start_nodes(nodes: &[Node<LedgerStorage>]) {
         // Nodes are started with a slight delay to avoid having peering intervals in phase (this
         // is the hypothetical worst case scenario).
         tokio::time::sleep(std::time::Duration::from_millis(10)).await;
-        node.start_services().await;
+        node.start_services().await.unwrap();
     }
 }
 
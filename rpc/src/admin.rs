@@ -0,0 +1,254 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+//! The admin namespace of the RPC, which holds the node-control endpoints.
+//!
+//! If admin tokens are configured, the admin methods can only be called with one of them, either as a bearer
+//! token or as the key of an HMAC signature of the request, and each token may be limited to some of the methods;
+//! otherwise they are guarded by the RPC credentials like the other private endpoints.
+
+use snarkos_network::verify_signature;
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use subtle::ConstantTimeEq;
+
+/// The node-control endpoints, which form the admin namespace of the RPC.
+pub const ADMIN_METHODS: [&str; 18] = [
+    "disconnect",
+    "rebindlistener",
+    "addpeer",
+    "removepeer",
+    "banpeer",
+    "bansubnet",
+    "unbansubnet",
+    "listbanned",
+    "exportpeers",
+    "importpeers",
+    "exportsnapshot",
+    "dumpmempool",
+    "loadmempool",
+    "annotatepeer",
+    "triggersync",
+    "reloadconfig",
+    "startmining",
+    "stopmining",
+];
+
+/// The prefix the admin methods can be called with, e.g. `admin_addpeer`.
+pub const ADMIN_NAMESPACE_PREFIX: &str = "admin_";
+
+/// The minimum length of the secret of an admin token.
+pub const MIN_ADMIN_SECRET_LEN: usize = 16;
+
+/// The maximum difference, in seconds, between the timestamp of an HMAC-signed request and the node's clock.
+pub const MAX_HMAC_CLOCK_SKEW_SECS: i64 = 300;
+
+/// The maximum length of the nonce of an HMAC-signed request.
+pub const MAX_HMAC_NONCE_LEN: usize = 64;
+
+/// Returns `true` if the given method belongs to the admin namespace.
+pub fn is_admin_method(method: &str) -> bool {
+    ADMIN_METHODS.contains(&method)
+}
+
+/// Resolves the name a method was called with, stripping the admin namespace prefix; returns `None` if the prefix
+/// is used with a method outside of the admin namespace.
+pub fn resolve_method(method: &str) -> Option<&str> {
+    match method.strip_prefix(ADMIN_NAMESPACE_PREFIX) {
+        Some(admin_method) if is_admin_method(admin_method) => Some(admin_method),
+        Some(_) => None,
+        None => Some(method),
+    }
+}
+
+/// A token granting access to the admin namespace of the RPC.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AdminToken {
+    /// The name of the token, which identifies it in HMAC-signed requests and in the logs.
+    pub name: String,
+    /// The secret of the token, sent as a bearer token or used as the key of HMAC signatures.
+    pub secret: String,
+    /// The admin methods the token may call; all of them if empty.
+    #[serde(default)]
+    pub methods: Vec<String>,
+}
+
+impl AdminToken {
+    /// Checks that the token is well-formed and only refers to admin methods.
+    pub fn check(&self) -> Result<(), String> {
+        if self.name.is_empty() || self.name.contains(':') {
+            return Err(format!("'{}' isn't a valid token name", self.name));
+        }
+        if self.secret.len() < MIN_ADMIN_SECRET_LEN {
+            return Err(format!(
+                "the secret of the token '{}' is shorter than {} characters",
+                self.name, MIN_ADMIN_SECRET_LEN
+            ));
+        }
+        if let Some(method) = self.methods.iter().find(|method| !is_admin_method(method)) {
+            return Err(format!("'{}' isn't an admin method", method));
+        }
+
+        Ok(())
+    }
+
+    /// Returns `true` if the token may call the given admin method.
+    pub fn allows(&self, method: &str) -> bool {
+        self.methods.is_empty() || self.methods.iter().any(|allowed| allowed == method)
+    }
+}
+
+/// The nonces of the recently accepted HMAC-signed requests of each token, along with their timestamps, so that
+/// the requests can't be replayed while their timestamps are still valid.
+#[derive(Debug, Default)]
+pub struct SeenNonces(Mutex<HashMap<(String, String), i64>>);
+
+impl SeenNonces {
+    /// Records the nonce of a request signed with the given token at the given time, forgetting the ones whose
+    /// timestamps expired; returns `false` if it was already recorded.
+    fn insert(&self, token_name: &str, nonce: &str, timestamp: i64, now: i64) -> bool {
+        let mut nonces = self.0.lock();
+        nonces.retain(|_, seen| (now - *seen).abs() <= MAX_HMAC_CLOCK_SKEW_SECS);
+
+        nonces
+            .insert((token_name.to_owned(), nonce.to_owned()), timestamp)
+            .is_none()
+    }
+}
+
+/// Finds the admin token a request was authenticated with, given its `Authorization` header and body, and the
+/// current Unix timestamp. The header is either `Bearer <secret>`, or `HMAC <name>:<timestamp>:<nonce>:<signature>`,
+/// where the signature is the hex-encoded HMAC-SHA256 of `<timestamp>:<nonce>:<body>`, keyed with the secret of the
+/// token; the nonce is unique to the request, and a request reusing one within the validity of its timestamp is
+/// refused as a replay.
+pub fn authenticate<'a>(
+    tokens: &'a [AdminToken],
+    seen_nonces: &SeenNonces,
+    auth: &str,
+    body: &[u8],
+    now: i64,
+) -> Option<&'a AdminToken> {
+    if let Some(secret) = auth.strip_prefix("Bearer ") {
+        return tokens
+            .iter()
+            .find(|token| token.secret.as_bytes().ct_eq(secret.as_bytes()).into());
+    }
+
+    let mut parts = auth.strip_prefix("HMAC ")?.splitn(4, ':');
+    let (name, timestamp, nonce, signature) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+    if nonce.is_empty() || nonce.len() > MAX_HMAC_NONCE_LEN {
+        return None;
+    }
+
+    let token = tokens.iter().find(|token| token.name == name)?;
+    let signed_at = timestamp.parse::<i64>().ok()?;
+    if (now - signed_at).abs() > MAX_HMAC_CLOCK_SKEW_SECS {
+        return None;
+    }
+
+    let mut message = format!("{}:{}:", timestamp, nonce).into_bytes();
+    message.extend_from_slice(body);
+    let signature = hex::decode(signature).ok()?;
+
+    // The nonce is only recorded once the signature is verified, so that others can't use it up.
+    if verify_signature(token.secret.as_bytes(), &message, &signature)
+        && seen_nonces.insert(&token.name, nonce, signed_at, now)
+    {
+        Some(token)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkos_network::sign;
+
+    fn tokens() -> Vec<AdminToken> {
+        vec![
+            AdminToken {
+                name: "ops".into(),
+                secret: "0123456789abcdef".into(),
+                methods: vec![],
+            },
+            AdminToken {
+                name: "monitoring".into(),
+                secret: "fedcba9876543210".into(),
+                methods: vec!["listbanned".into(), "triggersync".into()],
+            },
+        ]
+    }
+
+    #[test]
+    fn admin_methods_are_namespaced() {
+        assert_eq!(resolve_method("addpeer"), Some("addpeer"));
+        assert_eq!(resolve_method("admin_addpeer"), Some("addpeer"));
+        assert_eq!(resolve_method("getblockcount"), Some("getblockcount"));
+        assert_eq!(resolve_method("admin_getblockcount"), None);
+    }
+
+    #[test]
+    fn tokens_are_checked() {
+        let mut token = tokens().remove(1);
+        assert!(token.check().is_ok());
+        assert!(token.allows("triggersync"));
+        assert!(!token.allows("banpeer"));
+
+        token.methods.push("createaccount".into());
+        assert!(token.check().is_err());
+
+        token.methods.clear();
+        token.secret = "short".into();
+        assert!(token.check().is_err());
+    }
+
+    #[test]
+    fn requests_are_authenticated() {
+        let tokens = tokens();
+        let body = br#"{"jsonrpc": "2.0", "id": 1, "method": "admin_triggersync"}"#;
+        let now = 1_600_000_000;
+        let seen_nonces = SeenNonces::default();
+        let auth = |header: &str, body: &[u8], now| authenticate(&tokens, &seen_nonces, header, body, now);
+
+        let bearer = auth("Bearer fedcba9876543210", body, now);
+        assert_eq!(bearer.map(|token| token.name.as_str()), Some("monitoring"));
+        assert!(auth("Bearer fedcba987654321", body, now).is_none());
+
+        let signed_header = |name: &str, nonce: &str| {
+            let mut message = format!("{}:{}:", now, nonce).into_bytes();
+            message.extend_from_slice(body);
+            let signature = hex::encode(sign(b"0123456789abcdef", &message));
+
+            format!("HMAC {}:{}:{}:{}", name, now, nonce, signature)
+        };
+
+        // The signature covers the body, and expires.
+        let header = signed_header("ops", "1");
+        assert!(auth(&header, b"{}", now).is_none());
+        assert!(auth(&header, body, now + MAX_HMAC_CLOCK_SKEW_SECS + 1).is_none());
+        assert!(auth(&signed_header("monitoring", "1"), body, now).is_none());
+
+        let signed = auth(&header, body, now + 10);
+        assert_eq!(signed.map(|token| token.name.as_str()), Some("ops"));
+
+        // A request can't be replayed, but another one with a new nonce is accepted.
+        assert!(auth(&header, body, now + 20).is_none());
+        assert!(auth(&signed_header("ops", "2"), body, now + 20).is_some());
+    }
+}
@@ -17,17 +17,20 @@
 //! Logic for instantiating the RPC server.
 
 use crate::{
+    admin::{self, AdminToken},
+    error::RpcErrorCode,
     rpc_trait::RpcFunctions,
     rpc_types::{Meta, RpcCredentials},
     RpcImpl,
 };
 use snarkos_consensus::MerkleTreeLedger;
 use snarkos_metrics::{self as metrics, misc};
-use snarkos_network::Node;
+use snarkos_network::{Node, RpcHandler};
 use snarkvm_dpc::Storage;
 
+use futures::future::{self, BoxFuture};
 use hyper::{
-    body::HttpBody,
+    body::{Bytes, HttpBody},
     server::Server,
     service::{make_service_fn, service_fn},
     Body,
@@ -35,19 +38,26 @@ use hyper::{
 use json_rpc_types as jrt;
 use jsonrpc_core::Params;
 use serde::Serialize;
+use subtle::ConstantTimeEq;
 use tokio::task;
 
 use std::{convert::Infallible, net::SocketAddr, sync::Arc};
 
-const METHODS_EXPECTING_PARAMS: [&str; 14] = [
+const METHODS_EXPECTING_PARAMS: [&str; 35] = [
     // public
     "getblock",
     "getblockhash",
+    "getforkchoice",
     "getrawtransaction",
     "gettransactioninfo",
+    "gettransactionstatus",
+    "gettransactionsbycommitment",
+    "isserialnumberspent",
     "decoderawtransaction",
     "sendtransaction",
     "validaterawtransaction",
+    "getnodestatshistory",
+    "getnetworkgraphat",
     // private
     "createrawtransaction",
     "createtransactionkernel",
@@ -56,8 +66,36 @@ const METHODS_EXPECTING_PARAMS: [&str; 14] = [
     "decoderecord",
     "decryptrecord",
     "disconnect",
+    "addpeer",
+    "removepeer",
+    "banpeer",
+    "bansubnet",
+    "unbansubnet",
+    "exportpeers",
+    "importpeers",
+    "exportsnapshot",
+    "dumpmempool",
+    "loadmempool",
+    "getpeerdetails",
+    "getpeerevents",
+    "annotatepeer",
+    "getmininghistory",
+    "getjournal",
 ];
 
+/// The methods whose params may be omitted.
+const METHODS_WITH_OPTIONAL_PARAMS: [&str; 4] = [
+    // public
+    "getblocktemplate",
+    "getmemorypool",
+    // private
+    "triggersync",
+    "startmining",
+];
+
+/// The maximum size of the body of an RPC request, which fits a full batch of large transactions.
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
 #[allow(clippy::too_many_arguments)]
 pub fn start_rpc_server<S: Storage + Send + Sync + 'static>(
     rpc_addr: SocketAddr,
@@ -65,17 +103,19 @@ pub fn start_rpc_server<S: Storage + Send + Sync + 'static>(
     node_server: Node<S>,
     username: Option<String>,
     password: Option<String>,
+    admin_tokens: Vec<AdminToken>,
+    max_batch_size: usize,
 ) -> task::JoinHandle<()> {
     let credentials = match (username, password) {
         (Some(username), Some(password)) => Some(RpcCredentials { username, password }),
         _ => None,
     };
 
-    let rpc_impl = RpcImpl::new(secondary_storage, credentials, node_server);
+    let rpc_impl = RpcImpl::with_admin_tokens(secondary_storage, credentials, admin_tokens, node_server);
 
     let service = make_service_fn(move |_conn| {
         let rpc = rpc_impl.clone();
-        async move { Ok::<_, Infallible>(service_fn(move |req| handle_rpc(rpc.clone(), req))) }
+        async move { Ok::<_, Infallible>(service_fn(move |req| handle_rpc(rpc.clone(), max_batch_size, req))) }
     });
 
     let server = Server::bind(&rpc_addr).serve(service);
@@ -85,29 +125,114 @@ pub fn start_rpc_server<S: Storage + Send + Sync + 'static>(
     })
 }
 
+/// Starts a JSON-RPC HTTP server on a Unix domain socket at the given path, which is created with the given
+/// permissions (e.g. `0o600`), so that only the local users with access to it can use the RPC. A socket left
+/// behind at the path by a previous run is replaced. The socket is bound inside a private directory and only moved to
+/// the path once its permissions are set, so it's never reachable with the default ones.
+#[cfg(unix)]
+#[allow(clippy::too_many_arguments)]
+pub fn start_rpc_server_on_unix_socket<S: Storage + Send + Sync + 'static>(
+    path: &std::path::Path,
+    mode: u32,
+    secondary_storage: Arc<MerkleTreeLedger<S>>,
+    node_server: Node<S>,
+    username: Option<String>,
+    password: Option<String>,
+    admin_tokens: Vec<AdminToken>,
+    max_batch_size: usize,
+) -> std::io::Result<task::JoinHandle<()>> {
+    use hyper::server::conn::Http;
+    use std::{
+        fs,
+        io,
+        os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt},
+        process,
+    };
+    use tokio::net::UnixListener;
+
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => fs::remove_file(path)?,
+        Ok(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} exists and isn't a socket", path.display()),
+            ));
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("{} isn't a file", path.display())))?;
+    let mut private_dir_name = std::ffi::OsString::from(".");
+    private_dir_name.push(file_name);
+    private_dir_name.push(format!(".{}", process::id()));
+    let private_dir = path.with_file_name(private_dir_name);
+    fs::DirBuilder::new().mode(0o700).create(&private_dir)?;
+
+    let private_path = private_dir.join(file_name);
+    let bound = UnixListener::bind(&private_path).and_then(|listener| {
+        fs::set_permissions(&private_path, fs::Permissions::from_mode(mode))?;
+        fs::rename(&private_path, path)?;
+        Ok(listener)
+    });
+    let _ = fs::remove_file(&private_path);
+    fs::remove_dir(&private_dir)?;
+    let listener = bound?;
+
+    let credentials = match (username, password) {
+        (Some(username), Some(password)) => Some(RpcCredentials { username, password }),
+        _ => None,
+    };
+    let rpc_impl = RpcImpl::with_admin_tokens(secondary_storage, credentials, admin_tokens, node_server);
+
+    Ok(task::spawn(async move {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    error!("Failed to accept an RPC connection on the Unix socket: {}", e);
+                    continue;
+                }
+            };
+
+            let rpc = rpc_impl.clone();
+            task::spawn(async move {
+                let service = service_fn(move |req| handle_rpc(rpc.clone(), max_batch_size, req));
+                if let Err(e) = Http::new().serve_connection(stream, service).await {
+                    debug!("An RPC connection on the Unix socket failed: {}", e);
+                }
+            });
+        }
+    }))
+}
+
 async fn handle_rpc<S: Storage + Send + Sync + 'static>(
     rpc: RpcImpl<S>,
+    max_batch_size: usize,
     req: hyper::Request<Body>,
 ) -> Result<hyper::Response<Body>, Infallible> {
     // Register the request in the metrics.
     metrics::increment_counter!(misc::RPC_REQUESTS);
 
-    // Obtain the username and password, if present.
+    #[cfg(feature = "websocket")]
+    if crate::event_stream::is_event_stream_request(&req) {
+        return Ok(crate::event_stream::handle_event_stream(rpc, req));
+    }
+
+    // Obtain the username and password or the admin token, if present.
     let auth = req
         .headers()
         .get(hyper::header::AUTHORIZATION)
         .map(|h| h.to_str().unwrap_or("").to_owned());
-    let meta = Meta { auth };
 
-    // Ready the body of the request
-    let mut body = req.into_body();
-    let data = match body.data().await {
-        Some(Ok(data)) => data,
-        err_or_none => {
+    // Read the whole body of the request, as batches may span multiple chunks.
+    let data = match read_body(req.into_body()).await {
+        Ok(data) => data,
+        Err(err) => {
             let mut error = jrt::Error::with_custom_msg(jrt::ErrorCode::ParseError, "Couldn't read the RPC body");
-            if let Some(Err(err)) = err_or_none {
-                error.data = Some(err.to_string());
-            }
+            error.data = Some(err);
 
             let resp = jrt::Response::<(), String>::error(jrt::Version::V2, error, None);
             let body = serde_json::to_vec(&resp).unwrap_or_default();
@@ -116,52 +241,220 @@ async fn handle_rpc<S: Storage + Send + Sync + 'static>(
         }
     };
 
-    // Deserialize the JSON-RPC request.
-    let req: jrt::Request<Params> = match serde_json::from_slice(&data) {
-        Ok(req) => req,
+    // The body is kept, as HMAC-signed requests are authenticated against it.
+    let meta = Meta {
+        auth,
+        body: Some(data.clone()),
+    };
+    let body = handle_body(rpc, &data, meta, max_batch_size).await;
+
+    // Send the HTTP response.
+    Ok(hyper::Response::new(body.into()))
+}
+
+/// Reads the whole body of a request, unless it's larger than `MAX_BODY_SIZE`.
+async fn read_body(mut body: Body) -> Result<Bytes, String> {
+    let too_large = || format!("The body is larger than {} bytes", MAX_BODY_SIZE);
+
+    // Reject the requests that announce an oversized body upfront.
+    if body.size_hint().lower() > MAX_BODY_SIZE as u64 {
+        return Err(too_large());
+    }
+
+    let mut data = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|err| err.to_string())?;
+        if data.len() + chunk.len() > MAX_BODY_SIZE {
+            return Err(too_large());
+        }
+        data.extend_from_slice(&chunk);
+    }
+
+    Ok(data.into())
+}
+
+/// Deserializes the JSON-RPC request(s) and handles them, returning the serialized response(s).
+async fn handle_body<S: Storage + Send + Sync + 'static>(
+    rpc: RpcImpl<S>,
+    data: &[u8],
+    meta: Meta,
+    max_batch_size: usize,
+) -> Vec<u8> {
+    let body = match serde_json::from_slice::<serde_json::Value>(data) {
+        Ok(serde_json::Value::Array(batch)) => handle_batch(rpc, batch, meta, max_batch_size).await,
+        Ok(value) => match serde_json::from_value::<jrt::Request<Params>>(value) {
+            Ok(req) => serde_json::to_vec(&handle_request_blocking(rpc, req, meta).await),
+            Err(_) => serde_json::to_vec(&parse_error()),
+        },
+        Err(_) => serde_json::to_vec(&parse_error()),
+    };
+
+    body.unwrap_or_default()
+}
+
+/// Serves the RPC to the management clients authenticated over the P2P port of the node, which only
+/// accepts them if their credentials match the ones of the RPC server; as they can't present an admin
+/// token, they can only call the admin methods if no admin tokens are configured.
+pub struct P2pRpcHandler<S: Storage + Send + Sync + 'static> {
+    rpc: RpcImpl<S>,
+    auth: String,
+    max_batch_size: usize,
+}
+
+impl<S: Storage + Send + Sync + 'static> P2pRpcHandler<S> {
+    pub fn new(
+        secondary_storage: Arc<MerkleTreeLedger<S>>,
+        node_server: Node<S>,
+        username: String,
+        password: String,
+        admin_tokens: Vec<AdminToken>,
+        max_batch_size: usize,
+    ) -> Self {
+        let auth = format!("Basic {}", base64::encode(format!("{}:{}", username, password)));
+        let credentials = RpcCredentials { username, password };
+
+        Self {
+            rpc: RpcImpl::with_admin_tokens(secondary_storage, Some(credentials), admin_tokens, node_server),
+            auth,
+            max_batch_size,
+        }
+    }
+}
+
+impl<S: Storage + Send + Sync + 'static> RpcHandler for P2pRpcHandler<S> {
+    fn authenticate(&self, credentials: &[u8]) -> bool {
+        // The comparison takes the same time wherever the credentials differ, so that timing it reveals nothing.
+        let auth = format!("Basic {}", base64::encode(credentials));
+        auth.as_bytes().ct_eq(self.auth.as_bytes()).into()
+    }
+
+    fn handle(&self, request: Vec<u8>) -> BoxFuture<'static, Vec<u8>> {
+        // Register the request in the metrics.
+        metrics::increment_counter!(misc::RPC_REQUESTS);
+
+        // The client was authenticated when it connected, so its requests carry the credentials.
+        let meta = Meta {
+            auth: Some(self.auth.clone()),
+            body: None,
+        };
+        let rpc = self.rpc.clone();
+        let max_batch_size = self.max_batch_size;
+
+        Box::pin(async move { handle_body(rpc, &request, meta, max_batch_size).await })
+    }
+}
+
+/// Handles a batch of JSON-RPC requests concurrently, responding with an array of their responses.
+async fn handle_batch<S: Storage + Send + Sync + 'static>(
+    rpc: RpcImpl<S>,
+    batch: Vec<serde_json::Value>,
+    meta: Meta,
+    max_batch_size: usize,
+) -> serde_json::Result<Vec<u8>> {
+    if batch.is_empty() {
+        let err = jrt::Error::with_custom_msg(jrt::ErrorCode::InvalidRequest, "The batch is empty");
+        let resp = jrt::Response::<(), String>::error(jrt::Version::V2, err, None);
+
+        return serde_json::to_vec(&resp);
+    }
+
+    if batch.len() > max_batch_size {
+        let err = jrt::Error::with_custom_msg(jrt::ErrorCode::InvalidRequest, "The batch is too large")
+            .set_data(format!("{} requests, the limit is {}", batch.len(), max_batch_size));
+        let resp = jrt::Response::<(), String>::error(jrt::Version::V2, err, None);
+
+        return serde_json::to_vec(&resp);
+    }
+
+    let responses = batch.into_iter().map(|value| {
+        let rpc = rpc.clone();
+        let meta = meta.clone();
+
+        async move {
+            match serde_json::from_value::<jrt::Request<Params>>(value) {
+                Ok(req) => handle_request_blocking(rpc, req, meta).await,
+                Err(_) => {
+                    let err = jrt::Error::from_code(jrt::ErrorCode::InvalidRequest);
+                    jrt::Response::error(jrt::Version::V2, err, None)
+                }
+            }
+        }
+    });
+
+    serde_json::to_vec(&future::join_all(responses).await)
+}
+
+/// Handles a single JSON-RPC request on the blocking thread pool, as most of the methods read the storage
+/// synchronously; this way, neither the entries of a batch nor the other tasks of the node stall one another.
+async fn handle_request_blocking<S: Storage + Send + Sync + 'static>(
+    rpc: RpcImpl<S>,
+    req: jrt::Request<Params>,
+    meta: Meta,
+) -> jrt::Response<serde_json::Value, serde_json::Value> {
+    let id = req.id.clone();
+    let runtime = tokio::runtime::Handle::current();
+
+    match task::spawn_blocking(move || runtime.block_on(handle_request(rpc, req, meta))).await {
+        Ok(response) => response,
         Err(_) => {
-            let resp = jrt::Response::<(), ()>::error(
-                jrt::Version::V2,
-                jrt::Error::with_custom_msg(jrt::ErrorCode::ParseError, "Couldn't parse the RPC body"),
-                None,
-            );
-            let body = serde_json::to_vec(&resp).unwrap_or_default();
+            let err = jrt::Error::from_code(jrt::ErrorCode::InternalError);
+            jrt::Response::error(jrt::Version::V2, err, id)
+        }
+    }
+}
 
-            return Ok(hyper::Response::new(body.into()));
+/// Handles a single JSON-RPC request.
+async fn handle_request<S: Storage + Send + Sync + 'static>(
+    rpc: RpcImpl<S>,
+    req: jrt::Request<Params>,
+    meta: Meta,
+) -> jrt::Response<serde_json::Value, serde_json::Value> {
+    // The admin methods may be called in their namespace, e.g. as `admin_addpeer`.
+    let method = match admin::resolve_method(&req.method) {
+        Some(method) => method,
+        None => {
+            let err = jrt::Error::from_code(jrt::ErrorCode::MethodNotFound);
+            return jrt::Response::error(jrt::Version::V2, err, req.id.clone());
         }
     };
 
     // Read the request params.
-    let mut params = match read_params(&req) {
+    let mut params = match read_params(method, &req) {
         Ok(params) => params,
-        Err(err) => {
-            let resp = jrt::Response::<(), ()>::error(jrt::Version::V2, err, req.id.clone());
-            let body = serde_json::to_vec(&resp).unwrap_or_default();
-
-            return Ok(hyper::Response::new(body.into()));
-        }
+        Err(err) => return jrt::Response::error(jrt::Version::V2, err, req.id.clone()),
     };
 
     // Handle the request method.
-    let response = match &*req.method {
+    match method {
         // public
-        "getblock" => {
-            let result = rpc
-                .get_block(params[0].as_str().unwrap_or("").into())
-                .map_err(convert_crate_err);
-            result_to_response(&req, result)
-        }
+        "getblock" => match optional_param(&params, 1, |x| {
+            x.as_u64()
+                .filter(|verbosity| *verbosity <= u8::MAX as u64)
+                .map(|verbosity| verbosity as u8)
+        }) {
+            Some(verbosity) => {
+                let result = rpc
+                    .get_block(params[0].as_str().unwrap_or("").into(), verbosity)
+                    .await
+                    .map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            None => {
+                let err = jrt::Error::with_custom_msg(jrt::ErrorCode::ParseError, "Invalid verbosity!");
+                jrt::Response::error(jrt::Version::V2, err, req.id.clone())
+            }
+        },
         "getblockcount" => {
-            let result = rpc.get_block_count().map_err(convert_crate_err);
+            let result = rpc.get_block_count().await.map_err(convert_crate_err);
             result_to_response(&req, result)
         }
         "getbestblockhash" => {
-            let result = rpc.get_best_block_hash().map_err(convert_crate_err);
+            let result = rpc.get_best_block_hash().await.map_err(convert_crate_err);
             result_to_response(&req, result)
         }
         "getblockhash" => match serde_json::from_value::<u32>(params.remove(0)) {
             Ok(height) => {
-                let result = rpc.get_block_hash(height).map_err(convert_crate_err);
+                let result = rpc.get_block_hash(height).await.map_err(convert_crate_err);
                 result_to_response(&req, result)
             }
             Err(_) => {
@@ -169,56 +462,157 @@ async fn handle_rpc<S: Storage + Send + Sync + 'static>(
                 jrt::Response::error(jrt::Version::V2, err, req.id.clone())
             }
         },
+        "getchaintips" => {
+            let result = rpc.get_chain_tips().await.map_err(convert_crate_err);
+            result_to_response(&req, result)
+        }
+        "getchainanalytics" => {
+            let result = rpc.get_chain_analytics().await.map_err(convert_crate_err);
+            result_to_response(&req, result)
+        }
+        "getforkchoice" => {
+            let result = rpc
+                .get_fork_choice(params[0].as_str().unwrap_or("").into())
+                .await
+                .map_err(convert_crate_err);
+            result_to_response(&req, result)
+        }
         "getrawtransaction" => {
             let result = rpc
                 .get_raw_transaction(params[0].as_str().unwrap_or("").into())
+                .await
                 .map_err(convert_crate_err);
             result_to_response(&req, result)
         }
         "gettransactioninfo" => {
             let result = rpc
                 .get_transaction_info(params[0].as_str().unwrap_or("").into())
+                .await
+                .map_err(convert_crate_err);
+            result_to_response(&req, result)
+        }
+        "gettransactionstatus" => {
+            let result = rpc
+                .get_transaction_status(params[0].as_str().unwrap_or("").into())
+                .await
+                .map_err(convert_crate_err);
+            result_to_response(&req, result)
+        }
+        "gettransactionsbycommitment" => match serde_json::from_value::<Vec<String>>(params.remove(0)) {
+            Ok(commitments) => {
+                let result = rpc
+                    .get_transactions_by_commitment(commitments)
+                    .await
+                    .map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            Err(_) => {
+                let err = jrt::Error::with_custom_msg(jrt::ErrorCode::InvalidParams, "Invalid commitments!");
+                jrt::Response::error(jrt::Version::V2, err, req.id.clone())
+            }
+        },
+        "isserialnumberspent" => {
+            let result = rpc
+                .is_serial_number_spent(params[0].as_str().unwrap_or("").into())
+                .await
                 .map_err(convert_crate_err);
             result_to_response(&req, result)
         }
         "decoderawtransaction" => {
             let result = rpc
                 .decode_raw_transaction(params[0].as_str().unwrap_or("").into())
+                .await
                 .map_err(convert_crate_err);
             result_to_response(&req, result)
         }
         "sendtransaction" => {
             let result = rpc
                 .send_raw_transaction(params[0].as_str().unwrap_or("").into())
+                .await
                 .map_err(convert_crate_err);
             result_to_response(&req, result)
         }
         "validaterawtransaction" => {
             let result = rpc
                 .validate_raw_transaction(params[0].as_str().unwrap_or("").into())
+                .await
                 .map_err(convert_crate_err);
             result_to_response(&req, result)
         }
         "getconnectioncount" => {
-            let result = rpc.get_connection_count().map_err(convert_crate_err);
+            let result = rpc.get_connection_count().await.map_err(convert_crate_err);
             result_to_response(&req, result)
         }
         "getpeerinfo" => {
-            let result = rpc.get_peer_info().map_err(convert_crate_err);
+            let result = rpc.get_peer_info().await.map_err(convert_crate_err);
             result_to_response(&req, result)
         }
         "getnodeinfo" => {
-            let result = rpc.get_node_info().map_err(convert_crate_err);
+            let result = rpc.get_node_info().await.map_err(convert_crate_err);
             result_to_response(&req, result)
         }
-        "getnodestats" => {
-            let result = rpc.get_node_stats().map_err(convert_crate_err);
+        "getsyncstatus" => {
+            let result = rpc.get_sync_status().await.map_err(convert_crate_err);
+            result_to_response(&req, result)
+        }
+        "getnetworkgraph" => {
+            let result = rpc.get_network_graph().await.map_err(convert_crate_err);
             result_to_response(&req, result)
         }
-        "getblocktemplate" => {
-            let result = rpc.get_block_template().map_err(convert_crate_err);
+        "getnetworkgraphat" => match serde_json::from_value::<i64>(params.remove(0)) {
+            Ok(timestamp) => {
+                let result = rpc.get_network_graph_at(timestamp).await.map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            Err(_) => {
+                let err = jrt::Error::with_custom_msg(jrt::ErrorCode::ParseError, "Invalid timestamp!");
+                jrt::Response::error(jrt::Version::V2, err, req.id.clone())
+            }
+        },
+        "getnodestats" => {
+            let result = rpc.get_node_stats().await.map_err(convert_crate_err);
             result_to_response(&req, result)
         }
+        "getnodestatshistory" => match (params.get(0).map(|x| x.as_u64()), params.get(1).map(|x| x.as_u64())) {
+            (Some(Some(range_secs)), Some(Some(step_secs))) => {
+                let result = rpc
+                    .get_node_stats_history(range_secs, step_secs)
+                    .await
+                    .map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            _ => {
+                let err = jrt::Error::with_custom_msg(jrt::ErrorCode::ParseError, "Invalid range or step!");
+                jrt::Response::error(jrt::Version::V2, err, req.id.clone())
+            }
+        },
+        "getblocktemplate" => match optional_param(&params, 0, |x| x.as_str().map(String::from)) {
+            Some(longpoll_id) => {
+                let result = rpc.get_block_template(longpoll_id).await.map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            None => {
+                let err = jrt::Error::with_custom_msg(jrt::ErrorCode::ParseError, "Invalid long-poll id!");
+                jrt::Response::error(jrt::Version::V2, err, req.id.clone())
+            }
+        },
+        "getmemorypool" => match (
+            optional_param(&params, 0, |x| x.as_u64().map(|offset| offset as usize)),
+            optional_param(&params, 1, |x| x.as_u64().map(|limit| limit as usize)),
+            optional_param(&params, 2, serde_json::Value::as_i64),
+        ) {
+            (Some(offset), Some(limit), Some(min_fee)) => {
+                let result = rpc
+                    .get_memory_pool(offset, limit, min_fee)
+                    .await
+                    .map_err(convert_crate_err);
+                result_to_response(&req, result)
+            }
+            _ => {
+                let err = jrt::Error::with_custom_msg(jrt::ErrorCode::ParseError, "Invalid offset, limit or fee!");
+                jrt::Response::error(jrt::Version::V2, err, req.id.clone())
+            }
+        },
         // private
         "createaccount" => {
             let result = rpc
@@ -283,48 +677,246 @@ async fn handle_rpc<S: Storage + Send + Sync + 'static>(
                 .map_err(convert_core_err);
             result_to_response(&req, result)
         }
+        "rebindlistener" => {
+            let result = rpc
+                .rebind_listener_protected(Params::Array(params), meta)
+                .await
+                .map_err(convert_core_err);
+            result_to_response(&req, result)
+        }
+        "addpeer" => {
+            let result = rpc
+                .add_peer_protected(Params::Array(params), meta)
+                .await
+                .map_err(convert_core_err);
+            result_to_response(&req, result)
+        }
+        "removepeer" => {
+            let result = rpc
+                .remove_peer_protected(Params::Array(params), meta)
+                .await
+                .map_err(convert_core_err);
+            result_to_response(&req, result)
+        }
+        "banpeer" => {
+            let result = rpc
+                .ban_peer_protected(Params::Array(params), meta)
+                .await
+                .map_err(convert_core_err);
+            result_to_response(&req, result)
+        }
+        "bansubnet" => {
+            let result = rpc
+                .ban_subnet_protected(Params::Array(params), meta)
+                .await
+                .map_err(convert_core_err);
+            result_to_response(&req, result)
+        }
+        "unbansubnet" => {
+            let result = rpc
+                .unban_subnet_protected(Params::Array(params), meta)
+                .await
+                .map_err(convert_core_err);
+            result_to_response(&req, result)
+        }
+        "listbanned" => {
+            let result = rpc
+                .list_banned_protected(Params::Array(params), meta)
+                .await
+                .map_err(convert_core_err);
+            result_to_response(&req, result)
+        }
+        "exportpeers" => {
+            let result = rpc
+                .export_peers_protected(Params::Array(params), meta)
+                .await
+                .map_err(convert_core_err);
+            result_to_response(&req, result)
+        }
+        "exportsnapshot" => {
+            let result = rpc
+                .export_snapshot_protected(Params::Array(params), meta)
+                .await
+                .map_err(convert_core_err);
+            result_to_response(&req, result)
+        }
+        "importpeers" => {
+            let result = rpc
+                .import_peers_protected(Params::Array(params), meta)
+                .await
+                .map_err(convert_core_err);
+            result_to_response(&req, result)
+        }
+        "dumpmempool" => {
+            let result = rpc
+                .dump_mempool_protected(Params::Array(params), meta)
+                .await
+                .map_err(convert_core_err);
+            result_to_response(&req, result)
+        }
+        "loadmempool" => {
+            let result = rpc
+                .load_mempool_protected(Params::Array(params), meta)
+                .await
+                .map_err(convert_core_err);
+            result_to_response(&req, result)
+        }
+        "getpeerdetails" => {
+            let result = rpc
+                .get_peer_details_protected(Params::Array(params), meta)
+                .await
+                .map_err(convert_core_err);
+            result_to_response(&req, result)
+        }
+        "getpeerevents" => {
+            let result = rpc
+                .get_peer_events_protected(Params::Array(params), meta)
+                .await
+                .map_err(convert_core_err);
+            result_to_response(&req, result)
+        }
+        "annotatepeer" => {
+            let result = rpc
+                .annotate_peer_protected(Params::Array(params), meta)
+                .await
+                .map_err(convert_core_err);
+            result_to_response(&req, result)
+        }
+        "getpeerscores" => {
+            let result = rpc
+                .get_peer_scores_protected(Params::Array(params), meta)
+                .await
+                .map_err(convert_core_err);
+            result_to_response(&req, result)
+        }
+        "getpeerlatencies" => {
+            let result = rpc
+                .get_peer_latencies_protected(Params::Array(params), meta)
+                .await
+                .map_err(convert_core_err);
+            result_to_response(&req, result)
+        }
+        "getpeerages" => {
+            let result = rpc
+                .get_peer_ages_protected(Params::Array(params), meta)
+                .await
+                .map_err(convert_core_err);
+            result_to_response(&req, result)
+        }
+        "triggersync" => {
+            let result = rpc
+                .trigger_sync_protected(Params::Array(params), meta)
+                .await
+                .map_err(convert_core_err);
+            result_to_response(&req, result)
+        }
+        "reloadconfig" => {
+            let result = rpc
+                .reload_config_protected(Params::Array(params), meta)
+                .await
+                .map_err(convert_core_err);
+            result_to_response(&req, result)
+        }
+        "getmininghistory" => {
+            let result = rpc
+                .get_mining_history_protected(Params::Array(params), meta)
+                .await
+                .map_err(convert_core_err);
+            result_to_response(&req, result)
+        }
+        "getjournal" => {
+            let result = rpc
+                .get_journal_protected(Params::Array(params), meta)
+                .await
+                .map_err(convert_core_err);
+            result_to_response(&req, result)
+        }
+        "startmining" => {
+            let result = rpc
+                .start_mining_protected(Params::Array(params), meta)
+                .await
+                .map_err(convert_core_err);
+            result_to_response(&req, result)
+        }
+        "stopmining" => {
+            let result = rpc
+                .stop_mining_protected(Params::Array(params), meta)
+                .await
+                .map_err(convert_core_err);
+            result_to_response(&req, result)
+        }
         _ => {
             let err = jrt::Error::from_code(jrt::ErrorCode::MethodNotFound);
             jrt::Response::error(jrt::Version::V2, err, req.id.clone())
         }
-    };
-
-    // Serialize the response object.
-    let body = serde_json::to_vec(&response).unwrap_or_default();
+    }
+}
 
-    // Send the HTTP response.
-    Ok(hyper::Response::new(body.into()))
+/// Returns the response to a body that isn't a valid JSON-RPC request.
+fn parse_error() -> jrt::Response<(), ()> {
+    jrt::Response::error(
+        jrt::Version::V2,
+        jrt::Error::with_custom_msg(jrt::ErrorCode::ParseError, "Couldn't parse the RPC body"),
+        None,
+    )
 }
 
 /// Ensures that the params are a non-empty (this assumption is taken advantage of later) array and returns them.
-fn read_params(req: &jrt::Request<Params>) -> Result<Vec<serde_json::Value>, jrt::Error<()>> {
-    if METHODS_EXPECTING_PARAMS.contains(&&*req.method) {
+fn read_params(
+    method: &str,
+    req: &jrt::Request<Params>,
+) -> Result<Vec<serde_json::Value>, jrt::Error<serde_json::Value>> {
+    if METHODS_EXPECTING_PARAMS.contains(&method) {
         match &req.params {
             Some(Params::Array(arr)) if !arr.is_empty() => Ok(arr.clone()),
             Some(_) => Err(jrt::Error::from_code(jrt::ErrorCode::InvalidParams)),
             None => Err(jrt::Error::from_code(jrt::ErrorCode::InvalidParams)),
         }
+    } else if METHODS_WITH_OPTIONAL_PARAMS.contains(&method) {
+        match &req.params {
+            Some(Params::Array(arr)) => Ok(arr.clone()),
+            Some(_) => Err(jrt::Error::from_code(jrt::ErrorCode::InvalidParams)),
+            None => Ok(vec![]),
+        }
     } else {
         Ok(vec![]) // unused in methods other than METHODS_EXPECTING_PARAMS
     }
 }
 
-/// Converts the crate's RpcError into a jrt::RpcError
-fn convert_crate_err(err: crate::error::RpcError) -> jrt::Error<String> {
-    let error = jrt::Error::with_custom_msg(jrt::ErrorCode::ServerError(-32000), "internal error");
-    error.set_data(err.to_string())
+/// Reads an optional param; returns `Some(None)` if it's missing or `null`, and `None` if it's of the wrong type.
+fn optional_param<T>(
+    params: &[serde_json::Value],
+    index: usize,
+    extract: fn(&serde_json::Value) -> Option<T>,
+) -> Option<Option<T>> {
+    match params.get(index) {
+        None | Some(serde_json::Value::Null) => Some(None),
+        Some(value) => extract(value).map(Some),
+    }
+}
+
+/// Converts the crate's RpcError into a jrt::RpcError, named after its code and carrying its details
+fn convert_crate_err(err: crate::error::RpcError) -> jrt::Error<serde_json::Value> {
+    let code = err.code();
+    let error = jrt::Error::with_custom_msg(jrt::ErrorCode::from_code(code.code()), code.name());
+    error.set_data(err.data())
 }
 
-/// Converts the jsonrpc-core's Error into a jrt::RpcError
-fn convert_core_err(err: jsonrpc_core::Error) -> jrt::Error<String> {
-    let error = jrt::Error::with_custom_msg(jrt::ErrorCode::InternalError, "JSONRPC server error");
-    error.set_data(err.to_string())
+/// Converts the jsonrpc-core's Error into a jrt::RpcError, keeping its code
+fn convert_core_err(err: jsonrpc_core::Error) -> jrt::Error<serde_json::Value> {
+    let code = jrt::ErrorCode::from_code(err.code.code());
+    // The errors converted from the crate's RpcError are already named after their code.
+    let is_named = RpcErrorCode::from_code(err.code.code()).map_or(false, |code| code.name() == err.message);
+    match err.data {
+        Some(data) if is_named => jrt::Error::with_custom_msg(code, &err.message).set_data(data),
+        _ => jrt::Error::from_code(code).set_data(serde_json::Value::String(err.message)),
+    }
 }
 
 fn result_to_response<T: Serialize>(
     request: &jrt::Request<Params>,
-    result: Result<T, jrt::Error<String>>,
-) -> jrt::Response<serde_json::Value, String> {
+    result: Result<T, jrt::Error<serde_json::Value>>,
+) -> jrt::Response<serde_json::Value, serde_json::Value> {
     match result {
         Ok(res) => {
             let result = serde_json::to_value(&res).unwrap_or_default();
@@ -15,10 +15,105 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use snarkos_consensus::error::ConsensusError;
+use snarkos_network::NetworkError;
 use snarkvm_algorithms::errors::CRHError;
 use snarkvm_dpc::{AccountError, BlockError, DPCError, StorageError, TransactionError};
 
-use std::fmt::Debug;
+use serde_json::{json, Value};
+use std::{
+    fmt::{self, Debug},
+    net::SocketAddr,
+};
+
+/// The codes identifying the failure modes of the RPC endpoints; they are sent as the `code` of the JSON-RPC
+/// errors, while their names are sent as the `message`, so that clients don't need to parse the error messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpcErrorCode {
+    /// An unexpected failure, e.g. in the node's storage.
+    InternalError = -32000,
+    /// The requested block isn't known to the node.
+    BlockNotFound = -32001,
+    /// The requested block was pruned; only its header is available.
+    BlockPruned = -32002,
+    /// The requested transaction isn't known to the node.
+    TxNotFound = -32003,
+    /// The given transaction is malformed or doesn't verify.
+    TxInvalid = -32004,
+    /// The given transaction conflicts with the ledger, e.g. it spends records that were already spent.
+    TxConflict = -32005,
+    /// The node is syncing blocks, so its view of the chain is outdated.
+    NodeSyncing = -32006,
+    /// The node doesn't participate in consensus.
+    NoConsensus = -32007,
+    /// The node isn't running in crawler mode.
+    NotCrawler = -32008,
+    /// The given peer isn't known to the node or isn't connected.
+    PeerNotFound = -32009,
+    /// A failure of the node's networking stack, e.g. an invalid subnet or peer annotation.
+    NetworkError = -32010,
+    /// The admin token the request was made with may not call the method.
+    PermissionDenied = -32011,
+    /// No snapshot of the crawled network was taken at or before the requested time.
+    SnapshotNotFound = -32012,
+    /// The given parameters are invalid, e.g. not valid hex.
+    InvalidParams = -32602,
+}
+
+impl RpcErrorCode {
+    /// Returns the numeric value of the code.
+    pub fn code(self) -> i64 {
+        self as i64
+    }
+
+    /// Returns the code with the given numeric value, if there is one.
+    pub fn from_code(code: i64) -> Option<Self> {
+        let code = match code {
+            -32000 => Self::InternalError,
+            -32001 => Self::BlockNotFound,
+            -32002 => Self::BlockPruned,
+            -32003 => Self::TxNotFound,
+            -32004 => Self::TxInvalid,
+            -32005 => Self::TxConflict,
+            -32006 => Self::NodeSyncing,
+            -32007 => Self::NoConsensus,
+            -32008 => Self::NotCrawler,
+            -32009 => Self::PeerNotFound,
+            -32010 => Self::NetworkError,
+            -32011 => Self::PermissionDenied,
+            -32012 => Self::SnapshotNotFound,
+            -32602 => Self::InvalidParams,
+            _ => return None,
+        };
+
+        Some(code)
+    }
+
+    /// Returns the name of the code, e.g. `BLOCK_NOT_FOUND`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::InternalError => "INTERNAL_ERROR",
+            Self::BlockNotFound => "BLOCK_NOT_FOUND",
+            Self::BlockPruned => "BLOCK_PRUNED",
+            Self::TxNotFound => "TX_NOT_FOUND",
+            Self::TxInvalid => "TX_INVALID",
+            Self::TxConflict => "TX_CONFLICT",
+            Self::NodeSyncing => "NODE_SYNCING",
+            Self::NoConsensus => "NO_CONSENSUS",
+            Self::NotCrawler => "NOT_CRAWLER",
+            Self::PeerNotFound => "PEER_NOT_FOUND",
+            Self::NetworkError => "NETWORK_ERROR",
+            Self::PermissionDenied => "PERMISSION_DENIED",
+            Self::SnapshotNotFound => "SNAPSHOT_NOT_FOUND",
+            Self::InvalidParams => "INVALID_PARAMS",
+        }
+    }
+}
+
+impl fmt::Display for RpcErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
 
 #[derive(Debug, Error)]
 pub enum RpcError {
@@ -28,6 +123,9 @@ pub enum RpcError {
     #[error("{}", _0)]
     BlockError(BlockError),
 
+    #[error("unknown block: {}", _0)]
+    BlockNotFound(String),
+
     #[error("{}", _0)]
     ConsensusError(ConsensusError),
 
@@ -43,20 +141,128 @@ pub enum RpcError {
     #[error("invalid block hash: {}", _0)]
     InvalidBlockHash(String),
 
+    #[error("invalid hex: {}", _0)]
+    InvalidHex(String),
+
     #[error("invalid metadata: {}", _0)]
     InvalidMetadata(String),
 
+    #[error("invalid memory pool dump: {}", _0)]
+    InvalidMempoolDump(String),
+
+    #[error("invalid snapshot path: {}; it must be relative to the snapshot directory", _0)]
+    InvalidSnapshotPath(String),
+
+    #[error("invalid transaction: {}", _0)]
+    InvalidTransaction(String),
+
+    #[error("invalid verbosity: {}; it must be 0, 1 or 2", _0)]
+    InvalidVerbosity(u8),
+
     #[error("{}", _0)]
     Message(String),
 
+    #[error("{}", _0)]
+    NetworkError(NetworkError),
+
     #[error("The node doesn't have the sync layer running")]
     NoConsensus,
 
+    #[error("The node is syncing blocks")]
+    NodeSyncing,
+
+    #[error("The node isn't running in crawler mode")]
+    NotCrawler,
+
+    #[error("the admin token '{}' may not call '{}'", _0, _1)]
+    PermissionDenied(String, String),
+
+    #[error("block {} has been pruned; only its header is available", _0)]
+    PrunedBlock(String),
+
+    #[error("no snapshot of the network was taken at or before {}", _0)]
+    SnapshotNotFound(i64),
+
     #[error("{}", _0)]
     StorageError(StorageError),
 
+    #[error("at most {} items can be requested at once, but {} were", _0, _1)]
+    TooManyItems(usize, usize),
+
+    #[error("transaction {} spends records that were already spent", _0)]
+    TransactionConflict(String),
+
     #[error("{}", _0)]
     TransactionError(TransactionError),
+
+    #[error("unknown transaction: {}", _0)]
+    TransactionNotFound(String),
+
+    #[error("unknown peer: {}", _0)]
+    UnknownPeer(SocketAddr),
+}
+
+impl RpcError {
+    /// Returns the code identifying the failure mode behind the error.
+    pub fn code(&self) -> RpcErrorCode {
+        match self {
+            Self::BlockNotFound(_) => RpcErrorCode::BlockNotFound,
+            Self::PrunedBlock(_) => RpcErrorCode::BlockPruned,
+            Self::TransactionNotFound(_) => RpcErrorCode::TxNotFound,
+            Self::InvalidTransaction(_) | Self::TransactionError(_) => RpcErrorCode::TxInvalid,
+            Self::TransactionConflict(_) => RpcErrorCode::TxConflict,
+            Self::NodeSyncing => RpcErrorCode::NodeSyncing,
+            Self::NoConsensus => RpcErrorCode::NoConsensus,
+            Self::NotCrawler => RpcErrorCode::NotCrawler,
+            Self::UnknownPeer(_) => RpcErrorCode::PeerNotFound,
+            Self::NetworkError(_) => RpcErrorCode::NetworkError,
+            Self::PermissionDenied(..) => RpcErrorCode::PermissionDenied,
+            Self::SnapshotNotFound(_) => RpcErrorCode::SnapshotNotFound,
+            Self::InvalidBlockHash(_)
+            | Self::InvalidHex(_)
+            | Self::InvalidMetadata(_)
+            | Self::InvalidMempoolDump(_)
+            | Self::InvalidSnapshotPath(_)
+            | Self::InvalidVerbosity(_)
+            | Self::AccountError(_)
+            | Self::TooManyItems(..) => RpcErrorCode::InvalidParams,
+            Self::BlockError(_)
+            | Self::ConsensusError(_)
+            | Self::Crate(..)
+            | Self::CRHError(_)
+            | Self::DPCError(_)
+            | Self::Message(_)
+            | Self::StorageError(_) => RpcErrorCode::InternalError,
+        }
+    }
+
+    /// Returns the data sent along with the error: the name of its code, its message and the subject of the
+    /// error, if any, e.g. the hash of the block that wasn't found.
+    pub fn data(&self) -> Value {
+        let mut data = json!({
+            "error": self.code().name(),
+            "details": self.to_string(),
+        });
+
+        match self {
+            Self::BlockNotFound(block) | Self::InvalidBlockHash(block) | Self::PrunedBlock(block) => {
+                data["block"] = Value::from(block.as_str());
+            }
+            Self::TransactionConflict(txid) | Self::TransactionNotFound(txid) => {
+                data["txid"] = Value::from(txid.as_str());
+            }
+            Self::UnknownPeer(address) => {
+                data["address"] = Value::from(address.to_string());
+            }
+            Self::PermissionDenied(token, method) => {
+                data["token"] = Value::from(token.as_str());
+                data["method"] = Value::from(method.as_str());
+            }
+            _ => {}
+        }
+
+        data
+    }
 }
 
 impl From<AccountError> for RpcError {
@@ -91,7 +297,12 @@ impl From<DPCError> for RpcError {
 
 impl From<StorageError> for RpcError {
     fn from(error: StorageError) -> Self {
-        RpcError::StorageError(error)
+        match error {
+            StorageError::MissingBlockHash(height) => RpcError::BlockNotFound(height.to_string()),
+            StorageError::MissingBlockNumber(hash) => RpcError::BlockNotFound(hash),
+            StorageError::InvalidTransactionId(txid) => RpcError::TransactionNotFound(txid),
+            error => RpcError::StorageError(error),
+        }
     }
 }
 
@@ -101,9 +312,15 @@ impl From<TransactionError> for RpcError {
     }
 }
 
+impl From<NetworkError> for RpcError {
+    fn from(error: NetworkError) -> Self {
+        RpcError::NetworkError(error)
+    }
+}
+
 impl From<hex::FromHexError> for RpcError {
     fn from(error: hex::FromHexError) -> Self {
-        RpcError::Crate("hex", format!("{:?}", error))
+        RpcError::InvalidHex(error.to_string())
     }
 }
 
@@ -132,8 +349,14 @@ impl From<anyhow::Error> for RpcError {
 }
 
 impl From<RpcError> for jsonrpc_core::Error {
-    fn from(_error: RpcError) -> Self {
-        jsonrpc_core::Error::invalid_request()
+    fn from(error: RpcError) -> Self {
+        let code = error.code();
+
+        jsonrpc_core::Error {
+            code: jsonrpc_core::ErrorCode::from(code.code()),
+            message: code.name().into(),
+            data: Some(error.data()),
+        }
     }
 }
 
@@ -0,0 +1,157 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+//! An optional WebSocket endpoint at `/events` streaming the events journaled by the node as they occur, as JSON
+//! text messages; the clients authenticate with the RPC credentials and may only subscribe to some categories of
+//! events via the `categories` query parameter, e.g. `/events?categories=chain,alarms`.
+
+use crate::{rpc_types::Meta, RpcImpl};
+use snarkos_network::{EventCategory, JournalEvent};
+use snarkvm_dpc::Storage;
+
+use futures::{
+    future::{self, Either},
+    SinkExt,
+    StreamExt,
+};
+use hyper::{header, Body, Request, Response, StatusCode};
+use std::str::FromStr;
+use tokio::{sync::broadcast::error::RecvError, task};
+use tokio_tungstenite::{
+    tungstenite::{handshake::derive_accept_key, protocol::Role, Message},
+    WebSocketStream,
+};
+
+/// The path of the event stream endpoint.
+pub const EVENT_STREAM_PATH: &str = "/events";
+
+/// Checks whether the given request is an attempt to open the event stream.
+pub fn is_event_stream_request(req: &Request<Body>) -> bool {
+    req.uri().path() == EVENT_STREAM_PATH
+        && req
+            .headers()
+            .get(header::UPGRADE)
+            .and_then(|upgrade| upgrade.to_str().ok())
+            .map(|upgrade| upgrade.eq_ignore_ascii_case("websocket"))
+            .unwrap_or(false)
+}
+
+/// Parses the categories of events requested in the query of the given request; all of them are streamed if
+/// there's no `categories` parameter.
+fn requested_categories(req: &Request<Body>) -> Result<Vec<EventCategory>, String> {
+    let categories = req.uri().query().and_then(|query| {
+        query
+            .split('&')
+            .filter_map(|pair| pair.strip_prefix("categories="))
+            .next()
+    });
+
+    match categories {
+        Some(categories) => categories
+            .split(',')
+            .filter(|category| !category.is_empty())
+            .map(|category| EventCategory::from_str(category).map_err(|e| e.to_string()))
+            .collect(),
+        None => Ok(EventCategory::ALL.to_vec()),
+    }
+}
+
+/// Returns an error response with the given status and message.
+fn error_response(status: StatusCode, message: String) -> Response<Body> {
+    let mut response = Response::new(Body::from(message));
+    *response.status_mut() = status;
+    response
+}
+
+/// Authenticates the request and upgrades its connection to a WebSocket streaming the journaled events.
+pub fn handle_event_stream<S: Storage + Send + Sync + 'static>(
+    rpc: RpcImpl<S>,
+    mut req: Request<Body>,
+) -> Response<Body> {
+    let meta = Meta {
+        auth: req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .map(|h| h.to_str().unwrap_or("").to_owned()),
+        body: None,
+    };
+    if rpc.validate_auth(meta).is_err() {
+        return error_response(StatusCode::UNAUTHORIZED, "Authentication Error".to_string());
+    }
+
+    let categories = match requested_categories(&req) {
+        Ok(categories) => categories,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, e),
+    };
+
+    let accept_key = match req.headers().get(header::SEC_WEBSOCKET_KEY) {
+        Some(key) => derive_accept_key(key.as_bytes()),
+        None => return error_response(StatusCode::BAD_REQUEST, "Missing the WebSocket key".to_string()),
+    };
+
+    // Subscribe before the upgrade, so that no events are missed in between.
+    let mut events = rpc.node.journal.subscribe();
+
+    task::spawn(async move {
+        let upgraded = match hyper::upgrade::on(&mut req).await {
+            Ok(upgraded) => upgraded,
+            Err(e) => {
+                debug!("Couldn't upgrade a connection to the event stream: {}", e);
+                return;
+            }
+        };
+
+        let socket = WebSocketStream::from_raw_socket(upgraded, Role::Server, None).await;
+        let (mut outgoing, mut incoming) = socket.split();
+
+        loop {
+            // The incoming messages are only polled to detect the closure of the connection.
+            let next_event = Box::pin(events.recv());
+            match future::select(next_event, incoming.next()).await {
+                Either::Left((Ok(event), _)) => {
+                    if !categories.contains(&event.category) {
+                        continue;
+                    }
+                    if outgoing.send(Message::Text(serialize_event(&event))).await.is_err() {
+                        break;
+                    }
+                }
+                Either::Left((Err(RecvError::Lagged(missed)), _)) => {
+                    debug!("An event stream subscriber missed {} events", missed);
+                }
+                Either::Left((Err(RecvError::Closed), _)) => break,
+                Either::Right((Some(Ok(Message::Close(_))), _)) | Either::Right((Some(Err(_)), _)) => break,
+                Either::Right((None, _)) => break,
+                Either::Right((Some(Ok(_)), _)) => {}
+            }
+        }
+
+        let _ = outgoing.close().await;
+    });
+
+    Response::builder()
+        .status(StatusCode::SWITCHING_PROTOCOLS)
+        .header(header::UPGRADE, "websocket")
+        .header(header::CONNECTION, "Upgrade")
+        .header(header::SEC_WEBSOCKET_ACCEPT, accept_key)
+        .body(Body::empty())
+        .expect("invalid event stream response")
+}
+
+/// Serializes a journaled event into the text of a WebSocket message.
+fn serialize_event(event: &JournalEvent) -> String {
+    serde_json::to_string(event).expect("journal event serialization failed")
+}
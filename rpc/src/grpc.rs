@@ -0,0 +1,326 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+//! An optional gRPC server exposing the node, chain and peer queries of the JSON-RPC API, along with streams of
+//! the chain's events, for integrators who prefer strongly-typed clients.
+
+use crate::{
+    error::{RpcError, RpcErrorCode},
+    rpc_trait::RpcFunctions,
+    RpcImpl,
+};
+use snarkos_consensus::MerkleTreeLedger;
+use snarkos_network::Node;
+use snarkvm_dpc::Storage;
+
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{
+    sync::{broadcast, mpsc},
+    task,
+};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+/// The types generated from the protobuf definitions in `proto/snarkos.proto`.
+pub mod proto {
+    tonic::include_proto!("snarkos.v1");
+}
+
+use proto::node_api_server::{NodeApi, NodeApiServer};
+
+/// The interval at which the canon chain is checked for new blocks to stream to the subscribers.
+const BLOCK_POLL_INTERVAL_MILLIS: u64 = 1000;
+/// The number of events buffered for each subscriber; a subscriber that falls further behind is disconnected.
+const EVENT_STREAM_DEPTH: usize = 64;
+
+impl From<RpcError> for Status {
+    fn from(err: RpcError) -> Self {
+        let code = err.code();
+        let message = format!("{}: {}", code.name(), err);
+
+        match code {
+            RpcErrorCode::BlockNotFound
+            | RpcErrorCode::TxNotFound
+            | RpcErrorCode::PeerNotFound
+            | RpcErrorCode::SnapshotNotFound => Status::not_found(message),
+            RpcErrorCode::InvalidParams | RpcErrorCode::TxInvalid => Status::invalid_argument(message),
+            RpcErrorCode::BlockPruned | RpcErrorCode::NoConsensus | RpcErrorCode::NotCrawler => {
+                Status::failed_precondition(message)
+            }
+            RpcErrorCode::NodeSyncing => Status::unavailable(message),
+            RpcErrorCode::PermissionDenied => Status::permission_denied(message),
+            RpcErrorCode::TxConflict => Status::already_exists(message),
+            RpcErrorCode::InternalError | RpcErrorCode::NetworkError => Status::internal(message),
+        }
+    }
+}
+
+/// The gRPC service; it serves the requests with the JSON-RPC implementation.
+pub struct GrpcService<S: Storage + Send + Sync + 'static> {
+    rpc: RpcImpl<S>,
+}
+
+impl<S: Storage + Send + Sync + 'static> GrpcService<S> {
+    pub fn new(rpc: RpcImpl<S>) -> Self {
+        Self { rpc }
+    }
+}
+
+type EventStream<T> = ReceiverStream<Result<T, Status>>;
+
+#[tonic::async_trait]
+impl<S: Storage + Send + Sync + 'static> NodeApi for GrpcService<S> {
+    type SubscribeBlocksStream = EventStream<proto::BlockEvent>;
+    type SubscribeReorgsStream = EventStream<proto::ReorgEvent>;
+
+    async fn get_node_info(&self, _: Request<proto::Empty>) -> Result<Response<proto::NodeInfo>, Status> {
+        let info = self.rpc.get_node_info().await?;
+
+        Ok(Response::new(proto::NodeInfo {
+            listening_addr: info.listening_addr.to_string(),
+            is_bootnode: info.is_bootnode,
+            is_miner: info.is_miner,
+            is_syncing: info.is_syncing,
+            is_synced: info.is_synced,
+            network_height: info.network_height.unwrap_or(0),
+            is_shutting_down: info.is_shutting_down,
+            launched: info.launched.timestamp(),
+            version: info.version,
+        }))
+    }
+
+    async fn get_connection_count(&self, _: Request<proto::Empty>) -> Result<Response<proto::ConnectionCount>, Status> {
+        let count = self.rpc.get_connection_count().await?;
+
+        Ok(Response::new(proto::ConnectionCount { count: count as u64 }))
+    }
+
+    async fn get_peer_info(&self, _: Request<proto::Empty>) -> Result<Response<proto::PeerInfo>, Status> {
+        let info = self.rpc.get_peer_info().await?;
+
+        Ok(Response::new(proto::PeerInfo {
+            peers: info.peers.iter().map(ToString::to_string).collect(),
+            inbound_count: info.inbound_count,
+            outbound_count: info.outbound_count,
+        }))
+    }
+
+    async fn get_block_count(&self, _: Request<proto::Empty>) -> Result<Response<proto::BlockCount>, Status> {
+        let count = self.rpc.get_block_count().await?;
+
+        Ok(Response::new(proto::BlockCount { count }))
+    }
+
+    async fn get_best_block_hash(&self, _: Request<proto::Empty>) -> Result<Response<proto::BlockHash>, Status> {
+        let hash = self.rpc.get_best_block_hash().await?;
+
+        Ok(Response::new(proto::BlockHash { hash }))
+    }
+
+    async fn get_block_hash(&self, request: Request<proto::BlockHeight>) -> Result<Response<proto::BlockHash>, Status> {
+        let hash = self.rpc.get_block_hash(request.into_inner().height).await?;
+
+        Ok(Response::new(proto::BlockHash { hash }))
+    }
+
+    async fn get_block(&self, request: Request<proto::BlockHash>) -> Result<Response<proto::BlockInfo>, Status> {
+        let (block, _) = self.rpc.block_info(request.into_inner().hash)?;
+
+        Ok(Response::new(proto::BlockInfo {
+            hash: block.hash,
+            is_canon: block.height.is_some(),
+            height: block.height.unwrap_or(0),
+            confirmations: block.confirmations,
+            size: block.size as u64,
+            previous_block_hash: block.previous_block_hash,
+            merkle_root: block.merkle_root,
+            pedersen_merkle_root_hash: block.pedersen_merkle_root_hash,
+            proof: block.proof,
+            time: block.time,
+            difficulty_target: block.difficulty_target,
+            nonce: block.nonce,
+            transactions: block.transactions,
+        }))
+    }
+
+    async fn get_chain_tips(&self, _: Request<proto::Empty>) -> Result<Response<proto::ChainTips>, Status> {
+        let tips = self.rpc.get_chain_tips().await?;
+
+        Ok(Response::new(proto::ChainTips {
+            tips: tips
+                .into_iter()
+                .map(|tip| proto::ChainTip {
+                    hash: tip.hash,
+                    has_height: tip.height.is_some(),
+                    height: tip.height.unwrap_or(0),
+                    branch_length: tip.branch_length,
+                    status: tip.status,
+                })
+                .collect(),
+        }))
+    }
+
+    async fn get_chain_analytics(&self, _: Request<proto::Empty>) -> Result<Response<proto::ChainAnalytics>, Status> {
+        let analytics = self.rpc.get_chain_analytics().await?;
+
+        Ok(Response::new(proto::ChainAnalytics {
+            from_height: analytics.from_height,
+            to_height: analytics.to_height,
+            block_count: analytics.block_count as u64,
+            average_block_interval_secs: analytics.average_block_interval_secs.unwrap_or(0.0),
+            block_size: Some(proto::BlockSizeDistribution {
+                average: analytics.block_size.average,
+                min: analytics.block_size.min as u64,
+                median: analytics.block_size.median as u64,
+                p90: analytics.block_size.p90 as u64,
+                max: analytics.block_size.max as u64,
+            }),
+            average_fullness: analytics.average_fullness,
+            average_transactions: analytics.average_transactions,
+            max_transactions: analytics.max_transactions as u64,
+        }))
+    }
+
+    async fn get_raw_transaction(
+        &self,
+        request: Request<proto::TransactionId>,
+    ) -> Result<Response<proto::RawTransaction>, Status> {
+        let transaction = self.rpc.get_raw_transaction(request.into_inner().txid).await?;
+
+        Ok(Response::new(proto::RawTransaction { transaction }))
+    }
+
+    async fn get_transaction_status(
+        &self,
+        request: Request<proto::TransactionId>,
+    ) -> Result<Response<proto::TransactionStatus>, Status> {
+        let status = self.rpc.get_transaction_status(request.into_inner().txid).await?;
+        let mut response = proto::TransactionStatus {
+            txid: status.txid,
+            status: status.status,
+            ..Default::default()
+        };
+        if let Some(confirmation) = status.confirmation {
+            response.block_hash = confirmation.block_hash;
+            response.block_height = confirmation.block_height;
+            response.confirmations = confirmation.confirmations;
+        }
+
+        Ok(Response::new(response))
+    }
+
+    async fn subscribe_blocks(
+        &self,
+        _: Request<proto::Empty>,
+    ) -> Result<Response<Self::SubscribeBlocksStream>, Status> {
+        let storage = self.rpc.storage.clone();
+        storage.catch_up_secondary(false).map_err(RpcError::from)?;
+        let mut last_height = storage.get_current_block_height();
+
+        let (sender, receiver) = mpsc::channel(EVENT_STREAM_DEPTH);
+        task::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(BLOCK_POLL_INTERVAL_MILLIS));
+            loop {
+                interval.tick().await;
+                if sender.is_closed() {
+                    break;
+                }
+
+                if let Err(e) = storage.catch_up_secondary(false) {
+                    warn!("Couldn't update the storage for a block subscription: {}", e);
+                    continue;
+                }
+
+                // A reorganization can also lower the height; the new canon blocks are streamed once it's exceeded.
+                let height = storage.get_current_block_height();
+                while last_height < height {
+                    // The subscriber is told about an error, which ends the stream, as the same block would fail again.
+                    let event = next_block_event(&storage, last_height + 1);
+                    let is_err = event.is_err();
+                    if sender.send(event).await.is_err() || is_err {
+                        return;
+                    }
+                    last_height += 1;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+
+    async fn subscribe_reorgs(
+        &self,
+        _: Request<proto::Empty>,
+    ) -> Result<Response<Self::SubscribeReorgsStream>, Status> {
+        let mut reorgs = self.rpc.sync_handler()?.consensus.subscribe_reorgs();
+
+        let (sender, receiver) = mpsc::channel(EVENT_STREAM_DEPTH);
+        task::spawn(async move {
+            loop {
+                let event = match reorgs.recv().await {
+                    Ok(reorg) => Ok(proto::ReorgEvent {
+                        old_tip: reorg.old_tip.to_string(),
+                        old_height: reorg.old_height,
+                        new_tip: reorg.new_tip.to_string(),
+                        new_height: reorg.new_height,
+                        shared_block_height: reorg.shared_block_height,
+                        timestamp: reorg.timestamp.timestamp(),
+                    }),
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        Err(Status::data_loss(format!("{} reorganizations were missed", missed)))
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if sender.send(event).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+/// Returns the event of the canon block at the given height.
+fn next_block_event<S: Storage>(storage: &MerkleTreeLedger<S>, height: u32) -> Result<proto::BlockEvent, Status> {
+    let hash = storage.get_block_hash(height).map_err(RpcError::from)?;
+    let header = storage.get_block_header(&hash).map_err(RpcError::from)?;
+
+    Ok(proto::BlockEvent {
+        hash: hash.to_string(),
+        height,
+        time: header.time,
+    })
+}
+
+/// Starts a gRPC server on the given address; it serves the public queries of the JSON-RPC API.
+pub fn start_grpc_server<S: Storage + Send + Sync + 'static>(
+    grpc_addr: SocketAddr,
+    secondary_storage: Arc<MerkleTreeLedger<S>>,
+    node_server: Node<S>,
+) -> task::JoinHandle<()> {
+    let service = GrpcService::new(RpcImpl::new(secondary_storage, None, node_server));
+
+    task::spawn(async move {
+        if let Err(e) = tonic::transport::Server::builder()
+            .add_service(NodeApiServer::new(service))
+            .serve(grpc_addr)
+            .await
+        {
+            error!("The gRPC server failed: {}", e);
+        }
+    })
+}
@@ -27,12 +27,22 @@ extern crate derivative;
 #[macro_use]
 extern crate thiserror;
 
+pub mod admin;
+#[doc(inline)]
+pub use admin::*;
+
 pub mod custom_rpc_server;
 #[doc(inline)]
 pub use custom_rpc_server::*;
 
 pub mod error;
 
+#[cfg(feature = "websocket")]
+pub mod event_stream;
+
+#[cfg(feature = "grpc")]
+pub mod grpc;
+
 pub mod rpc_impl;
 #[doc(inline)]
 pub use rpc_impl::*;
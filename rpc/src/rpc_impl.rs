@@ -18,15 +18,31 @@
 //!
 //! See [RpcFunctions](../trait.RpcFunctions.html) for documentation of public endpoints.
 
-use crate::{error::RpcError, rpc_trait::RpcFunctions, rpc_types::*};
-use snarkos_consensus::{get_block_reward, memory_pool::Entry, ConsensusParameters, MemoryPool, MerkleTreeLedger};
-use snarkos_metrics::{snapshots::NodeStats, stats::NODE_STATS};
-use snarkos_network::{Node, Sync};
+use crate::{
+    admin::{AdminToken, SeenNonces},
+    error::RpcError,
+    rpc_trait::RpcFunctions,
+    rpc_types::*,
+};
+use snarkos_consensus::{
+    get_block_reward,
+    memory_pool::{Entry, FeeTransaction},
+    ConsensusParameters,
+    ForkChoiceOutcome,
+    MemoryPool,
+    MerkleTreeLedger,
+};
+use snarkos_metrics::snapshots::{NodeStats, NodeStatsEntry};
+#[cfg(feature = "crawler")]
+use snarkos_network::KnownNetwork;
+use snarkos_network::{Node, Sync, SyncStatus};
+use snarkos_storage::{MiningEvent, MiningEventKind};
 use snarkvm_dpc::{
     testnet1::{
         instantiated::{Components, Tx},
         parameters::PublicParameters,
     },
+    Block,
     BlockHeaderHash,
     Storage,
     TransactionScheme,
@@ -38,12 +54,31 @@ use snarkvm_utilities::{
 };
 
 use chrono::Utc;
+#[cfg(feature = "crawler")]
+use chrono::{DateTime, TimeZone};
+use futures::future;
+use jsonrpc_core::BoxFuture;
 
 use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
     ops::Deref,
-    sync::{atomic::Ordering, Arc},
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
+/// The number of memory pool transactions returned by `getmemorypool` if no limit is given.
+pub const DEFAULT_MEMORY_POOL_PAGE_SIZE: usize = 100;
+/// The maximum number of memory pool transactions returned by a single `getmemorypool` call.
+pub const MAX_MEMORY_POOL_PAGE_SIZE: usize = 1000;
+/// The maximum number of commitments that can be looked up in a single `gettransactionsbycommitment` call.
+pub const MAX_COMMITMENT_LOOKUPS: usize = 1000;
+/// The maximum number of seconds a long-polling `getblocktemplate` call is held open for.
+pub const LONG_POLL_TIMEOUT_SECS: u64 = 60;
+/// The minimum number of seconds a long-polling `getblocktemplate` call is held open for before
+/// a change to the memory pool alone (as opposed to a new chain tip) is reported.
+pub const LONG_POLL_MEMPOOL_DELAY_SECS: u64 = 10;
+
 /// Implements JSON-RPC HTTP endpoint functions for a node.
 /// The constructor is given Arc::clone() copies of all needed node components.
 #[derive(Derivative)]
@@ -65,6 +100,12 @@ pub struct RpcInner<S: Storage + Send + core::marker::Sync + 'static> {
     /// RPC credentials for accessing guarded endpoints
     pub(crate) credentials: Option<RpcCredentials>,
 
+    /// Tokens for accessing the admin endpoints; the credentials are used instead if there are none
+    pub(crate) admin_tokens: Vec<AdminToken>,
+
+    /// The nonces of the recent HMAC-signed admin requests, which can't be replayed
+    pub(crate) admin_nonces: SeenNonces,
+
     /// A clone of the network Node
     pub(crate) node: Node<S>,
 }
@@ -72,9 +113,21 @@ pub struct RpcInner<S: Storage + Send + core::marker::Sync + 'static> {
 impl<S: Storage + Send + core::marker::Sync + 'static> RpcImpl<S> {
     /// Creates a new struct for calling public and private RPC endpoints.
     pub fn new(storage: Arc<MerkleTreeLedger<S>>, credentials: Option<RpcCredentials>, node: Node<S>) -> Self {
+        Self::with_admin_tokens(storage, credentials, vec![], node)
+    }
+
+    /// Creates a new struct for calling RPC endpoints, whose admin endpoints are guarded by the given tokens.
+    pub fn with_admin_tokens(
+        storage: Arc<MerkleTreeLedger<S>>,
+        credentials: Option<RpcCredentials>,
+        admin_tokens: Vec<AdminToken>,
+        node: Node<S>,
+    ) -> Self {
         Self(Arc::new(RpcInner {
             storage,
             credentials,
+            admin_tokens,
+            admin_nonces: Default::default(),
             node,
         }))
     }
@@ -94,11 +147,30 @@ impl<S: Storage + Send + core::marker::Sync + 'static> RpcImpl<S> {
     pub fn memory_pool(&self) -> Result<&MemoryPool<Tx>, RpcError> {
         Ok(self.sync_handler()?.memory_pool())
     }
-}
 
-impl<S: Storage + Send + core::marker::Sync + 'static> RpcFunctions for RpcImpl<S> {
-    /// Returns information about a block from a block hash.
-    fn get_block(&self, block_hash_string: String) -> Result<BlockInfo, RpcError> {
+    /// Returns the position of the given transaction in the canon chain, if it's confirmed and its block wasn't pruned.
+    fn transaction_confirmation(&self, txid: &[u8]) -> Result<Option<TransactionConfirmation>, RpcError> {
+        let location = match self.storage.get_transaction_location(txid)? {
+            Some(location) => location,
+            None => return Ok(None),
+        };
+        let block_hash = BlockHeaderHash(location.block_hash);
+
+        // Transactions in side chain blocks aren't considered confirmed.
+        if !self.storage.is_canon(&block_hash) {
+            return Ok(None);
+        }
+        let block_height = self.storage.get_block_number(&block_hash)?;
+
+        Ok(Some(TransactionConfirmation {
+            block_hash: hex::encode(&block_hash.0),
+            block_height,
+            confirmations: self.storage.get_current_block_height().saturating_sub(block_height),
+        }))
+    }
+
+    /// Returns information about the block with the given hash, along with the block itself.
+    pub(crate) fn block_info(&self, block_hash_string: String) -> Result<(BlockInfo, Block<Tx>), RpcError> {
         let block_hash = hex::decode(&block_hash_string)?;
         if block_hash.len() != 32 {
             return Err(RpcError::InvalidBlockHash(block_hash_string));
@@ -122,78 +194,40 @@ impl<S: Storage + Send + core::marker::Sync + 'static> RpcFunctions for RpcImpl<
             None => 0,
         };
 
-        if let Ok(block) = storage.get_block(&block_header_hash) {
-            let mut transactions = Vec::with_capacity(block.transactions.len());
-
-            for transaction in block.transactions.iter() {
-                transactions.push(hex::encode(&transaction.transaction_id()?));
+        let block = match storage.get_block(&block_header_hash) {
+            Ok(block) => block,
+            Err(_) if storage.is_block_pruned(&block_header_hash) => {
+                return Err(RpcError::PrunedBlock(block_hash_string));
             }
+            Err(_) => return Err(RpcError::BlockNotFound(block_hash_string)),
+        };
 
-            Ok(BlockInfo {
-                hash: block_hash_string,
-                height,
-                confirmations,
-                size: block.serialize()?.len(),
-                previous_block_hash: block.header.previous_block_hash.to_string(),
-                merkle_root: block.header.merkle_root_hash.to_string(),
-                pedersen_merkle_root_hash: block.header.pedersen_merkle_root_hash.to_string(),
-                proof: block.header.proof.to_string(),
-                time: block.header.time,
-                difficulty_target: block.header.difficulty_target,
-                nonce: block.header.nonce,
-                transactions,
-            })
-        } else {
-            Err(RpcError::InvalidBlockHash(block_hash_string))
-        }
-    }
-
-    /// Returns the number of blocks in the canonical chain.
-    fn get_block_count(&self) -> Result<u32, RpcError> {
-        let storage = &self.storage;
-        storage.catch_up_secondary(false)?;
-        Ok(storage.get_block_count())
-    }
-
-    /// Returns the block hash of the head of the canonical chain.
-    fn get_best_block_hash(&self) -> Result<String, RpcError> {
-        let storage = &self.storage;
-        storage.catch_up_secondary(false)?;
-        let best_block_hash = storage.get_block_hash(storage.get_current_block_height())?;
-
-        Ok(hex::encode(&best_block_hash.0))
-    }
-
-    /// Returns the block hash of the index specified if it exists in the canonical chain.
-    fn get_block_hash(&self, block_height: u32) -> Result<String, RpcError> {
-        let storage = &self.storage;
-        storage.catch_up_secondary(false)?;
-        let block_hash = storage.get_block_hash(block_height)?;
+        let mut transactions = Vec::with_capacity(block.transactions.len());
 
-        Ok(hex::encode(&block_hash.0))
-    }
+        for transaction in block.transactions.iter() {
+            transactions.push(hex::encode(&transaction.transaction_id()?));
+        }
 
-    /// Returns the hex encoded bytes of a transaction from its transaction id.
-    fn get_raw_transaction(&self, transaction_id: String) -> Result<String, RpcError> {
-        let storage = &self.storage;
-        storage.catch_up_secondary(false)?;
-        Ok(hex::encode(
-            &storage.get_transaction_bytes(&hex::decode(transaction_id)?)?,
-        ))
-    }
+        let info = BlockInfo {
+            hash: block_hash_string,
+            height,
+            confirmations,
+            size: block.serialize()?.len(),
+            previous_block_hash: block.header.previous_block_hash.to_string(),
+            merkle_root: block.header.merkle_root_hash.to_string(),
+            pedersen_merkle_root_hash: block.header.pedersen_merkle_root_hash.to_string(),
+            proof: block.header.proof.to_string(),
+            time: block.header.time,
+            difficulty_target: block.header.difficulty_target,
+            nonce: block.header.nonce,
+            transactions,
+        };
 
-    /// Returns information about a transaction from a transaction id.
-    fn get_transaction_info(&self, transaction_id: String) -> Result<TransactionInfo, RpcError> {
-        let transaction_bytes = self.get_raw_transaction(transaction_id)?;
-        self.decode_raw_transaction(transaction_bytes)
+        Ok((info, block))
     }
 
-    /// Returns information about a transaction from serialized transaction bytes.
-    fn decode_raw_transaction(&self, transaction_bytes: String) -> Result<TransactionInfo, RpcError> {
-        self.storage.catch_up_secondary(false)?;
-        let transaction_bytes = hex::decode(transaction_bytes)?;
-        let transaction = Tx::read(&transaction_bytes[..])?;
-
+    /// Returns information about the given transaction, whose serialized form is of the given size.
+    fn transaction_info(&self, transaction: &Tx, size: usize) -> Result<TransactionInfo, RpcError> {
         let mut old_serial_numbers = Vec::with_capacity(transaction.old_serial_numbers().len());
 
         for sn in transaction.old_serial_numbers() {
@@ -234,7 +268,7 @@ impl<S: Storage + Send + core::marker::Sync + 'static> RpcFunctions for RpcImpl<
 
         Ok(TransactionInfo {
             txid: hex::encode(&transaction_id),
-            size: transaction_bytes.len(),
+            size,
             old_serial_numbers,
             new_commitments,
             memo,
@@ -250,131 +284,685 @@ impl<S: Storage + Send + core::marker::Sync + 'static> RpcFunctions for RpcImpl<
         })
     }
 
-    /// Send raw transaction bytes to this node to be added into the mempool.
-    /// If valid, the transaction will be stored and propagated to all peers.
-    /// Returns the transaction id if valid.
-    fn send_raw_transaction(&self, transaction_bytes: String) -> Result<String, RpcError> {
-        let transaction_bytes = hex::decode(transaction_bytes)?;
-        let transaction = Tx::read(&transaction_bytes[..])?;
-        let transaction_hex_id = hex::encode(transaction.transaction_id()?);
+    /// Returns the hex-encoded hash of the current chain tip and a fingerprint of the set of
+    /// transactions in the memory pool, which together identify a block template.
+    fn template_state(&self) -> Result<(String, u64), RpcError> {
+        let tip_hash = self.storage.get_block_hash(self.storage.get_current_block_height())?;
 
-        let storage = &self.storage;
+        let mut txids = self
+            .memory_pool()?
+            .transactions
+            .inner()
+            .iter()
+            .map(|(txid, _)| txid.clone())
+            .collect::<Vec<_>>();
+        txids.sort_unstable();
+        let mut hasher = DefaultHasher::new();
+        txids.hash(&mut hasher);
+
+        Ok((hex::encode(&tip_hash.0), hasher.finish()))
+    }
 
-        storage.catch_up_secondary(false)?;
+    /// Builds the graph of the given crawled network, as of the given time.
+    #[cfg(feature = "crawler")]
+    fn network_graph(&self, known_network: &KnownNetwork, time: DateTime<Utc>) -> NetworkGraph {
+        let bootnodes = self.node.config.bootnodes();
+
+        let vertices = known_network
+            .nodes()
+            .into_iter()
+            .map(|addr| {
+                let reliability = known_network.reliability(addr).unwrap_or_default();
+                let annotation = self.node.peer_book.peer_annotation(addr).unwrap_or_default();
+
+                Vertex {
+                    addr,
+                    is_bootnode: bootnodes.contains(&addr),
+                    unverified_alias: known_network.alias(addr),
+                    uptime_secs: reliability.uptime_at(time).as_secs(),
+                    responsiveness: reliability.responsiveness(),
+                    last_crawl_error: reliability.last_error().map(|error| error.to_string()),
+                    labels: annotation.labels,
+                    note: annotation.note,
+                }
+            })
+            .collect();
+
+        let edges = known_network
+            .connections()
+            .into_iter()
+            .map(|connection| Edge {
+                source: connection.source,
+                target: connection.target,
+            })
+            .collect();
+
+        NetworkGraph { vertices, edges }
+    }
+
+    /// Waits until the block template identified by the given long-poll id is outdated; that is
+    /// until the chain tip changes, the memory pool changes after `LONG_POLL_MEMPOOL_DELAY_SECS`,
+    /// or `LONG_POLL_TIMEOUT_SECS` pass. The state is only checked again when the chain tip or
+    /// the memory pool change, or once one of these delays passes.
+    async fn wait_for_new_template(&self, longpoll_id: &str) -> Result<(), RpcError> {
+        // An id that doesn't match the format refers to an unknown template, which is outdated by definition.
+        let mut id_parts = longpoll_id.splitn(2, ':');
+        let (known_tip_hash, known_fingerprint) = match (id_parts.next(), id_parts.next()) {
+            (Some(tip_hash), Some(fingerprint)) => (tip_hash, fingerprint),
+            _ => return Ok(()),
+        };
+
+        let sync = self.sync_handler()?;
+        let started = Instant::now();
+        let mempool_deadline = started + Duration::from_secs(LONG_POLL_MEMPOOL_DELAY_SECS);
+        let deadline = started + Duration::from_secs(LONG_POLL_TIMEOUT_SECS);
+
+        loop {
+            // The notifications are registered before the state is checked, so that no change in between is missed.
+            let tip_changed = sync.consensus.tip_changes.notified();
+            let mempool_changed = sync.memory_pool().changed();
+
+            self.storage.catch_up_secondary(false)?;
+            let (tip_hash, mempool_fingerprint) = self.template_state()?;
 
-        if !self.sync_handler()?.consensus.verify_transaction(&transaction)? {
-            // TODO (raychu86) Add more descriptive message. (e.g. tx already exists)
-            return Ok("Transaction did not verify".into());
+            if known_tip_hash != tip_hash {
+                return Ok(());
+            }
+            let now = Instant::now();
+            let is_mempool_outdated = known_fingerprint != format!("{:016x}", mempool_fingerprint);
+            if (is_mempool_outdated && now >= mempool_deadline) || now >= deadline {
+                return Ok(());
+            }
+
+            // Once the memory pool changed, only the chain tip and the delay are worth waiting for.
+            let changed = async {
+                if is_mempool_outdated {
+                    tip_changed.await;
+                } else {
+                    future::select(Box::pin(tip_changed), Box::pin(mempool_changed)).await;
+                }
+            };
+            let wake_at = if is_mempool_outdated { mempool_deadline } else { deadline };
+            tokio::time::timeout_at(wake_at.into(), changed).await.ok();
         }
+    }
+}
 
-        match !storage.transaction_conflicts(&transaction) {
-            true => {
-                let entry = Entry::<Tx> {
-                    size_in_bytes: transaction_bytes.len(),
-                    transaction,
-                };
+/// Deserializes a transaction given to an endpoint.
+fn read_transaction(transaction_bytes: &[u8]) -> Result<Tx, RpcError> {
+    Tx::read(transaction_bytes).map_err(|e| RpcError::InvalidTransaction(e.to_string()))
+}
+
+impl<S: Storage + Send + core::marker::Sync + 'static> RpcFunctions for RpcImpl<S> {
+    /// Returns a block from a block hash, either raw or along with information about it and, optionally, its
+    /// decoded transactions.
+    fn get_block(&self, block_hash_string: String, verbosity: Option<u8>) -> BoxFuture<Result<BlockData, RpcError>> {
+        let rpc = self.clone();
+        Box::pin(async move {
+            let verbosity = verbosity.unwrap_or(1);
+            if verbosity > 2 {
+                return Err(RpcError::InvalidVerbosity(verbosity));
+            }
+
+            let (info, block) = rpc.block_info(block_hash_string)?;
 
-                // this block_on will halt the tokio worker until insert completion -- can cause problems if not in a multi-threaded environment (tests)
-                if let Ok(inserted) = futures::executor::block_on(self.memory_pool()?.insert(storage, entry)) {
-                    if inserted.is_some() {
-                        info!("Transaction added to the memory pool.");
-                        // TODO(ljedrz): checks if needs to be propagated to the network; if need be, this could
-                        // be made automatic at the time when a tx from any source is added the memory pool
+            match verbosity {
+                0 => Ok(BlockData::Raw(hex::encode(block.serialize()?))),
+                1 => Ok(BlockData::Info(info)),
+                _ => {
+                    let mut transaction_info = Vec::with_capacity(block.transactions.len());
+                    for transaction in block.transactions.iter() {
+                        transaction_info.push(rpc.transaction_info(transaction, transaction.size())?);
                     }
+
+                    Ok(BlockData::Verbose(VerboseBlockInfo { info, transaction_info }))
                 }
+            }
+        })
+    }
 
-                Ok(transaction_hex_id)
+    /// Returns the tips of the known chains, with the lengths of their branches and their statuses.
+    fn get_chain_tips(&self) -> BoxFuture<Result<Vec<ChainTipInfo>, RpcError>> {
+        let rpc = self.clone();
+        Box::pin(async move {
+            let storage = &rpc.storage;
+
+            storage.catch_up_secondary(false)?;
+
+            let tips = storage
+                .get_chain_tips()?
+                .into_iter()
+                .map(|tip| ChainTipInfo {
+                    hash: tip.hash.to_string(),
+                    height: tip.height,
+                    branch_length: tip.branch_length,
+                    status: tip.status.to_string(),
+                })
+                .collect();
+
+            Ok(tips)
+        })
+    }
+
+    /// Returns rolling statistics about the latest blocks of the canonical chain.
+    fn get_chain_analytics(&self) -> BoxFuture<Result<ChainAnalytics, RpcError>> {
+        let rpc = self.clone();
+        Box::pin(async move {
+            let stats = rpc
+                .sync_handler()?
+                .consensus
+                .chain_statistics()?
+                .ok_or_else(|| RpcError::Message("the canon chain is empty".into()))?;
+
+            Ok(ChainAnalytics {
+                from_height: stats.from_height,
+                to_height: stats.to_height,
+                block_count: stats.block_count,
+                average_block_interval_secs: stats.average_block_interval_secs,
+                block_size: BlockSizeDistribution {
+                    average: stats.average_block_size,
+                    min: stats.min_block_size,
+                    median: stats.median_block_size,
+                    p90: stats.p90_block_size,
+                    max: stats.max_block_size,
+                },
+                average_fullness: stats.average_fullness,
+                average_transactions: stats.average_transactions,
+                max_transactions: stats.max_transactions,
+            })
+        })
+    }
+
+    /// Returns the reason why a known block is or isn't part of the canonical chain.
+    fn get_fork_choice(&self, block_hash_string: String) -> BoxFuture<Result<ForkChoiceInfo, RpcError>> {
+        let rpc = self.clone();
+        Box::pin(async move {
+            let block_hash = hex::decode(&block_hash_string)?;
+            if block_hash.len() != 32 {
+                return Err(RpcError::InvalidBlockHash(block_hash_string));
             }
-            false => Ok("Transaction contains spent records".into()),
-        }
+
+            let storage = &rpc.storage;
+
+            storage.catch_up_secondary(false)?;
+
+            let block_header_hash = BlockHeaderHash::new(block_hash);
+            if !storage.block_hash_exists(&block_header_hash) {
+                return Err(RpcError::BlockNotFound(block_hash_string));
+            }
+
+            let is_canon = storage.is_canon(&block_header_hash);
+            let fork_choice = rpc.sync_handler()?.consensus.get_fork_choice(&block_header_hash);
+
+            let reason = match (is_canon, fork_choice.as_ref().map(|fork_choice| fork_choice.outcome)) {
+                (true, None) => "The block is part of the canon chain",
+                (true, Some(ForkChoiceOutcome::Reorganized)) => {
+                    "The block's side chain was longer than the canon chain, so it became the canon chain"
+                }
+                (true, Some(ForkChoiceOutcome::Stored)) => {
+                    "The block's side chain wasn't longer than the canon chain at first, but it became the canon chain later"
+                }
+                (false, Some(ForkChoiceOutcome::Stored)) => {
+                    "The block's side chain wasn't longer than the canon chain, which was received earlier"
+                }
+                (false, Some(ForkChoiceOutcome::Reorganized)) => {
+                    "The block's side chain became the canon chain, but it was later replaced by a longer chain"
+                }
+                (false, None) => {
+                    "The block isn't part of the canon chain and no fork choice is known for it; it may be an orphan"
+                }
+            };
+
+            let decision = fork_choice.map(|fork_choice| ForkChoiceDecision {
+                received_order: fork_choice.received_order,
+                shared_block_height: fork_choice.shared_block_height,
+                side_chain_height: fork_choice.side_chain_height,
+                canon_height: fork_choice.canon_height,
+                side_chain_work: fork_choice.side_chain_work.to_string(),
+                canon_chain_work: fork_choice.canon_chain_work.to_string(),
+                outcome: fork_choice.outcome.to_string(),
+            });
+
+            Ok(ForkChoiceInfo {
+                hash: block_hash_string,
+                is_canon,
+                reason: reason.into(),
+                decision,
+            })
+        })
+    }
+
+    /// Returns the number of blocks in the canonical chain.
+    fn get_block_count(&self) -> BoxFuture<Result<u32, RpcError>> {
+        let rpc = self.clone();
+        Box::pin(async move {
+            let storage = &rpc.storage;
+            storage.catch_up_secondary(false)?;
+            Ok(storage.get_block_count())
+        })
+    }
+
+    /// Returns the block hash of the head of the canonical chain.
+    fn get_best_block_hash(&self) -> BoxFuture<Result<String, RpcError>> {
+        let rpc = self.clone();
+        Box::pin(async move {
+            let storage = &rpc.storage;
+            storage.catch_up_secondary(false)?;
+            let best_block_hash = storage.get_block_hash(storage.get_current_block_height())?;
+
+            Ok(hex::encode(&best_block_hash.0))
+        })
+    }
+
+    /// Returns the block hash of the index specified if it exists in the canonical chain.
+    fn get_block_hash(&self, block_height: u32) -> BoxFuture<Result<String, RpcError>> {
+        let rpc = self.clone();
+        Box::pin(async move {
+            let storage = &rpc.storage;
+            storage.catch_up_secondary(false)?;
+            let block_hash = storage.get_block_hash(block_height)?;
+
+            Ok(hex::encode(&block_hash.0))
+        })
+    }
+
+    /// Returns the hex encoded bytes of a transaction from its transaction id.
+    fn get_raw_transaction(&self, transaction_id: String) -> BoxFuture<Result<String, RpcError>> {
+        let rpc = self.clone();
+        Box::pin(async move {
+            let storage = &rpc.storage;
+            storage.catch_up_secondary(false)?;
+            Ok(hex::encode(
+                &storage.get_transaction_bytes(&hex::decode(transaction_id)?)?,
+            ))
+        })
+    }
+
+    /// Returns information about a transaction from a transaction id.
+    fn get_transaction_info(&self, transaction_id: String) -> BoxFuture<Result<TransactionInfo, RpcError>> {
+        let rpc = self.clone();
+        Box::pin(async move {
+            let transaction_bytes = rpc.get_raw_transaction(transaction_id).await?;
+            rpc.decode_raw_transaction(transaction_bytes).await
+        })
+    }
+
+    /// Returns the canon transactions that created the records with the given commitments.
+    fn get_transactions_by_commitment(
+        &self,
+        commitments: Vec<String>,
+    ) -> BoxFuture<Result<Vec<RecordTransaction>, RpcError>> {
+        let rpc = self.clone();
+        Box::pin(async move {
+            if commitments.len() > MAX_COMMITMENT_LOOKUPS {
+                return Err(RpcError::TooManyItems(MAX_COMMITMENT_LOOKUPS, commitments.len()));
+            }
+
+            rpc.storage.catch_up_secondary(false)?;
+
+            let mut record_transactions = Vec::with_capacity(commitments.len());
+            for commitment in commitments {
+                let cm_bytes = hex::decode(&commitment)?;
+
+                let (txid, confirmation) = match rpc.storage.get_transaction_id_by_commitment(&cm_bytes)? {
+                    Some(txid) => (Some(hex::encode(&txid)), rpc.transaction_confirmation(&txid)?),
+                    None => (None, None),
+                };
+
+                record_transactions.push(RecordTransaction {
+                    commitment,
+                    txid,
+                    confirmation,
+                });
+            }
+
+            Ok(record_transactions)
+        })
+    }
+
+    /// Returns whether the record with the given serial number was spent in the canon chain, and by which transaction.
+    fn is_serial_number_spent(&self, serial_number: String) -> BoxFuture<Result<SerialNumberStatus, RpcError>> {
+        let rpc = self.clone();
+        Box::pin(async move {
+            let sn_bytes = hex::decode(&serial_number)?;
+
+            rpc.storage.catch_up_secondary(false)?;
+
+            let (txid, confirmation) = match rpc.storage.get_transaction_id_by_serial_number(&sn_bytes)? {
+                Some(txid) => (Some(hex::encode(&txid)), rpc.transaction_confirmation(&txid)?),
+                None => (None, None),
+            };
+
+            Ok(SerialNumberStatus {
+                serial_number,
+                spent: txid.is_some(),
+                txid,
+                confirmation,
+            })
+        })
+    }
+
+    /// Returns whether a transaction is unknown, waiting in the memory pool, or confirmed in the canon chain.
+    fn get_transaction_status(&self, transaction_id: String) -> BoxFuture<Result<TransactionStatus, RpcError>> {
+        let rpc = self.clone();
+        Box::pin(async move {
+            let txid = hex::decode(&transaction_id)?;
+
+            rpc.storage.catch_up_secondary(false)?;
+
+            if let Some(confirmation) = rpc.transaction_confirmation(&txid)? {
+                return Ok(TransactionStatus {
+                    txid: transaction_id,
+                    status: "confirmed".into(),
+                    mempool_entry: None,
+                    confirmation: Some(confirmation),
+                });
+            }
+
+            // Nodes that don't participate in consensus don't have a memory pool.
+            let mempool_entry = match rpc.memory_pool() {
+                Ok(memory_pool) => memory_pool.transactions.get(&txid).map(|entry| MempoolEntryInfo {
+                    received: entry.received,
+                    size: entry.size_in_bytes,
+                }),
+                Err(_) => None,
+            };
+
+            Ok(TransactionStatus {
+                txid: transaction_id,
+                status: if mempool_entry.is_some() { "mempool" } else { "unknown" }.into(),
+                mempool_entry,
+                confirmation: None,
+            })
+        })
+    }
+
+    /// Returns information about a transaction from serialized transaction bytes.
+    fn decode_raw_transaction(&self, transaction_bytes: String) -> BoxFuture<Result<TransactionInfo, RpcError>> {
+        let rpc = self.clone();
+        Box::pin(async move {
+            rpc.storage.catch_up_secondary(false)?;
+            let transaction_bytes = hex::decode(transaction_bytes)?;
+            let transaction = read_transaction(&transaction_bytes)?;
+
+            rpc.transaction_info(&transaction, transaction_bytes.len())
+        })
+    }
+
+    /// Send raw transaction bytes to this node to be added into the mempool.
+    /// If valid, the transaction will be stored and propagated to all peers.
+    /// Returns the transaction id if valid.
+    fn send_raw_transaction(&self, transaction_bytes: String) -> BoxFuture<Result<String, RpcError>> {
+        let rpc = self.clone();
+        Box::pin(async move {
+            let transaction_bytes = hex::decode(transaction_bytes)?;
+            let transaction = read_transaction(&transaction_bytes)?;
+            let transaction_hex_id = hex::encode(transaction.transaction_id()?);
+
+            let storage = &rpc.storage;
+
+            storage.catch_up_secondary(false)?;
+
+            if storage.transaction_conflicts(&transaction) {
+                return Err(RpcError::TransactionConflict(transaction_hex_id));
+            }
+
+            if !rpc.sync_handler()?.consensus.verify_transaction(&transaction)? {
+                return Err(RpcError::InvalidTransaction(format!(
+                    "transaction {} doesn't verify",
+                    transaction_hex_id
+                )));
+            }
+
+            let entry = Entry::<Tx>::new(transaction, transaction_bytes.len());
+
+            if let Ok(inserted) = rpc.memory_pool()?.insert(storage, entry).await {
+                if inserted.is_some() {
+                    info!("Transaction added to the memory pool.");
+                    // TODO(ljedrz): checks if needs to be propagated to the network; if need be, this could
+                    // be made automatic at the time when a tx from any source is added the memory pool
+                }
+            }
+
+            Ok(transaction_hex_id)
+        })
     }
 
     /// Validate and return if the transaction is valid.
-    fn validate_raw_transaction(&self, transaction_bytes: String) -> Result<bool, RpcError> {
-        let transaction_bytes = hex::decode(transaction_bytes)?;
-        let transaction = Tx::read(&transaction_bytes[..])?;
+    fn validate_raw_transaction(&self, transaction_bytes: String) -> BoxFuture<Result<bool, RpcError>> {
+        let rpc = self.clone();
+        Box::pin(async move {
+            let transaction_bytes = hex::decode(transaction_bytes)?;
+            let transaction = read_transaction(&transaction_bytes)?;
 
-        let storage = &self.storage;
+            let storage = &rpc.storage;
 
-        storage.catch_up_secondary(false)?;
+            storage.catch_up_secondary(false)?;
 
-        Ok(self.sync_handler()?.consensus.verify_transaction(&transaction)?)
+            Ok(rpc.sync_handler()?.consensus.verify_transaction(&transaction)?)
+        })
     }
 
     /// Fetch the number of connected peers this node has.
-    fn get_connection_count(&self) -> Result<usize, RpcError> {
-        // Create a temporary tokio runtime to make an asynchronous function call
-        let number = self.node.peer_book.get_active_peer_count();
+    fn get_connection_count(&self) -> BoxFuture<Result<usize, RpcError>> {
+        let rpc = self.clone();
+        Box::pin(async move {
+            // Create a temporary tokio runtime to make an asynchronous function call
+            let number = rpc.node.peer_book.get_active_peer_count();
 
-        Ok(number as usize)
+            Ok(number as usize)
+        })
     }
 
     /// Returns this nodes connected peers.
-    fn get_peer_info(&self) -> Result<PeerInfo, RpcError> {
-        // Create a temporary tokio runtime to make an asynchronous function call
-        let peers = self.node.peer_book.connected_peers();
-
-        Ok(PeerInfo { peers })
+    fn get_peer_info(&self) -> BoxFuture<Result<PeerInfo, RpcError>> {
+        let rpc = self.clone();
+        Box::pin(async move {
+            // Create a temporary tokio runtime to make an asynchronous function call
+            let peers = rpc.node.peer_book.connected_peers();
+
+            Ok(PeerInfo {
+                peers,
+                inbound_count: rpc.node.peer_book.get_inbound_peer_count(),
+                outbound_count: rpc.node.peer_book.get_outbound_peer_count(),
+            })
+        })
     }
 
     /// Returns data about the node.
-    fn get_node_info(&self) -> Result<NodeInfo, RpcError> {
-        Ok(NodeInfo {
-            listening_addr: self.node.config.desired_address,
-            is_bootnode: self.node.config.is_bootnode(),
-            is_miner: self.sync_handler()?.is_miner(),
-            is_syncing: self.node.is_syncing_blocks(),
-            launched: self.node.launched,
-            version: env!("CARGO_PKG_VERSION").into(),
+    fn get_node_info(&self) -> BoxFuture<Result<NodeInfo, RpcError>> {
+        let rpc = self.clone();
+        Box::pin(async move {
+            Ok(NodeInfo {
+                listening_addr: rpc.node.config.desired_address,
+                is_bootnode: rpc.node.config.is_bootnode(),
+                is_miner: rpc.node.mining().map(|mining| mining.is_mining()).unwrap_or(false),
+                is_syncing: rpc.node.is_syncing_blocks(),
+                is_synced: rpc.node.is_synced().await,
+                network_height: rpc.node.peer_book.network_height().await,
+                is_shutting_down: rpc.node.is_shutting_down(),
+                time_offset: rpc.node.clock.offset(),
+                is_clock_skewed: rpc.node.clock.is_skewed(),
+                launched: rpc.node.launched,
+                version: env!("CARGO_PKG_VERSION").into(),
+            })
         })
     }
 
-    /// Returns statistics related to the node.
-    fn get_node_stats(&self) -> Result<NodeStats, RpcError> {
-        let mut metrics = NODE_STATS.snapshot();
+    /// Returns the state of the node's block sync.
+    fn get_sync_status(&self) -> BoxFuture<Result<SyncStatus, RpcError>> {
+        let rpc = self.clone();
+        Box::pin(async move { Ok(rpc.sync_handler()?.sync_status()) })
+    }
 
-        // Note: Temporarily overriding node metrics here, as they aren't all correctly updated
-        // @sadroeck - remove me
-        metrics.misc.block_height = self.storage.current_block_height.load(Ordering::Relaxed) as u64;
-        metrics.connections.connected_peers = self.node.peer_book.get_active_peer_count();
-        metrics.connections.disconnected_peers = self.node.peer_book.get_disconnected_peer_count();
-        metrics.misc.block_height = self
-            .node
-            .sync()
-            .map(|sync| sync.current_block_height() as u64)
-            .unwrap_or(0);
+    /// Returns the network graph discovered by the crawler.
+    #[cfg(feature = "crawler")]
+    fn get_network_graph(&self) -> BoxFuture<Result<NetworkGraph, RpcError>> {
+        let rpc = self.clone();
+        Box::pin(async move {
+            let known_network = rpc.node.known_network().ok_or(RpcError::NotCrawler)?;
 
-        Ok(metrics)
+            Ok(rpc.network_graph(known_network, Utc::now()))
+        })
     }
 
-    /// Returns the current mempool and sync information known by this node.
-    fn get_block_template(&self) -> Result<BlockTemplate, RpcError> {
-        let storage = &self.storage;
-        storage.catch_up_secondary(false)?;
+    /// Returns the latest snapshot of the network graph the crawler took at or before the given UNIX timestamp.
+    #[cfg(feature = "crawler")]
+    fn get_network_graph_at(&self, timestamp: i64) -> BoxFuture<Result<NetworkGraphSnapshot, RpcError>> {
+        let rpc = self.clone();
+        Box::pin(async move {
+            rpc.node.known_network().ok_or(RpcError::NotCrawler)?;
+
+            rpc.storage.catch_up_secondary(false)?;
+            let (taken_at, snapshot) = rpc
+                .storage
+                .get_network_snapshot_at(timestamp)?
+                .ok_or(RpcError::SnapshotNotFound(timestamp))?;
+            let known_network = KnownNetwork::deserialize(&snapshot)?;
+            let taken_at = Utc.timestamp(taken_at, 0);
+
+            Ok(NetworkGraphSnapshot {
+                taken_at,
+                graph: rpc.network_graph(&known_network, taken_at),
+            })
+        })
+    }
 
-        let block_height = storage.get_current_block_height();
-        let block = storage.get_block_from_block_number(block_height)?;
+    /// Nodes built without the `crawler` feature can't map the network.
+    #[cfg(not(feature = "crawler"))]
+    fn get_network_graph(&self) -> BoxFuture<Result<NetworkGraph, RpcError>> {
+        Box::pin(async { Err(RpcError::NotCrawler) })
+    }
 
-        let time = Utc::now().timestamp();
+    /// Nodes built without the `crawler` feature can't map the network.
+    #[cfg(not(feature = "crawler"))]
+    fn get_network_graph_at(&self, _timestamp: i64) -> BoxFuture<Result<NetworkGraphSnapshot, RpcError>> {
+        Box::pin(async { Err(RpcError::NotCrawler) })
+    }
 
-        let full_transactions = self
-            .memory_pool()?
-            .get_candidates(storage, self.consensus_parameters()?.max_block_size)?;
+    /// Returns statistics related to the node.
+    fn get_node_stats(&self) -> BoxFuture<Result<NodeStats, RpcError>> {
+        let rpc = self.clone();
+        Box::pin(async move { Ok(rpc.node.stats_snapshot()) })
+    }
 
-        let transaction_strings = full_transactions.serialize_as_str()?;
+    /// Returns the node's stats from the given number of seconds ago until now, with the given
+    /// minimum number of seconds between the snapshots.
+    fn get_node_stats_history(
+        &self,
+        range_secs: u64,
+        step_secs: u64,
+    ) -> BoxFuture<Result<Vec<NodeStatsEntry>, RpcError>> {
+        let rpc = self.clone();
+        Box::pin(async move { Ok(rpc.node.stats_history.query(range_secs, step_secs)) })
+    }
 
-        let mut coinbase_value = get_block_reward(block_height + 1);
-        for transaction in full_transactions.iter() {
-            coinbase_value = coinbase_value.add(transaction.value_balance())
-        }
+    /// Returns the current mempool and sync information known by this node. If a long-poll id is
+    /// given, the response is withheld until the template it identifies is outdated.
+    fn get_block_template(&self, longpoll_id: Option<String>) -> BoxFuture<Result<BlockTemplate, RpcError>> {
+        let rpc = self.clone();
+        Box::pin(async move {
+            if let Some(longpoll_id) = longpoll_id {
+                rpc.wait_for_new_template(&longpoll_id).await?;
+            }
 
-        Ok(BlockTemplate {
-            previous_block_hash: hex::encode(&block.header.get_hash().0),
-            block_height: block_height + 1,
-            time,
-            difficulty_target: self.consensus_parameters()?.get_block_difficulty(&block.header, time),
-            transactions: transaction_strings,
-            coinbase_value: coinbase_value.0 as u64,
+            // A template built on an outdated chain tip would only produce a stale block.
+            if rpc.node.is_syncing_blocks() {
+                return Err(RpcError::NodeSyncing);
+            }
+
+            let storage = &rpc.storage;
+            storage.catch_up_secondary(false)?;
+
+            let block_height = storage.get_current_block_height();
+            let block = storage.get_block_from_block_number(block_height)?;
+
+            // Hosts with a skewed clock would otherwise produce blocks rejected by the network.
+            let time = rpc.node.adjusted_time();
+
+            let full_transactions = rpc
+                .memory_pool()?
+                .get_candidates(storage, rpc.consensus_parameters()?.max_block_size)?;
+
+            // Record the template, so that miners using it can later reconcile their rewards.
+            let template_event = MiningEvent {
+                kind: MiningEventKind::Template,
+                block_height: block_height + 1,
+                block_hash: None,
+                previous_block_hash: block.header.get_hash().0,
+                timestamp: time,
+            };
+            if let Err(e) = rpc.sync_handler()?.storage().store_mining_event(&template_event) {
+                warn!("Couldn't record a mining event: {}", e);
+            }
+
+            let transaction_strings = full_transactions.serialize_as_str()?;
+            let (tip_hash, mempool_fingerprint) = rpc.template_state()?;
+
+            let mut coinbase_value = get_block_reward(block_height + 1);
+            for transaction in full_transactions.iter() {
+                coinbase_value = coinbase_value.add(transaction.value_balance())
+            }
+
+            Ok(BlockTemplate {
+                previous_block_hash: hex::encode(&block.header.get_hash().0),
+                block_height: block_height + 1,
+                time,
+                difficulty_target: rpc.consensus_parameters()?.get_block_difficulty(&block.header, time),
+                transactions: transaction_strings,
+                coinbase_value: coinbase_value.0 as u64,
+                longpoll_id: format!("{}:{:016x}", tip_hash, mempool_fingerprint),
+            })
+        })
+    }
+
+    /// Returns the summaries of the transactions in the memory pool offering at least the given fee,
+    /// from the oldest to the newest, skipping the given number of them.
+    fn get_memory_pool(
+        &self,
+        offset: Option<usize>,
+        limit: Option<usize>,
+        min_fee: Option<i64>,
+    ) -> BoxFuture<Result<MemoryPoolPage, RpcError>> {
+        let rpc = self.clone();
+        Box::pin(async move {
+            let offset = offset.unwrap_or(0);
+            let limit = limit
+                .unwrap_or(DEFAULT_MEMORY_POOL_PAGE_SIZE)
+                .min(MAX_MEMORY_POOL_PAGE_SIZE);
+            let min_fee = min_fee.unwrap_or(i64::MIN);
+
+            let mut entries = rpc
+                .memory_pool()?
+                .transactions
+                .inner()
+                .iter()
+                .filter(|(_, entry)| entry.transaction.fee() >= min_fee)
+                .map(|(txid, entry)| (txid.clone(), entry.clone()))
+                .collect::<Vec<_>>();
+            // The transactions are ordered by their arrival, so that the pages stay stable as new ones come in.
+            entries.sort_unstable_by(|(txid1, entry1), (txid2, entry2)| {
+                entry1.received.cmp(&entry2.received).then_with(|| txid1.cmp(txid2))
+            });
+
+            let now = Utc::now();
+            let transactions = entries
+                .iter()
+                .skip(offset)
+                .take(limit)
+                .map(|(txid, entry)| MempoolTransactionSummary {
+                    txid: hex::encode(txid),
+                    size: entry.size_in_bytes,
+                    fee: entry.transaction.fee(),
+                    received: entry.received,
+                    age_secs: (now - entry.received).num_seconds(),
+                })
+                .collect();
+
+            Ok(MemoryPoolPage {
+                total: entries.len(),
+                transactions,
+            })
         })
     }
 }
@@ -18,8 +18,18 @@
 //!
 //! See [ProtectedRpcFunctions](../trait.ProtectedRpcFunctions.html) for documentation of private endpoints.
 
-use crate::{error::RpcError, rpc_trait::ProtectedRpcFunctions, rpc_types::*, RpcImpl};
-use snarkos_consensus::ConsensusParameters;
+use crate::{admin, error::RpcError, rpc_trait::ProtectedRpcFunctions, rpc_types::*, RpcImpl};
+use snarkos_consensus::{memory_pool::Entry, ConsensusParameters};
+use snarkos_network::{
+    JournalEvent,
+    MisbehaviorEvent,
+    NetworkError,
+    PeerAnnotation,
+    PeerStatus,
+    Subnet,
+    JOURNAL_QUERY_LIMIT,
+    MAX_MISBEHAVIOR_EVENTS,
+};
 use snarkos_toolkit::{
     account::{Address, PrivateKey},
     dpc::{Record, TransactionKernelBuilder},
@@ -28,7 +38,7 @@ use snarkvm_algorithms::CRH;
 use snarkvm_dpc::{
     testnet1::{
         encrypted_record::EncryptedRecord,
-        instantiated::{Components, InstantiatedDPC},
+        instantiated::{Components, InstantiatedDPC, Tx},
         payload::Payload as RecordPayload,
         record::Record as DPCRecord,
         record_encryption::RecordEncryption,
@@ -39,20 +49,29 @@ use snarkvm_dpc::{
     AccountPrivateKey,
     AccountScheme,
     AccountViewKey,
+    BlockHeaderHash,
     DPCComponents,
     DPCScheme,
     RecordScheme as RecordModel,
     Storage,
+    TransactionScheme,
 };
 use snarkvm_utilities::{
     bytes::{FromBytes, ToBytes},
     to_bytes,
 };
 
+use chrono::{TimeZone, Utc};
 use itertools::Itertools;
 use jsonrpc_core::{IoDelegate, MetaIoHandler, Params, Value};
 use rand::{thread_rng, Rng};
-use std::{net::SocketAddr, str::FromStr, sync::Arc};
+use std::{
+    net::SocketAddr,
+    path::{Component, Path},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
 
 type JsonRPCError = jsonrpc_core::Error;
 
@@ -76,6 +95,27 @@ impl<S: Storage + Send + Sync + 'static> RpcImpl<S> {
         Ok(())
     }
 
+    /// Validate the authentication of a request to the given admin method: it needs one of the admin tokens
+    /// that may call it, if any are configured, and the RPC credentials otherwise
+    pub fn validate_admin_auth(&self, meta: Meta, method: &str) -> Result<(), JsonRPCError> {
+        if self.admin_tokens.is_empty() {
+            return self.validate_auth(meta);
+        }
+
+        let auth = meta.auth.as_deref().unwrap_or("");
+        let body = meta.body.as_deref().unwrap_or(&[]);
+        let now = Utc::now().timestamp();
+
+        match admin::authenticate(&self.admin_tokens, &self.admin_nonces, auth, body, now) {
+            Some(token) if token.allows(method) => {
+                debug!("The admin token '{}' was used to call '{}'", token.name, method);
+                Ok(())
+            }
+            Some(token) => Err(RpcError::PermissionDenied(token.name.clone(), method.to_string()).into()),
+            None => Err(JsonRPCError::invalid_params("Authentication Error")),
+        }
+    }
+
     /// Wrap authentication around `create_raw_transaction`
     pub async fn create_raw_transaction_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
         self.validate_auth(meta)?;
@@ -90,7 +130,7 @@ impl<S: Storage + Send + Sync + 'static> RpcImpl<S> {
 
         match self.create_raw_transaction(val) {
             Ok(result) => Ok(serde_json::to_value(result).expect("transaction output serialization failed")),
-            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+            Err(err) => Err(err.into()),
         }
     }
 
@@ -108,7 +148,7 @@ impl<S: Storage + Send + Sync + 'static> RpcImpl<S> {
 
         match self.create_transaction_kernel(val) {
             Ok(result) => Ok(serde_json::to_value(result).expect("transaction kernel serialization failed")),
-            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+            Err(err) => Err(err.into()),
         }
     }
 
@@ -133,7 +173,7 @@ impl<S: Storage + Send + Sync + 'static> RpcImpl<S> {
 
         match self.create_transaction(transaction_kernel) {
             Ok(result) => Ok(serde_json::to_value(result).expect("transaction output serialization failed")),
-            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+            Err(err) => Err(err.into()),
         }
     }
 
@@ -149,7 +189,7 @@ impl<S: Storage + Send + Sync + 'static> RpcImpl<S> {
 
         match self.get_record_commitment_count() {
             Ok(num_record_commitments) => Ok(Value::from(num_record_commitments)),
-            Err(_) => Err(JsonRPCError::invalid_request()),
+            Err(err) => Err(err.into()),
         }
     }
 
@@ -161,7 +201,7 @@ impl<S: Storage + Send + Sync + 'static> RpcImpl<S> {
 
         match self.get_record_commitments() {
             Ok(record_commitments) => Ok(Value::from(record_commitments)),
-            Err(_) => Err(JsonRPCError::invalid_request()),
+            Err(err) => Err(err.into()),
         }
     }
 
@@ -186,7 +226,7 @@ impl<S: Storage + Send + Sync + 'static> RpcImpl<S> {
 
         match self.get_raw_record(record_commitment) {
             Ok(record) => Ok(Value::from(record)),
-            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+            Err(err) => Err(err.into()),
         }
     }
 
@@ -211,7 +251,7 @@ impl<S: Storage + Send + Sync + 'static> RpcImpl<S> {
 
         match self.decode_record(record_bytes) {
             Ok(record) => Ok(serde_json::to_value(record).expect("record deserialization failed")),
-            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+            Err(err) => Err(err.into()),
         }
     }
 
@@ -229,7 +269,7 @@ impl<S: Storage + Send + Sync + 'static> RpcImpl<S> {
 
         match self.decrypt_record(decrypt_record_input) {
             Ok(result) => Ok(serde_json::to_value(result).expect("record serialization failed")),
-            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+            Err(err) => Err(err.into()),
         }
     }
 
@@ -241,13 +281,13 @@ impl<S: Storage + Send + Sync + 'static> RpcImpl<S> {
 
         match self.create_account() {
             Ok(account) => Ok(serde_json::to_value(account).expect("account serialization failed")),
-            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+            Err(err) => Err(err.into()),
         }
     }
 
     /// Disconnects from the given address
     pub async fn disconnect_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
-        self.validate_auth(meta)?;
+        self.validate_admin_auth(meta, "disconnect")?;
 
         let value = match params {
             Params::Array(arr) => arr,
@@ -262,6 +302,671 @@ impl<S: Storage + Send + Sync + 'static> RpcImpl<S> {
         Ok(Value::Null)
     }
 
+    /// Rebinds the listener to the current local address and announces it to peers
+    pub async fn rebind_listener_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_admin_auth(meta, "rebindlistener")?;
+
+        params.expect_no_params()?;
+
+        match self.node.rebind().await {
+            Ok(address) => Ok(Value::from(address.to_string())),
+            Err(err) => Err(RpcError::from(err).into()),
+        }
+    }
+
+    /// Adds the given address to the peer book and connects to it
+    pub async fn add_peer_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_admin_auth(meta, "addpeer")?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        let address: SocketAddr = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        match self.node.add_peer(address).await {
+            Ok(()) => Ok(Value::Null),
+            Err(err) => Err(RpcError::from(err).into()),
+        }
+    }
+
+    /// Disconnects from the given address and removes it from the peer book
+    pub async fn remove_peer_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_admin_auth(meta, "removepeer")?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        let address: SocketAddr = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        Ok(Value::from(self.node.remove_peer(address).await))
+    }
+
+    /// Disconnects from the given address and refuses connections with it for the given duration
+    pub async fn ban_peer_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_admin_auth(meta, "banpeer")?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        if value.len() != 2 {
+            return Err(JsonRPCError::invalid_params(format!(
+                "invalid length {}, expected 2 elements",
+                value.len()
+            )));
+        }
+
+        let address: SocketAddr = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+        let duration_secs: u64 = serde_json::from_value(value[1].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        self.node.ban_peer(address, Duration::from_secs(duration_secs)).await;
+
+        Ok(Value::Null)
+    }
+
+    /// Disconnects from the peers in the given IP or subnet and refuses connections with it for the given duration
+    pub async fn ban_subnet_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_admin_auth(meta, "bansubnet")?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        if value.len() != 2 {
+            return Err(JsonRPCError::invalid_params(format!(
+                "invalid length {}, expected 2 elements",
+                value.len()
+            )));
+        }
+
+        let subnet: String = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+        let subnet: Subnet = subnet
+            .parse()
+            .map_err(|e: NetworkError| JsonRPCError::invalid_params(e.to_string()))?;
+        let duration_secs: u64 = serde_json::from_value(value[1].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        match self.node.ban(subnet, Duration::from_secs(duration_secs)).await {
+            Ok(()) => Ok(Value::Null),
+            Err(err) => Err(RpcError::from(err).into()),
+        }
+    }
+
+    /// Lifts the ban of the given IP or subnet
+    pub async fn unban_subnet_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_admin_auth(meta, "unbansubnet")?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        let subnet: String = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        match self.unban_subnet(subnet) {
+            Ok(unbanned) => Ok(Value::from(unbanned)),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Returns the currently banned IPs and subnets
+    pub async fn list_banned_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_admin_auth(meta, "listbanned")?;
+
+        params.expect_no_params()?;
+
+        match self.list_banned() {
+            Ok(bans) => Ok(serde_json::to_value(bans).expect("ban list serialization failed")),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Writes the known peers to a JSON file at the given path
+    pub async fn export_peers_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_admin_auth(meta, "exportpeers")?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        let path: String = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        match self.export_peers(path) {
+            Ok(count) => Ok(Value::from(count)),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Adds the peers listed in a JSON file at the given path to the peer book
+    pub async fn import_peers_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_admin_auth(meta, "importpeers")?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        let path: String = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        match self.node.import_peers(Path::new(&path)).await {
+            Ok(count) => Ok(Value::from(count)),
+            Err(err) => Err(RpcError::from(err).into()),
+        }
+    }
+
+    /// Writes the transactions in the memory pool to a JSON file at the given path
+    pub async fn dump_mempool_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_admin_auth(meta, "dumpmempool")?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        let path: String = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        match self.dump_mempool(path) {
+            Ok(count) => Ok(Value::from(count)),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Writes the state of the ledger as of the block at the given height, or the latest one, to a snapshot file
+    pub async fn export_snapshot_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_admin_auth(meta, "exportsnapshot")?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        if value.is_empty() || value.len() > 2 {
+            return Err(JsonRPCError::invalid_params(format!(
+                "invalid length {}, expected 1 or 2 elements",
+                value.len()
+            )));
+        }
+
+        let path: String = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+        let height: Option<u32> = match value.get(1) {
+            Some(height) => serde_json::from_value(height.clone())
+                .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?,
+            None => None,
+        };
+
+        // Every block up to the given height is read and written to the file, so the export is kept off the async
+        // workers.
+        let snapshot = tokio::task::spawn_blocking(move || self.export_snapshot(path, height))
+            .await
+            .map_err(|e| RpcError::Message(e.to_string()))?;
+
+        match snapshot {
+            Ok(snapshot) => Ok(serde_json::to_value(snapshot).expect("snapshot info serialization failed")),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Adds the valid transactions from a memory pool snapshot at the given path to the memory pool
+    pub async fn load_mempool_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_admin_auth(meta, "loadmempool")?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        let path: String = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        match self.load_mempool_from(Path::new(&path)).await {
+            Ok(loaded) => Ok(serde_json::to_value(loaded).expect("loaded memory pool serialization failed")),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn load_mempool_from(&self, path: &Path) -> Result<LoadedMempool, RpcError> {
+        let json = std::fs::read(path)?;
+        let dump: MempoolDump =
+            serde_json::from_slice(&json).map_err(|e| RpcError::InvalidMempoolDump(e.to_string()))?;
+        let memory_pool = self.memory_pool()?;
+
+        self.storage.catch_up_secondary(false)?;
+
+        let mut loaded = LoadedMempool {
+            added: 0,
+            rejected: vec![],
+        };
+        for dumped in dump.transactions {
+            // The verification of a transaction is CPU-bound, so it's kept off the async workers.
+            let rpc = self.clone();
+            let (dumped, read) = tokio::task::spawn_blocking(move || {
+                let read = rpc.read_dumped_transaction(&dumped);
+                (dumped, read)
+            })
+            .await
+            .map_err(|e| RpcError::Message(e.to_string()))?;
+
+            let result = match read {
+                Ok(mut entry) => {
+                    // The transactions keep their place in the order of arrival.
+                    entry.received = dumped.received;
+                    match memory_pool.insert(&self.storage, entry).await {
+                        Ok(Some(_)) => Ok(()),
+                        Ok(None) => {
+                            Err("it's already in the memory pool, conflicts with it or doesn't fit".to_string())
+                        }
+                        Err(e) => Err(e.to_string()),
+                    }
+                }
+                Err(reason) => Err(reason),
+            };
+
+            match result {
+                Ok(()) => loaded.added += 1,
+                Err(reason) => {
+                    debug!(
+                        "Rejected transaction {} from a memory pool dump: {}",
+                        dumped.txid, reason
+                    );
+                    loaded.rejected.push(RejectedTransaction {
+                        txid: dumped.txid,
+                        reason,
+                    });
+                }
+            }
+        }
+
+        info!(
+            "Loaded {} transactions from the memory pool dump at {}, rejected {}",
+            loaded.added,
+            path.display(),
+            loaded.rejected.len()
+        );
+
+        Ok(loaded)
+    }
+
+    /// Decodes a transaction from a memory pool snapshot, applying the same checks as to the ones sent to the node,
+    /// and checking that it matches its recorded id.
+    fn read_dumped_transaction(&self, dumped: &MempoolDumpEntry) -> Result<Entry<Tx>, String> {
+        let transaction_bytes = hex::decode(&dumped.transaction).map_err(|e| format!("invalid hex: {}", e))?;
+        let transaction = Tx::read(&transaction_bytes[..]).map_err(|e| format!("malformed transaction: {}", e))?;
+        let txid = transaction.transaction_id().map_err(|e| e.to_string())?;
+
+        if !hex::encode(txid).eq_ignore_ascii_case(&dumped.txid) {
+            return Err(format!("its actual id is {}", hex::encode(txid)));
+        }
+        if self.storage.transaction_conflicts(&transaction) {
+            return Err("it spends records that were already spent".into());
+        }
+        let consensus = &self.sync_handler().map_err(|e| e.to_string())?.consensus;
+        if !consensus.verify_transaction(&transaction).map_err(|e| e.to_string())? {
+            return Err("it doesn't verify".into());
+        }
+
+        Ok(Entry::new(transaction, transaction_bytes.len()))
+    }
+
+    /// Returns the details of the given peer
+    pub async fn get_peer_details_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        let address: SocketAddr = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        match self.peer_details(address).await {
+            Ok(details) => Ok(serde_json::to_value(details).expect("peer details serialization failed")),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn peer_details(&self, address: SocketAddr) -> Result<PeerDetails, RpcError> {
+        let mut peer = self
+            .node
+            .peer_book
+            .get_peer(address)
+            .await
+            .ok_or(RpcError::UnknownPeer(address))?;
+        let annotation = self.node.peer_book.peer_annotation(address).unwrap_or_default();
+
+        Ok(PeerDetails {
+            address,
+            is_connected: peer.status == PeerStatus::Connected,
+            is_bootnode: peer.is_bootnode,
+            is_banned: self.node.is_banned(address.ip()),
+            failures: peer.failures(),
+            block_height: peer.quality.block_height,
+            earliest_block_height: peer.quality.earliest_block_height,
+            rtt_ms: peer.quality.rtt_ms,
+            num_messages_received: peer.quality.num_messages_received,
+            first_seen: peer.quality.first_seen,
+            last_seen: peer.quality.last_seen,
+            last_connected: peer.quality.last_connected,
+            last_disconnected: peer.quality.last_disconnected,
+            connected_count: peer.quality.connected_count,
+            disconnected_count: peer.quality.disconnected_count,
+            relayed_transactions_accepted: peer.quality.relayed_transactions_accepted,
+            relayed_transactions_rejected: peer.quality.relayed_transactions_rejected,
+            relay_acceptance_ratio: peer.relay_acceptance_ratio(),
+            has_relay_privileges: peer.has_relay_privileges(),
+            sync_queries: peer.quality.sync_queries,
+            deep_sync_queries: peer.quality.deep_sync_queries,
+            repeated_sync_queries: peer.quality.repeated_sync_queries,
+            throttled_sync_requests: peer.quality.throttled_sync_requests,
+            unverified_alias: peer.quality.alias,
+            labels: annotation.labels,
+            note: annotation.note,
+        })
+    }
+
+    /// Returns the most recent misbehavior events of the given peer
+    pub async fn get_peer_events_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        if value.len() != 1 && value.len() != 2 {
+            return Err(JsonRPCError::invalid_params(format!(
+                "invalid length {}, expected 1 or 2 elements",
+                value.len()
+            )));
+        }
+
+        let address: SocketAddr = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+        let limit: Option<usize> = match value.get(1) {
+            Some(limit) => serde_json::from_value(limit.clone())
+                .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?,
+            None => None,
+        };
+
+        match self.peer_events(address, limit).await {
+            Ok(events) => Ok(serde_json::to_value(events).expect("peer events serialization failed")),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn peer_events(&self, address: SocketAddr, limit: Option<usize>) -> Result<Vec<MisbehaviorEvent>, RpcError> {
+        let peer = self
+            .node
+            .peer_book
+            .get_peer(address)
+            .await
+            .ok_or(RpcError::UnknownPeer(address))?;
+
+        Ok(peer.quality.recent_misbehavior(limit.unwrap_or(MAX_MISBEHAVIOR_EVENTS)))
+    }
+
+    /// Replaces the labels and the note attached to the given peer
+    pub async fn annotate_peer_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_admin_auth(meta, "annotatepeer")?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        if value.len() != 2 && value.len() != 3 {
+            return Err(JsonRPCError::invalid_params(format!(
+                "invalid length {}, expected 2 or 3 elements",
+                value.len()
+            )));
+        }
+
+        let address: SocketAddr = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+        let labels: Vec<String> = serde_json::from_value(value[1].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+        let note: Option<String> = match value.get(2) {
+            Some(note) => serde_json::from_value(note.clone())
+                .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?,
+            None => None,
+        };
+
+        match self.node.annotate_peer(address, PeerAnnotation { labels, note }).await {
+            Ok(()) => Ok(Value::Null),
+            Err(err) => Err(RpcError::from(err).into()),
+        }
+    }
+
+    /// Returns the quality scores of the connected peers
+    pub async fn get_peer_scores_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        params.expect_no_params()?;
+
+        Ok(serde_json::to_value(self.peer_scores().await).expect("peer scores serialization failed"))
+    }
+
+    async fn peer_scores(&self) -> Vec<PeerScoreInfo> {
+        let weights = self.node.config.peer_score_weights;
+
+        let mut scores = self
+            .node
+            .peer_book
+            .connected_peers_snapshot()
+            .await
+            .into_iter()
+            .map(|mut peer| PeerScoreInfo {
+                address: peer.address,
+                score: peer.score(&weights),
+            })
+            .collect::<Vec<_>>();
+        scores.sort_unstable_by_key(|info| info.address);
+
+        scores
+    }
+
+    /// Returns the round-trip time percentiles of the connected peers
+    pub async fn get_peer_latencies_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        params.expect_no_params()?;
+
+        Ok(serde_json::to_value(self.peer_latencies().await).expect("peer latencies serialization failed"))
+    }
+
+    async fn peer_latencies(&self) -> PeerLatencies {
+        let mut peers = self
+            .node
+            .peer_book
+            .connected_peers_snapshot()
+            .await
+            .into_iter()
+            .map(|peer| PeerLatencyInfo {
+                address: peer.address,
+                rtt_ms: peer.quality.rtt_ms,
+                samples: peer.quality.rtt_samples.len(),
+                percentiles: peer.quality.rtt_percentiles(),
+            })
+            .collect::<Vec<_>>();
+        peers.sort_unstable_by_key(|info| info.address);
+
+        PeerLatencies {
+            network: self.node.rtt_percentiles().await,
+            peers,
+        }
+    }
+
+    /// Returns the disconnected peers bucketed by how long ago they were last seen
+    pub async fn get_peer_ages_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        params.expect_no_params()?;
+
+        Ok(serde_json::to_value(self.peer_ages()).expect("peer ages serialization failed"))
+    }
+
+    fn peer_ages(&self) -> PeerAges {
+        let buckets = self
+            .node
+            .peer_book
+            .disconnected_peer_ages()
+            .into_iter()
+            .map(|bucket| PeerAgeBucketInfo {
+                last_seen_within: bucket.last_seen_within.to_string(),
+                peers: bucket.peers,
+                unroutable: bucket.unroutable,
+            })
+            .collect::<Vec<_>>();
+
+        PeerAges {
+            disconnected: buckets.iter().map(|bucket| bucket.peers).sum(),
+            unroutable: buckets.iter().map(|bucket| bucket.unroutable).sum(),
+            buckets,
+        }
+    }
+
+    /// Starts a block sync right away, optionally only with the given peer
+    pub async fn trigger_sync_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_admin_auth(meta, "triggersync")?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            Params::None => vec![],
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        let address: Option<SocketAddr> = match value.get(0) {
+            Some(address) => Some(
+                serde_json::from_value(address.clone())
+                    .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?,
+            ),
+            None => None,
+        };
+
+        match self.trigger_sync(address) {
+            Ok(started) => Ok(Value::from(started)),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Returns the mining history for the blocks within the given range of heights
+    pub async fn get_mining_history_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        if value.len() != 2 {
+            return Err(JsonRPCError::invalid_params(format!(
+                "invalid length {}, expected 2 elements",
+                value.len()
+            )));
+        }
+
+        let start_height: u32 = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+        let end_height: u32 = serde_json::from_value(value[1].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        match self.get_mining_history(start_height, end_height) {
+            Ok(history) => Ok(serde_json::to_value(history).expect("mining history serialization failed")),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Returns the journaled events that occurred at or after the given time
+    pub async fn get_journal_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        if value.len() != 1 {
+            return Err(JsonRPCError::invalid_params(format!(
+                "invalid length {}, expected 1 element",
+                value.len()
+            )));
+        }
+
+        let since: i64 = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        match self.get_journal(since) {
+            Ok(events) => Ok(serde_json::to_value(events).expect("journal serialization failed")),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Reloads the peer limits, the bootnodes and the sync intervals from the node's configuration
+    pub async fn reload_config_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_admin_auth(meta, "reloadconfig")?;
+
+        params.expect_no_params()?;
+
+        match self.reload_config() {
+            Ok(reloaded) => Ok(serde_json::to_value(reloaded).expect("reloaded config serialization failed")),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Starts mining with the given number of threads, replacing the current ones if the node is already mining
+    pub async fn start_mining_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_admin_auth(meta, "startmining")?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            Params::None => vec![],
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        let num_threads: usize = match value.get(0) {
+            Some(num_threads) => serde_json::from_value(num_threads.clone())
+                .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?,
+            None => 1,
+        };
+        if num_threads == 0 {
+            return Err(JsonRPCError::invalid_params("at least one mining thread is required"));
+        }
+
+        match self.start_mining(num_threads) {
+            Ok(started) => Ok(Value::from(started)),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Stops mining
+    pub async fn stop_mining_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_admin_auth(meta, "stopmining")?;
+
+        params.expect_no_params()?;
+
+        match self.stop_mining() {
+            Ok(stopped) => Ok(Value::from(stopped)),
+            Err(err) => Err(err.into()),
+        }
+    }
+
     /// Expose the protected functions as RPC enpoints
     pub fn add_protected(&self, io: &mut MetaIoHandler<Meta>) {
         let mut d = IoDelegate::<Self, Meta>::new(Arc::new(self.clone()));
@@ -306,6 +1011,102 @@ impl<S: Storage + Send + Sync + 'static> RpcImpl<S> {
             let rpc = rpc.clone();
             rpc.disconnect_protected(params, meta)
         });
+        d.add_method_with_meta("rebindlistener", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.rebind_listener_protected(params, meta)
+        });
+        d.add_method_with_meta("addpeer", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.add_peer_protected(params, meta)
+        });
+        d.add_method_with_meta("removepeer", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.remove_peer_protected(params, meta)
+        });
+        d.add_method_with_meta("banpeer", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.ban_peer_protected(params, meta)
+        });
+        d.add_method_with_meta("bansubnet", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.ban_subnet_protected(params, meta)
+        });
+        d.add_method_with_meta("unbansubnet", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.unban_subnet_protected(params, meta)
+        });
+        d.add_method_with_meta("listbanned", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.list_banned_protected(params, meta)
+        });
+        d.add_method_with_meta("exportpeers", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.export_peers_protected(params, meta)
+        });
+        d.add_method_with_meta("exportsnapshot", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.export_snapshot_protected(params, meta)
+        });
+        d.add_method_with_meta("importpeers", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.import_peers_protected(params, meta)
+        });
+        d.add_method_with_meta("dumpmempool", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.dump_mempool_protected(params, meta)
+        });
+        d.add_method_with_meta("loadmempool", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.load_mempool_protected(params, meta)
+        });
+        d.add_method_with_meta("getpeerdetails", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.get_peer_details_protected(params, meta)
+        });
+        d.add_method_with_meta("getpeerevents", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.get_peer_events_protected(params, meta)
+        });
+        d.add_method_with_meta("annotatepeer", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.annotate_peer_protected(params, meta)
+        });
+        d.add_method_with_meta("getpeerscores", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.get_peer_scores_protected(params, meta)
+        });
+        d.add_method_with_meta("getpeerlatencies", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.get_peer_latencies_protected(params, meta)
+        });
+        d.add_method_with_meta("getpeerages", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.get_peer_ages_protected(params, meta)
+        });
+        d.add_method_with_meta("triggersync", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.trigger_sync_protected(params, meta)
+        });
+        d.add_method_with_meta("reloadconfig", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.reload_config_protected(params, meta)
+        });
+        d.add_method_with_meta("getmininghistory", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.get_mining_history_protected(params, meta)
+        });
+        d.add_method_with_meta("getjournal", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.get_journal_protected(params, meta)
+        });
+        d.add_method_with_meta("startmining", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.start_mining_protected(params, meta)
+        });
+        d.add_method_with_meta("stopmining", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.stop_mining_protected(params, meta)
+        });
 
         io.extend_with(d)
     }
@@ -654,4 +1455,248 @@ impl<S: Storage + Send + Sync + 'static> ProtectedRpcFunctions for RpcImpl<S> {
         let node = self.node.clone();
         tokio::spawn(async move { node.disconnect_from_peer(address).await });
     }
+
+    fn rebind_listener(&self) {
+        let node = self.node.clone();
+        tokio::spawn(async move {
+            if let Err(e) = node.rebind().await {
+                error!("Couldn't rebind the listener: {}", e);
+            }
+        });
+    }
+
+    fn add_peer(&self, address: SocketAddr) {
+        let node = self.node.clone();
+        tokio::spawn(async move {
+            if let Err(e) = node.add_peer(address).await {
+                error!("Couldn't add peer {}: {}", address, e);
+            }
+        });
+    }
+
+    fn remove_peer(&self, address: SocketAddr) {
+        let node = self.node.clone();
+        tokio::spawn(async move { node.remove_peer(address).await });
+    }
+
+    fn ban_peer(&self, address: SocketAddr, duration_secs: u64) {
+        let node = self.node.clone();
+        tokio::spawn(async move { node.ban_peer(address, Duration::from_secs(duration_secs)).await });
+    }
+
+    fn ban_subnet(&self, subnet: String, duration_secs: u64) -> Result<(), RpcError> {
+        let subnet: Subnet = subnet.parse()?;
+
+        let node = self.node.clone();
+        tokio::spawn(async move {
+            if let Err(e) = node.ban(subnet, Duration::from_secs(duration_secs)).await {
+                error!("Couldn't persist the ban of {}: {}", subnet, e);
+            }
+        });
+
+        Ok(())
+    }
+
+    fn unban_subnet(&self, subnet: String) -> Result<bool, RpcError> {
+        let subnet: Subnet = subnet.parse()?;
+
+        Ok(self.node.unban(subnet))
+    }
+
+    fn list_banned(&self) -> Result<Vec<BanInfo>, RpcError> {
+        let config_bans = self
+            .node
+            .config
+            .banned_subnets()
+            .iter()
+            .map(|subnet| BanInfo {
+                subnet: subnet.to_string(),
+                banned_until: None,
+            })
+            .collect::<Vec<_>>();
+        let runtime_bans = self.node.bans.active().into_iter().map(|(subnet, expiry)| BanInfo {
+            subnet: subnet.to_string(),
+            banned_until: Some(expiry),
+        });
+
+        Ok(config_bans.into_iter().chain(runtime_bans).collect())
+    }
+
+    fn export_peers(&self, path: String) -> Result<usize, RpcError> {
+        Ok(self.node.export_peers(Path::new(&path))?)
+    }
+
+    fn import_peers(&self, path: String) -> Result<usize, RpcError> {
+        // this block_on will halt the tokio worker until the peers are imported
+        Ok(futures::executor::block_on(self.node.import_peers(Path::new(&path)))?)
+    }
+
+    fn dump_mempool(&self, path: String) -> Result<usize, RpcError> {
+        let mut entries = self
+            .memory_pool()?
+            .transactions
+            .inner()
+            .iter()
+            .map(|(txid, entry)| (txid.clone(), entry.clone()))
+            .collect::<Vec<_>>();
+        entries.sort_unstable_by(|(txid1, entry1), (txid2, entry2)| {
+            entry1.received.cmp(&entry2.received).then_with(|| txid1.cmp(txid2))
+        });
+
+        let mut transactions = Vec::with_capacity(entries.len());
+        for (txid, entry) in entries {
+            transactions.push(MempoolDumpEntry {
+                txid: hex::encode(txid),
+                transaction: hex::encode(to_bytes![entry.transaction]?),
+                received: entry.received,
+            });
+        }
+        let dump = MempoolDump {
+            dumped_at: Utc::now(),
+            block_height: self.storage.get_current_block_height(),
+            transactions,
+        };
+
+        let json = serde_json::to_vec_pretty(&dump).expect("memory pool dump serialization failed");
+        std::fs::write(&path, json)?;
+        info!(
+            "Dumped {} memory pool transactions to {}",
+            dump.transactions.len(),
+            path
+        );
+
+        Ok(dump.transactions.len())
+    }
+
+    fn load_mempool(&self, path: String) -> Result<LoadedMempool, RpcError> {
+        // this block_on will halt the tokio worker until the transactions are verified and added
+        futures::executor::block_on(self.load_mempool_from(Path::new(&path)))
+    }
+
+    fn export_snapshot(&self, path: String, height: Option<u32>) -> Result<LedgerSnapshotInfo, RpcError> {
+        let storage = &self.storage;
+        storage.catch_up_secondary(false)?;
+
+        // The snapshots may only be written to the node's snapshot directory.
+        let dir = match self.node.config.snapshot_dir {
+            Some(ref dir) => dir,
+            None => return Err(RpcError::Message("The node can't write ledger snapshots".into())),
+        };
+        let relative_path = Path::new(&path);
+        let is_confined = relative_path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+        if path.is_empty() || !is_confined {
+            return Err(RpcError::InvalidSnapshotPath(path));
+        }
+        std::fs::create_dir_all(dir)?;
+        let file = dir.join(relative_path);
+
+        let height = height.unwrap_or_else(|| storage.get_current_block_height());
+        let summary = storage.export_snapshot(height, &file)?;
+
+        Ok(LedgerSnapshotInfo {
+            path: file.display().to_string(),
+            height: summary.height,
+            block_hash: summary.block_hash.to_string(),
+            checksum: hex::encode(summary.checksum),
+        })
+    }
+
+    fn get_peer_details(&self, address: SocketAddr) -> Result<PeerDetails, RpcError> {
+        // this block_on will halt the tokio worker until the peer is loaded
+        futures::executor::block_on(self.peer_details(address))
+    }
+
+    fn get_peer_events(&self, address: SocketAddr, limit: Option<usize>) -> Result<Vec<MisbehaviorEvent>, RpcError> {
+        futures::executor::block_on(self.peer_events(address, limit))
+    }
+
+    fn annotate_peer(&self, address: SocketAddr, labels: Vec<String>, note: Option<String>) -> Result<(), RpcError> {
+        // this block_on will halt the tokio worker until the annotation is applied
+        Ok(futures::executor::block_on(
+            self.node.annotate_peer(address, PeerAnnotation { labels, note }),
+        )?)
+    }
+
+    fn get_peer_scores(&self) -> Result<Vec<PeerScoreInfo>, RpcError> {
+        // this block_on will halt the tokio worker until the peers are loaded
+        Ok(futures::executor::block_on(self.peer_scores()))
+    }
+
+    fn get_peer_latencies(&self) -> Result<PeerLatencies, RpcError> {
+        // this block_on will halt the tokio worker until the peers are loaded
+        Ok(futures::executor::block_on(self.peer_latencies()))
+    }
+
+    fn get_peer_ages(&self) -> Result<PeerAges, RpcError> {
+        Ok(self.peer_ages())
+    }
+
+    fn trigger_sync(&self, address: Option<SocketAddr>) -> Result<bool, RpcError> {
+        self.sync_handler()?;
+
+        if let Some(address) = address {
+            if !self.node.peer_book.is_connected(address) {
+                return Err(RpcError::UnknownPeer(address));
+            }
+        }
+
+        Ok(self.node.trigger_sync(address))
+    }
+
+    fn reload_config(&self) -> Result<ReloadedConfig, RpcError> {
+        let reloaded = self.node.reload_config()?;
+
+        Ok(ReloadedConfig {
+            min_peers: reloaded.min_peers,
+            max_peers: reloaded.max_peers,
+            bootnodes: reloaded.bootnodes,
+            peer_sync_interval: reloaded.peer_sync_interval.as_secs(),
+            block_sync_interval: reloaded.block_sync_interval.as_secs(),
+            mempool_sync_interval: reloaded.mempool_sync_interval.as_secs(),
+        })
+    }
+
+    fn get_mining_history(&self, start_height: u32, end_height: u32) -> Result<Vec<MiningHistoryEntry>, RpcError> {
+        self.storage.catch_up_secondary(false)?;
+
+        let history = self
+            .storage
+            .get_mining_history(start_height, end_height)?
+            .into_iter()
+            .map(|event| MiningHistoryEntry {
+                kind: event.kind.to_string(),
+                block_height: event.block_height,
+                block_hash: event.block_hash.map(hex::encode),
+                previous_block_hash: hex::encode(event.previous_block_hash),
+                time: event.timestamp,
+                is_canon: event
+                    .block_hash
+                    .map(|hash| self.storage.is_canon(&BlockHeaderHash(hash))),
+            })
+            .collect();
+
+        Ok(history)
+    }
+
+    fn get_journal(&self, since: i64) -> Result<Vec<JournalEvent>, RpcError> {
+        let since = Utc
+            .timestamp_opt(since, 0)
+            .single()
+            .ok_or_else(|| RpcError::Message(format!("invalid timestamp: {}", since)))?;
+
+        Ok(self.node.journal_events(since, JOURNAL_QUERY_LIMIT)?)
+    }
+
+    fn start_mining(&self, num_threads: usize) -> Result<bool, RpcError> {
+        let was_mining = self.node.mining().map(|mining| mining.is_mining()).unwrap_or(false);
+        self.node.start_mining(num_threads)?;
+
+        Ok(!was_mining)
+    }
+
+    fn stop_mining(&self) -> Result<bool, RpcError> {
+        Ok(self.node.stop_mining())
+    }
 }
@@ -17,54 +17,95 @@
 //! Definition of the public and private RPC endpoints.
 
 use crate::{error::RpcError, rpc_types::*};
-use snarkos_metrics::snapshots::NodeStats;
+use snarkos_metrics::snapshots::{NodeStats, NodeStatsEntry};
+use snarkos_network::{JournalEvent, MisbehaviorEvent, SyncStatus};
 
+use jsonrpc_core::BoxFuture;
 use jsonrpc_derive::rpc;
 
 use std::net::SocketAddr;
 
 /// Definition of public RPC endpoints.
+///
+/// The endpoints are asynchronous, so that the RPC server can process the entries of a batch request
+/// concurrently.
 #[rpc]
 pub trait RpcFunctions {
     // todo: readd in Rust 1.54
     // #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getblock.md"))]
     #[rpc(name = "getblock")]
-    fn get_block(&self, block_hash_string: String) -> Result<BlockInfo, RpcError>;
+    fn get_block(&self, block_hash_string: String, verbosity: Option<u8>) -> BoxFuture<Result<BlockData, RpcError>>;
 
     // todo: readd in Rust 1.54
     // #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getblockcount.md"))]
     #[rpc(name = "getblockcount")]
-    fn get_block_count(&self) -> Result<u32, RpcError>;
+    fn get_block_count(&self) -> BoxFuture<Result<u32, RpcError>>;
 
     // todo: readd in Rust 1.54
     // #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getbestblockhash.md"))]
     #[rpc(name = "getbestblockhash")]
-    fn get_best_block_hash(&self) -> Result<String, RpcError>;
+    fn get_best_block_hash(&self) -> BoxFuture<Result<String, RpcError>>;
 
     // todo: readd in Rust 1.54
     // #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getblockhash.md"))]
     #[rpc(name = "getblockhash")]
-    fn get_block_hash(&self, block_height: u32) -> Result<String, RpcError>;
+    fn get_block_hash(&self, block_height: u32) -> BoxFuture<Result<String, RpcError>>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getchaintips.md"))]
+    #[rpc(name = "getchaintips")]
+    fn get_chain_tips(&self) -> BoxFuture<Result<Vec<ChainTipInfo>, RpcError>>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getchainanalytics.md"))]
+    #[rpc(name = "getchainanalytics")]
+    fn get_chain_analytics(&self) -> BoxFuture<Result<ChainAnalytics, RpcError>>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getforkchoice.md"))]
+    #[rpc(name = "getforkchoice")]
+    fn get_fork_choice(&self, block_hash_string: String) -> BoxFuture<Result<ForkChoiceInfo, RpcError>>;
 
     // todo: readd in Rust 1.54
     // #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getrawtransaction.md"))]
     #[rpc(name = "getrawtransaction")]
-    fn get_raw_transaction(&self, transaction_id: String) -> Result<String, RpcError>;
+    fn get_raw_transaction(&self, transaction_id: String) -> BoxFuture<Result<String, RpcError>>;
 
     // todo: readd in Rust 1.54
     // #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/gettransactioninfo.md"))]
     #[rpc(name = "gettransactioninfo")]
-    fn get_transaction_info(&self, transaction_id: String) -> Result<TransactionInfo, RpcError>;
+    fn get_transaction_info(&self, transaction_id: String) -> BoxFuture<Result<TransactionInfo, RpcError>>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/gettransactionstatus.md"))]
+    #[rpc(name = "gettransactionstatus")]
+    fn get_transaction_status(&self, transaction_id: String) -> BoxFuture<Result<TransactionStatus, RpcError>>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(
+    //     nightly,
+    //     doc(include = "../documentation/public_endpoints/gettransactionsbycommitment.md")
+    // )]
+    #[rpc(name = "gettransactionsbycommitment")]
+    fn get_transactions_by_commitment(
+        &self,
+        commitments: Vec<String>,
+    ) -> BoxFuture<Result<Vec<RecordTransaction>, RpcError>>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/isserialnumberspent.md"))]
+    #[rpc(name = "isserialnumberspent")]
+    fn is_serial_number_spent(&self, serial_number: String) -> BoxFuture<Result<SerialNumberStatus, RpcError>>;
 
     // todo: readd in Rust 1.54
     // #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/decoderawtransaction.md"))]
     #[rpc(name = "decoderawtransaction")]
-    fn decode_raw_transaction(&self, transaction_bytes: String) -> Result<TransactionInfo, RpcError>;
+    fn decode_raw_transaction(&self, transaction_bytes: String) -> BoxFuture<Result<TransactionInfo, RpcError>>;
 
     // todo: readd in Rust 1.54
     // #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/sendtransaction.md"))]
     #[rpc(name = "sendtransaction")]
-    fn send_raw_transaction(&self, transaction_bytes: String) -> Result<String, RpcError>;
+    fn send_raw_transaction(&self, transaction_bytes: String) -> BoxFuture<Result<String, RpcError>>;
 
     // todo: readd in Rust 1.54
     // #[cfg_attr(
@@ -72,32 +113,66 @@ pub trait RpcFunctions {
     //     doc(include = "../documentation/public_endpoints/validaterawtransaction.md")
     // )]
     #[rpc(name = "validaterawtransaction")]
-    fn validate_raw_transaction(&self, transaction_bytes: String) -> Result<bool, RpcError>;
+    fn validate_raw_transaction(&self, transaction_bytes: String) -> BoxFuture<Result<bool, RpcError>>;
 
     // todo: readd in Rust 1.54
     // #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getconnectioncount.md"))]
     #[rpc(name = "getconnectioncount")]
-    fn get_connection_count(&self) -> Result<usize, RpcError>;
+    fn get_connection_count(&self) -> BoxFuture<Result<usize, RpcError>>;
 
     // todo: readd in Rust 1.54
     // #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getpeerinfo.md"))]
     #[rpc(name = "getpeerinfo")]
-    fn get_peer_info(&self) -> Result<PeerInfo, RpcError>;
+    fn get_peer_info(&self) -> BoxFuture<Result<PeerInfo, RpcError>>;
 
     // todo: readd in Rust 1.54
     // #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getnodeinfo.md"))]
     #[rpc(name = "getnodeinfo")]
-    fn get_node_info(&self) -> Result<NodeInfo, RpcError>;
+    fn get_node_info(&self) -> BoxFuture<Result<NodeInfo, RpcError>>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getsyncstatus.md"))]
+    #[rpc(name = "getsyncstatus")]
+    fn get_sync_status(&self) -> BoxFuture<Result<SyncStatus, RpcError>>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getnetworkgraph.md"))]
+    #[rpc(name = "getnetworkgraph")]
+    fn get_network_graph(&self) -> BoxFuture<Result<NetworkGraph, RpcError>>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getnetworkgraphat.md"))]
+    #[rpc(name = "getnetworkgraphat")]
+    fn get_network_graph_at(&self, timestamp: i64) -> BoxFuture<Result<NetworkGraphSnapshot, RpcError>>;
 
     // todo: readd in Rust 1.54
     // #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getnodestats.md"))]
     #[rpc(name = "getnodestats")]
-    fn get_node_stats(&self) -> Result<NodeStats, RpcError>;
+    fn get_node_stats(&self) -> BoxFuture<Result<NodeStats, RpcError>>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getnodestatshistory.md"))]
+    #[rpc(name = "getnodestatshistory")]
+    fn get_node_stats_history(
+        &self,
+        range_secs: u64,
+        step_secs: u64,
+    ) -> BoxFuture<Result<Vec<NodeStatsEntry>, RpcError>>;
 
     // todo: readd in Rust 1.54
     // #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getblocktemplate.md"))]
     #[rpc(name = "getblocktemplate")]
-    fn get_block_template(&self) -> Result<BlockTemplate, RpcError>;
+    fn get_block_template(&self, longpoll_id: Option<String>) -> BoxFuture<Result<BlockTemplate, RpcError>>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getmemorypool.md"))]
+    #[rpc(name = "getmemorypool")]
+    fn get_memory_pool(
+        &self,
+        offset: Option<usize>,
+        limit: Option<usize>,
+        min_fee: Option<i64>,
+    ) -> BoxFuture<Result<MemoryPoolPage, RpcError>>;
 }
 
 /// Definition of private RPC endpoints that require authentication.
@@ -150,4 +225,100 @@ pub trait ProtectedRpcFunctions {
     // todo: readd in Rust 1.54
     // #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/disconnect.md"))]
     fn disconnect(&self, address: SocketAddr);
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/rebindlistener.md"))]
+    fn rebind_listener(&self);
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/addpeer.md"))]
+    fn add_peer(&self, address: SocketAddr);
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/removepeer.md"))]
+    fn remove_peer(&self, address: SocketAddr);
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/banpeer.md"))]
+    fn ban_peer(&self, address: SocketAddr, duration_secs: u64);
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/bansubnet.md"))]
+    fn ban_subnet(&self, subnet: String, duration_secs: u64) -> Result<(), RpcError>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/unbansubnet.md"))]
+    fn unban_subnet(&self, subnet: String) -> Result<bool, RpcError>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/listbanned.md"))]
+    fn list_banned(&self) -> Result<Vec<BanInfo>, RpcError>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/exportpeers.md"))]
+    fn export_peers(&self, path: String) -> Result<usize, RpcError>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/importpeers.md"))]
+    fn import_peers(&self, path: String) -> Result<usize, RpcError>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/dumpmempool.md"))]
+    fn dump_mempool(&self, path: String) -> Result<usize, RpcError>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/loadmempool.md"))]
+    fn load_mempool(&self, path: String) -> Result<LoadedMempool, RpcError>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/exportsnapshot.md"))]
+    fn export_snapshot(&self, path: String, height: Option<u32>) -> Result<LedgerSnapshotInfo, RpcError>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/getpeerdetails.md"))]
+    fn get_peer_details(&self, address: SocketAddr) -> Result<PeerDetails, RpcError>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/getpeerevents.md"))]
+    fn get_peer_events(&self, address: SocketAddr, limit: Option<usize>) -> Result<Vec<MisbehaviorEvent>, RpcError>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/annotatepeer.md"))]
+    fn annotate_peer(&self, address: SocketAddr, labels: Vec<String>, note: Option<String>) -> Result<(), RpcError>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/getpeerscores.md"))]
+    fn get_peer_scores(&self) -> Result<Vec<PeerScoreInfo>, RpcError>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/getpeerlatencies.md"))]
+    fn get_peer_latencies(&self) -> Result<PeerLatencies, RpcError>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/getpeerages.md"))]
+    fn get_peer_ages(&self) -> Result<PeerAges, RpcError>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/triggersync.md"))]
+    fn trigger_sync(&self, address: Option<SocketAddr>) -> Result<bool, RpcError>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/reloadconfig.md"))]
+    fn reload_config(&self) -> Result<ReloadedConfig, RpcError>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/getmininghistory.md"))]
+    fn get_mining_history(&self, start_height: u32, end_height: u32) -> Result<Vec<MiningHistoryEntry>, RpcError>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/getjournal.md"))]
+    fn get_journal(&self, since: i64) -> Result<Vec<JournalEvent>, RpcError>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/startmining.md"))]
+    fn start_mining(&self, num_threads: usize) -> Result<bool, RpcError>;
+
+    // todo: readd in Rust 1.54
+    // #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/stopmining.md"))]
+    fn stop_mining(&self) -> Result<bool, RpcError>;
 }
@@ -16,6 +16,8 @@
 
 //! Structures for RPC endpoint requests and responses.
 
+use snarkos_network::{PeerAlias, PeerScore, RttPercentiles};
+
 use chrono::{DateTime, Utc};
 use jsonrpc_core::Metadata;
 use serde::{Deserialize, Serialize};
@@ -35,6 +37,8 @@ pub struct RpcCredentials {
 pub struct Meta {
     /// An optional authentication string for protected RPC functions
     pub auth: Option<String>,
+    /// The body of the HTTP request, which HMAC-signed requests to admin functions are authenticated against
+    pub body: Option<hyper::body::Bytes>,
 }
 
 impl Metadata for Meta {}
@@ -79,6 +83,31 @@ pub struct BlockInfo {
     pub transactions: Vec<String>,
 }
 
+/// Returned value for the `getblock` rpc call with a verbosity of 2
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct VerboseBlockInfo {
+    /// Information about the block
+    #[serde(flatten)]
+    pub info: BlockInfo,
+
+    /// The decoded transactions, in the same order as their ids
+    pub transaction_info: Vec<TransactionInfo>,
+}
+
+/// Returned value for the `getblock` rpc call, depending on the requested verbosity
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BlockData {
+    /// The hex-encoded block bytes (verbosity 0)
+    Raw(String),
+
+    /// Information about the block, along with its decoded transactions (verbosity 2)
+    Verbose(VerboseBlockInfo),
+
+    /// Information about the block (verbosity 1)
+    Info(BlockInfo),
+}
+
 /// Returned value for the `getblocktemplate` rpc call
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct BlockTemplate {
@@ -99,6 +128,9 @@ pub struct BlockTemplate {
 
     /// Amount spendable by the coinbase transaction (block rewards + transaction fees)
     pub coinbase_value: u64,
+
+    /// Identifier of the template, to be passed back in order to long-poll for the next one
+    pub longpoll_id: String,
 }
 
 /// Output for the `createrawtransaction` rpc call
@@ -135,6 +167,21 @@ pub struct NodeInfo {
     /// Flag indicating if the node is currently syncing
     pub is_syncing: bool,
 
+    /// Flag indicating if the node's chain is close to the network's estimated height
+    pub is_synced: bool,
+
+    /// The estimated height of the network's chain; the median of the connected peers' block heights
+    pub network_height: Option<u32>,
+
+    /// Flag indicating if the node is shutting down
+    pub is_shutting_down: bool,
+
+    /// The offset applied to the node's clock in order to follow the network's time, in seconds
+    pub time_offset: i64,
+
+    /// Flag indicating if the node's clock deviates from the network's time by more than a minute
+    pub is_clock_skewed: bool,
+
     /// The timestamp of when the node was launched.
     pub launched: DateTime<Utc>,
 
@@ -142,11 +189,428 @@ pub struct NodeInfo {
     pub version: String,
 }
 
+/// Returned value for the `gettransactionstatus` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TransactionStatus {
+    /// Transaction id
+    pub txid: String,
+    /// The status of the transaction; either `unknown`, `mempool` or `confirmed`
+    pub status: String,
+    /// The memory pool entry of the transaction, if it's in the memory pool
+    pub mempool_entry: Option<MempoolEntryInfo>,
+    /// The position of the transaction in the canon chain, if it's confirmed
+    pub confirmation: Option<TransactionConfirmation>,
+}
+
+/// The details of a transaction waiting in the memory pool
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MempoolEntryInfo {
+    /// The time the transaction was received at
+    pub received: DateTime<Utc>,
+    /// Transaction size
+    pub size: usize,
+}
+
+/// Returned value for the `getmemorypool` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MemoryPoolPage {
+    /// The number of transactions in the memory pool offering at least the requested fee
+    pub total: usize,
+    /// The requested page of those transactions, from the oldest to the newest
+    pub transactions: Vec<MempoolTransactionSummary>,
+}
+
+/// The summary of a transaction waiting in the memory pool
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MempoolTransactionSummary {
+    /// Transaction id
+    pub txid: String,
+    /// Transaction size
+    pub size: usize,
+    /// The fee offered by the transaction
+    pub fee: i64,
+    /// The time the transaction was received at
+    pub received: DateTime<Utc>,
+    /// The number of seconds the transaction has been waiting for
+    pub age_secs: i64,
+}
+
+/// A snapshot of the memory pool written by the `dumpmempool` rpc call, which `loadmempool` can replay
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MempoolDump {
+    /// The time the snapshot was taken at
+    pub dumped_at: DateTime<Utc>,
+    /// The height of the node's chain at the time
+    pub block_height: u32,
+    /// The transactions in the memory pool, from the oldest to the newest
+    pub transactions: Vec<MempoolDumpEntry>,
+}
+
+/// A transaction in a memory pool snapshot
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MempoolDumpEntry {
+    /// Transaction id
+    pub txid: String,
+    /// The hex-encoded transaction
+    pub transaction: String,
+    /// The time the transaction was received at
+    pub received: DateTime<Utc>,
+}
+
+/// Returned value for the `exportsnapshot` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LedgerSnapshotInfo {
+    /// The path of the file the snapshot was written to
+    pub path: String,
+    /// The height of the block the ledger was exported at
+    pub height: u32,
+    /// The hash of the block the ledger was exported at, which the importing nodes can check the snapshot against
+    pub block_hash: String,
+    /// The hex-encoded SHA-256 checksum of the snapshot's contents
+    pub checksum: String,
+}
+
+/// Returned value for the `loadmempool` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LoadedMempool {
+    /// The number of transactions added to the memory pool
+    pub added: usize,
+    /// The transactions that weren't added, along with the reasons why
+    pub rejected: Vec<RejectedTransaction>,
+}
+
+/// A transaction from a memory pool snapshot that wasn't added to the memory pool
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RejectedTransaction {
+    /// Transaction id, as recorded in the snapshot
+    pub txid: String,
+    /// The reason the transaction was rejected
+    pub reason: String,
+}
+
+/// The position of a confirmed transaction in the canon chain
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TransactionConfirmation {
+    /// The hash of the block including the transaction
+    pub block_hash: String,
+    /// The height of the block including the transaction
+    pub block_height: u32,
+    /// The number of blocks on top of the block including the transaction
+    pub confirmations: u32,
+}
+
+/// Returned value for the `gettransactionsbycommitment` rpc call, for each of the requested commitments
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RecordTransaction {
+    /// The record commitment
+    pub commitment: String,
+    /// The id of the canon transaction that created the record, if it's on-chain
+    pub txid: Option<String>,
+    /// The position of the transaction in the canon chain, unless its block was pruned
+    pub confirmation: Option<TransactionConfirmation>,
+}
+
+/// Returned value for the `isserialnumberspent` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SerialNumberStatus {
+    /// The record serial number
+    pub serial_number: String,
+    /// Whether the record was spent in the canon chain
+    pub spent: bool,
+    /// The id of the canon transaction that spent the record, if it's spent
+    pub txid: Option<String>,
+    /// The position of the transaction in the canon chain, unless its block was pruned
+    pub confirmation: Option<TransactionConfirmation>,
+}
+
+/// Returned value for the `getchaintips` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ChainTipInfo {
+    /// The hash of the block at the tip
+    pub hash: String,
+    /// The height of the tip; it's unknown for orphaned branches
+    pub height: Option<u32>,
+    /// The number of blocks in the branch that aren't part of the canon chain
+    pub branch_length: u32,
+    /// The status of the tip: `active`, `fork` or `orphan`
+    pub status: String,
+}
+
+/// Returned value for the `getchainanalytics` rpc call
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChainAnalytics {
+    /// The height of the oldest block the statistics cover
+    pub from_height: u32,
+    /// The height of the newest block the statistics cover, i.e. the tip of the canon chain
+    pub to_height: u32,
+    /// The number of blocks the statistics cover
+    pub block_count: usize,
+    /// The average number of seconds between two consecutive blocks; unknown with fewer than two blocks
+    pub average_block_interval_secs: Option<f64>,
+    /// The distribution of the sizes of the blocks, in bytes
+    pub block_size: BlockSizeDistribution,
+    /// The average block size as a fraction of the maximum block size
+    pub average_fullness: f64,
+    /// The average number of transactions per block, including the coinbase transactions
+    pub average_transactions: f64,
+    /// The largest number of transactions in a single block
+    pub max_transactions: usize,
+}
+
+/// The distribution of block sizes, in bytes
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BlockSizeDistribution {
+    pub average: f64,
+    pub min: usize,
+    pub median: usize,
+    pub p90: usize,
+    pub max: usize,
+}
+
+/// Returned value for the `getforkchoice` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ForkChoiceInfo {
+    /// Block hash
+    pub hash: String,
+    /// Flag indicating if the block is part of the canon chain
+    pub is_canon: bool,
+    /// The explanation of the block's status
+    pub reason: String,
+    /// The inputs and the outcome of the last fork choice caused by the block, if it's still known
+    pub decision: Option<ForkChoiceDecision>,
+}
+
+/// The inputs and the outcome of a fork choice between the canon chain and a side chain
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ForkChoiceDecision {
+    /// The position of the block among all the blocks received by the node since it started
+    pub received_order: u64,
+    /// The height of the last block shared by the canon chain and the side chain
+    pub shared_block_height: u32,
+    /// The height of the side chain including the block
+    pub side_chain_height: u32,
+    /// The height of the canon chain at the time of the decision
+    pub canon_height: u32,
+    /// The cumulative work of the side chain after the shared block
+    pub side_chain_work: String,
+    /// The cumulative work of the canon chain after the shared block
+    pub canon_chain_work: String,
+    /// The outcome of the decision; either `reorganized` or `stored`
+    pub outcome: String,
+}
+
+/// Returned value for the `getnetworkgraph` rpc call
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NetworkGraph {
+    /// The nodes discovered while crawling the network
+    pub vertices: Vec<Vertex>,
+    /// The connections between the discovered nodes
+    pub edges: Vec<Edge>,
+}
+
+/// Returned value for the `getnetworkgraphat` rpc call
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NetworkGraphSnapshot {
+    /// The time the snapshot of the network was taken at
+    pub taken_at: DateTime<Utc>,
+    /// The nodes and connections the crawler knew of at the time
+    #[serde(flatten)]
+    pub graph: NetworkGraph,
+}
+
+/// A node in the `NetworkGraph`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Vertex {
+    /// The address of the node
+    pub addr: SocketAddr,
+    /// Flag indicating if the node is a bootnode
+    pub is_bootnode: bool,
+    /// The alias the node has signed with its own key; it isn't vouched for by anyone
+    pub unverified_alias: Option<PeerAlias>,
+    /// The estimated number of seconds the node has been continuously reachable for
+    pub uptime_secs: u64,
+    /// The fraction of the recent handshakes with the node that were successful, if any were attempted
+    pub responsiveness: Option<f64>,
+    /// The category of the failure of the last attempt to crawl the node, if it failed
+    pub last_crawl_error: Option<String>,
+    /// The labels the crawler's operator attached to the node
+    pub labels: Vec<String>,
+    /// The note the crawler's operator attached to the node
+    pub note: Option<String>,
+}
+
+/// A connection between two nodes in the `NetworkGraph`
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Edge {
+    /// The address of one side of the connection
+    pub source: SocketAddr,
+    /// The address of the other side of the connection
+    pub target: SocketAddr,
+}
+
 /// Returned value for the `getpeerinfo` rpc call
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct PeerInfo {
     /// The peers connected to this node
     pub peers: Vec<SocketAddr>,
+    /// The number of connected peers that connected to this node
+    pub inbound_count: u32,
+    /// The number of connected peers this node connected to
+    pub outbound_count: u32,
+}
+
+/// Returned value for the `getpeerdetails` rpc call
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PeerDetails {
+    /// The address of the peer
+    pub address: SocketAddr,
+    /// Flag indicating if the peer is currently connected
+    pub is_connected: bool,
+    /// Flag indicating if the peer is a bootnode
+    pub is_bootnode: bool,
+    /// Flag indicating if the peer is currently banned
+    pub is_banned: bool,
+    /// The last known block height of the peer
+    pub block_height: u32,
+    /// The height of the earliest block the peer is able to serve
+    pub earliest_block_height: u32,
+    /// The last measured round-trip time to the peer in milliseconds
+    pub rtt_ms: u64,
+    /// The number of recent failures associated with the peer
+    pub failures: usize,
+    /// The number of messages received from the peer
+    pub num_messages_received: u64,
+    /// The timestamp of when the peer was first seen
+    pub first_seen: Option<DateTime<Utc>>,
+    /// The timestamp of when the peer was last seen
+    pub last_seen: Option<DateTime<Utc>>,
+    /// The timestamp of the last connection to the peer
+    pub last_connected: Option<DateTime<Utc>>,
+    /// The timestamp of the last disconnection from the peer
+    pub last_disconnected: Option<DateTime<Utc>>,
+    /// The number of times the node has connected to the peer
+    pub connected_count: u64,
+    /// The number of times the node has disconnected from the peer
+    pub disconnected_count: u64,
+    /// The alias the peer has signed with its own key; it isn't vouched for by anyone
+    pub unverified_alias: Option<PeerAlias>,
+    /// The number of transactions relayed by the peer that were later included in a block
+    pub relayed_transactions_accepted: u64,
+    /// The number of transactions relayed by the peer that were invalid or never included in a block
+    pub relayed_transactions_rejected: u64,
+    /// The share of the peer's settled relayed transactions that were included in a block
+    pub relay_acceptance_ratio: Option<f64>,
+    /// Flag indicating if the transactions relayed by the peer are still processed
+    pub has_relay_privileges: bool,
+    /// The number of `GetSync` requests received from the peer
+    pub sync_queries: u64,
+    /// The number of `GetSync` requests from the peer that were far behind the node's tip
+    pub deep_sync_queries: u64,
+    /// The number of `GetSync` requests from the peer that repeated its previous one
+    pub repeated_sync_queries: u64,
+    /// The number of `GetBlocks` and `GetSync` requests from the peer that exceeded its serving quota
+    pub throttled_sync_requests: u64,
+    /// The labels the node's operator attached to the peer
+    pub labels: Vec<String>,
+    /// The note the node's operator attached to the peer
+    pub note: Option<String>,
+}
+
+/// Returned value for the `listbanned` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BanInfo {
+    /// The banned IP or subnet
+    pub subnet: String,
+    /// The timestamp of when the ban expires; bans from the node's config don't expire
+    pub banned_until: Option<DateTime<Utc>>,
+}
+
+/// Returned value for the `getpeerscores` rpc call
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PeerScoreInfo {
+    /// The address of the peer
+    pub address: SocketAddr,
+    /// The current quality score of the peer
+    pub score: PeerScore,
+}
+
+/// Returned value for the `getpeerlatencies` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PeerLatencies {
+    /// The percentiles of the recent round-trip times of all the connected peers combined
+    pub network: Option<RttPercentiles>,
+    /// The latencies of the individual connected peers
+    pub peers: Vec<PeerLatencyInfo>,
+}
+
+/// The latency of a single peer, as returned by the `getpeerlatencies` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PeerLatencyInfo {
+    /// The address of the peer
+    pub address: SocketAddr,
+    /// The last measured round-trip time
+    pub rtt_ms: u64,
+    /// The number of recent round-trip times the percentiles are based on
+    pub samples: usize,
+    /// The percentiles of the recent round-trip times
+    pub percentiles: Option<RttPercentiles>,
+}
+
+/// Returned value for the `getpeerages` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PeerAges {
+    /// The number of known disconnected peers
+    pub disconnected: u32,
+    /// The number of disconnected peers whose connection history makes them not worth connecting to anymore
+    pub unroutable: u32,
+    /// The disconnected peers bucketed by how long ago they were last seen, from the most recent ones
+    pub buckets: Vec<PeerAgeBucketInfo>,
+}
+
+/// A single bucket of disconnected peers, as returned by the `getpeerages` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PeerAgeBucketInfo {
+    /// The period the peers were last seen within, e.g. `1d`, or `older` or `never`
+    pub last_seen_within: String,
+    /// The number of disconnected peers in the bucket
+    pub peers: u32,
+    /// The number of unroutable peers in the bucket
+    pub unroutable: u32,
+}
+
+/// Returned value for the `reloadconfig` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ReloadedConfig {
+    /// The minimum number of peers the node maintains a connection with
+    pub min_peers: u16,
+    /// The maximum number of peers the node maintains a connection with
+    pub max_peers: u16,
+    /// The bootnodes of the network
+    pub bootnodes: Vec<SocketAddr>,
+    /// The interval between each peer sync, in seconds
+    pub peer_sync_interval: u64,
+    /// The interval between each block sync, in seconds
+    pub block_sync_interval: u64,
+    /// The interval between each memory pool sync, in seconds
+    pub mempool_sync_interval: u64,
+}
+
+/// Returned value for the `getmininghistory` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MiningHistoryEntry {
+    /// The kind of the event: `template`, `submitted`, `accepted` or `rejected`
+    pub kind: String,
+    /// The height of the produced or submitted block
+    pub block_height: u32,
+    /// The hash of the submitted block; templates don't have one
+    pub block_hash: Option<String>,
+    /// The hash of the parent block
+    pub previous_block_hash: String,
+    /// The timestamp of the event
+    pub time: i64,
+    /// Flag indicating if the submitted block is currently in the canonical chain; accepted blocks can be
+    /// reorganized out of it later on
+    pub is_canon: Option<bool>,
 }
 
 /// Record payload data
@@ -0,0 +1,269 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+/// Tests for the gRPC service
+#[cfg(feature = "grpc")]
+mod grpc_tests {
+    use snarkos_consensus::{Consensus, MerkleTreeLedger};
+    use snarkos_network::Node;
+    use snarkos_rpc::{
+        grpc::{
+            proto::{self, node_api_server::NodeApi},
+            GrpcService,
+        },
+        RpcImpl,
+    };
+    use snarkos_storage::LedgerStorage;
+    use snarkos_testing::{
+        network::{test_config, ConsensusSetup, TestSetup},
+        sync::*,
+    };
+    use snarkvm_dpc::{testnet1::instantiated::Tx, Block, TransactionScheme};
+    use snarkvm_utilities::{bytes::FromBytes, to_bytes, ToBytes};
+
+    use std::{sync::Arc, time::Duration};
+    use tokio::time::timeout;
+    use tokio_stream::StreamExt;
+    use tonic::{Code, Request};
+
+    async fn initialize_test_service(
+        ledger: Arc<MerkleTreeLedger<LedgerStorage>>,
+    ) -> (GrpcService<LedgerStorage>, Arc<Consensus<LedgerStorage>>) {
+        let environment = test_config(TestSetup::default());
+        let mut node = Node::new(environment).await.unwrap();
+        let consensus_setup = ConsensusSetup::default();
+        let consensus = Arc::new(snarkos_testing::sync::create_test_consensus_from_ledger(ledger.clone()));
+
+        let node_consensus = snarkos_network::Sync::new(
+            consensus.clone(),
+            consensus_setup.is_miner,
+            Duration::from_secs(consensus_setup.block_sync_interval),
+            Duration::from_secs(consensus_setup.tx_sync_interval),
+        );
+        node.set_sync(node_consensus);
+
+        (GrpcService::new(RpcImpl::new(ledger, None, node)), consensus)
+    }
+
+    fn empty() -> Request<proto::Empty> {
+        Request::new(proto::Empty {})
+    }
+
+    fn genesis_hash() -> String {
+        hex::encode(GENESIS_BLOCK_HEADER_HASH.to_vec())
+    }
+
+    #[tokio::test]
+    async fn grpc_get_node_info() {
+        let (service, _) = initialize_test_service(Arc::new(FIXTURE_VK.ledger())).await;
+
+        let info = service.get_node_info(empty()).await.unwrap().into_inner();
+
+        assert!(!info.is_miner);
+        assert!(!info.is_syncing);
+        assert!(!info.is_shutting_down);
+        // there are no peers to compare the chain with
+        assert_eq!(info.network_height, 0);
+        assert!(!info.version.is_empty());
+    }
+
+    #[tokio::test]
+    async fn grpc_get_connection_count() {
+        let (service, _) = initialize_test_service(Arc::new(FIXTURE_VK.ledger())).await;
+
+        let count = service.get_connection_count(empty()).await.unwrap().into_inner();
+
+        assert_eq!(count.count, 0);
+    }
+
+    #[tokio::test]
+    async fn grpc_get_peer_info() {
+        let (service, _) = initialize_test_service(Arc::new(FIXTURE_VK.ledger())).await;
+
+        let info = service.get_peer_info(empty()).await.unwrap().into_inner();
+
+        assert!(info.peers.is_empty());
+        assert_eq!((info.inbound_count, info.outbound_count), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn grpc_get_block_count() {
+        let (service, _) = initialize_test_service(Arc::new(FIXTURE_VK.ledger())).await;
+
+        let count = service.get_block_count(empty()).await.unwrap().into_inner();
+
+        assert_eq!(count.count, 1);
+    }
+
+    #[tokio::test]
+    async fn grpc_get_best_block_hash() {
+        let (service, _) = initialize_test_service(Arc::new(FIXTURE_VK.ledger())).await;
+
+        let hash = service.get_best_block_hash(empty()).await.unwrap().into_inner();
+
+        assert_eq!(hash.hash, genesis_hash());
+    }
+
+    #[tokio::test]
+    async fn grpc_get_block_hash() {
+        let (service, _) = initialize_test_service(Arc::new(FIXTURE_VK.ledger())).await;
+
+        let hash = service
+            .get_block_hash(Request::new(proto::BlockHeight { height: 0 }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(hash.hash, genesis_hash());
+
+        // the errors are mapped to the matching status codes
+        let status = service
+            .get_block_hash(Request::new(proto::BlockHeight { height: 10 }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn grpc_get_block() {
+        let (service, _) = initialize_test_service(Arc::new(FIXTURE_VK.ledger())).await;
+        let genesis_block = genesis();
+
+        let block = service
+            .get_block(Request::new(proto::BlockHash { hash: genesis_hash() }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(block.hash, genesis_hash());
+        assert!(block.is_canon);
+        assert_eq!(block.height, 0);
+        assert_eq!(block.previous_block_hash, genesis_block.header.previous_block_hash.to_string());
+        assert_eq!(block.nonce, genesis_block.header.nonce);
+        assert_eq!(block.transactions.len(), genesis_block.transactions.0.len());
+
+        let status = service
+            .get_block(Request::new(proto::BlockHash { hash: "00".into() }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn grpc_get_chain_tips() {
+        let (service, _) = initialize_test_service(Arc::new(FIXTURE_VK.ledger())).await;
+
+        let tips = service.get_chain_tips(empty()).await.unwrap().into_inner().tips;
+
+        assert_eq!(tips, vec![proto::ChainTip {
+            hash: genesis_hash(),
+            has_height: true,
+            height: 0,
+            branch_length: 0,
+            status: "active".into(),
+        }]);
+    }
+
+    #[tokio::test]
+    async fn grpc_get_chain_analytics() {
+        let (service, _) = initialize_test_service(Arc::new(FIXTURE_VK.ledger())).await;
+
+        let analytics = service.get_chain_analytics(empty()).await.unwrap().into_inner();
+
+        // only the genesis block is sampled
+        assert_eq!((analytics.from_height, analytics.to_height, analytics.block_count), (0, 0, 1));
+        let block_size = analytics.block_size.unwrap();
+        assert_eq!(block_size.min, block_size.max);
+    }
+
+    #[tokio::test]
+    async fn grpc_get_raw_transaction() {
+        let (service, _) = initialize_test_service(Arc::new(FIXTURE_VK.ledger())).await;
+        let transaction = &genesis().transactions.0[0];
+        let txid = hex::encode(transaction.transaction_id().unwrap());
+
+        let raw = service
+            .get_raw_transaction(Request::new(proto::TransactionId { txid }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(raw.transaction, hex::encode(to_bytes![transaction].unwrap()));
+    }
+
+    #[tokio::test]
+    async fn grpc_get_transaction_status() {
+        let (service, _) = initialize_test_service(Arc::new(FIXTURE_VK.ledger())).await;
+        let transaction = &genesis().transactions.0[0];
+        let txid = hex::encode(transaction.transaction_id().unwrap());
+
+        let status = service
+            .get_transaction_status(Request::new(proto::TransactionId { txid: txid.clone() }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(status.txid, txid);
+        assert_eq!(status.status, "confirmed");
+        assert_eq!(status.block_hash, genesis_hash());
+        assert_eq!((status.block_height, status.confirmations), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn grpc_subscribe_blocks() {
+        let (service, consensus) = initialize_test_service(Arc::new(FIXTURE_VK.ledger())).await;
+        let mut blocks = service.subscribe_blocks(empty()).await.unwrap().into_inner();
+
+        // only the blocks committed after the subscription are streamed
+        let block_1 = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+        consensus.receive_block(&block_1).await.unwrap();
+
+        let event = timeout(Duration::from_secs(5), blocks.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(event, proto::BlockEvent {
+            hash: block_1.header.get_hash().to_string(),
+            height: 1,
+            time: block_1.header.time,
+        });
+    }
+
+    #[tokio::test]
+    async fn grpc_subscribe_reorgs() {
+        let (service, consensus) = initialize_test_service(Arc::new(FIXTURE_VK.ledger())).await;
+        let mut reorgs = service.subscribe_reorgs(empty()).await.unwrap().into_inner();
+
+        // switch to a longer side chain
+        let block_1_canon = Block::<Tx>::read(&ALTERNATIVE_BLOCK_1[..]).unwrap();
+        let block_1_side = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+        let block_2_side = Block::<Tx>::read(&BLOCK_2[..]).unwrap();
+        consensus.receive_block(&block_1_canon).await.unwrap();
+        consensus.receive_block(&block_1_side).await.unwrap();
+        consensus.receive_block(&block_2_side).await.unwrap();
+
+        let event = timeout(Duration::from_secs(5), reorgs.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.old_tip, block_1_canon.header.get_hash().to_string());
+        assert_eq!(event.old_height, 1);
+        assert_eq!(event.new_tip, block_2_side.header.get_hash().to_string());
+        assert_eq!(event.new_height, 2);
+        assert_eq!(event.shared_block_height, 0);
+    }
+}
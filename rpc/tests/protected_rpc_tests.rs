@@ -17,12 +17,13 @@
 /// Tests for protected RPC endpoints
 mod protected_rpc_tests {
     use snarkos_consensus::{Consensus, MerkleTreeLedger};
-    use snarkos_network::Node;
+    use snarkos_network::{read_peer_list, write_peer_list, JournalEvent, KnownPeer, Node, Payload, ReloadableConfig};
     use snarkos_rpc::*;
-    use snarkos_storage::LedgerStorage;
+    use snarkos_storage::{LedgerStorage, MiningEvent, MiningEventKind};
     use snarkos_testing::{
-        network::{test_config, ConsensusSetup, TestSetup},
+        network::{handshaken_node_and_peer, test_config, ConsensusSetup, TestSetup},
         sync::*,
+        wait_until,
     };
 
     use snarkvm_dpc::{
@@ -34,7 +35,9 @@ mod protected_rpc_tests {
         AccountAddress,
         AccountPrivateKey,
         AccountViewKey,
+        BlockHeaderHash,
         RecordScheme,
+        TransactionScheme,
     };
     use snarkvm_utilities::{
         bytes::{FromBytes, ToBytes},
@@ -47,6 +50,7 @@ mod protected_rpc_tests {
 
     const TEST_USERNAME: &str = "TEST_USERNAME";
     const TEST_PASSWORD: &str = "TEST_PASSWORD";
+    const TEST_ADMIN_SECRET: &str = "TEST_ADMIN_SECRET";
 
     fn invalid_authentication() -> Meta {
         let basic_auth_encoding = format!(
@@ -56,6 +60,7 @@ mod protected_rpc_tests {
 
         Meta {
             auth: Some(basic_auth_encoding),
+            body: None,
         }
     }
 
@@ -67,17 +72,28 @@ mod protected_rpc_tests {
 
         Meta {
             auth: Some(basic_auth_encoding),
+            body: None,
         }
     }
 
     async fn initialize_test_rpc(
         ledger: Arc<MerkleTreeLedger<LedgerStorage>>,
     ) -> (MetaIoHandler<Meta>, Arc<Consensus<LedgerStorage>>) {
-        let credentials = RpcCredentials {
-            username: TEST_USERNAME.to_string(),
-            password: TEST_PASSWORD.to_string(),
-        };
+        initialize_test_rpc_with_admin_tokens(ledger, vec![]).await
+    }
+
+    async fn initialize_test_rpc_with_admin_tokens(
+        ledger: Arc<MerkleTreeLedger<LedgerStorage>>,
+        admin_tokens: Vec<AdminToken>,
+    ) -> (MetaIoHandler<Meta>, Arc<Consensus<LedgerStorage>>) {
+        let (node, consensus) = initialize_test_node(ledger.clone()).await;
+
+        (initialize_test_io(ledger, admin_tokens, node), consensus)
+    }
 
+    async fn initialize_test_node(
+        ledger: Arc<MerkleTreeLedger<LedgerStorage>>,
+    ) -> (Node<LedgerStorage>, Arc<Consensus<LedgerStorage>>) {
         let environment = test_config(TestSetup::default());
         let mut node = Node::new(environment).await.unwrap();
         let consensus_setup = ConsensusSetup::default();
@@ -91,13 +107,37 @@ mod protected_rpc_tests {
         );
 
         node.set_sync(node_consensus);
+        node.set_config_source(|| {
+            Ok(ReloadableConfig {
+                min_peers: 2,
+                max_peers: 10,
+                bootnodes: vec!["127.0.0.1:4131".parse().unwrap()],
+                banned_subnets: vec!["10.0.0.0/8".parse().unwrap()],
+                peer_sync_interval: Duration::from_secs(5),
+                block_sync_interval: Duration::from_secs(3),
+                mempool_sync_interval: Duration::from_secs(4),
+            })
+        });
+
+        (node, consensus)
+    }
 
-        let rpc_impl = RpcImpl::new(ledger, Some(credentials), node);
+    fn initialize_test_io(
+        ledger: Arc<MerkleTreeLedger<LedgerStorage>>,
+        admin_tokens: Vec<AdminToken>,
+        node: Node<LedgerStorage>,
+    ) -> MetaIoHandler<Meta> {
+        let credentials = RpcCredentials {
+            username: TEST_USERNAME.to_string(),
+            password: TEST_PASSWORD.to_string(),
+        };
+
+        let rpc_impl = RpcImpl::with_admin_tokens(ledger, Some(credentials), admin_tokens, node);
         let mut io = jsonrpc_core::MetaIoHandler::default();
 
         rpc_impl.add_protected(&mut io);
 
-        (io, consensus)
+        io
     }
 
     #[tokio::test]
@@ -427,4 +467,480 @@ mod protected_rpc_tests {
         let _private_key = AccountPrivateKey::<Components>::from_str(&account.private_key).unwrap();
         let _address = AccountAddress::<Components>::from_str(&account.address).unwrap();
     }
+
+    #[tokio::test]
+    async fn test_rpc_manage_peers() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let meta = authentication();
+        let (rpc, _consensus) = initialize_test_rpc(storage).await;
+
+        let address = "127.0.0.1:4242";
+        let request = |method: &str, params: String| {
+            let request = format!(
+                "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"{}\", \"params\": {} }}",
+                method, params
+            );
+            let response = rpc.handle_request_sync(&request, meta.clone()).unwrap();
+            serde_json::from_str::<Value>(&response).unwrap()
+        };
+
+        // unknown peers can't be removed or inspected
+        let extracted = request("removepeer", format!("[\"{}\"]", address));
+        assert_eq!(extracted["result"], false);
+
+        let extracted = request("getpeerdetails", format!("[\"{}\"]", address));
+        assert_eq!(extracted["error"]["message"], "PEER_NOT_FOUND");
+        assert_eq!(extracted["error"]["data"]["address"], address.to_string());
+
+        let extracted = request("getpeerevents", format!("[\"{}\", 10]", address));
+        assert_eq!(extracted["error"]["message"], "PEER_NOT_FOUND");
+
+        // banned peers can't be added
+        let extracted = request("banpeer", format!("[\"{}\", 60]", address));
+        assert_eq!(extracted["result"], Value::Null);
+
+        let extracted = request("addpeer", format!("[\"{}\"]", address));
+        assert_eq!(extracted["error"]["message"], "NETWORK_ERROR");
+
+        // no peers are connected, so there's nothing to score
+        let extracted = request("getpeerscores", "[]".to_string());
+        assert_eq!(extracted["result"], Value::Array(vec![]));
+
+        // ...and no latencies to report
+        let extracted = request("getpeerlatencies", "[]".to_string());
+        assert_eq!(extracted["result"]["network"], Value::Null);
+        assert_eq!(extracted["result"]["peers"], Value::Array(vec![]));
+
+        // the disconnected peers are bucketed by how long ago they were last seen
+        let extracted = request("getpeerages", "[]".to_string());
+        assert_eq!(extracted["result"]["unroutable"], 0);
+        assert_eq!(extracted["result"]["buckets"].as_array().unwrap().len(), 6);
+        assert_eq!(extracted["result"]["buckets"][5]["last_seen_within"], "never");
+
+        // peers can be annotated with well-formed labels and a note
+        let extracted = request(
+            "annotatepeer",
+            format!("[\"{}\", [\"suspicious\"], \"sent a flood of pings\"]", address),
+        );
+        assert_eq!(extracted["result"], Value::Null);
+
+        let extracted = request("annotatepeer", format!("[\"{}\", [\"not a label\"]]", address));
+        assert!(extracted["error"].is_object());
+    }
+
+    #[tokio::test]
+    async fn test_rpc_import_export_peers() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let meta = authentication();
+        let (rpc, _consensus) = initialize_test_rpc(storage).await;
+
+        let request = |method: &str, path: &std::path::Path| {
+            let request = format!(
+                "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"{}\", \"params\": [{:?}] }}",
+                method, path
+            );
+            let response = rpc.handle_request_sync(&request, meta.clone()).unwrap();
+            serde_json::from_str::<Value>(&response).unwrap()
+        };
+
+        let known_peer = |address: &str| KnownPeer {
+            address: address.parse().unwrap(),
+            tried: true,
+            failed_attempts: 0,
+            last_attempt: None,
+            last_success: Some(chrono::Utc::now()),
+            added: chrono::Utc::now(),
+        };
+        let peers = vec![known_peer("127.0.0.1:4242"), known_peer("127.0.0.2:4242")];
+
+        let import_path = std::env::temp_dir().join(format!("import_peers-{}.json", rand::random::<u64>()));
+        let export_path = std::env::temp_dir().join(format!("export_peers-{}.json", rand::random::<u64>()));
+        write_peer_list(&import_path, &peers).unwrap();
+
+        let extracted = request("importpeers", &import_path);
+        assert_eq!(extracted["result"], 2);
+        // the peers are only imported once
+        let extracted = request("importpeers", &import_path);
+        assert_eq!(extracted["result"], 0);
+
+        let extracted = request("exportpeers", &export_path);
+        assert_eq!(extracted["result"], 2);
+        let exported = read_peer_list(&export_path).unwrap();
+        let addresses = exported.iter().map(|peer| peer.address).collect::<Vec<_>>();
+        assert_eq!(addresses, vec![peers[0].address, peers[1].address]);
+        // imported peers need to be connected to again before they're trusted
+        assert!(exported.iter().all(|peer| !peer.tried));
+
+        // files that aren't peer lists are rejected
+        std::fs::write(&import_path, b"[1, 2, 3]").unwrap();
+        let extracted = request("importpeers", &import_path);
+        assert!(extracted["error"].is_object());
+
+        std::fs::remove_file(import_path).ok();
+        std::fs::remove_file(export_path).ok();
+    }
+
+    // multithreaded necessary due to use of non-async jsonrpc & internal use of async
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_rpc_get_peer_events() {
+        let (node, mut peer) = handshaken_node_and_peer(TestSetup::default()).await;
+        wait_until!(1, node.peer_book.get_active_peer_count() == 1);
+        let address = node.peer_book.connected_peers()[0];
+
+        let rpc = initialize_test_io(Arc::new(FIXTURE_VK.ledger()), vec![], node.clone());
+        let meta = authentication();
+        let request = |limit: usize| {
+            let request = format!(
+                "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"getpeerevents\", \"params\": [\"{}\", {}] }}",
+                address, limit
+            );
+            let response = rpc.handle_request_sync(&request, meta.clone()).unwrap();
+            serde_json::from_str::<Value>(&response).unwrap()
+        };
+
+        // a well-behaved peer has no events
+        let extracted = request(10);
+        assert_eq!(extracted["result"], Value::Array(vec![]));
+
+        // a sync response that wasn't requested is recorded
+        peer.write_message(&Payload::Sync(vec![BlockHeaderHash::new(vec![1u8; 32])]))
+            .await;
+        wait_until!(1, request(10)["result"].as_array().map(Vec::len) == Some(1));
+
+        let extracted = request(10);
+        assert_eq!(extracted["result"][0]["kind"], "unsolicited_message");
+        assert_eq!(extracted["result"][0]["details"], "an unexpected 'sync' message");
+
+        // the limit caps the number of returned events
+        let extracted = request(0);
+        assert_eq!(extracted["result"], Value::Array(vec![]));
+    }
+
+    // multithreaded necessary due to use of non-async jsonrpc & internal use of async
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_rpc_dump_load_mempool() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let meta = authentication();
+        let (rpc, _consensus) = initialize_test_rpc(storage).await;
+
+        let request = |method: &str, path: &std::path::Path| {
+            let request = format!(
+                "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"{}\", \"params\": [{:?}] }}",
+                method, path
+            );
+            let response = rpc.handle_request_sync(&request, meta.clone()).unwrap();
+            serde_json::from_str::<Value>(&response).unwrap()
+        };
+
+        let transaction = Tx::read(&TRANSACTION_1[..]).unwrap();
+        let txid = hex::encode(transaction.transaction_id().unwrap());
+        let received = chrono::Utc::now() - chrono::Duration::minutes(5);
+        let dumped = |txid: &str| MempoolDumpEntry {
+            txid: txid.into(),
+            transaction: hex::encode(&TRANSACTION_1[..]),
+            received,
+        };
+        let dump = MempoolDump {
+            dumped_at: chrono::Utc::now(),
+            block_height: 0,
+            // the second entry doesn't match its id
+            transactions: vec![dumped(&txid), dumped(&"00".repeat(32))],
+        };
+
+        let load_path = std::env::temp_dir().join(format!("load_mempool-{}.json", rand::random::<u64>()));
+        let dump_path = std::env::temp_dir().join(format!("dump_mempool-{}.json", rand::random::<u64>()));
+        std::fs::write(&load_path, serde_json::to_vec(&dump).unwrap()).unwrap();
+
+        let extracted = request("loadmempool", &load_path);
+        let loaded: LoadedMempool = serde_json::from_value(extracted["result"].clone()).unwrap();
+        assert_eq!(loaded.added, 1);
+        assert_eq!(loaded.rejected.len(), 1);
+        assert_eq!(loaded.rejected[0].txid, "00".repeat(32));
+
+        // the transactions are only added once
+        let extracted = request("loadmempool", &load_path);
+        let loaded: LoadedMempool = serde_json::from_value(extracted["result"].clone()).unwrap();
+        assert_eq!(loaded.added, 0);
+        assert_eq!(loaded.rejected.len(), 2);
+
+        let extracted = request("dumpmempool", &dump_path);
+        assert_eq!(extracted["result"], 1);
+        let redumped: MempoolDump = serde_json::from_slice(&std::fs::read(&dump_path).unwrap()).unwrap();
+        assert_eq!(redumped.transactions, vec![dumped(&txid)]);
+
+        // files that aren't memory pool dumps are rejected
+        std::fs::write(&load_path, b"[1, 2, 3]").unwrap();
+        let extracted = request("loadmempool", &load_path);
+        assert!(extracted["error"].is_object());
+
+        std::fs::remove_file(load_path).ok();
+        std::fs::remove_file(dump_path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_rpc_manage_bans() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let meta = authentication();
+        let (rpc, _consensus) = initialize_test_rpc(storage).await;
+
+        let request = |method: &str, params: &str| {
+            let request = format!(
+                "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"{}\", \"params\": {} }}",
+                method, params
+            );
+            let response = rpc.handle_request_sync(&request, meta.clone()).unwrap();
+            serde_json::from_str::<Value>(&response).unwrap()
+        };
+
+        let extracted = request("bansubnet", "[\"127.0.0.0/8\", 60]");
+        assert_eq!(extracted["result"], Value::Null);
+
+        // invalid subnets are rejected
+        let extracted = request("bansubnet", "[\"127.0.0.0/33\", 60]");
+        assert!(extracted["error"].is_object());
+
+        // the addresses in a banned subnet can't be added
+        let extracted = request("addpeer", "[\"127.0.0.1:4242\"]");
+        assert!(extracted["error"].is_object());
+
+        let extracted = request("listbanned", "[]");
+        let bans: Vec<BanInfo> = serde_json::from_value(extracted["result"].clone()).unwrap();
+        assert_eq!(bans.len(), 1);
+        assert_eq!(bans[0].subnet, "127.0.0.0/8");
+        assert!(bans[0].banned_until.is_some());
+
+        let extracted = request("unbansubnet", "[\"127.0.0.0/8\"]");
+        assert_eq!(extracted["result"], true);
+        let extracted = request("unbansubnet", "[\"127.0.0.0/8\"]");
+        assert_eq!(extracted["result"], false);
+
+        // the bans from the config are listed once it's loaded, and they don't expire
+        request("reloadconfig", "[]");
+        let extracted = request("listbanned", "[]");
+        let bans: Vec<BanInfo> = serde_json::from_value(extracted["result"].clone()).unwrap();
+        assert_eq!(
+            bans,
+            vec![BanInfo {
+                subnet: "10.0.0.0/8".into(),
+                banned_until: None,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rpc_trigger_sync() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let meta = authentication();
+        let (rpc, _consensus) = initialize_test_rpc(storage).await;
+
+        let request = |params: &str| {
+            let request = format!(
+                "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"triggersync\", \"params\": {} }}",
+                params
+            );
+            let response = rpc.handle_request_sync(&request, meta.clone()).unwrap();
+            serde_json::from_str::<Value>(&response).unwrap()
+        };
+
+        // only connected peers can be synced with
+        let extracted = request("[\"127.0.0.1:4242\"]");
+        assert!(extracted["error"].is_object());
+
+        let extracted = request("[]");
+        assert_eq!(extracted["result"], true);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_mining_controls() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let meta = authentication();
+        let (rpc, _consensus) = initialize_test_rpc(storage).await;
+
+        let request = |method: &str, params: &str| {
+            let request = format!(
+                "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"{}\", \"params\": {} }}",
+                method, params
+            );
+            let response = rpc.handle_request_sync(&request, meta.clone()).unwrap();
+            serde_json::from_str::<Value>(&response).unwrap()
+        };
+
+        // at least one mining thread is required
+        let extracted = request("startmining", "[0]");
+        assert!(extracted["error"].is_object());
+
+        // the test node has no miner address, so it can't mine
+        let extracted = request("startmining", "[2]");
+        assert!(extracted["error"].is_object());
+
+        let extracted = request("stopmining", "[]");
+        assert_eq!(extracted["result"], false);
+    }
+
+    // multithreaded necessary due to use of non-async jsonrpc & internal use of async
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_rpc_mining_controls_with_miner_address() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let meta = authentication();
+        let (node, _consensus) = initialize_test_node(storage.clone()).await;
+        node.enable_mining(FIXTURE_VK.test_accounts[0].address.clone());
+        let rpc = initialize_test_io(storage, vec![], node.clone());
+
+        let request = |method: &str, params: &str| {
+            let request = format!(
+                "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"{}\", \"params\": {} }}",
+                method, params
+            );
+            let response = rpc.handle_request_sync(&request, meta.clone()).unwrap();
+            serde_json::from_str::<Value>(&response).unwrap()
+        };
+
+        let extracted = request("startmining", "[2]");
+        assert_eq!(extracted["result"], true);
+        let mining = node.mining().unwrap();
+        assert!(mining.is_mining());
+        assert_eq!(mining.num_threads(), 2);
+        assert_eq!(node.stats_snapshot().misc.mining_threads, 2);
+
+        let extracted = request("stopmining", "[]");
+        assert_eq!(extracted["result"], true);
+        assert!(!mining.is_mining());
+        assert_eq!(node.stats_snapshot().misc.mining_threads, 0);
+
+        // the node can mine again once stopped
+        let extracted = request("startmining", "[]");
+        assert_eq!(extracted["result"], true);
+        assert_eq!(mining.num_threads(), 1);
+
+        let extracted = request("stopmining", "[]");
+        assert_eq!(extracted["result"], true);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_admin_tokens() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let admin_tokens = vec![AdminToken {
+            name: "monitoring".into(),
+            secret: TEST_ADMIN_SECRET.into(),
+            methods: vec!["listbanned".into()],
+        }];
+        let (rpc, _consensus) = initialize_test_rpc_with_admin_tokens(storage, admin_tokens).await;
+
+        let request = |method: &str, meta: Meta| {
+            let request = format!(
+                "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"{}\", \"params\": [] }}",
+                method
+            );
+            let response = rpc.handle_request_sync(&request, meta).unwrap();
+            serde_json::from_str::<Value>(&response).unwrap()
+        };
+        let bearer = Meta {
+            auth: Some(format!("Bearer {}", TEST_ADMIN_SECRET)),
+            body: None,
+        };
+
+        // the credentials no longer grant access to the admin methods, only to the other private ones
+        let extracted = request("listbanned", authentication());
+        assert_eq!(extracted["error"]["message"], "Authentication Error");
+        let extracted = request("getrecordcommitments", authentication());
+        assert!(extracted["result"].is_array());
+
+        let extracted = request("listbanned", bearer.clone());
+        assert!(extracted["result"].is_array());
+
+        // the token may only call the methods it was granted
+        let extracted = request("triggersync", bearer);
+        assert_eq!(extracted["error"]["message"], "PERMISSION_DENIED");
+        assert_eq!(extracted["error"]["data"]["method"], "triggersync");
+    }
+
+    #[tokio::test]
+    async fn test_rpc_reload_config() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let meta = authentication();
+        let (rpc, _consensus) = initialize_test_rpc(storage).await;
+
+        let request = "{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"reloadconfig\" }";
+        let response = rpc.handle_request_sync(request, meta).unwrap();
+        let extracted: Value = serde_json::from_str(&response).unwrap();
+
+        let reloaded: ReloadedConfig = serde_json::from_value(extracted["result"].clone()).unwrap();
+        assert_eq!(reloaded, ReloadedConfig {
+            min_peers: 2,
+            max_peers: 10,
+            bootnodes: vec!["127.0.0.1:4131".parse().unwrap()],
+            peer_sync_interval: 5,
+            block_sync_interval: 3,
+            mempool_sync_interval: 4,
+        });
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_mining_history() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let meta = authentication();
+        let (rpc, consensus) = initialize_test_rpc(storage).await;
+
+        let genesis_hash = consensus.ledger.get_block_hash(0).unwrap().0;
+        let event = |kind, block_height, block_hash| MiningEvent {
+            kind,
+            block_height,
+            block_hash,
+            previous_block_hash: [0u8; 32],
+            timestamp: 1_600_000_000 + block_height as i64,
+        };
+        let events = vec![
+            event(MiningEventKind::Accepted, 0, Some(genesis_hash)),
+            event(MiningEventKind::Template, 1, None),
+            event(MiningEventKind::Rejected, 2, Some([2u8; 32])),
+        ];
+        for event in &events {
+            consensus.ledger.store_mining_event(event).unwrap();
+        }
+
+        let request = "{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"getmininghistory\", \"params\": [0, 1] }";
+        let response = rpc.handle_request_sync(request, meta).unwrap();
+        let extracted: Value = serde_json::from_str(&response).unwrap();
+
+        let history: Vec<MiningHistoryEntry> = serde_json::from_value(extracted["result"].clone()).unwrap();
+        assert_eq!(history, vec![
+            MiningHistoryEntry {
+                kind: "accepted".to_string(),
+                block_height: 0,
+                block_hash: Some(hex::encode(genesis_hash)),
+                previous_block_hash: hex::encode([0u8; 32]),
+                time: 1_600_000_000,
+                is_canon: Some(true),
+            },
+            MiningHistoryEntry {
+                kind: "template".to_string(),
+                block_height: 1,
+                block_hash: None,
+                previous_block_hash: hex::encode([0u8; 32]),
+                time: 1_600_000_001,
+                is_canon: None,
+            },
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_journal() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let meta = authentication();
+        let (rpc, _consensus) = initialize_test_rpc(storage).await;
+
+        let request = "{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"getjournal\", \"params\": [0] }";
+        let response = rpc.handle_request_sync(request, meta.clone()).unwrap();
+        let extracted: Value = serde_json::from_str(&response).unwrap();
+
+        // the node's services weren't started, so nothing was journaled yet
+        let events: Vec<JournalEvent> = serde_json::from_value(extracted["result"].clone()).unwrap();
+        assert!(events.is_empty());
+
+        let request = "{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"getjournal\", \"params\": [] }";
+        let response = rpc.handle_request_sync(request, meta).unwrap();
+        let extracted: Value = serde_json::from_str(&response).unwrap();
+
+        assert!(extracted["error"].is_object());
+    }
 }
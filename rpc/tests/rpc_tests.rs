@@ -17,9 +17,9 @@
 /// Tests for public RPC endpoints
 mod rpc_tests {
     use snarkos_consensus::{get_block_reward, MerkleTreeLedger};
-    use snarkos_network::Node;
-    use snarkos_rpc::*;
-    use snarkos_storage::LedgerStorage;
+    use snarkos_network::{Node, SyncState, SyncStatus};
+    use snarkos_rpc::{error::RpcErrorCode, *};
+    use snarkos_storage::{LedgerStorage, MiningEventKind};
     use snarkos_testing::{
         network::{test_config, ConsensusSetup, TestSetup},
         sync::*,
@@ -150,6 +150,74 @@ mod rpc_tests {
         assert_eq!(genesis_block.header.nonce, block_response["nonce"]);
     }
 
+    #[tokio::test]
+    async fn test_rpc_get_block_verbosity() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let rpc = initialize_test_rpc(storage).await;
+
+        let genesis_block = genesis();
+        let hash = hex::encode(GENESIS_BLOCK_HEADER_HASH.to_vec());
+
+        let raw_block: Value = serde_json::from_str(&rpc.request("getblock", &(&hash, 0))).unwrap();
+        assert_eq!(hex::encode(genesis_block.serialize().unwrap()), raw_block);
+
+        let block_info: Value = serde_json::from_str(&rpc.request("getblock", &(&hash, 1))).unwrap();
+        assert_eq!(hash, block_info["hash"]);
+        assert!(block_info.get("transaction_info").is_none());
+
+        let verbose_block: Value = serde_json::from_str(&rpc.request("getblock", &(&hash, 2))).unwrap();
+        assert_eq!(hash, verbose_block["hash"]);
+        let transaction_info = verbose_block["transaction_info"].as_array().unwrap();
+        assert_eq!(transaction_info.len(), genesis_block.transactions.len());
+        for (transaction, info) in genesis_block.transactions.iter().zip(transaction_info) {
+            verify_transaction_info(to_bytes![transaction].unwrap(), info.clone());
+        }
+
+        let error: Value = serde_json::from_str(&rpc.request("getblock", &(&hash, 3))).unwrap();
+        assert_eq!(error["code"], RpcErrorCode::InvalidParams.code());
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_fork_choice() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let rpc = initialize_test_rpc(storage).await;
+
+        let response = rpc.request("getforkchoice", &[hex::encode(GENESIS_BLOCK_HEADER_HASH.to_vec())]);
+
+        let fork_choice: Value = serde_json::from_str(&response).unwrap();
+
+        assert_eq!(hex::encode(GENESIS_BLOCK_HEADER_HASH.to_vec()), fork_choice["hash"]);
+        assert_eq!(fork_choice["is_canon"], true);
+        assert!(fork_choice["decision"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_rpc_error_codes() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let rpc = initialize_test_rpc(storage).await;
+
+        // Unknown blocks and transactions are reported with their own codes, along with their subjects.
+        let unknown_hash = hex::encode([7u8; 32]);
+        let error: Value = serde_json::from_str(&rpc.request("getblock", &[&unknown_hash])).unwrap();
+        assert_eq!(error["code"], RpcErrorCode::BlockNotFound.code());
+        assert_eq!(error["message"], "BLOCK_NOT_FOUND");
+        assert_eq!(error["data"]["block"], unknown_hash);
+
+        let error: Value = serde_json::from_str(&rpc.request("getblockhash", &[1000u32])).unwrap();
+        assert_eq!(error["message"], "BLOCK_NOT_FOUND");
+
+        let error: Value = serde_json::from_str(&rpc.request("getrawtransaction", &[&unknown_hash])).unwrap();
+        assert_eq!(error["code"], RpcErrorCode::TxNotFound.code());
+        assert_eq!(error["data"]["error"], "TX_NOT_FOUND");
+
+        // Malformed parameters are invalid, rather than unknown.
+        let error: Value = serde_json::from_str(&rpc.request("getblock", &["not hex"])).unwrap();
+        assert_eq!(error["code"], RpcErrorCode::InvalidParams.code());
+
+        let error: Value = serde_json::from_str(&rpc.request("sendtransaction", &["00"])).unwrap();
+        assert_eq!(error["message"], "TX_INVALID");
+    }
+
     #[tokio::test]
     async fn test_rpc_get_block_count() {
         let storage = Arc::new(FIXTURE_VK.ledger());
@@ -177,6 +245,42 @@ mod rpc_tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_rpc_get_chain_tips() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let rpc = initialize_test_rpc(storage).await;
+
+        let method = "getchaintips".to_string();
+
+        let result = make_request_no_params(&rpc, method);
+
+        let tips: Vec<ChainTipInfo> = serde_json::from_value(result).unwrap();
+        assert_eq!(tips[0], ChainTipInfo {
+            hash: hex::encode(GENESIS_BLOCK_HEADER_HASH.to_vec()),
+            height: Some(0),
+            branch_length: 0,
+            status: "active".to_string(),
+        });
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_chain_analytics() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let rpc = initialize_test_rpc(storage).await;
+
+        let method = "getchainanalytics".to_string();
+
+        let result = make_request_no_params(&rpc, method);
+
+        // only the genesis block is sampled
+        let analytics: ChainAnalytics = serde_json::from_value(result).unwrap();
+        assert_eq!((analytics.from_height, analytics.to_height, analytics.block_count), (0, 0, 1));
+        assert_eq!(analytics.average_block_interval_secs, None);
+        assert_eq!(analytics.block_size.min, analytics.block_size.max);
+        assert!(analytics.average_fullness > 0.0);
+        assert_eq!(analytics.average_transactions, analytics.max_transactions as f64);
+    }
+
     #[tokio::test]
     async fn test_rpc_get_block_hash() {
         let storage = Arc::new(FIXTURE_VK.ledger());
@@ -221,6 +325,86 @@ mod rpc_tests {
         verify_transaction_info(to_bytes![transaction].unwrap(), transaction_info);
     }
 
+    // multithreaded necessary due to use of non-async jsonrpc & internal use of async
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_rpc_get_transaction_status() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let rpc = initialize_test_rpc(storage).await;
+
+        // A transaction included in the genesis block is confirmed.
+        let genesis_block = genesis();
+        let transaction = &genesis_block.transactions.0[0];
+        let response = rpc.request("gettransactionstatus", &[hex::encode(
+            transaction.transaction_id().unwrap(),
+        )]);
+        let status: TransactionStatus = serde_json::from_str(&response).unwrap();
+
+        assert_eq!(status.status, "confirmed");
+        assert!(status.mempool_entry.is_none());
+        let confirmation = status.confirmation.unwrap();
+        assert_eq!(confirmation.block_hash, hex::encode(genesis_block.header.get_hash().0));
+        assert_eq!(confirmation.block_height, 0);
+        assert_eq!(confirmation.confirmations, 0);
+
+        // A transaction is unknown until it's sent to the node.
+        let transaction = Tx::read(&TRANSACTION_1[..]).unwrap();
+        let transaction_id = hex::encode(transaction.transaction_id().unwrap());
+        let response = rpc.request("gettransactionstatus", &[&transaction_id]);
+        let status: TransactionStatus = serde_json::from_str(&response).unwrap();
+
+        assert_eq!(status.status, "unknown");
+        assert!(status.mempool_entry.is_none());
+        assert!(status.confirmation.is_none());
+
+        rpc.request("sendtransaction", &[hex::encode(TRANSACTION_1.to_vec())]);
+        let response = rpc.request("gettransactionstatus", &[&transaction_id]);
+        let status: TransactionStatus = serde_json::from_str(&response).unwrap();
+
+        assert_eq!(status.status, "mempool");
+        assert_eq!(status.mempool_entry.unwrap().size, TRANSACTION_1.len());
+        assert!(status.confirmation.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_rpc_record_lookups() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let rpc = initialize_test_rpc(storage).await;
+
+        let genesis_block = genesis();
+        let transaction = &genesis_block.transactions.0[0];
+        let transaction_id = hex::encode(transaction.transaction_id().unwrap());
+        let commitment = hex::encode(to_bytes![transaction.new_commitments()[0]].unwrap());
+        let serial_number = hex::encode(to_bytes![transaction.old_serial_numbers()[0]].unwrap());
+
+        // The records created in the genesis block are found, and unknown commitments aren't.
+        let unknown_commitment = hex::encode([0u8; 32]);
+        let response = rpc.request("gettransactionsbycommitment", &[vec![&commitment, &unknown_commitment]]);
+        let record_transactions: Vec<RecordTransaction> = serde_json::from_str(&response).unwrap();
+
+        assert_eq!(record_transactions.len(), 2);
+        assert_eq!(record_transactions[0].commitment, commitment);
+        assert_eq!(record_transactions[0].txid.as_ref(), Some(&transaction_id));
+        let confirmation = record_transactions[0].confirmation.as_ref().unwrap();
+        assert_eq!(confirmation.block_hash, hex::encode(genesis_block.header.get_hash().0));
+        assert_eq!(confirmation.block_height, 0);
+        assert!(record_transactions[1].txid.is_none());
+        assert!(record_transactions[1].confirmation.is_none());
+
+        // The records spent in the genesis block are reported as spent.
+        let response = rpc.request("isserialnumberspent", &[&serial_number]);
+        let status: SerialNumberStatus = serde_json::from_str(&response).unwrap();
+
+        assert!(status.spent);
+        assert_eq!(status.txid, Some(transaction_id));
+        assert_eq!(status.confirmation.unwrap().block_height, 0);
+
+        let response = rpc.request("isserialnumberspent", &[hex::encode([0u8; 32])]);
+        let status: SerialNumberStatus = serde_json::from_str(&response).unwrap();
+
+        assert!(!status.spent);
+        assert!(status.txid.is_none());
+    }
+
     #[tokio::test]
     async fn test_rpc_decode_raw_transaction() {
         let storage = Arc::new(FIXTURE_VK.ledger());
@@ -284,6 +468,8 @@ mod rpc_tests {
         let expected_peers: Vec<SocketAddr> = vec![];
 
         assert_eq!(peer_info.peers, expected_peers);
+        assert_eq!(peer_info.inbound_count, 0);
+        assert_eq!(peer_info.outbound_count, 0);
     }
 
     #[tokio::test]
@@ -299,6 +485,75 @@ mod rpc_tests {
 
         assert_eq!(peer_info.is_miner, false);
         assert_eq!(peer_info.is_syncing, false);
+        // there are no peers to compare the chain with
+        assert_eq!(peer_info.is_synced, false);
+        assert_eq!(peer_info.network_height, None);
+        assert_eq!(peer_info.is_shutting_down, false);
+        // the clock isn't adjusted without enough peers
+        assert_eq!(peer_info.time_offset, 0);
+        assert_eq!(peer_info.is_clock_skewed, false);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_sync_status() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let rpc = initialize_test_rpc(storage).await;
+
+        let method = "getsyncstatus".to_string();
+
+        let result = make_request_no_params(&rpc, method);
+
+        assert_eq!(result["state"], "idle");
+        assert!(result["last_attempt"].is_null());
+
+        let sync_status: SyncStatus = serde_json::from_value(result).unwrap();
+
+        assert_eq!(sync_status.state, SyncState::Idle);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_node_stats_history() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let environment = test_config(TestSetup::default());
+        let node = Node::new(environment).await.unwrap();
+
+        node.stats_history.push(node.stats_snapshot());
+        node.stats_history.push(node.stats_snapshot());
+
+        let rpc = Rpc::new(RpcImpl::new(storage, None, node).to_delegate());
+
+        let all: Value = serde_json::from_str(&rpc.request("getnodestatshistory", &[60u64, 0])).unwrap();
+        assert_eq!(all.as_array().unwrap().len(), 2);
+
+        let spaced: Value = serde_json::from_str(&rpc.request("getnodestatshistory", &[60u64, 60])).unwrap();
+        assert_eq!(spaced.as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_memory_pool() {
+        let storage = Arc::new(FIXTURE_VK.ledger());
+        let rpc = initialize_test_rpc(storage).await;
+
+        let transaction = Tx::read(&TRANSACTION_1[..]).unwrap();
+        let fee = transaction.value_balance().0;
+        rpc.request("sendtransaction", &[hex::encode(TRANSACTION_1.to_vec())]);
+
+        let page: MemoryPoolPage = serde_json::from_str(&rpc.request("getmemorypool", &[0i64, 10])).unwrap();
+        assert_eq!(page.total, 1);
+        let summary = &page.transactions[0];
+        assert_eq!(summary.txid, hex::encode(transaction.transaction_id().unwrap()));
+        assert_eq!(summary.size, TRANSACTION_1.len());
+        assert_eq!(summary.fee, fee);
+
+        // The offset skips past the only transaction.
+        let page: MemoryPoolPage = serde_json::from_str(&rpc.request("getmemorypool", &[1i64, 10])).unwrap();
+        assert_eq!(page.total, 1);
+        assert!(page.transactions.is_empty());
+
+        // The transaction doesn't offer a high enough fee.
+        let page: MemoryPoolPage = serde_json::from_str(&rpc.request("getmemorypool", &[0, 10, fee + 1])).unwrap();
+        assert_eq!(page.total, 0);
+        assert!(page.transactions.is_empty());
     }
 
     #[tokio::test]
@@ -307,7 +562,7 @@ mod rpc_tests {
         let curr_height = storage.get_current_block_height();
         let latest_block_hash = hex::encode(storage.get_latest_block().unwrap().header.get_hash().0);
 
-        let rpc = initialize_test_rpc(storage).await;
+        let rpc = initialize_test_rpc(storage.clone()).await;
 
         let method = "getblocktemplate".to_string();
 
@@ -324,5 +579,20 @@ mod rpc_tests {
         assert_eq!(template.block_height, new_height);
         assert_eq!(template.transactions, expected_transactions);
         assert!(template.coinbase_value >= block_reward.0 as u64);
+
+        // The template is recorded in the mining history.
+        let history = storage.get_mining_history(new_height, new_height).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].kind, MiningEventKind::Template);
+        assert_eq!(hex::encode(history[0].previous_block_hash), latest_block_hash);
+
+        // The long-poll id identifies the chain tip the template builds on.
+        assert!(template.longpoll_id.starts_with(&format!("{}:", latest_block_hash)));
+
+        // An outdated long-poll id results in an immediate response.
+        let stale_id = format!("{}:{:016x}", hex::encode([0u8; 32]), 0);
+        let template: BlockTemplate = serde_json::from_str(&rpc.request("getblocktemplate", &[stale_id])).unwrap();
+        assert_eq!(template.previous_block_hash, latest_block_hash);
+        assert_eq!(template.block_height, new_height);
     }
 }
@@ -18,13 +18,42 @@ use crate::{
     cli::CLI,
     errors::CliError,
     parameters::{flag, option, subcommand, types::*},
+    peers::PeersCLI,
+    snapshot::SnapshotCLI,
+    units::{ByteSize, HumanDuration},
     update::UpdateCLI,
 };
 
+use snarkos_consensus::ConsensusParameters;
+use snarkos_network::{
+    BanScope,
+    EventCategory,
+    NetworkError,
+    NetworkId,
+    PeerEvictionPolicy,
+    PeerScoreWeights,
+    PortPolicy,
+    RelayPolicy,
+    Subnet,
+    SyncResponseShaping,
+    SyncServingQuota,
+    JOURNAL_MEMORY_CAPACITY,
+    JOURNAL_STORAGE_RETENTION_SECS,
+    MAX_BLOCK_SYNC_COUNT,
+    MIN_PRUNE_DEPTH,
+};
+use snarkos_rpc::AdminToken;
+use snarkvm_dpc::Network;
+use snarkvm_posw::PoswMarlin;
+
 use clap::ArgMatches;
 use dirs::home_dir;
 use serde::{Deserialize, Serialize};
-use std::{fs, path::PathBuf};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 /// Bootnodes maintained by Aleo.
 /// A node should try and connect to these first after coming online.
@@ -43,56 +72,302 @@ pub const TESTNET_BOOTNODES: &[&str] = &[
     "178.128.18.3:4131",
 ];
 
-/// Represents all configuration options for a node.
+/// Returns the default bootnodes of the given network; only the networks maintained by Aleo have any.
+fn default_bootnodes(network: NetworkId) -> Vec<String> {
+    let bootnodes = match network {
+        NetworkId::Mainnet => MAINNET_BOOTNODES,
+        NetworkId::Testnet1 => TESTNET_BOOTNODES,
+        NetworkId::Devnet | NetworkId::Custom(_) => &[],
+    };
+
+    bootnodes.iter().map(|node| (*node).to_string()).collect()
+}
+
+/// Returns the name of the default storage of the given network; it's named after its numeric id, as it was before
+/// the networks had names, so that the existing storage of the nodes on networks other than mainnet is still found.
+fn default_db(network: NetworkId) -> String {
+    match network {
+        NetworkId::Mainnet => "snarkos_mainnet".into(),
+        network => format!("snarkos_testnet{}", network.id()),
+    }
+}
+
+/// The range of the peer and block sync intervals.
+const MIN_SYNC_INTERVAL: HumanDuration = HumanDuration::from_secs(2);
+const MAX_SYNC_INTERVAL: HumanDuration = HumanDuration::from_secs(300);
+/// The smallest memory pool that can be configured.
+const MIN_MEMPOOL_SIZE: ByteSize = ByteSize::from_mib(1);
+/// The shortest time after which a disconnected peer can be forgotten.
+const MIN_PEER_EXPIRY: HumanDuration = HumanDuration::from_secs(3600);
+/// The lowest outbound bandwidth limit that can be configured, per second.
+const MIN_UPLOAD_LIMIT: ByteSize = ByteSize(64 << 10);
+/// The shortest maximum age of the latest block that can be configured; the blocks are usually further apart.
+const MIN_ALARM_TIP_AGE: HumanDuration = HumanDuration::from_secs(60);
+/// The default minimum fee of the transactions received from peers; the ones paying none are still relayed.
+const DEFAULT_MIN_RELAY_FEE: i64 = 0;
+
+/// Represents all configuration options for a node; the options missing from the `config.toml` file, e.g. the ones
+/// added since it was written, take their default values.
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub aleo: Aleo,
     pub node: Node,
     pub miner: Miner,
     pub rpc: JsonRPC,
     pub p2p: P2P,
+    pub webhook: Webhook,
+    pub alarms: Alarms,
+    pub journal: Journal,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Aleo {
     pub network_id: u8,
 }
 
+impl Aleo {
+    /// Returns the network the node is a part of.
+    pub fn network(&self) -> NetworkId {
+        NetworkId::from_id(self.network_id)
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct JsonRPC {
     pub json_rpc: bool,
     pub ip: String,
     pub port: u16,
     pub username: Option<String>,
     pub password: Option<String>,
+    pub max_batch_size: u16,
+    /// Also serves the RPC to authenticated management clients over the node's P2P port.
+    #[serde(default)]
+    pub p2p: bool,
+    /// The path of a Unix domain socket the RPC is also served on, e.g. for co-located tooling (Unix only).
+    #[serde(default)]
+    pub unix_socket: Option<PathBuf>,
+    /// The permissions of the Unix domain socket in octal, e.g. `660`; `600` (only the node's user) by default.
+    #[serde(default)]
+    pub unix_socket_mode: Option<String>,
+    /// Only serves the RPC on the Unix domain socket, and not on the TCP port.
+    #[serde(default)]
+    pub unix_socket_only: bool,
+    /// The tokens granting access to the admin namespace of the RPC, each with its own permissions; if there are
+    /// none, the admin endpoints are guarded by the username and password like the other private ones.
+    #[serde(default)]
+    pub admin_tokens: Vec<AdminToken>,
+    /// The port a gRPC server with the public queries of the RPC is started on; only available in builds with the
+    /// `grpc` feature.
+    #[serde(default)]
+    pub grpc_port: Option<u16>,
+}
+
+impl JsonRPC {
+    /// The permissions the Unix domain socket is created with if none are configured.
+    const DEFAULT_UNIX_SOCKET_MODE: u32 = 0o600;
+
+    /// Returns the permissions of the Unix domain socket the RPC is served on.
+    pub fn unix_socket_mode(&self) -> u32 {
+        self.unix_socket_mode
+            .as_deref()
+            .and_then(|mode| u32::from_str_radix(mode, 8).ok())
+            .unwrap_or(Self::DEFAULT_UNIX_SOCKET_MODE)
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Node {
     pub dir: PathBuf,
     pub db: String,
     pub is_bootnode: bool,
+    pub is_crawler: bool,
     pub ip: String,
     pub port: u16,
+    pub listen_addresses: Vec<String>,
+    pub advertised_address: Option<String>,
+    pub alias: Option<String>,
     pub verbose: u8,
+    pub daemon: bool,
+    pub windows_service: bool,
+    /// Opens the storage strictly read-only and only serves RPC requests, without connecting to any peers.
+    pub read_only: bool,
+    pub pidfile: Option<PathBuf>,
+    /// The number of most recent blocks kept in full; the transactions of the older ones are pruned from the storage.
+    #[serde(default)]
+    pub prune_depth: Option<u32>,
+}
+
+impl Node {
+    /// Returns the path of the pidfile, if any; a relative one is resolved against the node's directory.
+    pub fn pidfile_path(&self) -> Option<PathBuf> {
+        self.pidfile.as_ref().map(|pidfile| self.dir.join(pidfile))
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Miner {
     pub is_miner: bool,
     pub miner_address: String,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct P2P {
-    #[serde(skip_serializing, skip_deserializing)]
+    /// The network's default bootnodes are used if none are listed in the config file.
+    #[serde(default, skip_serializing)]
     pub bootnodes: Vec<String>,
+    /// The IPs and subnets the node never connects to or accepts connections from, e.g. `10.0.0.0/8`.
+    #[serde(default)]
+    pub banned: Vec<String>,
+    /// The prefix length of the IPv4 subnets banned along with a peer, e.g. `24`; only its IP by default.
+    #[serde(default)]
+    pub peer_ban_prefix_ipv4: Option<u8>,
+    /// The prefix length of the IPv6 subnets banned along with a peer, e.g. `64`; only its IP by default.
+    #[serde(default)]
+    pub peer_ban_prefix_ipv6: Option<u8>,
+    /// The ports the addresses gossiped by peers may use, e.g. `4130-4139, 5000` or `any`; the network's
+    /// standard ports by default.
+    #[serde(default)]
+    pub gossip_ports: Option<String>,
+    /// If `true`, the addresses gossiped by peers are probed with a plain TCP connection before they're preferred
+    /// as connection candidates; it has no effect if `proxy` is set.
+    #[serde(default)]
+    pub reachability_probes: bool,
+    /// The number of connection slots reserved for the peers the node connects to, so that the peers connecting to it
+    /// can't occupy all of them; 8 by default, and at most half of `max_peers`.
+    #[serde(default)]
+    pub reserved_outbound_slots: Option<u16>,
+    /// The maximum number of outbound handshakes performed at the same time; 10 by default.
+    #[serde(default)]
+    pub max_concurrent_handshakes: Option<usize>,
+    /// The file or HTTP(S) URL of a crawler's signed list of recommended peers, which the node falls back on when it
+    /// has no peers, e.g. because its bootnodes are down.
+    #[serde(default)]
+    pub recommended_peers: Option<String>,
+    /// The hex-encoded public keys of the crawlers whose lists of recommended peers are trusted.
+    #[serde(default)]
+    pub recommended_peers_keys: Vec<String>,
+    /// The path a crawler periodically exports its signed list of recommended peers to; it's signed with the
+    /// node key, so `node.alias` needs to be set.
+    #[serde(default)]
+    pub recommended_peers_export: Option<PathBuf>,
+    /// The maximum number of block hashes sent in response to a peer's `GetSync` request; 64 by default.
+    #[serde(default)]
+    pub sync_response_cap: Option<u32>,
+    /// The maximum number of block hashes the responses to the peers' `GetSync` requests are randomly shortened
+    /// by, so that they don't reveal the node's exact view of the chain; 0 by default.
+    #[serde(default)]
+    pub sync_response_jitter: Option<u32>,
+    /// The maximum number of blocks served to a single peer per `sync_serving_window`; 4096 by default.
+    #[serde(default)]
+    pub sync_serving_max_blocks: Option<u32>,
+    /// The maximum number of block hashes served to a single peer per `sync_serving_window`; 8192 by default.
+    #[serde(default)]
+    pub sync_serving_max_hashes: Option<u32>,
+    /// The window the numbers of blocks and block hashes served to each peer are counted over, e.g. `1m`.
+    #[serde(default)]
+    pub sync_serving_window: Option<HumanDuration>,
+    /// The minimum fee of the transactions received from peers that the node accepts and relays; 0 by default.
+    #[serde(default)]
+    pub min_relay_fee: Option<i64>,
+    /// The maximum size of the transactions received from peers that the node accepts and relays, e.g. `64KiB`.
+    #[serde(default)]
+    pub max_relay_transaction_size: Option<ByteSize>,
+    /// The hex-encoded byte sequences whose occurrence in the memo of a transaction received from a peer makes the
+    /// node refuse it.
+    #[serde(default)]
+    pub relay_memo_blocklist: Vec<String>,
+    /// The maximum size of the transactions spilled to storage once the memory pool is full, e.g. `256MiB`; they're
+    /// dropped instead if it's not set.
+    #[serde(default)]
+    pub mempool_overflow_size: Option<ByteSize>,
+    /// The maximum number of bytes sent to all the peers combined per second, e.g. `1MiB`; unlimited by default.
+    #[serde(default)]
+    pub upload_limit: Option<ByteSize>,
+    /// The maximum number of bytes sent to any single peer per second, e.g. `256KiB`; unlimited by default.
+    #[serde(default)]
+    pub peer_upload_limit: Option<ByteSize>,
+    /// The maximum number of disconnected peers retained in the peer book, not counting the bootnodes; 2048 by default.
+    #[serde(default)]
+    pub max_disconnected_peers: Option<u32>,
+    /// The amount of time after which a disconnected peer that wasn't successfully connected to is forgotten, e.g.
+    /// `7d`; 30 days by default.
+    #[serde(default)]
+    pub disconnected_peer_expiry: Option<HumanDuration>,
+    /// The number of consecutive failed connection attempts after which a disconnected peer is forgotten; 10 by
+    /// default.
+    #[serde(default)]
+    pub max_peer_failures: Option<u32>,
     #[serde(alias = "mempool_interval")]
-    pub mempool_sync_interval: u8,
-    pub block_sync_interval: u16,
-    pub peer_sync_interval: u16,
+    pub mempool_sync_interval: HumanDuration,
+    /// The maximum size of the memory pool; plain numbers are in megabytes, like on the command line.
+    #[serde(deserialize_with = "crate::units::deserialize_mib")]
+    pub mempool_size: ByteSize,
+    pub block_sync_interval: HumanDuration,
+    pub peer_sync_interval: HumanDuration,
     pub min_peers: u16,
     pub max_peers: u16,
+    pub nat_traversal: bool,
+    pub proxy: Option<String>,
+    pub no_listen: bool,
+    pub dual_stack: bool,
+    /// The weights of the components of the connected peers' quality scores, and the score below which a peer is
+    /// disconnected from; the ones that aren't set take their default values.
+    #[serde(default)]
+    pub peer_score_weights: PeerScoreWeights,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Webhook {
+    pub url: Option<String>,
+    pub secret: Option<String>,
+    pub events: Vec<String>,
+}
+
+/// The thresholds of the alarms; the ones that aren't set are disabled.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Alarms {
+    /// The age of the latest block above which the chain is considered stalled.
+    pub max_tip_age: Option<HumanDuration>,
+    /// The number of connected peers below which the node is considered poorly connected.
+    pub min_peers: Option<u16>,
+    /// The number of pending transactions above which the memory pool is considered stuck.
+    pub max_mempool_transactions: Option<usize>,
+    /// The amount of time the memory pool needs to stay above its threshold for to be considered stuck.
+    pub mempool_stuck_for: HumanDuration,
+}
+
+/// The categories of the journaled events received by each of the journal's sinks: `lifecycle`, `peers`, `chain`,
+/// `alarms` and `crashes`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Journal {
+    /// The categories of events kept in memory and included in crash reports.
+    pub memory: Vec<String>,
+    /// The number of the most recent events kept in memory.
+    pub memory_capacity: usize,
+    /// The categories of events persisted in storage.
+    pub storage: Vec<String>,
+    /// The amount of time the events persisted in storage are kept for.
+    pub storage_retention: HumanDuration,
+    /// The categories of events reported to the webhook, if one is configured.
+    pub webhook: Vec<String>,
+    /// The categories of events streamed to the subscribers of the RPC server's event stream.
+    pub stream: Vec<String>,
+}
+
+impl Journal {
+    /// Parses the configured categories of the given sink.
+    pub fn categories(categories: &[String]) -> Result<Vec<EventCategory>, NetworkError> {
+        categories.iter().map(|category| category.parse()).collect()
+    }
 }
 
 impl Default for Config {
@@ -101,11 +376,20 @@ impl Default for Config {
             aleo: Aleo { network_id: 1 },
             node: Node {
                 dir: Self::snarkos_dir(),
-                db: "snarkos_testnet1".into(),
+                db: default_db(NetworkId::Testnet1),
                 is_bootnode: false,
+                is_crawler: false,
                 ip: "0.0.0.0".into(),
                 port: 4131,
+                listen_addresses: vec![],
+                advertised_address: None,
+                alias: None,
                 verbose: 2,
+                daemon: false,
+                windows_service: false,
+                read_only: false,
+                pidfile: None,
+                prune_depth: None,
             },
             miner: Miner {
                 is_miner: false,
@@ -118,22 +402,123 @@ impl Default for Config {
                 // TODO (raychu86) Establish a random username and password for the node operator by default
                 username: Some("Username".into()),
                 password: Some("Password".into()),
+                max_batch_size: 100,
+                p2p: false,
+                unix_socket: None,
+                unix_socket_mode: None,
+                unix_socket_only: false,
+                admin_tokens: vec![],
+                grpc_port: None,
             },
             p2p: P2P {
-                bootnodes: TESTNET_BOOTNODES
-                    .iter()
-                    .map(|node| (*node).to_string())
-                    .collect::<Vec<String>>(),
-                mempool_sync_interval: 12,
-                peer_sync_interval: 15,
-                block_sync_interval: 4,
+                bootnodes: default_bootnodes(NetworkId::Testnet1),
+                banned: vec![],
+                peer_ban_prefix_ipv4: None,
+                peer_ban_prefix_ipv6: None,
+                gossip_ports: None,
+                reachability_probes: false,
+                reserved_outbound_slots: None,
+                max_concurrent_handshakes: None,
+                recommended_peers: None,
+                recommended_peers_keys: vec![],
+                recommended_peers_export: None,
+                sync_response_cap: None,
+                sync_response_jitter: None,
+                sync_serving_max_blocks: None,
+                sync_serving_max_hashes: None,
+                sync_serving_window: None,
+                min_relay_fee: None,
+                max_relay_transaction_size: None,
+                relay_memo_blocklist: vec![],
+                mempool_overflow_size: None,
+                upload_limit: None,
+                peer_upload_limit: None,
+                max_disconnected_peers: None,
+                disconnected_peer_expiry: None,
+                max_peer_failures: None,
+                mempool_sync_interval: HumanDuration::from_secs(12),
+                mempool_size: ByteSize::from_mib(32),
+                peer_sync_interval: HumanDuration::from_secs(15),
+                block_sync_interval: HumanDuration::from_secs(4),
                 min_peers: 20,
                 max_peers: 50,
+                nat_traversal: false,
+                proxy: None,
+                no_listen: false,
+                dual_stack: false,
+                peer_score_weights: PeerScoreWeights::default(),
+            },
+            webhook: Webhook {
+                url: None,
+                secret: None,
+                events: vec![],
+            },
+            alarms: Alarms {
+                max_tip_age: None,
+                min_peers: None,
+                max_mempool_transactions: None,
+                mempool_stuck_for: HumanDuration::from_secs(600),
+            },
+            journal: Journal {
+                memory: EventCategory::ALL.iter().map(ToString::to_string).collect(),
+                memory_capacity: JOURNAL_MEMORY_CAPACITY,
+                storage: vec![],
+                storage_retention: HumanDuration::from_secs(JOURNAL_STORAGE_RETENTION_SECS.into()),
+                webhook: EventCategory::ALL.iter().map(ToString::to_string).collect(),
+                stream: EventCategory::ALL.iter().map(ToString::to_string).collect(),
             },
         }
     }
 }
 
+impl Default for Aleo {
+    fn default() -> Self {
+        Config::default().aleo
+    }
+}
+
+impl Default for Node {
+    fn default() -> Self {
+        Config::default().node
+    }
+}
+
+impl Default for Miner {
+    fn default() -> Self {
+        Config::default().miner
+    }
+}
+
+impl Default for JsonRPC {
+    fn default() -> Self {
+        Config::default().rpc
+    }
+}
+
+impl Default for P2P {
+    fn default() -> Self {
+        Config::default().p2p
+    }
+}
+
+impl Default for Webhook {
+    fn default() -> Self {
+        Config::default().webhook
+    }
+}
+
+impl Default for Alarms {
+    fn default() -> Self {
+        Config::default().alarms
+    }
+}
+
+impl Default for Journal {
+    fn default() -> Self {
+        Config::default().journal
+    }
+}
+
 impl Config {
     /// The directory that snarkOS system files will be stored
     fn snarkos_dir() -> PathBuf {
@@ -143,25 +528,89 @@ impl Config {
         path
     }
 
+    /// Returns the shaping applied to the responses to the peers' `GetSync` requests.
+    pub fn sync_response_shaping(&self) -> Result<SyncResponseShaping, NetworkError> {
+        SyncResponseShaping::new(
+            self.p2p.sync_response_cap.unwrap_or(MAX_BLOCK_SYNC_COUNT),
+            self.p2p.sync_response_jitter.unwrap_or(0),
+        )
+    }
+
+    /// Returns the caps on the blocks and block hashes served to each syncing peer.
+    pub fn sync_serving_quota(&self) -> Result<SyncServingQuota, NetworkError> {
+        let default = SyncServingQuota::default();
+
+        SyncServingQuota::new(
+            self.p2p.sync_serving_max_blocks.unwrap_or_else(|| default.max_blocks()),
+            self.p2p.sync_serving_max_hashes.unwrap_or_else(|| default.max_hashes()),
+            self.p2p
+                .sync_serving_window
+                .map(Duration::from)
+                .unwrap_or_else(|| default.window()),
+        )
+    }
+
+    /// Returns the rules deciding which of the transactions received from peers are accepted and relayed.
+    pub fn relay_policy(&self) -> Result<RelayPolicy, NetworkError> {
+        Ok(RelayPolicy {
+            min_fee: self.p2p.min_relay_fee.unwrap_or(DEFAULT_MIN_RELAY_FEE),
+            max_transaction_size: self.p2p.max_relay_transaction_size.map(|size| size.0 as usize),
+            memo_blocklist: self
+                .p2p
+                .relay_memo_blocklist
+                .iter()
+                .map(|pattern| pattern.parse())
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Returns the consensus parameters of the node's network, under which the given inner circuits are authorized.
+    pub fn consensus_parameters(&self, authorized_inner_snark_ids: Vec<Vec<u8>>) -> ConsensusParameters {
+        ConsensusParameters {
+            max_block_size: 1_000_000_000usize,
+            max_nonce: u32::max_value(),
+            target_block_time: 10i64,
+            network_id: Network::from_network_id(self.aleo.network_id),
+            verifier: PoswMarlin::verify_only().expect("could not instantiate PoSW verifier"),
+            authorized_inner_snark_ids,
+        }
+    }
+
+    /// Returns the range of addresses the ban of a peer covers.
+    pub fn peer_ban_scope(&self) -> Result<BanScope, NetworkError> {
+        BanScope::new(
+            self.p2p.peer_ban_prefix_ipv4.unwrap_or(32),
+            self.p2p.peer_ban_prefix_ipv6.unwrap_or(128),
+        )
+    }
+
+    /// Returns the rules for forgetting the disconnected peers that aren't worth keeping.
+    pub fn peer_eviction_policy(&self) -> PeerEvictionPolicy {
+        let default = PeerEvictionPolicy::default();
+
+        PeerEvictionPolicy {
+            max_peers: self
+                .p2p
+                .max_disconnected_peers
+                .map(|max| max as usize)
+                .unwrap_or(default.max_peers),
+            max_age: self
+                .p2p
+                .disconnected_peer_expiry
+                .map(|expiry| expiry.0)
+                .unwrap_or(default.max_age),
+            max_failures: self.p2p.max_peer_failures.unwrap_or(default.max_failures),
+        }
+    }
+
     /// Read the config from the `config.toml` file
     fn read_config() -> Result<Self, CliError> {
         let snarkos_path = Self::snarkos_dir();
         let mut config_path = snarkos_path.clone();
         config_path.push("config.toml");
 
-        // TODO (howardwu): Revert to this logic after testnet, when configs stabilize.
-        // if !Path::exists(&config_path) {
-        //     // Create a new default `config.toml` file if it doesn't already exist
-        //     fs::create_dir_all(&snarkos_path)?;
-        //
-        //     let default_config_string = toml::to_string(&Config::default())?;
-        //
-        //     fs::write(&config_path, default_config_string)?;
-        // }
-
-        // TODO (howardwu): Revisit this.
-        // For now, override the config.toml file each time.
-        {
+        // The existing `config.toml` file is kept, so that the settings only available in it take effect.
+        if !config_path.exists() {
             // Create a new default `config.toml` file if it doesn't already exist
             fs::create_dir_all(&snarkos_path)?;
 
@@ -170,10 +619,27 @@ impl Config {
             fs::write(&config_path, default_config_string)?;
         }
 
+        Self::load_config_file(&snarkos_path)
+    }
+
+    /// Re-reads the `config.toml` file without overwriting it, and applies the given CLI arguments on top of it,
+    /// so that the options given on the command line keep taking precedence over the ones in the file.
+    pub fn reload(arguments: &ArgMatches) -> Result<Self, CliError> {
+        let mut config = Self::load_config_file(&Self::snarkos_dir())?;
+        config.parse(arguments, ConfigCli::ARGUMENTS)?;
+        config.check()?;
+
+        Ok(config)
+    }
+
+    /// Parses the `config.toml` file in the given directory.
+    fn load_config_file(snarkos_path: &Path) -> Result<Self, CliError> {
+        let config_path = snarkos_path.join("config.toml");
+
         let toml_string = match fs::read_to_string(&config_path) {
             Ok(toml) => toml,
             Err(_) => {
-                fs::create_dir_all(&snarkos_path)?;
+                fs::create_dir_all(snarkos_path)?;
                 String::new()
             }
         };
@@ -181,78 +647,125 @@ impl Config {
         // Parse the contents into the `Config` struct
         let mut config: Config = toml::from_str(&toml_string)?;
 
-        let bootnodes = match config.aleo.network_id {
-            0 => MAINNET_BOOTNODES,
-            _ => TESTNET_BOOTNODES,
-        };
-
-        config.p2p.bootnodes = bootnodes
-            .iter()
-            .map(|node| (*node).to_string())
-            .collect::<Vec<String>>();
+        if config.p2p.bootnodes.is_empty() {
+            config.p2p.bootnodes = default_bootnodes(config.aleo.network());
+        }
 
         Ok(config)
     }
 
-    fn parse(&mut self, arguments: &ArgMatches, options: &[&str]) {
-        options.iter().for_each(|option| match *option {
-            // Flags
-            "is-bootnode" => self.is_bootnode(arguments.is_present(option)),
-            "is-miner" => self.is_miner(arguments.is_present(option)),
-            "no-jsonrpc" => self.no_jsonrpc(arguments.is_present(option)),
-            // Options
-            "connect" => self.connect(arguments.value_of(option)),
-            "ip" => self.ip(arguments.value_of(option)),
-            "miner-address" => self.miner_address(arguments.value_of(option)),
-            "mempool-interval" => self.mempool_interval(clap::value_t!(arguments.value_of(*option), u8).ok()),
-            "max-peers" => self.max_peers(clap::value_t!(arguments.value_of(*option), u16).ok()),
-            "min-peers" => self.min_peers(clap::value_t!(arguments.value_of(*option), u16).ok()),
-            "network" => self.network(clap::value_t!(arguments.value_of(*option), u8).ok()),
-            "path" => self.path(arguments.value_of(option)),
-            "port" => self.port(clap::value_t!(arguments.value_of(*option), u16).ok()),
-            "rpc-ip" => self.rpc_ip(arguments.value_of(option)),
-            "rpc-port" => self.rpc_port(clap::value_t!(arguments.value_of(*option), u16).ok()),
-            "rpc-username" => self.rpc_username(arguments.value_of(option)),
-            "rpc-password" => self.rpc_password(arguments.value_of(option)),
-            "verbose" => self.verbose(clap::value_t!(arguments.value_of(*option), u8).ok()),
-            _ => (),
-        });
-    }
-
-    /// Sets `network` to the specified network, overriding its previous state.
-    fn network(&mut self, argument: Option<u8>) {
-        if let Some(network_id) = argument {
-            match network_id {
-                0 => {
-                    self.node.db = "snarkos_mainnet".into();
-                    self.node.port = 4130;
-                    self.p2p.bootnodes = MAINNET_BOOTNODES
-                        .iter()
-                        .map(|node| (*node).to_string())
-                        .collect::<Vec<String>>();
-                    self.aleo.network_id = network_id;
-                }
-                _ => {
-                    self.node.db = format!("snarkos_testnet{}", network_id);
-                    self.node.port = 4130 + (network_id as u16);
-                    self.p2p.bootnodes = TESTNET_BOOTNODES
-                        .iter()
-                        .map(|node| (*node).to_string())
-                        .collect::<Vec<String>>();
-                    self.aleo.network_id = network_id;
-                }
+    fn parse(&mut self, arguments: &ArgMatches, options: &[&str]) -> Result<(), CliError> {
+        for option in options {
+            match *option {
+                // Flags
+                "is-bootnode" => self.is_bootnode(arguments.is_present(option)),
+                "is-crawler" => self.is_crawler(arguments.is_present(option)),
+                "is-miner" => self.is_miner(arguments.is_present(option)),
+                "no-jsonrpc" => self.no_jsonrpc(arguments.is_present(option)),
+                "nat" => self.nat(arguments.is_present(option)),
+                "no-listen" => self.no_listen(arguments.is_present(option)),
+                "dual-stack" => self.dual_stack(arguments.is_present(option)),
+                "daemon" => self.daemon(arguments.is_present(option)),
+                "windows-service" => self.windows_service(arguments.is_present(option)),
+                "read-only" => self.read_only(arguments.is_present(option)),
+                "rpc-over-p2p" => self.rpc_over_p2p(arguments.is_present(option)),
+                "rpc-socket-only" => self.rpc_socket_only(arguments.is_present(option)),
+                // Options
+                "advertise" => self.advertise(arguments.value_of(option)),
+                "alias" => self.alias(arguments.value_of(option)),
+                "connect" => self.connect(arguments.value_of(option)),
+                "ip" => self.ip(arguments.value_of(option)),
+                "listen" => self.listen(arguments.value_of(option)),
+                "miner-address" => self.miner_address(arguments.value_of(option)),
+                "mempool-interval" => self.mempool_interval(arguments.value_of(option))?,
+                "mempool-size" => self.mempool_size(arguments.value_of(option))?,
+                "max-peers" => self.max_peers(clap::value_t!(arguments.value_of(*option), u16).ok()),
+                "min-peers" => self.min_peers(clap::value_t!(arguments.value_of(*option), u16).ok()),
+                "network" => self.network(arguments.value_of(option))?,
+                "path" => self.path(arguments.value_of(option)),
+                "pidfile" => self.pidfile(arguments.value_of(option)),
+                "prune-depth" => self.prune_depth(clap::value_t!(arguments.value_of(*option), u32).ok()),
+                "proxy" => self.proxy(arguments.value_of(option)),
+                "port" => self.port(clap::value_t!(arguments.value_of(*option), u16).ok()),
+                "rpc-ip" => self.rpc_ip(arguments.value_of(option)),
+                "rpc-port" => self.rpc_port(clap::value_t!(arguments.value_of(*option), u16).ok()),
+                "rpc-username" => self.rpc_username(arguments.value_of(option)),
+                "rpc-password" => self.rpc_password(arguments.value_of(option)),
+                "rpc-max-batch-size" => self.rpc_max_batch_size(clap::value_t!(arguments.value_of(*option), u16).ok()),
+                "rpc-socket" => self.rpc_socket(arguments.value_of(option)),
+                "webhook" => self.webhook(arguments.value_of(option)),
+                "webhook-secret" => self.webhook_secret(arguments.value_of(option)),
+                "webhook-events" => self.webhook_events(arguments.value_of(option)),
+                "verbose" => self.verbose(clap::value_t!(arguments.value_of(*option), u8).ok()),
+                _ => (),
             }
         }
+
+        Ok(())
+    }
+
+    /// Sets `network` to the specified network, overriding its previous state; the storage, the port and the
+    /// default bootnodes are specific to each network, so that the nodes on different networks don't clash.
+    fn network(&mut self, argument: Option<&str>) -> Result<(), CliError> {
+        if let Some(network) = argument {
+            let network = network
+                .parse::<NetworkId>()
+                .map_err(|e| CliError::InvalidValue("--network", e.to_string()))?;
+
+            self.node.db = default_db(network);
+            self.node.port = 4130 + network.id() as u16;
+            self.p2p.bootnodes = default_bootnodes(network);
+            self.aleo.network_id = network.id();
+        }
+        Ok(())
     }
 
     fn no_jsonrpc(&mut self, argument: bool) {
         self.rpc.json_rpc = !argument;
     }
 
+    fn nat(&mut self, argument: bool) {
+        self.p2p.nat_traversal = argument;
+    }
+
+    fn no_listen(&mut self, argument: bool) {
+        self.p2p.no_listen = argument;
+    }
+
+    fn dual_stack(&mut self, argument: bool) {
+        self.p2p.dual_stack = argument;
+    }
+
+    fn daemon(&mut self, argument: bool) {
+        self.node.daemon = argument;
+    }
+
+    fn windows_service(&mut self, argument: bool) {
+        self.node.windows_service = argument;
+    }
+
+    fn read_only(&mut self, argument: bool) {
+        self.node.read_only = argument;
+    }
+
+    fn rpc_over_p2p(&mut self, argument: bool) {
+        self.rpc.p2p = argument;
+    }
+
+    fn rpc_socket_only(&mut self, argument: bool) {
+        if argument {
+            self.rpc.unix_socket_only = true;
+        }
+    }
+
     fn is_bootnode(&mut self, argument: bool) {
         self.node.is_bootnode = argument;
     }
 
+    fn is_crawler(&mut self, argument: bool) {
+        self.node.is_crawler = argument;
+    }
+
     fn is_miner(&mut self, argument: bool) {
         self.miner.is_miner = argument;
     }
@@ -269,6 +782,43 @@ impl Config {
         }
     }
 
+    fn listen(&mut self, argument: Option<&str>) {
+        if let Some(addresses) = argument {
+            let sanitize_addresses = addresses.replace(&['[', ']', ' '][..], "");
+            self.node.listen_addresses = sanitize_addresses.split(',').map(|s| s.to_string()).collect();
+        }
+    }
+
+    fn advertise(&mut self, argument: Option<&str>) {
+        if let Some(address) = argument {
+            self.node.advertised_address = Some(address.to_string());
+        }
+    }
+
+    fn alias(&mut self, argument: Option<&str>) {
+        if let Some(alias) = argument {
+            self.node.alias = Some(alias.to_string());
+        }
+    }
+
+    fn pidfile(&mut self, argument: Option<&str>) {
+        if let Some(pidfile) = argument {
+            self.node.pidfile = Some(pidfile.into());
+        }
+    }
+
+    fn prune_depth(&mut self, argument: Option<u32>) {
+        if let Some(depth) = argument {
+            self.node.prune_depth = Some(depth);
+        }
+    }
+
+    fn proxy(&mut self, argument: Option<&str>) {
+        if let Some(address) = argument {
+            self.p2p.proxy = Some(address.to_string());
+        }
+    }
+
     fn path(&mut self, argument: Option<&str>) {
         if let Some(path) = argument {
             self.node.db = path.into();
@@ -289,10 +839,22 @@ impl Config {
         }
     }
 
-    fn mempool_interval(&mut self, argument: Option<u8>) {
+    fn mempool_interval(&mut self, argument: Option<&str>) -> Result<(), CliError> {
         if let Some(interval) = argument {
             self.p2p.mempool_sync_interval = interval
+                .parse()
+                .map_err(|e| CliError::InvalidValue("--mempool-interval", e))?;
+        }
+        Ok(())
+    }
+
+    fn mempool_size(&mut self, argument: Option<&str>) -> Result<(), CliError> {
+        if let Some(size) = argument {
+            // Plain numbers are in megabytes, like before the units were introduced.
+            self.p2p.mempool_size = ByteSize::parse_with_default_unit(size, "MiB")
+                .map_err(|e| CliError::InvalidValue("--mempool-size", e))?;
         }
+        Ok(())
     }
 
     fn min_peers(&mut self, argument: Option<u16>) {
@@ -331,6 +893,37 @@ impl Config {
         }
     }
 
+    fn rpc_max_batch_size(&mut self, argument: Option<u16>) {
+        if let Some(max_batch_size) = argument {
+            self.rpc.max_batch_size = max_batch_size;
+        }
+    }
+
+    fn rpc_socket(&mut self, argument: Option<&str>) {
+        if let Some(path) = argument {
+            self.rpc.unix_socket = Some(path.into());
+        }
+    }
+
+    fn webhook(&mut self, argument: Option<&str>) {
+        if let Some(url) = argument {
+            self.webhook.url = Some(url.to_string());
+        }
+    }
+
+    fn webhook_secret(&mut self, argument: Option<&str>) {
+        if let Some(secret) = argument {
+            self.webhook.secret = Some(secret.to_string());
+        }
+    }
+
+    fn webhook_events(&mut self, argument: Option<&str>) {
+        if let Some(events) = argument {
+            let sanitize_events = events.replace(&['[', ']', ' '][..], "");
+            self.webhook.events = sanitize_events.split(',').map(|s| s.to_string()).collect();
+        }
+    }
+
     fn verbose(&mut self, argument: Option<u8>) {
         if let Some(verbose) = argument {
             self.node.verbose = verbose
@@ -339,20 +932,235 @@ impl Config {
 
     pub fn check(&self) -> Result<(), CliError> {
         // Check that the minimum and maximum number of peers is valid.
-        if self.p2p.min_peers == 0 || self.p2p.max_peers == 0 {
+        if self.p2p.min_peers == 0 || self.p2p.max_peers == 0 || self.p2p.min_peers > self.p2p.max_peers {
             return Err(CliError::PeerCountInvalid);
         }
 
-        // Check that the sync interval is a reasonable number of seconds.
-        if !(2..=300).contains(&self.p2p.peer_sync_interval) || !(2..=300).contains(&self.p2p.block_sync_interval) {
-            return Err(CliError::SyncIntervalInvalid);
+        // Check that the sync intervals are reasonable.
+        let sync_intervals = [
+            ("p2p.peer_sync_interval", self.p2p.peer_sync_interval),
+            ("p2p.block_sync_interval", self.p2p.block_sync_interval),
+        ];
+        for &(field, interval) in sync_intervals.iter() {
+            if !(MIN_SYNC_INTERVAL..=MAX_SYNC_INTERVAL).contains(&interval) {
+                return Err(CliError::InvalidValue(
+                    field,
+                    format!(
+                        "{} is not between {} and {}",
+                        interval, MIN_SYNC_INTERVAL, MAX_SYNC_INTERVAL
+                    ),
+                ));
+            }
+        }
+
+        if self.p2p.mempool_sync_interval < HumanDuration::from_secs(1) {
+            return Err(CliError::InvalidValue(
+                "p2p.mempool_sync_interval",
+                format!("{} is shorter than 1s", self.p2p.mempool_sync_interval),
+            ));
+        }
+
+        for subnet in &self.p2p.banned {
+            if let Err(e) = subnet.parse::<Subnet>() {
+                return Err(CliError::InvalidValue("p2p.banned", e.to_string()));
+            }
+        }
+
+        if self.p2p.peer_ban_prefix_ipv4.unwrap_or(32) > 32 {
+            return Err(CliError::InvalidValue(
+                "p2p.peer_ban_prefix_ipv4",
+                "the prefix length can't exceed 32".into(),
+            ));
+        }
+        if self.p2p.peer_ban_prefix_ipv6.unwrap_or(128) > 128 {
+            return Err(CliError::InvalidValue(
+                "p2p.peer_ban_prefix_ipv6",
+                "the prefix length can't exceed 128".into(),
+            ));
+        }
+
+        if let Some(ref ports) = self.p2p.gossip_ports {
+            if let Err(e) = ports.parse::<PortPolicy>() {
+                return Err(CliError::InvalidValue("p2p.gossip_ports", e.to_string()));
+            }
+        }
+
+        if self.p2p.recommended_peers.is_some() && self.p2p.recommended_peers_keys.is_empty() {
+            return Err(CliError::InvalidValue(
+                "p2p.recommended_peers_keys",
+                "no trusted keys are configured for the recommended peers".to_string(),
+            ));
+        }
+
+        if self.p2p.recommended_peers_export.is_some() && (!self.node.is_crawler || self.node.alias.is_none()) {
+            return Err(CliError::InvalidValue(
+                "p2p.recommended_peers_export",
+                "only crawlers with a `node.alias` can export recommended peers".to_string(),
+            ));
+        }
+
+        for (i, token) in self.rpc.admin_tokens.iter().enumerate() {
+            if let Err(e) = token.check() {
+                return Err(CliError::InvalidValue("rpc.admin_tokens", e));
+            }
+            if self.rpc.admin_tokens[..i].iter().any(|other| other.name == token.name) {
+                return Err(CliError::InvalidValue(
+                    "rpc.admin_tokens",
+                    format!("the token name '{}' is used more than once", token.name),
+                ));
+            }
+        }
+
+        // The cap is checked on its own first, so that the error points to the right key.
+        let sync_response_cap = self.p2p.sync_response_cap.unwrap_or(MAX_BLOCK_SYNC_COUNT);
+        if let Err(e) = SyncResponseShaping::new(sync_response_cap, 0) {
+            return Err(CliError::InvalidValue("p2p.sync_response_cap", e.to_string()));
+        }
+        if let Err(e) = self.sync_response_shaping() {
+            return Err(CliError::InvalidValue("p2p.sync_response_jitter", e.to_string()));
+        }
+
+        // Likewise, each of the serving caps is checked alongside the defaults of the other settings.
+        let default = SyncServingQuota::default();
+        let max_blocks = self.p2p.sync_serving_max_blocks.unwrap_or_else(|| default.max_blocks());
+        let max_hashes = self.p2p.sync_serving_max_hashes.unwrap_or_else(|| default.max_hashes());
+        if let Err(e) = SyncServingQuota::new(max_blocks, default.max_hashes(), default.window()) {
+            return Err(CliError::InvalidValue("p2p.sync_serving_max_blocks", e.to_string()));
+        }
+        if let Err(e) = SyncServingQuota::new(default.max_blocks(), max_hashes, default.window()) {
+            return Err(CliError::InvalidValue("p2p.sync_serving_max_hashes", e.to_string()));
+        }
+        if let Err(e) = self.sync_serving_quota() {
+            return Err(CliError::InvalidValue("p2p.sync_serving_window", e.to_string()));
+        }
+
+        if let Err(e) = self.relay_policy() {
+            return Err(CliError::InvalidValue("p2p.relay_memo_blocklist", e.to_string()));
+        }
+
+        if let Err(e) = self.p2p.peer_score_weights.check() {
+            return Err(CliError::InvalidValue("p2p.peer_score_weights", e.to_string()));
+        }
+
+        // Guard against sizes given in the wrong unit, e.g. `32` instead of `32MiB`.
+        if self.p2p.mempool_size < MIN_MEMPOOL_SIZE {
+            return Err(CliError::InvalidValue(
+                "p2p.mempool_size",
+                format!("{} is smaller than {}", self.p2p.mempool_size, MIN_MEMPOOL_SIZE),
+            ));
+        }
+
+        if self.p2p.max_disconnected_peers == Some(0) {
+            return Err(CliError::InvalidValue(
+                "p2p.max_disconnected_peers",
+                "the peer book needs to retain some peers".into(),
+            ));
+        }
+
+        if let Some(expiry) = self.p2p.disconnected_peer_expiry {
+            if expiry < MIN_PEER_EXPIRY {
+                return Err(CliError::InvalidValue(
+                    "p2p.disconnected_peer_expiry",
+                    format!("{} is shorter than {}", expiry, MIN_PEER_EXPIRY),
+                ));
+            }
+        }
+
+        if self.p2p.max_peer_failures == Some(0) {
+            return Err(CliError::InvalidValue(
+                "p2p.max_peer_failures",
+                "the peers need to be allowed at least one failed connection attempt".into(),
+            ));
+        }
+
+        for (name, limit) in [
+            ("p2p.upload_limit", self.p2p.upload_limit),
+            ("p2p.peer_upload_limit", self.p2p.peer_upload_limit),
+        ] {
+            if let Some(limit) = limit.filter(|limit| *limit < MIN_UPLOAD_LIMIT) {
+                return Err(CliError::InvalidValue(
+                    name,
+                    format!("{} is smaller than {}", limit, MIN_UPLOAD_LIMIT),
+                ));
+            }
+        }
+
+        if let Some(max_tip_age) = self.alarms.max_tip_age {
+            if max_tip_age < MIN_ALARM_TIP_AGE {
+                return Err(CliError::InvalidValue(
+                    "alarms.max_tip_age",
+                    format!("{} is shorter than {}", max_tip_age, MIN_ALARM_TIP_AGE),
+                ));
+            }
+        }
+
+        for &(sink, categories) in &[
+            ("journal.memory", &self.journal.memory),
+            ("journal.storage", &self.journal.storage),
+            ("journal.webhook", &self.journal.webhook),
+            ("journal.stream", &self.journal.stream),
+        ] {
+            if let Err(e) = Journal::categories(categories) {
+                return Err(CliError::InvalidValue(sink, e.to_string()));
+            }
         }
 
         if self.node.is_bootnode && self.miner.is_miner {
             return Err(CliError::MinerBootstrapper);
         }
 
-        // TODO (howardwu): Check the memory pool interval.
+        if self.node.is_crawler && !cfg!(feature = "crawler") {
+            return Err(CliError::CrawlerUnsupported);
+        }
+
+        if self.node.is_crawler && self.miner.is_miner {
+            return Err(CliError::MinerCrawler);
+        }
+
+        if self.node.read_only && self.miner.is_miner {
+            return Err(CliError::MinerReadOnly);
+        }
+
+        if self.node.is_bootnode && (self.p2p.no_listen || self.node.read_only) {
+            return Err(CliError::NotListeningBootstrapper);
+        }
+
+        if self.node.daemon && !cfg!(unix) {
+            return Err(CliError::DaemonUnsupported);
+        }
+
+        if self.node.windows_service && !cfg!(windows) {
+            return Err(CliError::WindowsServiceUnsupported);
+        }
+
+        if let Some(depth) = self.node.prune_depth {
+            if depth < MIN_PRUNE_DEPTH {
+                return Err(CliError::InvalidValue(
+                    "node.prune_depth",
+                    format!("{} is smaller than {}", depth, MIN_PRUNE_DEPTH),
+                ));
+            }
+        }
+
+        if self.rpc.unix_socket.is_some() && !cfg!(unix) {
+            return Err(CliError::UnixSocketUnsupported);
+        }
+
+        if let Some(ref mode) = self.rpc.unix_socket_mode {
+            if !matches!(u32::from_str_radix(mode, 8), Ok(mode) if mode <= 0o777) {
+                return Err(CliError::InvalidValue(
+                    "rpc.unix_socket_mode",
+                    format!("'{}' is not an octal file mode", mode),
+                ));
+            }
+        }
+
+        if self.rpc.unix_socket_only && self.rpc.unix_socket.is_none() {
+            return Err(CliError::InvalidValue(
+                "rpc.unix_socket_only",
+                "no Unix socket path is configured".to_string(),
+            ));
+        }
 
         Ok(())
     }
@@ -361,19 +1169,83 @@ impl Config {
 /// Parses command line arguments into node configuration parameters.
 pub struct ConfigCli;
 
+impl ConfigCli {
+    /// The CLI arguments applied to the config, in order.
+    const ARGUMENTS: &'static [&'static str] = &[
+        "network",
+        "no-jsonrpc",
+        "is-bootnode",
+        "is-crawler",
+        "is-miner",
+        "nat",
+        "no-listen",
+        "dual-stack",
+        "daemon",
+        "windows-service",
+        "read-only",
+        "rpc-over-p2p",
+        "rpc-socket-only",
+        "ip",
+        "port",
+        "listen",
+        "advertise",
+        "alias",
+        "proxy",
+        "path",
+        "pidfile",
+        "prune-depth",
+        "connect",
+        "miner-address",
+        "mempool-interval",
+        "mempool-size",
+        "min-peers",
+        "max-peers",
+        "rpc-ip",
+        "rpc-port",
+        "rpc-username",
+        "rpc-password",
+        "rpc-max-batch-size",
+        "rpc-socket",
+        "webhook",
+        "webhook-secret",
+        "webhook-events",
+        "verbose",
+    ];
+}
+
 impl CLI for ConfigCli {
     type Config = Config;
 
     const ABOUT: AboutType = "Run an Aleo node (include -h for more options)";
-    const FLAGS: &'static [FlagType] = &[flag::NO_JSONRPC, flag::IS_BOOTNODE, flag::IS_MINER];
+    const FLAGS: &'static [FlagType] = &[
+        flag::NO_JSONRPC,
+        flag::IS_BOOTNODE,
+        flag::IS_CRAWLER,
+        flag::IS_MINER,
+        flag::NAT,
+        flag::NO_LISTEN,
+        flag::DUAL_STACK,
+        flag::DAEMON,
+        flag::WINDOWS_SERVICE,
+        flag::READ_ONLY,
+        flag::RPC_OVER_P2P,
+        flag::RPC_SOCKET_ONLY,
+    ];
     const NAME: NameType = "snarkOS";
     const OPTIONS: &'static [OptionType] = &[
         option::IP,
         option::PORT,
+        option::LISTEN,
+        option::ADVERTISE,
+        option::ALIAS,
+        option::PROXY,
         option::PATH,
+        option::PIDFILE,
+        option::PRUNE_DEPTH,
         option::CONNECT,
         option::MINER_ADDRESS,
         option::MEMPOOL_INTERVAL,
+        option::MEMPOOL_SIZE,
         option::MIN_PEERS,
         option::MAX_PEERS,
         option::NETWORK,
@@ -381,36 +1253,45 @@ impl CLI for ConfigCli {
         option::RPC_PORT,
         option::RPC_USERNAME,
         option::RPC_PASSWORD,
+        option::RPC_MAX_BATCH_SIZE,
+        option::RPC_SOCKET,
+        option::WEBHOOK,
+        option::WEBHOOK_SECRET,
+        option::WEBHOOK_EVENTS,
         option::VERBOSE,
     ];
-    const SUBCOMMANDS: &'static [SubCommandType] = &[subcommand::UPDATE];
+    const SUBCOMMANDS: &'static [SubCommandType] = &[
+        subcommand::UPDATE,
+        subcommand::EXPORT_PEERS,
+        subcommand::IMPORT_PEERS,
+        subcommand::EXPORT_SNAPSHOT,
+        subcommand::IMPORT_SNAPSHOT,
+        subcommand::RPC,
+    ];
 
     /// Handle all CLI arguments and flags for skeleton node
     fn parse(arguments: &ArgMatches) -> Result<Self::Config, CliError> {
         let mut config = Config::read_config()?;
-        config.parse(arguments, &[
-            "network",
-            "no-jsonrpc",
-            "is-bootnode",
-            "is-miner",
-            "ip",
-            "port",
-            "path",
-            "connect",
-            "miner-address",
-            "mempool-interval",
-            "min-peers",
-            "max-peers",
-            "rpc-ip",
-            "rpc-port",
-            "rpc-username",
-            "rpc-password",
-            "verbose",
-        ]);
-
-        if let ("update", Some(arguments)) = arguments.subcommand() {
-            UpdateCLI::parse(arguments)?;
-            std::process::exit(0x0100);
+        config.parse(arguments, ConfigCli::ARGUMENTS)?;
+
+        match arguments.subcommand() {
+            ("update", Some(arguments)) => {
+                UpdateCLI::parse(arguments)?;
+                std::process::exit(0x0100);
+            }
+            (command @ "export-peers", Some(arguments)) | (command @ "import-peers", Some(arguments)) => {
+                PeersCLI::parse(&config, command, arguments)?;
+                std::process::exit(0);
+            }
+            (command @ "export-snapshot", Some(arguments)) | (command @ "import-snapshot", Some(arguments)) => {
+                SnapshotCLI::parse(&config, command, arguments)?;
+                std::process::exit(0);
+            }
+            ("rpc", Some(arguments)) => {
+                RpcClientCLI::parse(&config, arguments)?;
+                std::process::exit(0);
+            }
+            _ => {}
         }
 
         Ok(config)
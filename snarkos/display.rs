@@ -60,10 +60,7 @@ pub fn render_welcome(config: &Config) -> String {
         }
     }
 
-    let network = match config.aleo.network_id {
-        0 => "mainnet".to_string(),
-        i => format!("testnet{}", i),
-    };
+    let network = config.aleo.network();
     if is_miner {
         output += &format!("Starting a mining node on {}.\n", network).bold().to_string();
     } else {
@@ -31,9 +31,30 @@ pub enum CliError {
     #[error("The node can't be a bootstrapper and a miner at the same time")]
     MinerBootstrapper,
 
+    #[error("The node can't be a crawler and a miner at the same time")]
+    MinerCrawler,
+
+    #[error("The node can't be a crawler, as it was built without the `crawler` feature")]
+    CrawlerUnsupported,
+
+    #[error("The node can't be a miner with read-only storage")]
+    MinerReadOnly,
+
+    #[error("The node can't be a bootstrapper without accepting inbound connections")]
+    NotListeningBootstrapper,
+
     #[error("The minimum or maximum value for peer count is invalid")]
     PeerCountInvalid,
 
-    #[error("One of the sync intervals is invalid")]
-    SyncIntervalInvalid,
+    #[error("Invalid value for '{}': {}", _0, _1)]
+    InvalidValue(&'static str, String),
+
+    #[error("The node can only run as a daemon on Unix")]
+    DaemonUnsupported,
+
+    #[error("The node can only run as a Windows service on Windows")]
+    WindowsServiceUnsupported,
+
+    #[error("The RPC can only be served on a Unix domain socket on Unix")]
+    UnixSocketUnsupported,
 }
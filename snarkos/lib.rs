@@ -16,10 +16,18 @@
 
 #[macro_use]
 extern crate thiserror;
+#[macro_use]
+extern crate tracing;
 
 pub mod cli;
 pub mod config;
 pub mod display;
 pub mod errors;
+pub mod logging;
 pub mod parameters;
+pub mod peers;
+pub mod rpc_client;
+pub mod service;
+pub mod snapshot;
+pub mod units;
 pub mod update;
@@ -0,0 +1,306 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+//! Deduplication of the repeated warnings of the network layer, which can flood the logs at high peer churn.
+//!
+//! The first occurrence of a warning is logged, while the identical ones that follow within the deduplication
+//! window are suppressed; once the window expires, a summary with the number of suppressed warnings is logged.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+use tracing::{
+    field::{Field, Visit},
+    span,
+    subscriber::Interest,
+    Event,
+    Level,
+    Metadata,
+    Subscriber,
+};
+use tracing_subscriber::{
+    filter::LevelFilter,
+    layer::{Context, Layer},
+};
+
+/// The amount of time the identical warnings are suppressed for after the first one is logged.
+pub const LOG_DEDUP_WINDOW_SECS: u64 = 60;
+/// The maximum number of distinct warnings tracked at once; the ones beyond it are logged without deduplication.
+pub const MAX_TRACKED_WARNINGS: usize = 1024;
+/// The prefix of the targets of the events that are deduplicated.
+const DEDUPLICATED_TARGET: &str = "snarkos_network";
+
+/// The identity of a warning: its message and the peer it concerns, if it's recorded as a field.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WarningKey {
+    pub message: String,
+    pub peer: Option<String>,
+}
+
+impl fmt::Display for WarningKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.peer {
+            Some(peer) => write!(f, "{} (peer: {})", self.message, peer),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Collects the fields identifying a warning.
+#[derive(Default)]
+struct KeyVisitor {
+    message: String,
+    peer: Option<String>,
+}
+
+impl Visit for KeyVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        match field.name() {
+            "message" => self.message = format!("{:?}", value),
+            "peer" | "address" | "remote_address" => self.peer = Some(format!("{:?}", value)),
+            _ => {}
+        }
+    }
+}
+
+/// A warning logged within its deduplication window.
+struct TrackedWarning {
+    first_logged: Instant,
+    suppressed: u64,
+}
+
+/// The warnings logged within their deduplication windows.
+pub struct DedupState {
+    window: Duration,
+    warnings: HashMap<WarningKey, TrackedWarning>,
+}
+
+impl DedupState {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            warnings: Default::default(),
+        }
+    }
+
+    /// Returns `true` if the given warning should be logged, or `false` if it's suppressed as a duplicate.
+    pub fn admit(&mut self, key: WarningKey, now: Instant) -> bool {
+        match self.warnings.get_mut(&key) {
+            Some(warning) if now.duration_since(warning.first_logged) < self.window => {
+                warning.suppressed += 1;
+                false
+            }
+            // A warning recurring after its window expired is logged again and starts a new window; the duplicates
+            // suppressed in the previous one are still summarized by the next `expire`.
+            Some(warning) => {
+                warning.first_logged = now;
+                true
+            }
+            None if self.warnings.len() >= MAX_TRACKED_WARNINGS => true,
+            None => {
+                let warning = TrackedWarning {
+                    first_logged: now,
+                    suppressed: 0,
+                };
+                self.warnings.insert(key, warning);
+                true
+            }
+        }
+    }
+
+    /// Forgets the warnings whose windows have expired, returning the ones that had duplicates suppressed
+    /// along with their numbers.
+    pub fn expire(&mut self, now: Instant) -> Vec<(WarningKey, u64)> {
+        let window = self.window;
+        let mut summaries = Vec::new();
+
+        self.warnings.retain(|key, warning| {
+            if now.duration_since(warning.first_logged) < window {
+                return true;
+            }
+            if warning.suppressed != 0 {
+                summaries.push((key.clone(), warning.suppressed));
+            }
+            false
+        });
+
+        summaries
+    }
+}
+
+/// A handle to the deduplication state, shared by the `DedupLayer` and the task logging the summaries.
+#[derive(Clone)]
+pub struct LogDeduplicator(Arc<Mutex<DedupState>>);
+
+impl LogDeduplicator {
+    pub fn new(window: Duration) -> Self {
+        Self(Arc::new(Mutex::new(DedupState::new(window))))
+    }
+
+    /// Wraps the given layer, so that it doesn't receive the suppressed warnings.
+    pub fn layer<L>(&self, inner: L) -> DedupLayer<L> {
+        DedupLayer {
+            inner,
+            deduplicator: self.clone(),
+        }
+    }
+
+    /// Logs the summaries of the warnings suppressed in the expired windows; it must be called outside of
+    /// the logging subsystem, as the events emitted from within it are discarded.
+    pub fn flush(&self) {
+        let summaries = self.0.lock().unwrap().expire(Instant::now());
+
+        for (key, suppressed) in summaries {
+            warn!("Suppressed {} similar messages: {}", suppressed, key);
+        }
+    }
+
+    /// Spawns a thread logging the summaries of the suppressed warnings at the given interval.
+    pub fn spawn_flusher(&self, interval: Duration) {
+        let deduplicator = self.clone();
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            deduplicator.flush();
+        });
+    }
+
+    fn admit(&self, event: &Event<'_>) -> bool {
+        let metadata = event.metadata();
+        if *metadata.level() != Level::WARN || !metadata.target().starts_with(DEDUPLICATED_TARGET) {
+            return true;
+        }
+
+        let mut visitor = KeyVisitor::default();
+        event.record(&mut visitor);
+        let key = WarningKey {
+            message: visitor.message,
+            peer: visitor.peer,
+        };
+
+        self.0.lock().unwrap().admit(key, Instant::now())
+    }
+}
+
+/// A layer forwarding everything to the inner one, except for the suppressed warnings.
+pub struct DedupLayer<L> {
+    inner: L,
+    deduplicator: LogDeduplicator,
+}
+
+impl<S: Subscriber, L: Layer<S>> Layer<S> for DedupLayer<L> {
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        self.inner.register_callsite(metadata)
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.enabled(metadata, ctx)
+    }
+
+    fn new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        self.inner.new_span(attrs, id, ctx)
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        self.inner.max_level_hint()
+    }
+
+    fn on_record(&self, span: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        self.inner.on_record(span, values, ctx)
+    }
+
+    fn on_follows_from(&self, span: &span::Id, follows: &span::Id, ctx: Context<'_, S>) {
+        self.inner.on_follows_from(span, follows, ctx)
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if self.deduplicator.admit(event) {
+            self.inner.on_event(event, ctx)
+        }
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        self.inner.on_enter(id, ctx)
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        self.inner.on_exit(id, ctx)
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        self.inner.on_close(id, ctx)
+    }
+
+    fn on_id_change(&self, old: &span::Id, new: &span::Id, ctx: Context<'_, S>) {
+        self.inner.on_id_change(old, new, ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(message: &str, peer: Option<&str>) -> WarningKey {
+        WarningKey {
+            message: message.into(),
+            peer: peer.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn duplicate_warnings_are_suppressed_and_summarized() {
+        let window = Duration::from_secs(60);
+        let mut state = DedupState::new(window);
+        let start = Instant::now();
+        let message = "Tried updating state of a peer that's not connected";
+        let warning = key(message, Some("1.2.3.4:4131"));
+
+        assert!(state.admit(warning.clone(), start));
+        assert!(!state.admit(warning.clone(), start + Duration::from_secs(1)));
+        assert!(!state.admit(warning.clone(), start + Duration::from_secs(2)));
+
+        // The same message concerning another peer, or another message, is logged.
+        assert!(state.admit(key(message, Some("5.6.7.8:4131")), start));
+        assert!(state.admit(key("Another warning", None), start));
+
+        // Nothing is summarized before the window expires.
+        assert!(state.expire(start + Duration::from_secs(30)).is_empty());
+
+        let summaries = state.expire(start + window);
+        assert_eq!(summaries, vec![(warning.clone(), 2)]);
+
+        // The warning is logged again once its window expired.
+        assert!(state.admit(warning, start + window));
+    }
+
+    #[test]
+    fn recurring_warnings_keep_their_suppressed_count() {
+        let window = Duration::from_secs(60);
+        let mut state = DedupState::new(window);
+        let start = Instant::now();
+        let warning = key("Couldn't connect to a peer", None);
+
+        assert!(state.admit(warning.clone(), start));
+        assert!(!state.admit(warning.clone(), start + Duration::from_secs(1)));
+
+        // The warning recurs after its window expired, but before the summary was logged.
+        assert!(state.admit(warning.clone(), start + window));
+        assert_eq!(state.expire(start + window * 2), vec![(warning, 1)]);
+    }
+}
@@ -19,28 +19,46 @@ extern crate tracing;
 
 use snarkos::{
     cli::CLI,
-    config::{Config, ConfigCli},
+    config::{Config, ConfigCli, Journal},
     display::render_welcome,
     errors::NodeError,
+    logging::{LogDeduplicator, LOG_DEDUP_WINDOW_SECS},
+    service::{self, PidFile, ShutdownSignal},
 };
-use snarkos_consensus::{Consensus, ConsensusParameters, MemoryPool, MerkleTreeLedger};
-use snarkos_network::{config::Config as NodeConfig, MinerInstance, Node, Sync};
-use snarkos_rpc::start_rpc_server;
+use snarkos_consensus::{Consensus, MemoryPool, MerkleTreeLedger};
+use snarkos_network::{
+    config::Config as NodeConfig,
+    AlarmRules,
+    DropJoin,
+    JournalConfig,
+    NetworkError,
+    Node,
+    NodeIdentity,
+    RecommendedPeersConfig,
+    ReloadableConfig,
+    ServiceRegistry,
+    Sync,
+    WebhookConfig,
+};
+use snarkos_rpc::{start_rpc_server, P2pRpcHandler};
 use snarkos_storage::LedgerStorage;
 use snarkvm_algorithms::{CRH, SNARK};
 use snarkvm_dpc::{
     testnet1::{instantiated::Components, parameters::PublicParameters, BaseDPCComponents},
     AccountAddress,
-    Network,
     Storage,
 };
-use snarkvm_posw::PoswMarlin;
 use snarkvm_utilities::{to_bytes, ToBytes};
 
-use std::{net::SocketAddr, str::FromStr, sync::Arc, time::Duration};
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    str::FromStr,
+    sync::Arc,
+};
 
+use clap::ArgMatches;
 use tokio::runtime;
-use tracing_subscriber::EnvFilter;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 fn initialize_logger(config: &Config) {
     match config.node.verbose {
@@ -56,11 +74,16 @@ fn initialize_logger(config: &Config) {
             // disable undesirable logs
             let filter = EnvFilter::from_default_env().add_directive("mio=off".parse().unwrap());
 
-            // initialize tracing
-            tracing_subscriber::fmt()
-                .with_env_filter(filter)
-                .with_target(config.node.verbose == 4)
+            // initialize tracing; the repeated warnings of the network layer are deduplicated
+            let dedup_window = std::time::Duration::from_secs(LOG_DEDUP_WINDOW_SECS);
+            let deduplicator = LogDeduplicator::new(dedup_window);
+            let fmt_layer = tracing_subscriber::fmt::layer().with_target(config.node.verbose == 4);
+
+            tracing_subscriber::registry()
+                .with(filter)
+                .with(deduplicator.layer(fmt_layer))
                 .init();
+            deduplicator.spawn_flusher(dedup_window);
         }
     }
 }
@@ -69,6 +92,31 @@ fn print_welcome(config: &Config) {
     println!("{}", render_welcome(config));
 }
 
+/// Extracts the parts of the configuration that can be reloaded while the node is running.
+fn reloadable_config(config: &Config) -> ReloadableConfig {
+    ReloadableConfig {
+        min_peers: config.p2p.min_peers,
+        max_peers: config.p2p.max_peers,
+        // Like on startup, the bootnodes that can't be parsed are skipped.
+        bootnodes: config
+            .p2p
+            .bootnodes
+            .iter()
+            .filter_map(|addr| addr.parse().ok())
+            .collect(),
+        // The bans are validated by `Config::check`.
+        banned_subnets: config
+            .p2p
+            .banned
+            .iter()
+            .filter_map(|subnet| subnet.parse().ok())
+            .collect(),
+        peer_sync_interval: config.p2p.peer_sync_interval.into(),
+        block_sync_interval: config.p2p.block_sync_interval.into(),
+        mempool_sync_interval: config.p2p.mempool_sync_interval.into(),
+    }
+}
+
 ///
 /// Builds a node from configuration parameters.
 ///
@@ -76,51 +124,177 @@ fn print_welcome(config: &Config) {
 /// 2. Creates new memory pool or uses existing from storage.
 /// 3. Creates sync parameters.
 /// 4. Creates network server.
-/// 5. Starts rpc server thread.
-/// 6. Starts miner thread.
-/// 7. Starts network server listener.
+/// 5. Starts network server listener and services.
+/// 6. Starts rpc server thread.
+/// 7. Reloads the configuration on SIGHUP.
+/// 8. Starts miner thread.
+/// 9. Shuts the node down once the shutdown signal resolves.
 ///
-async fn start_server(config: Config) -> anyhow::Result<()> {
+async fn start_server(config: Config, arguments: ArgMatches<'static>, shutdown: ShutdownSignal) -> anyhow::Result<()> {
     initialize_logger(&config);
 
     print_welcome(&config);
 
-    let address = format!("{}:{}", config.node.ip, config.node.port);
-    let desired_address = address.parse::<SocketAddr>()?;
+    // The IP is parsed on its own, as IPv6 addresses need to be bracketed when joined with the port.
+    let ip = config
+        .node
+        .ip
+        .trim_matches(|c| c == '[' || c == ']')
+        .parse::<IpAddr>()?;
+    // A dual-stack listener needs to be bound to the IPv6 wildcard in order to accept both families.
+    let ip = if config.p2p.dual_stack && ip == IpAddr::V4(Ipv4Addr::UNSPECIFIED) {
+        IpAddr::V6(Ipv6Addr::UNSPECIFIED)
+    } else {
+        ip
+    };
+    let desired_address = SocketAddr::new(ip, config.node.port);
+    let additional_listen_addresses = config
+        .node
+        .listen_addresses
+        .iter()
+        .map(|address| address.parse::<SocketAddr>())
+        .collect::<Result<Vec<_>, _>>()?;
+    let advertised_address = match config.node.advertised_address {
+        Some(ref address) => Some(address.parse::<SocketAddr>()?),
+        None => None,
+    };
+    let socks5_proxy = match config.p2p.proxy {
+        Some(ref address) => Some(address.parse::<SocketAddr>()?),
+        None => None,
+    };
 
+    // The node key is only used to sign the alias; it is kept next to the storage.
+    let node_key_path = config.node.dir.join("node.key");
+    let crash_reports_path = config.node.dir.join("crash_reports");
     let mut path = config.node.dir;
     path.push(&config.node.db);
 
-    let node_config = NodeConfig::new(
+    let mut node_config = NodeConfig::new(
         desired_address,
+        additional_listen_addresses,
+        advertised_address,
         config.p2p.min_peers,
         config.p2p.max_peers,
         config.p2p.bootnodes.clone(),
         config.node.is_bootnode,
+        config.node.is_crawler,
         // Set sync intervals for peers, blocks and transactions (memory pool).
-        Duration::from_secs(config.p2p.peer_sync_interval.into()),
+        config.p2p.peer_sync_interval.into(),
+        config.p2p.nat_traversal,
+        socks5_proxy,
+        config.p2p.no_listen,
+        config.p2p.dual_stack,
     )?;
+    node_config.network_id = config.aleo.network();
+    node_config.banned_subnets.store(Arc::new(
+        config
+            .p2p
+            .banned
+            .iter()
+            .filter_map(|subnet| subnet.parse().ok())
+            .collect(),
+    ));
+    node_config.peer_ban_scope = config.peer_ban_scope()?;
+    if let Some(ref ports) = config.p2p.gossip_ports {
+        node_config.gossip_port_policy = ports.parse()?;
+    }
+    node_config.reachability_probes = config.p2p.reachability_probes;
+    if let Some(slots) = config.p2p.reserved_outbound_slots {
+        node_config.reserved_outbound_slots = slots;
+    }
+    if let Some(handshakes) = config.p2p.max_concurrent_handshakes {
+        node_config.max_concurrent_handshakes = handshakes;
+    }
+    node_config.sync_response_shaping = config.sync_response_shaping()?;
+    node_config.sync_serving_quota = config.sync_serving_quota()?;
+    node_config.relay_policy = config.relay_policy()?;
+    node_config.peer_score_weights = config.p2p.peer_score_weights;
+    node_config.upload_limit = config.p2p.upload_limit.map(|limit| limit.0);
+    node_config.peer_upload_limit = config.p2p.peer_upload_limit.map(|limit| limit.0);
+    node_config.peer_eviction = config.peer_eviction_policy();
+    if let Some(alias) = config.node.alias.clone() {
+        node_config.identity = Some(NodeIdentity::load_or_generate(node_key_path, alias)?);
+    }
+    if let Some(ref source) = config.p2p.recommended_peers {
+        node_config.recommended_peers = Some(RecommendedPeersConfig {
+            source: source.parse()?,
+            trusted_keys: config.p2p.recommended_peers_keys.clone(),
+        });
+    }
+    if let (Some(path), Some(identity)) = (&config.p2p.recommended_peers_export, &node_config.identity) {
+        info!(
+            "Exporting the recommended peers to {}, signed with the key {}",
+            path.display(),
+            identity.public_key_hex()?
+        );
+        node_config.recommended_peers_export = Some(path.clone());
+    }
+    node_config.snapshot_dir = Some(config.node.dir.join("snapshots"));
+    node_config.prune_depth = config.node.prune_depth;
+    node_config.alarms = AlarmRules {
+        max_tip_age: config.alarms.max_tip_age.map(Into::into),
+        min_peers: config.alarms.min_peers.map(Into::into),
+        max_mempool_transactions: config.alarms.max_mempool_transactions,
+        mempool_stuck_for: config.alarms.mempool_stuck_for.into(),
+    };
+    node_config.journal = JournalConfig {
+        memory: Journal::categories(&config.journal.memory)?,
+        memory_capacity: config.journal.memory_capacity,
+        storage: Journal::categories(&config.journal.storage)?,
+        storage_retention: config.journal.storage_retention.into(),
+        webhook: Journal::categories(&config.journal.webhook)?,
+        stream: Journal::categories(&config.journal.stream)?,
+    };
+    if let Some(url) = config.webhook.url.clone() {
+        let events = config
+            .webhook
+            .events
+            .iter()
+            .map(|event| event.parse())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        node_config.webhook = Some(WebhookConfig {
+            url,
+            secret: config.webhook.secret.clone(),
+            events,
+        });
+    }
 
     // Construct the node instance. Note this does not start the network services.
     // This is done early on, so that the local address can be discovered
     // before any other object (miner, RPC) needs to use it.
     let mut node = Node::new(node_config).await?;
 
+    // Panics are recorded in crash reports with the state of the node, so that they can be attached to bug reports.
+    node.install_panic_hook(crash_reports_path);
+
     let is_storage_in_memory = LedgerStorage::IN_MEMORY;
 
     let storage = if is_storage_in_memory {
         Arc::new(MerkleTreeLedger::<LedgerStorage>::new_empty(
             None::<std::path::PathBuf>,
         )?)
+    } else if config.node.read_only {
+        info!("Loading storage at '{}' (read-only)...", path.to_str().unwrap_or_default());
+        Arc::new(MerkleTreeLedger::<LedgerStorage>::open_read_only_at_path(path.clone())?)
     } else {
         info!("Loading storage at '{}'...", path.to_str().unwrap_or_default());
         Arc::new(MerkleTreeLedger::<LedgerStorage>::open_at_path(path.clone())?)
     };
     info!("Storage finished loading");
 
-    // Enable the sync layer.
-    {
-        let memory_pool = MemoryPool::from_storage(&storage).await?;
+    // Crawlers persist the network they discover, along with its periodic snapshots, in the storage.
+    #[cfg(feature = "crawler")]
+    if config.node.is_crawler && !config.node.read_only {
+        node.set_crawler_storage(storage.clone());
+    }
+
+    // Enable the sync layer; crawlers don't participate in consensus, so they don't need it, and
+    // neither do nodes with read-only storage, which can't store any blocks or transactions.
+    if !config.node.is_crawler && !config.node.read_only {
+        let overflow_size = config.p2p.mempool_overflow_size.map(|size| size.0 as usize);
+        let memory_pool =
+            MemoryPool::from_storage(&storage, config.p2p.mempool_size.0 as usize, overflow_size.unwrap_or(0)).await?;
 
         debug!("Loading Aleo parameters...");
         let dpc_parameters = PublicParameters::<Components>::load(!config.miner.is_miner)?;
@@ -137,98 +311,256 @@ async fn start_server(config: Config) -> anyhow::Result<()> {
         let authorized_inner_snark_ids = vec![to_bytes![inner_snark_id]?];
 
         // Set the initial sync parameters.
-        let consensus_params = ConsensusParameters {
-            max_block_size: 1_000_000_000usize,
-            max_nonce: u32::max_value(),
-            target_block_time: 10i64,
-            network_id: Network::from_network_id(config.aleo.network_id),
-            verifier: PoswMarlin::verify_only().expect("could not instantiate PoSW verifier"),
-            authorized_inner_snark_ids,
-        };
+        let consensus_params = config.consensus_parameters(authorized_inner_snark_ids);
 
         let consensus = Arc::new(Consensus {
             ledger: Arc::clone(&storage),
             memory_pool,
             parameters: consensus_params,
             public_parameters: dpc_parameters,
+            preverified_blocks: Default::default(),
+            received_blocks: Default::default(),
+            fork_choices: Default::default(),
+            reorgs: Default::default(),
+            analytics: Default::default(),
+            tip_changes: Default::default(),
         });
 
         let sync = Sync::new(
             consensus,
             config.miner.is_miner,
-            Duration::from_secs(config.p2p.block_sync_interval.into()),
-            Duration::from_secs(config.p2p.mempool_sync_interval.into()),
+            config.p2p.block_sync_interval.into(),
+            config.p2p.mempool_sync_interval.into(),
         );
 
         node.set_sync(sync);
     }
 
+    // The peer limits, the bootnodes and the sync intervals can be reloaded from the config file.
+    node.set_config_source(move || {
+        let config = Config::reload(&arguments).map_err(|e| NetworkError::ConfigReload(e.to_string()))?;
+        Ok(reloadable_config(&config))
+    });
+
     // Initialize metrics framework
     node.initialize_metrics();
 
-    // Start listening for incoming connections.
-    node.listen().await?;
+    let secondary_storage = if !config.rpc.json_rpc && !config.rpc.p2p {
+        None
+    } else if is_storage_in_memory || config.node.read_only {
+        // In-memory and read-only storage don't require a secondary instance.
+        Some(storage)
+    } else {
+        // Open a secondary storage instance to prevent resource sharing and bottle-necking.
+        Some(Arc::new(MerkleTreeLedger::open_secondary_at_path(path.clone())?))
+    };
 
-    // Start RPC thread, if the RPC configuration is enabled.
-    if config.rpc.json_rpc {
-        let secondary_storage = if is_storage_in_memory {
-            // In-memory storage doesn't require a secondary instance.
-            storage
-        } else {
-            // Open a secondary storage instance to prevent resource sharing and bottle-necking.
-            Arc::new(MerkleTreeLedger::open_secondary_at_path(path.clone())?)
-        };
+    // Serve the RPC over the P2P port too, if enabled; it has to be set up before the node starts listening,
+    // as the support for it is advertised during the handshakes.
+    if config.rpc.p2p && !config.node.read_only {
+        match (
+            secondary_storage.clone(),
+            config.rpc.username.clone(),
+            config.rpc.password.clone(),
+        ) {
+            (Some(secondary_storage), Some(username), Some(password)) => {
+                let handler = P2pRpcHandler::new(
+                    secondary_storage,
+                    node.clone(),
+                    username,
+                    password,
+                    config.rpc.admin_tokens.clone(),
+                    config.rpc.max_batch_size as usize,
+                );
+                node.set_rpc_handler(Arc::new(handler));
+
+                info!("Serving RPC requests over the P2P port {}", config.node.port);
+            }
+            _ => warn!("The RPC can't be served over the P2P port without an RPC username and password"),
+        }
+    }
+
+    // The services are started in the order of their dependencies, and stopped in the reverse one.
+    let mut services = ServiceRegistry::new();
+
+    // Start listening for incoming connections and the network services; nodes with read-only storage
+    // only serve RPC requests.
+    if !config.node.read_only {
+        let node_clone = node.clone();
+        let stopped_node = node.clone();
+        services
+            .register("network", &[], move || async move {
+                node_clone.listen().await?;
+                node_clone.start_services().await
+            })
+            .on_stop(move || async move { stopped_node.stop_services().await });
+    }
+    let network_dependencies: &[&'static str] = if config.node.read_only { &[] } else { &["network"] };
 
+    // Start RPC thread, if the RPC configuration is enabled; the known peers are loaded first.
+    if let Some(secondary_storage) = secondary_storage.filter(|_| config.rpc.json_rpc) {
         let rpc_address = format!("{}:{}", config.rpc.ip, config.rpc.port)
             .parse()
             .expect("Invalid RPC server address!");
 
-        let rpc_handle = start_rpc_server(
-            rpc_address,
-            secondary_storage,
-            node.clone(),
-            config.rpc.username,
-            config.rpc.password,
-        );
-        node.register_task(rpc_handle);
-
-        info!("Listening for RPC requests on port {}", config.rpc.port);
+        let node_clone = node.clone();
+        let rpc = config.rpc.clone();
+        let rpc_tasks = DropJoin::new();
+        let service_tasks = rpc_tasks.clone();
+        services
+            .register("rpc", network_dependencies, move || async move {
+                // Serve the RPC on a Unix socket too, or only there, if configured; the socket's permissions
+                // control which local users can access it.
+                #[cfg(unix)]
+                if let Some(ref path) = rpc.unix_socket {
+                    let rpc_handle = snarkos_rpc::start_rpc_server_on_unix_socket(
+                        path,
+                        rpc.unix_socket_mode(),
+                        secondary_storage.clone(),
+                        node_clone.clone(),
+                        rpc.username.clone(),
+                        rpc.password.clone(),
+                        rpc.admin_tokens.clone(),
+                        rpc.max_batch_size as usize,
+                    )?;
+                    service_tasks.append(rpc_handle);
+
+                    info!("Listening for RPC requests on {}", path.display());
+                }
+
+                // Serve the public queries over gRPC too, if configured.
+                #[cfg(feature = "grpc")]
+                if let Some(grpc_port) = rpc.grpc_port {
+                    let grpc_address = format!("{}:{}", rpc.ip, grpc_port)
+                        .parse()
+                        .expect("Invalid gRPC server address!");
+                    let grpc_handle = snarkos_rpc::grpc::start_grpc_server(
+                        grpc_address,
+                        secondary_storage.clone(),
+                        node_clone.clone(),
+                    );
+                    service_tasks.append(grpc_handle);
+
+                    info!("Listening for gRPC requests on port {}", grpc_port);
+                }
+
+                if !rpc.unix_socket_only {
+                    let rpc_handle = start_rpc_server(
+                        rpc_address,
+                        secondary_storage,
+                        node_clone.clone(),
+                        rpc.username,
+                        rpc.password,
+                        rpc.admin_tokens,
+                        rpc.max_batch_size as usize,
+                    );
+                    service_tasks.append(rpc_handle);
+
+                    info!("Listening for RPC requests on port {}", rpc.port);
+                }
+                Ok(())
+            })
+            .on_stop(move || async move { rpc_tasks.flush() });
     }
 
-    // Start the network services
-    node.start_services().await;
+    // Reload the config whenever a SIGHUP is received.
+    #[cfg(unix)]
+    {
+        let node_clone = node.clone();
+        let reload_tasks = DropJoin::new();
+        let service_tasks = reload_tasks.clone();
+        services
+            .register("config reload", &[], move || async move {
+                use tokio::signal::unix::{signal, SignalKind};
+
+                let mut hangups = signal(SignalKind::hangup())?;
+                let reload_task = tokio::spawn(async move {
+                    while hangups.recv().await.is_some() {
+                        if let Err(e) = node_clone.reload_config() {
+                            error!("Couldn't reload the config: {}", e);
+                        }
+                    }
+                });
+                service_tasks.append(reload_task);
+                Ok(())
+            })
+            .on_stop(move || async move { reload_tasks.flush() });
+    }
 
-    // Start the miner task if mining configuration is enabled.
-    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
-    if config.miner.is_miner {
+    // Nodes with a valid miner address can mine; the miner is started right away if mining configuration is
+    // enabled, and can otherwise be started via the `startmining` RPC endpoint.
+    let can_mine = !(config.node.is_bootnode || config.node.is_crawler || config.node.read_only);
+    if can_mine && node.sync().is_some() {
         match AccountAddress::<Components>::from_str(&config.miner.miner_address) {
-            Ok(miner_address) => {
-                let handle = MinerInstance::new(miner_address, node.clone()).spawn();
-                node.register_task(handle);
-            }
-            Err(_) => info!(
+            Ok(miner_address) => node.enable_mining(miner_address),
+            Err(_) if config.miner.is_miner => info!(
                 "Miner not started. Please specify a valid miner address in your ~/.snarkOS/config.toml file or by using the --miner-address option in the CLI."
             ),
+            Err(_) => {}
         }
     }
 
-    std::future::pending::<()>().await;
+    // Start the miner if mining configuration is enabled.
+    if config.miner.is_miner && node.mining().is_some() {
+        let node_clone = node.clone();
+        let stopped_node = node.clone();
+        services
+            .register("miner", network_dependencies, move || async move {
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                node_clone.start_mining(1)?;
+                Ok(())
+            })
+            .on_stop(move || async move {
+                stopped_node.stop_mining();
+            });
+    }
+
+    services.start().await?;
+
+    // Run until the node is asked to shut down, e.g. by the service manager.
+    shutdown.await;
+
+    info!("Shutting down the node...");
+    services.stop().await;
+    node.shut_down().await;
 
     Ok(())
 }
 
-fn main() -> Result<(), NodeError> {
-    let arguments = ConfigCli::args();
-
-    let config: Config = ConfigCli::parse(&arguments)?;
-    config.check().map_err(|e| NodeError::Message(e.to_string()))?;
+/// Runs the node until the given shutdown signal resolves.
+fn run_node(config: Config, arguments: ArgMatches<'static>, shutdown: ShutdownSignal) -> Result<(), NodeError> {
+    // The pidfile is removed once the node has shut down.
+    let _pidfile = match config.node.pidfile_path() {
+        Some(ref path) => Some(PidFile::create(path)?),
+        None => None,
+    };
 
     let runtime = runtime::Builder::new_multi_thread()
         .enable_all()
         .thread_stack_size(8 * 1024 * 1024)
         .build()?;
 
-    runtime.block_on(start_server(config))?;
+    runtime.block_on(start_server(config, arguments, shutdown))?;
 
     Ok(())
 }
+
+fn main() -> Result<(), NodeError> {
+    let arguments = ConfigCli::args();
+
+    let config: Config = ConfigCli::parse(&arguments)?;
+    config.check().map_err(|e| NodeError::Message(e.to_string()))?;
+
+    #[cfg(windows)]
+    if config.node.windows_service {
+        return service::run_windows_service(move |shutdown| run_node(config, arguments, shutdown));
+    }
+
+    // The process needs to be forked before any other threads are started.
+    #[cfg(unix)]
+    if config.node.daemon {
+        service::daemonize(&config.node.dir)?;
+    }
+
+    run_node(config, arguments, Box::pin(service::shutdown_signal()))
+}
@@ -21,6 +21,31 @@ pub const NO_JSONRPC: &str = "[no-jsonrpc] --no-jsonrpc 'Run the node without ru
 pub const IS_BOOTNODE: &str =
     "[is-bootnode] --is-bootnode 'Run the node as a bootnode (IP is hard coded in the protocol)'";
 
+pub const IS_CRAWLER: &str =
+    "[is-crawler] --is-crawler 'Run the node as a crawler mapping the network, without participating in consensus'";
+
 pub const IS_MINER: &str = "[is-miner] --is-miner 'Start mining blocks from this node'";
 
+pub const NAT: &str = "[nat] --nat 'Attempt to map the node's port on the local router via NAT-PMP or UPnP'";
+
+pub const NO_LISTEN: &str = "[no-listen] --no-listen 'Don't accept any inbound connections'";
+
+pub const DUAL_STACK: &str =
+    "[dual-stack] --dual-stack 'Accept both IPv4 and IPv6 connections on the node's port, and connect to peers of both families'";
+
+pub const DAEMON: &str =
+    "[daemon] --daemon 'Detach the node from the terminal and run it in the background, logging to the snarkOS directory (Unix only)'";
+
+pub const WINDOWS_SERVICE: &str =
+    "[windows-service] --windows-service 'Run the node as a Windows service; only meant to be used by the service control manager'";
+
+pub const READ_ONLY: &str =
+    "[read-only] --read-only 'Open the storage read-only and only serve RPC requests, e.g. to inspect another node's storage'";
+
+pub const RPC_OVER_P2P: &str =
+    "[rpc-over-p2p] --rpc-over-p2p 'Also serve the json rpc to authenticated clients over the node's encrypted P2P port'";
+
+pub const RPC_SOCKET_ONLY: &str =
+    "[rpc-socket-only] --rpc-socket-only 'Only serve the json rpc on the Unix domain socket, and not on the rpc port'";
+
 pub const LIST: &str = "[list] -l --list 'List all available releases of snarkOS'";
@@ -28,6 +28,13 @@ pub const PATH: OptionType = (
     &[],
 );
 
+pub const PIDFILE: OptionType = (
+    "[pidfile] --pidfile=[path] 'Specify a file to write the id of the node's process to while it's running'",
+    &[],
+    &[],
+    &[],
+);
+
 pub const IP: OptionType = ("[ip] -i --ip=[ip] 'Specify the ip of your node'", &[], &[], &[]);
 
 pub const PORT: OptionType = (
@@ -37,6 +44,62 @@ pub const PORT: OptionType = (
     &[],
 );
 
+pub const LISTEN: OptionType = (
+    "[listen] --listen=[ip:port] 'Specify one or more additional addresses to accept connections on'",
+    &[],
+    &[],
+    &[],
+);
+
+pub const ADVERTISE: OptionType = (
+    "[advertise] --advertise=[ip:port] 'Specify the address other nodes should use to connect to your node'",
+    &[],
+    &[],
+    &[],
+);
+
+pub const ALIAS: OptionType = (
+    "[alias] --alias=[alias] 'Specify a name for your node, signed with its node key and shown to peers as unverified'",
+    &[],
+    &[],
+    &[],
+);
+
+pub const PRUNE_DEPTH: OptionType = (
+    "[prune-depth] --prune-depth=[blocks] 'Prune the transactions of the blocks deeper than the given number of blocks from the storage, keeping their headers'",
+    &["read-only"],
+    &[],
+    &[],
+);
+
+pub const PROXY: OptionType = (
+    "[proxy] --proxy=[ip:port] 'Specify the address of a SOCKS5 proxy, e.g. Tor, to route all outbound connections through'",
+    &[],
+    &[],
+    &[],
+);
+
+pub const WEBHOOK: OptionType = (
+    "[webhook] --webhook=[url] 'Specify a URL to POST notifications about notable node events to'",
+    &[],
+    &[],
+    &[],
+);
+
+pub const WEBHOOK_SECRET: OptionType = (
+    "[webhook-secret] --webhook-secret=[secret] 'Specify a secret to sign the webhook notifications with'",
+    &[],
+    &[],
+    &["webhook"],
+);
+
+pub const WEBHOOK_EVENTS: OptionType = (
+    "[webhook-events] --webhook-events=[events] 'Specify the events to notify the webhook of: out_of_sync, low_peer_count, potential_fork, reorg, peer_banned, alarm, crash (all by default)'",
+    &[],
+    &[],
+    &["webhook"],
+);
+
 pub const CONNECT: OptionType = (
     "[connect] --connect=[ip] 'Specify one or more node ip addresses to connect to on startup'",
     &[],
@@ -52,7 +115,14 @@ pub const MINER_ADDRESS: OptionType = (
 );
 
 pub const MEMPOOL_INTERVAL: OptionType = (
-    "[mempool-interval] --mempool-interval=[mempool-interval] 'Specify the frequency in seconds the node should fetch a sync node's mempool'",
+    "[mempool-interval] --mempool-interval=[mempool-interval] 'Specify the frequency the node should fetch a sync node's mempool, e.g. 30s or 1m; plain numbers are in seconds'",
+    &[],
+    &[],
+    &[],
+);
+
+pub const MEMPOOL_SIZE: OptionType = (
+    "[mempool-size] --mempool-size=[mempool-size] 'Specify the maximum size of the node's mempool, e.g. 64MiB; plain numbers are in megabytes. The lowest-fee transactions are evicted when it's full'",
     &[],
     &[],
     &[],
@@ -73,7 +143,7 @@ pub const MAX_PEERS: OptionType = (
 );
 
 pub const NETWORK: OptionType = (
-    "[network] --network=[network-id] 'Specify the network id (default = 1) of the node'",
+    "[network] --network=[network] 'Specify the network of the node: mainnet, testnet1, devnet or a numeric id (default = testnet1)'",
     &[],
     &[],
     &[],
@@ -107,9 +177,54 @@ pub const RPC_PASSWORD: OptionType = (
     &["rpc-username"],
 );
 
+pub const RPC_MAX_BATCH_SIZE: OptionType = (
+    "[rpc-max-batch-size] --rpc-max-batch-size=[size] 'Specify the maximum number of requests in a json rpc batch request'",
+    &["no-jsonrpc"],
+    &[],
+    &[],
+);
+
+pub const RPC_SOCKET: OptionType = (
+    "[rpc-socket] --rpc-socket=[path] 'Also serve the json rpc on a Unix domain socket at the given path (Unix only)'",
+    &["no-jsonrpc"],
+    &[],
+    &[],
+);
+
 pub const VERBOSE: OptionType = (
     "[verbose] --verbose=[verbose] 'Specify the verbosity (default = 1) of the node'",
     &[],
     &["0", "1", "2", "3", "4"],
     &[],
 );
+
+// Peers
+
+pub const PEER_LIST: OptionType = ("<file> 'Specify the JSON file listing the peers'", &[], &[], &[]);
+
+// Snapshots
+
+pub const SNAPSHOT_FILE: OptionType = ("<file> 'Specify the ledger snapshot file'", &[], &[], &[]);
+
+pub const SNAPSHOT_HEIGHT: OptionType = (
+    "[height] --height=[height] 'Specify the height of the block to export the ledger at; the latest one by default'",
+    &[],
+    &[],
+    &[],
+);
+
+pub const SNAPSHOT_BLOCK_HASH: OptionType = (
+    "<block-hash> --block-hash=<hash> 'Specify the hash the latest block in the snapshot must have, as obtained from a trusted node'",
+    &[],
+    &[],
+    &[],
+);
+
+pub const RPC_NODE: OptionType = ("<node> 'Specify the address of the node's P2P port'", &[], &[], &[]);
+
+pub const RPC_REQUEST: OptionType = (
+    "<request> 'Specify the JSON-RPC request, or a batch of them'",
+    &[],
+    &[],
+    &[],
+);
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::parameters::{flag, types::*};
+use crate::parameters::{flag, option, types::*};
 
 use clap::AppSettings;
 
@@ -32,3 +32,63 @@ pub const UPDATE: SubCommandType = (
         AppSettings::DisableVersion,
     ],
 );
+
+pub const EXPORT_PEERS: SubCommandType = (
+    "export-peers",
+    "Export the peers known to the node, along with their connection history, to a JSON file",
+    &[option::PEER_LIST],
+    &[],
+    &[
+        AppSettings::ColoredHelp,
+        AppSettings::DisableHelpSubcommand,
+        AppSettings::DisableVersion,
+    ],
+);
+
+pub const IMPORT_PEERS: SubCommandType = (
+    "import-peers",
+    "Import the peers listed in a JSON file, e.g. one exported by another node, while the node is stopped",
+    &[option::PEER_LIST],
+    &[],
+    &[
+        AppSettings::ColoredHelp,
+        AppSettings::DisableHelpSubcommand,
+        AppSettings::DisableVersion,
+    ],
+);
+
+pub const EXPORT_SNAPSHOT: SubCommandType = (
+    "export-snapshot",
+    "Export the canon chain to a snapshot file that new nodes can be bootstrapped from",
+    &[option::SNAPSHOT_FILE, option::SNAPSHOT_HEIGHT],
+    &[],
+    &[
+        AppSettings::ColoredHelp,
+        AppSettings::DisableHelpSubcommand,
+        AppSettings::DisableVersion,
+    ],
+);
+
+pub const IMPORT_SNAPSHOT: SubCommandType = (
+    "import-snapshot",
+    "Bootstrap the ledger of a new node from a snapshot file, after verifying it",
+    &[option::SNAPSHOT_FILE, option::SNAPSHOT_BLOCK_HASH],
+    &[],
+    &[
+        AppSettings::ColoredHelp,
+        AppSettings::DisableHelpSubcommand,
+        AppSettings::DisableVersion,
+    ],
+);
+
+pub const RPC: SubCommandType = (
+    "rpc",
+    "Send a JSON-RPC request to a node over its P2P port, using the configured RPC username and password",
+    &[option::RPC_NODE, option::RPC_REQUEST],
+    &[],
+    &[
+        AppSettings::ColoredHelp,
+        AppSettings::DisableHelpSubcommand,
+        AppSettings::DisableVersion,
+    ],
+);
@@ -0,0 +1,118 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{config::Config, errors::CliError};
+use snarkos_consensus::MerkleTreeLedger;
+use snarkos_network::{read_peer_list, write_peer_list, AddressManager, NetworkError, Subnet};
+use snarkos_storage::LedgerStorage;
+use snarkvm_dpc::errors::StorageError;
+
+use clap::ArgMatches;
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+fn storage_err(error: StorageError) -> CliError {
+    CliError::Crate("snarkos-storage", error.to_string())
+}
+
+fn network_err(error: NetworkError) -> CliError {
+    CliError::Crate("snarkos-network", error.to_string())
+}
+
+/// Exports and imports the peer book persisted in the node's storage; a running node can do the same
+/// via the `exportpeers` and `importpeers` RPC endpoints.
+pub struct PeersCLI;
+
+impl PeersCLI {
+    /// Writes the peers persisted in the given storage to a JSON file; the storage is opened read-only,
+    /// so the node may keep running.
+    fn export_peers(storage_path: &Path, file: &Path) -> Result<usize, CliError> {
+        let storage = MerkleTreeLedger::<LedgerStorage>::open_read_only_at_path(storage_path).map_err(storage_err)?;
+
+        let peers = match storage.get_peer_book().map_err(storage_err)? {
+            Some(bytes) => AddressManager::deserialize(&bytes).map_err(network_err)?.known_peers(),
+            None => vec![],
+        };
+        write_peer_list(file, &peers).map_err(network_err)?;
+
+        Ok(peers.len())
+    }
+
+    /// Adds the peers listed in a JSON file to the peer book persisted in the given storage, skipping the
+    /// banned ones; the storage is created if it doesn't exist yet, so that new nodes can be seeded.
+    fn import_peers(storage_path: &Path, file: &Path, banned: &[Subnet]) -> Result<usize, CliError> {
+        let mut peers = read_peer_list(file).map_err(network_err)?;
+
+        let storage = MerkleTreeLedger::<LedgerStorage>::open_at_path(storage_path).map_err(storage_err)?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs() as i64)
+            .unwrap_or_default();
+        let stored_bans = storage
+            .get_bans()
+            .map_err(storage_err)?
+            .into_iter()
+            .filter(|(_, expiry)| *expiry > now)
+            .filter_map(|(subnet, _)| subnet.parse::<Subnet>().ok())
+            .collect::<Vec<_>>();
+        peers.retain(|peer| {
+            let ip = peer.address.ip();
+            !banned
+                .iter()
+                .chain(stored_bans.iter())
+                .any(|subnet| subnet.contains(ip))
+        });
+
+        let mut address_manager = match storage.get_peer_book().map_err(storage_err)? {
+            Some(bytes) => AddressManager::deserialize(&bytes).map_err(network_err)?,
+            None => Default::default(),
+        };
+        let imported = address_manager.import(&peers);
+
+        let bytes = address_manager.serialize().map_err(network_err)?;
+        storage.save_peer_book_to_storage(bytes).map_err(storage_err)?;
+
+        Ok(imported)
+    }
+
+    pub fn parse(config: &Config, command: &str, arguments: &ArgMatches) -> Result<(), CliError> {
+        let storage_path = config.node.dir.join(&config.node.db);
+        let file = PathBuf::from(arguments.value_of("file").unwrap_or_default());
+
+        match command {
+            "export-peers" => {
+                let count = Self::export_peers(&storage_path, &file)?;
+                println!("Exported {} peers to '{}'", count, file.display());
+            }
+            "import-peers" => {
+                let banned = config
+                    .p2p
+                    .banned
+                    .iter()
+                    .filter_map(|subnet| subnet.parse().ok())
+                    .collect::<Vec<_>>();
+                let count = Self::import_peers(&storage_path, &file, &banned)?;
+                println!("Imported {} new peers from '{}'", count, file.display());
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
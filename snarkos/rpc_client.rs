@@ -0,0 +1,69 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{config::Config, errors::CliError};
+use snarkos_network::{NetworkError, RpcClient};
+
+use clap::ArgMatches;
+use std::net::SocketAddr;
+use tokio::runtime;
+
+fn network_err(error: NetworkError) -> CliError {
+    CliError::Crate("snarkos-network", error.to_string())
+}
+
+/// Sends a JSON-RPC request to a node serving the RPC over its P2P port, authenticating with the RPC
+/// username and password from the config, e.g. if the node doesn't expose its RPC port.
+pub struct RpcClientCLI;
+
+impl RpcClientCLI {
+    async fn request(address: SocketAddr, username: &str, password: &str, request: &str) -> Result<String, CliError> {
+        let mut client = RpcClient::connect(address, username, password)
+            .await
+            .map_err(network_err)?;
+        let response = client.request(request.as_bytes().to_vec()).await.map_err(network_err)?;
+
+        Ok(String::from_utf8_lossy(&response).into_owned())
+    }
+
+    pub fn parse(config: &Config, arguments: &ArgMatches) -> Result<(), CliError> {
+        let address = arguments
+            .value_of("node")
+            .unwrap_or_default()
+            .parse::<SocketAddr>()
+            .map_err(|e| CliError::Crate("std::net::AddrParseError", e.to_string()))?;
+        let request = arguments.value_of("request").unwrap_or_default();
+
+        let (username, password) = match (&config.rpc.username, &config.rpc.password) {
+            (Some(username), Some(password)) => (username, password),
+            _ => {
+                return Err(CliError::Crate(
+                    "snarkos-rpc",
+                    "an RPC username and password are required".into(),
+                ))
+            }
+        };
+
+        let runtime = runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| CliError::Crate("tokio", e.to_string()))?;
+        let response = runtime.block_on(Self::request(address, username, password, request))?;
+        println!("{}", response);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,236 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+//! Support for running the node under a system service manager: detaching it from the terminal on Unix,
+//! running it as a Windows service, and shutting it down gracefully when asked to.
+
+use crate::errors::NodeError;
+
+use std::{
+    fs,
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+    process,
+};
+
+/// A future resolving once the node is asked to shut down.
+pub type ShutdownSignal = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Resolves once the process receives a `SIGTERM` (on Unix) or a Ctrl-C.
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {},
+                    _ = terminate.recv() => {},
+                }
+            }
+            Err(e) => {
+                error!("Couldn't listen for SIGTERM: {}", e);
+                tokio::signal::ctrl_c().await.ok();
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await.ok();
+}
+
+/// A file containing the id of the node's process; it is removed when dropped, i.e. once the node shuts down.
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    /// Writes the id of the current process to the given file; fails if the file belongs to a node that's
+    /// still running, while stale ones are overwritten.
+    pub fn create<P: Into<PathBuf>>(path: P) -> Result<Self, NodeError> {
+        let path = path.into();
+
+        if let Some(pid) = fs::read_to_string(&path)
+            .ok()
+            .and_then(|pid| pid.trim().parse::<u32>().ok())
+        {
+            if pid != process::id() && is_running(pid) {
+                return Err(NodeError::Message(format!(
+                    "the node is already running as process {}, according to {}",
+                    pid,
+                    path.display()
+                )));
+            }
+        }
+
+        fs::write(&path, format!("{}\n", process::id()))?;
+
+        Ok(Self { path })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            warn!("Couldn't remove the pidfile {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// Checks whether a process with the given id is running; only Linux exposes that without platform-specific
+/// calls, so elsewhere the process is assumed to be gone.
+fn is_running(pid: u32) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+/// Detaches the node from the terminal and runs it in the background, with its output redirected to
+/// the `snarkos.log` file in the given directory. It must be called before the runtime is started, as
+/// only the calling thread survives the fork.
+#[cfg(unix)]
+pub fn daemonize(dir: &Path) -> Result<(), NodeError> {
+    fs::create_dir_all(dir)?;
+
+    let log = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join("snarkos.log"))?;
+
+    daemonize::Daemonize::new()
+        .working_directory(dir)
+        .umask(0o027)
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        .start()
+        .map_err(|e| NodeError::Message(format!("couldn't daemonize the node: {}", e)))
+}
+
+#[cfg(windows)]
+pub use self::windows::run_windows_service;
+
+#[cfg(windows)]
+mod windows {
+    use super::ShutdownSignal;
+    use crate::errors::NodeError;
+
+    use once_cell::sync::OnceCell;
+    use std::{ffi::OsString, sync::Mutex, time::Duration};
+    use tokio::sync::oneshot;
+    use windows_service::{
+        define_windows_service,
+        service::{ServiceControl, ServiceControlAccept, ServiceExitCode, ServiceState, ServiceStatus, ServiceType},
+        service_control_handler::{self, ServiceControlHandlerResult, ServiceStatusHandle},
+        service_dispatcher,
+    };
+
+    /// The name the node is registered with in the service control manager.
+    const SERVICE_NAME: &str = "snarkos";
+
+    type RunNode = Box<dyn FnOnce(ShutdownSignal) -> Result<(), NodeError> + Send>;
+
+    /// The node run by the service; the service entry point can't capture anything.
+    static RUN_NODE: OnceCell<Mutex<Option<RunNode>>> = OnceCell::new();
+
+    define_windows_service!(ffi_service_main, service_main);
+
+    /// Runs the node as a Windows service, blocking until the service is stopped; the node is given a
+    /// signal that resolves once the service control manager asks it to stop.
+    pub fn run_windows_service<F>(run_node: F) -> Result<(), NodeError>
+    where
+        F: FnOnce(ShutdownSignal) -> Result<(), NodeError> + Send + 'static,
+    {
+        RUN_NODE
+            .set(Mutex::new(Some(Box::new(run_node))))
+            .map_err(|_| NodeError::Message("the service was already started".into()))?;
+
+        service_dispatcher::start(SERVICE_NAME, ffi_service_main).map_err(service_error)
+    }
+
+    fn service_main(_arguments: Vec<OsString>) {
+        if let Err(e) = run_service() {
+            error!("The service failed: {}", e);
+        }
+    }
+
+    fn run_service() -> Result<(), NodeError> {
+        let run_node = RUN_NODE
+            .get()
+            .and_then(|run_node| run_node.lock().unwrap().take())
+            .ok_or_else(|| NodeError::Message("the service has no node to run".into()))?;
+
+        let (stop_sender, stop_receiver) = oneshot::channel();
+        let stop_sender = Mutex::new(Some(stop_sender));
+        let status_handle = service_control_handler::register(SERVICE_NAME, move |control| match control {
+            ServiceControl::Stop | ServiceControl::Shutdown => {
+                if let Some(stop_sender) = stop_sender.lock().unwrap().take() {
+                    stop_sender.send(()).ok();
+                }
+                ServiceControlHandlerResult::NoError
+            }
+            ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+            _ => ServiceControlHandlerResult::NotImplemented,
+        })
+        .map_err(service_error)?;
+
+        set_status(
+            &status_handle,
+            ServiceState::Running,
+            ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN,
+            ServiceExitCode::Win32(0),
+        )?;
+
+        let result = run_node(Box::pin(async move {
+            stop_receiver.await.ok();
+        }));
+
+        let exit_code = match &result {
+            Ok(()) => ServiceExitCode::Win32(0),
+            Err(_) => ServiceExitCode::ServiceSpecific(1),
+        };
+        set_status(
+            &status_handle,
+            ServiceState::Stopped,
+            ServiceControlAccept::empty(),
+            exit_code,
+        )?;
+
+        result
+    }
+
+    fn set_status(
+        status_handle: &ServiceStatusHandle,
+        state: ServiceState,
+        controls_accepted: ServiceControlAccept,
+        exit_code: ServiceExitCode,
+    ) -> Result<(), NodeError> {
+        status_handle
+            .set_service_status(ServiceStatus {
+                service_type: ServiceType::OWN_PROCESS,
+                current_state: state,
+                controls_accepted,
+                exit_code,
+                checkpoint: 0,
+                wait_hint: Duration::default(),
+                process_id: None,
+            })
+            .map_err(service_error)
+    }
+
+    fn service_error(error: windows_service::Error) -> NodeError {
+        NodeError::Message(format!("Windows service error: {}", error))
+    }
+}
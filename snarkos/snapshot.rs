@@ -0,0 +1,115 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{config::Config, errors::CliError};
+use snarkos_consensus::MerkleTreeLedger;
+use snarkos_storage::{LedgerStorage, SnapshotSummary};
+use snarkvm_dpc::{errors::StorageError, testnet1::instantiated::Tx, Block, BlockHeader, BlockHeaderHash};
+
+use clap::ArgMatches;
+use std::path::{Path, PathBuf};
+
+fn storage_err(error: StorageError) -> CliError {
+    CliError::Crate("snarkos-storage", error.to_string())
+}
+
+/// Exports the node's canon chain to a snapshot file, and bootstraps new nodes from one, so that they only need
+/// to sync the blocks above its height; a running node can export one via the `exportsnapshot` RPC
+/// endpoint.
+pub struct SnapshotCLI;
+
+impl SnapshotCLI {
+    /// Writes the canon chain up to the block at the given height, or the latest one, to a snapshot file; the
+    /// storage is opened read-only, so the node may keep running.
+    fn export_snapshot(storage_path: &Path, file: &Path, height: Option<u32>) -> Result<SnapshotSummary, CliError> {
+        let storage = MerkleTreeLedger::<LedgerStorage>::open_read_only_at_path(storage_path).map_err(storage_err)?;
+        let height = height.unwrap_or_else(|| storage.get_current_block_height());
+
+        storage.export_snapshot(height, file).map_err(storage_err)
+    }
+
+    /// Creates the node's ledger from a snapshot file, which is verified first; the storage must not exist yet.
+    /// The headers of the snapshot's blocks are checked against the consensus parameters, while their
+    /// transactions are only checked against the headers' merkle roots.
+    fn import_snapshot(
+        config: &Config,
+        storage_path: &Path,
+        file: &Path,
+        block_hash: &str,
+    ) -> Result<SnapshotSummary, CliError> {
+        let block_hash = match hex::decode(block_hash) {
+            Ok(bytes) if bytes.len() == 32 => BlockHeaderHash::new(bytes),
+            _ => {
+                return Err(CliError::InvalidValue(
+                    "--block-hash",
+                    format!("'{}' isn't a block hash", block_hash),
+                ))
+            }
+        };
+
+        // The authorized inner circuits only matter to the verification of transactions.
+        let consensus_params = config.consensus_parameters(vec![]);
+        let verify_block = |block: &Block<Tx>, parent: &BlockHeader| {
+            consensus_params
+                .verify_block_header(block, parent)
+                .map_err(|e| e.to_string())
+        };
+        let (_, summary) =
+            MerkleTreeLedger::<LedgerStorage>::import_snapshot(storage_path, file, &block_hash, verify_block)
+                .map_err(storage_err)?;
+
+        Ok(summary)
+    }
+
+    pub fn parse(config: &Config, command: &str, arguments: &ArgMatches) -> Result<(), CliError> {
+        let storage_path = config.node.dir.join(&config.node.db);
+        let file = PathBuf::from(arguments.value_of("file").unwrap_or_default());
+
+        match command {
+            "export-snapshot" => {
+                let height = match arguments.value_of("height") {
+                    Some(height) => Some(
+                        height
+                            .parse()
+                            .map_err(|_| CliError::InvalidValue("--height", format!("'{}' isn't a height", height)))?,
+                    ),
+                    None => None,
+                };
+                let summary = Self::export_snapshot(&storage_path, &file, height)?;
+                println!(
+                    "Exported the ledger at block {} ({}) to '{}', with the checksum {}",
+                    summary.height,
+                    summary.block_hash,
+                    file.display(),
+                    hex::encode(summary.checksum)
+                );
+            }
+            "import-snapshot" => {
+                let block_hash = arguments.value_of("block-hash").unwrap_or_default();
+                let summary = Self::import_snapshot(config, &storage_path, &file, block_hash)?;
+                println!(
+                    "Imported the ledger at block {} ({}) from '{}'; the node syncs the subsequent blocks once started",
+                    summary.height,
+                    summary.block_hash,
+                    file.display()
+                );
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
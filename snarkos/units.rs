@@ -0,0 +1,319 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+//! Typed configuration values that can be written in a human-friendly way, e.g. `30s` or `8MiB`.
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr, time::Duration};
+
+/// The units a `HumanDuration` can be expressed in, from the largest to the smallest one.
+const DURATION_UNITS: &[(&str, u64)] = &[
+    ("d", 86_400_000),
+    ("h", 3_600_000),
+    ("m", 60_000),
+    ("s", 1000),
+    ("ms", 1),
+];
+
+/// The units a `ByteSize` can be expressed in, from the largest to the smallest one.
+const SIZE_UNITS: &[(&str, u64)] = &[
+    ("GiB", 1 << 30),
+    ("GB", 1_000_000_000),
+    ("MiB", 1 << 20),
+    ("MB", 1_000_000),
+    ("KiB", 1 << 10),
+    ("KB", 1000),
+    ("B", 1),
+];
+
+/// Splits the given value into its number and its unit, e.g. `"30s"` into `(30, "s")`.
+fn split_value(value: &str) -> Result<(u64, &str), String> {
+    let value = value.trim();
+    let unit_start = value.find(|c: char| !c.is_ascii_digit()).unwrap_or_else(|| value.len());
+    let (number, unit) = value.split_at(unit_start);
+
+    let number = number
+        .parse::<u64>()
+        .map_err(|_| format!("'{}' doesn't start with a non-negative integer", value))?;
+
+    Ok((number, unit.trim()))
+}
+
+/// Converts the given value into the smallest of the given units.
+fn parse_value(value: &str, units: &[(&str, u64)], default_unit: &str) -> Result<u64, String> {
+    let (number, unit) = split_value(value)?;
+    let unit = if unit.is_empty() { default_unit } else { unit };
+
+    let multiplier = units
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(unit))
+        .map(|(_, multiplier)| *multiplier)
+        .ok_or_else(|| {
+            let names = units.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+            format!(
+                "unknown unit '{}' in '{}'; expected one of {}",
+                unit,
+                value,
+                names.join(", ")
+            )
+        })?;
+
+    number
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("'{}' is too large", value))
+}
+
+/// Formats the given value using the largest of the given units it's a multiple of.
+fn format_value(f: &mut fmt::Formatter, value: u64, units: &[(&str, u64)]) -> fmt::Result {
+    match units
+        .iter()
+        .find(|(_, multiplier)| value != 0 && value % multiplier == 0)
+    {
+        Some((name, multiplier)) => write!(f, "{}{}", value / multiplier, name),
+        None => write!(f, "{}{}", value, units.last().unwrap().0),
+    }
+}
+
+/// A duration, e.g. `500ms`, `30s` or `5m`; plain numbers are in seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HumanDuration(pub Duration);
+
+impl HumanDuration {
+    pub const fn from_secs(secs: u64) -> Self {
+        Self(Duration::from_secs(secs))
+    }
+}
+
+impl From<HumanDuration> for Duration {
+    fn from(duration: HumanDuration) -> Self {
+        duration.0
+    }
+}
+
+impl FromStr for HumanDuration {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_value(s, DURATION_UNITS, "s").map(|millis| Self(Duration::from_millis(millis)))
+    }
+}
+
+impl fmt::Display for HumanDuration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        format_value(f, self.0.as_millis() as u64, DURATION_UNITS)
+    }
+}
+
+/// A size in bytes, e.g. `512KiB`, `8MiB` or `1GB`; plain numbers are in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+    pub const fn from_mib(mib: u64) -> Self {
+        Self(mib << 20)
+    }
+
+    /// Parses the given size, interpreting a plain number as the given unit instead of bytes.
+    pub fn parse_with_default_unit(s: &str, default_unit: &str) -> Result<Self, String> {
+        parse_value(s, SIZE_UNITS, default_unit).map(Self)
+    }
+}
+
+impl FromStr for ByteSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with_default_unit(s, "B")
+    }
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Only the binary units are used, so that the values are read back as written.
+        let binary_units = SIZE_UNITS
+            .iter()
+            .copied()
+            .filter(|(name, _)| name.ends_with("iB") || *name == "B")
+            .collect::<Vec<_>>();
+        format_value(f, self.0, &binary_units)
+    }
+}
+
+/// Implements serde support for a typed value; it's serialized as a string, and deserialized from either
+/// a string or a plain number, so that the configs written before the units were introduced stay valid.
+macro_rules! impl_serde {
+    ($type:ty, $expecting:expr) => {
+        impl Serialize for $type {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $type {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct Visitor;
+
+                impl<'de> de::Visitor<'de> for Visitor {
+                    type Value = $type;
+
+                    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                        f.write_str($expecting)
+                    }
+
+                    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                        value.parse().map_err(E::custom)
+                    }
+
+                    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+                        self.visit_str(&value.to_string())
+                    }
+
+                    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+                        if value < 0 {
+                            Err(E::custom(format!("'{}' is negative", value)))
+                        } else {
+                            self.visit_u64(value as u64)
+                        }
+                    }
+                }
+
+                deserializer.deserialize_any(Visitor)
+            }
+        }
+    };
+}
+
+impl_serde!(HumanDuration, "a duration, e.g. \"30s\" or \"5m\"");
+impl_serde!(ByteSize, "a size, e.g. \"512KiB\" or \"8MiB\"");
+
+/// Deserializes a size whose plain numbers are in mebibytes; it's used for the sizes that were given in megabytes
+/// before the units were introduced, so that they're read the same way from the command line and the config file.
+pub fn deserialize_mib<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ByteSize, D::Error> {
+    struct Visitor;
+
+    impl<'de> de::Visitor<'de> for Visitor {
+        type Value = ByteSize;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a size, e.g. \"64MiB\", or a number of mebibytes")
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+            ByteSize::parse_with_default_unit(value, "MiB").map_err(E::custom)
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+            self.visit_str(&value.to_string())
+        }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+            if value < 0 {
+                Err(E::custom(format!("'{}' is negative", value)))
+            } else {
+                self.visit_u64(value as u64)
+            }
+        }
+    }
+
+    deserializer.deserialize_any(Visitor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations_are_parsed() {
+        assert_eq!("30s".parse(), Ok(HumanDuration::from_secs(30)));
+        assert_eq!("5m".parse(), Ok(HumanDuration::from_secs(300)));
+        assert_eq!(" 2 h ".parse(), Ok(HumanDuration::from_secs(7200)));
+        assert_eq!("15".parse(), Ok(HumanDuration::from_secs(15)));
+        assert_eq!("250ms".parse(), Ok(HumanDuration(Duration::from_millis(250))));
+
+        assert!("5x".parse::<HumanDuration>().is_err());
+        assert!("-5s".parse::<HumanDuration>().is_err());
+        assert!("s".parse::<HumanDuration>().is_err());
+    }
+
+    #[test]
+    fn sizes_are_parsed() {
+        assert_eq!("8MiB".parse(), Ok(ByteSize(8 * 1024 * 1024)));
+        assert_eq!("8mb".parse(), Ok(ByteSize(8_000_000)));
+        assert_eq!("512KiB".parse(), Ok(ByteSize(512 * 1024)));
+        assert_eq!("100".parse(), Ok(ByteSize(100)));
+        assert_eq!(
+            ByteSize::parse_with_default_unit("32", "MiB"),
+            Ok(ByteSize::from_mib(32))
+        );
+
+        assert!("8XB".parse::<ByteSize>().is_err());
+        assert!("99999999999GiB".parse::<ByteSize>().is_err());
+    }
+
+    #[test]
+    fn values_are_displayed_in_the_largest_exact_unit() {
+        assert_eq!(HumanDuration::from_secs(300).to_string(), "5m");
+        assert_eq!(HumanDuration::from_secs(90).to_string(), "90s");
+        assert_eq!(HumanDuration(Duration::from_millis(1500)).to_string(), "1500ms");
+        assert_eq!(HumanDuration::from_secs(0).to_string(), "0ms");
+        assert_eq!(ByteSize::from_mib(32).to_string(), "32MiB");
+        assert_eq!(ByteSize(1000).to_string(), "1000B");
+    }
+
+    #[test]
+    fn values_roundtrip_through_toml() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Values {
+            interval: HumanDuration,
+            size: ByteSize,
+        }
+
+        let values = Values {
+            interval: HumanDuration::from_secs(15),
+            size: ByteSize::from_mib(8),
+        };
+        let toml_string = toml::to_string(&values).unwrap();
+        assert_eq!(toml::from_str::<Values>(&toml_string).unwrap(), values);
+
+        // plain numbers are still accepted
+        let legacy: Values = toml::from_str("interval = 15\nsize = 1024").unwrap();
+        assert_eq!(legacy.interval, HumanDuration::from_secs(15));
+        assert_eq!(legacy.size, ByteSize(1024));
+
+        // invalid values name the offending field
+        let error = toml::from_str::<Values>("interval = \"15x\"\nsize = 1024").unwrap_err();
+        assert!(error.to_string().contains("interval"));
+    }
+
+    #[test]
+    fn plain_sizes_can_be_read_in_mebibytes() {
+        #[derive(Debug, Deserialize)]
+        struct Values {
+            #[serde(deserialize_with = "deserialize_mib")]
+            size: ByteSize,
+        }
+
+        let size = |s: &str| toml::from_str::<Values>(s).unwrap().size;
+
+        assert_eq!(size("size = 32"), ByteSize::from_mib(32));
+        assert_eq!(size("size = \"32\""), ByteSize::from_mib(32));
+        assert_eq!(size("size = \"512KiB\""), ByteSize(512 * 1024));
+
+        // a written-out config is read back as is
+        let written = format!("size = \"{}\"", ByteSize(1536 << 10));
+        assert_eq!(size(&written), ByteSize(1536 << 10));
+    }
+}
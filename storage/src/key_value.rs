@@ -29,11 +29,25 @@ pub const COL_MEMO: u32 = 7; // Memo -> index
 pub const COL_DIGEST: u32 = 8; // Ledger digest -> index
 pub const COL_RECORDS: u32 = 9; // commitment -> record bytes
 pub const COL_CHILD_HASHES: u32 = 10; // block hash -> vector of potential child hashes
-pub const NUM_COLS: u32 = 11;
+pub const COL_BANS: u32 = 11; // banned IP or subnet -> ban expiry timestamp
+pub const COL_MINING_HISTORY: u32 = 12; // block height -> mining events
+pub const COL_CM_TRANSACTION: u32 = 13; // commitment -> id of the canon transaction that created it
+pub const COL_SN_TRANSACTION: u32 = 14; // SN -> id of the canon transaction that spent it
+pub const COL_NETWORK_SNAPSHOTS: u32 = 15; // UNIX timestamp -> crawled network topology
+pub const COL_JOURNAL: u32 = 16; // UNIX timestamp in milliseconds and sequence number -> journaled node event
+pub const COL_MEMPOOL_OVERFLOW: u32 = 17; // Transaction id -> memory pool entry spilled out of memory
+pub const COL_BRANCH_TIPS: u32 = 18; // Block hash -> nothing, for the non-canon blocks without known children
+pub const NUM_COLS: u32 = 19;
 
+pub const KEY_ANNOUNCED_ADDRESS: &str = "ANNOUNCED_ADDRESS";
 pub const KEY_BEST_BLOCK_NUMBER: &str = "BEST_BLOCK_NUMBER";
+pub const KEY_KNOWN_NETWORK: &str = "KNOWN_NETWORK";
 pub const KEY_MEMORY_POOL: &str = "MEMORY_POOL";
+pub const KEY_MINING_HISTORY_TOP: &str = "MINING_HISTORY_TOP";
 pub const KEY_PEER_BOOK: &str = "PEER_BOOK";
+pub const KEY_PRUNED_HEIGHT: &str = "PRUNED_HEIGHT";
+pub const KEY_SCHEMA_VERSION: &str = "SCHEMA_VERSION";
+pub const KEY_SYNC_CHECKPOINT: &str = "SYNC_CHECKPOINT";
 
 pub const KEY_CURR_CM_INDEX: &str = "CURRENT_CM_INDEX";
 pub const KEY_CURR_SN_INDEX: &str = "CURRENT_SN_INDEX";
@@ -34,6 +34,19 @@ use std::{
 
 pub type BlockHeight = u32;
 
+/// A storage backend that can be opened strictly read-only, e.g. to inspect the storage of another node.
+pub trait ReadOnlyStorage: Storage {
+    /// Opens the existing storage at the given path without creating or writing any files; all the
+    /// subsequent writes, including catching up with a primary instance, are refused.
+    fn open_read_only(path: &Path) -> Result<Self, StorageError>;
+}
+
+/// A storage backend that can write a consistent copy of itself while it's open, e.g. to back it up before a migration.
+pub trait CheckpointStorage: Storage {
+    /// Writes a point-in-time copy of the storage to the given path, which must not exist yet.
+    fn create_checkpoint(&self, path: &Path) -> Result<(), StorageError>;
+}
+
 pub struct Ledger<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> {
     pub current_block_height: AtomicU32,
     pub ledger_parameters: Arc<P>,
@@ -43,37 +56,6 @@ pub struct Ledger<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage>
 }
 
 impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P, S> {
-    /// Create a fresh blockchain, optionally at the specified path.
-    /// Warning: if specified, any existing storage at that location is removed.
-    pub fn new_empty<PATH: AsRef<Path>>(path: Option<PATH>) -> Result<Self, StorageError> {
-        if let Some(ref path) = path {
-            let _ = fs::remove_dir_all(path);
-
-            Self::open_at_path(path)
-        } else {
-            let crh = P::H::from(FromBytes::read(&LedgerMerkleTreeParameters::load_bytes()?[..])?);
-            let ledger_parameters = Arc::new(P::from(crh));
-
-            let genesis_block: Block<T> = FromBytes::read(GenesisBlock::load_bytes().as_slice())?;
-
-            Ok(Self::new(None, ledger_parameters, genesis_block).expect("Ledger could not be instantiated"))
-        }
-    }
-
-    /// Open the blockchain storage at a particular path.
-    pub fn open_at_path<PATH: AsRef<Path>>(path: PATH) -> Result<Self, StorageError> {
-        fs::create_dir_all(path.as_ref())?;
-
-        Self::load_ledger_state(path, true)
-    }
-
-    /// Open the blockchain storage at a particular path as a secondary read-only instance.
-    pub fn open_secondary_at_path<PATH: AsRef<Path>>(path: PATH) -> Result<Self, StorageError> {
-        fs::create_dir_all(path.as_ref())?;
-
-        Self::load_ledger_state(path, false)
-    }
-
     /// Returns true if there are no blocks in the ledger.
     pub fn is_empty(&self) -> bool {
         self.get_latest_block().is_err()
@@ -114,6 +96,113 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
         self.storage.batch(DatabaseTransaction(vec![op]))
     }
 
+    /// Get the stored address the node last announced to its peers, along with the latest change of it.
+    pub fn get_announced_address(&self) -> Result<Option<Vec<u8>>, StorageError> {
+        self.storage.get(COL_META, KEY_ANNOUNCED_ADDRESS.as_bytes())
+    }
+
+    /// Store the address the node announces to its peers, along with the latest change of it.
+    pub fn save_announced_address(&self, address_serialized: Vec<u8>) -> Result<(), StorageError> {
+        let op = Op::Insert {
+            col: COL_META,
+            key: KEY_ANNOUNCED_ADDRESS.as_bytes().to_vec(),
+            value: address_serialized,
+        };
+        self.storage.batch(DatabaseTransaction(vec![op]))
+    }
+
+    /// Returns a `Ledger` built from the given storage, whose latest block number is already known.
+    pub(crate) fn load_from_storage(
+        storage: S,
+        ledger_parameters: Arc<P>,
+        latest_block_number: &[u8],
+    ) -> Result<Self, StorageError> {
+        // Build commitment merkle tree
+
+        let mut cm_and_indices = vec![];
+
+        let cms = storage.get_col(COL_COMMITMENT)?;
+
+        for (commitment_key, index_value) in cms {
+            let commitment: T::Commitment = FromBytes::read(&commitment_key[..])?;
+            let index = bytes_to_u32(&index_value) as usize;
+
+            cm_and_indices.push((commitment, index));
+        }
+
+        cm_and_indices.sort_by(|&(_, i), &(_, j)| i.cmp(&j));
+        let commitments = cm_and_indices.into_iter().map(|(cm, _)| cm).collect::<Vec<_>>();
+
+        let merkle_tree = MerkleTree::new(ledger_parameters.clone(), &commitments[..])?;
+
+        Ok(Self {
+            current_block_height: AtomicU32::new(bytes_to_u32(latest_block_number)),
+            storage,
+            cm_merkle_tree: ArcSwap::new(Arc::new(merkle_tree)),
+            ledger_parameters,
+            _transaction: PhantomData,
+        })
+    }
+
+    /// Attempt to catch the secondary read-only storage instance with the primary instance.
+    pub fn catch_up_secondary(&self, update_merkle_tree: bool) -> Result<(), StorageError> {
+        // Sync the secondary and primary instances
+        if self.storage.try_catch_up_with_primary().is_ok() {
+            let new_current_block_height = self.get_best_block_number()?;
+            let current_block_height = self.get_current_block_height();
+
+            // If the new block height is greater than the stored block height,
+            // update the block height and merkle tree.
+            if new_current_block_height > current_block_height {
+                // Update the latest block height of the secondary instance.
+                self.current_block_height
+                    .store(new_current_block_height, Ordering::SeqCst);
+
+                // Optional `cm_merkle_tree` regeneration because not all usages of
+                // the secondary instance requires it.
+                if update_merkle_tree {
+                    // Update the Merkle tree of the secondary instance.
+                    self.rebuild_merkle_tree(vec![])?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: TransactionScheme, P: LoadableMerkleParameters, S: CheckpointStorage> Ledger<T, P, S> {
+    /// Create a fresh blockchain, optionally at the specified path.
+    /// Warning: if specified, any existing storage at that location is removed.
+    pub fn new_empty<PATH: AsRef<Path>>(path: Option<PATH>) -> Result<Self, StorageError> {
+        if let Some(ref path) = path {
+            let _ = fs::remove_dir_all(path);
+
+            Self::open_at_path(path)
+        } else {
+            let crh = P::H::from(FromBytes::read(&LedgerMerkleTreeParameters::load_bytes()?[..])?);
+            let ledger_parameters = Arc::new(P::from(crh));
+
+            let genesis_block: Block<T> = FromBytes::read(GenesisBlock::load_bytes().as_slice())?;
+
+            Ok(Self::new(None, ledger_parameters, genesis_block).expect("Ledger could not be instantiated"))
+        }
+    }
+
+    /// Open the blockchain storage at a particular path.
+    pub fn open_at_path<PATH: AsRef<Path>>(path: PATH) -> Result<Self, StorageError> {
+        fs::create_dir_all(path.as_ref())?;
+
+        Self::load_ledger_state(path, true)
+    }
+
+    /// Open the blockchain storage at a particular path as a secondary read-only instance.
+    pub fn open_secondary_at_path<PATH: AsRef<Path>>(path: PATH) -> Result<Self, StorageError> {
+        fs::create_dir_all(path.as_ref())?;
+
+        Self::load_ledger_state(path, false)
+    }
+
     /// Returns a `Ledger` with the latest state loaded from storage at a given path as
     /// a primary or secondary ledger. A secondary ledger runs as a read-only instance.
     fn load_ledger_state<PATH: AsRef<Path>>(path: PATH, primary: bool) -> Result<Self, StorageError> {
@@ -127,7 +216,18 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
                 true => S::open(Some(path.as_ref()), None)?,
                 false => S::open(Some(path.as_ref()), Some(&secondary_path))?,
             };
-            storage.get(COL_META, KEY_BEST_BLOCK_NUMBER.as_bytes())?
+            let latest_block_number = storage.get(COL_META, KEY_BEST_BLOCK_NUMBER.as_bytes())?;
+
+            // New storage is created with the latest schema, so only existing storage is checked.
+            if latest_block_number.is_some() {
+                if primary {
+                    Self::migrate(&storage, path.as_ref())?;
+                } else {
+                    check_schema_version(&storage)?;
+                }
+            }
+
+            latest_block_number
         };
 
         let crh = P::H::from(FromBytes::read(&LedgerMerkleTreeParameters::load_bytes()?[..])?);
@@ -140,31 +240,7 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
                     false => S::open(Some(path.as_ref()), Some(&secondary_path))?,
                 };
 
-                // Build commitment merkle tree
-
-                let mut cm_and_indices = vec![];
-
-                let cms = storage.get_col(COL_COMMITMENT)?;
-
-                for (commitment_key, index_value) in cms {
-                    let commitment: T::Commitment = FromBytes::read(&commitment_key[..])?;
-                    let index = bytes_to_u32(&index_value) as usize;
-
-                    cm_and_indices.push((commitment, index));
-                }
-
-                cm_and_indices.sort_by(|&(_, i), &(_, j)| i.cmp(&j));
-                let commitments = cm_and_indices.into_iter().map(|(cm, _)| cm).collect::<Vec<_>>();
-
-                let merkle_tree = MerkleTree::new(ledger_parameters.clone(), &commitments[..])?;
-
-                Ok(Self {
-                    current_block_height: AtomicU32::new(bytes_to_u32(&val)),
-                    storage,
-                    cm_merkle_tree: ArcSwap::new(Arc::new(merkle_tree)),
-                    ledger_parameters,
-                    _transaction: PhantomData,
-                })
+                Self::load_from_storage(storage, ledger_parameters, &val)
             }
             None => {
                 // Add genesis block to database
@@ -185,29 +261,50 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
         }
     }
 
-    /// Attempt to catch the secondary read-only storage instance with the primary instance.
-    pub fn catch_up_secondary(&self, update_merkle_tree: bool) -> Result<(), StorageError> {
-        // Sync the secondary and primary instances
-        if self.storage.try_catch_up_with_primary().is_ok() {
-            let new_current_block_height = self.get_best_block_number()?;
-            let current_block_height = self.get_current_block_height();
+    /// Brings the schema of the storage at the given path up to date, backing the storage up first if
+    /// there are any pending migrations. Storage with a newer schema than the supported one is rejected.
+    fn migrate(storage: &S, path: &Path) -> Result<(), StorageError> {
+        check_schema_version(storage)?;
 
-            // If the new block height is greater than the stored block height,
-            // update the block height and merkle tree.
-            if new_current_block_height > current_block_height {
-                // Update the latest block height of the secondary instance.
-                self.current_block_height
-                    .store(new_current_block_height, Ordering::SeqCst);
-
-                // Optional `cm_merkle_tree` regeneration because not all usages of
-                // the secondary instance requires it.
-                if update_merkle_tree {
-                    // Update the Merkle tree of the secondary instance.
-                    self.rebuild_merkle_tree(vec![])?;
-                }
-            }
-        }
+        MigrationRunner::new(storage, migrations::<T, S>())
+            .with_backup(|version| backup_storage(storage, path, version))
+            .run()?;
 
         Ok(())
     }
 }
+
+impl<T: TransactionScheme, P: LoadableMerkleParameters, S: ReadOnlyStorage> Ledger<T, P, S> {
+    /// Open the existing blockchain storage at a particular path strictly read-only; unlike a secondary
+    /// instance, it doesn't create any files and never catches up with the primary instance.
+    pub fn open_read_only_at_path<PATH: AsRef<Path>>(path: PATH) -> Result<Self, StorageError> {
+        let storage = S::open_read_only(path.as_ref())?;
+
+        let latest_block_number = storage
+            .get(COL_META, KEY_BEST_BLOCK_NUMBER.as_bytes())?
+            .ok_or_else(|| StorageError::Message(format!("No ledger found at '{}'", path.as_ref().display())))?;
+        check_schema_version(&storage)?;
+
+        let crh = P::H::from(FromBytes::read(&LedgerMerkleTreeParameters::load_bytes()?[..])?);
+        let ledger_parameters = Arc::new(P::from(crh));
+
+        Self::load_from_storage(storage, ledger_parameters, &latest_block_number)
+    }
+}
+
+/// Backs the storage at the given path up to a sibling directory named after the given schema version. An existing
+/// backup of the same version is kept, as it predates any migration that might have been applied partially.
+fn backup_storage<S: CheckpointStorage>(storage: &S, path: &Path, version: u32) -> Result<(), StorageError> {
+    let mut backup_path = path.to_path_buf().into_os_string();
+    backup_path.push(format!("_backup_v{}", version));
+    let backup_path = PathBuf::from(backup_path);
+
+    if backup_path.exists() {
+        tracing::info!("Keeping the existing storage backup at '{}'", backup_path.display());
+        return Ok(());
+    }
+
+    tracing::info!("Backing the storage up at '{}'...", backup_path.display());
+
+    storage.create_checkpoint(&backup_path)
+}
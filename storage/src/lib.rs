@@ -23,6 +23,9 @@ pub use key_value::*;
 pub mod objects;
 pub use objects::*;
 
+pub mod migration;
+pub use migration::*;
+
 pub mod mem;
 #[cfg(feature = "mem_storage")]
 pub use mem::MemDb as LedgerStorage;
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::NUM_COLS;
+use crate::{CheckpointStorage, ReadOnlyStorage, NUM_COLS};
 use snarkvm_dpc::{DatabaseTransaction, Op, Storage, StorageError};
 
 use parking_lot::RwLock;
@@ -83,3 +83,16 @@ impl Storage for MemDb {
         Err(StorageError::Message("MemDb has no secondary instance".into()))
     }
 }
+
+impl ReadOnlyStorage for MemDb {
+    fn open_read_only(_path: &Path) -> Result<Self, StorageError> {
+        Err(StorageError::Message("MemDb can't be opened read-only".into()))
+    }
+}
+
+impl CheckpointStorage for MemDb {
+    fn create_checkpoint(&self, _path: &Path) -> Result<(), StorageError> {
+        // in-memory storage is never persisted, so there is nothing to back up
+        Ok(())
+    }
+}
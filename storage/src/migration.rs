@@ -0,0 +1,288 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    bytes_to_u32,
+    COL_BLOCK_HEADER,
+    COL_BLOCK_LOCATOR,
+    COL_BLOCK_TRANSACTIONS,
+    COL_BRANCH_TIPS,
+    COL_CHILD_HASHES,
+    COL_CM_TRANSACTION,
+    COL_META,
+    COL_SN_TRANSACTION,
+    KEY_BEST_BLOCK_NUMBER,
+    KEY_SCHEMA_VERSION,
+};
+use snarkvm_dpc::{
+    errors::StorageError,
+    BlockHeaderHash,
+    DatabaseTransaction,
+    Op,
+    Storage,
+    TransactionScheme,
+    Transactions as DPCTransactions,
+};
+use snarkvm_utilities::{to_bytes, FromBytes, ToBytes};
+
+use tracing::*;
+
+/// The version of the storage schema expected by this node; it must match the version of the latest migration.
+pub const SCHEMA_VERSION: u32 = 5;
+
+/// A single step in the evolution of the storage schema.
+pub struct Migration<S: Storage> {
+    /// The version of the schema once the migration is applied.
+    pub version: u32,
+    /// A short description of the changes, used in logs.
+    pub description: &'static str,
+    /// Applies the changes to the storage.
+    pub apply: fn(&S) -> Result<(), StorageError>,
+}
+
+/// Returns all the known migrations, in the order they need to be applied in.
+pub fn migrations<T: TransactionScheme, S: Storage>() -> Vec<Migration<S>> {
+    vec![
+        Migration {
+            version: 1,
+            description: "record the version of the storage schema",
+            // The version record is written by the runner after each migration.
+            apply: |_| Ok(()),
+        },
+        Migration {
+            version: 2,
+            description: "add the column of banned addresses",
+            // Missing columns are created when the storage is opened; the version bump only prevents older
+            // nodes, which don't know about the new column, from opening the storage.
+            apply: |_| Ok(()),
+        },
+        Migration {
+            version: 3,
+            description: "add the column of the mining history",
+            // See the note on the previous migration.
+            apply: |_| Ok(()),
+        },
+        Migration {
+            version: 4,
+            description: "index the canon transactions by their commitments and serial numbers",
+            apply: index_transaction_records::<T, S>,
+        },
+        Migration {
+            version: 5,
+            description: "index the tips of the side chains and the orphaned branches",
+            apply: index_branch_tips::<S>,
+        },
+    ]
+}
+
+/// Indexes the transactions of the canon blocks by the commitments of the records they create and the serial
+/// numbers of the ones they spend; the transactions of pruned blocks are no longer available, so they aren't indexed.
+fn index_transaction_records<T: TransactionScheme, S: Storage>(storage: &S) -> Result<(), StorageError> {
+    let best_block_number = match storage.get(COL_META, KEY_BEST_BLOCK_NUMBER.as_bytes())? {
+        Some(bytes) => bytes_to_u32(&bytes),
+        None => return Ok(()),
+    };
+
+    for block_number in 0..=best_block_number {
+        let block_hash = match storage.get(COL_BLOCK_LOCATOR, &block_number.to_le_bytes())? {
+            Some(block_hash) => block_hash,
+            None => continue,
+        };
+        let transactions: DPCTransactions<T> = match storage.get(COL_BLOCK_TRANSACTIONS, &block_hash)? {
+            Some(transactions) => FromBytes::read(&transactions[..])?,
+            None => continue,
+        };
+
+        // Every block is indexed in its own batch, so that the memory use doesn't depend on the size of the chain.
+        let mut database_transaction = DatabaseTransaction::new();
+        for transaction in transactions.0 {
+            let transaction_id = transaction.transaction_id()?.to_vec();
+
+            for sn in transaction.old_serial_numbers() {
+                database_transaction.push(Op::Insert {
+                    col: COL_SN_TRANSACTION,
+                    key: to_bytes![sn]?,
+                    value: transaction_id.clone(),
+                });
+            }
+            for cm in transaction.new_commitments() {
+                database_transaction.push(Op::Insert {
+                    col: COL_CM_TRANSACTION,
+                    key: to_bytes![cm]?,
+                    value: transaction_id.clone(),
+                });
+            }
+        }
+
+        storage.batch(database_transaction)?;
+    }
+
+    Ok(())
+}
+
+/// Indexes the stored blocks that aren't canon and have no known children, i.e. the tips of the other branches, so
+/// that they can be listed without inspecting all the stored block headers.
+fn index_branch_tips<S: Storage>(storage: &S) -> Result<(), StorageError> {
+    let mut database_transaction = DatabaseTransaction::new();
+    for block_hash in storage.get_keys(COL_BLOCK_HEADER)? {
+        if storage.exists(COL_BLOCK_LOCATOR, &block_hash) {
+            continue;
+        }
+        if let Some(child_hashes) = storage.get(COL_CHILD_HASHES, &block_hash)? {
+            if !bincode::deserialize::<Vec<BlockHeaderHash>>(&child_hashes)?.is_empty() {
+                continue;
+            }
+        }
+
+        database_transaction.push(Op::Insert {
+            col: COL_BRANCH_TIPS,
+            key: block_hash.to_vec(),
+            value: vec![],
+        });
+    }
+
+    storage.batch(database_transaction)
+}
+
+/// Returns the schema version recorded in the storage; storage created before the introduction of
+/// the record doesn't have one.
+pub fn get_schema_version<S: Storage>(storage: &S) -> Result<Option<u32>, StorageError> {
+    match storage.get(COL_META, KEY_SCHEMA_VERSION.as_bytes())? {
+        Some(bytes) if bytes.len() == 4 => Ok(Some(bytes_to_u32(&bytes))),
+        Some(bytes) => Err(StorageError::Message(format!(
+            "invalid storage schema version record ({} bytes)",
+            bytes.len()
+        ))),
+        None => Ok(None),
+    }
+}
+
+/// Records the given schema version in the storage.
+pub fn set_schema_version<S: Storage>(storage: &S, version: u32) -> Result<(), StorageError> {
+    let op = Op::Insert {
+        col: COL_META,
+        key: KEY_SCHEMA_VERSION.as_bytes().to_vec(),
+        value: version.to_le_bytes().to_vec(),
+    };
+    storage.batch(DatabaseTransaction(vec![op]))
+}
+
+/// Ensures that the storage schema isn't newer than the one expected by this node, which would mean that
+/// the storage was written by a newer version of snarkOS and can't be used safely.
+pub fn check_schema_version<S: Storage>(storage: &S) -> Result<(), StorageError> {
+    match get_schema_version(storage)? {
+        Some(version) if version > SCHEMA_VERSION => Err(StorageError::Message(format!(
+            "the storage schema (version {}) is newer than the supported one (version {}); please update snarkOS",
+            version, SCHEMA_VERSION
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// The hook called before the first pending migration is applied, with the schema version at the time.
+type BackupHook<'a> = Box<dyn FnMut(u32) -> Result<(), StorageError> + 'a>;
+
+///
+/// Applies the pending migrations to a storage, in order.
+///
+pub struct MigrationRunner<'a, S: Storage> {
+    storage: &'a S,
+    migrations: Vec<Migration<S>>,
+    dry_run: bool,
+    backup: Option<BackupHook<'a>>,
+}
+
+impl<'a, S: Storage> MigrationRunner<'a, S> {
+    pub fn new(storage: &'a S, mut migrations: Vec<Migration<S>>) -> Self {
+        migrations.sort_unstable_by_key(|migration| migration.version);
+
+        Self {
+            storage,
+            migrations,
+            dry_run: false,
+            backup: None,
+        }
+    }
+
+    /// Only reports the pending migrations instead of applying them.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Registers a hook that can back the storage up before it's migrated.
+    pub fn with_backup<F: FnMut(u32) -> Result<(), StorageError> + 'a>(mut self, backup: F) -> Self {
+        self.backup = Some(Box::new(backup));
+        self
+    }
+
+    /// Returns the current version of the storage schema; storage without a version record is at version 0.
+    pub fn current_version(&self) -> Result<u32, StorageError> {
+        Ok(get_schema_version(self.storage)?.unwrap_or(0))
+    }
+
+    ///
+    /// Applies the pending migrations, recording the new schema version after each one. Returns the versions
+    /// of the applied migrations, or of the ones that would be applied in a dry run.
+    ///
+    pub fn run(mut self) -> Result<Vec<u32>, StorageError> {
+        let current_version = self.current_version()?;
+        let latest_version = self.migrations.last().map(|migration| migration.version).unwrap_or(0);
+
+        if current_version > latest_version {
+            return Err(StorageError::Message(format!(
+                "the storage schema (version {}) is newer than the latest known migration (version {})",
+                current_version, latest_version
+            )));
+        }
+
+        let pending = self
+            .migrations
+            .iter()
+            .filter(|migration| migration.version > current_version)
+            .collect::<Vec<_>>();
+        let versions = pending.iter().map(|migration| migration.version).collect::<Vec<_>>();
+
+        if pending.is_empty() {
+            return Ok(versions);
+        }
+
+        if self.dry_run {
+            for migration in &pending {
+                info!(
+                    "Storage migration {} is pending: {}",
+                    migration.version, migration.description
+                );
+            }
+            return Ok(versions);
+        }
+
+        if let Some(backup) = self.backup.as_mut() {
+            backup(current_version)?;
+        }
+
+        for migration in pending {
+            info!(
+                "Applying storage migration {}: {}",
+                migration.version, migration.description
+            );
+            (migration.apply)(self.storage)?;
+            set_schema_version(self.storage, migration.version)?;
+        }
+
+        Ok(versions)
+    }
+}
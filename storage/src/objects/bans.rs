@@ -0,0 +1,63 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{Ledger, COL_BANS};
+use snarkvm_algorithms::traits::LoadableMerkleParameters;
+use snarkvm_dpc::{errors::StorageError, DatabaseTransaction, Op, Storage, TransactionScheme};
+
+use std::convert::TryInto;
+
+impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P, S> {
+    /// Get the stored bans, as pairs of the banned IPs or subnets and the UNIX timestamps the bans expire at.
+    pub fn get_bans(&self) -> Result<Vec<(String, i64)>, StorageError> {
+        let mut bans = Vec::new();
+
+        for (key, value) in self.storage.get_col(COL_BANS)? {
+            let subnet = String::from_utf8(key.to_vec())
+                .map_err(|_| StorageError::Message("invalid banned subnet record".into()))?;
+            let expiry = value[..]
+                .try_into()
+                .map(i64::from_le_bytes)
+                .map_err(|_| StorageError::Message(format!("invalid ban expiry record for {}", subnet)))?;
+
+            bans.push((subnet, expiry));
+        }
+
+        Ok(bans)
+    }
+
+    /// Store a ban of the given IP or subnet, replacing any previous one.
+    pub fn store_ban(&self, subnet: &str, expiry: i64) -> Result<(), StorageError> {
+        let op = Op::Insert {
+            col: COL_BANS,
+            key: subnet.as_bytes().to_vec(),
+            value: expiry.to_le_bytes().to_vec(),
+        };
+        self.storage.batch(DatabaseTransaction(vec![op]))
+    }
+
+    /// Remove the bans of the given IPs or subnets, e.g. once they expire.
+    pub fn remove_bans(&self, subnets: &[String]) -> Result<(), StorageError> {
+        let ops = subnets
+            .iter()
+            .map(|subnet| Op::Delete {
+                col: COL_BANS,
+                key: subnet.as_bytes().to_vec(),
+            })
+            .collect();
+        self.storage.batch(DatabaseTransaction(ops))
+    }
+}
@@ -39,10 +39,16 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
 
     /// Get a block given the block hash.
     pub fn get_block(&self, block_hash: &BlockHeaderHash) -> Result<Block<T>, StorageError> {
-        Ok(Block {
-            header: self.get_block_header(block_hash)?,
-            transactions: self.get_block_transactions(block_hash)?,
-        })
+        let header = self.get_block_header(block_hash)?;
+        let transactions = match self.get_block_transactions(block_hash) {
+            Ok(transactions) => transactions,
+            Err(_) if self.is_block_pruned(block_hash) => {
+                return Err(StorageError::Message(format!("Block {} has been pruned", block_hash)));
+            }
+            Err(e) => return Err(e),
+        };
+
+        Ok(Block { header, transactions })
     }
 
     /// Get a block given the block number.
@@ -99,7 +105,7 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
 
         let existing_block_number = previous_block_number + 1;
 
-        if self.get_block_from_block_number(existing_block_number).is_ok() {
+        if self.get_block_hash(existing_block_number).is_ok() {
             // the storage has a conflicting block with the same previous_block_hash
             Ok(Some(existing_block_number))
         } else {
@@ -150,8 +156,25 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
                     break;
                 }
             }
+
+            // A side chain parent left without children becomes the tip of its branch again.
+            if child_hashes.is_empty()
+                && self.block_hash_exists(&block_header.previous_block_hash)
+                && !self.is_canon(&block_header.previous_block_hash)
+            {
+                database_transaction.push(Op::Insert {
+                    col: COL_BRANCH_TIPS,
+                    key: block_header.previous_block_hash.0.to_vec(),
+                    value: vec![],
+                });
+            }
         }
 
+        database_transaction.push(Op::Delete {
+            col: COL_BRANCH_TIPS,
+            key: block_hash.0.to_vec(),
+        });
+
         self.storage.batch(database_transaction)
     }
 
@@ -187,17 +210,27 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
 
         for transaction in self.get_block_transactions(&block_hash)?.0 {
             for sn in transaction.old_serial_numbers() {
+                let sn_bytes = to_bytes![sn]?.to_vec();
+                database_transaction.push(Op::Delete {
+                    col: COL_SN_TRANSACTION,
+                    key: sn_bytes.clone(),
+                });
                 database_transaction.push(Op::Delete {
                     col: COL_SERIAL_NUMBER,
-                    key: to_bytes![sn]?.to_vec(),
+                    key: sn_bytes,
                 });
                 sn_index -= 1;
             }
 
             for cm in transaction.new_commitments() {
+                let cm_bytes = to_bytes![cm]?.to_vec();
+                database_transaction.push(Op::Delete {
+                    col: COL_CM_TRANSACTION,
+                    key: cm_bytes.clone(),
+                });
                 database_transaction.push(Op::Delete {
                     col: COL_COMMITMENT,
-                    key: to_bytes![cm]?.to_vec(),
+                    key: cm_bytes,
                 });
                 cm_index -= 1;
             }
@@ -237,6 +270,15 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
             key: block_hash.0.to_vec(),
         });
 
+        // The former canon tip becomes the tip of a side chain.
+        if self.get_child_block_hashes(&block_hash)?.is_empty() {
+            database_transaction.push(Op::Insert {
+                col: COL_BRANCH_TIPS,
+                key: block_hash.0.to_vec(),
+                value: vec![],
+            });
+        }
+
         self.storage.batch(database_transaction)?;
 
         self.current_block_height.fetch_sub(1, Ordering::SeqCst);
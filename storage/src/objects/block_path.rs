@@ -14,10 +14,12 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::Ledger;
+use crate::{Ledger, COL_BRANCH_TIPS};
 use snarkvm_algorithms::traits::LoadableMerkleParameters;
 use snarkvm_dpc::{errors::StorageError, BlockError, BlockHeader, BlockHeaderHash, Storage, TransactionScheme};
 
+use std::fmt;
+
 const OLDEST_FORK_THRESHOLD: u32 = 1024;
 
 #[derive(Clone, Debug)]
@@ -39,6 +41,40 @@ pub struct SideChainPath {
     pub path: Vec<BlockHeaderHash>,
 }
 
+/// The status of a chain tip.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChainTipStatus {
+    /// The tip of the canon chain.
+    Active,
+    /// The tip of a side chain branching off the canon chain.
+    Fork,
+    /// The tip of a branch that can't be traced back to the canon chain, e.g. because some of its blocks are missing.
+    Orphan,
+}
+
+impl fmt::Display for ChainTipStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Active => write!(f, "active"),
+            Self::Fork => write!(f, "fork"),
+            Self::Orphan => write!(f, "orphan"),
+        }
+    }
+}
+
+/// A stored block without any known children.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChainTip {
+    /// The hash of the block.
+    pub hash: BlockHeaderHash,
+    /// The height of the block; it's unknown for orphans.
+    pub height: Option<u32>,
+    /// The number of blocks in the branch that aren't part of the canon chain; 0 for the canon chain.
+    pub branch_length: u32,
+    /// The status of the tip.
+    pub status: ChainTipStatus,
+}
+
 impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P, S> {
     /// Get the block's path/origin.
     pub fn get_block_path(&self, block_header: &BlockHeader) -> Result<BlockPath, StorageError> {
@@ -88,6 +124,62 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
         )))
     }
 
+    /// Returns the tips of the known chains: the tip of the canon chain first, followed by the tips of the side
+    /// chains and the orphaned branches, from the longest branch to the shortest one.
+    /// The tips of the other branches are indexed as the blocks are inserted, committed and reverted, so only the
+    /// branches themselves are walked.
+    pub fn get_chain_tips(&self) -> Result<Vec<ChainTip>, StorageError> {
+        let canon_height = self.get_current_block_height();
+        let mut tips = vec![ChainTip {
+            hash: self.get_block_hash(canon_height)?,
+            height: Some(canon_height),
+            branch_length: 0,
+            status: ChainTipStatus::Active,
+        }];
+
+        for key in self.storage.get_keys(COL_BRANCH_TIPS)? {
+            let hash = BlockHeaderHash::new(key.to_vec());
+
+            // Walk the branch back to the canon chain (with a maximum length of OLDEST_FORK_THRESHOLD).
+            let mut branch_length = 1;
+            let mut parent_hash = self.get_block_header(&hash)?.previous_block_hash;
+            let mut shared_block_number = None;
+            for _ in 0..=OLDEST_FORK_THRESHOLD {
+                if let Ok(block_number) = self.get_block_number(&parent_hash) {
+                    shared_block_number = Some(block_number);
+                    break;
+                }
+
+                match self.get_block_header(&parent_hash) {
+                    Ok(parent_header) => {
+                        branch_length += 1;
+                        parent_hash = parent_header.previous_block_hash;
+                    }
+                    Err(_) => break,
+                }
+            }
+
+            tips.push(match shared_block_number {
+                Some(block_number) => ChainTip {
+                    hash,
+                    height: Some(block_number + branch_length),
+                    branch_length,
+                    status: ChainTipStatus::Fork,
+                },
+                None => ChainTip {
+                    hash,
+                    height: None,
+                    branch_length,
+                    status: ChainTipStatus::Orphan,
+                },
+            });
+        }
+
+        tips[1..].sort_by(|tip1, tip2| tip2.branch_length.cmp(&tip1.branch_length));
+
+        Ok(tips)
+    }
+
     /// Returns the path length and the longest path of children from the given block header
     pub fn longest_child_path(&self, block_hash: BlockHeaderHash) -> Result<Vec<BlockHeaderHash>, StorageError> {
         let mut round = vec![vec![block_hash]];
@@ -43,8 +43,9 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
     ) -> Result<(Vec<Op>, Vec<(T::Commitment, usize)>), StorageError> {
         let old_serial_numbers = transaction.old_serial_numbers();
         let new_commitments = transaction.new_commitments();
+        let transaction_id = transaction.transaction_id()?.to_vec();
 
-        let mut ops = Vec::with_capacity(old_serial_numbers.len() + new_commitments.len());
+        let mut ops = Vec::with_capacity(2 * (old_serial_numbers.len() + new_commitments.len()));
         let mut cms = Vec::with_capacity(new_commitments.len());
 
         for sn in old_serial_numbers {
@@ -53,6 +54,11 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
                 return Err(StorageError::ExistingSn(sn_bytes.to_vec()));
             }
 
+            ops.push(Op::Insert {
+                col: COL_SN_TRANSACTION,
+                key: sn_bytes.clone(),
+                value: transaction_id.clone(),
+            });
             ops.push(Op::Insert {
                 col: COL_SERIAL_NUMBER,
                 key: sn_bytes,
@@ -67,6 +73,11 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
                 return Err(StorageError::ExistingCm(cm_bytes.to_vec()));
             }
 
+            ops.push(Op::Insert {
+                col: COL_CM_TRANSACTION,
+                key: cm_bytes.clone(),
+                value: transaction_id.clone(),
+            });
             ops.push(Op::Insert {
                 col: COL_COMMITMENT,
                 key: cm_bytes,
@@ -158,7 +169,7 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
         let mut child_hashes = self.get_child_block_hashes(&block.header.previous_block_hash)?;
 
         if !child_hashes.contains(&block_hash) {
-            child_hashes.push(block_hash);
+            child_hashes.push(block_hash.clone());
 
             database_transaction.push(Op::Insert {
                 col: COL_CHILD_HASHES,
@@ -167,6 +178,19 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
             });
         }
 
+        // The new block ends its branch, unless its children were already received, and its parent no longer does.
+        if self.get_child_block_hashes(&block_hash)?.is_empty() {
+            database_transaction.push(Op::Insert {
+                col: COL_BRANCH_TIPS,
+                key: block_hash.0.to_vec(),
+                value: vec![],
+            });
+        }
+        database_transaction.push(Op::Delete {
+            col: COL_BRANCH_TIPS,
+            key: block.header.previous_block_hash.0.to_vec(),
+        });
+
         self.storage.batch(database_transaction)?;
 
         Ok(())
@@ -282,6 +306,10 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
             key: new_best_block_number.to_le_bytes().to_vec(),
             value: block_header_hash.0.to_vec(),
         });
+        database_transaction.push(Op::Delete {
+            col: COL_BRANCH_TIPS,
+            key: block_header_hash.0.to_vec(),
+        });
 
         // Rebuild the new commitment merkle tree
         self.rebuild_merkle_tree(transaction_cms)?;
@@ -0,0 +1,86 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{Ledger, COL_JOURNAL};
+use snarkvm_algorithms::traits::LoadableMerkleParameters;
+use snarkvm_dpc::{errors::StorageError, DatabaseTransaction, Op, Storage, TransactionScheme};
+
+use std::convert::TryInto;
+
+/// Returns the storage key of a journaled event; it starts with the time of the event, so that the keys are ordered
+/// by it, followed by its sequence number, which tells apart the events from the same millisecond.
+fn journal_key(timestamp_millis: i64, seq: u64) -> Vec<u8> {
+    let mut key = Vec::with_capacity(8 + 8);
+    key.extend_from_slice(&timestamp_millis.to_be_bytes());
+    key.extend_from_slice(&seq.to_be_bytes());
+    key
+}
+
+/// Decodes the UNIX timestamp in milliseconds and the sequence number a journaled event is stored under.
+fn journal_key_parts(key: &[u8]) -> Result<(i64, u64), StorageError> {
+    let timestamp = key
+        .get(..8)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(i64::from_be_bytes);
+    let seq = key
+        .get(8..)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_be_bytes);
+
+    timestamp
+        .zip(seq)
+        .ok_or_else(|| StorageError::Message("invalid journal record".into()))
+}
+
+impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P, S> {
+    /// Store a serialized event of the node's journal, which happened at the given UNIX timestamp in milliseconds.
+    pub fn store_journal_event(&self, timestamp_millis: i64, seq: u64, event: Vec<u8>) -> Result<(), StorageError> {
+        let op = Op::Insert {
+            col: COL_JOURNAL,
+            key: journal_key(timestamp_millis, seq),
+            value: event,
+        };
+        self.storage.batch(DatabaseTransaction(vec![op]))
+    }
+
+    /// Get the UNIX timestamps in milliseconds and the sequence numbers of all the stored journal events, in no
+    /// particular order; it reads the whole journal, so it's meant to build an index of it once.
+    pub fn get_journal_keys(&self) -> Result<Vec<(i64, u64)>, StorageError> {
+        self.storage
+            .get_keys(COL_JOURNAL)?
+            .iter()
+            .map(|key| journal_key_parts(key))
+            .collect()
+    }
+
+    /// Get the serialized journal event stored under the given UNIX timestamp in milliseconds and sequence number.
+    pub fn get_journal_event(&self, timestamp_millis: i64, seq: u64) -> Result<Option<Vec<u8>>, StorageError> {
+        self.storage.get(COL_JOURNAL, &journal_key(timestamp_millis, seq))
+    }
+
+    /// Remove the journal events stored under the given UNIX timestamps in milliseconds and sequence numbers.
+    pub fn remove_journal_events(&self, keys: &[(i64, u64)]) -> Result<(), StorageError> {
+        let ops = keys
+            .iter()
+            .map(|&(timestamp_millis, seq)| Op::Delete {
+                col: COL_JOURNAL,
+                key: journal_key(timestamp_millis, seq),
+            })
+            .collect();
+
+        self.storage.batch(DatabaseTransaction(ops))
+    }
+}
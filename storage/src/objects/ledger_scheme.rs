@@ -66,6 +66,7 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> LedgerScheme
         };
 
         ledger_storage.insert_and_commit(&genesis_block)?;
+        set_schema_version(&ledger_storage.storage, SCHEMA_VERSION)?;
 
         Ok(ledger_storage)
     }
@@ -0,0 +1,566 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::*;
+use snarkos_parameters::GenesisBlock;
+use snarkvm_algorithms::{merkle_tree::MerkleTree, traits::LoadableMerkleParameters};
+use snarkvm_dpc::{
+    errors::StorageError,
+    Block,
+    BlockError,
+    BlockHeader,
+    BlockHeaderHash,
+    DatabaseTransaction,
+    Op,
+    Storage,
+    TransactionScheme,
+    Transactions as DPCTransactions,
+};
+use snarkvm_parameters::{traits::genesis::Genesis, LedgerMerkleTreeParameters, Parameter};
+use snarkvm_utilities::{
+    bytes::{FromBytes, ToBytes},
+    to_bytes,
+};
+
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// The bytes the ledger snapshot files start with.
+const SNAPSHOT_MAGIC: &[u8; 8] = b"SNARKSNP";
+/// The version of the format of the ledger snapshot files.
+const SNAPSHOT_FORMAT_VERSION: u32 = 3;
+/// The largest serialized block a snapshot file may contain; it only guards the allocations against a corrupted length.
+const MAX_SNAPSHOT_BLOCK_LEN: usize = 64 * 1024 * 1024;
+
+fn invalid_snapshot(reason: String) -> StorageError {
+    StorageError::Message(format!("Invalid ledger snapshot: {}", reason))
+}
+
+/// Returns the path of the temporary file a snapshot is written to before it's moved to the given path.
+fn partial_snapshot_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".partial");
+    path.with_file_name(file_name)
+}
+
+/// Fills the buffer from a snapshot file, which is invalid if it ends prematurely.
+fn read_snapshot<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<(), StorageError> {
+    reader.read_exact(buf).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => invalid_snapshot("it's truncated".into()),
+        _ => e.into(),
+    })
+}
+
+/// Writes a ledger snapshot one block at a time. The file starts with its magic bytes, format version and number
+/// of blocks, which are followed by the length-prefixed serialized canon blocks, from the genesis block up to the
+/// latest one, and the SHA-256 checksum of everything after the format version.
+struct SnapshotWriter<W: Write> {
+    writer: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> SnapshotWriter<W> {
+    fn new(mut writer: W, block_count: u32) -> Result<Self, StorageError> {
+        writer.write_all(SNAPSHOT_MAGIC)?;
+        writer.write_all(&SNAPSHOT_FORMAT_VERSION.to_le_bytes())?;
+
+        let mut snapshot = Self {
+            writer,
+            hasher: Sha256::new(),
+        };
+        snapshot.write_hashed(&block_count.to_le_bytes())?;
+
+        Ok(snapshot)
+    }
+
+    fn write_hashed(&mut self, bytes: &[u8]) -> Result<(), StorageError> {
+        self.hasher.update(bytes);
+        Ok(self.writer.write_all(bytes)?)
+    }
+
+    fn write_block(&mut self, block: &[u8]) -> Result<(), StorageError> {
+        self.write_hashed(&(block.len() as u32).to_le_bytes())?;
+        self.write_hashed(block)
+    }
+
+    /// Appends the checksum, which is returned along with the underlying writer.
+    fn finish(mut self) -> Result<(W, [u8; 32]), StorageError> {
+        let mut checksum = [0u8; 32];
+        checksum.copy_from_slice(&self.hasher.finalize());
+        self.writer.write_all(&checksum)?;
+
+        Ok((self.writer, checksum))
+    }
+}
+
+/// Reads a ledger snapshot written by a `SnapshotWriter` one block at a time.
+struct SnapshotReader<R: Read> {
+    reader: R,
+    hasher: Sha256,
+    /// The number of blocks in the snapshot.
+    block_count: u32,
+}
+
+impl<R: Read> SnapshotReader<R> {
+    fn new(mut reader: R) -> Result<Self, StorageError> {
+        let mut header = [0u8; SNAPSHOT_MAGIC.len() + 4];
+        read_snapshot(&mut reader, &mut header)?;
+        let (magic, version) = header.split_at(SNAPSHOT_MAGIC.len());
+        if magic != SNAPSHOT_MAGIC {
+            return Err(invalid_snapshot("it isn't a ledger snapshot file".into()));
+        }
+        if bytes_to_u32(version) != SNAPSHOT_FORMAT_VERSION {
+            return Err(invalid_snapshot(format!(
+                "its format version {} isn't supported",
+                bytes_to_u32(version)
+            )));
+        }
+
+        let mut snapshot = Self {
+            reader,
+            hasher: Sha256::new(),
+            block_count: 0,
+        };
+        let mut block_count = [0u8; 4];
+        snapshot.read_hashed(&mut block_count)?;
+        snapshot.block_count = u32::from_le_bytes(block_count);
+
+        Ok(snapshot)
+    }
+
+    fn read_hashed(&mut self, buf: &mut [u8]) -> Result<(), StorageError> {
+        read_snapshot(&mut self.reader, buf)?;
+        self.hasher.update(&buf[..]);
+        Ok(())
+    }
+
+    /// Returns the next serialized block; it may only be called `block_count` times.
+    fn next_block(&mut self) -> Result<Vec<u8>, StorageError> {
+        let mut len = [0u8; 4];
+        self.read_hashed(&mut len)?;
+        let len = u32::from_le_bytes(len) as usize;
+        if len > MAX_SNAPSHOT_BLOCK_LEN {
+            return Err(invalid_snapshot(format!("it contains a block of {} bytes", len)));
+        }
+
+        let mut block = vec![0u8; len];
+        self.read_hashed(&mut block)?;
+
+        Ok(block)
+    }
+
+    /// Verifies the checksum following the blocks, which is returned.
+    fn finish(mut self) -> Result<[u8; 32], StorageError> {
+        let mut checksum = [0u8; 32];
+        read_snapshot(&mut self.reader, &mut checksum)?;
+        if self.hasher.finalize()[..] != checksum {
+            return Err(invalid_snapshot(
+                "its checksum doesn't match; it may be corrupted".into(),
+            ));
+        }
+        if self.reader.read(&mut [0u8; 1])? != 0 {
+            return Err(invalid_snapshot("it continues past its checksum".into()));
+        }
+
+        Ok(checksum)
+    }
+}
+
+/// Adds the operations storing the given transactions of the given block to the database transaction.
+fn write_block_transactions<T: TransactionScheme>(
+    database_transaction: &mut DatabaseTransaction,
+    block_hash: &BlockHeaderHash,
+    transactions: &DPCTransactions<T>,
+) -> Result<(), StorageError> {
+    for (index, transaction) in transactions.0.iter().enumerate() {
+        let transaction_id = transaction.transaction_id()?.to_vec();
+        let transaction_location = TransactionLocation {
+            index: index as u32,
+            block_hash: block_hash.0,
+        };
+        database_transaction.push(Op::Insert {
+            col: COL_TRANSACTION_LOCATION,
+            key: transaction_id.clone(),
+            value: to_bytes![transaction_location]?,
+        });
+        for sn in transaction.old_serial_numbers() {
+            database_transaction.push(Op::Insert {
+                col: COL_SN_TRANSACTION,
+                key: to_bytes![sn]?,
+                value: transaction_id.clone(),
+            });
+        }
+        for cm in transaction.new_commitments() {
+            database_transaction.push(Op::Insert {
+                col: COL_CM_TRANSACTION,
+                key: to_bytes![cm]?,
+                value: transaction_id.clone(),
+            });
+        }
+    }
+    database_transaction.push(Op::Insert {
+        col: COL_BLOCK_TRANSACTIONS,
+        key: block_hash.0.to_vec(),
+        value: to_bytes![transactions]?,
+    });
+
+    Ok(())
+}
+
+/// The summary of an exported or imported ledger snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotSummary {
+    /// The height of the latest block in the snapshot.
+    pub height: BlockHeight,
+    /// The hash of the latest block in the snapshot; it should be checked against a trusted node before an import.
+    pub block_hash: BlockHeaderHash,
+    /// The SHA-256 checksum of the snapshot's contents.
+    pub checksum: [u8; 32],
+}
+
+/// The state of the ledger replayed from the transactions of a snapshot's blocks, in the order they're committed in.
+struct ReplayedState<T: TransactionScheme, P: LoadableMerkleParameters> {
+    /// The commitments, ordered by their indices.
+    commitments: Vec<T::Commitment>,
+    /// The serialized commitments, ordered by their indices.
+    commitment_keys: Vec<Vec<u8>>,
+    /// The serialized serial numbers, ordered by their indices.
+    serial_numbers: Vec<Vec<u8>>,
+    /// The serialized memos, ordered by their indices.
+    memos: Vec<Vec<u8>>,
+    /// The ledger digests, along with the heights of the blocks they were recorded at.
+    digests: Vec<(Vec<u8>, BlockHeight)>,
+    /// The kinds and keys seen so far, which may not repeat.
+    seen: HashSet<(&'static str, Vec<u8>)>,
+    /// The commitment merkle tree as of the latest replayed block.
+    tree: MerkleTree<P>,
+}
+
+impl<T: TransactionScheme, P: LoadableMerkleParameters> ReplayedState<T, P> {
+    fn new(ledger_parameters: Arc<P>) -> Result<Self, StorageError> {
+        let empty_leaves: &[T::Commitment] = &[];
+
+        Ok(Self {
+            commitments: vec![],
+            commitment_keys: vec![],
+            serial_numbers: vec![],
+            memos: vec![],
+            digests: vec![],
+            seen: HashSet::new(),
+            tree: MerkleTree::new(ledger_parameters, empty_leaves)?,
+        })
+    }
+
+    /// Returns the serialized key, unless it was already seen.
+    fn unique_key<K: ToBytes>(
+        &mut self,
+        key: &K,
+        block_number: usize,
+        kind: &'static str,
+    ) -> Result<Vec<u8>, StorageError> {
+        let key = to_bytes![key]?;
+        if !self.seen.insert((kind, key.clone())) {
+            return Err(invalid_snapshot(format!("block {} repeats a {}", block_number, kind)));
+        }
+
+        Ok(key)
+    }
+
+    /// Applies the transactions of the given block, and records the resulting ledger digest.
+    fn apply(&mut self, block_number: usize, transactions: &DPCTransactions<T>) -> Result<(), StorageError> {
+        let old_cm_count = self.commitments.len();
+        for transaction in &transactions.0 {
+            for sn in transaction.old_serial_numbers() {
+                let key = self.unique_key(sn, block_number, "serial number")?;
+                self.serial_numbers.push(key);
+            }
+            for cm in transaction.new_commitments() {
+                let key = self.unique_key(cm, block_number, "commitment")?;
+                self.commitment_keys.push(key);
+                self.commitments.push(cm.clone());
+            }
+            let key = self.unique_key(transaction.memorandum(), block_number, "memo")?;
+            self.memos.push(key);
+        }
+
+        let old_commitments = self.commitments[..old_cm_count].iter().cloned();
+        self.tree = self.tree.rebuild(old_commitments, &self.commitments[old_cm_count..])?;
+        self.digests
+            .push((to_bytes![self.tree.root()]?, block_number as BlockHeight));
+
+        Ok(())
+    }
+
+    /// Returns the ledger digest as of the latest replayed block.
+    fn digest(&self) -> Vec<u8> {
+        self.digests
+            .last()
+            .map(|(digest, _)| digest.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P, S> {
+    ///
+    /// Writes the canon chain up to the block at the given height to a snapshot file, so that the ledger's state
+    /// can be replayed from it. None of the blocks' transactions may have been pruned.
+    ///
+    pub fn export_snapshot(&self, height: BlockHeight, path: &Path) -> Result<SnapshotSummary, StorageError> {
+        if height > self.get_current_block_height() {
+            return Err(StorageError::BlockError(BlockError::InvalidBlockNumber(height)));
+        }
+        let earliest_height = self.get_earliest_block_height()?;
+        if earliest_height != 0 {
+            return Err(StorageError::Message(format!(
+                "The transactions of the blocks below {} have been pruned",
+                earliest_height
+            )));
+        }
+
+        // The snapshot is only moved to the given path once it's complete, so that a failed export doesn't leave a
+        // partial one behind.
+        let partial_path = partial_snapshot_path(path);
+        let result = self.write_snapshot(height, &partial_path).and_then(|summary| {
+            fs::rename(&partial_path, path)?;
+            Ok(summary)
+        });
+        if result.is_err() {
+            let _ = fs::remove_file(&partial_path);
+        }
+
+        result
+    }
+
+    /// Streams the canon chain up to the block at the given height to a new snapshot file.
+    fn write_snapshot(&self, height: BlockHeight, path: &Path) -> Result<SnapshotSummary, StorageError> {
+        let mut snapshot = SnapshotWriter::new(BufWriter::new(File::create(path)?), height + 1)?;
+        let mut previous_hash = None;
+        for block_number in 0..=height {
+            let block = self.get_block_from_block_number(block_number)?;
+            // A block committed or reverted in the meantime could have changed the canon chain.
+            if previous_hash.is_some() && previous_hash.as_ref() != Some(&block.header.previous_block_hash) {
+                return Err(StorageError::Message(
+                    "The ledger changed during the export of the snapshot".into(),
+                ));
+            }
+            previous_hash = Some(block.header.get_hash());
+            snapshot.write_block(&to_bytes![block]?)?;
+        }
+        let block_hash = previous_hash.expect("the genesis block is always exported");
+
+        let (writer, checksum) = snapshot.finish()?;
+        writer.into_inner().map_err(io::Error::from)?.sync_all()?;
+
+        Ok(SnapshotSummary {
+            height,
+            block_hash,
+            checksum,
+        })
+    }
+
+    ///
+    /// Creates a ledger at the given path from a snapshot file, so that only the blocks above its height need
+    /// to be synced. The snapshot is verified before anything is written: its checksum, that its blocks form a chain starting with
+    /// the genesis block and ending with the expected block, and that each of them passes the given check, e.g.
+    /// of its proof of work and difficulty, against its parent's header. The state of the ledger is then replayed
+    /// from the blocks' transactions; the blocks below its height, other than the genesis block, are treated as pruned.
+    ///
+    pub fn import_snapshot<PATH, V>(
+        path: PATH,
+        snapshot_path: &Path,
+        expected_block_hash: &BlockHeaderHash,
+        verify_block: V,
+    ) -> Result<(Self, SnapshotSummary), StorageError>
+    where
+        PATH: AsRef<Path>,
+        V: Fn(&Block<T>, &BlockHeader) -> Result<(), String>,
+    {
+        // The blocks are read and replayed one at a time, so that only their headers are kept in memory.
+        let mut snapshot = SnapshotReader::new(BufReader::new(File::open(snapshot_path)?))?;
+        if snapshot.block_count == 0 {
+            return Err(invalid_snapshot("it doesn't contain any blocks".into()));
+        }
+
+        let crh = P::H::from(FromBytes::read(&LedgerMerkleTreeParameters::load_bytes()?[..])?);
+        let ledger_parameters = Arc::new(P::from(crh));
+        let genesis_block: Block<T> = FromBytes::read(GenesisBlock::load_bytes().as_slice())?;
+
+        let height = snapshot.block_count - 1;
+        let mut state = ReplayedState::<T, P>::new(ledger_parameters.clone())?;
+        let mut headers = vec![];
+        let mut hashes = vec![];
+        let mut parent: Option<BlockHeader> = None;
+        let mut transactions = None;
+        for block_number in 0..=height as usize {
+            let block = Block::<T>::read(&snapshot.next_block()?[..])?;
+            let hash = block.header.get_hash();
+
+            match &parent {
+                None if hash != genesis_block.header.get_hash() => {
+                    return Err(invalid_snapshot(
+                        "it doesn't start with this network's genesis block".into(),
+                    ));
+                }
+                None => {}
+                Some(parent) if block.header.previous_block_hash != parent.get_hash() => {
+                    return Err(invalid_snapshot(format!(
+                        "block {} doesn't follow the previous one",
+                        block_number
+                    )));
+                }
+                Some(parent) => verify_block(&block, parent)
+                    .map_err(|e| invalid_snapshot(format!("block {} is invalid: {}", block_number, e)))?,
+            }
+
+            // The genesis block's transactions aren't covered by the check above, so the known ones are used.
+            let block_transactions = if block_number == 0 {
+                &genesis_block.transactions
+            } else {
+                &block.transactions
+            };
+            state.apply(block_number, block_transactions)?;
+
+            headers.push(to_bytes![block.header]?);
+            hashes.push(hash);
+            if block_number == height as usize {
+                transactions = Some(block_transactions.clone());
+            }
+            parent = Some(block.header);
+        }
+        let checksum = snapshot.finish()?;
+
+        let block_hash = hashes.last().cloned().expect("the snapshot contains blocks");
+        if &block_hash != expected_block_hash {
+            return Err(invalid_snapshot(format!(
+                "its latest block is {} rather than {}",
+                block_hash, expected_block_hash
+            )));
+        }
+        let transactions = transactions.expect("the latest block was replayed");
+
+        fs::create_dir_all(path.as_ref())?;
+        let storage = S::open(Some(path.as_ref()), None)?;
+        if storage.get(COL_META, KEY_BEST_BLOCK_NUMBER.as_bytes())?.is_some() {
+            return Err(StorageError::Message(format!(
+                "A ledger already exists at '{}'",
+                path.as_ref().display()
+            )));
+        }
+
+        // The headers and the chain.
+        let mut database_transaction = DatabaseTransaction::new();
+        for (block_number, (header, hash)) in headers.into_iter().zip(&hashes).enumerate() {
+            let block_number = block_number as BlockHeight;
+            database_transaction.push(Op::Insert {
+                col: COL_BLOCK_HEADER,
+                key: hash.0.to_vec(),
+                value: header,
+            });
+            database_transaction.push(Op::Insert {
+                col: COL_BLOCK_LOCATOR,
+                key: hash.0.to_vec(),
+                value: block_number.to_le_bytes().to_vec(),
+            });
+            database_transaction.push(Op::Insert {
+                col: COL_BLOCK_LOCATOR,
+                key: block_number.to_le_bytes().to_vec(),
+                value: hash.0.to_vec(),
+            });
+            if block_number != 0 {
+                database_transaction.push(Op::Insert {
+                    col: COL_CHILD_HASHES,
+                    key: hashes[block_number as usize - 1].0.to_vec(),
+                    value: bincode::serialize(&vec![hash.clone()])?,
+                });
+            }
+        }
+        storage.batch(database_transaction)?;
+
+        // The transactions of the latest block, and of the genesis block, which is never pruned.
+        let mut full_blocks = vec![(&block_hash, &transactions)];
+        if height != 0 {
+            full_blocks.push((&hashes[0], &genesis_block.transactions));
+        }
+        let mut database_transaction = DatabaseTransaction::new();
+        for (full_block_hash, full_block_transactions) in full_blocks {
+            write_block_transactions(&mut database_transaction, full_block_hash, full_block_transactions)?;
+        }
+        storage.batch(database_transaction)?;
+
+        // The state as of the latest block.
+        let mut database_transaction = DatabaseTransaction::new();
+        for (col, keys) in &[
+            (COL_COMMITMENT, &state.commitment_keys),
+            (COL_SERIAL_NUMBER, &state.serial_numbers),
+            (COL_MEMO, &state.memos),
+        ] {
+            for (index, key) in keys.iter().enumerate() {
+                database_transaction.push(Op::Insert {
+                    col: *col,
+                    key: key.clone(),
+                    value: (index as u32).to_le_bytes().to_vec(),
+                });
+            }
+        }
+        for (digest, block_number) in &state.digests {
+            database_transaction.push(Op::Insert {
+                col: COL_DIGEST,
+                key: digest.clone(),
+                value: block_number.to_le_bytes().to_vec(),
+            });
+        }
+        storage.batch(database_transaction)?;
+
+        // The metadata goes last, so that an interrupted import doesn't leave a seemingly valid ledger behind.
+        let mut database_transaction = DatabaseTransaction::new();
+        for (key, value) in [
+            (
+                KEY_CURR_CM_INDEX,
+                (state.commitment_keys.len() as u32).to_le_bytes().to_vec(),
+            ),
+            (
+                KEY_CURR_SN_INDEX,
+                (state.serial_numbers.len() as u32).to_le_bytes().to_vec(),
+            ),
+            (KEY_CURR_MEMO_INDEX, (state.memos.len() as u32).to_le_bytes().to_vec()),
+            (KEY_CURR_DIGEST, state.digest()),
+            (KEY_PRUNED_HEIGHT, height.to_le_bytes().to_vec()),
+            (KEY_BEST_BLOCK_NUMBER, height.to_le_bytes().to_vec()),
+        ] {
+            database_transaction.push(Op::Insert {
+                col: COL_META,
+                key: key.as_bytes().to_vec(),
+                value,
+            });
+        }
+        storage.batch(database_transaction)?;
+        set_schema_version(&storage, SCHEMA_VERSION)?;
+
+        let ledger = Self::load_from_storage(storage, ledger_parameters, &height.to_le_bytes())?;
+
+        Ok((ledger, SnapshotSummary {
+            height,
+            block_hash,
+            checksum,
+        }))
+    }
+}
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{Ledger, COL_META, KEY_MEMORY_POOL};
+use crate::{Ledger, COL_MEMPOOL_OVERFLOW, COL_META, KEY_MEMORY_POOL};
 use snarkvm_algorithms::traits::LoadableMerkleParameters;
 use snarkvm_dpc::{errors::StorageError, DatabaseTransaction, Op, Storage, TransactionScheme};
 
@@ -33,4 +33,46 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
         };
         self.storage.batch(DatabaseTransaction(vec![op]))
     }
+
+    /// Store a serialized memory pool entry that was spilled out of the in-memory pool.
+    pub fn store_overflow_transaction(&self, transaction_id: &[u8], entry: Vec<u8>) -> Result<(), StorageError> {
+        let op = Op::Insert {
+            col: COL_MEMPOOL_OVERFLOW,
+            key: transaction_id.to_vec(),
+            value: entry,
+        };
+        self.storage.batch(DatabaseTransaction(vec![op]))
+    }
+
+    /// Returns `true` if the transaction with the given id was spilled out of the in-memory pool.
+    pub fn contains_overflow_transaction(&self, transaction_id: &[u8]) -> bool {
+        self.storage.exists(COL_MEMPOOL_OVERFLOW, transaction_id)
+    }
+
+    /// Get the serialized entry of the transaction with the given id spilled out of the in-memory pool.
+    pub fn get_overflow_transaction(&self, transaction_id: &[u8]) -> Result<Option<Vec<u8>>, StorageError> {
+        self.storage.get(COL_MEMPOOL_OVERFLOW, transaction_id)
+    }
+
+    /// Get the ids and the serialized entries of the transactions spilled out of the in-memory pool.
+    pub fn get_overflow_transactions(&self) -> Result<Vec<(Vec<u8>, Vec<u8>)>, StorageError> {
+        Ok(self
+            .storage
+            .get_col(COL_MEMPOOL_OVERFLOW)?
+            .into_iter()
+            .map(|(id, entry)| (id.into_vec(), entry.into_vec()))
+            .collect())
+    }
+
+    /// Remove the given transactions from the ones spilled out of the in-memory pool.
+    pub fn remove_overflow_transactions(&self, transaction_ids: &[Vec<u8>]) -> Result<(), StorageError> {
+        let ops = transaction_ids
+            .iter()
+            .map(|id| Op::Delete {
+                col: COL_MEMPOOL_OVERFLOW,
+                key: id.clone(),
+            })
+            .collect();
+        self.storage.batch(DatabaseTransaction(ops))
+    }
 }
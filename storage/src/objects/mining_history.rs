@@ -0,0 +1,145 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{bytes_to_u32, Ledger, COL_META, COL_MINING_HISTORY, KEY_MINING_HISTORY_TOP};
+use snarkvm_algorithms::traits::LoadableMerkleParameters;
+use snarkvm_dpc::{errors::StorageError, DatabaseTransaction, Op, Storage, TransactionScheme};
+
+use parking_lot::{const_mutex, Mutex};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Serializes the updates of the mining history, as the events of each height are read and written back together.
+static MINING_HISTORY_LOCK: Mutex<()> = const_mutex(());
+
+/// The kinds of events recorded in the mining history.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MiningEventKind {
+    /// A block template was produced.
+    Template,
+    /// A mined block was submitted to the ledger.
+    Submitted,
+    /// A submitted block was accepted into the canonical chain.
+    Accepted,
+    /// A submitted block was rejected, or only stored as part of a fork.
+    Rejected,
+}
+
+impl fmt::Display for MiningEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self {
+            Self::Template => "template",
+            Self::Submitted => "submitted",
+            Self::Accepted => "accepted",
+            Self::Rejected => "rejected",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+/// An event in the mining history, used to reconcile the mining rewards.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MiningEvent {
+    /// The kind of the event.
+    pub kind: MiningEventKind,
+    /// The height of the produced or submitted block.
+    pub block_height: u32,
+    /// The hash of the submitted block; templates don't have one yet.
+    pub block_hash: Option<[u8; 32]>,
+    /// The hash of the parent of the produced or submitted block.
+    pub previous_block_hash: [u8; 32],
+    /// The UNIX timestamp of the event.
+    pub timestamp: i64,
+}
+
+impl MiningEvent {
+    /// Checks whether the event is about the same block as the given one, i.e. it has the same kind and height,
+    /// and the same hash or, for templates, the same parent.
+    fn is_about_same_block(&self, other: &MiningEvent) -> bool {
+        let block_id = |event: &MiningEvent| event.block_hash.unwrap_or(event.previous_block_hash);
+
+        self.kind == other.kind && self.block_height == other.block_height && block_id(self) == block_id(other)
+    }
+}
+
+impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P, S> {
+    /// Store an event in the mining history. Events about the same block replace one another, so that e.g.
+    /// frequently polled templates for the same height and parent block don't pile up.
+    pub fn store_mining_event(&self, event: &MiningEvent) -> Result<(), StorageError> {
+        let _guard = MINING_HISTORY_LOCK.lock();
+
+        let mut events = self.get_mining_events(event.block_height)?;
+        events.retain(|stored| !stored.is_about_same_block(event));
+        events.push(event.clone());
+
+        let mut ops = vec![Op::Insert {
+            col: COL_MINING_HISTORY,
+            key: event.block_height.to_be_bytes().to_vec(),
+            value: bincode::serialize(&events)?,
+        }];
+
+        // The highest height with events bounds the lookups of the open-ended ranges.
+        let top = self.get_mining_history_top()?;
+        if top.map_or(true, |top| top < event.block_height) {
+            ops.push(Op::Insert {
+                col: COL_META,
+                key: KEY_MINING_HISTORY_TOP.as_bytes().to_vec(),
+                value: event.block_height.to_le_bytes().to_vec(),
+            });
+        }
+
+        self.storage.batch(DatabaseTransaction(ops))
+    }
+
+    /// Get the events in the mining history for the blocks within the given (inclusive) range of heights,
+    /// ordered by the heights and then by the times they happened at. Only the heights up to the highest
+    /// one with any events are looked up.
+    pub fn get_mining_history(&self, start_height: u32, end_height: u32) -> Result<Vec<MiningEvent>, StorageError> {
+        let end_height = match self.get_mining_history_top()? {
+            Some(top) => end_height.min(top),
+            None => return Ok(vec![]),
+        };
+
+        let mut events = Vec::new();
+        for height in start_height..=end_height {
+            let mut events_at_height = self.get_mining_events(height)?;
+            events_at_height.sort_by_key(|event| event.timestamp);
+            events.extend(events_at_height);
+        }
+
+        Ok(events)
+    }
+
+    /// Get the events in the mining history for the blocks at the given height.
+    fn get_mining_events(&self, height: u32) -> Result<Vec<MiningEvent>, StorageError> {
+        match self.storage.get(COL_MINING_HISTORY, &height.to_be_bytes())? {
+            Some(events) => Ok(bincode::deserialize(&events)?),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Get the highest height with any events in the mining history.
+    fn get_mining_history_top(&self) -> Result<Option<u32>, StorageError> {
+        match self.storage.get(COL_META, KEY_MINING_HISTORY_TOP.as_bytes())? {
+            Some(bytes) if bytes.len() == 4 => Ok(Some(bytes_to_u32(&bytes))),
+            Some(bytes) => Err(StorageError::Message(format!(
+                "invalid mining history height record ({} bytes)",
+                bytes.len()
+            ))),
+            None => Ok(None),
+        }
+    }
+}
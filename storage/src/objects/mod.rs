@@ -15,6 +15,9 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 /// The methods defined in this module require direct access to the storage module.
+pub mod bans;
+pub use bans::*;
+
 pub mod block;
 pub use block::*;
 
@@ -30,14 +33,32 @@ pub use dpc_state::*;
 pub mod insert_commit;
 pub use insert_commit::*;
 
+pub mod journal;
+pub use journal::*;
+
 pub mod ledger_scheme;
 pub use ledger_scheme::*;
 
+pub mod ledger_snapshot;
+pub use ledger_snapshot::*;
+
 pub mod memory_pool;
 pub use memory_pool::*;
 
+pub mod mining_history;
+pub use mining_history::*;
+
+pub mod network_snapshots;
+pub use network_snapshots::*;
+
+pub mod pruning;
+pub use pruning::*;
+
 pub mod records;
 pub use records::*;
 
+pub mod sync_checkpoint;
+pub use sync_checkpoint::*;
+
 pub mod transaction;
 pub use transaction::*;
@@ -0,0 +1,104 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{Ledger, COL_META, COL_NETWORK_SNAPSHOTS, KEY_KNOWN_NETWORK};
+use snarkvm_algorithms::traits::LoadableMerkleParameters;
+use snarkvm_dpc::{errors::StorageError, DatabaseTransaction, Op, Storage, TransactionScheme};
+
+use std::convert::TryInto;
+
+/// Decodes the UNIX timestamp a network snapshot is stored under.
+fn snapshot_timestamp(key: &[u8]) -> Result<i64, StorageError> {
+    key.try_into()
+        .map(i64::from_be_bytes)
+        .map_err(|_| StorageError::Message("invalid network snapshot record".into()))
+}
+
+impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P, S> {
+    /// Get the stored view of the network discovered by the crawler.
+    pub fn get_known_network(&self) -> Result<Option<Vec<u8>>, StorageError> {
+        self.storage.get(COL_META, KEY_KNOWN_NETWORK.as_bytes())
+    }
+
+    /// Store the view of the network discovered by the crawler.
+    pub fn save_known_network(&self, network_serialized: Vec<u8>) -> Result<(), StorageError> {
+        let op = Op::Insert {
+            col: COL_META,
+            key: KEY_KNOWN_NETWORK.as_bytes().to_vec(),
+            value: network_serialized,
+        };
+        self.storage.batch(DatabaseTransaction(vec![op]))
+    }
+
+    /// Store a snapshot of the network discovered by the crawler, taken at the given UNIX timestamp.
+    pub fn store_network_snapshot(&self, timestamp: i64, network_serialized: Vec<u8>) -> Result<(), StorageError> {
+        let op = Op::Insert {
+            col: COL_NETWORK_SNAPSHOTS,
+            key: timestamp.to_be_bytes().to_vec(),
+            value: network_serialized,
+        };
+        self.storage.batch(DatabaseTransaction(vec![op]))
+    }
+
+    /// Get the UNIX timestamps of the stored network snapshots, in ascending order.
+    pub fn get_network_snapshot_times(&self) -> Result<Vec<i64>, StorageError> {
+        let mut times = self
+            .storage
+            .get_keys(COL_NETWORK_SNAPSHOTS)?
+            .iter()
+            .map(|key| snapshot_timestamp(key))
+            .collect::<Result<Vec<_>, _>>()?;
+        times.sort_unstable();
+
+        Ok(times)
+    }
+
+    /// Get the latest network snapshot taken at or before the given UNIX timestamp, along with the time it was
+    /// taken at.
+    pub fn get_network_snapshot_at(&self, timestamp: i64) -> Result<Option<(i64, Vec<u8>)>, StorageError> {
+        let taken_at = match self
+            .get_network_snapshot_times()?
+            .into_iter()
+            .take_while(|&time| time <= timestamp)
+            .last()
+        {
+            Some(taken_at) => taken_at,
+            None => return Ok(None),
+        };
+
+        Ok(self
+            .storage
+            .get(COL_NETWORK_SNAPSHOTS, &taken_at.to_be_bytes())?
+            .map(|snapshot| (taken_at, snapshot)))
+    }
+
+    /// Remove the network snapshots taken before the given UNIX timestamp; returns the number of removed snapshots.
+    pub fn prune_network_snapshots(&self, before: i64) -> Result<usize, StorageError> {
+        let ops = self
+            .get_network_snapshot_times()?
+            .into_iter()
+            .take_while(|&time| time < before)
+            .map(|time| Op::Delete {
+                col: COL_NETWORK_SNAPSHOTS,
+                key: time.to_be_bytes().to_vec(),
+            })
+            .collect::<Vec<_>>();
+        let count = ops.len();
+        self.storage.batch(DatabaseTransaction(ops))?;
+
+        Ok(count)
+    }
+}
@@ -0,0 +1,83 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    bytes_to_u32,
+    BlockHeight,
+    Ledger,
+    COL_BLOCK_TRANSACTIONS,
+    COL_META,
+    COL_TRANSACTION_LOCATION,
+    KEY_PRUNED_HEIGHT,
+};
+use snarkvm_algorithms::traits::LoadableMerkleParameters;
+use snarkvm_dpc::{errors::StorageError, BlockHeaderHash, DatabaseTransaction, Op, Storage, TransactionScheme};
+
+impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P, S> {
+    /// Returns the height of the earliest canon block whose transactions are still stored; the ones below it
+    /// were pruned, and only their headers are kept, except for the genesis block, which is always kept in full.
+    pub fn get_earliest_block_height(&self) -> Result<BlockHeight, StorageError> {
+        match self.storage.get(COL_META, KEY_PRUNED_HEIGHT.as_bytes())? {
+            Some(height_bytes) => Ok(bytes_to_u32(&height_bytes)),
+            None => Ok(0),
+        }
+    }
+
+    /// Returns `true` if the given block is canon and its transactions were pruned.
+    pub fn is_block_pruned(&self, block_hash: &BlockHeaderHash) -> bool {
+        match (self.get_block_number(block_hash), self.get_earliest_block_height()) {
+            (Ok(block_number), Ok(earliest_block_height)) => block_number != 0 && block_number < earliest_block_height,
+            _ => false,
+        }
+    }
+
+    /// Removes the transactions of the canon blocks that are more than `depth` blocks below the tip, keeping their
+    /// headers; the current state of the ledger is unaffected, and the genesis block is never pruned. Returns the
+    /// number of newly pruned blocks.
+    pub fn prune_blocks(&self, depth: u32) -> Result<u32, StorageError> {
+        let earliest_block_height = self.get_earliest_block_height()?.max(1);
+        let target_height = self.get_current_block_height().saturating_sub(depth);
+
+        for block_number in earliest_block_height..target_height {
+            let block_hash = self.get_block_hash(block_number)?;
+
+            // Every block is pruned in its own batch, so that an interrupted pruning can be resumed.
+            let mut database_transaction = DatabaseTransaction::new();
+
+            for transaction in self.get_block_transactions(&block_hash)?.0 {
+                database_transaction.push(Op::Delete {
+                    col: COL_TRANSACTION_LOCATION,
+                    key: transaction.transaction_id()?.to_vec(),
+                });
+            }
+
+            database_transaction.push(Op::Delete {
+                col: COL_BLOCK_TRANSACTIONS,
+                key: block_hash.0.to_vec(),
+            });
+
+            database_transaction.push(Op::Insert {
+                col: COL_META,
+                key: KEY_PRUNED_HEIGHT.as_bytes().to_vec(),
+                value: (block_number + 1).to_le_bytes().to_vec(),
+            });
+
+            self.storage.batch(database_transaction)?;
+        }
+
+        Ok(target_height.saturating_sub(earliest_block_height))
+    }
+}
@@ -0,0 +1,45 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{Ledger, COL_META, KEY_SYNC_CHECKPOINT};
+use snarkvm_algorithms::traits::LoadableMerkleParameters;
+use snarkvm_dpc::{errors::StorageError, DatabaseTransaction, Op, Storage, TransactionScheme};
+
+impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P, S> {
+    /// Get the stored block sync checkpoint.
+    pub fn get_sync_checkpoint(&self) -> Result<Option<Vec<u8>>, StorageError> {
+        self.storage.get(COL_META, &KEY_SYNC_CHECKPOINT.as_bytes().to_vec())
+    }
+
+    /// Store the block sync checkpoint.
+    pub fn store_sync_checkpoint(&self, checkpoint_serialized: Vec<u8>) -> Result<(), StorageError> {
+        let op = Op::Insert {
+            col: COL_META,
+            key: KEY_SYNC_CHECKPOINT.as_bytes().to_vec(),
+            value: checkpoint_serialized,
+        };
+        self.storage.batch(DatabaseTransaction(vec![op]))
+    }
+
+    /// Remove the block sync checkpoint, e.g. once the sync it tracked has concluded.
+    pub fn clear_sync_checkpoint(&self) -> Result<(), StorageError> {
+        let op = Op::Delete {
+            col: COL_META,
+            key: KEY_SYNC_CHECKPOINT.as_bytes().to_vec(),
+        };
+        self.storage.batch(DatabaseTransaction(vec![op]))
+    }
+}
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{Ledger, TransactionLocation, COL_TRANSACTION_LOCATION};
+use crate::{Ledger, TransactionLocation, COL_CM_TRANSACTION, COL_SN_TRANSACTION, COL_TRANSACTION_LOCATION};
 use snarkvm_algorithms::traits::LoadableMerkleParameters;
 use snarkvm_dpc::{errors::StorageError, BlockHeaderHash, LedgerScheme, Storage, TransactionScheme};
 use snarkvm_utilities::{
@@ -47,6 +47,16 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
         }
     }
 
+    /// Returns the ID of the canon transaction that created the record with the given commitment, if there is one.
+    pub fn get_transaction_id_by_commitment(&self, cm_bytes: &[u8]) -> Result<Option<Vec<u8>>, StorageError> {
+        self.storage.get(COL_CM_TRANSACTION, cm_bytes)
+    }
+
+    /// Returns the ID of the canon transaction that spent the record with the given serial number, if there is one.
+    pub fn get_transaction_id_by_serial_number(&self, sn_bytes: &[u8]) -> Result<Option<Vec<u8>>, StorageError> {
+        self.storage.get(COL_SN_TRANSACTION, sn_bytes)
+    }
+
     /// Returns a transaction in bytes given a transaction ID.
     pub fn get_transaction_bytes(&self, transaction_id: &[u8]) -> Result<Vec<u8>, StorageError> {
         match self.get_transaction(transaction_id)? {
@@ -57,10 +67,21 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
 
     /// Returns true if the transaction has internal parameters that already exist in the ledger.
     pub fn transaction_conflicts(&self, transaction: &T) -> bool {
-        let transaction_serial_numbers = transaction.old_serial_numbers();
-        let transaction_commitments = transaction.new_commitments();
-        let transaction_memo = transaction.memorandum();
+        self.transaction_parts_conflict(
+            transaction.old_serial_numbers(),
+            transaction.new_commitments(),
+            transaction.memorandum(),
+        )
+    }
 
+    /// Returns true if the given serial numbers, commitments and memo of a transaction, which doesn't need to be at
+    /// hand, conflict with each other or already exist in the ledger.
+    pub fn transaction_parts_conflict(
+        &self,
+        transaction_serial_numbers: &[T::SerialNumber],
+        transaction_commitments: &[T::Commitment],
+        transaction_memo: &T::Memorandum,
+    ) -> bool {
         // Check if the transactions in the block have duplicate serial numbers
         if has_duplicates(transaction_serial_numbers) {
             return true;
@@ -14,10 +14,10 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::NUM_COLS;
+use crate::{CheckpointStorage, ReadOnlyStorage, NUM_COLS};
 use snarkvm_dpc::{errors::StorageError, DatabaseTransaction, Op, Storage};
 
-use rocksdb::{ColumnFamily, ColumnFamilyDescriptor, IteratorMode, Options, WriteBatch, DB};
+use rocksdb::{checkpoint::Checkpoint, ColumnFamily, ColumnFamilyDescriptor, IteratorMode, Options, WriteBatch, DB};
 use std::path::Path;
 
 fn convert_err(err: rocksdb::Error) -> StorageError {
@@ -27,6 +27,7 @@ fn convert_err(err: rocksdb::Error) -> StorageError {
 pub struct RocksDb {
     db: Option<DB>, // the option is only for Drop (destroy) purposes
     cf_names: Vec<String>,
+    read_only: bool,
 }
 
 impl Storage for RocksDb {
@@ -64,10 +65,12 @@ impl Storage for RocksDb {
     }
 
     fn put<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, col: u32, key: K, value: V) -> Result<(), StorageError> {
+        self.check_writable()?;
         self.db().put_cf(self.get_cf_ref(col), key, value).map_err(convert_err)
     }
 
     fn batch(&self, transaction: DatabaseTransaction) -> Result<(), StorageError> {
+        self.check_writable()?;
         let mut batch = WriteBatch::default();
 
         for operation in transaction.0 {
@@ -96,12 +99,28 @@ impl Storage for RocksDb {
     }
 
     fn try_catch_up_with_primary(&self) -> Result<(), StorageError> {
+        self.check_writable()?;
         self.db()
             .try_catch_up_with_primary()
             .map_err(|e| StorageError::Message(format!("Can't catch up with primary storage: {}", e)))
     }
 }
 
+impl ReadOnlyStorage for RocksDb {
+    fn open_read_only(path: &Path) -> Result<Self, StorageError> {
+        RocksDb::open_read_only_cf(path, NUM_COLS)
+    }
+}
+
+impl CheckpointStorage for RocksDb {
+    fn create_checkpoint(&self, path: &Path) -> Result<(), StorageError> {
+        // the checkpoint hard-links the immutable SST files and copies the rest, after flushing the memtables
+        Checkpoint::new(self.db())
+            .and_then(|checkpoint| checkpoint.create_checkpoint(path))
+            .map_err(convert_err)
+    }
+}
+
 impl Drop for RocksDb {
     fn drop(&mut self) {
         // as of rocksdb = 0.15, DB::drop must be called before DB::destroy
@@ -109,9 +128,9 @@ impl Drop for RocksDb {
         let _path = db.path().to_path_buf();
         drop(db);
 
-        // destroy the database in test conditions
+        // destroy the database in test conditions, unless it belongs to another instance
         #[cfg(feature = "test")]
-        {
+        if !self.read_only {
             let _ = DB::destroy(&Options::default(), _path);
         }
     }
@@ -150,6 +169,7 @@ impl RocksDb {
         Ok(Self {
             db: Some(storage),
             cf_names,
+            read_only: false,
         })
     }
 
@@ -176,9 +196,39 @@ impl RocksDb {
         Ok(Self {
             db: Some(storage),
             cf_names,
+            read_only: false,
+        })
+    }
+
+    /// Opens a read-only storage instance from the given path with its given names; unlike a secondary
+    /// instance, it never writes any files and can't catch up with the primary instance.
+    /// If RocksDB fails to open, returns [StorageError](snarkvm_errors::storage::StorageError).
+    pub fn open_read_only_cf<P: AsRef<Path>>(path: P, num_cfs: u32) -> Result<Self, StorageError> {
+        let mut cf_names: Vec<String> = Vec::with_capacity(num_cfs as usize);
+        for column in 0..num_cfs {
+            let column_name = format!("col{}", column.to_string());
+            cf_names.push(column_name);
+        }
+        let storage_opts = Options::default();
+
+        let storage = DB::open_cf_for_read_only(&storage_opts, path, cf_names.clone(), false).map_err(convert_err)?;
+
+        Ok(Self {
+            db: Some(storage),
+            cf_names,
+            read_only: true,
         })
     }
 
+    /// Returns an error if the storage was opened read-only.
+    fn check_writable(&self) -> Result<(), StorageError> {
+        if self.read_only {
+            Err(StorageError::Message("the storage is opened read-only".into()))
+        } else {
+            Ok(())
+        }
+    }
+
     /// Returns the column family reference from a given index.
     /// If the given index does not exist, returns [None](std::option::Option).
     pub(crate) fn get_cf_ref(&self, index: u32) -> &ColumnFamily {
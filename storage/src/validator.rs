@@ -91,7 +91,7 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
     validate_tx_components!(validate_transaction_cms, "commitments", COL_COMMITMENT);
 
     /// Validates the storage of the canon blocks, their child-parent relationships, and their transactions; starts
-    /// at the current block height and goes down until the genesis block, making sure that the block-related data
+    /// at the current block height and goes down until the genesis block (or the earliest one that wasn't pruned), making sure that the block-related data
     /// stored in the database is coherent. The optional limit restricts the number of blocks to check, as
     /// it is likely that any issues are applicable only to the last few blocks. The `fix` argument determines whether
     /// the validation process should also attempt to fix the issues it encounters.
@@ -128,6 +128,9 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
 
         debug!("The block height is {}", current_height);
 
+        // The transactions of pruned blocks aren't available, so the validation stops at the earliest full block.
+        let earliest_block_height = self.get_earliest_block_height().unwrap_or(0);
+
         match self.get_best_block_number() {
             Err(_) => {
                 is_valid = false;
@@ -212,7 +215,14 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
                 &mut is_valid,
             );
 
-            if current_height == 0 {
+            if current_height == earliest_block_height {
+                if earliest_block_height != 0 {
+                    info!(
+                        "The blocks below height {} are pruned; the check is complete.",
+                        current_height
+                    );
+                }
+
                 break;
             }
 
@@ -269,7 +279,9 @@ impl<T: TransactionScheme, P: LoadableMerkleParameters, S: Storage> Ledger<T, P,
             current_hash = previous_hash;
         }
 
-        if [FixMode::TxComponents, FixMode::Everything].contains(&fix_mode) {
+        if [FixMode::TxComponents, FixMode::Everything].contains(&fix_mode) && earliest_block_height != 0 {
+            warn!("The transaction components of a pruned storage can't be validated; skipping them.");
+        } else if [FixMode::TxComponents, FixMode::Everything].contains(&fix_mode) {
             self.validate_transaction_memos(&tx_memos, &mut db_ops, &mut is_valid);
             self.validate_transaction_sns(&tx_sns, &mut db_ops, &mut is_valid);
             self.validate_transaction_cms(&tx_cms, &mut db_ops, &mut is_valid);
@@ -54,7 +54,7 @@ async fn mine_block<S: Storage>(
     let old_block_height = miner.consensus.ledger.get_current_block_height();
 
     // Duplicate blocks dont do anything
-    miner.consensus.receive_block(&block).await.ok(); // throws a duplicate error -- seemingly intentional
+    miner.consensus.receive_block(&block).await.ok(); // the block is already known -- seemingly intentional
 
     let new_block_height = miner.consensus.ledger.get_current_block_height();
     assert_eq!(old_block_height + 1, new_block_height);
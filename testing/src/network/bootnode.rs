@@ -0,0 +1,91 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    network::{handshaken_peer, test_config, test_node, TestSetup},
+    wait_until,
+};
+
+use snarkos_network::{Node, Payload};
+
+use std::{collections::HashSet, net::SocketAddr};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn bootnode_shares_fresh_reachable_addresses() {
+    // A bootnode that only shares two addresses at a time, so that the rotation is observable.
+    let mut config = test_config(TestSetup {
+        consensus_setup: None,
+        is_bootnode: true,
+        ..Default::default()
+    });
+    config.shared_peer_count = 2;
+    let bootnode = Node::new(config).await.unwrap();
+    bootnode.listen().await.unwrap();
+    bootnode.start_services().await.unwrap();
+    let bootnode_address = bootnode.local_address().unwrap();
+
+    // A small network of nodes bootstrapping from the bootnode.
+    let mut nodes = vec![];
+    for _ in 0..3 {
+        let setup = TestSetup {
+            consensus_setup: None,
+            bootnodes: vec![bootnode_address.to_string()],
+            ..Default::default()
+        };
+        nodes.push(test_node(setup).await);
+    }
+    let node_addresses: HashSet<SocketAddr> = nodes.iter().map(|node| node.local_address().unwrap()).collect();
+
+    wait_until!(
+        10,
+        node_addresses.iter().all(|addr| bootnode.peer_book.is_connected(*addr)),
+        100
+    );
+
+    let mut requester = handshaken_peer(bootnode_address).await;
+    wait_until!(5, bootnode.peer_book.connected_peers().len() == 4);
+    let requester_address = bootnode
+        .peer_book
+        .connected_peers()
+        .into_iter()
+        .find(|addr| !node_addresses.contains(addr))
+        .unwrap();
+
+    // The sharing pool contains all the other nodes, but not the requester itself.
+    let pool: HashSet<SocketAddr> = bootnode.shareable_peers(requester_address).await.into_iter().collect();
+    assert_eq!(pool, node_addresses);
+
+    // The local addresses aren't leaked to peers on other networks, nor to ones of another address family.
+    for requester in &["203.0.113.7:4131", "[::1]:4131"] {
+        assert!(bootnode.shareable_peers(requester.parse().unwrap()).await.is_empty());
+    }
+
+    // Every response is a different selection from the pool, so all of the nodes are shared over time.
+    let mut shared = HashSet::new();
+    for _ in 0..20 {
+        requester.write_message(&Payload::GetPeers).await;
+        let peers = loop {
+            if let Payload::Peers(peers) = requester.read_payload().await.unwrap() {
+                break peers;
+            }
+        };
+
+        assert_eq!(peers.len(), 2);
+        assert!(peers.iter().all(|addr| node_addresses.contains(addr)));
+        shared.extend(peers);
+    }
+    assert_eq!(shared, node_addresses);
+}
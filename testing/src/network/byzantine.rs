@@ -0,0 +1,275 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+//! A corpus of malicious message sequences, replayed against a node in order to check that protocol-hardening
+//! changes don't regress the way it treats misbehaving peers. The node doesn't ban peers on its own, so the
+//! possible verdicts are limited to ignoring, penalizing and disconnecting from them.
+
+use crate::{
+    network::{handshaken_peer, test_node, ConsensusSetup, TestSetup},
+    sync::{BLOCK_1, DATA},
+    wait_until,
+};
+
+use snarkos_network::{Node, Payload, MAX_BLOCK_SYNC_COUNT, MAX_PEERS_PAYLOAD_SIZE};
+use snarkos_storage::LedgerStorage;
+use snarkvm_dpc::{Block, BlockHeaderHash};
+use snarkvm_utilities::{to_bytes, ToBytes};
+
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+use tokio::{io::AsyncWriteExt, net::TcpStream, time::sleep};
+
+/// The time the node is given to reach the expected verdict.
+const VERDICT_TIMEOUT: Duration = Duration::from_secs(5);
+/// The time a tolerated peer needs to remain connected and unpenalized for.
+const TOLERANCE_PERIOD: Duration = Duration::from_millis(500);
+
+/// A single step of a malicious message sequence.
+pub enum Step {
+    /// Sends a payload; requires a completed handshake.
+    Send(Payload),
+    /// Writes raw bytes to the connection, bypassing the encryption.
+    SendRaw(Vec<u8>),
+    /// Waits for the node to send a payload of the given type, e.g. "getsync", skipping the ones before it;
+    /// requires a completed handshake.
+    Await(&'static str),
+    /// Pauses for the given amount of time.
+    Wait(Duration),
+}
+
+/// The way the node treats the peer at the end of a malicious message sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// The peer remains connected and isn't penalized.
+    Tolerated,
+    /// The peer remains connected, but a failure is registered against it.
+    Penalized,
+    /// The node drops the connection with the peer.
+    Disconnected,
+}
+
+/// A malicious message sequence along with the expected reaction of the node.
+pub struct ByzantineCase {
+    pub name: &'static str,
+    pub node_setup: TestSetup,
+    /// Indicates whether the handshake is completed before the steps are replayed.
+    pub handshake: bool,
+    pub steps: Vec<Step>,
+    pub verdict: Verdict,
+}
+
+impl ByzantineCase {
+    fn new(name: &'static str, handshake: bool, steps: Vec<Step>, verdict: Verdict) -> Self {
+        Self {
+            name,
+            node_setup: TestSetup::default(),
+            handshake,
+            steps,
+            verdict,
+        }
+    }
+}
+
+/// A block whose transactions don't match its header, i.e. the header of the first block following the genesis
+/// block combined with the transactions of the second one.
+fn conflicting_block() -> Vec<u8> {
+    let block = Block {
+        header: DATA.block_1.header.clone(),
+        transactions: DATA.block_2.transactions.clone(),
+    };
+
+    to_bytes![block].unwrap()
+}
+
+/// Returns the malicious message sequences along with the expected reactions of the node.
+pub fn byzantine_corpus() -> Vec<ByzantineCase> {
+    let oversized_peer_list = (0..MAX_PEERS_PAYLOAD_SIZE as u32)
+        .map(|i| SocketAddr::from((i.to_be_bytes(), 4131)))
+        .collect();
+    let oversized_sync = (0..=MAX_BLOCK_SYNC_COUNT)
+        .map(|i| BlockHeaderHash::new(vec![i as u8; 32]))
+        .collect();
+    let mut truncated_handshake = vec![16u8];
+    truncated_handshake.extend_from_slice(&[0xab; 16]);
+
+    vec![
+        ByzantineCase::new(
+            "empty handshake message",
+            false,
+            vec![Step::SendRaw(vec![0u8; 64])],
+            Verdict::Disconnected,
+        ),
+        ByzantineCase::new(
+            "truncated handshake message",
+            false,
+            vec![Step::SendRaw(truncated_handshake)],
+            Verdict::Disconnected,
+        ),
+        ByzantineCase::new(
+            "garbage after the handshake",
+            true,
+            vec![Step::SendRaw(vec![0xff; 64])],
+            Verdict::Disconnected,
+        ),
+        ByzantineCase::new(
+            "oversized peer list",
+            true,
+            vec![Step::Send(Payload::Peers(oversized_peer_list))],
+            Verdict::Disconnected,
+        ),
+        ByzantineCase::new(
+            "payload that wasn't negotiated",
+            true,
+            vec![Step::Send(Payload::BlockHash(BlockHeaderHash::new(vec![1u8; 32])))],
+            Verdict::Disconnected,
+        ),
+        ByzantineCase::new(
+            "unsolicited sync",
+            true,
+            vec![Step::Send(Payload::Sync(vec![BlockHeaderHash::new(vec![1u8; 32])]))],
+            Verdict::Penalized,
+        ),
+        ByzantineCase::new(
+            "unsolicited sync block",
+            true,
+            vec![Step::Send(Payload::SyncBlock(BLOCK_1.to_vec()))],
+            Verdict::Penalized,
+        ),
+        ByzantineCase {
+            node_setup: TestSetup {
+                consensus_setup: Some(ConsensusSetup {
+                    block_sync_interval: 1,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            ..ByzantineCase::new(
+                "more sync hashes than requested",
+                true,
+                vec![
+                    Step::Wait(Duration::from_secs(1)),
+                    Step::Send(Payload::Ping(2, 0)),
+                    Step::Await("getsync"),
+                    Step::Send(Payload::Sync(oversized_sync)),
+                ],
+                Verdict::Penalized,
+            )
+        },
+        ByzantineCase::new(
+            "block conflicting with its header",
+            true,
+            vec![Step::Send(Payload::Block(conflicting_block()))],
+            Verdict::Penalized,
+        ),
+        ByzantineCase::new(
+            "garbage block",
+            true,
+            vec![Step::Send(Payload::Block(vec![0xab; 256]))],
+            Verdict::Tolerated,
+        ),
+        ByzantineCase::new(
+            "transaction spam",
+            true,
+            (0..16)
+                .map(|_| Step::Send(Payload::Transaction(vec![0xab; 256])))
+                .collect(),
+            Verdict::Tolerated,
+        ),
+    ]
+}
+
+/// Determines the way the node currently treats the single peer it's been connected to.
+async fn current_verdict(node: &Node<LedgerStorage>) -> Verdict {
+    if node.peer_book.get_active_peer_count() == 0 {
+        return Verdict::Disconnected;
+    }
+
+    let peers = node.peer_book.connected_peers_snapshot().await;
+    if peers.iter().any(|peer| !peer.quality.failures.is_empty()) {
+        Verdict::Penalized
+    } else {
+        Verdict::Tolerated
+    }
+}
+
+/// Replays the given case against a new node and returns the node's verdict on the peer; it's the expected one
+/// if the node reaches it within `VERDICT_TIMEOUT`, or the last observed one otherwise.
+pub async fn replay(case: &ByzantineCase) -> Verdict {
+    let node = test_node(case.node_setup.clone()).await;
+    let node_address = node.local_address().unwrap();
+
+    if case.handshake {
+        let mut peer = handshaken_peer(node_address).await;
+        wait_until!(1, node.peer_book.get_active_peer_count() == 1);
+
+        for step in &case.steps {
+            match step {
+                Step::Send(payload) => peer.write_message(payload).await,
+                Step::SendRaw(bytes) => peer.write_bytes(bytes).await,
+                Step::Await(payload_type) => loop {
+                    let payload = tokio::time::timeout(VERDICT_TIMEOUT, peer.read_payload())
+                        .await
+                        .unwrap_or_else(|_| panic!("{}: no '{}' message arrived", case.name, payload_type))
+                        .unwrap();
+                    if payload.to_string() == *payload_type {
+                        break;
+                    }
+                },
+                Step::Wait(duration) => sleep(*duration).await,
+            }
+        }
+
+        judge(&node, case.verdict).await
+    } else {
+        let mut stream = TcpStream::connect(node_address).await.unwrap();
+        wait_until!(1, node.peer_book.get_active_peer_count() == 1);
+
+        for step in &case.steps {
+            match step {
+                Step::SendRaw(bytes) => {
+                    // The node may have already dropped the connection.
+                    let _ = stream.write_all(bytes).await;
+                }
+                Step::Wait(duration) => sleep(*duration).await,
+                Step::Send(..) | Step::Await(..) => panic!("{}: payloads require a handshake", case.name),
+            }
+        }
+
+        judge(&node, case.verdict).await
+    }
+}
+
+/// Waits for the node to reach the expected verdict; a tolerated peer also needs to remain so for a while.
+async fn judge(node: &Node<LedgerStorage>, expected: Verdict) -> Verdict {
+    let start = Instant::now();
+    let mut verdict = current_verdict(node).await;
+    while verdict != expected && start.elapsed() < VERDICT_TIMEOUT {
+        sleep(Duration::from_millis(10)).await;
+        verdict = current_verdict(node).await;
+    }
+
+    if verdict == Verdict::Tolerated {
+        sleep(TOLERANCE_PERIOD).await;
+        verdict = current_verdict(node).await;
+    }
+
+    node.shut_down().await;
+
+    verdict
+}
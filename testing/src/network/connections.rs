@@ -0,0 +1,191 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    network::{handshaken_node_and_peer, handshaken_peer, test_node, TestSetup},
+    wait_until,
+};
+
+use snarkos_network::{NodeBuilder, Payload, PortPolicy};
+use snarkos_storage::LedgerStorage;
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    io::AsyncReadExt,
+    net::{TcpListener, TcpStream},
+    time::{sleep, timeout},
+};
+
+#[tokio::test]
+async fn inbound_connections_leave_outbound_slots_free() {
+    // half of the 4 slots are reserved for outbound connections
+    let setup = TestSetup {
+        max_peers: 4,
+        ..Default::default()
+    };
+    let node = test_node(setup).await;
+    let node_listener = node.local_address().unwrap();
+    assert_eq!(node.max_inbound_peers(), 2);
+
+    let _peer_1 = handshaken_peer(node_listener).await;
+    let _peer_2 = handshaken_peer(node_listener).await;
+    wait_until!(5, node.peer_book.get_inbound_peer_count() == 2);
+    assert_eq!(node.peer_book.get_outbound_peer_count(), 0);
+
+    // another inbound connection is closed right away, instead of awaiting the handshake
+    let mut stream = TcpStream::connect(node_listener).await.unwrap();
+    let mut buf = [0u8; 1];
+    let read = timeout(Duration::from_secs(5), stream.read(&mut buf))
+        .await
+        .expect("the connection wasn't closed");
+    assert!(matches!(read, Ok(0) | Err(_)));
+}
+
+#[tokio::test]
+async fn simultaneous_dialing_keeps_one_connection() {
+    let setup = || TestSetup {
+        consensus_setup: None,
+        ..Default::default()
+    };
+    let node_a = test_node(setup()).await;
+    let node_b = test_node(setup()).await;
+    let address_a = node_a.local_address().unwrap();
+    let address_b = node_b.local_address().unwrap();
+
+    // both nodes dial each other before either connection is established
+    let _ = tokio::join!(node_a.add_peer(address_b), node_b.add_peer(address_a));
+
+    wait_until!(
+        5,
+        node_a.peer_book.is_connected(address_b)
+            && node_b.peer_book.is_connected(address_a)
+            && node_a.peer_book.pending_connections() == 0
+            && node_b.peer_book.pending_connections() == 0
+    );
+
+    // give the duplicate connection time to be dropped on both sides
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    assert!(node_a.peer_book.is_connected(address_b));
+    assert!(node_b.peer_book.is_connected(address_a));
+    assert_eq!(node_a.peer_book.get_active_peer_count(), 1);
+    assert_eq!(node_b.peer_book.get_active_peer_count(), 1);
+
+    // both sides kept the connection initiated by the node with the lower ID
+    let a_initiated = node_a.id < node_b.id;
+    assert_eq!(node_a.peer_book.is_outbound(address_b), a_initiated);
+    assert_eq!(node_b.peer_book.is_outbound(address_a), !a_initiated);
+}
+
+#[tokio::test]
+async fn builder_reports_connected_peers() {
+    let connected = Arc::new(Mutex::new(Vec::new()));
+    let connected_clone = connected.clone();
+
+    let node = NodeBuilder::<LedgerStorage>::new("127.0.0.1:0".parse().unwrap())
+        .peer_sync_interval(Duration::from_secs(600))
+        .configure(|config| config.gossip_port_policy = PortPolicy::Any)
+        .on_peer_connected(move |peer| connected_clone.lock().unwrap().push(peer.clone()))
+        .start()
+        .await
+        .unwrap();
+    let node_listener = node.local_address().unwrap();
+
+    let _peer = handshaken_peer(node_listener).await;
+    wait_until!(5, connected.lock().unwrap().len() == 1);
+
+    let peer = connected.lock().unwrap()[0].clone();
+    assert!(!peer.outbound);
+    assert!(node.peer_book.is_connected(peer.address));
+}
+
+#[tokio::test]
+async fn queued_handshakes_wait_for_a_permit_before_connecting() {
+    let node = NodeBuilder::<LedgerStorage>::new("127.0.0.1:0".parse().unwrap())
+        .peer_sync_interval(Duration::from_secs(600))
+        .configure(|config| {
+            config.gossip_port_policy = PortPolicy::Any;
+            config.max_concurrent_handshakes = 1;
+        })
+        .start()
+        .await
+        .unwrap();
+
+    // a listener that accepts the connection, but never responds to the handshake, holds the only permit
+    let stalled = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let stalled_address = stalled.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (_stream, _) = stalled.accept().await.unwrap();
+        sleep(Duration::from_secs(60)).await;
+    });
+
+    // nothing listens at the other address, so its handshake fails as soon as it's attempted
+    let closed_address = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+
+    let now_millis = || SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as i64;
+    node.add_peer(stalled_address).await.unwrap();
+    let queued_at = now_millis();
+    node.add_peer(closed_address).await.unwrap();
+
+    wait_until!(
+        15,
+        node.peer_book.pending_connections() == 0 && node.peer_book.is_disconnected(closed_address)
+    );
+
+    // the queued attempt only started, and marked the peer as seen, once the stalled handshake timed out
+    let first_seen = node
+        .peer_book
+        .get_disconnected_peer(closed_address)
+        .and_then(|peer| peer.quality.first_seen)
+        .unwrap();
+    let handshake_timeout_millis = snarkos_network::HANDSHAKE_PEER_TIMEOUT_SECS as i64 * 1000;
+    assert!(first_seen.timestamp_millis() - queued_at >= handshake_timeout_millis - 1000);
+}
+
+#[tokio::test]
+async fn peers_report_their_degrees_when_asked_for_their_peers() {
+    let setup = TestSetup {
+        consensus_setup: None,
+        peer_sync_interval: 1,
+        ..Default::default()
+    };
+    let (node, mut peer) = handshaken_node_and_peer(setup).await;
+    wait_until!(5, node.peer_book.get_active_peer_count() == 1);
+    let peer_address = node.connected_peers()[0];
+    let peer_info = node.peer_book.get_peer(peer_address).await.unwrap();
+    assert_eq!(peer_info.quality.reported_degree, None);
+
+    // The node periodically asks its peers for theirs; the response lists the peer's connections.
+    loop {
+        if let Payload::GetPeers = peer.read_payload().await.unwrap() {
+            break;
+        }
+    }
+    let connections = vec![
+        "127.0.0.1:1".parse().unwrap(),
+        "127.0.0.1:2".parse().unwrap(),
+        "127.0.0.1:3".parse().unwrap(),
+    ];
+    peer.write_message(&Payload::Peers(connections)).await;
+
+    wait_until!(5, {
+        let peer = node.peer_book.get_peer(peer_address).await.unwrap();
+        peer.quality.reported_degree == Some(3)
+    });
+}
@@ -0,0 +1,132 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+//! The seeds of the fuzzing tests: payloads captured from the traffic of a real node, along with synthetic ones
+//! covering the payload types that the capture doesn't.
+
+use crate::network::{handshaken_node_and_peer, TestSetup};
+
+use snarkos_network::{AddressChange, Cipher, Payload, MAX_MESSAGE_SIZE, NOISE_BUF_LEN};
+use snarkvm_dpc::BlockHeaderHash;
+
+use std::time::Duration;
+
+/// The requests sent to the node whose traffic is captured.
+fn capture_requests() -> Vec<Payload> {
+    vec![
+        Payload::Ping(0, 0),
+        Payload::GetPeers,
+        Payload::GetMemoryPool,
+        Payload::GetSync(vec![]),
+    ]
+}
+
+/// Sends a series of requests to a node and returns the serialized payloads exchanged with it, i.e. both the
+/// requests and everything the node sends back until it goes quiet.
+pub async fn capture_corpus(node_setup: TestSetup) -> Vec<Vec<u8>> {
+    let (_node, mut peer) = handshaken_node_and_peer(node_setup).await;
+
+    let mut corpus = vec![];
+    for request in capture_requests() {
+        corpus.push(Payload::serialize(&request).unwrap());
+        peer.write_message(&request).await;
+    }
+
+    while let Ok(Ok(payload)) = tokio::time::timeout(Duration::from_millis(500), peer.read_raw_payload()).await {
+        corpus.push(payload);
+    }
+
+    corpus
+}
+
+/// Returns a serialized payload of every type that can be constructed without a node.
+pub fn synthetic_corpus() -> Vec<Vec<u8>> {
+    let hash = BlockHeaderHash::new(vec![1u8; 32]);
+    let blob = vec![2u8; 256];
+
+    vec![
+        Payload::Block(blob.clone()),
+        Payload::GetBlocks(vec![hash.clone(); 4]),
+        Payload::GetMemoryPool,
+        Payload::GetPeers,
+        Payload::GetSync(vec![hash.clone(); 4]),
+        Payload::MemoryPool(vec![blob.clone(); 4]),
+        Payload::Peers(vec![
+            "127.0.0.1:4131".parse().unwrap(),
+            "[2001:db8::1]:4131".parse().unwrap(),
+        ]),
+        Payload::Ping(u32::MAX, i64::MAX),
+        Payload::Pong,
+        Payload::Sync(vec![hash.clone(); 4]),
+        Payload::SyncBlock(blob.clone()),
+        Payload::Transaction(blob),
+        Payload::BlockHash(hash.clone()),
+        Payload::GetBlock(hash),
+        Payload::Rekey,
+        Payload::AddressChange(AddressChange {
+            previous: "0.0.0.0:4131".parse().unwrap(),
+            address: "0.0.0.0:4141".parse().unwrap(),
+            public_key: vec![3u8; 32],
+            signature: vec![4u8; 64],
+        }),
+    ]
+    .iter()
+    .map(|payload| Payload::serialize(payload).unwrap())
+    .collect()
+}
+
+/// Returns a pair of ciphers established with each other, so that the frames encrypted by one can be decrypted
+/// by the other.
+pub fn cipher_pair() -> (Cipher, Cipher) {
+    let builder = || {
+        snow::Builder::with_resolver(
+            snarkos_network::HANDSHAKE_PATTERN.parse().unwrap(),
+            Box::new(snow::resolvers::SodiumResolver),
+        )
+    };
+    let (initiator_builder, responder_builder) = (builder(), builder());
+    let initiator_key = initiator_builder.generate_keypair().unwrap().private;
+    let responder_key = responder_builder.generate_keypair().unwrap().private;
+    let mut initiator = initiator_builder
+        .local_private_key(&initiator_key)
+        .psk(3, snarkos_network::HANDSHAKE_PSK)
+        .build_initiator()
+        .unwrap();
+    let mut responder = responder_builder
+        .local_private_key(&responder_key)
+        .psk(3, snarkos_network::HANDSHAKE_PSK)
+        .build_responder()
+        .unwrap();
+    let (mut buffer, mut payload) = ([0u8; 1024], [0u8; 1024]);
+
+    // -> e; <- e, ee, s, es; -> s, se, psk
+    let len = initiator.write_message(&[], &mut buffer).unwrap();
+    responder.read_message(&buffer[..len], &mut payload).unwrap();
+    let len = responder.write_message(&[], &mut buffer).unwrap();
+    initiator.read_message(&buffer[..len], &mut payload).unwrap();
+    let len = initiator.write_message(&[], &mut buffer).unwrap();
+    responder.read_message(&buffer[..len], &mut payload).unwrap();
+
+    let cipher = |noise: snow::HandshakeState| {
+        Cipher::new(
+            noise.into_transport_mode().unwrap(),
+            vec![0u8; MAX_MESSAGE_SIZE + 4096].into(),
+            vec![0u8; NOISE_BUF_LEN].into(),
+        )
+    };
+
+    (cipher(initiator), cipher(responder))
+}
@@ -0,0 +1,127 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    network::{test_config, test_node, TestSetup},
+    sync::FIXTURE_VK,
+    wait_until,
+};
+
+use snarkos_network::{Connection, KnownNetwork, Node};
+
+use std::sync::Arc;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn crawler_discovers_connections() {
+    let setup = TestSetup {
+        consensus_setup: None,
+        ..Default::default()
+    };
+    let hub = test_node(setup).await;
+    let hub_address = hub.local_address().unwrap();
+
+    let setup = TestSetup {
+        consensus_setup: None,
+        bootnodes: vec![hub_address.to_string()],
+        ..Default::default()
+    };
+    let spoke = test_node(setup).await;
+    let spoke_address = spoke.local_address().unwrap();
+
+    wait_until!(5, hub.peer_book.is_connected(spoke_address));
+
+    let setup = TestSetup {
+        consensus_setup: None,
+        peer_sync_interval: 1,
+        is_crawler: true,
+        bootnodes: vec![hub_address.to_string()],
+        ..Default::default()
+    };
+    let crawler = test_node(setup).await;
+    let known_network = crawler.known_network().unwrap();
+
+    // The crawler learns about the connection between the hub and the spoke from the hub.
+    wait_until!(
+        15,
+        known_network
+            .connections()
+            .contains(&Connection::new(hub_address, spoke_address)),
+        100
+    );
+    assert!(known_network.nodes().contains(&spoke_address));
+
+    // The crawler has connected to the hub, so it can vouch for its responsiveness.
+    let reliability = known_network.reliability(hub_address).unwrap();
+    assert!(reliability.responsiveness().unwrap() > 0.0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn crawler_persists_the_network() {
+    let setup = TestSetup {
+        consensus_setup: None,
+        ..Default::default()
+    };
+    let hub = test_node(setup).await;
+    let hub_address = hub.local_address().unwrap();
+
+    let setup = TestSetup {
+        consensus_setup: None,
+        bootnodes: vec![hub_address.to_string()],
+        ..Default::default()
+    };
+    let spoke = test_node(setup).await;
+    let spoke_address = spoke.local_address().unwrap();
+
+    wait_until!(5, hub.peer_book.is_connected(spoke_address));
+
+    let storage = Arc::new(FIXTURE_VK.ledger());
+    let crawler_setup = TestSetup {
+        consensus_setup: None,
+        peer_sync_interval: 1,
+        is_crawler: true,
+        bootnodes: vec![hub_address.to_string()],
+        ..Default::default()
+    };
+    let mut crawler = Node::new(test_config(crawler_setup.clone())).await.unwrap();
+    crawler.set_crawler_storage(storage.clone());
+    crawler.listen().await.unwrap();
+    crawler.start_services().await.unwrap();
+
+    // The crawled network is persisted at the end of each crawling round.
+    let connection = Connection::new(hub_address, spoke_address);
+    let is_persisted = || match storage.get_known_network().unwrap() {
+        Some(bytes) => KnownNetwork::deserialize(&bytes)
+            .unwrap()
+            .connections()
+            .contains(&connection),
+        None => false,
+    };
+    wait_until!(20, is_persisted(), 100);
+
+    // The first round also took a snapshot of the network.
+    let (taken_at, snapshot) = storage.get_network_snapshot_at(i64::MAX).unwrap().unwrap();
+    assert!(KnownNetwork::deserialize(&snapshot).is_ok());
+    assert!(storage.get_network_snapshot_at(taken_at - 1).unwrap().is_none());
+    crawler.shut_down().await;
+
+    // A restarted crawler starts from the persisted network.
+    let mut restarted = Node::new(test_config(crawler_setup)).await.unwrap();
+    restarted.set_crawler_storage(storage);
+    restarted.listen().await.unwrap();
+    restarted.start_services().await.unwrap();
+
+    assert!(restarted.known_network().unwrap().connections().contains(&connection));
+}
@@ -0,0 +1,40 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkos_network::{LinkFaults, Node};
+use snarkos_storage::LedgerStorage;
+
+/// Injects the given faults into the messages sent in both directions between two nodes.
+///
+/// This function assumes both nodes have an established address; the faults apply to any
+/// current or future connection between them.
+pub fn set_link_faults(a: &Node<LedgerStorage>, b: &Node<LedgerStorage>, faults: LinkFaults) {
+    let (addr_a, addr_b) = (a.local_address().unwrap(), b.local_address().unwrap());
+
+    a.fault_injector.set_link(addr_b, faults.clone());
+    b.fault_injector.set_link(addr_a, faults);
+}
+
+/// Injects the given faults only into the messages sent from one node to another.
+pub fn set_one_way_link_faults(from: &Node<LedgerStorage>, to: &Node<LedgerStorage>, faults: LinkFaults) {
+    from.fault_injector.set_link(to.local_address().unwrap(), faults);
+}
+
+/// Stops injecting faults into the messages sent in both directions between two nodes.
+pub fn clear_link_faults(a: &Node<LedgerStorage>, b: &Node<LedgerStorage>) {
+    a.fault_injector.clear_link(b.local_address().unwrap());
+    b.fault_injector.clear_link(a.local_address().unwrap());
+}
@@ -0,0 +1,117 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkos_network::{Features, HandshakeVector, Version};
+
+/// The environment variable naming a file the generated vectors are written to, so that they can be used to
+/// update the expected ones after an intentional change to the handshake.
+const VECTORS_OUTPUT_VAR: &str = "SNARKOS_HANDSHAKE_VECTORS_OUTPUT";
+
+/// The versions exchanged in the test vectors; changing them changes the vectors.
+fn vector_versions() -> (Version, Version) {
+    let initiator = Version::new(2, 4131, 1)
+        .with_features(Features::NODE_ALIAS | Features::COMPACT_BLOCKS)
+        .with_timestamp(1_600_000_000);
+    let responder = Version::new(2, 4132, 2)
+        .with_external_address(Some("203.0.113.7:4132".parse().unwrap()))
+        .with_earliest_block_height(1024)
+        .with_features(Features::REKEY | Features::RPC)
+        .with_timestamp(1_600_000_002);
+
+    (initiator, responder)
+}
+
+fn expected_vectors() -> Vec<HandshakeVector> {
+    vec![
+        HandshakeVector {
+            initiator_seed: 1,
+            responder_seed: 2,
+            handshake_messages: vec![
+                "308c392e7d852ceea76f649732d3c91a42eeef50452652406a0b3215f98b4172178e3171c31d0e8e3d1d7250973cad6c3b".into(),
+                "880925822a33fbd9c4c023b4fe321acaa4a870503aaa26af5c02e2d2f74e9eb97a5a312a13254aa39a31ce5121415725c69b755555085e4f6d85f566557d74bb5741ca12014a85ea3dc4ea969944f2e6be9996b5b85a8cb6d11187e3b82ccf45709a11576838b2dd356dd0b380e5717d8ccfe22c972454089163d4dfce111042a191bfa923c8ade4e1".into(),
+                "541504e8091b52e272d8156c193a0a25464a519150729e4817bf30d9280052edd09a8e07460710ba917757eca840069b745598e265176a68685e804b7d100a687aad4bc01c72b4df0dc67efa91fcf5a897934328f1".into(),
+            ],
+            handshake_hash: "b9c51bcf0e21fc8678dc1793d5fcd515eaf79008a8d80d3cd072079e6faa399a".into(),
+            transport_messages: vec![
+                "000000388bbda51c326e6da23d00561aab8b878ef4a5b9bd6fb7c9b6008a8d805cc4d559e5359a7450552ed0a21d4b7ca5a4172fae874357fb78ec41".into(),
+                "000000385c3dac51fb0d9bebd63e3392f3199810c54caed37103f79308785b23471bea24c0e263cd8bd2a28276440c6e2f6d98354ddb88b5ba1fde80".into(),
+            ],
+        },
+        HandshakeVector {
+            initiator_seed: 0xdead_beef,
+            responder_seed: 0x5eed,
+            handshake_messages: vec![
+                "3004aca98783cf6786aafda5b52a51e0fce93f72b1e560e244b965d0f0fd6f92298516654e2b813e4ec840de26b628b170".into(),
+                "886565ba3fcef232c5f4c9a9f556432552301e93f6068f3cbcdd08995cd718ea6d75ce8af5b9c9d9c91482fafb257891ec7fe248aae1105337633ec94c32e94d0956cfe6f6a44913a3170910790aaa7314d1a7b1ca1b622341c50ef23293c00fc524ca7f1711c24471b86a5ff8b30457a1bf59f57a15c99a930f12b833b25958183f1460693725cd3f".into(),
+                "54269be21dce4b898804e52c3094c013d6416523fafe3912476f54651676062b7e8058e2cb296e2dfab445e95522644ef73f520166d6a43f5b542ab6f38a3b1804afcf5f3b22f2c2927c454edc7d5a2d7f6b5cff9b".into(),
+            ],
+            handshake_hash: "a40dcd5301f124a8356a39e48e8dcc60acdf73a2c903186250f9bc32216db516".into(),
+            transport_messages: vec![
+                "00000038ae174bd18c83bf62e2c7178252d095bfcd2047c0c7789e6c3c47808075d6cf96b7ecf6517dbd7afc6344cf95fc89b4959f5be32b13a15386".into(),
+                "0000003836535b13f5f9c05f8d3ecb7e5d8eed26a025e546618466e031b2610423529e8f6b7625058c7af0e7e9b7a29159cdda4c612c11671bf96aff".into(),
+            ],
+        },
+    ]
+}
+
+#[tokio::test]
+async fn handshake_matches_test_vectors() {
+    let (initiator_version, responder_version) = vector_versions();
+    let mut generated = Vec::new();
+
+    for expected in expected_vectors() {
+        let vector = HandshakeVector::generate(
+            expected.initiator_seed,
+            &initiator_version,
+            expected.responder_seed,
+            &responder_version,
+        )
+        .await
+        .unwrap();
+
+        generated.push((vector, expected));
+    }
+
+    if let Ok(output_path) = std::env::var(VECTORS_OUTPUT_VAR) {
+        let json = generated.iter().map(|(vector, _)| vector.to_json()).collect::<Vec<_>>();
+        std::fs::write(output_path, json.join("\n")).unwrap();
+    }
+
+    for (vector, expected) in generated {
+        assert_eq!(vector, expected);
+    }
+}
+
+#[tokio::test]
+async fn handshake_vectors_are_deterministic() {
+    let (initiator_version, responder_version) = vector_versions();
+
+    let vector = HandshakeVector::generate(7, &initiator_version, 8, &responder_version)
+        .await
+        .unwrap();
+    let repeated = HandshakeVector::generate(7, &initiator_version, 8, &responder_version)
+        .await
+        .unwrap();
+    assert_eq!(vector, repeated);
+
+    // a different responder key changes everything but the first message, sent before its keys are used
+    let other = HandshakeVector::generate(7, &initiator_version, 9, &responder_version)
+        .await
+        .unwrap();
+    assert_eq!(vector.handshake_messages[0], other.handshake_messages[0]);
+    assert_ne!(vector.handshake_messages[1], other.handshake_messages[1]);
+    assert_ne!(vector.handshake_hash, other.handshake_hash);
+}
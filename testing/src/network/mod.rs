@@ -14,14 +14,35 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
+#[cfg(test)]
+pub mod bootnode;
+
+pub mod byzantine;
+
+#[cfg(test)]
+pub mod connections;
+
+pub mod corpus;
+
+#[cfg(test)]
+pub mod crawler;
+
 #[cfg(test)]
 pub mod encryption;
 
+pub mod faults;
+
+#[cfg(test)]
+pub mod handshake_vectors;
+
 #[cfg(test)]
 pub mod sync;
 
 pub mod topology;
 
+#[cfg(test)]
+pub mod watcher;
+
 use crate::sync::FIXTURE;
 
 use snarkos_network::{errors::*, *};
@@ -102,6 +123,7 @@ pub struct TestSetup {
     pub min_peers: u16,
     pub max_peers: u16,
     pub is_bootnode: bool,
+    pub is_crawler: bool,
     pub bootnodes: Vec<String>,
     pub tokio_handle: Option<runtime::Handle>,
 }
@@ -116,6 +138,7 @@ impl TestSetup {
         min_peers: u16,
         max_peers: u16,
         is_bootnode: bool,
+        is_crawler: bool,
         bootnodes: Vec<String>,
         tokio_handle: Option<runtime::Handle>,
     ) -> Self {
@@ -127,6 +150,7 @@ impl TestSetup {
             min_peers,
             max_peers,
             is_bootnode,
+            is_crawler,
             bootnodes,
             tokio_handle,
         }
@@ -143,6 +167,7 @@ impl Default for TestSetup {
             min_peers: 1,
             max_peers: 100,
             is_bootnode: false,
+            is_crawler: false,
             bootnodes: vec![],
             tokio_handle: None,
         }
@@ -162,15 +187,26 @@ pub fn test_consensus(setup: ConsensusSetup) -> Sync<LedgerStorage> {
 
 /// Returns a `Config` struct based on the given `TestSetup`.
 pub fn test_config(setup: TestSetup) -> Config {
-    Config::new(
+    let mut config = Config::new(
         setup.socket_address,
+        vec![],
+        None,
         setup.min_peers,
         setup.max_peers,
         setup.bootnodes,
         setup.is_bootnode,
+        setup.is_crawler,
         Duration::from_secs(setup.peer_sync_interval),
+        false,
+        None,
+        false,
+        false,
     )
-    .unwrap()
+    .unwrap();
+    // The test nodes listen on random ports.
+    config.gossip_port_policy = PortPolicy::Any;
+
+    config
 }
 
 /// Starts a node with the specified bootnodes.
@@ -185,16 +221,38 @@ pub async fn test_node(setup: TestSetup) -> Node<LedgerStorage> {
     }
 
     node.listen().await.unwrap();
-    node.start_services().await;
+    node.start_services().await.unwrap();
 
     if is_miner {
         let miner_address = FIXTURE.test_accounts[0].address.clone();
-        MinerInstance::new(miner_address, node.clone()).spawn();
+        node.enable_mining(miner_address);
+        node.start_mining(1).unwrap();
     }
 
     node
 }
 
+/// Returns a `Watcher` following the test chain from its genesis block.
+pub fn test_watcher() -> Watcher {
+    Watcher::new(
+        crate::sync::TEST_CONSENSUS_PARAMS.clone(),
+        crate::sync::genesis().header,
+        0,
+    )
+}
+
+/// Starts a watcher node with the specified bootnodes; the consensus setup is ignored.
+pub async fn test_watcher_node(setup: TestSetup) -> Node<LedgerStorage> {
+    let config = test_config(setup);
+    let mut node = Node::new(config).await.unwrap();
+    node.set_watcher(test_watcher());
+
+    node.listen().await.unwrap();
+    node.start_services().await.unwrap();
+
+    node
+}
+
 pub struct FakeNode {
     network: PeerIOHandle,
     reader: PeerReader<OwnedReadHalf>,
@@ -204,15 +262,16 @@ impl FakeNode {
     pub fn new(stream: TcpStream, _peer_addr: SocketAddr, noise: snow::TransportState) -> Self {
         let (reader, writer) = stream.into_split();
 
-        let mut network = PeerIOHandle {
-            reader: Some(reader),
+        let mut network = PeerIOHandle::new(
+            reader,
             writer,
-            cipher: Cipher::new(
+            Cipher::new(
                 noise,
                 vec![0u8; MAX_MESSAGE_SIZE + 4096].into(),
                 vec![0u8; NOISE_BUF_LEN].into(),
             ),
-        };
+            vec![],
+        );
 
         let reader = network.take_reader();
 
@@ -220,20 +279,22 @@ impl FakeNode {
     }
 
     pub async fn read_payload(&mut self) -> Result<Payload, NetworkError> {
+        let msg = Payload::deserialize(&self.read_raw_payload().await?)?;
+        debug!("read a {}", msg);
+
+        Ok(msg)
+    }
+
+    /// Reads a payload without deserializing it, i.e. the decrypted bytes sent by the peer.
+    pub async fn read_raw_payload(&mut self) -> Result<Vec<u8>, NetworkError> {
         let raw = self.reader.read_raw_payload().await?;
-        let message = match self.network.read_payload(raw) {
-            Ok(msg) => {
-                let msg = Payload::deserialize(msg)?;
-                debug!("read a {}", msg);
-                msg
-            }
+        match self.network.read_payload(raw) {
+            Ok(msg) => Ok(msg.to_vec()),
             Err(e) => {
                 error!("can't read a payload: {}", e);
-                return Err(e);
+                Err(e)
             }
-        };
-
-        Ok(message)
+        }
     }
 
     pub async fn write_message(&mut self, payload: &Payload) {
@@ -17,19 +17,23 @@
 use tokio::time::sleep;
 
 use crate::{
-    network::{handshaken_node_and_peer, test_node, ConsensusSetup, TestSetup},
+    network::{handshaken_node_and_peer, test_consensus, test_node, ConsensusSetup, FakeNode, TestSetup},
     sync::{BLOCK_1, BLOCK_1_HEADER_HASH, BLOCK_2, BLOCK_2_HEADER_HASH, TRANSACTION_1, TRANSACTION_2},
     wait_until,
 };
 
-use snarkos_consensus::memory_pool::Entry;
-use snarkos_network::message::*;
+use snarkos_consensus::{memory_pool::Entry, BlockOutcome};
+use snarkos_network::{message::*, Node, RelayPolicy, RelayRejection};
+use snarkos_storage::LedgerStorage;
 
 use snarkvm_dpc::{block_header_hash::BlockHeaderHash, testnet1::instantiated::Tx};
 #[cfg(test)]
 use snarkvm_utilities::FromBytes;
 
-use std::time::Duration;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 #[tokio::test]
 async fn block_initiator_side() {
@@ -51,7 +55,7 @@ async fn block_initiator_side() {
     sleep(Duration::from_secs(1)).await;
 
     // trigger the full node to request synchronization by sending it a higher block_height than it has
-    let ping = Payload::Ping(2u32);
+    let ping = Payload::Ping(2u32, 0);
     peer.write_message(&ping).await;
 
     // read the Pong
@@ -144,6 +148,201 @@ async fn block_responder_side() {
     assert_eq!(block, block_struct_1);
 }
 
+#[tokio::test]
+async fn blocks_are_served_in_requested_order() {
+    let (node, mut peer) = handshaken_node_and_peer(TestSetup::default()).await;
+
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+
+    // insert the blocks into the node
+    let blocks = vec![
+        snarkvm_dpc::Block::deserialize(&BLOCK_1).unwrap(),
+        snarkvm_dpc::Block::deserialize(&BLOCK_2).unwrap(),
+    ];
+    for block in &blocks {
+        node.expect_sync().consensus.receive_block(block).await.unwrap();
+    }
+
+    // request the blocks in reverse order
+    let hashes = blocks.iter().rev().map(|block| block.header.get_hash()).collect();
+    peer.write_message(&Payload::GetBlocks(hashes)).await;
+
+    // the blocks are read concurrently, but should be sent in the requested order
+    for expected_block in blocks.iter().rev() {
+        let payload = peer.read_payload().await.unwrap();
+        let block = if let Payload::SyncBlock(block) = payload {
+            block
+        } else {
+            unreachable!();
+        };
+        assert_eq!(&snarkvm_dpc::Block::deserialize(&block).unwrap(), expected_block);
+    }
+}
+
+/// Sets up a node and a peer which claims to be ahead of it, and waits for the node's `GetSync`.
+async fn node_requesting_sync_from_peer() -> (Node<LedgerStorage>, FakeNode) {
+    let setup = TestSetup {
+        consensus_setup: Some(ConsensusSetup {
+            block_sync_interval: 1,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let (node, mut peer) = handshaken_node_and_peer(setup).await;
+
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+
+    sleep(Duration::from_secs(1)).await;
+
+    peer.write_message(&Payload::Ping(2u32, 0)).await;
+
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Pong));
+
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::GetSync(..)));
+
+    (node, peer)
+}
+
+#[tokio::test]
+async fn empty_sync_response() {
+    let (node, mut peer) = node_requesting_sync_from_peer().await;
+
+    // the peer has no blocks to share after all
+    peer.write_message(&Payload::Sync(vec![])).await;
+
+    // the node should stop syncing without waiting for the timeout
+    wait_until!(1, !node.is_syncing_blocks());
+
+    // the peer is no longer considered to be ahead of the node, but it isn't penalized either
+    wait_until!(1, {
+        let peers = node.peer_book.connected_peers_snapshot().await;
+        peers.len() == 1 && peers[0].quality.block_height == 0
+    });
+    let peers = node.peer_book.connected_peers_snapshot().await;
+    assert!(peers[0].quality.failures.is_empty());
+}
+
+#[tokio::test]
+async fn partial_sync_response() {
+    let (node, mut peer) = node_requesting_sync_from_peer().await;
+
+    // the peer shares fewer hashes than its declared height would imply
+    let block_1_header_hash = BlockHeaderHash::new(BLOCK_1_HEADER_HASH.to_vec());
+    let sync = Payload::Sync(vec![block_1_header_hash.clone()]);
+    peer.write_message(&sync).await;
+
+    // only the shared block is requested
+    let payload = peer.read_payload().await.unwrap();
+    let block_hashes = if let Payload::GetBlocks(block_hashes) = payload {
+        block_hashes
+    } else {
+        unreachable!();
+    };
+    assert_eq!(block_hashes, vec![block_1_header_hash.clone()]);
+
+    peer.write_message(&Payload::SyncBlock(BLOCK_1.to_vec())).await;
+
+    wait_until!(1, node.expect_sync().storage().block_hash_exists(&block_1_header_hash));
+}
+
+#[tokio::test]
+async fn oversized_sync_response() {
+    let (node, mut peer) = node_requesting_sync_from_peer().await;
+
+    // the peer shares more hashes than the protocol permits
+    let block_hashes = (0..=snarkos_network::MAX_BLOCK_SYNC_COUNT)
+        .map(|i| BlockHeaderHash::new(vec![i as u8; 32]))
+        .collect();
+    peer.write_message(&Payload::Sync(block_hashes)).await;
+
+    // the node should stop syncing and register a failure
+    wait_until!(1, !node.is_syncing_blocks());
+    wait_until!(1, {
+        let peers = node.peer_book.connected_peers_snapshot().await;
+        peers.len() == 1 && !peers[0].quality.failures.is_empty()
+    });
+}
+
+#[tokio::test]
+async fn announced_block_is_requested() {
+    // handshake between a fake node and a full node
+    let (node, mut peer) = handshaken_node_and_peer(TestSetup::default()).await;
+
+    // check if the peer has received an automatic Ping message from the node
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+
+    // announce a block the node doesn't have yet
+    let block_1_header_hash = BlockHeaderHash::new(BLOCK_1_HEADER_HASH.to_vec());
+    peer.write_message(&Payload::BlockHash(block_1_header_hash.clone()))
+        .await;
+
+    // the node should request the announced block
+    let payload = peer.read_payload().await.unwrap();
+    assert_eq!(payload, Payload::GetBlock(block_1_header_hash.clone()));
+
+    // respond with the full block
+    peer.write_message(&Payload::Block(BLOCK_1.to_vec())).await;
+
+    // check the block has been added to the node's chain
+    wait_until!(1, node.expect_sync().storage().block_hash_exists(&block_1_header_hash));
+}
+
+#[tokio::test]
+async fn accepted_block_is_reported() {
+    // handshake between a fake node and a full node
+    let (node, mut peer) = handshaken_node_and_peer(TestSetup::default()).await;
+
+    let accepted = Arc::new(Mutex::new(Vec::new()));
+    let accepted_clone = accepted.clone();
+    node.on_block_accepted(move |block| accepted_clone.lock().unwrap().push(block.clone()));
+
+    // check if the peer has received an automatic Ping message from the node
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+
+    // send a new block to the node
+    peer.write_message(&Payload::Block(BLOCK_1.to_vec())).await;
+
+    // check the block was reported to the callback
+    wait_until!(1, accepted.lock().unwrap().len() == 1);
+    let block = accepted.lock().unwrap()[0].clone();
+    assert_eq!(block.hash, BlockHeaderHash::new(BLOCK_1_HEADER_HASH.to_vec()));
+    assert_eq!(block.outcome, BlockOutcome::Canon);
+    assert_eq!(block.height, 1);
+    assert!(block.source.is_some());
+}
+
+#[tokio::test]
+async fn requested_block_is_served() {
+    // handshake between a fake node and a full node
+    let (node, mut peer) = handshaken_node_and_peer(TestSetup::default()).await;
+
+    // check if the peer has received an automatic Ping message from the node
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Ping(..)));
+
+    // insert block into node
+    let block_struct_1 = snarkvm_dpc::Block::deserialize(&BLOCK_1).unwrap();
+    node.expect_sync()
+        .consensus
+        .receive_block(&block_struct_1)
+        .await
+        .unwrap();
+
+    // request the block by its hash
+    let block_1_header_hash = BlockHeaderHash::new(BLOCK_1_HEADER_HASH.to_vec());
+    peer.write_message(&Payload::GetBlock(block_1_header_hash)).await;
+
+    // the node should respond with the full block
+    let payload = peer.read_payload().await.unwrap();
+    assert_eq!(payload, Payload::Block(BLOCK_1.to_vec()));
+}
+
 #[test]
 #[ignore]
 fn block_propagation() {
@@ -231,21 +430,46 @@ async fn transaction_initiator_side() {
     peer.write_message(&memory_pool).await;
 
     // Create the entries to verify
-    let entry_1 = Entry {
-        size_in_bytes: TRANSACTION_1.len(),
-        transaction: Tx::read(&TRANSACTION_1[..]).unwrap(),
-    };
+    let entry_1 = Entry::new(Tx::read(&TRANSACTION_1[..]).unwrap(), TRANSACTION_1.len());
 
-    let entry_2 = Entry {
-        size_in_bytes: TRANSACTION_2.len(),
-        transaction: Tx::read(&TRANSACTION_2[..]).unwrap(),
-    };
+    let entry_2 = Entry::new(Tx::read(&TRANSACTION_2[..]).unwrap(), TRANSACTION_2.len());
 
     // Verify the transactions have been stored in the node's memory pool
     wait_until!(1, node.expect_sync().memory_pool().contains(&entry_1));
     wait_until!(1, node.expect_sync().memory_pool().contains(&entry_2));
 }
 
+#[test]
+fn fruitless_mempool_syncs_back_off() {
+    let sync = test_consensus(ConsensusSetup {
+        tx_sync_interval: 1,
+        ..Default::default()
+    });
+
+    // the interval doubles after each sync that turns up nothing new, up to a limit
+    sync.conclude_mempool_sync_round();
+    assert_eq!(sync.current_mempool_sync_interval(), Duration::from_secs(2));
+    sync.conclude_mempool_sync_round();
+    assert_eq!(sync.current_mempool_sync_interval(), Duration::from_secs(4));
+    for _ in 0..snarkos_network::MAX_MEMPOOL_SYNC_BACKOFF {
+        sync.conclude_mempool_sync_round();
+    }
+    assert_eq!(
+        sync.current_mempool_sync_interval(),
+        Duration::from_secs(2u64.pow(snarkos_network::MAX_MEMPOOL_SYNC_BACKOFF))
+    );
+
+    // a fruitful sync returns to the configured interval
+    sync.record_mempool_novelty(1);
+    sync.conclude_mempool_sync_round();
+    assert_eq!(sync.current_mempool_sync_interval(), Duration::from_secs(1));
+
+    // and so does any other news, e.g. a new block
+    sync.conclude_mempool_sync_round();
+    sync.reset_mempool_sync_backoff();
+    assert_eq!(sync.current_mempool_sync_interval(), Duration::from_secs(1));
+}
+
 #[tokio::test]
 async fn transaction_responder_side() {
     // handshake between a fake node and a full node
@@ -259,15 +483,9 @@ async fn transaction_responder_side() {
     let memory_pool = node.expect_sync().memory_pool();
     let storage = node.expect_sync().storage();
 
-    let entry_1 = Entry {
-        size_in_bytes: TRANSACTION_1.len(),
-        transaction: Tx::read(&TRANSACTION_1[..]).unwrap(),
-    };
+    let entry_1 = Entry::new(Tx::read(&TRANSACTION_1[..]).unwrap(), TRANSACTION_1.len());
 
-    let entry_2 = Entry {
-        size_in_bytes: TRANSACTION_2.len(),
-        transaction: Tx::read(&TRANSACTION_2[..]).unwrap(),
-    };
+    let entry_2 = Entry::new(Tx::read(&TRANSACTION_2[..]).unwrap(), TRANSACTION_2.len());
 
     memory_pool.insert(&storage, entry_1).await.unwrap().unwrap();
     memory_pool.insert(&storage, entry_2).await.unwrap().unwrap();
@@ -289,6 +507,45 @@ async fn transaction_responder_side() {
     assert!(txs.contains(&TRANSACTION_2.to_vec()));
 }
 
+#[test]
+fn relay_policy_checks_transactions() {
+    let transaction_1 = Tx::read(&TRANSACTION_1[..]).unwrap();
+    let transaction_2 = Tx::read(&TRANSACTION_2[..]).unwrap();
+    let fee = transaction_1.value_balance.0;
+
+    // a policy blocking the memo of the first transaction
+    let blocked_memo: String = transaction_1.memorandum.iter().map(|b| format!("{:02x}", b)).collect();
+    let policy = RelayPolicy {
+        min_fee: i64::MIN,
+        memo_blocklist: vec![blocked_memo.parse().unwrap()],
+        ..Default::default()
+    };
+    assert!(matches!(
+        policy.check(&transaction_1),
+        Err(RelayRejection::BlockedMemo(..))
+    ));
+    assert!(policy.check(&transaction_2).is_ok());
+
+    // a policy requiring a higher fee than the first transaction pays
+    let policy = RelayPolicy {
+        min_fee: fee + 1,
+        ..Default::default()
+    };
+    assert_eq!(
+        policy.check(&transaction_1),
+        Err(RelayRejection::FeeTooLow { fee, min_fee: fee + 1 })
+    );
+
+    // a policy limiting the size of the transactions
+    let policy = RelayPolicy {
+        min_fee: i64::MIN,
+        max_transaction_size: Some(TRANSACTION_1.len() - 1),
+        ..Default::default()
+    };
+    assert!(policy.check_size(TRANSACTION_1.len()).is_err());
+    assert!(policy.check_size(TRANSACTION_1.len() - 1).is_ok());
+}
+
 #[tokio::test]
 async fn transaction_two_node() {
     use snarkos_consensus::memory_pool::Entry;
@@ -304,10 +561,7 @@ async fn transaction_two_node() {
 
     let transaction = Tx::read(&TRANSACTION_1[..]).unwrap();
     let size = TRANSACTION_1.len();
-    let entry = Entry {
-        size_in_bytes: size,
-        transaction: transaction.clone(),
-    };
+    let entry = Entry::new(transaction.clone(), size);
 
     memory_pool.insert(&storage, entry.clone()).await.unwrap().unwrap();
 
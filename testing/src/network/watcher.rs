@@ -0,0 +1,109 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    network::{test_node, test_watcher, test_watcher_node, TestSetup},
+    sync::{ALTERNATIVE_BLOCK_1, ALTERNATIVE_BLOCK_2, BLOCK_1, BLOCK_2},
+    wait_until,
+};
+
+use snarkos_network::{WatchedBlock, WatcherEvent, WATCHER_SYNC_INTERVAL_SECS};
+use snarkvm_dpc::Block;
+
+#[test]
+fn watcher_follows_the_longest_chain() {
+    let watcher = test_watcher();
+    let mut events = watcher.subscribe();
+
+    let block_1 = Block::deserialize(&BLOCK_1).unwrap();
+    let block_2 = Block::deserialize(&BLOCK_2).unwrap();
+    let alternative_block_1 = Block::deserialize(&ALTERNATIVE_BLOCK_1).unwrap();
+    let alternative_block_2 = Block::deserialize(&ALTERNATIVE_BLOCK_2).unwrap();
+
+    // the parent of the second block isn't known yet
+    assert_eq!(watcher.receive_block(block_2.clone()).unwrap(), WatchedBlock::Orphan);
+
+    assert_eq!(watcher.receive_block(block_1.clone()).unwrap(), WatchedBlock::Canon);
+    assert_eq!(watcher.receive_block(block_1.clone()).unwrap(), WatchedBlock::Known);
+    assert_eq!(watcher.block_height(), 1);
+
+    // the alternative chain overtakes the canon one with its second block
+    assert_eq!(
+        watcher.receive_block(alternative_block_1.clone()).unwrap(),
+        WatchedBlock::SideChain
+    );
+    assert_eq!(
+        watcher.receive_block(alternative_block_2.clone()).unwrap(),
+        WatchedBlock::Canon
+    );
+    assert_eq!(watcher.block_height(), 2);
+    assert_eq!(watcher.canon_height(&alternative_block_1.header.get_hash()), Some(1));
+    assert_eq!(watcher.canon_height(&block_1.header.get_hash()), None);
+    assert!(watcher.is_known(&block_1.header.get_hash()));
+
+    // the original chain is now the shorter one
+    assert_eq!(watcher.receive_block(block_2).unwrap(), WatchedBlock::SideChain);
+    assert_eq!(watcher.tip().0, alternative_block_2.header.get_hash());
+
+    let mut chain_events = vec![];
+    while let Ok(event) = events.try_recv() {
+        if !matches!(event, WatcherEvent::TransactionIncluded { .. }) {
+            chain_events.push(event);
+        }
+    }
+
+    assert_eq!(
+        chain_events,
+        vec![
+            WatcherEvent::NewTip {
+                hash: block_1.header.get_hash(),
+                height: 1
+            },
+            WatcherEvent::Reorganized { fork_height: 0 },
+            WatcherEvent::NewTip {
+                hash: alternative_block_2.header.get_hash(),
+                height: 2
+            },
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn watcher_node_follows_full_node() {
+    let full_node = test_node(TestSetup::default()).await;
+    let full_node_address = full_node.local_address().unwrap();
+
+    for block in [&*BLOCK_1, &*BLOCK_2].iter() {
+        let block = Block::deserialize(block).unwrap();
+        full_node.expect_sync().consensus.receive_block(&block).await.unwrap();
+    }
+
+    let setup = TestSetup {
+        consensus_setup: None,
+        peer_sync_interval: 1,
+        bootnodes: vec![full_node_address.to_string()],
+        ..Default::default()
+    };
+    let watcher_node = test_watcher_node(setup).await;
+    let watcher = watcher_node.watcher().unwrap();
+
+    // the watcher requests the blocks it's missing from the full node, but only keeps their headers
+    wait_until!(WATCHER_SYNC_INTERVAL_SECS as u64 + 5, watcher.block_height() == 2, 100);
+
+    // the full node knows it's connected to a watcher
+    let peers = full_node.peer_book.connected_peers_snapshot().await;
+    assert!(!peers.is_empty() && peers.iter().all(|peer| peer.quality.is_watcher));
+}
@@ -15,15 +15,37 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 #[cfg(test)]
-use crate::sync::{create_test_consensus, TestBlocks};
+use crate::sync::{create_test_consensus, create_test_consensus_from_ledger, TestBlocks};
+#[cfg(test)]
+use snarkos_consensus::MerkleTreeLedger;
+#[cfg(test)]
+use snarkos_storage::{
+    check_schema_version,
+    get_schema_version,
+    migrations,
+    set_schema_version,
+    Migration,
+    MigrationRunner,
+    MiningEvent,
+    MiningEventKind,
+    COL_CM_TRANSACTION,
+    COL_META,
+    COL_SN_TRANSACTION,
+    SCHEMA_VERSION,
+};
+#[cfg(test)]
+use snarkvm_utilities::{to_bytes, ToBytes};
 
 use crate::sync::TestTx;
 pub use snarkos_storage::{validator::FixMode, Ledger, LedgerStorage};
 use snarkvm_algorithms::traits::merkle_tree::LoadableMerkleParameters;
 use snarkvm_dpc::{
-    testnet1::instantiated::CommitmentMerkleParameters,
+    testnet1::instantiated::{CommitmentMerkleParameters, Tx},
     Block,
+    BlockHeader,
+    DatabaseTransaction,
     LedgerScheme,
+    Op,
     Storage,
     TransactionScheme,
 };
@@ -62,3 +84,435 @@ async fn valid_storage_validates() {
 
     assert!(consensus.ledger.validate(None, FixMode::Everything));
 }
+
+#[tokio::test]
+async fn old_blocks_are_pruned() {
+    let consensus = create_test_consensus();
+    let ledger = &consensus.ledger;
+
+    let blocks = TestBlocks::load(Some(10), "test_blocks_100_1").0;
+    for block in &blocks {
+        consensus.receive_block(block).await.unwrap();
+    }
+    assert_eq!(ledger.get_current_block_height(), 10);
+    assert_eq!(ledger.get_earliest_block_height().unwrap(), 0);
+
+    // the blocks below height 7 other than genesis are pruned, and pruning again doesn't affect any more of them
+    assert_eq!(ledger.prune_blocks(3).unwrap(), 6);
+    assert_eq!(ledger.prune_blocks(3).unwrap(), 0);
+    assert_eq!(ledger.get_earliest_block_height().unwrap(), 7);
+
+    // only the headers of the pruned blocks are kept
+    let pruned_hash = blocks[0].header.get_hash();
+    assert!(ledger.is_block_pruned(&pruned_hash));
+    assert!(ledger.get_block_header(&pruned_hash).is_ok());
+    assert!(ledger.get_block(&pruned_hash).is_err());
+
+    // the genesis block is always kept in full
+    let genesis_hash = ledger.get_block_hash(0).unwrap();
+    assert!(!ledger.is_block_pruned(&genesis_hash));
+    assert!(ledger.get_block(&genesis_hash).is_ok());
+
+    let kept_hash = blocks[9].header.get_hash();
+    assert!(!ledger.is_block_pruned(&kept_hash));
+    assert!(ledger.get_block(&kept_hash).is_ok());
+
+    // the remaining blocks are still valid
+    assert!(ledger.validate(None, FixMode::Nothing));
+}
+
+#[tokio::test]
+async fn ledger_snapshots_bootstrap_new_ledgers() {
+    let consensus = create_test_consensus();
+    let ledger = &consensus.ledger;
+
+    let blocks = TestBlocks::load(Some(10), "test_blocks_100_1").0;
+    for block in &blocks {
+        consensus.receive_block(block).await.unwrap();
+    }
+
+    let snapshot_path = std::env::temp_dir().join(format!("ledger_snapshot-{}", thread_rng().gen::<u64>()));
+    let summary = ledger.export_snapshot(6, &snapshot_path).unwrap();
+    assert_eq!(summary.height, 6);
+    assert_eq!(summary.block_hash, blocks[5].header.get_hash());
+    // the snapshot is only moved into place once it's complete
+    let file_name = snapshot_path.file_name().unwrap().to_str().unwrap();
+    assert!(!snapshot_path.with_file_name(format!("{}.partial", file_name)).exists());
+
+    let verify_block = |block: &Block<Tx>, parent: &BlockHeader| {
+        consensus
+            .parameters
+            .verify_block_header(block, parent)
+            .map_err(|e| e.to_string())
+    };
+
+    // a snapshot that doesn't end with the expected block is refused
+    let storage_path = std::env::temp_dir().join(random_storage_path());
+    let unexpected_hash = blocks[6].header.get_hash();
+    let result = MerkleTreeLedger::<LedgerStorage>::import_snapshot(
+        &storage_path,
+        &snapshot_path,
+        &unexpected_hash,
+        verify_block,
+    );
+    assert!(result.is_err());
+
+    // a snapshot whose blocks don't pass the check is refused
+    let reject_block = |_: &Block<Tx>, _: &BlockHeader| Err("invalid proof of work".to_string());
+    let result = MerkleTreeLedger::<LedgerStorage>::import_snapshot(
+        &storage_path,
+        &snapshot_path,
+        &summary.block_hash,
+        reject_block,
+    );
+    assert!(result.is_err());
+
+    let (imported, imported_summary) = MerkleTreeLedger::<LedgerStorage>::import_snapshot(
+        &storage_path,
+        &snapshot_path,
+        &summary.block_hash,
+        verify_block,
+    )
+    .unwrap();
+    assert_eq!(imported_summary, summary);
+    assert_eq!(imported.get_current_block_height(), 6);
+    assert!(imported.is_block_pruned(&blocks[4].header.get_hash()));
+    assert!(imported.get_block(&blocks[5].header.get_hash()).is_ok());
+    assert!(imported.get_block_from_block_number(0).is_ok());
+
+    // the subsequent blocks are synced on top of the snapshot, reaching the same state
+    let imported_consensus = create_test_consensus_from_ledger(Arc::new(imported));
+    for block in &blocks[6..] {
+        imported_consensus.receive_block(block).await.unwrap();
+    }
+    assert_eq!(imported_consensus.ledger.get_current_block_height(), 10);
+    assert_eq!(
+        imported_consensus.ledger.current_digest().unwrap(),
+        ledger.current_digest().unwrap()
+    );
+
+    // a corrupted snapshot is refused
+    let mut bytes = std::fs::read(&snapshot_path).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 1;
+    std::fs::write(&snapshot_path, bytes).unwrap();
+    let storage_path = std::env::temp_dir().join(random_storage_path());
+    let result = MerkleTreeLedger::<LedgerStorage>::import_snapshot(
+        &storage_path,
+        &snapshot_path,
+        &summary.block_hash,
+        verify_block,
+    );
+    assert!(result.is_err());
+
+    std::fs::remove_file(&snapshot_path).unwrap();
+}
+
+#[tokio::test]
+async fn transactions_are_indexed_by_their_records() {
+    let consensus = create_test_consensus();
+    let ledger = &consensus.ledger;
+
+    let blocks = TestBlocks::load(Some(2), "test_blocks_100_1").0;
+    for block in &blocks {
+        consensus.receive_block(block).await.unwrap();
+    }
+
+    let transaction = &blocks[1].transactions.0[0];
+    let transaction_id = transaction.transaction_id().unwrap().to_vec();
+    let cm_bytes = to_bytes![transaction.new_commitments()[0]].unwrap();
+    let sn_bytes = to_bytes![transaction.old_serial_numbers()[0]].unwrap();
+
+    assert_eq!(
+        ledger.get_transaction_id_by_commitment(&cm_bytes).unwrap(),
+        Some(transaction_id.clone())
+    );
+    assert_eq!(
+        ledger.get_transaction_id_by_serial_number(&sn_bytes).unwrap(),
+        Some(transaction_id.clone())
+    );
+
+    // the indexes of storage created before their introduction are filled in by the migration
+    let ops = vec![
+        Op::Delete {
+            col: COL_CM_TRANSACTION,
+            key: cm_bytes.clone(),
+        },
+        Op::Delete {
+            col: COL_SN_TRANSACTION,
+            key: sn_bytes.clone(),
+        },
+    ];
+    ledger.storage.batch(DatabaseTransaction(ops)).unwrap();
+    set_schema_version(&ledger.storage, 3).unwrap();
+    assert_eq!(
+        MigrationRunner::new(&ledger.storage, migrations::<TestTx, _>())
+            .run()
+            .unwrap(),
+        vec![4, 5]
+    );
+    assert_eq!(
+        ledger.get_transaction_id_by_commitment(&cm_bytes).unwrap(),
+        Some(transaction_id)
+    );
+
+    // the records of decommitted blocks are no longer indexed
+    ledger.decommit_latest_block().unwrap();
+    assert!(ledger.get_transaction_id_by_commitment(&cm_bytes).unwrap().is_none());
+    assert!(ledger.get_transaction_id_by_serial_number(&sn_bytes).unwrap().is_none());
+}
+
+#[test]
+fn sync_checkpoint_round_trip() {
+    let consensus = create_test_consensus();
+    let ledger = &consensus.ledger;
+
+    assert!(ledger.get_sync_checkpoint().unwrap().is_none());
+
+    let checkpoint = vec![1u8; 64];
+    ledger.store_sync_checkpoint(checkpoint.clone()).unwrap();
+    assert_eq!(ledger.get_sync_checkpoint().unwrap(), Some(checkpoint));
+
+    ledger.clear_sync_checkpoint().unwrap();
+    assert!(ledger.get_sync_checkpoint().unwrap().is_none());
+}
+
+#[test]
+fn bans_are_stored_and_removed() {
+    let consensus = create_test_consensus();
+    let ledger = &consensus.ledger;
+
+    assert!(ledger.get_bans().unwrap().is_empty());
+
+    ledger.store_ban("10.0.0.0/8", 1_700_000_000).unwrap();
+    ledger.store_ban("192.168.1.1", i64::MAX).unwrap();
+    ledger.store_ban("10.0.0.0/8", 1_800_000_000).unwrap();
+
+    let mut bans = ledger.get_bans().unwrap();
+    bans.sort();
+    assert_eq!(
+        bans,
+        vec![
+            ("10.0.0.0/8".to_string(), 1_800_000_000),
+            ("192.168.1.1".to_string(), i64::MAX)
+        ]
+    );
+
+    ledger.remove_bans(&["10.0.0.0/8".to_string()]).unwrap();
+    assert_eq!(ledger.get_bans().unwrap(), vec![("192.168.1.1".to_string(), i64::MAX)]);
+}
+
+#[test]
+fn mining_history_is_stored_by_height() {
+    let consensus = create_test_consensus();
+    let ledger = &consensus.ledger;
+
+    let event = |kind, block_height, block_hash, timestamp| MiningEvent {
+        kind,
+        block_height,
+        block_hash,
+        previous_block_hash: [block_height as u8; 32],
+        timestamp,
+    };
+
+    ledger.store_mining_event(&event(MiningEventKind::Template, 1, None, 100)).unwrap();
+    ledger.store_mining_event(&event(MiningEventKind::Submitted, 1, Some([1; 32]), 110)).unwrap();
+    ledger.store_mining_event(&event(MiningEventKind::Accepted, 1, Some([1; 32]), 111)).unwrap();
+    ledger.store_mining_event(&event(MiningEventKind::Template, 2, None, 120)).unwrap();
+    // A newer template for the same parent replaces the previous one.
+    ledger.store_mining_event(&event(MiningEventKind::Template, 2, None, 130)).unwrap();
+    ledger.store_mining_event(&event(MiningEventKind::Rejected, 3, Some([3; 32]), 140)).unwrap();
+
+    assert_eq!(ledger.get_mining_history(0, u32::MAX).unwrap().len(), 5);
+    assert_eq!(
+        ledger.get_mining_history(1, 2).unwrap(),
+        vec![
+            event(MiningEventKind::Template, 1, None, 100),
+            event(MiningEventKind::Submitted, 1, Some([1; 32]), 110),
+            event(MiningEventKind::Accepted, 1, Some([1; 32]), 111),
+            event(MiningEventKind::Template, 2, None, 130),
+        ]
+    );
+    assert!(ledger.get_mining_history(4, 10).unwrap().is_empty());
+}
+
+#[test]
+fn network_snapshots_are_stored_by_time() {
+    let consensus = create_test_consensus();
+    let ledger = &consensus.ledger;
+
+    assert!(ledger.get_known_network().unwrap().is_none());
+    ledger.save_known_network(vec![1, 2, 3]).unwrap();
+    assert_eq!(ledger.get_known_network().unwrap(), Some(vec![1, 2, 3]));
+
+    ledger.store_network_snapshot(2_000, vec![2]).unwrap();
+    ledger.store_network_snapshot(1_000, vec![1]).unwrap();
+    ledger.store_network_snapshot(3_000, vec![3]).unwrap();
+    assert_eq!(ledger.get_network_snapshot_times().unwrap(), vec![1_000, 2_000, 3_000]);
+
+    // The latest snapshot taken at or before the requested time is returned.
+    assert!(ledger.get_network_snapshot_at(999).unwrap().is_none());
+    assert_eq!(ledger.get_network_snapshot_at(1_000).unwrap(), Some((1_000, vec![1])));
+    assert_eq!(ledger.get_network_snapshot_at(2_999).unwrap(), Some((2_000, vec![2])));
+    assert_eq!(
+        ledger.get_network_snapshot_at(i64::MAX).unwrap(),
+        Some((3_000, vec![3]))
+    );
+
+    assert_eq!(ledger.prune_network_snapshots(2_500).unwrap(), 2);
+    assert_eq!(ledger.get_network_snapshot_times().unwrap(), vec![3_000]);
+}
+
+#[test]
+fn journal_events_are_stored_by_time() {
+    let consensus = create_test_consensus();
+    let ledger = &consensus.ledger;
+
+    ledger.store_journal_event(2_000, 1, vec![2]).unwrap();
+    ledger.store_journal_event(1_000, 7, vec![1]).unwrap();
+    ledger.store_journal_event(2_000, 0, vec![3]).unwrap();
+    ledger.store_journal_event(3_000, 2, vec![4]).unwrap();
+
+    let mut keys = ledger.get_journal_keys().unwrap();
+    keys.sort_unstable();
+    assert_eq!(keys, vec![(1_000, 7), (2_000, 0), (2_000, 1), (3_000, 2)]);
+    assert_eq!(ledger.get_journal_event(2_000, 0).unwrap(), Some(vec![3]));
+    assert_eq!(ledger.get_journal_event(2_000, 2).unwrap(), None);
+
+    ledger.remove_journal_events(&[(1_000, 7), (2_000, 1)]).unwrap();
+    keys = ledger.get_journal_keys().unwrap();
+    keys.sort_unstable();
+    assert_eq!(keys, vec![(2_000, 0), (3_000, 2)]);
+    assert_eq!(ledger.get_journal_event(1_000, 7).unwrap(), None);
+}
+
+#[test]
+fn overflow_transactions_are_stored_by_id() {
+    let consensus = create_test_consensus();
+    let ledger = &consensus.ledger;
+
+    ledger.store_overflow_transaction(&[1], vec![10]).unwrap();
+    ledger.store_overflow_transaction(&[2], vec![20]).unwrap();
+    assert!(ledger.contains_overflow_transaction(&[1]));
+    assert!(!ledger.contains_overflow_transaction(&[3]));
+    assert_eq!(ledger.get_overflow_transaction(&[2]).unwrap(), Some(vec![20]));
+    assert_eq!(ledger.get_overflow_transaction(&[3]).unwrap(), None);
+
+    let mut stored = ledger.get_overflow_transactions().unwrap();
+    stored.sort();
+    assert_eq!(stored, vec![(vec![1], vec![10]), (vec![2], vec![20])]);
+
+    ledger.remove_overflow_transactions(&[vec![1], vec![3]]).unwrap();
+    assert!(!ledger.contains_overflow_transaction(&[1]));
+    assert_eq!(ledger.get_overflow_transactions().unwrap(), vec![(vec![2], vec![20])]);
+}
+
+#[test]
+fn new_storage_has_the_latest_schema() {
+    let consensus = create_test_consensus();
+
+    assert_eq!(
+        get_schema_version(&consensus.ledger.storage).unwrap(),
+        Some(SCHEMA_VERSION)
+    );
+    assert!(
+        MigrationRunner::new(&consensus.ledger.storage, migrations::<TestTx, _>())
+            .run()
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn newer_schema_is_refused() {
+    let consensus = create_test_consensus();
+    let storage = &consensus.ledger.storage;
+
+    set_schema_version(storage, SCHEMA_VERSION + 1).unwrap();
+
+    assert!(check_schema_version(storage).is_err());
+    assert!(MigrationRunner::new(storage, migrations::<TestTx, _>()).run().is_err());
+}
+
+#[test]
+fn pending_migrations_are_applied_in_order() {
+    const KEY_MIGRATION_TEST: &[u8] = b"MIGRATION_TEST";
+
+    fn test_migrations() -> Vec<Migration<LedgerStorage>> {
+        vec![
+            Migration {
+                version: SCHEMA_VERSION + 2,
+                description: "overwrite the test value",
+                apply: |storage| storage.put(COL_META, KEY_MIGRATION_TEST, [4u8]),
+            },
+            Migration {
+                version: SCHEMA_VERSION + 1,
+                description: "insert the test value",
+                apply: |storage| {
+                    assert!(storage.get(COL_META, KEY_MIGRATION_TEST)?.is_none());
+                    storage.put(COL_META, KEY_MIGRATION_TEST, [3u8])
+                },
+            },
+        ]
+    }
+
+    let consensus = create_test_consensus();
+    let storage = &consensus.ledger.storage;
+
+    // A dry run doesn't change anything.
+    let pending = MigrationRunner::new(storage, test_migrations())
+        .dry_run(true)
+        .run()
+        .unwrap();
+    assert_eq!(pending, vec![SCHEMA_VERSION + 1, SCHEMA_VERSION + 2]);
+    assert_eq!(get_schema_version(storage).unwrap(), Some(SCHEMA_VERSION));
+    assert!(storage.get(COL_META, KEY_MIGRATION_TEST).unwrap().is_none());
+
+    let mut backups = vec![];
+    let applied = MigrationRunner::new(storage, test_migrations())
+        .with_backup(|version| {
+            backups.push(version);
+            Ok(())
+        })
+        .run()
+        .unwrap();
+    assert_eq!(applied, vec![SCHEMA_VERSION + 1, SCHEMA_VERSION + 2]);
+    assert_eq!(backups, vec![SCHEMA_VERSION]);
+    assert_eq!(get_schema_version(storage).unwrap(), Some(SCHEMA_VERSION + 2));
+    assert_eq!(storage.get(COL_META, KEY_MIGRATION_TEST).unwrap(), Some(vec![4u8]));
+
+    // Applied migrations aren't applied again.
+    assert!(MigrationRunner::new(storage, test_migrations())
+        .run()
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn read_only_storage_refuses_writes() {
+    let mut path = std::env::temp_dir();
+    path.push(random_storage_path());
+
+    let primary = Store::open_at_path(&path).unwrap();
+    let read_only = Store::open_read_only_at_path(&path).unwrap();
+
+    assert_eq!(read_only.get_current_block_height(), primary.get_current_block_height());
+    assert_eq!(read_only.get_best_block_number().unwrap(), 0);
+
+    assert!(read_only.store_ban("10.0.0.0/8", i64::MAX).is_err());
+    assert!(read_only.storage.try_catch_up_with_primary().is_err());
+    assert!(read_only.get_bans().unwrap().is_empty());
+
+    // The primary instance is unaffected.
+    primary.store_ban("10.0.0.0/8", i64::MAX).unwrap();
+    assert!(read_only.get_bans().unwrap().is_empty());
+}
+
+#[test]
+fn read_only_storage_requires_an_existing_ledger() {
+    let mut path = std::env::temp_dir();
+    path.push(random_storage_path());
+
+    assert!(Store::open_read_only_at_path(&path).is_err());
+    assert!(!path.exists());
+}
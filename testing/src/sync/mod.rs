@@ -146,5 +146,11 @@ pub fn create_test_consensus_from_ledger(
         memory_pool: Default::default(),
         parameters: TEST_CONSENSUS_PARAMS.clone(),
         public_parameters: FIXTURE.parameters.clone(),
+        preverified_blocks: Default::default(),
+        received_blocks: Default::default(),
+        fork_choices: Default::default(),
+        reorgs: Default::default(),
+        analytics: Default::default(),
+        tip_changes: Default::default(),
     }
 }